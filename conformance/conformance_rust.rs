@@ -4,11 +4,11 @@
 // license that can be found in the LICENSE file or at
 // https://developers.google.com/open-source/licenses/bsd
 
-use conformance_rust_proto::{ConformanceRequest, ConformanceResponse, WireFormat};
+use conformance_rust_proto::{ConformanceRequest, ConformanceResponse, TestCategory, WireFormat};
 
 use protobuf::prelude::*;
-use protobuf::Optional::{Set, Unset};
-use protobuf::{Message, ParseError};
+use protobuf::Optional::Set;
+use protobuf::{JsonParseOptions, Message, TextFormatPrintOptions};
 
 use std::io::{self, ErrorKind, Read, Write};
 use test_messages_edition2023_rust_proto::TestAllTypesEdition2023;
@@ -52,53 +52,87 @@ fn write_response_to_stdout(resp: &ConformanceResponse) {
     handle.flush().unwrap();
 }
 
+/// Parses the payload of `req` as a `T`, and sets `resp` to the result of
+/// serializing it in the requested format.
+fn roundtrip<T: Message>(req: &ConformanceRequest, resp: &mut ConformanceResponse) {
+    let parsed = if let Set(bytes) = req.protobuf_payload_opt() {
+        T::parse(bytes).map_err(|e| e.to_string())
+    } else if req.has_json_payload() {
+        let Ok(json) = req.json_payload().to_str() else {
+            resp.set_parse_error("JSON payload is not UTF-8");
+            return;
+        };
+        let options = JsonParseOptions::new().ignore_unknown_fields(
+            req.test_category() == TestCategory::JsonIgnoreUnknownParsingTest,
+        );
+        T::from_json_with_options(json, &options).map_err(|e| e.to_string())
+    } else {
+        let Ok(text) = req.text_payload().to_str() else {
+            resp.set_parse_error("text format payload is not UTF-8");
+            return;
+        };
+        T::parse_text_format(text).map_err(|e| e.to_string())
+    };
+    let msg = match parsed {
+        Ok(msg) => msg,
+        Err(e) => {
+            resp.set_parse_error(e);
+            return;
+        }
+    };
+
+    match req.requested_output_format() {
+        WireFormat::Protobuf => match msg.serialize() {
+            Ok(serialized) => resp.set_protobuf_payload(serialized),
+            Err(e) => resp.set_serialize_error(e.to_string()),
+        },
+        WireFormat::Json => match msg.to_json() {
+            Ok(json) => resp.set_json_payload(json),
+            Err(e) => resp.set_serialize_error(e.to_string()),
+        },
+        WireFormat::TextFormat => {
+            let options =
+                TextFormatPrintOptions::new().print_unknown_fields(req.print_unknown_fields());
+            resp.set_text_payload(msg.to_text_format_with_options(&options))
+        }
+        _ => unreachable!("unsupported output formats are skipped"),
+    }
+}
+
 fn do_test(req: &ConformanceRequest) -> ConformanceResponse {
     let mut resp = ConformanceResponse::new();
     let message_type = req.message_type();
 
-    if req.requested_output_format() != WireFormat::Protobuf {
-        resp.set_skipped("only wire format output implemented");
+    if !matches!(
+        req.requested_output_format(),
+        WireFormat::Protobuf | WireFormat::Json | WireFormat::TextFormat
+    ) {
+        resp.set_skipped("only wire format, JSON and text format output implemented");
         return resp;
     }
 
-    let bytes = match req.protobuf_payload_opt() {
-        Unset(_) => {
-            resp.set_skipped("only wire format input implemented");
-            return resp;
-        }
-        Set(bytes) => bytes,
-    };
-
-    fn roundtrip<T: Message>(bytes: &[u8]) -> Result<Vec<u8>, ParseError> {
-        T::parse(bytes).map(|msg| msg.serialize().unwrap())
+    if !req.has_protobuf_payload() && !req.has_json_payload() && !req.has_text_payload() {
+        resp.set_skipped("only wire format, JSON and text format input implemented");
+        return resp;
     }
 
-    let serialized = match message_type.as_bytes() {
+    match message_type.as_bytes() {
         b"protobuf_test_messages.proto2.TestAllTypesProto2" => {
-            roundtrip::<TestAllTypesProto2>(bytes)
+            roundtrip::<TestAllTypesProto2>(req, &mut resp)
         }
         b"protobuf_test_messages.proto3.TestAllTypesProto3" => {
-            roundtrip::<TestAllTypesProto3>(bytes)
+            roundtrip::<TestAllTypesProto3>(req, &mut resp)
         }
         b"protobuf_test_messages.editions.TestAllTypesEdition2023" => {
-            roundtrip::<TestAllTypesEdition2023>(bytes)
+            roundtrip::<TestAllTypesEdition2023>(req, &mut resp)
         }
         b"protobuf_test_messages.editions.proto2.TestAllTypesProto2" => {
-            roundtrip::<EditionsTestAllTypesProto2>(bytes)
+            roundtrip::<EditionsTestAllTypesProto2>(req, &mut resp)
         }
         b"protobuf_test_messages.editions.proto3.TestAllTypesProto3" => {
-            roundtrip::<EditionsTestAllTypesProto3>(bytes)
+            roundtrip::<EditionsTestAllTypesProto3>(req, &mut resp)
         }
         _ => panic!("unexpected msg type {message_type}"),
-    };
-
-    match serialized {
-        Ok(serialized) => {
-            resp.set_protobuf_payload(serialized);
-        }
-        Err(_) => {
-            resp.set_parse_error("failed to parse bytes");
-        }
     }
 
     resp
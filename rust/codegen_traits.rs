@@ -8,6 +8,7 @@
 //! Traits that are implemented by codegen types.
 
 use crate::__internal::SealedInternal;
+use crate::reflect::{ReflectMessage, ReflectMessageMut};
 use crate::{MutProxied, MutProxy, ViewProxy};
 use create::Parse;
 use interop::{MessageMutInterop, MessageViewInterop, OwnedMessageInterop};
@@ -16,14 +17,21 @@ use std::fmt::Debug;
 use write::{Clear, ClearAndParse, MergeFrom};
 
 /// A trait that all generated owned message types implement.
+///
+/// Messages, views and muts of the same type can be compared with `==` in any
+/// combination. Two messages are equal if they have the same fields and
+/// extensions set to equal values: repeated fields are compared in order and
+/// map fields regardless of order. Floating point values are compared by their
+/// bits, so a NaN is equal to an identical NaN, but `0.0` and `-0.0` differ.
+/// Unknown fields are ignored.
 pub trait Message: SealedInternal
   + MutProxied
   // Create traits:
   + Parse + Default
   // Read traits:
-  + Debug + Serialize
+  + Debug + Serialize + ReflectMessage + MessageName + PartialEq
   // Write traits:
-  + Clear + ClearAndParse + MergeFrom
+  + Clear + ClearAndParse + MergeFrom + ReflectMessageMut
   // Thread safety:
   + Send + Sync
   // Copy/Clone:
@@ -33,11 +41,23 @@ pub trait Message: SealedInternal
 {
 }
 
+/// The name of a generated message type, as used by `google.protobuf.Any`.
+pub trait MessageName: SealedInternal {
+    /// The fully qualified name of the message type, such as
+    /// `google.protobuf.Timestamp`.
+    const FULL_NAME: &'static str;
+
+    /// The type URL that identifies the message type in an `Any`, which is
+    /// [`FULL_NAME`](MessageName::FULL_NAME) with a `type.googleapis.com/`
+    /// prefix.
+    const TYPE_URL: &'static str;
+}
+
 /// A trait that all generated message views implement.
 pub trait MessageView<'msg>: SealedInternal
     + ViewProxy<'msg, Proxied = Self::Message>
     // Read traits:
-    + Debug + Serialize + Default
+    + Debug + Serialize + Default + ReflectMessage + PartialEq
     // Thread safety:
     + Send + Sync
     // Copy/Clone:
@@ -53,10 +73,10 @@ pub trait MessageView<'msg>: SealedInternal
 pub trait MessageMut<'msg>: SealedInternal
     + MutProxy<'msg, MutProxied = Self::Message>
     // Read traits:
-    + Debug + Serialize
+    + Debug + Serialize + ReflectMessage + PartialEq
     // Write traits:
     // TODO: MsgMut should impl ClearAndParse.
-    + Clear + MergeFrom
+    + Clear + MergeFrom + ReflectMessageMut
     // Thread safety:
     + Sync
     // Copy/Clone:
@@ -72,8 +92,39 @@ pub trait MessageMut<'msg>: SealedInternal
 /// these traits.
 pub(crate) mod create {
     use super::SealedInternal;
+    use crate::__internal::{read_to_end_limited, Private};
+    use std::io::Read;
+
     pub trait Parse: SealedInternal + Sized {
         fn parse(serialized: &[u8]) -> Result<Self, crate::ParseError>;
+
+        /// Parses a message as controlled by `options`.
+        fn parse_with_options(
+            serialized: &[u8],
+            options: &crate::ParseOptions<'_>,
+        ) -> Result<Self, crate::ParseError>;
+
+        /// Parses a message whose `string` and `bytes` fields may point into
+        /// `serialized` instead of being copied out of it.
+        ///
+        /// This avoids copying large payloads, and works with any buffer that
+        /// derefs to `[u8]`, such as a memory-mapped file. The upb kernel
+        /// aliases the input; the C++ kernel copies it like
+        /// [`parse`](Parse::parse).
+        fn parse_borrowed(
+            serialized: &[u8],
+        ) -> Result<crate::ParsedView<'_, Self>, crate::ParseError>;
+
+        /// Parses a message from all of the bytes produced by `reader`.
+        ///
+        /// Fails with [`ReadError::TooLarge`] without parsing anything if
+        /// `reader` produces more than `max_size` bytes.
+        ///
+        /// [`ReadError::TooLarge`]: crate::ReadError::TooLarge
+        fn parse_from_reader(reader: impl Read, max_size: usize) -> Result<Self, crate::ReadError> {
+            let data = read_to_end_limited(Private, reader, max_size)?;
+            Ok(Self::parse(&data)?)
+        }
     }
 }
 
@@ -82,9 +133,62 @@ pub(crate) mod create {
 /// muts all implement these traits.
 pub(crate) mod read {
     use super::SealedInternal;
+    use std::io::Write;
 
     pub trait Serialize: SealedInternal {
+        /// Serializes the message with the default [`SerializeOptions`].
+        ///
+        /// Unset required fields are not checked, so partially initialized
+        /// messages can be serialized.
+        ///
+        /// [`SerializeOptions`]: crate::SerializeOptions
         fn serialize(&self) -> Result<Vec<u8>, crate::SerializeError>;
+
+        /// Serializes the message as controlled by `options`.
+        fn serialize_with_options(
+            &self,
+            options: &crate::SerializeOptions,
+        ) -> Result<Vec<u8>, crate::SerializeError>;
+
+        /// Appends the serialized message to `out`, reusing its capacity.
+        ///
+        /// On error, `out` is left unchanged.
+        fn serialize_to_vec(&self, out: &mut Vec<u8>) -> Result<(), crate::SerializeError>;
+
+        /// Serializes the message into the front of `buf` and returns the
+        /// number of bytes written.
+        ///
+        /// Fails with [`SerializeErrorKind::BufferTooSmall`] if the message
+        /// does not fit.
+        ///
+        /// [`SerializeErrorKind::BufferTooSmall`]: crate::SerializeErrorKind::BufferTooSmall
+        fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, crate::SerializeError>;
+
+        /// Serializes the message and writes it to `writer`.
+        ///
+        /// This allocates a buffer for the serialized message on every call;
+        /// use [`serialize_to_writer_with_buffer`] to reuse one instead.
+        ///
+        /// [`serialize_to_writer_with_buffer`]: Self::serialize_to_writer_with_buffer
+        fn serialize_to_writer(&self, writer: impl Write) -> Result<(), crate::WriteError> {
+            self.serialize_to_writer_with_buffer(writer, &mut Vec::new())
+        }
+
+        /// Serializes the message into `buf` and writes it to `writer`.
+        ///
+        /// `buf` is cleared first and keeps its capacity, so reusing it across
+        /// calls avoids allocating once it has grown to fit the largest
+        /// message.
+        fn serialize_to_writer_with_buffer(
+            &self,
+            mut writer: impl Write,
+            buf: &mut Vec<u8>,
+        ) -> Result<(), crate::WriteError> {
+            buf.clear();
+            self.serialize_to_vec(buf)?;
+            writer.write_all(buf)?;
+            Ok(())
+        }
     }
 }
 
@@ -94,6 +198,7 @@ pub(crate) mod read {
 pub(crate) mod write {
     use super::SealedInternal;
     use crate::AsView;
+    use std::io::Read;
 
     pub trait Clear: SealedInternal {
         fn clear(&mut self);
@@ -101,6 +206,20 @@ pub(crate) mod write {
 
     pub trait ClearAndParse: SealedInternal {
         fn clear_and_parse(&mut self, data: &[u8]) -> Result<(), crate::ParseError>;
+
+        /// Parses all of the bytes produced by `reader` and merges them into
+        /// this message.
+        ///
+        /// Fails with [`ReadError::TooLarge`] without modifying the message if
+        /// `reader` produces more than `max_size` bytes. If parsing fails,
+        /// the message may have been partially modified.
+        ///
+        /// [`ReadError::TooLarge`]: crate::ReadError::TooLarge
+        fn merge_from_reader(
+            &mut self,
+            reader: impl Read,
+            max_size: usize,
+        ) -> Result<(), crate::ReadError>;
     }
 
     pub trait MergeFrom: AsView + SealedInternal {
@@ -8,12 +8,21 @@
 // Rust Protobuf runtime using the C++ kernel.
 
 use crate::__internal::{Enum, Private};
+use crate::reflect::{
+    EnumDescriptor, FieldDescriptor, FieldType, MessageDescriptor, MessageRef, MessageRefMut,
+    ReflectValueRef,
+};
 use crate::{
-    IntoProxied, Map, MapIter, MapMut, MapView, Mut, ProtoBytes, ProtoStr, ProtoString, Proxied,
-    ProxiedInMapValue, ProxiedInRepeated, Repeated, RepeatedMut, RepeatedView, View,
+    ContiguousRepeated, ExtensionType, IntoProxied, JsonParseOptions, JsonPrintOptions, Map,
+    MapIter, MapMut, MapView, Mut, ParseError, ParseErrorKind, ParseOptions, ProtoBytes,
+    ProtoBytesCow, ProtoStr, ProtoString, ProtoStringCow, Proxied, ProxiedInMapValue,
+    ProxiedInRepeated, Repeated, RepeatedMut, RepeatedView, SerializeError, SerializeErrorKind,
+    SerializeOptions, TextFormatPrintOptions, UnknownFields, UnknownValue, View,
 };
 use core::fmt::Debug;
 use paste::paste;
+use std::any::TypeId;
+use std::collections::HashMap;
 use std::convert::identity;
 use std::ffi::{c_int, c_void};
 use std::fmt;
@@ -22,6 +31,7 @@ use std::mem::{ManuallyDrop, MaybeUninit};
 use std::ops::Deref;
 use std::ptr::{self, NonNull};
 use std::slice;
+use std::sync::{Mutex, OnceLock, PoisonError};
 
 /// Defines a set of opaque, unique, non-accessible pointees.
 ///
@@ -76,6 +86,96 @@ mod _opaque_pointees {
         _data: [u8; 0],
         _marker: std::marker::PhantomData<(*mut u8, ::std::marker::PhantomPinned)>,
     }
+
+    /// Opaque pointee for [`RawUnknownFieldSet`]
+    ///
+    /// This type is not meant to be dereferenced in Rust code.
+    /// It is only meant to provide type safety for raw pointers
+    /// which are manipulated behind FFI.
+    ///
+    /// [`RawUnknownFieldSet`]: super::RawUnknownFieldSet
+    #[repr(C)]
+    pub struct RawUnknownFieldSetData {
+        _data: [u8; 0],
+        _marker: std::marker::PhantomData<(*mut u8, ::std::marker::PhantomPinned)>,
+    }
+
+    /// Opaque pointee for a C++ `DescriptorPool`
+    ///
+    /// This type is not meant to be dereferenced in Rust code.
+    /// It is only meant to provide type safety for raw pointers
+    /// which are manipulated behind FFI.
+    #[repr(C)]
+    pub struct DescriptorPoolData {
+        _data: [u8; 0],
+        _marker: std::marker::PhantomData<(*mut u8, ::std::marker::PhantomPinned)>,
+    }
+
+    /// Opaque pointee for a C++ `FileDescriptor`
+    ///
+    /// This type is not meant to be dereferenced in Rust code.
+    /// It is only meant to provide type safety for raw pointers
+    /// which are manipulated behind FFI.
+    #[repr(C)]
+    pub struct FileDescriptorData {
+        _data: [u8; 0],
+        _marker: std::marker::PhantomData<(*mut u8, ::std::marker::PhantomPinned)>,
+    }
+
+    /// Opaque pointee for a C++ `Descriptor`
+    ///
+    /// This type is not meant to be dereferenced in Rust code.
+    /// It is only meant to provide type safety for raw pointers
+    /// which are manipulated behind FFI.
+    #[repr(C)]
+    pub struct DescriptorData {
+        _data: [u8; 0],
+        _marker: std::marker::PhantomData<(*mut u8, ::std::marker::PhantomPinned)>,
+    }
+
+    /// Opaque pointee for a C++ `FieldDescriptor`
+    ///
+    /// This type is not meant to be dereferenced in Rust code.
+    /// It is only meant to provide type safety for raw pointers
+    /// which are manipulated behind FFI.
+    #[repr(C)]
+    pub struct FieldDescriptorData {
+        _data: [u8; 0],
+        _marker: std::marker::PhantomData<(*mut u8, ::std::marker::PhantomPinned)>,
+    }
+
+    /// Opaque pointee for a C++ `OneofDescriptor`
+    ///
+    /// This type is not meant to be dereferenced in Rust code.
+    /// It is only meant to provide type safety for raw pointers
+    /// which are manipulated behind FFI.
+    #[repr(C)]
+    pub struct OneofDescriptorData {
+        _data: [u8; 0],
+        _marker: std::marker::PhantomData<(*mut u8, ::std::marker::PhantomPinned)>,
+    }
+
+    /// Opaque pointee for a C++ `EnumDescriptor`
+    ///
+    /// This type is not meant to be dereferenced in Rust code.
+    /// It is only meant to provide type safety for raw pointers
+    /// which are manipulated behind FFI.
+    #[repr(C)]
+    pub struct EnumDescriptorData {
+        _data: [u8; 0],
+        _marker: std::marker::PhantomData<(*mut u8, ::std::marker::PhantomPinned)>,
+    }
+
+    /// Opaque pointee for a C++ `EnumValueDescriptor`
+    ///
+    /// This type is not meant to be dereferenced in Rust code.
+    /// It is only meant to provide type safety for raw pointers
+    /// which are manipulated behind FFI.
+    #[repr(C)]
+    pub struct EnumValueDescriptorData {
+        _data: [u8; 0],
+        _marker: std::marker::PhantomData<(*mut u8, ::std::marker::PhantomPinned)>,
+    }
 }
 
 /// A raw pointer to the underlying message for this runtime.
@@ -104,8 +204,35 @@ pub struct InnerProtoString {
 extern "C" {
     pub fn proto2_rust_Message_delete(m: RawMessage);
     pub fn proto2_rust_Message_clear(m: RawMessage);
-    pub fn proto2_rust_Message_parse(m: RawMessage, input: SerializedData) -> bool;
-    pub fn proto2_rust_Message_serialize(m: RawMessage, output: &mut SerializedData) -> bool;
+    pub fn proto2_rust_Message_parse(m: RawMessage, input: PtrAndLen) -> ParseResult;
+    pub fn proto2_rust_Message_merge_parse(m: RawMessage, input: PtrAndLen) -> ParseResult;
+    pub fn proto2_rust_Message_parse_with_options(
+        m: RawMessage,
+        input: PtrAndLen,
+        options: RawParseOptions,
+    ) -> ParseResult;
+    pub fn proto2_rust_Message_serialize(
+        m: RawMessage,
+        output: &mut SerializedData,
+    ) -> SerializeResult;
+    pub fn proto2_rust_Message_byte_size(
+        m: RawMessage,
+        check_required: bool,
+        size: &mut usize,
+    ) -> SerializeResult;
+    pub fn proto2_rust_Message_serialize_with_cached_sizes(
+        m: RawMessage,
+        buf: *mut u8,
+        deterministic: bool,
+    );
+    pub fn proto2_rust_Message_serialize_into(
+        m: RawMessage,
+        buf: *mut u8,
+        len: usize,
+        deterministic: bool,
+        check_required: bool,
+        size: &mut usize,
+    ) -> SerializeResult;
     pub fn proto2_rust_Message_copy_from(dst: RawMessage, src: RawMessage) -> bool;
     pub fn proto2_rust_Message_merge_from(dst: RawMessage, src: RawMessage) -> bool;
 }
@@ -320,6 +447,224 @@ impl From<RustStringRawParts> for String {
     }
 }
 
+// LINT.IfChange(parse_status)
+/// The reason a C++ parse failed. The numbering matches `upb_DecodeStatus`.
+#[doc(hidden)]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseStatus {
+    Ok = 0,
+    Malformed = 1,
+    OutOfMemory = 2,
+    BadUtf8 = 3,
+    MaxDepthExceeded = 4,
+    MissingRequired = 5,
+}
+
+/// The outcome of `proto2_rust_Message_parse`.
+///
+/// Owns `field_path`, so it must always be consumed with
+/// [`ParseResult::into_result`].
+#[doc(hidden)]
+#[repr(C)]
+pub struct ParseResult {
+    status: ParseStatus,
+    has_offset: bool,
+    offset: usize,
+    field_path: RustStringRawParts,
+}
+
+/// The kernel-level view of [`ParseOptions`].
+#[doc(hidden)]
+#[repr(C)]
+pub struct RawParseOptions {
+    /// Negative to use the default recursion limit.
+    recursion_limit: i32,
+    check_required: bool,
+    validate_utf8: bool,
+    /// The sorted `FieldDescriptor`s of the registered extensions, or null to
+    /// parse every extension that is linked into the binary.
+    extension_registry: *const *const c_void,
+    extension_registry_len: usize,
+}
+// LINT.ThenChange(//depot/google3/third_party/protobuf/rust/cpp_kernel/message.cc:
+// parse_status)
+
+impl From<&ParseOptions<'_>> for RawParseOptions {
+    fn from(options: &ParseOptions<'_>) -> Self {
+        RawParseOptions {
+            recursion_limit: options.recursion_limit.map_or(-1, i32::from),
+            check_required: options.check_required,
+            validate_utf8: options.validate_utf8,
+            extension_registry: options
+                .extension_registry
+                .map_or(ptr::null(), |r| r.inner(Private).descriptors.as_ptr()),
+            extension_registry_len: options
+                .extension_registry
+                .map_or(0, |r| r.inner(Private).descriptors.len()),
+        }
+    }
+}
+
+/// Parses `data` into `msg`, replacing its contents, as controlled by
+/// `options`.
+///
+/// # Safety
+/// - `msg` must be a valid, mutable message.
+pub unsafe fn parse_with_options(
+    msg: RawMessage,
+    data: &[u8],
+    options: &ParseOptions<'_>,
+) -> Result<(), ParseError> {
+    options.check_len(data)?;
+    // SAFETY:
+    // - `msg` is a valid, mutable message as promised by the caller.
+    // - `data` is valid to read for the duration of the call.
+    unsafe { proto2_rust_Message_parse_with_options(msg, data.into(), options.into()) }
+        .into_result()
+}
+
+impl ParseResult {
+    pub fn into_result(self) -> Result<(), ParseError> {
+        let field_path: String = self.field_path.into();
+        let kind = match self.status {
+            ParseStatus::Ok => return Ok(()),
+            ParseStatus::Malformed => ParseErrorKind::Malformed,
+            ParseStatus::OutOfMemory => ParseErrorKind::OutOfMemory,
+            ParseStatus::BadUtf8 => ParseErrorKind::BadUtf8,
+            ParseStatus::MaxDepthExceeded => ParseErrorKind::MaxDepthExceeded,
+            ParseStatus::MissingRequired => ParseErrorKind::MissingRequired,
+        };
+        let mut err = ParseError::new(Private, kind);
+        if self.has_offset {
+            err = err.with_offset(Private, self.offset);
+        }
+        if !field_path.is_empty() {
+            err = err.with_field_path(Private, field_path);
+        }
+        Err(err)
+    }
+}
+
+// LINT.IfChange(serialize_status)
+/// The reason a C++ serialization failed. The numbering matches
+/// `upb_EncodeStatus`.
+#[doc(hidden)]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerializeStatus {
+    Ok = 0,
+    OutOfMemory = 1,
+    MaxDepthExceeded = 2,
+    MissingRequired = 3,
+    TooLarge = 4,
+    BufferTooSmall = 5,
+}
+
+/// The outcome of `proto2_rust_Message_serialize`.
+///
+/// Owns `missing_required_fields`, so it must always be consumed with
+/// [`SerializeResult::into_result`].
+#[doc(hidden)]
+#[repr(C)]
+pub struct SerializeResult {
+    status: SerializeStatus,
+    missing_required_fields: RustStringRawParts,
+}
+// LINT.ThenChange(//depot/google3/third_party/protobuf/rust/cpp_kernel/message.cc:
+// serialize_status)
+
+impl SerializeResult {
+    pub fn into_result(self) -> Result<(), SerializeError> {
+        let missing_required_fields: String = self.missing_required_fields.into();
+        let kind = match self.status {
+            SerializeStatus::Ok => return Ok(()),
+            SerializeStatus::OutOfMemory => SerializeErrorKind::OutOfMemory,
+            SerializeStatus::MaxDepthExceeded => SerializeErrorKind::MaxDepthExceeded,
+            SerializeStatus::MissingRequired => SerializeErrorKind::MissingRequired,
+            SerializeStatus::TooLarge => SerializeErrorKind::TooLarge,
+            SerializeStatus::BufferTooSmall => SerializeErrorKind::BufferTooSmall,
+        };
+        let missing_required_fields = missing_required_fields
+            .split('\0')
+            .filter(|path| !path.is_empty())
+            .map(String::from)
+            .collect();
+        Err(SerializeError::new(Private, kind)
+            .with_missing_required_fields(Private, missing_required_fields))
+    }
+}
+
+/// Serializes `msg` as controlled by `options`.
+///
+/// # Safety
+/// - `msg` must be a valid message.
+pub unsafe fn serialize_with_options(
+    msg: RawMessage,
+    options: &SerializeOptions,
+) -> Result<Vec<u8>, SerializeError> {
+    let mut out = Vec::new();
+    // SAFETY: `msg` is a valid message as promised by the caller.
+    unsafe { serialize_to_vec(msg, &mut out, options) }?;
+    Ok(out)
+}
+
+/// Serializes `msg` into the front of `buf`, returning the number of bytes
+/// written.
+///
+/// # Safety
+/// - `msg` must be a valid message.
+pub unsafe fn serialize_into(msg: RawMessage, buf: &mut [u8]) -> Result<usize, SerializeError> {
+    let mut size = 0;
+    // SAFETY:
+    // - `msg` is a valid message as promised by the caller.
+    // - `buf` is valid to write for `buf.len()` bytes.
+    unsafe {
+        proto2_rust_Message_serialize_into(
+            msg,
+            buf.as_mut_ptr(),
+            buf.len(),
+            false,
+            false,
+            &mut size,
+        )
+    }
+    .into_result()?;
+    Ok(size)
+}
+
+/// Appends the serialization of `msg` to `out`.
+///
+/// The serialized size is computed first, so that `out` is grown at most once
+/// and the message is serialized straight into its spare capacity.
+///
+/// # Safety
+/// - `msg` must be a valid message.
+pub unsafe fn serialize_to_vec(
+    msg: RawMessage,
+    out: &mut Vec<u8>,
+    options: &SerializeOptions,
+) -> Result<(), SerializeError> {
+    let mut size = 0;
+    // SAFETY: `msg` is a valid message as promised by the caller.
+    unsafe { proto2_rust_Message_byte_size(msg, options.is_check_required(), &mut size) }
+        .into_result()?;
+    out.reserve(size);
+    // SAFETY:
+    // - `msg` is a valid message as promised by the caller, and its sizes were
+    //   just cached.
+    // - the spare capacity of `out` is valid to write for `size` bytes.
+    unsafe {
+        proto2_rust_Message_serialize_with_cached_sizes(
+            msg,
+            out.spare_capacity_mut().as_mut_ptr().cast(),
+            options.is_deterministic(),
+        );
+        out.set_len(out.len() + size);
+    }
+    Ok(())
+}
+
 extern "C" {
     fn proto2_rust_utf8_debug_string(msg: RawMessage) -> RustStringRawParts;
 }
@@ -395,129 +740,1984 @@ impl<'msg> MutatorMessageRef<'msg> {
     }
 }
 
-/// The raw type-erased version of an owned `Repeated`.
-#[derive(Debug)]
+/// The kernel-specific part of an [`ExtensionId`](crate::ExtensionId): the
+/// generated thunks that access the extension.
+///
+/// `get` writes the value of the extension to its out pointer, and `set`
+/// reads the new value from its pointer. Scalars are passed as themselves,
+/// `string` and `bytes` values as a [`PtrAndLen`], and messages and repeated
+/// fields as a pointer to the C++ message or `RepeatedField`/`RepeatedPtrField`.
+///
+/// `descriptor` returns the `FieldDescriptor` of the extension, or null if the
+/// extension is declared in a lite file.
 #[doc(hidden)]
-pub struct InnerRepeated {
-    raw: RawRepeatedField,
+#[derive(Clone, Copy, Debug)]
+pub struct InnerExtension {
+    has: unsafe extern "C" fn(RawMessage) -> bool,
+    clear: unsafe extern "C" fn(RawMessage),
+    get: unsafe extern "C" fn(RawMessage, *mut c_void),
+    set: unsafe extern "C" fn(RawMessage, *const c_void),
+    descriptor: unsafe extern "C" fn() -> *const c_void,
 }
 
-impl InnerRepeated {
-    pub fn as_mut(&mut self) -> InnerRepeatedMut<'_> {
-        InnerRepeatedMut::new(self.raw)
+impl InnerExtension {
+    /// # Safety
+    /// - The thunks must access the same extension, and pass its values as
+    ///   described on [`InnerExtension`].
+    pub const unsafe fn new(
+        has: unsafe extern "C" fn(RawMessage) -> bool,
+        clear: unsafe extern "C" fn(RawMessage),
+        get: unsafe extern "C" fn(RawMessage, *mut c_void),
+        set: unsafe extern "C" fn(RawMessage, *const c_void),
+        descriptor: unsafe extern "C" fn() -> *const c_void,
+    ) -> Self {
+        InnerExtension { has, clear, get, set, descriptor }
     }
 
-    pub fn raw(&self) -> RawRepeatedField {
-        self.raw
+    /// # Safety
+    /// - `msg` must be a message of the type extended by `self`.
+    pub unsafe fn has(&self, msg: RawMessage) -> bool {
+        // SAFETY: `msg` is extended by `self`, as promised by the caller.
+        unsafe { (self.has)(msg) }
     }
 
     /// # Safety
-    /// - `raw` must be a valid `proto2::RepeatedField*` or
-    ///   `proto2::RepeatedPtrField*`.
-    pub unsafe fn from_raw(raw: RawRepeatedField) -> Self {
-        Self { raw }
+    /// - `msg` must be a mutable message of the type extended by `self`.
+    pub unsafe fn clear(&self, msg: RawMessage) {
+        // SAFETY: `msg` is extended by `self`, as promised by the caller.
+        unsafe { (self.clear)(msg) }
     }
 }
 
-/// The raw type-erased pointer version of `RepeatedMut`.
-///
-/// Contains a `proto2::RepeatedField*` or `proto2::RepeatedPtrField*`.
-#[derive(Clone, Copy, Debug)]
-#[doc(hidden)]
-pub struct InnerRepeatedMut<'msg> {
-    pub(crate) raw: RawRepeatedField,
-    _phantom: PhantomData<&'msg ()>,
+macro_rules! impl_extension_type_for_scalars {
+    ($($t:ty),*) => {
+        $(
+            impl ExtensionType for $t {
+                type Default = $t;
+
+                fn default_view(_private: Private, default: $t) -> $t {
+                    default
+                }
+
+                unsafe fn get_extension<'msg>(
+                    _private: Private,
+                    msg: RawMessage,
+                    ext: &InnerExtension,
+                    _default: $t,
+                ) -> View<'msg, $t> {
+                    let mut val = MaybeUninit::<$t>::uninit();
+                    // SAFETY:
+                    // - `msg` is extended by `ext`, as promised by the caller.
+                    // - `ext.get` writes a `$t` to `val`.
+                    unsafe {
+                        (ext.get)(msg, val.as_mut_ptr().cast());
+                        val.assume_init()
+                    }
+                }
+
+                unsafe fn set_extension(
+                    _private: Private,
+                    msg: MutatorMessageRef<'_>,
+                    ext: &InnerExtension,
+                    val: impl IntoProxied<$t>,
+                ) {
+                    let val: $t = val.into_proxied(Private);
+                    // SAFETY:
+                    // - `msg` is extended by `ext`, as promised by the caller.
+                    // - `ext.set` reads a `$t` from `val`.
+                    unsafe { (ext.set)(msg.msg(), (&val as *const $t).cast()) }
+                }
+            }
+        )*
+    };
 }
 
-impl<'msg> InnerRepeatedMut<'msg> {
-    #[doc(hidden)]
-    pub fn new(raw: RawRepeatedField) -> Self {
-        InnerRepeatedMut { raw, _phantom: PhantomData }
+impl_extension_type_for_scalars!(f32, f64, i32, u32, i64, u64, bool);
+
+/// # Safety
+/// - `msg` must be a message of the type extended by `ext`.
+/// - `ext` must be a `string` or `bytes` extension.
+/// - `msg` must be valid for `'msg`.
+unsafe fn get_bytes_extension<'msg>(msg: RawMessage, ext: &InnerExtension) -> &'msg [u8] {
+    let mut val = PtrAndLen { ptr: ptr::null(), len: 0 };
+    // SAFETY:
+    // - `msg` is extended by `ext`, as promised by the caller.
+    // - `ext.get` writes a `PtrAndLen` to `val` that borrows from `msg`.
+    unsafe {
+        (ext.get)(msg, (&mut val as *mut PtrAndLen).cast());
+        val.as_ref()
     }
 }
 
-trait CppTypeConversions: Proxied {
-    type InsertElemType;
-    type ElemType;
-
-    fn elem_to_view<'msg>(v: Self::ElemType) -> View<'msg, Self>;
-    fn into_insertelem(v: Self) -> Self::InsertElemType;
+/// # Safety
+/// - `msg` must be a message of the type extended by `ext`.
+/// - `ext` must be a `string` or `bytes` extension.
+unsafe fn set_bytes_extension(msg: MutatorMessageRef<'_>, ext: &InnerExtension, val: &[u8]) {
+    let val = PtrAndLen::from(val);
+    // SAFETY:
+    // - `msg` is extended by `ext`, as promised by the caller.
+    // - `ext.set` copies the bytes that `val` points to.
+    unsafe { (ext.set)(msg.msg(), (&val as *const PtrAndLen).cast()) }
 }
 
-macro_rules! impl_cpp_type_conversions_for_scalars {
-    ($($t:ty),* $(,)?) => {
-        $(
-            impl CppTypeConversions for $t {
-                type InsertElemType = Self;
-                type ElemType = Self;
+impl ExtensionType for ProtoBytes {
+    type Default = &'static [u8];
 
-                fn elem_to_view<'msg>(v: Self) -> View<'msg, Self> {
-                    v
-                }
+    fn default_view(_private: Private, default: &'static [u8]) -> &'static [u8] {
+        default
+    }
 
-                fn into_insertelem(v: Self) -> Self {
-                    v
-                }
-            }
-        )*
+    unsafe fn get_extension<'msg>(
+        _private: Private,
+        msg: RawMessage,
+        ext: &InnerExtension,
+        _default: &'static [u8],
+    ) -> &'msg [u8] {
+        // SAFETY: `msg` is extended by `ext`, as promised by the caller.
+        unsafe { get_bytes_extension(msg, ext) }
+    }
+
+    unsafe fn set_extension(
+        _private: Private,
+        msg: MutatorMessageRef<'_>,
+        ext: &InnerExtension,
+        val: impl IntoProxied<ProtoBytes>,
+    ) {
+        let val = val.into_proxied(Private);
+        // SAFETY: `msg` is extended by `ext`, as promised by the caller.
+        unsafe { set_bytes_extension(msg, ext, val.as_view()) }
     }
 }
 
-impl_cpp_type_conversions_for_scalars!(i32, u32, i64, u64, f32, f64, bool);
+impl ExtensionType for ProtoString {
+    type Default = &'static [u8];
 
-impl CppTypeConversions for ProtoString {
-    type InsertElemType = CppStdString;
-    type ElemType = PtrAndLen;
+    fn default_view(_private: Private, default: &'static [u8]) -> &'static ProtoStr {
+        // SAFETY: default values of `string` extensions are UTF-8.
+        unsafe { ProtoStr::from_utf8_unchecked(default) }
+    }
 
-    fn elem_to_view<'msg>(v: PtrAndLen) -> View<'msg, ProtoString> {
-        ptrlen_to_str(v)
+    unsafe fn get_extension<'msg>(
+        _private: Private,
+        msg: RawMessage,
+        ext: &InnerExtension,
+        _default: &'static [u8],
+    ) -> &'msg ProtoStr {
+        // SAFETY: `msg` is extended by `ext`, as promised by the caller.
+        let bytes = unsafe { get_bytes_extension(msg, ext) };
+        // SAFETY: `string` extensions are UTF-8, as with `string` fields.
+        unsafe { ProtoStr::from_utf8_unchecked(bytes) }
     }
 
-    fn into_insertelem(v: Self) -> CppStdString {
-        v.into_inner(Private).into_raw()
+    unsafe fn set_extension(
+        _private: Private,
+        msg: MutatorMessageRef<'_>,
+        ext: &InnerExtension,
+        val: impl IntoProxied<ProtoString>,
+    ) {
+        let val = val.into_proxied(Private);
+        // SAFETY: `msg` is extended by `ext`, as promised by the caller.
+        unsafe { set_bytes_extension(msg, ext, val.as_bytes()) }
     }
 }
 
-impl CppTypeConversions for ProtoBytes {
-    type InsertElemType = CppStdString;
-    type ElemType = PtrAndLen;
+/// Returns the message that a message extension of `msg` holds, which is the
+/// default instance if the extension is not set.
+///
+/// # Safety
+/// - `msg` must be a message of the type extended by `ext`.
+/// - `ext` must be a singular message extension.
+/// - `msg` must be valid for `'msg`.
+#[doc(hidden)]
+pub unsafe fn get_message_extension(msg: RawMessage, ext: &InnerExtension) -> Option<RawMessage> {
+    let mut val: *const c_void = ptr::null();
+    // SAFETY:
+    // - `msg` is extended by `ext`, as promised by the caller.
+    // - `ext.get` writes a pointer to the extension's message to `val`.
+    unsafe { (ext.get)(msg, (&mut val as *mut *const c_void).cast()) };
+    NonNull::new(val as *mut _)
+}
 
-    fn elem_to_view<'msg>(v: Self::ElemType) -> View<'msg, Self> {
-        ptrlen_to_bytes(v)
+/// Sets a message extension of `msg` to a copy of `val`.
+///
+/// # Safety
+/// - `msg` must be a message of the type extended by `ext`.
+/// - `ext` must be a singular message extension.
+/// - `val` must be a message of the type of the extension.
+#[doc(hidden)]
+pub unsafe fn set_message_extension(
+    msg: MutatorMessageRef<'_>,
+    ext: &InnerExtension,
+    val: RawMessage,
+) {
+    // SAFETY:
+    // - `msg` is extended by `ext`, as promised by the caller.
+    // - `ext.set` copies the message that `val` points to.
+    unsafe { (ext.set)(msg.msg(), val.as_ptr() as *const c_void) }
+}
+
+impl<T: ProxiedInRepeated + 'static> ExtensionType for Repeated<T> {
+    type Default = ();
+
+    fn default_view(_private: Private, _default: ()) -> RepeatedView<'static, T> {
+        empty_array()
     }
 
-    fn into_insertelem(v: Self) -> CppStdString {
-        v.into_inner(Private).into_raw()
+    unsafe fn get_extension<'msg>(
+        _private: Private,
+        msg: RawMessage,
+        ext: &InnerExtension,
+        _default: (),
+    ) -> RepeatedView<'msg, T> {
+        let mut val: *const c_void = ptr::null();
+        // SAFETY:
+        // - `msg` is extended by `ext`, as promised by the caller.
+        // - `ext.get` writes a pointer to the repeated field, which is empty
+        //   but valid if the extension is not set.
+        unsafe {
+            (ext.get)(msg, (&mut val as *mut *const c_void).cast());
+            RepeatedView::from_raw(Private, NonNull::new_unchecked(val as *mut _))
+        }
     }
+
+    unsafe fn set_extension(
+        _private: Private,
+        msg: MutatorMessageRef<'_>,
+        ext: &InnerExtension,
+        val: impl IntoProxied<Repeated<T>>,
+    ) {
+        let val = val.into_proxied(Private);
+        // SAFETY:
+        // - `msg` is extended by `ext`, as promised by the caller.
+        // - `ext.set` copies the repeated field that `val` points to.
+        unsafe { (ext.set)(msg.msg(), val.as_view().as_raw(Private).as_ptr() as *const c_void) }
+    }
+}
+
+/// Returns a static empty RepeatedView.
+pub fn empty_array<T: ProxiedInRepeated + 'static>() -> RepeatedView<'static, T> {
+    // C++ repeated fields of different element types have different layouts,
+    // so unlike with upb, one empty repeated field is kept for every element
+    // type. They are never freed.
+    static EMPTY_REPEATED: OnceLock<Mutex<HashMap<TypeId, usize>>> = OnceLock::new();
+
+    let raw = *EMPTY_REPEATED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(TypeId::of::<T>())
+        .or_insert_with(|| {
+            let repeated = ManuallyDrop::new(Repeated::<T>::new());
+            repeated.as_view().as_raw(Private).as_ptr() as usize
+        });
+    // SAFETY: `raw` is a `Repeated<T>` that is never mutated or freed.
+    unsafe { RepeatedView::from_raw(Private, NonNull::new_unchecked(raw as *mut _)) }
+}
+
+/// The kernel-specific part of an
+/// [`ExtensionRegistry`](crate::ExtensionRegistry): the sorted
+/// `FieldDescriptor`s of the registered extensions.
+#[doc(hidden)]
+#[derive(Debug, Default)]
+pub struct InnerExtensionRegistry {
+    descriptors: Vec<*const c_void>,
 }
 
-macro_rules! impl_repeated_primitives {
-    (@impl $($t:ty => [
-        $new_thunk:ident,
-        $free_thunk:ident,
-        $add_thunk:ident,
-        $size_thunk:ident,
-        $get_thunk:ident,
-        $set_thunk:ident,
-        $clear_thunk:ident,
-        $copy_from_thunk:ident,
-        $reserve_thunk:ident $(,)?
-    ]),* $(,)?) => {
-        $(
-            extern "C" {
-                fn $new_thunk() -> RawRepeatedField;
-                fn $free_thunk(f: RawRepeatedField);
-                fn $add_thunk(f: RawRepeatedField, v: <$t as CppTypeConversions>::InsertElemType);
-                fn $size_thunk(f: RawRepeatedField) -> usize;
-                fn $get_thunk(
-                    f: RawRepeatedField,
-                    i: usize) -> <$t as CppTypeConversions>::ElemType;
-                fn $set_thunk(
-                    f: RawRepeatedField,
+impl InnerExtensionRegistry {
+    pub fn new() -> Self {
+        InnerExtensionRegistry { descriptors: Vec::new() }
+    }
+
+    /// # Panics
+    /// Panics if `ext` is declared in a lite file: the C++ kernel can only
+    /// leave unregistered extensions unparsed with reflection.
+    pub fn add(&mut self, ext: &InnerExtension) {
+        // SAFETY: `descriptor` has no preconditions.
+        let descriptor = unsafe { (ext.descriptor)() };
+        assert!(
+            !descriptor.is_null(),
+            "the C++ kernel does not support extension registries for lite extensions"
+        );
+        if let Err(pos) = self.descriptors.binary_search(&descriptor) {
+            self.descriptors.insert(pos, descriptor);
+        }
+    }
+}
+
+// SAFETY:
+// - The registry is only mutated through `&mut self`.
+// - The descriptors are immutable and live for the whole program.
+unsafe impl Send for InnerExtensionRegistry {}
+unsafe impl Sync for InnerExtensionRegistry {}
+
+/// A raw pointer to a C++ `UnknownFieldSet`.
+#[doc(hidden)]
+pub type RawUnknownFieldSet = NonNull<_opaque_pointees::RawUnknownFieldSetData>;
+
+// LINT.IfChange(unknown_field)
+/// The numbering matches `UnknownField::Type`.
+#[doc(hidden)]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownFieldType {
+    Varint = 0,
+    Fixed32 = 1,
+    Fixed64 = 2,
+    LengthDelimited = 3,
+    Group = 4,
+}
+
+/// A field of an `UnknownFieldSet`. Only the member that belongs to `type_`
+/// is meaningful.
+#[doc(hidden)]
+#[repr(C)]
+pub struct RawUnknownField {
+    number: u32,
+    type_: UnknownFieldType,
+    int_value: u64,
+    bytes: PtrAndLen,
+    group: Option<RawUnknownFieldSet>,
+}
+// LINT.ThenChange(//depot/google3/third_party/protobuf/rust/cpp_kernel/unknown_fields.cc:
+// unknown_field)
+
+extern "C" {
+    fn proto2_rust_Message_unknown_fields(m: RawMessage) -> Option<RawUnknownFieldSet>;
+    fn proto2_rust_Message_clear_unknown_fields(m: RawMessage);
+    fn proto2_rust_Message_discard_unknown_fields(m: RawMessage);
+    fn proto2_rust_UnknownFieldSet_field_count(s: RawUnknownFieldSet) -> usize;
+    fn proto2_rust_UnknownFieldSet_field(s: RawUnknownFieldSet, i: usize) -> RawUnknownField;
+}
+
+/// The kernel-specific part of [`UnknownFields`], which is the C++
+/// `UnknownFieldSet` of the message.
+#[doc(hidden)]
+#[derive(Clone, Copy)]
+pub struct InnerUnknownFields<'msg> {
+    // None for lite messages, which have no `UnknownFieldSet`.
+    raw: Option<RawUnknownFieldSet>,
+    _phantom: PhantomData<&'msg ()>,
+}
+
+impl<'msg> InnerUnknownFields<'msg> {
+    fn len(&self) -> usize {
+        // SAFETY: `raw` is a valid `UnknownFieldSet` for `'msg`.
+        self.raw.map_or(0, |raw| unsafe { proto2_rust_UnknownFieldSet_field_count(raw) })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> InnerUnknownFieldsIter<'msg> {
+        InnerUnknownFieldsIter { fields: *self, index: 0, len: self.len() }
+    }
+}
+
+#[doc(hidden)]
+pub struct InnerUnknownFieldsIter<'msg> {
+    fields: InnerUnknownFields<'msg>,
+    index: usize,
+    len: usize,
+}
+
+impl<'msg> Iterator for InnerUnknownFieldsIter<'msg> {
+    type Item = (u32, UnknownValue<'msg>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == self.len {
+            return None;
+        }
+        // `len` is only non-zero if there is an `UnknownFieldSet`.
+        let raw = self.fields.raw?;
+        // SAFETY: `raw` is valid for `'msg` and `self.index` is in bounds.
+        let field = unsafe { proto2_rust_UnknownFieldSet_field(raw, self.index) };
+        self.index += 1;
+        let value = match field.type_ {
+            UnknownFieldType::Varint => UnknownValue::Varint(field.int_value),
+            UnknownFieldType::Fixed32 => UnknownValue::Fixed32(field.int_value as u32),
+            UnknownFieldType::Fixed64 => UnknownValue::Fixed64(field.int_value),
+            // SAFETY: the bytes are owned by the `UnknownFieldSet`, which is
+            // valid and not mutated for `'msg`.
+            UnknownFieldType::LengthDelimited => {
+                UnknownValue::LengthDelimited(unsafe { field.bytes.as_ref() })
+            }
+            UnknownFieldType::Group => UnknownValue::Group(UnknownFields::new(
+                Private,
+                InnerUnknownFields { raw: field.group, _phantom: PhantomData },
+            )),
+        };
+        Some((field.number, value))
+    }
+}
+
+/// # Safety
+/// - `msg` must be valid for `'msg` and must not be mutated during `'msg`.
+pub unsafe fn unknown_fields<'msg>(msg: RawMessage) -> UnknownFields<'msg> {
+    // SAFETY: `msg` is valid, as promised by the caller.
+    let raw = unsafe { proto2_rust_Message_unknown_fields(msg) };
+    UnknownFields::new(Private, InnerUnknownFields { raw, _phantom: PhantomData })
+}
+
+/// Removes the unknown fields of `msg`, but not those of its submessages.
+///
+/// # Safety
+/// - `msg` must be a valid mutable message.
+pub unsafe fn clear_unknown_fields(msg: RawMessage) {
+    // SAFETY: `msg` is valid and mutable, as promised by the caller.
+    unsafe { proto2_rust_Message_clear_unknown_fields(msg) }
+}
+
+/// Removes the unknown fields of `msg` and of all of its submessages.
+///
+/// # Safety
+/// - `msg` must be a valid mutable message.
+pub unsafe fn discard_unknown_fields(msg: RawMessage) {
+    // SAFETY: `msg` is valid and mutable, as promised by the caller.
+    unsafe { proto2_rust_Message_discard_unknown_fields(msg) }
+}
+
+/// The descriptor of a `.proto` file that is compiled into the binary.
+///
+/// The descriptors of most files are part of the C++ generated pool. Files
+/// that are compiled with `optimize_for = LITE_RUNTIME` are not, so their
+/// serialized descriptor is embedded in the gencode and built into a separate
+/// pool the first time that one of its descriptors is requested.
+#[doc(hidden)]
+pub struct FileDescriptorInit {
+    name: &'static str,
+    // Empty unless the file is lite.
+    serialized: &'static [u8],
+    deps: &'static [&'static FileDescriptorInit],
+}
+
+impl FileDescriptorInit {
+    /// # Safety
+    /// - `serialized` must be empty or the serialized `FileDescriptorProto` of
+    ///   the file called `name`, and `deps` must hold the files that it
+    ///   imports.
+    pub const unsafe fn new(
+        name: &'static str,
+        serialized: &'static [u8],
+        deps: &'static [&'static FileDescriptorInit],
+    ) -> Self {
+        FileDescriptorInit { name, serialized, deps }
+    }
+}
+
+/// A raw pointer to a C++ `DescriptorPool`.
+type RawDescriptorPool = *mut _opaque_pointees::DescriptorPoolData;
+/// A raw pointer to a C++ `FileDescriptor`.
+type RawFileDescriptor = *const _opaque_pointees::FileDescriptorData;
+/// A raw pointer to a C++ `Descriptor`.
+type RawDescriptor = *const _opaque_pointees::DescriptorData;
+/// A raw pointer to a C++ `FieldDescriptor`.
+type RawFieldDescriptor = *const _opaque_pointees::FieldDescriptorData;
+/// A raw pointer to a C++ `OneofDescriptor`.
+type RawOneofDescriptor = *const _opaque_pointees::OneofDescriptorData;
+/// A raw pointer to a C++ `EnumDescriptor`.
+type RawEnumDescriptor = *const _opaque_pointees::EnumDescriptorData;
+/// A raw pointer to a C++ `EnumValueDescriptor`.
+type RawEnumValueDescriptor = *const _opaque_pointees::EnumValueDescriptorData;
+
+// Unless noted otherwise, these require a valid descriptor and an index that
+// is in bounds.
+extern "C" {
+    /// Returns the descriptor of the file, building it from `serialized` if
+    /// it is not part of the generated pool. The imports of the file must
+    /// already have been loaded.
+    fn proto2_rust_DescriptorPool_load_file(
+        name: PtrAndLen,
+        serialized: PtrAndLen,
+    ) -> RawFileDescriptor;
+    fn proto2_rust_DescriptorPool_new() -> RawDescriptorPool;
+    fn proto2_rust_DescriptorPool_delete(pool: RawDescriptorPool);
+    /// Builds the file from `serialized`. The imports of the file must
+    /// already have been built. Returns null and sets `error` on failure.
+    fn proto2_rust_DescriptorPool_build_file(
+        pool: RawDescriptorPool,
+        serialized: PtrAndLen,
+        error: *mut Option<CppStdString>,
+    ) -> RawFileDescriptor;
+    fn proto2_rust_DescriptorPool_find_file(
+        pool: RawDescriptorPool,
+        name: PtrAndLen,
+    ) -> RawFileDescriptor;
+    fn proto2_rust_DescriptorPool_find_message(
+        pool: RawDescriptorPool,
+        full_name: PtrAndLen,
+    ) -> RawDescriptor;
+    fn proto2_rust_DescriptorPool_find_enum(
+        pool: RawDescriptorPool,
+        full_name: PtrAndLen,
+    ) -> RawEnumDescriptor;
+    /// `d` must belong to `pool`.
+    fn proto2_rust_DescriptorPool_new_message(
+        pool: RawDescriptorPool,
+        d: RawDescriptor,
+    ) -> Option<RawMessage>;
+    fn proto2_rust_FileDescriptor_find_message(
+        f: RawFileDescriptor,
+        full_name: PtrAndLen,
+    ) -> RawDescriptor;
+    fn proto2_rust_FileDescriptor_find_enum(
+        f: RawFileDescriptor,
+        full_name: PtrAndLen,
+    ) -> RawEnumDescriptor;
+
+    fn proto2_rust_FileDescriptor_name(f: RawFileDescriptor) -> PtrAndLen;
+    fn proto2_rust_FileDescriptor_package(f: RawFileDescriptor) -> PtrAndLen;
+    fn proto2_rust_FileDescriptor_dependency_count(f: RawFileDescriptor) -> c_int;
+    fn proto2_rust_FileDescriptor_dependency(f: RawFileDescriptor, i: c_int) -> RawFileDescriptor;
+    fn proto2_rust_FileDescriptor_message_type_count(f: RawFileDescriptor) -> c_int;
+    fn proto2_rust_FileDescriptor_message_type(f: RawFileDescriptor, i: c_int) -> RawDescriptor;
+    fn proto2_rust_FileDescriptor_enum_type_count(f: RawFileDescriptor) -> c_int;
+    fn proto2_rust_FileDescriptor_enum_type(f: RawFileDescriptor, i: c_int) -> RawEnumDescriptor;
+
+    fn proto2_rust_Descriptor_name(d: RawDescriptor) -> PtrAndLen;
+    fn proto2_rust_Descriptor_full_name(d: RawDescriptor) -> PtrAndLen;
+    fn proto2_rust_Descriptor_file(d: RawDescriptor) -> RawFileDescriptor;
+    fn proto2_rust_Descriptor_containing_type(d: RawDescriptor) -> RawDescriptor;
+    fn proto2_rust_Descriptor_is_map_entry(d: RawDescriptor) -> bool;
+    fn proto2_rust_Descriptor_field_count(d: RawDescriptor) -> c_int;
+    fn proto2_rust_Descriptor_field(d: RawDescriptor, i: c_int) -> RawFieldDescriptor;
+    fn proto2_rust_Descriptor_find_field_by_number(
+        d: RawDescriptor,
+        number: c_int,
+    ) -> RawFieldDescriptor;
+    fn proto2_rust_Descriptor_find_field_by_name(
+        d: RawDescriptor,
+        name: PtrAndLen,
+    ) -> RawFieldDescriptor;
+    fn proto2_rust_Descriptor_real_oneof_decl_count(d: RawDescriptor) -> c_int;
+    fn proto2_rust_Descriptor_oneof_decl(d: RawDescriptor, i: c_int) -> RawOneofDescriptor;
+    fn proto2_rust_Descriptor_nested_type_count(d: RawDescriptor) -> c_int;
+    fn proto2_rust_Descriptor_nested_type(d: RawDescriptor, i: c_int) -> RawDescriptor;
+    fn proto2_rust_Descriptor_enum_type_count(d: RawDescriptor) -> c_int;
+    fn proto2_rust_Descriptor_enum_type(d: RawDescriptor, i: c_int) -> RawEnumDescriptor;
+
+    fn proto2_rust_FieldDescriptor_name(f: RawFieldDescriptor) -> PtrAndLen;
+    fn proto2_rust_FieldDescriptor_full_name(f: RawFieldDescriptor) -> PtrAndLen;
+    fn proto2_rust_FieldDescriptor_json_name(f: RawFieldDescriptor) -> PtrAndLen;
+    fn proto2_rust_FieldDescriptor_number(f: RawFieldDescriptor) -> c_int;
+    fn proto2_rust_FieldDescriptor_index(f: RawFieldDescriptor) -> c_int;
+    fn proto2_rust_FieldDescriptor_type(f: RawFieldDescriptor) -> c_int;
+    fn proto2_rust_FieldDescriptor_is_repeated(f: RawFieldDescriptor) -> bool;
+    fn proto2_rust_FieldDescriptor_is_map(f: RawFieldDescriptor) -> bool;
+    fn proto2_rust_FieldDescriptor_has_presence(f: RawFieldDescriptor) -> bool;
+    fn proto2_rust_FieldDescriptor_is_required(f: RawFieldDescriptor) -> bool;
+    fn proto2_rust_FieldDescriptor_is_extension(f: RawFieldDescriptor) -> bool;
+    fn proto2_rust_FieldDescriptor_containing_type(f: RawFieldDescriptor) -> RawDescriptor;
+    fn proto2_rust_FieldDescriptor_real_containing_oneof(
+        f: RawFieldDescriptor,
+    ) -> RawOneofDescriptor;
+    fn proto2_rust_FieldDescriptor_message_type(f: RawFieldDescriptor) -> RawDescriptor;
+    fn proto2_rust_FieldDescriptor_enum_type(f: RawFieldDescriptor) -> RawEnumDescriptor;
+
+    fn proto2_rust_OneofDescriptor_name(o: RawOneofDescriptor) -> PtrAndLen;
+    fn proto2_rust_OneofDescriptor_full_name(o: RawOneofDescriptor) -> PtrAndLen;
+    fn proto2_rust_OneofDescriptor_containing_type(o: RawOneofDescriptor) -> RawDescriptor;
+    fn proto2_rust_OneofDescriptor_field_count(o: RawOneofDescriptor) -> c_int;
+    fn proto2_rust_OneofDescriptor_field(o: RawOneofDescriptor, i: c_int) -> RawFieldDescriptor;
+
+    fn proto2_rust_EnumDescriptor_name(e: RawEnumDescriptor) -> PtrAndLen;
+    fn proto2_rust_EnumDescriptor_full_name(e: RawEnumDescriptor) -> PtrAndLen;
+    fn proto2_rust_EnumDescriptor_file(e: RawEnumDescriptor) -> RawFileDescriptor;
+    fn proto2_rust_EnumDescriptor_containing_type(e: RawEnumDescriptor) -> RawDescriptor;
+    fn proto2_rust_EnumDescriptor_is_closed(e: RawEnumDescriptor) -> bool;
+    fn proto2_rust_EnumDescriptor_value_count(e: RawEnumDescriptor) -> c_int;
+    fn proto2_rust_EnumDescriptor_value(e: RawEnumDescriptor, i: c_int) -> RawEnumValueDescriptor;
+    fn proto2_rust_EnumDescriptor_find_value_by_number(
+        e: RawEnumDescriptor,
+        number: c_int,
+    ) -> RawEnumValueDescriptor;
+
+    fn proto2_rust_EnumValueDescriptor_name(v: RawEnumValueDescriptor) -> PtrAndLen;
+    fn proto2_rust_EnumValueDescriptor_full_name(v: RawEnumValueDescriptor) -> PtrAndLen;
+    fn proto2_rust_EnumValueDescriptor_number(v: RawEnumValueDescriptor) -> c_int;
+    fn proto2_rust_EnumValueDescriptor_type(v: RawEnumValueDescriptor) -> RawEnumDescriptor;
+}
+
+/// Loads `file` and its imports, and returns its descriptor.
+fn load_file(file: &'static FileDescriptorInit) -> RawFileDescriptor {
+    for dep in file.deps {
+        load_file(dep);
+    }
+    // SAFETY: the imports of the file were loaded above.
+    let raw = unsafe {
+        proto2_rust_DescriptorPool_load_file(file.name.as_bytes().into(), file.serialized.into())
+    };
+    assert!(!raw.is_null(), "failed to load the descriptor of {}", file.name);
+    raw
+}
+
+/// Returns the descriptor of the message called `full_name`, which must be
+/// defined in `file`.
+pub fn message_descriptor(file: &'static FileDescriptorInit, full_name: &str) -> MessageDescriptor {
+    // SAFETY: `load_file` returns a valid descriptor.
+    let raw = unsafe {
+        proto2_rust_FileDescriptor_find_message(load_file(file), full_name.as_bytes().into())
+    };
+    let inner = InnerMessageDescriptor::from_raw(raw)
+        .unwrap_or_else(|| panic!("{full_name} is not defined in {}", file.name));
+    MessageDescriptor::new(Private, inner)
+}
+
+/// Returns the descriptor of the enum called `full_name`, which must be
+/// defined in `file`.
+pub fn enum_descriptor(file: &'static FileDescriptorInit, full_name: &str) -> EnumDescriptor {
+    // SAFETY: `load_file` returns a valid descriptor.
+    let raw = unsafe {
+        proto2_rust_FileDescriptor_find_enum(load_file(file), full_name.as_bytes().into())
+    };
+    let inner = InnerEnumDescriptor::from_raw(raw)
+        .unwrap_or_else(|| panic!("{full_name} is not defined in {}", file.name));
+    EnumDescriptor::new(Private, inner)
+}
+
+/// The kernel-specific part of a
+/// [`DescriptorPool`](crate::reflect::DescriptorPool): a C++ `DescriptorPool`
+/// that holds files which were built at runtime.
+#[doc(hidden)]
+#[derive(Clone, Copy)]
+pub struct InnerDescriptorPool {
+    raw: RawDescriptorPool,
+}
+
+// SAFETY: pools are only written to by `build_file`, which is called before
+// the pool is shared.
+unsafe impl Send for InnerDescriptorPool {}
+unsafe impl Sync for InnerDescriptorPool {}
+
+impl InnerDescriptorPool {
+    #[allow(clippy::new_without_default)] // Pools are only freed explicitly.
+    pub fn new() -> Self {
+        // SAFETY: always safe to call.
+        InnerDescriptorPool { raw: unsafe { proto2_rust_DescriptorPool_new() } }
+    }
+
+    /// Builds the file from its serialized `FileDescriptorProto`, returning a
+    /// description of the problem on failure.
+    ///
+    /// # Safety
+    /// - The imports of the file must already have been built.
+    /// - No other thread may be using the pool.
+    pub unsafe fn build_file(
+        self,
+        _name: &str,
+        serialized: &[u8],
+    ) -> Result<InnerFileDescriptor, String> {
+        let mut error = None;
+        // SAFETY: the pool is valid and not used by any other thread, as
+        // promised by the caller.
+        let raw = unsafe {
+            proto2_rust_DescriptorPool_build_file(self.raw, serialized.into(), &mut error)
+        };
+        InnerFileDescriptor::from_raw(raw).ok_or_else(|| {
+            let error = error.expect("a failed build sets the error");
+            // SAFETY: `error` is a new string that is owned by the caller.
+            let error = unsafe { InnerProtoString::from_raw(error) };
+            String::from_utf8_lossy(error.as_bytes()).into_owned()
+        })
+    }
+
+    /// Frees the pool.
+    ///
+    /// # Safety
+    /// - No descriptors of the pool may be used afterwards.
+    pub unsafe fn free(self) {
+        // SAFETY: the pool is valid and unused, as promised by the caller.
+        unsafe { proto2_rust_DescriptorPool_delete(self.raw) }
+    }
+
+    // SAFETY (for the lookups below): the pool is valid and no longer written
+    // to.
+    pub fn find_file(self, name: &str) -> Option<InnerFileDescriptor> {
+        InnerFileDescriptor::from_raw(unsafe {
+            proto2_rust_DescriptorPool_find_file(self.raw, name.as_bytes().into())
+        })
+    }
+    pub fn find_message(self, full_name: &str) -> Option<InnerMessageDescriptor> {
+        InnerMessageDescriptor::from_raw(unsafe {
+            proto2_rust_DescriptorPool_find_message(self.raw, full_name.as_bytes().into())
+        })
+    }
+    pub fn find_enum(self, full_name: &str) -> Option<InnerEnumDescriptor> {
+        InnerEnumDescriptor::from_raw(unsafe {
+            proto2_rust_DescriptorPool_find_enum(self.raw, full_name.as_bytes().into())
+        })
+    }
+}
+
+/// Converts a string that is owned by a descriptor. It is only `'static` if
+/// the pool is compiled in; the public descriptors, which keep runtime-built
+/// pools alive, only lend it out for as long as they are borrowed.
+///
+/// # Safety
+/// - `s` must point to a string that lives as long as its descriptor pool.
+unsafe fn descriptor_str(s: PtrAndLen) -> &'static str {
+    // SAFETY: `s` lives as long as its pool, as promised by the caller.
+    std::str::from_utf8(unsafe { s.as_ref() }).expect("descriptor names are valid UTF-8")
+}
+
+/// Defines a kernel-specific descriptor type, which is a pointer to a C++
+/// descriptor.
+macro_rules! define_inner_descriptor {
+    ($(#[$attr:meta])* $name:ident, $raw:ident) => {
+        $(#[$attr])*
+        #[doc(hidden)]
+        #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $name {
+            raw: $raw,
+        }
+
+        // SAFETY: descriptors are immutable once they are built, and the pools
+        // that own them outlive the public descriptors that hold them.
+        unsafe impl Send for $name {}
+        unsafe impl Sync for $name {}
+
+        impl $name {
+            fn from_raw(raw: $raw) -> Option<Self> {
+                (!raw.is_null()).then_some($name { raw })
+            }
+
+            pub fn raw(self) -> *const c_void {
+                self.raw.cast()
+            }
+        }
+    };
+}
+
+define_inner_descriptor!(
+    /// The kernel-specific part of a
+    /// [`FileDescriptor`](crate::reflect::FileDescriptor).
+    InnerFileDescriptor,
+    RawFileDescriptor
+);
+define_inner_descriptor!(
+    /// The kernel-specific part of a
+    /// [`MessageDescriptor`](crate::reflect::MessageDescriptor).
+    InnerMessageDescriptor,
+    RawDescriptor
+);
+define_inner_descriptor!(
+    /// The kernel-specific part of a
+    /// [`FieldDescriptor`](crate::reflect::FieldDescriptor).
+    InnerFieldDescriptor,
+    RawFieldDescriptor
+);
+define_inner_descriptor!(
+    /// The kernel-specific part of a
+    /// [`OneofDescriptor`](crate::reflect::OneofDescriptor).
+    InnerOneofDescriptor,
+    RawOneofDescriptor
+);
+define_inner_descriptor!(
+    /// The kernel-specific part of an
+    /// [`EnumDescriptor`](crate::reflect::EnumDescriptor).
+    InnerEnumDescriptor,
+    RawEnumDescriptor
+);
+define_inner_descriptor!(
+    /// The kernel-specific part of an
+    /// [`EnumValueDescriptor`](crate::reflect::EnumValueDescriptor).
+    InnerEnumValueDescriptor,
+    RawEnumValueDescriptor
+);
+
+// SAFETY (for all of the accessors below): `self.raw` is a valid descriptor
+// that lives as long as its pool, and indices are checked by the callers in
+// `reflect.rs` to be in bounds. Descriptors that are never null are unwrapped.
+impl InnerFileDescriptor {
+    pub fn name(self) -> &'static str {
+        unsafe { descriptor_str(proto2_rust_FileDescriptor_name(self.raw)) }
+    }
+    pub fn package(self) -> &'static str {
+        unsafe { descriptor_str(proto2_rust_FileDescriptor_package(self.raw)) }
+    }
+    pub fn dependency_count(self) -> usize {
+        unsafe { proto2_rust_FileDescriptor_dependency_count(self.raw) as usize }
+    }
+    pub fn dependency(self, i: usize) -> InnerFileDescriptor {
+        Self::from_raw(unsafe { proto2_rust_FileDescriptor_dependency(self.raw, i as c_int) })
+            .unwrap()
+    }
+    pub fn message_count(self) -> usize {
+        unsafe { proto2_rust_FileDescriptor_message_type_count(self.raw) as usize }
+    }
+    pub fn message(self, i: usize) -> InnerMessageDescriptor {
+        let raw = unsafe { proto2_rust_FileDescriptor_message_type(self.raw, i as c_int) };
+        InnerMessageDescriptor::from_raw(raw).unwrap()
+    }
+    pub fn enum_count(self) -> usize {
+        unsafe { proto2_rust_FileDescriptor_enum_type_count(self.raw) as usize }
+    }
+    pub fn enum_type(self, i: usize) -> InnerEnumDescriptor {
+        let raw = unsafe { proto2_rust_FileDescriptor_enum_type(self.raw, i as c_int) };
+        InnerEnumDescriptor::from_raw(raw).unwrap()
+    }
+}
+
+impl InnerMessageDescriptor {
+    pub fn name(self) -> &'static str {
+        unsafe { descriptor_str(proto2_rust_Descriptor_name(self.raw)) }
+    }
+    pub fn full_name(self) -> &'static str {
+        unsafe { descriptor_str(proto2_rust_Descriptor_full_name(self.raw)) }
+    }
+    pub fn file(self) -> InnerFileDescriptor {
+        InnerFileDescriptor::from_raw(unsafe { proto2_rust_Descriptor_file(self.raw) }).unwrap()
+    }
+    pub fn containing_type(self) -> Option<InnerMessageDescriptor> {
+        Self::from_raw(unsafe { proto2_rust_Descriptor_containing_type(self.raw) })
+    }
+    pub fn is_map_entry(self) -> bool {
+        unsafe { proto2_rust_Descriptor_is_map_entry(self.raw) }
+    }
+    pub fn field_count(self) -> usize {
+        unsafe { proto2_rust_Descriptor_field_count(self.raw) as usize }
+    }
+    pub fn field(self, i: usize) -> InnerFieldDescriptor {
+        let raw = unsafe { proto2_rust_Descriptor_field(self.raw, i as c_int) };
+        InnerFieldDescriptor::from_raw(raw).unwrap()
+    }
+    pub fn field_by_number(self, number: u32) -> Option<InnerFieldDescriptor> {
+        let number = c_int::try_from(number).ok()?;
+        let raw = unsafe { proto2_rust_Descriptor_find_field_by_number(self.raw, number) };
+        InnerFieldDescriptor::from_raw(raw)
+    }
+    pub fn field_by_name(self, name: &str) -> Option<InnerFieldDescriptor> {
+        let raw =
+            unsafe { proto2_rust_Descriptor_find_field_by_name(self.raw, name.as_bytes().into()) };
+        InnerFieldDescriptor::from_raw(raw)
+    }
+    pub fn oneof_count(self) -> usize {
+        unsafe { proto2_rust_Descriptor_real_oneof_decl_count(self.raw) as usize }
+    }
+    pub fn oneof(self, i: usize) -> InnerOneofDescriptor {
+        let raw = unsafe { proto2_rust_Descriptor_oneof_decl(self.raw, i as c_int) };
+        InnerOneofDescriptor::from_raw(raw).unwrap()
+    }
+    pub fn nested_message_count(self) -> usize {
+        unsafe { proto2_rust_Descriptor_nested_type_count(self.raw) as usize }
+    }
+    pub fn nested_message(self, i: usize) -> InnerMessageDescriptor {
+        Self::from_raw(unsafe { proto2_rust_Descriptor_nested_type(self.raw, i as c_int) }).unwrap()
+    }
+    pub fn nested_enum_count(self) -> usize {
+        unsafe { proto2_rust_Descriptor_enum_type_count(self.raw) as usize }
+    }
+    pub fn nested_enum(self, i: usize) -> InnerEnumDescriptor {
+        let raw = unsafe { proto2_rust_Descriptor_enum_type(self.raw, i as c_int) };
+        InnerEnumDescriptor::from_raw(raw).unwrap()
+    }
+}
+
+impl InnerFieldDescriptor {
+    pub fn name(self) -> &'static str {
+        unsafe { descriptor_str(proto2_rust_FieldDescriptor_name(self.raw)) }
+    }
+    pub fn full_name(self) -> &'static str {
+        unsafe { descriptor_str(proto2_rust_FieldDescriptor_full_name(self.raw)) }
+    }
+    pub fn json_name(self) -> &'static str {
+        unsafe { descriptor_str(proto2_rust_FieldDescriptor_json_name(self.raw)) }
+    }
+    pub fn number(self) -> u32 {
+        unsafe { proto2_rust_FieldDescriptor_number(self.raw) as u32 }
+    }
+    pub fn index(self) -> usize {
+        unsafe { proto2_rust_FieldDescriptor_index(self.raw) as usize }
+    }
+    pub fn field_type(self) -> i32 {
+        unsafe { proto2_rust_FieldDescriptor_type(self.raw) }
+    }
+    pub fn is_repeated(self) -> bool {
+        unsafe { proto2_rust_FieldDescriptor_is_repeated(self.raw) }
+    }
+    pub fn is_map(self) -> bool {
+        unsafe { proto2_rust_FieldDescriptor_is_map(self.raw) }
+    }
+    pub fn has_presence(self) -> bool {
+        unsafe { proto2_rust_FieldDescriptor_has_presence(self.raw) }
+    }
+    pub fn is_required(self) -> bool {
+        unsafe { proto2_rust_FieldDescriptor_is_required(self.raw) }
+    }
+    pub fn is_extension(self) -> bool {
+        unsafe { proto2_rust_FieldDescriptor_is_extension(self.raw) }
+    }
+    pub fn containing_type(self) -> InnerMessageDescriptor {
+        let raw = unsafe { proto2_rust_FieldDescriptor_containing_type(self.raw) };
+        InnerMessageDescriptor::from_raw(raw).unwrap()
+    }
+    pub fn containing_oneof(self) -> Option<InnerOneofDescriptor> {
+        InnerOneofDescriptor::from_raw(unsafe {
+            proto2_rust_FieldDescriptor_real_containing_oneof(self.raw)
+        })
+    }
+    pub fn message_type(self) -> Option<InnerMessageDescriptor> {
+        InnerMessageDescriptor::from_raw(unsafe {
+            proto2_rust_FieldDescriptor_message_type(self.raw)
+        })
+    }
+    pub fn enum_type(self) -> Option<InnerEnumDescriptor> {
+        InnerEnumDescriptor::from_raw(unsafe { proto2_rust_FieldDescriptor_enum_type(self.raw) })
+    }
+}
+
+impl InnerOneofDescriptor {
+    pub fn name(self) -> &'static str {
+        unsafe { descriptor_str(proto2_rust_OneofDescriptor_name(self.raw)) }
+    }
+    pub fn full_name(self) -> &'static str {
+        unsafe { descriptor_str(proto2_rust_OneofDescriptor_full_name(self.raw)) }
+    }
+    pub fn containing_type(self) -> InnerMessageDescriptor {
+        let raw = unsafe { proto2_rust_OneofDescriptor_containing_type(self.raw) };
+        InnerMessageDescriptor::from_raw(raw).unwrap()
+    }
+    pub fn field_count(self) -> usize {
+        unsafe { proto2_rust_OneofDescriptor_field_count(self.raw) as usize }
+    }
+    pub fn field(self, i: usize) -> InnerFieldDescriptor {
+        let raw = unsafe { proto2_rust_OneofDescriptor_field(self.raw, i as c_int) };
+        InnerFieldDescriptor::from_raw(raw).unwrap()
+    }
+}
+
+impl InnerEnumDescriptor {
+    pub fn name(self) -> &'static str {
+        unsafe { descriptor_str(proto2_rust_EnumDescriptor_name(self.raw)) }
+    }
+    pub fn full_name(self) -> &'static str {
+        unsafe { descriptor_str(proto2_rust_EnumDescriptor_full_name(self.raw)) }
+    }
+    pub fn file(self) -> InnerFileDescriptor {
+        InnerFileDescriptor::from_raw(unsafe { proto2_rust_EnumDescriptor_file(self.raw) }).unwrap()
+    }
+    pub fn containing_type(self) -> Option<InnerMessageDescriptor> {
+        InnerMessageDescriptor::from_raw(unsafe {
+            proto2_rust_EnumDescriptor_containing_type(self.raw)
+        })
+    }
+    pub fn is_closed(self) -> bool {
+        unsafe { proto2_rust_EnumDescriptor_is_closed(self.raw) }
+    }
+    pub fn value_count(self) -> usize {
+        unsafe { proto2_rust_EnumDescriptor_value_count(self.raw) as usize }
+    }
+    pub fn value(self, i: usize) -> InnerEnumValueDescriptor {
+        let raw = unsafe { proto2_rust_EnumDescriptor_value(self.raw, i as c_int) };
+        InnerEnumValueDescriptor::from_raw(raw).unwrap()
+    }
+    pub fn value_by_number(self, number: i32) -> Option<InnerEnumValueDescriptor> {
+        InnerEnumValueDescriptor::from_raw(unsafe {
+            proto2_rust_EnumDescriptor_find_value_by_number(self.raw, number)
+        })
+    }
+}
+
+impl InnerEnumValueDescriptor {
+    pub fn name(self) -> &'static str {
+        unsafe { descriptor_str(proto2_rust_EnumValueDescriptor_name(self.raw)) }
+    }
+    pub fn full_name(self) -> &'static str {
+        unsafe { descriptor_str(proto2_rust_EnumValueDescriptor_full_name(self.raw)) }
+    }
+    pub fn number(self) -> i32 {
+        unsafe { proto2_rust_EnumValueDescriptor_number(self.raw) }
+    }
+    pub fn enum_type(self) -> InnerEnumDescriptor {
+        InnerEnumDescriptor::from_raw(unsafe { proto2_rust_EnumValueDescriptor_type(self.raw) })
+            .unwrap()
+    }
+}
+
+// Unless noted otherwise, these require a non-lite message and a field of it
+// of the accessor's type and shape, and indices that are in bounds.
+extern "C" {
+    /// Returns false for lite messages, which the other accessors don't
+    /// support.
+    fn proto2_rust_Reflection_is_supported(m: RawMessage) -> bool;
+    fn proto2_rust_Reflection_has(m: RawMessage, f: RawFieldDescriptor) -> bool;
+    fn proto2_rust_Reflection_clear(m: RawMessage, f: RawFieldDescriptor);
+    fn proto2_rust_Reflection_field_size(m: RawMessage, f: RawFieldDescriptor) -> usize;
+    /// Writes up to `cap` of the extensions that are set in `m` to `out`, and
+    /// returns how many are set.
+    fn proto2_rust_Reflection_list_extensions(
+        m: RawMessage,
+        out: *mut RawFieldDescriptor,
+        cap: usize,
+    ) -> usize;
+
+    /// If the string had to be copied out of the message, `owned` is set to
+    /// the copy, which the caller must delete. Otherwise it is set to null.
+    fn proto2_rust_Reflection_get_string(
+        m: RawMessage,
+        f: RawFieldDescriptor,
+        owned: *mut Option<CppStdString>,
+    ) -> PtrAndLen;
+    fn proto2_rust_Reflection_set_string(m: RawMessage, f: RawFieldDescriptor, val: PtrAndLen);
+    /// See `proto2_rust_Reflection_get_string`.
+    fn proto2_rust_Reflection_get_repeated_string(
+        m: RawMessage,
+        f: RawFieldDescriptor,
+        i: usize,
+        owned: *mut Option<CppStdString>,
+    ) -> PtrAndLen;
+    fn proto2_rust_Reflection_set_repeated_string(
+        m: RawMessage,
+        f: RawFieldDescriptor,
+        i: usize,
+        val: PtrAndLen,
+    );
+    fn proto2_rust_Reflection_add_string(m: RawMessage, f: RawFieldDescriptor, val: PtrAndLen);
+
+    /// Returns the default instance if the field is unset.
+    fn proto2_rust_Reflection_get_message(m: RawMessage, f: RawFieldDescriptor) -> RawMessage;
+    fn proto2_rust_Reflection_mutable_message(m: RawMessage, f: RawFieldDescriptor) -> RawMessage;
+    fn proto2_rust_Reflection_get_repeated_message(
+        m: RawMessage,
+        f: RawFieldDescriptor,
+        i: usize,
+    ) -> RawMessage;
+    fn proto2_rust_Reflection_mutable_repeated_message(
+        m: RawMessage,
+        f: RawFieldDescriptor,
+        i: usize,
+    ) -> RawMessage;
+    fn proto2_rust_Reflection_add_message(m: RawMessage, f: RawFieldDescriptor) -> RawMessage;
+    fn proto2_rust_Reflection_remove_last(m: RawMessage, f: RawFieldDescriptor);
+    fn proto2_rust_Reflection_swap_elements(
+        m: RawMessage,
+        f: RawFieldDescriptor,
+        i: usize,
+        j: usize,
+    );
+
+    /// Returns null if `d` has no generated class.
+    fn proto2_rust_Reflection_new_message(d: RawDescriptor) -> Option<RawMessage>;
+}
+
+macro_rules! reflection_scalar_thunks {
+    ($($t:ident: $ty:ty),* $(,)?) => {
+        paste! {
+            extern "C" {
+                $(
+                    fn [<proto2_rust_Reflection_get_ $t>](m: RawMessage, f: RawFieldDescriptor) -> $ty;
+                    fn [<proto2_rust_Reflection_set_ $t>](m: RawMessage, f: RawFieldDescriptor, val: $ty);
+                    fn [<proto2_rust_Reflection_get_repeated_ $t>](
+                        m: RawMessage,
+                        f: RawFieldDescriptor,
+                        i: usize,
+                    ) -> $ty;
+                    fn [<proto2_rust_Reflection_set_repeated_ $t>](
+                        m: RawMessage,
+                        f: RawFieldDescriptor,
+                        i: usize,
+                        val: $ty,
+                    );
+                    fn [<proto2_rust_Reflection_add_ $t>](m: RawMessage, f: RawFieldDescriptor, val: $ty);
+                )*
+            }
+        }
+    };
+}
+
+reflection_scalar_thunks!(
+    bool: bool,
+    i32: i32,
+    i64: i64,
+    u32: u32,
+    u64: u64,
+    f32: f32,
+    f64: f64,
+    enum: c_int,
+);
+
+/// Where a value is read from or written to: a singular field, or an element
+/// of a repeated field.
+#[derive(Clone, Copy)]
+enum Slot {
+    Singular,
+    Element(usize),
+}
+
+/// Reads the value of `f` at `slot`.
+///
+/// # Safety
+/// - `f` must be a field of `msg` of the shape of `slot`, and `msg` must
+///   outlive `'msg` without being mutated.
+unsafe fn reflect_read<'msg>(
+    msg: RawMessage,
+    f: &FieldDescriptor,
+    slot: Slot,
+) -> ReflectValueRef<'msg> {
+    let raw = f.inner(Private).raw;
+    macro_rules! read_value {
+        ($t:ident) => {
+            paste! {
+                match slot {
+                    Slot::Singular => [<proto2_rust_Reflection_get_ $t>](msg, raw),
+                    Slot::Element(i) => [<proto2_rust_Reflection_get_repeated_ $t>](msg, raw, i),
+                }
+            }
+        };
+    }
+    // SAFETY: `f` is a field of `msg` of the shape of `slot`, and is read with
+    // the accessor for its type. Borrowed strings and messages are owned by
+    // `msg`.
+    unsafe {
+        match f.field_type() {
+            FieldType::Bool => ReflectValueRef::Bool(read_value!(bool)),
+            FieldType::Int32 | FieldType::SInt32 | FieldType::SFixed32 => {
+                ReflectValueRef::I32(read_value!(i32))
+            }
+            FieldType::Int64 | FieldType::SInt64 | FieldType::SFixed64 => {
+                ReflectValueRef::I64(read_value!(i64))
+            }
+            FieldType::UInt32 | FieldType::Fixed32 => ReflectValueRef::U32(read_value!(u32)),
+            FieldType::UInt64 | FieldType::Fixed64 => ReflectValueRef::U64(read_value!(u64)),
+            FieldType::Float => ReflectValueRef::F32(read_value!(f32)),
+            FieldType::Double => ReflectValueRef::F64(read_value!(f64)),
+            FieldType::Enum => ReflectValueRef::Enum(read_value!(enum)),
+            FieldType::String | FieldType::Bytes => {
+                let mut owned = None;
+                let view = match slot {
+                    Slot::Singular => proto2_rust_Reflection_get_string(msg, raw, &mut owned),
+                    Slot::Element(i) => {
+                        proto2_rust_Reflection_get_repeated_string(msg, raw, i, &mut owned)
+                    }
+                };
+                let owned = owned.map(|s| InnerProtoString::from_raw(s));
+                if f.field_type() == FieldType::String {
+                    ReflectValueRef::String(match owned {
+                        Some(s) => ProtoStringCow::Owned(ProtoString::from_inner(Private, s)),
+                        None => {
+                            ProtoStringCow::Borrowed(ProtoStr::from_utf8_unchecked(view.as_ref()))
+                        }
+                    })
+                } else {
+                    ReflectValueRef::Bytes(match owned {
+                        Some(s) => ProtoBytesCow::Owned(ProtoBytes::from_inner(Private, s)),
+                        None => ProtoBytesCow::Borrowed(view.as_ref()),
+                    })
+                }
+            }
+            FieldType::Message | FieldType::Group => {
+                let sub = match slot {
+                    Slot::Singular => proto2_rust_Reflection_get_message(msg, raw),
+                    Slot::Element(i) => proto2_rust_Reflection_get_repeated_message(msg, raw, i),
+                };
+                ReflectValueRef::Message(MessageRef::new(Private, f.message_type().unwrap(), sub))
+            }
+        }
+    }
+}
+
+/// Writes `val` to `f` at `slot`, or appends it to `f` if `slot` is `None`.
+///
+/// # Safety
+/// - `f` must be a field of `msg` of the shape of `slot`, and `val` must be of
+///   its type.
+unsafe fn reflect_write(
+    msg: RawMessage,
+    f: &FieldDescriptor,
+    slot: Option<Slot>,
+    val: &ReflectValueRef<'_>,
+) {
+    let raw = f.inner(Private).raw;
+    macro_rules! write_value {
+        ($t:ident, $val:expr) => {
+            paste! {
+                match slot {
+                    Some(Slot::Singular) => [<proto2_rust_Reflection_set_ $t>](msg, raw, $val),
+                    Some(Slot::Element(i)) => {
+                        [<proto2_rust_Reflection_set_repeated_ $t>](msg, raw, i, $val)
+                    }
+                    None => [<proto2_rust_Reflection_add_ $t>](msg, raw, $val),
+                }
+            }
+        };
+    }
+    // SAFETY: `f` is a field of `msg` of the shape of `slot`, and `val` is of
+    // its type.
+    unsafe {
+        match val {
+            ReflectValueRef::Bool(v) => write_value!(bool, *v),
+            ReflectValueRef::I32(v) => write_value!(i32, *v),
+            ReflectValueRef::I64(v) => write_value!(i64, *v),
+            ReflectValueRef::U32(v) => write_value!(u32, *v),
+            ReflectValueRef::U64(v) => write_value!(u64, *v),
+            ReflectValueRef::F32(v) => write_value!(f32, *v),
+            ReflectValueRef::F64(v) => write_value!(f64, *v),
+            ReflectValueRef::Enum(v) => write_value!(enum, *v),
+            ReflectValueRef::String(v) => write_value!(string, v.as_bytes().into()),
+            ReflectValueRef::Bytes(v) => write_value!(string, v.as_ref().into()),
+            ReflectValueRef::Message(v) => {
+                let dst = match slot {
+                    Some(Slot::Singular) => proto2_rust_Reflection_mutable_message(msg, raw),
+                    Some(Slot::Element(i)) => {
+                        proto2_rust_Reflection_mutable_repeated_message(msg, raw, i)
+                    }
+                    None => proto2_rust_Reflection_add_message(msg, raw),
+                };
+                assert!(proto2_rust_Message_copy_from(dst, v.raw(Private)));
+            }
+        }
+    }
+}
+
+/// Compares two map keys, which are always integers, bools or strings.
+fn map_key_eq(a: &ReflectValueRef<'_>, b: &ReflectValueRef<'_>) -> bool {
+    match (a, b) {
+        (ReflectValueRef::Bool(a), ReflectValueRef::Bool(b)) => a == b,
+        (ReflectValueRef::I32(a), ReflectValueRef::I32(b)) => a == b,
+        (ReflectValueRef::I64(a), ReflectValueRef::I64(b)) => a == b,
+        (ReflectValueRef::U32(a), ReflectValueRef::U32(b)) => a == b,
+        (ReflectValueRef::U64(a), ReflectValueRef::U64(b)) => a == b,
+        (ReflectValueRef::String(a), ReflectValueRef::String(b)) => a.as_bytes() == b.as_bytes(),
+        _ => false,
+    }
+}
+
+/// Returns whether the fields of `msg` can be accessed through reflection,
+/// which lite messages don't support.
+///
+/// # Safety
+/// - `msg` must be a valid message.
+pub unsafe fn reflect_is_supported(msg: RawMessage) -> bool {
+    // SAFETY: `msg` is a valid message.
+    unsafe { proto2_rust_Reflection_is_supported(msg) }
+}
+
+/// Returns whether the singular field `f` with presence is set.
+///
+/// # Safety
+/// - `f` must be a singular field with presence of the valid message `msg`.
+pub unsafe fn reflect_has(msg: RawMessage, f: &FieldDescriptor) -> bool {
+    // SAFETY: `f` is a field of `msg`.
+    unsafe { proto2_rust_Reflection_has(msg, f.inner(Private).raw) }
+}
+
+/// Returns the value of the singular field `f`, or its default if it's unset.
+///
+/// # Safety
+/// - `f` must be a singular field of `msg`, which must outlive `'msg` without
+///   being mutated.
+pub unsafe fn reflect_get<'msg>(msg: RawMessage, f: &FieldDescriptor) -> ReflectValueRef<'msg> {
+    // SAFETY: `f` is a singular field of `msg`.
+    unsafe { reflect_read(msg, f, Slot::Singular) }
+}
+
+/// Sets the singular field `f` to a copy of `val`.
+///
+/// # Safety
+/// - `f` must be a singular field of `msg`, and `val` must be of its type.
+pub unsafe fn reflect_set(
+    msg: MutatorMessageRef<'_>,
+    f: &FieldDescriptor,
+    val: &ReflectValueRef<'_>,
+) {
+    // SAFETY: `f` is a singular field of `msg`, and `val` is of its type.
+    unsafe { reflect_write(msg.msg(), f, Some(Slot::Singular), val) }
+}
+
+/// Clears the field `f`, which may be repeated or a map.
+///
+/// # Safety
+/// - `f` must be a field of `msg`.
+pub unsafe fn reflect_clear(msg: MutatorMessageRef<'_>, f: &FieldDescriptor) {
+    // SAFETY: `f` is a field of `msg`.
+    unsafe { proto2_rust_Reflection_clear(msg.msg(), f.inner(Private).raw) }
+}
+
+/// Returns the singular message field `f`, setting it to an empty message if
+/// it's unset.
+///
+/// # Safety
+/// - `f` must be a singular message field of `msg`.
+pub unsafe fn reflect_mut_message<'msg>(
+    msg: MutatorMessageRef<'msg>,
+    f: &FieldDescriptor,
+) -> MutatorMessageRef<'msg> {
+    // SAFETY: `f` is a singular message field of `msg`.
+    let sub = unsafe { proto2_rust_Reflection_mutable_message(msg.msg(), f.inner(Private).raw) };
+    MutatorMessageRef::from_parent(msg, sub)
+}
+
+/// # Safety
+/// - `f` must be a repeated field of `msg`.
+pub unsafe fn reflect_repeated_len(msg: RawMessage, f: &FieldDescriptor) -> usize {
+    // SAFETY: `f` is a repeated field of `msg`.
+    unsafe { proto2_rust_Reflection_field_size(msg, f.inner(Private).raw) }
+}
+
+/// Returns the extensions that are set in `msg`, in order of field number.
+///
+/// # Safety
+/// - `msg` must be a valid message of the type `descriptor`.
+pub unsafe fn reflect_extensions(
+    msg: RawMessage,
+    descriptor: &MessageDescriptor,
+) -> Vec<FieldDescriptor> {
+    // SAFETY: `msg` is a valid message, and `raw` has room for `len`
+    // descriptors. Nothing mutates `msg` between the two calls.
+    let raw = unsafe {
+        let len = proto2_rust_Reflection_list_extensions(msg, ptr::null_mut(), 0);
+        let mut raw = Vec::with_capacity(len);
+        proto2_rust_Reflection_list_extensions(msg, raw.as_mut_ptr(), len);
+        raw.set_len(len);
+        raw
+    };
+    raw.into_iter()
+        .map(|raw| {
+            let inner = InnerFieldDescriptor::from_raw(raw).unwrap();
+            FieldDescriptor::new_in(Private, inner, descriptor.pool(Private))
+        })
+        .collect()
+}
+
+/// # Safety
+/// - `f` must be a repeated field of `msg`, which must outlive `'msg` without
+///   being mutated.
+/// - `i` must be less than the length of the field.
+pub unsafe fn reflect_repeated_get<'msg>(
+    msg: RawMessage,
+    f: &FieldDescriptor,
+    i: usize,
+) -> ReflectValueRef<'msg> {
+    // SAFETY: `f` is a repeated field of `msg`, and `i` is in bounds.
+    unsafe { reflect_read(msg, f, Slot::Element(i)) }
+}
+
+/// # Safety
+/// - `f` must be a repeated field of `msg`, and `val` must be of its type.
+/// - `i` must be less than the length of the field.
+pub unsafe fn reflect_repeated_set(
+    msg: MutatorMessageRef<'_>,
+    f: &FieldDescriptor,
+    i: usize,
+    val: &ReflectValueRef<'_>,
+) {
+    // SAFETY: `f` is a repeated field of `msg`, `i` is in bounds and `val` is
+    // of the type of `f`.
+    unsafe { reflect_write(msg.msg(), f, Some(Slot::Element(i)), val) }
+}
+
+/// # Safety
+/// - `f` must be a repeated field of `msg`, and `val` must be of its type.
+pub unsafe fn reflect_repeated_push(
+    msg: MutatorMessageRef<'_>,
+    f: &FieldDescriptor,
+    val: &ReflectValueRef<'_>,
+) {
+    // SAFETY: `f` is a repeated field of `msg`, and `val` is of its type.
+    unsafe { reflect_write(msg.msg(), f, None, val) }
+}
+
+/// # Safety
+/// - `f` must be a repeated message field of `msg`.
+/// - `i` must be less than the length of the field.
+pub unsafe fn reflect_repeated_mut_message<'msg>(
+    msg: MutatorMessageRef<'msg>,
+    f: &FieldDescriptor,
+    i: usize,
+) -> MutatorMessageRef<'msg> {
+    // SAFETY: `f` is a repeated message field of `msg`, and `i` is in bounds.
+    let sub = unsafe {
+        proto2_rust_Reflection_mutable_repeated_message(msg.msg(), f.inner(Private).raw, i)
+    };
+    MutatorMessageRef::from_parent(msg, sub)
+}
+
+// Through `Reflection`, a map field is a repeated field of entry messages
+// whose key and value are the fields numbered 1 and 2. Lookups are linear.
+
+/// Returns the index of the entry for `key`, if there is one.
+///
+/// # Safety
+/// - `f` must be a map field of `msg`.
+unsafe fn map_find(
+    msg: RawMessage,
+    f: &FieldDescriptor,
+    key: &ReflectValueRef<'_>,
+) -> Option<usize> {
+    let key_field = f.message_type().unwrap().field_by_number(1).unwrap();
+    // SAFETY: `f` is a map field of `msg`, and every index is in bounds.
+    (0..unsafe { reflect_repeated_len(msg, f) }).find(|&i| unsafe {
+        let entry = proto2_rust_Reflection_get_repeated_message(msg, f.inner(Private).raw, i);
+        map_key_eq(&reflect_get(entry, &key_field), key)
+    })
+}
+
+/// # Safety
+/// - `f` must be a map field of `msg`.
+pub unsafe fn reflect_map_len(msg: RawMessage, f: &FieldDescriptor) -> usize {
+    // SAFETY: `f` is a map field of `msg`.
+    unsafe { reflect_repeated_len(msg, f) }
+}
+
+/// # Safety
+/// - `f` must be a map field of `msg`, which must outlive `'msg` without
+///   being mutated.
+/// - `key` must be of the key type of `f`.
+pub unsafe fn reflect_map_get<'msg>(
+    msg: RawMessage,
+    f: &FieldDescriptor,
+    key: &ReflectValueRef<'_>,
+) -> Option<ReflectValueRef<'msg>> {
+    let value_field = f.message_type().unwrap().field_by_number(2).unwrap();
+    // SAFETY: `f` is a map field of `msg`, and `i` is the index of an entry.
+    unsafe {
+        let i = map_find(msg, f, key)?;
+        let entry = proto2_rust_Reflection_get_repeated_message(msg, f.inner(Private).raw, i);
+        Some(reflect_get(entry, &value_field))
+    }
+}
+
+/// Sets the value for `key` to a copy of `val`, returning whether `key` was
+/// newly inserted.
+///
+/// # Safety
+/// - `f` must be a map field of `msg`, and `key` and `val` must be of its key
+///   and value types.
+pub unsafe fn reflect_map_insert(
+    msg: MutatorMessageRef<'_>,
+    f: &FieldDescriptor,
+    key: &ReflectValueRef<'_>,
+    val: &ReflectValueRef<'_>,
+) -> bool {
+    let entry_type = f.message_type().unwrap();
+    let (key_field, value_field) =
+        (entry_type.field_by_number(1).unwrap(), entry_type.field_by_number(2).unwrap());
+    let raw = f.inner(Private).raw;
+    // SAFETY: `f` is a map field of `msg`, `i` is the index of an entry, and
+    // `key` and `val` are of the types of the entry fields.
+    unsafe {
+        match map_find(msg.msg(), f, key) {
+            Some(i) => {
+                let entry = proto2_rust_Reflection_mutable_repeated_message(msg.msg(), raw, i);
+                reflect_write(entry, &value_field, Some(Slot::Singular), val);
+                false
+            }
+            None => {
+                let entry = proto2_rust_Reflection_add_message(msg.msg(), raw);
+                reflect_write(entry, &key_field, Some(Slot::Singular), key);
+                reflect_write(entry, &value_field, Some(Slot::Singular), val);
+                true
+            }
+        }
+    }
+}
+
+/// Removes the entry for `key`, returning whether it was present.
+///
+/// # Safety
+/// - `f` must be a map field of `msg`, and `key` must be of its key type.
+pub unsafe fn reflect_map_remove(
+    msg: MutatorMessageRef<'_>,
+    f: &FieldDescriptor,
+    key: &ReflectValueRef<'_>,
+) -> bool {
+    let raw = f.inner(Private).raw;
+    // SAFETY: `f` is a map field of `msg`, and `i` is the index of an entry so
+    // the field is not empty.
+    unsafe {
+        let Some(i) = map_find(msg.msg(), f, key) else { return false };
+        let last = reflect_repeated_len(msg.msg(), f) - 1;
+        if i != last {
+            proto2_rust_Reflection_swap_elements(msg.msg(), raw, i, last);
+        }
+        proto2_rust_Reflection_remove_last(msg.msg(), raw);
+        true
+    }
+}
+
+/// The kernel-specific part of a
+/// [`MapFieldIter`](crate::reflect::MapFieldIter).
+#[doc(hidden)]
+pub struct InnerMapFieldIter<'msg> {
+    msg: RawMessage,
+    field: FieldDescriptor,
+    key_field: FieldDescriptor,
+    value_field: FieldDescriptor,
+    index: usize,
+    len: usize,
+    _phantom: PhantomData<&'msg ()>,
+}
+
+impl<'msg> InnerMapFieldIter<'msg> {
+    /// # Safety
+    /// - `f` must be a map field of `msg`, which must outlive `'msg` without
+    ///   being mutated.
+    pub unsafe fn new(msg: RawMessage, f: &FieldDescriptor) -> Self {
+        let entry = f.message_type().unwrap();
+        InnerMapFieldIter {
+            msg,
+            field: f.clone(),
+            key_field: entry.field_by_number(1).unwrap(),
+            value_field: entry.field_by_number(2).unwrap(),
+            index: 0,
+            // SAFETY: `f` is a map field of `msg`.
+            len: unsafe { reflect_map_len(msg, f) },
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'msg> Iterator for InnerMapFieldIter<'msg> {
+    type Item = (ReflectValueRef<'msg>, ReflectValueRef<'msg>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == self.len {
+            return None;
+        }
+        // SAFETY: `self.field` is a map field of `self.msg`, which outlives
+        // `'msg` without being mutated, and `self.index` is in bounds.
+        let entry = unsafe {
+            proto2_rust_Reflection_get_repeated_message(
+                self.msg,
+                self.field.inner(Private).raw,
+                self.index,
+            )
+        };
+        self.index += 1;
+        // SAFETY: `entry` is a map entry, which lives as long as `self.msg`.
+        unsafe {
+            Some((reflect_get(entry, &self.key_field), reflect_get(entry, &self.value_field)))
+        }
+    }
+}
+
+/// The kernel-specific part of a
+/// [`DynamicMessage`](crate::reflect::DynamicMessage): a heap-allocated
+/// message.
+#[doc(hidden)]
+pub struct InnerDynamicMessage {
+    msg: RawMessage,
+}
+
+impl InnerDynamicMessage {
+    pub fn new(descriptor: &MessageDescriptor) -> Self {
+        let raw = descriptor.inner(Private).raw;
+        // SAFETY: the descriptor is valid, and belongs to the runtime-built
+        // pool if there is one.
+        let msg = unsafe {
+            match descriptor.pool(Private).inner() {
+                Some(pool) => proto2_rust_DescriptorPool_new_message(pool.raw, raw),
+                None => proto2_rust_Reflection_new_message(raw),
+            }
+        }
+        .unwrap_or_else(|| panic!("{} has no generated C++ class", descriptor.full_name()));
+        InnerDynamicMessage { msg }
+    }
+
+    /// # Safety
+    /// - `msg` must be a valid message of the type `descriptor`.
+    pub unsafe fn copy_of(descriptor: &MessageDescriptor, msg: RawMessage) -> Self {
+        let copy = Self::new(descriptor);
+        // SAFETY: both messages are of the type `descriptor`.
+        assert!(unsafe { proto2_rust_Message_copy_from(copy.msg, msg) });
+        copy
+    }
+
+    pub fn raw(&self) -> RawMessage {
+        self.msg
+    }
+
+    pub fn as_mutator_message_ref(&mut self) -> MutatorMessageRef<'_> {
+        // SAFETY: `self.msg` is owned by `self`, which is borrowed mutably for
+        // the lifetime of the returned mutator.
+        unsafe { MutatorMessageRef::wrap_raw(self.msg) }
+    }
+
+    /// Parses `data` into this message, which must be empty.
+    ///
+    /// # Safety
+    /// - `self` must be a message of the type `descriptor`.
+    pub unsafe fn parse_with_options(
+        &mut self,
+        _descriptor: &MessageDescriptor,
+        data: &[u8],
+        options: &ParseOptions<'_>,
+    ) -> Result<(), ParseError> {
+        // SAFETY: `self.msg` is a valid, mutable message.
+        unsafe { parse_with_options(self.msg, data, options) }
+    }
+
+    /// Replaces the contents of this message with those parsed from `data`.
+    ///
+    /// # Safety
+    /// - `self` must be a message of the type `descriptor`.
+    pub unsafe fn clear_and_parse(
+        &mut self,
+        _descriptor: &MessageDescriptor,
+        data: &[u8],
+    ) -> Result<(), ParseError> {
+        // SAFETY: `self.msg` is a valid, mutable message.
+        unsafe { proto2_rust_Message_parse(self.msg, data.into()) }.into_result()
+    }
+
+    /// Merges the fields that are parsed from `data` into this message.
+    ///
+    /// # Safety
+    /// - `self` must be a message of the type `descriptor`.
+    pub unsafe fn merge_parse(
+        &mut self,
+        _descriptor: &MessageDescriptor,
+        data: &[u8],
+    ) -> Result<(), ParseError> {
+        // SAFETY: `self.msg` is a valid, mutable message.
+        unsafe { proto2_rust_Message_merge_parse(self.msg, data.into()) }.into_result()
+    }
+}
+
+impl Drop for InnerDynamicMessage {
+    fn drop(&mut self) {
+        // SAFETY: `self.msg` was allocated by `new` and is not used again.
+        unsafe { proto2_rust_Message_delete(self.msg) }
+    }
+}
+
+/// # Safety
+/// - `msg` must be a valid message of the type `descriptor`.
+pub unsafe fn reflect_debug_string(
+    msg: RawMessage,
+    _descriptor: &MessageDescriptor,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    debug_string(msg, f)
+}
+
+/// Appends the serialization of `msg` to `out`.
+///
+/// # Safety
+/// - `msg` must be a valid message of the type `descriptor`.
+pub unsafe fn reflect_serialize_to_vec(
+    msg: RawMessage,
+    _descriptor: &MessageDescriptor,
+    out: &mut Vec<u8>,
+    options: &SerializeOptions,
+) -> Result<(), SerializeError> {
+    // SAFETY: `msg` is a valid message.
+    unsafe { serialize_to_vec(msg, out, options) }
+}
+
+/// Serializes `msg` into the front of `buf`, returning the number of bytes
+/// written.
+///
+/// # Safety
+/// - `msg` must be a valid message of the type `descriptor`.
+pub unsafe fn reflect_serialize_into(
+    msg: RawMessage,
+    _descriptor: &MessageDescriptor,
+    buf: &mut [u8],
+) -> Result<usize, SerializeError> {
+    // SAFETY: `msg` is a valid message.
+    unsafe { serialize_into(msg, buf) }
+}
+
+/// Clears all of the fields of `msg`.
+///
+/// # Safety
+/// - `msg` must be a message of the type `descriptor`.
+pub unsafe fn reflect_clear_message(msg: MutatorMessageRef<'_>, _descriptor: &MessageDescriptor) {
+    // SAFETY: `msg` is a valid, mutable message.
+    unsafe { proto2_rust_Message_clear(msg.msg()) }
+}
+
+/// Merges the fields of `src` into `msg`.
+///
+/// # Safety
+/// - `msg` and `src` must both be messages of the type `descriptor`.
+pub unsafe fn reflect_merge_from(
+    msg: MutatorMessageRef<'_>,
+    _descriptor: &MessageDescriptor,
+    src: RawMessage,
+) {
+    // SAFETY: both messages are of the same type.
+    assert!(unsafe { proto2_rust_Message_merge_from(msg.msg(), src) });
+}
+
+extern "C" {
+    /// Prints `msg` as JSON. Sets `out` to the JSON on success, and to a
+    /// description of the problem on failure.
+    fn proto2_rust_Message_to_json(
+        msg: RawMessage,
+        preserve_proto_field_names: bool,
+        emit_default_values: bool,
+        out: *mut Option<CppStdString>,
+    ) -> bool;
+    /// Parses `json` into the empty message `msg`. Sets `error` on failure.
+    fn proto2_rust_Message_from_json(
+        msg: RawMessage,
+        json: PtrAndLen,
+        ignore_unknown_fields: bool,
+        error: *mut Option<CppStdString>,
+    ) -> bool;
+}
+
+/// Takes ownership of a string that was allocated by a thunk.
+fn take_cpp_string(s: Option<CppStdString>) -> Vec<u8> {
+    let s = s.expect("the thunk sets the string");
+    // SAFETY: `s` is a new string that is owned by the caller.
+    let s = unsafe { InnerProtoString::from_raw(s) };
+    s.as_bytes().to_vec()
+}
+
+/// Prints `msg` as proto3 JSON, returning a description of the problem on
+/// failure.
+///
+/// # Safety
+/// - `msg` must be a valid message of the type `descriptor`.
+pub unsafe fn json_encode(
+    msg: RawMessage,
+    _descriptor: &MessageDescriptor,
+    options: &JsonPrintOptions,
+) -> Result<String, String> {
+    let mut out = None;
+    // SAFETY: `msg` is a valid message.
+    let ok = unsafe {
+        proto2_rust_Message_to_json(
+            msg,
+            options.is_preserve_proto_field_names(),
+            options.is_emit_default_values(),
+            &mut out,
+        )
+    };
+    let out = take_cpp_string(out);
+    if ok {
+        String::from_utf8(out).map_err(|_| "a string field holds invalid UTF-8".to_string())
+    } else {
+        Err(String::from_utf8_lossy(&out).into_owned())
+    }
+}
+
+/// Parses the message that is encoded as proto3 JSON in `json` into `msg`,
+/// returning a description of the problem on failure.
+///
+/// # Safety
+/// - `msg` must be an empty message of the type `descriptor`.
+pub unsafe fn json_decode(
+    msg: MutatorMessageRef<'_>,
+    _descriptor: &MessageDescriptor,
+    json: &str,
+    options: &JsonParseOptions,
+) -> Result<(), String> {
+    let mut error = None;
+    // SAFETY: `msg` is a valid, mutable message.
+    let ok = unsafe {
+        proto2_rust_Message_from_json(
+            msg.msg(),
+            json.as_bytes().into(),
+            options.is_ignore_unknown_fields(),
+            &mut error,
+        )
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&take_cpp_string(error)).into_owned())
+    }
+}
+
+extern "C" {
+    /// Prints `msg` in the text format. Sets `out` to the text on success, and
+    /// to a description of the problem on failure.
+    fn proto2_rust_Message_to_text_format(
+        msg: RawMessage,
+        single_line: bool,
+        print_unknown_fields: bool,
+        out: *mut Option<CppStdString>,
+    ) -> bool;
+    /// Parses `text` into `msg`, which must be empty unless `merge` is set.
+    /// Sets `error` on failure.
+    fn proto2_rust_Message_parse_text_format(
+        msg: RawMessage,
+        text: PtrAndLen,
+        merge: bool,
+        error: *mut Option<CppStdString>,
+    ) -> bool;
+}
+
+/// Prints `msg` in the text format.
+///
+/// # Safety
+/// - `msg` must be a valid message of the type `descriptor`.
+pub unsafe fn text_format_encode(
+    msg: RawMessage,
+    _descriptor: &MessageDescriptor,
+    options: &TextFormatPrintOptions,
+) -> String {
+    let mut out = None;
+    // SAFETY: `msg` is a valid message.
+    let ok = unsafe {
+        proto2_rust_Message_to_text_format(
+            msg,
+            options.is_single_line(),
+            options.is_print_unknown_fields(),
+            &mut out,
+        )
+    };
+    let out = String::from_utf8_lossy(&take_cpp_string(out)).into_owned();
+    assert!(ok, "{out}");
+    out
+}
+
+/// Parses the text format in `text` into `msg`, returning a description of
+/// the problem on failure. Unless `merge` is set, `msg` must be empty and
+/// singular fields may only be set once.
+pub fn text_format_decode(
+    mut msg: MessageRefMut<'_>,
+    text: &str,
+    merge: bool,
+) -> Result<(), String> {
+    let mut error = None;
+    // SAFETY: `msg` is a valid, mutable message.
+    let ok = unsafe {
+        proto2_rust_Message_parse_text_format(
+            msg.inner(Private).msg(),
+            text.as_bytes().into(),
+            merge,
+            &mut error,
+        )
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&take_cpp_string(error)).into_owned())
+    }
+}
+
+/// The raw type-erased version of an owned `Repeated`.
+#[derive(Debug)]
+#[doc(hidden)]
+pub struct InnerRepeated {
+    raw: RawRepeatedField,
+}
+
+impl InnerRepeated {
+    pub fn as_mut(&mut self) -> InnerRepeatedMut<'_> {
+        InnerRepeatedMut::new(self.raw)
+    }
+
+    pub fn raw(&self) -> RawRepeatedField {
+        self.raw
+    }
+
+    /// # Safety
+    /// - `raw` must be a valid `proto2::RepeatedField*` or
+    ///   `proto2::RepeatedPtrField*`.
+    pub unsafe fn from_raw(raw: RawRepeatedField) -> Self {
+        Self { raw }
+    }
+}
+
+/// The raw type-erased pointer version of `RepeatedMut`.
+///
+/// Contains a `proto2::RepeatedField*` or `proto2::RepeatedPtrField*`.
+#[derive(Clone, Copy, Debug)]
+#[doc(hidden)]
+pub struct InnerRepeatedMut<'msg> {
+    pub(crate) raw: RawRepeatedField,
+    _phantom: PhantomData<&'msg ()>,
+}
+
+impl<'msg> InnerRepeatedMut<'msg> {
+    #[doc(hidden)]
+    pub fn new(raw: RawRepeatedField) -> Self {
+        InnerRepeatedMut { raw, _phantom: PhantomData }
+    }
+}
+
+trait CppTypeConversions: Proxied {
+    type InsertElemType;
+    type ElemType;
+
+    fn elem_to_view<'msg>(v: Self::ElemType) -> View<'msg, Self>;
+    fn into_insertelem(v: Self) -> Self::InsertElemType;
+}
+
+macro_rules! impl_cpp_type_conversions_for_scalars {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl CppTypeConversions for $t {
+                type InsertElemType = Self;
+                type ElemType = Self;
+
+                fn elem_to_view<'msg>(v: Self) -> View<'msg, Self> {
+                    v
+                }
+
+                fn into_insertelem(v: Self) -> Self {
+                    v
+                }
+            }
+        )*
+    }
+}
+
+impl_cpp_type_conversions_for_scalars!(i32, u32, i64, u64, f32, f64, bool);
+
+impl CppTypeConversions for ProtoString {
+    type InsertElemType = CppStdString;
+    type ElemType = PtrAndLen;
+
+    fn elem_to_view<'msg>(v: PtrAndLen) -> View<'msg, ProtoString> {
+        ptrlen_to_str(v)
+    }
+
+    fn into_insertelem(v: Self) -> CppStdString {
+        v.into_inner(Private).into_raw()
+    }
+}
+
+impl CppTypeConversions for ProtoBytes {
+    type InsertElemType = CppStdString;
+    type ElemType = PtrAndLen;
+
+    fn elem_to_view<'msg>(v: Self::ElemType) -> View<'msg, Self> {
+        ptrlen_to_bytes(v)
+    }
+
+    fn into_insertelem(v: Self) -> CppStdString {
+        v.into_inner(Private).into_raw()
+    }
+}
+
+macro_rules! impl_repeated_primitives {
+    (@impl $($t:ty => [
+        $new_thunk:ident,
+        $free_thunk:ident,
+        $add_thunk:ident,
+        $size_thunk:ident,
+        $get_thunk:ident,
+        $set_thunk:ident,
+        $clear_thunk:ident,
+        $copy_from_thunk:ident,
+        $swap_thunk:ident,
+        $truncate_thunk:ident,
+        $reserve_thunk:ident $(,)?
+    ]),* $(,)?) => {
+        $(
+            extern "C" {
+                fn $new_thunk() -> RawRepeatedField;
+                fn $free_thunk(f: RawRepeatedField);
+                fn $add_thunk(f: RawRepeatedField, v: <$t as CppTypeConversions>::InsertElemType);
+                fn $size_thunk(f: RawRepeatedField) -> usize;
+                fn $get_thunk(
+                    f: RawRepeatedField,
+                    i: usize) -> <$t as CppTypeConversions>::ElemType;
+                fn $set_thunk(
+                    f: RawRepeatedField,
                     i: usize,
                     v: <$t as CppTypeConversions>::InsertElemType);
                 fn $clear_thunk(f: RawRepeatedField);
                 fn $copy_from_thunk(src: RawRepeatedField, dst: RawRepeatedField);
+                fn $swap_thunk(f: RawRepeatedField, a: usize, b: usize);
+                fn $truncate_thunk(f: RawRepeatedField, len: usize);
                 fn $reserve_thunk(
                     f: RawRepeatedField,
                     additional: usize);
@@ -562,6 +2762,14 @@ macro_rules! impl_repeated_primitives {
                     unsafe { $copy_from_thunk(src.as_raw(Private), dest.as_raw(Private)) }
                 }
                 #[inline]
+                unsafe fn repeated_swap_unchecked(mut f: Mut<Repeated<$t>>, a: usize, b: usize) {
+                    unsafe { $swap_thunk(f.as_raw(Private), a, b) }
+                }
+                #[inline]
+                fn repeated_truncate(mut f: Mut<Repeated<$t>>, len: usize) {
+                    unsafe { $truncate_thunk(f.as_raw(Private), len) }
+                }
+                #[inline]
                 fn repeated_reserve(mut f: Mut<Repeated<$t>>, additional: usize) {
                     unsafe { $reserve_thunk(f.as_raw(Private), additional) }
                 }
@@ -580,6 +2788,8 @@ macro_rules! impl_repeated_primitives {
                     [< proto2_rust_RepeatedField_ $t _set >],
                     [< proto2_rust_RepeatedField_ $t _clear >],
                     [< proto2_rust_RepeatedField_ $t _copy_from >],
+                    [< proto2_rust_RepeatedField_ $t _swap >],
+                    [< proto2_rust_RepeatedField_ $t _truncate >],
                     [< proto2_rust_RepeatedField_ $t _reserve >],
                 ],
             )*);
@@ -589,6 +2799,41 @@ macro_rules! impl_repeated_primitives {
 
 impl_repeated_primitives!(i32, u32, i64, u64, f32, f64, bool, ProtoString, ProtoBytes);
 
+macro_rules! impl_contiguous_repeated {
+    ($($t:ty),* $(,)?) => {
+        paste!{ $(
+            extern "C" {
+                fn [< proto2_rust_RepeatedField_ $t _data >](f: RawRepeatedField) -> *const $t;
+                fn [< proto2_rust_RepeatedField_ $t _mutable_data >](f: RawRepeatedField) -> *mut $t;
+                fn [< proto2_rust_RepeatedField_ $t _extend_from_slice >](
+                    f: RawRepeatedField,
+                    data: *const $t,
+                    len: usize);
+            }
+
+            unsafe impl ContiguousRepeated for $t {
+                #[inline]
+                fn repeated_data(f: View<Repeated<$t>>) -> *const $t {
+                    unsafe { [< proto2_rust_RepeatedField_ $t _data >](f.as_raw(Private)) }
+                }
+                #[inline]
+                fn repeated_data_mut(mut f: Mut<Repeated<$t>>) -> *mut $t {
+                    unsafe { [< proto2_rust_RepeatedField_ $t _mutable_data >](f.as_raw(Private)) }
+                }
+                #[inline]
+                fn repeated_extend_from_slice(mut f: Mut<Repeated<$t>>, src: &[$t]) {
+                    unsafe {
+                        [< proto2_rust_RepeatedField_ $t _extend_from_slice >](
+                            f.as_raw(Private), src.as_ptr(), src.len())
+                    }
+                }
+            }
+        )* }
+    };
+}
+
+impl_contiguous_repeated!(i32, u32, i64, u64, f32, f64, bool);
+
 extern "C" {
     pub fn proto2_rust_RepeatedField_Message_new() -> RawRepeatedField;
     pub fn proto2_rust_RepeatedField_Message_free(field: RawRepeatedField);
@@ -610,6 +2855,8 @@ extern "C" {
         dst: RawRepeatedField,
         src: RawRepeatedField,
     );
+    pub fn proto2_rust_RepeatedField_Message_swap(field: RawRepeatedField, a: usize, b: usize);
+    pub fn proto2_rust_RepeatedField_Message_truncate(field: RawRepeatedField, len: usize);
     pub fn proto2_rust_RepeatedField_Message_reserve(field: RawRepeatedField, additional: usize);
 }
 
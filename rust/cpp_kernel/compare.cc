@@ -1,11 +1,25 @@
 #include "rust/cpp_kernel/compare.h"
 
+#include <cstdint>
 #include <string>
+#include <vector>
 
+#include "absl/base/casts.h"
+#include "absl/container/flat_hash_map.h"
+#include "absl/strings/str_cat.h"
+#include "google/protobuf/descriptor.h"
 #include "google/protobuf/io/zero_copy_stream_impl_lite.h"
+#include "google/protobuf/message.h"
 #include "google/protobuf/message_lite.h"
 
-static std::string SerializeDeterministically(const google::protobuf::MessageLite& m) {
+namespace {
+
+using google::protobuf::DynamicCastMessage;
+using google::protobuf::FieldDescriptor;
+using google::protobuf::Message;
+using google::protobuf::Reflection;
+
+std::string SerializeDeterministically(const google::protobuf::MessageLite& m) {
   std::string serialized;
   {
     google::protobuf::io::StringOutputStream output_stream(&serialized);
@@ -16,11 +30,140 @@ static std::string SerializeDeterministically(const google::protobuf::MessageLit
   return serialized;
 }
 
+bool MessagesEqual(const Message& m1, const Message& m2);
+
+// Compares the value of `f` in `m1` at `i1` with the one in `m2` at `i2`, where
+// an index of -1 selects the value of a singular field. Floating point values
+// are compared by their bits, like `upb_Message_IsEqual` does.
+bool ValuesEqual(const Message& m1, int i1, const Message& m2, int i2,
+                 const FieldDescriptor* f) {
+  const Reflection* r1 = m1.GetReflection();
+  const Reflection* r2 = m2.GetReflection();
+#define PROTO2_RUST_COMPARE(Type)                                        \
+  ((i1 < 0 ? r1->Get##Type(m1, f) : r1->GetRepeated##Type(m1, f, i1)) == \
+   (i2 < 0 ? r2->Get##Type(m2, f) : r2->GetRepeated##Type(m2, f, i2)))
+  switch (f->cpp_type()) {
+    case FieldDescriptor::CPPTYPE_BOOL:
+      return PROTO2_RUST_COMPARE(Bool);
+    case FieldDescriptor::CPPTYPE_INT32:
+      return PROTO2_RUST_COMPARE(Int32);
+    case FieldDescriptor::CPPTYPE_INT64:
+      return PROTO2_RUST_COMPARE(Int64);
+    case FieldDescriptor::CPPTYPE_UINT32:
+      return PROTO2_RUST_COMPARE(UInt32);
+    case FieldDescriptor::CPPTYPE_UINT64:
+      return PROTO2_RUST_COMPARE(UInt64);
+    case FieldDescriptor::CPPTYPE_ENUM:
+      return PROTO2_RUST_COMPARE(EnumValue);
+    case FieldDescriptor::CPPTYPE_STRING:
+      return PROTO2_RUST_COMPARE(String);
+    case FieldDescriptor::CPPTYPE_FLOAT: {
+      float v1 = i1 < 0 ? r1->GetFloat(m1, f) : r1->GetRepeatedFloat(m1, f, i1);
+      float v2 = i2 < 0 ? r2->GetFloat(m2, f) : r2->GetRepeatedFloat(m2, f, i2);
+      return absl::bit_cast<uint32_t>(v1) == absl::bit_cast<uint32_t>(v2);
+    }
+    case FieldDescriptor::CPPTYPE_DOUBLE: {
+      double v1 =
+          i1 < 0 ? r1->GetDouble(m1, f) : r1->GetRepeatedDouble(m1, f, i1);
+      double v2 =
+          i2 < 0 ? r2->GetDouble(m2, f) : r2->GetRepeatedDouble(m2, f, i2);
+      return absl::bit_cast<uint64_t>(v1) == absl::bit_cast<uint64_t>(v2);
+    }
+    case FieldDescriptor::CPPTYPE_MESSAGE:
+      return MessagesEqual(
+          i1 < 0 ? r1->GetMessage(m1, f) : r1->GetRepeatedMessage(m1, f, i1),
+          i2 < 0 ? r2->GetMessage(m2, f) : r2->GetRepeatedMessage(m2, f, i2));
+  }
+#undef PROTO2_RUST_COMPARE
+  return false;
+}
+
+// Returns the key of a map entry as a string that identifies it among the
+// keys of the same map field.
+std::string MapKey(const Message& entry, const FieldDescriptor* key) {
+  const Reflection* r = entry.GetReflection();
+  switch (key->cpp_type()) {
+    case FieldDescriptor::CPPTYPE_BOOL:
+      return r->GetBool(entry, key) ? "1" : "0";
+    case FieldDescriptor::CPPTYPE_INT32:
+      return absl::StrCat(r->GetInt32(entry, key));
+    case FieldDescriptor::CPPTYPE_INT64:
+      return absl::StrCat(r->GetInt64(entry, key));
+    case FieldDescriptor::CPPTYPE_UINT32:
+      return absl::StrCat(r->GetUInt32(entry, key));
+    case FieldDescriptor::CPPTYPE_UINT64:
+      return absl::StrCat(r->GetUInt64(entry, key));
+    default:
+      return r->GetString(entry, key);
+  }
+}
+
+// Compares the map field `f` without regard to the order of its entries. If
+// a key appears more than once, its last entry wins, as when parsing.
+bool MapsEqual(const Message& m1, const Message& m2, const FieldDescriptor* f) {
+  const FieldDescriptor* key = f->message_type()->map_key();
+  const FieldDescriptor* value = f->message_type()->map_value();
+  auto entries = [&](const Message& m) {
+    absl::flat_hash_map<std::string, const Message*> out;
+    const Reflection* r = m.GetReflection();
+    for (int i = 0; i < r->FieldSize(m, f); ++i) {
+      const Message& entry = r->GetRepeatedMessage(m, f, i);
+      out[MapKey(entry, key)] = &entry;
+    }
+    return out;
+  };
+  auto entries1 = entries(m1);
+  auto entries2 = entries(m2);
+  if (entries1.size() != entries2.size()) return false;
+  for (const auto& [k, entry1] : entries1) {
+    auto it = entries2.find(k);
+    if (it == entries2.end() ||
+        !ValuesEqual(*entry1, -1, *it->second, -1, value)) {
+      return false;
+    }
+  }
+  return true;
+}
+
+// Compares the fields and extensions that are set in `m1` and `m2`, which are
+// of the same type. Unknown fields are ignored.
+bool MessagesEqual(const Message& m1, const Message& m2) {
+  if (&m1 == &m2) return true;
+  std::vector<const FieldDescriptor*> fields1, fields2;
+  m1.GetReflection()->ListFields(m1, &fields1);
+  m2.GetReflection()->ListFields(m2, &fields2);
+  if (fields1 != fields2) return false;
+  for (const FieldDescriptor* f : fields1) {
+    if (f->is_map()) {
+      if (!MapsEqual(m1, m2, f)) return false;
+    } else if (f->is_repeated()) {
+      int size = m1.GetReflection()->FieldSize(m1, f);
+      if (size != m2.GetReflection()->FieldSize(m2, f)) return false;
+      for (int i = 0; i < size; ++i) {
+        if (!ValuesEqual(m1, i, m2, i, f)) return false;
+      }
+    } else if (!ValuesEqual(m1, -1, m2, -1, f)) {
+      return false;
+    }
+  }
+  return true;
+}
+
+}  // namespace
+
 extern "C" {
 
 bool proto2_rust_messagelite_equals(const google::protobuf::MessageLite* msg1,
                                     const google::protobuf::MessageLite* msg2) {
-  return SerializeDeterministically(*msg1) == SerializeDeterministically(*msg2);
+  const Message* m1 = DynamicCastMessage<Message>(msg1);
+  const Message* m2 = DynamicCastMessage<Message>(msg2);
+  if (m1 == nullptr || m2 == nullptr) {
+    // Lite messages have no reflection, so the best we can do is to compare
+    // their encodings.
+    return SerializeDeterministically(*msg1) ==
+           SerializeDeterministically(*msg2);
+  }
+  return MessagesEqual(*m1, *m2);
 }
 
 }  // extern "C"
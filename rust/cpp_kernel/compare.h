@@ -5,6 +5,10 @@
 
 extern "C" {
 
+// Returns whether two messages of the same type are equal: the same fields and
+// extensions are set with equal values, where map fields are compared without
+// regard to order and floating point values by their bits. Unknown fields are
+// ignored. This matches `upb_Message_IsEqual` with no options set.
 bool proto2_rust_messagelite_equals(const google::protobuf::MessageLite* msg1,
                                     const google::protobuf::MessageLite* msg2);
 
@@ -0,0 +1,355 @@
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <utility>
+
+#include "absl/base/thread_annotations.h"
+#include "absl/strings/str_cat.h"
+#include "absl/strings/string_view.h"
+#include "absl/synchronization/mutex.h"
+#include "google/protobuf/descriptor.h"
+#include "google/protobuf/descriptor.pb.h"
+#include "google/protobuf/dynamic_message.h"
+#include "google/protobuf/message.h"
+#include "google/protobuf/message_lite.h"
+#include "rust/cpp_kernel/strings.h"
+
+namespace {
+
+using google::protobuf::Descriptor;
+using google::protobuf::DescriptorPool;
+using google::protobuf::DynamicMessageFactory;
+using google::protobuf::EnumDescriptor;
+using google::protobuf::EnumValueDescriptor;
+using google::protobuf::FieldDescriptor;
+using google::protobuf::FileDescriptor;
+using google::protobuf::FileDescriptorProto;
+using google::protobuf::Message;
+using google::protobuf::MessageLite;
+using google::protobuf::OneofDescriptor;
+using google::protobuf::rust::PtrAndLen;
+
+// Files compiled with `optimize_for = LITE_RUNTIME` are not part of the
+// generated pool, so their descriptors are built into this pool from the
+// descriptors that are embedded in the Rust gencode. Lite files can only
+// import other lite files, so it doesn't need the generated pool as underlay.
+//
+// DescriptorPool is only thread-safe for const methods, so every access to
+// this pool must hold `lite_pool_mutex`.
+absl::Mutex lite_pool_mutex(absl::kConstInit);
+
+DescriptorPool& LitePool() ABSL_EXCLUSIVE_LOCKS_REQUIRED(lite_pool_mutex) {
+  static DescriptorPool* pool = new DescriptorPool();
+  return *pool;
+}
+
+template <typename T>
+PtrAndLen ToPtrAndLen(const T& s) {
+  return PtrAndLen{s.data(), s.size()};
+}
+
+// Keeps the first error that is reported while building a file.
+class FirstErrorCollector : public DescriptorPool::ErrorCollector {
+ public:
+  void RecordError(absl::string_view filename, absl::string_view element_name,
+                   const Message* descriptor, ErrorLocation location,
+                   absl::string_view message) override {
+    if (error_.empty()) error_ = absl::StrCat(element_name, ": ", message);
+  }
+
+  std::string& error() { return error_; }
+
+ private:
+  std::string error_;
+};
+
+// A pool that is built from a `FileDescriptorSet` at runtime, with the
+// factory of the messages of its types. Messages refer to the factory that
+// created them, so the Rust side only deletes the pool once they are gone.
+struct RuntimePool {
+  DescriptorPool pool;
+  DynamicMessageFactory factory{&pool};
+};
+
+}  // namespace
+
+extern "C" {
+
+// Returns the descriptor of the file called `name`, building it from
+// `serialized` if the file is not part of the generated pool. Returns nullptr
+// if the file is not in the generated pool and `serialized` is empty.
+//
+// The imports of the file must already have been loaded.
+const FileDescriptor* proto2_rust_DescriptorPool_load_file(
+    PtrAndLen name, PtrAndLen serialized) {
+  const FileDescriptor* file =
+      DescriptorPool::generated_pool()->FindFileByName(name.AsStringView());
+  if (file != nullptr) return file;
+
+  absl::MutexLock lock(&lite_pool_mutex);
+  file = LitePool().FindFileByName(name.AsStringView());
+  if (file != nullptr || serialized.len == 0) return file;
+
+  FileDescriptorProto proto;
+  if (!proto.ParseFromString(serialized.AsStringView())) return nullptr;
+  return LitePool().BuildFile(proto);
+}
+
+const Descriptor* proto2_rust_FileDescriptor_find_message(
+    const FileDescriptor* file, PtrAndLen full_name) {
+  if (file->pool() == DescriptorPool::generated_pool()) {
+    return file->pool()->FindMessageTypeByName(full_name.AsStringView());
+  }
+  absl::MutexLock lock(&lite_pool_mutex);
+  return file->pool()->FindMessageTypeByName(full_name.AsStringView());
+}
+
+const EnumDescriptor* proto2_rust_FileDescriptor_find_enum(
+    const FileDescriptor* file, PtrAndLen full_name) {
+  if (file->pool() == DescriptorPool::generated_pool()) {
+    return file->pool()->FindEnumTypeByName(full_name.AsStringView());
+  }
+  absl::MutexLock lock(&lite_pool_mutex);
+  return file->pool()->FindEnumTypeByName(full_name.AsStringView());
+}
+
+// Pools that are built from a `FileDescriptorSet` at runtime. They are only
+// written to while they are being built, before any of their descriptors are
+// handed out, so unlike the lite pool they don't need a lock.
+RuntimePool* proto2_rust_DescriptorPool_new() { return new RuntimePool(); }
+
+void proto2_rust_DescriptorPool_delete(RuntimePool* pool) { delete pool; }
+
+// Builds the file from its serialized `FileDescriptorProto`. The imports of
+// the file must already have been built. On failure, returns nullptr and sets
+// `*error` to a new string that describes the problem.
+const FileDescriptor* proto2_rust_DescriptorPool_build_file(
+    RuntimePool* pool, PtrAndLen serialized, std::string** error) {
+  FileDescriptorProto proto;
+  if (!proto.ParseFromString(serialized.AsStringView())) {
+    *error = new std::string("the FileDescriptorProto is malformed");
+    return nullptr;
+  }
+  FirstErrorCollector collector;
+  const FileDescriptor* file =
+      pool->pool.BuildFileCollectingErrors(proto, &collector);
+  if (file == nullptr) *error = new std::string(std::move(collector.error()));
+  return file;
+}
+
+const FileDescriptor* proto2_rust_DescriptorPool_find_file(
+    const RuntimePool* pool, PtrAndLen name) {
+  return pool->pool.FindFileByName(name.AsStringView());
+}
+
+const Descriptor* proto2_rust_DescriptorPool_find_message(
+    const RuntimePool* pool, PtrAndLen full_name) {
+  return pool->pool.FindMessageTypeByName(full_name.AsStringView());
+}
+
+const EnumDescriptor* proto2_rust_DescriptorPool_find_enum(
+    const RuntimePool* pool, PtrAndLen full_name) {
+  return pool->pool.FindEnumTypeByName(full_name.AsStringView());
+}
+
+// Returns a new, heap-allocated message of the type `d`, which must belong to
+// `pool`.
+MessageLite* proto2_rust_DescriptorPool_new_message(RuntimePool* pool,
+                                                    const Descriptor* d) {
+  const Message* prototype = pool->factory.GetPrototype(d);
+  if (prototype == nullptr) return nullptr;
+  return prototype->New();
+}
+
+PtrAndLen proto2_rust_FileDescriptor_name(const FileDescriptor* f) {
+  return ToPtrAndLen(f->name());
+}
+PtrAndLen proto2_rust_FileDescriptor_package(const FileDescriptor* f) {
+  return ToPtrAndLen(f->package());
+}
+int proto2_rust_FileDescriptor_dependency_count(const FileDescriptor* f) {
+  return f->dependency_count();
+}
+const FileDescriptor* proto2_rust_FileDescriptor_dependency(
+    const FileDescriptor* f, int i) {
+  return f->dependency(i);
+}
+int proto2_rust_FileDescriptor_message_type_count(const FileDescriptor* f) {
+  return f->message_type_count();
+}
+const Descriptor* proto2_rust_FileDescriptor_message_type(
+    const FileDescriptor* f, int i) {
+  return f->message_type(i);
+}
+int proto2_rust_FileDescriptor_enum_type_count(const FileDescriptor* f) {
+  return f->enum_type_count();
+}
+const EnumDescriptor* proto2_rust_FileDescriptor_enum_type(
+    const FileDescriptor* f, int i) {
+  return f->enum_type(i);
+}
+
+PtrAndLen proto2_rust_Descriptor_name(const Descriptor* d) {
+  return ToPtrAndLen(d->name());
+}
+PtrAndLen proto2_rust_Descriptor_full_name(const Descriptor* d) {
+  return ToPtrAndLen(d->full_name());
+}
+const FileDescriptor* proto2_rust_Descriptor_file(const Descriptor* d) {
+  return d->file();
+}
+const Descriptor* proto2_rust_Descriptor_containing_type(const Descriptor* d) {
+  return d->containing_type();
+}
+bool proto2_rust_Descriptor_is_map_entry(const Descriptor* d) {
+  return d->options().map_entry();
+}
+int proto2_rust_Descriptor_field_count(const Descriptor* d) {
+  return d->field_count();
+}
+const FieldDescriptor* proto2_rust_Descriptor_field(const Descriptor* d,
+                                                     int i) {
+  return d->field(i);
+}
+const FieldDescriptor* proto2_rust_Descriptor_find_field_by_number(
+    const Descriptor* d, int number) {
+  return d->FindFieldByNumber(number);
+}
+const FieldDescriptor* proto2_rust_Descriptor_find_field_by_name(
+    const Descriptor* d, PtrAndLen name) {
+  return d->FindFieldByName(name.AsStringView());
+}
+int proto2_rust_Descriptor_real_oneof_decl_count(const Descriptor* d) {
+  return d->real_oneof_decl_count();
+}
+const OneofDescriptor* proto2_rust_Descriptor_oneof_decl(const Descriptor* d,
+                                                         int i) {
+  return d->oneof_decl(i);
+}
+int proto2_rust_Descriptor_nested_type_count(const Descriptor* d) {
+  return d->nested_type_count();
+}
+const Descriptor* proto2_rust_Descriptor_nested_type(const Descriptor* d,
+                                                     int i) {
+  return d->nested_type(i);
+}
+int proto2_rust_Descriptor_enum_type_count(const Descriptor* d) {
+  return d->enum_type_count();
+}
+const EnumDescriptor* proto2_rust_Descriptor_enum_type(const Descriptor* d,
+                                                       int i) {
+  return d->enum_type(i);
+}
+
+PtrAndLen proto2_rust_FieldDescriptor_name(const FieldDescriptor* f) {
+  return ToPtrAndLen(f->name());
+}
+PtrAndLen proto2_rust_FieldDescriptor_full_name(const FieldDescriptor* f) {
+  return ToPtrAndLen(f->full_name());
+}
+PtrAndLen proto2_rust_FieldDescriptor_json_name(const FieldDescriptor* f) {
+  return ToPtrAndLen(f->json_name());
+}
+int proto2_rust_FieldDescriptor_number(const FieldDescriptor* f) {
+  return f->number();
+}
+int proto2_rust_FieldDescriptor_index(const FieldDescriptor* f) {
+  return f->index();
+}
+int proto2_rust_FieldDescriptor_type(const FieldDescriptor* f) {
+  return f->type();
+}
+bool proto2_rust_FieldDescriptor_is_repeated(const FieldDescriptor* f) {
+  return f->is_repeated();
+}
+bool proto2_rust_FieldDescriptor_is_map(const FieldDescriptor* f) {
+  return f->is_map();
+}
+bool proto2_rust_FieldDescriptor_has_presence(const FieldDescriptor* f) {
+  return f->has_presence();
+}
+bool proto2_rust_FieldDescriptor_is_required(const FieldDescriptor* f) {
+  return f->is_required();
+}
+bool proto2_rust_FieldDescriptor_is_extension(const FieldDescriptor* f) {
+  return f->is_extension();
+}
+const Descriptor* proto2_rust_FieldDescriptor_containing_type(
+    const FieldDescriptor* f) {
+  return f->containing_type();
+}
+const OneofDescriptor* proto2_rust_FieldDescriptor_real_containing_oneof(
+    const FieldDescriptor* f) {
+  return f->real_containing_oneof();
+}
+const Descriptor* proto2_rust_FieldDescriptor_message_type(
+    const FieldDescriptor* f) {
+  return f->message_type();
+}
+const EnumDescriptor* proto2_rust_FieldDescriptor_enum_type(
+    const FieldDescriptor* f) {
+  return f->enum_type();
+}
+
+PtrAndLen proto2_rust_OneofDescriptor_name(const OneofDescriptor* o) {
+  return ToPtrAndLen(o->name());
+}
+PtrAndLen proto2_rust_OneofDescriptor_full_name(const OneofDescriptor* o) {
+  return ToPtrAndLen(o->full_name());
+}
+const Descriptor* proto2_rust_OneofDescriptor_containing_type(
+    const OneofDescriptor* o) {
+  return o->containing_type();
+}
+int proto2_rust_OneofDescriptor_field_count(const OneofDescriptor* o) {
+  return o->field_count();
+}
+const FieldDescriptor* proto2_rust_OneofDescriptor_field(
+    const OneofDescriptor* o, int i) {
+  return o->field(i);
+}
+
+PtrAndLen proto2_rust_EnumDescriptor_name(const EnumDescriptor* e) {
+  return ToPtrAndLen(e->name());
+}
+PtrAndLen proto2_rust_EnumDescriptor_full_name(const EnumDescriptor* e) {
+  return ToPtrAndLen(e->full_name());
+}
+const FileDescriptor* proto2_rust_EnumDescriptor_file(const EnumDescriptor* e) {
+  return e->file();
+}
+const Descriptor* proto2_rust_EnumDescriptor_containing_type(
+    const EnumDescriptor* e) {
+  return e->containing_type();
+}
+bool proto2_rust_EnumDescriptor_is_closed(const EnumDescriptor* e) {
+  return e->is_closed();
+}
+int proto2_rust_EnumDescriptor_value_count(const EnumDescriptor* e) {
+  return e->value_count();
+}
+const EnumValueDescriptor* proto2_rust_EnumDescriptor_value(
+    const EnumDescriptor* e, int i) {
+  return e->value(i);
+}
+const EnumValueDescriptor* proto2_rust_EnumDescriptor_find_value_by_number(
+    const EnumDescriptor* e, int number) {
+  return e->FindValueByNumber(number);
+}
+
+PtrAndLen proto2_rust_EnumValueDescriptor_name(const EnumValueDescriptor* v) {
+  return ToPtrAndLen(v->name());
+}
+PtrAndLen proto2_rust_EnumValueDescriptor_full_name(
+    const EnumValueDescriptor* v) {
+  return ToPtrAndLen(v->full_name());
+}
+int proto2_rust_EnumValueDescriptor_number(const EnumValueDescriptor* v) {
+  return v->number();
+}
+const EnumDescriptor* proto2_rust_EnumValueDescriptor_type(
+    const EnumValueDescriptor* v) {
+  return v->type();
+}
+
+}  // extern "C"
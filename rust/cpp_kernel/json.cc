@@ -0,0 +1,67 @@
+#include <string>
+#include <utility>
+
+#include "absl/status/status.h"
+#include "absl/strings/string_view.h"
+#include "google/protobuf/json/json.h"
+#include "google/protobuf/message.h"
+#include "google/protobuf/message_lite.h"
+#include "rust/cpp_kernel/strings.h"
+
+namespace {
+
+using google::protobuf::DynamicCastMessage;
+using google::protobuf::Message;
+using google::protobuf::MessageLite;
+using google::protobuf::json::JsonStringToMessage;
+using google::protobuf::json::MessageToJsonString;
+using google::protobuf::json::ParseOptions;
+using google::protobuf::json::PrintOptions;
+using google::protobuf::rust::PtrAndLen;
+
+constexpr absl::string_view kLiteError =
+    "JSON is not supported for lite messages";
+
+}  // namespace
+
+extern "C" {
+
+// Prints `m` as JSON. Returns whether it succeeded, and sets `*out` to a new
+// string that holds either the JSON or a description of the problem.
+bool proto2_rust_Message_to_json(const MessageLite* m,
+                                 bool preserve_proto_field_names,
+                                 bool emit_default_values, std::string** out) {
+  const Message* msg = DynamicCastMessage<Message>(m);
+  if (msg == nullptr) {
+    *out = new std::string(kLiteError);
+    return false;
+  }
+  PrintOptions options;
+  options.preserve_proto_field_names = preserve_proto_field_names;
+  options.always_print_fields_with_no_presence = emit_default_values;
+  std::string json;
+  absl::Status status = MessageToJsonString(*msg, &json, options);
+  *out = new std::string(status.ok() ? std::move(json)
+                                     : std::string(status.message()));
+  return status.ok();
+}
+
+// Parses the JSON in `json` into `m`, which must be empty. Returns whether it
+// succeeded, and sets `*error` to a new string that describes the problem if
+// it didn't.
+bool proto2_rust_Message_from_json(MessageLite* m, PtrAndLen json,
+                                   bool ignore_unknown_fields,
+                                   std::string** error) {
+  Message* msg = DynamicCastMessage<Message>(m);
+  if (msg == nullptr) {
+    *error = new std::string(kLiteError);
+    return false;
+  }
+  ParseOptions options;
+  options.ignore_unknown_fields = ignore_unknown_fields;
+  absl::Status status = JsonStringToMessage(json.AsStringView(), msg, options);
+  if (!status.ok()) *error = new std::string(status.message());
+  return status.ok();
+}
+
+}  // extern "C"
@@ -1,7 +1,479 @@
+#include <algorithm>
+#include <climits>
+#include <cstddef>
+#include <cstdint>
+#include <functional>
 #include <limits>
+#include <string>
+#include <vector>
 
+#include "absl/log/absl_check.h"
+#include "absl/log/absl_log.h"
+#include "absl/strings/str_cat.h"
+#include "absl/strings/str_join.h"
+#include "absl/strings/string_view.h"
+#include "absl/types/span.h"
+#include "google/protobuf/descriptor.h"
+#include "google/protobuf/io/coded_stream.h"
+#include "google/protobuf/io/zero_copy_stream_impl_lite.h"
+#include "google/protobuf/message.h"
 #include "google/protobuf/message_lite.h"
+#include "google/protobuf/wire_format.h"
+#include "google/protobuf/wire_format_lite.h"
 #include "rust/cpp_kernel/serialized_data.h"
+#include "rust/cpp_kernel/strings.h"
+#include "utf8_validity.h"
+
+namespace {
+
+using google::protobuf::Descriptor;
+using google::protobuf::FieldDescriptor;
+using google::protobuf::internal::WireFormatLite;
+
+// LINT.IfChange(parse_status)
+enum class ParseStatus {
+  kOk = 0,
+  kMalformed = 1,
+  kOutOfMemory = 2,
+  kBadUtf8 = 3,
+  kMaxDepthExceeded = 4,
+  kMissingRequired = 5,
+};
+
+struct ParseResult {
+  ParseStatus status;
+  // `offset` is only meaningful if `has_offset` is true.
+  bool has_offset;
+  size_t offset;
+  // Empty if the path is unknown.
+  google::protobuf::rust::RustStringRawParts field_path;
+};
+
+struct RawParseOptions {
+  // Negative to use the default recursion limit.
+  int32_t recursion_limit;
+  bool check_required;
+  bool validate_utf8;
+  // The sorted descriptors of the registered extensions, or null to parse
+  // every extension that is linked into the binary.
+  const FieldDescriptor* const* extension_registry;
+  size_t extension_registry_len;
+};
+// LINT.ThenChange(//depot/google3/third_party/protobuf/rust/cpp.rs:parse_status)
+
+constexpr RawParseOptions kDefaultParseOptions = {
+    /*recursion_limit=*/-1,        /*check_required=*/true,
+    /*validate_utf8=*/false,       /*extension_registry=*/nullptr,
+    /*extension_registry_len=*/0};
+
+int RecursionLimit(const RawParseOptions& options) {
+  return options.recursion_limit < 0
+             ? google::protobuf::io::CodedInputStream::GetDefaultRecursionLimit()
+             : options.recursion_limit;
+}
+
+// LINT.IfChange(serialize_status)
+enum class SerializeStatus {
+  kOk = 0,
+  kOutOfMemory = 1,
+  kMaxDepthExceeded = 2,
+  kMissingRequired = 3,
+  kTooLarge = 4,
+  kBufferTooSmall = 5,
+};
+
+struct SerializeResult {
+  SerializeStatus status;
+  // NUL-separated paths of the unset required fields. Map keys in the paths
+  // escape NUL, so it cannot occur inside a path. Empty unless `status` is
+  // kMissingRequired.
+  google::protobuf::rust::RustStringRawParts missing_required_fields;
+};
+// LINT.ThenChange(//depot/google3/third_party/protobuf/rust/cpp.rs:serialize_status)
+
+ParseResult MakeParseResult(ParseStatus status, std::string field_path = "") {
+  return ParseResult{status, false, 0,
+                     google::protobuf::rust::RustStringRawParts(std::move(field_path))};
+}
+
+// Re-walks wire format data that failed to parse in order to find out where
+// and why it failed. The C++ parser only reports success or failure, so this
+// mirrors the checks that it performs which can be attributed to a field.
+class ParseFailureLocator {
+ public:
+  // If `validate_all_utf8` is true, every string field is checked for valid
+  // UTF-8, not only the ones that require it.
+  ParseFailureLocator(absl::string_view input, int recursion_limit,
+                      bool validate_all_utf8)
+      : input_(reinterpret_cast<const uint8_t*>(input.data()),
+               static_cast<int>(input.size())),
+        recursion_limit_(recursion_limit),
+        validate_all_utf8_(validate_all_utf8) {}
+
+  // Returns the status describing the first failure found in the input, or
+  // ParseStatus::kOk if the input has no failure that the locator can detect.
+  ParseStatus Locate(const Descriptor* descriptor) {
+    input_.SetRecursionLimit(std::numeric_limits<int>::max());
+    return WalkMessage(descriptor, /*depth=*/0, /*end_group_tag=*/0);
+  }
+
+  // The offset of the tag of the field where the failure was detected.
+  size_t offset() const { return offset_; }
+
+  std::string field_path() const { return absl::StrJoin(path_, "."); }
+
+ private:
+  ParseStatus Fail(ParseStatus status, int offset) {
+    offset_ = static_cast<size_t>(offset);
+    return status;
+  }
+
+  // Walks fields until the end of the current limit, or until the end group
+  // tag if `end_group_tag` is non-zero. `descriptor` may be null for unknown
+  // groups, in which case only the wire structure is checked.
+  ParseStatus WalkMessage(const Descriptor* descriptor, int depth,
+                          uint32_t end_group_tag) {
+    if (depth > recursion_limit_) {
+      return Fail(ParseStatus::kMaxDepthExceeded, input_.CurrentPosition());
+    }
+    while (true) {
+      int field_start = input_.CurrentPosition();
+      uint32_t tag = input_.ReadTag();
+      if (tag == 0) {
+        // End of input (or of the current limit) is only legal outside of a
+        // group. A literal zero tag is always malformed.
+        if (end_group_tag == 0 && input_.ConsumedEntireMessage()) {
+          return ParseStatus::kOk;
+        }
+        return Fail(ParseStatus::kMalformed, field_start);
+      }
+      if (tag == end_group_tag) return ParseStatus::kOk;
+
+      const FieldDescriptor* field =
+          descriptor == nullptr
+              ? nullptr
+              : descriptor->FindFieldByNumber(
+                    WireFormatLite::GetTagFieldNumber(tag));
+      if (field != nullptr) path_.emplace_back(field->name());
+      ParseStatus status = WalkField(field, tag, field_start, depth);
+      if (status != ParseStatus::kOk) return status;
+      if (field != nullptr) path_.pop_back();
+    }
+  }
+
+  ParseStatus WalkField(const FieldDescriptor* field, uint32_t tag,
+                        int field_start, int depth) {
+    switch (WireFormatLite::GetTagWireType(tag)) {
+      case WireFormatLite::WIRETYPE_VARINT: {
+        uint64_t value;
+        if (!input_.ReadVarint64(&value)) {
+          return Fail(ParseStatus::kMalformed, field_start);
+        }
+        return ParseStatus::kOk;
+      }
+      case WireFormatLite::WIRETYPE_FIXED64: {
+        uint64_t value;
+        if (!input_.ReadLittleEndian64(&value)) {
+          return Fail(ParseStatus::kMalformed, field_start);
+        }
+        return ParseStatus::kOk;
+      }
+      case WireFormatLite::WIRETYPE_FIXED32: {
+        uint32_t value;
+        if (!input_.ReadLittleEndian32(&value)) {
+          return Fail(ParseStatus::kMalformed, field_start);
+        }
+        return ParseStatus::kOk;
+      }
+      case WireFormatLite::WIRETYPE_LENGTH_DELIMITED:
+        return WalkLengthDelimited(field, field_start, depth);
+      case WireFormatLite::WIRETYPE_START_GROUP: {
+        const Descriptor* group_descriptor =
+            field != nullptr && field->type() == FieldDescriptor::TYPE_GROUP
+                ? field->message_type()
+                : nullptr;
+        uint32_t end_tag = WireFormatLite::MakeTag(
+            WireFormatLite::GetTagFieldNumber(tag),
+            WireFormatLite::WIRETYPE_END_GROUP);
+        return WalkMessage(group_descriptor, depth + 1, end_tag);
+      }
+      default:
+        // Unexpected END_GROUP tags and the two unused wire types.
+        return Fail(ParseStatus::kMalformed, field_start);
+    }
+  }
+
+  ParseStatus WalkLengthDelimited(const FieldDescriptor* field,
+                                  int field_start, int depth) {
+    uint32_t length;
+    if (!input_.ReadVarint32(&length) ||
+        length > static_cast<uint32_t>(std::numeric_limits<int>::max())) {
+      return Fail(ParseStatus::kMalformed, field_start);
+    }
+    int bytes_left = input_.BytesUntilLimit();
+    if (bytes_left >= 0 && static_cast<int>(length) > bytes_left) {
+      return Fail(ParseStatus::kMalformed, field_start);
+    }
+
+    if (field != nullptr && field->type() == FieldDescriptor::TYPE_MESSAGE) {
+      auto limit = input_.PushLimit(static_cast<int>(length));
+      ParseStatus status = WalkMessage(field->message_type(), depth + 1, 0);
+      input_.PopLimit(limit);
+      return status;
+    }
+
+    if (field != nullptr && field->type() == FieldDescriptor::TYPE_STRING &&
+        (validate_all_utf8_ || field->requires_utf8_validation())) {
+      std::string value;
+      if (!input_.ReadString(&value, static_cast<int>(length))) {
+        return Fail(ParseStatus::kMalformed, field_start);
+      }
+      if (!utf8_range::IsStructurallyValid(value)) {
+        return Fail(ParseStatus::kBadUtf8, field_start);
+      }
+      return ParseStatus::kOk;
+    }
+
+    if (!input_.Skip(static_cast<int>(length))) {
+      return Fail(ParseStatus::kMalformed, field_start);
+    }
+    return ParseStatus::kOk;
+  }
+
+  google::protobuf::io::CodedInputStream input_;
+  int recursion_limit_;
+  bool validate_all_utf8_;
+  std::vector<std::string> path_;
+  size_t offset_ = 0;
+};
+
+// Builds the result for the first failure that the locator finds in `input`,
+// or returns `fallback` if it finds none. Only messages with full reflection
+// can report more than `fallback`.
+ParseResult LocateParseFailure(const google::protobuf::MessageLite* msg,
+                               absl::string_view input,
+                               const RawParseOptions& options,
+                               ParseStatus fallback) {
+  const google::protobuf::Message* full_msg =
+      google::protobuf::DynamicCastMessage<google::protobuf::Message>(msg);
+  if (full_msg == nullptr) return MakeParseResult(fallback);
+
+  ParseFailureLocator locator(input, RecursionLimit(options),
+                              options.validate_utf8);
+  ParseStatus status = locator.Locate(full_msg->GetDescriptor());
+  if (status == ParseStatus::kOk) {
+    return MakeParseResult(fallback);
+  }
+
+  ParseResult result = MakeParseResult(status, locator.field_path());
+  result.has_offset = true;
+  result.offset = locator.offset();
+  return result;
+}
+
+// Quotes and escapes `value` the way Rust's `Debug` formats a string, so that
+// both kernels report the same path for an entry of a string-keyed map.
+std::string RustDebugQuote(absl::string_view value) {
+  std::string out = "\"";
+  for (char c : value) {
+    switch (c) {
+      case '"':
+        out += "\\\"";
+        break;
+      case '\\':
+        out += "\\\\";
+        break;
+      case '\0':
+        out += "\\0";
+        break;
+      case '\n':
+        out += "\\n";
+        break;
+      case '\r':
+        out += "\\r";
+        break;
+      case '\t':
+        out += "\\t";
+        break;
+      default:
+        if ((c >= 0 && c < 0x20) || c == 0x7f) {
+          absl::StrAppend(&out, "\\u{", absl::Hex(static_cast<int>(c)), "}");
+        } else {
+          out += c;
+        }
+    }
+  }
+  out += "\"";
+  return out;
+}
+
+// Formats the key of the map entry `entry` the way Rust's `Debug` does.
+std::string MapKeyDebugString(const google::protobuf::Message& entry) {
+  const google::protobuf::Reflection* reflection = entry.GetReflection();
+  const FieldDescriptor* key = entry.GetDescriptor()->map_key();
+  switch (key->cpp_type()) {
+    case FieldDescriptor::CPPTYPE_INT32:
+      return absl::StrCat(reflection->GetInt32(entry, key));
+    case FieldDescriptor::CPPTYPE_INT64:
+      return absl::StrCat(reflection->GetInt64(entry, key));
+    case FieldDescriptor::CPPTYPE_UINT32:
+      return absl::StrCat(reflection->GetUInt32(entry, key));
+    case FieldDescriptor::CPPTYPE_UINT64:
+      return absl::StrCat(reflection->GetUInt64(entry, key));
+    case FieldDescriptor::CPPTYPE_BOOL:
+      return reflection->GetBool(entry, key) ? "true" : "false";
+    case FieldDescriptor::CPPTYPE_STRING:
+      return RustDebugQuote(reflection->GetString(entry, key));
+    default:
+      ABSL_LOG(FATAL) << "unsupported map key type: " << key->type_name();
+  }
+}
+
+// Appends the path of every unset required field of `msg` and of its
+// submessages to `paths`, each prefixed with `prefix`.
+//
+// This matches the paths that generated upb code reports: repeated elements
+// are addressed by index and map values by their `Debug`-formatted key.
+void FindMissingRequiredFields(const google::protobuf::Message& msg,
+                               const std::string& prefix,
+                               std::vector<std::string>* paths) {
+  const Descriptor* descriptor = msg.GetDescriptor();
+  const google::protobuf::Reflection* reflection = msg.GetReflection();
+  for (int i = 0; i < descriptor->field_count(); ++i) {
+    const FieldDescriptor* field = descriptor->field(i);
+    if (field->is_required() && !reflection->HasField(msg, field)) {
+      paths->push_back(absl::StrCat(prefix, field->name()));
+    }
+    if (field->cpp_type() != FieldDescriptor::CPPTYPE_MESSAGE) continue;
+
+    if (field->is_map()) {
+      const FieldDescriptor* value = field->message_type()->map_value();
+      if (value->cpp_type() != FieldDescriptor::CPPTYPE_MESSAGE) continue;
+      for (int j = 0; j < reflection->FieldSize(msg, field); ++j) {
+        const google::protobuf::Message& entry =
+            reflection->GetRepeatedMessage(msg, field, j);
+        FindMissingRequiredFields(
+            entry.GetReflection()->GetMessage(entry, value),
+            absl::StrCat(prefix, field->name(), "[", MapKeyDebugString(entry),
+                         "]."),
+            paths);
+      }
+    } else if (field->is_repeated()) {
+      for (int j = 0; j < reflection->FieldSize(msg, field); ++j) {
+        FindMissingRequiredFields(
+            reflection->GetRepeatedMessage(msg, field, j),
+            absl::StrCat(prefix, field->name(), "[", j, "]."), paths);
+      }
+    } else if (reflection->HasField(msg, field)) {
+      FindMissingRequiredFields(reflection->GetMessage(msg, field),
+                                absl::StrCat(prefix, field->name(), "."),
+                                paths);
+    }
+  }
+}
+
+// Returns the paths of the unset required fields of `msg`. Lite messages
+// cannot determine the paths, so the result is empty for them.
+std::vector<std::string> MissingRequiredFields(
+    const google::protobuf::MessageLite* msg) {
+  std::vector<std::string> paths;
+  const google::protobuf::Message* full_msg =
+      google::protobuf::DynamicCastMessage<google::protobuf::Message>(msg);
+  if (full_msg != nullptr) FindMissingRequiredFields(*full_msg, "", &paths);
+  return paths;
+}
+
+SerializeResult MissingRequiredResult(const google::protobuf::MessageLite* msg) {
+  return SerializeResult{
+      SerializeStatus::kMissingRequired,
+      google::protobuf::rust::RustStringRawParts(absl::StrJoin(
+          MissingRequiredFields(msg), absl::string_view("\0", 1)))};
+}
+
+// Moves every extension of `msg` and of its submessages that is not in
+// `registered` to the unknown fields, as if it had not been linked into the
+// binary. The C++ parser cannot be limited to a set of extensions, so this
+// runs after parsing. Cached sizes must be up to date; moving an extension
+// does not change the size of its message.
+void UnparseUnregisteredExtensions(
+    google::protobuf::Message* msg, absl::Span<const FieldDescriptor* const> registered) {
+  const google::protobuf::Reflection* reflection = msg->GetReflection();
+  std::vector<const FieldDescriptor*> fields;
+  reflection->ListFields(*msg, &fields);
+  for (const FieldDescriptor* field : fields) {
+    if (field->is_extension() &&
+        !std::binary_search(registered.begin(), registered.end(), field,
+                            std::less<const FieldDescriptor*>())) {
+      std::string data;
+      {
+        google::protobuf::io::StringOutputStream stream(&data);
+        google::protobuf::io::CodedOutputStream coded(&stream);
+        google::protobuf::internal::WireFormat::SerializeFieldWithCachedSizes(
+            field, *msg, &coded);
+      }
+      reflection->ClearField(msg, field);
+      google::protobuf::io::CodedInputStream coded(
+          reinterpret_cast<const uint8_t*>(data.data()),
+          static_cast<int>(data.size()));
+      ABSL_CHECK(reflection->MutableUnknownFields(msg)->MergeFromCodedStream(
+          &coded));
+      continue;
+    }
+    if (field->cpp_type() != FieldDescriptor::CPPTYPE_MESSAGE) continue;
+    if (field->is_repeated()) {
+      for (int i = 0; i < reflection->FieldSize(*msg, field); ++i) {
+        UnparseUnregisteredExtensions(
+            reflection->MutableRepeatedMessage(msg, field, i), registered);
+      }
+    } else {
+      UnparseUnregisteredExtensions(reflection->MutableMessage(msg, field),
+                                    registered);
+    }
+  }
+}
+
+// Parses `input` into `m`, either replacing or merging into its contents.
+ParseResult ParseInto(google::protobuf::MessageLite* m, google::protobuf::rust::PtrAndLen input,
+                      bool merge, const RawParseOptions& options) {
+  if (input.len > std::numeric_limits<int>::max()) {
+    return MakeParseResult(ParseStatus::kMalformed);
+  }
+  if (!merge) m->Clear();
+  google::protobuf::io::CodedInputStream coded(
+      reinterpret_cast<const uint8_t*>(input.ptr), static_cast<int>(input.len));
+  coded.SetRecursionLimit(RecursionLimit(options));
+  if (!m->MergePartialFromCodedStream(&coded) ||
+      !coded.ConsumedEntireMessage()) {
+    return LocateParseFailure(m, input.AsStringView(), options,
+                              ParseStatus::kMalformed);
+  }
+  google::protobuf::Message* full_msg = google::protobuf::DynamicCastMessage<google::protobuf::Message>(m);
+  // The registry is ignored for lite messages: its extensions are not lite,
+  // and only lite extensions can extend a lite message.
+  if (options.extension_registry != nullptr && full_msg != nullptr) {
+    full_msg->ByteSizeLong();
+    UnparseUnregisteredExtensions(
+        full_msg, absl::MakeConstSpan(options.extension_registry,
+                                      options.extension_registry_len));
+  }
+  if (options.validate_utf8) {
+    // The C++ parser accepts invalid UTF-8 in fields that don't require
+    // validation, so re-walk the input to check them.
+    ParseResult result = LocateParseFailure(m, input.AsStringView(), options,
+                                            ParseStatus::kOk);
+    if (result.status != ParseStatus::kOk) return result;
+  }
+  if (options.check_required && !m->IsInitialized()) {
+    // Only report the first missing field; the rest are reported by
+    // serialization after fixing it.
+    std::vector<std::string> missing = MissingRequiredFields(m);
+    return MakeParseResult(ParseStatus::kMissingRequired,
+                           missing.empty() ? "" : std::move(missing.front()));
+  }
+  return MakeParseResult(ParseStatus::kOk);
+}
+
+}  // namespace
 
 extern "C" {
 
@@ -9,17 +481,77 @@ void proto2_rust_Message_delete(google::protobuf::MessageLite* m) { delete m; }
 
 void proto2_rust_Message_clear(google::protobuf::MessageLite* m) { m->Clear(); }
 
-bool proto2_rust_Message_parse(google::protobuf::MessageLite* m,
-                               google::protobuf::rust::SerializedData input) {
-  if (input.len > std::numeric_limits<int>::max()) {
-    return false;
+ParseResult proto2_rust_Message_parse(google::protobuf::MessageLite* m,
+                                      google::protobuf::rust::PtrAndLen input) {
+  return ParseInto(m, input, /*merge=*/false, kDefaultParseOptions);
+}
+
+ParseResult proto2_rust_Message_merge_parse(google::protobuf::MessageLite* m,
+                                            google::protobuf::rust::PtrAndLen input) {
+  return ParseInto(m, input, /*merge=*/true, kDefaultParseOptions);
+}
+
+ParseResult proto2_rust_Message_parse_with_options(
+    google::protobuf::MessageLite* m, google::protobuf::rust::PtrAndLen input,
+    RawParseOptions options) {
+  return ParseInto(m, input, /*merge=*/false, options);
+}
+
+SerializeResult proto2_rust_Message_serialize(
+    const google::protobuf::MessageLite* m, google::protobuf::rust::SerializedData* output) {
+  SerializeStatus status = google::protobuf::rust::SerializePartialMsg(m, output)
+                               ? SerializeStatus::kOk
+                               : SerializeStatus::kTooLarge;
+  return SerializeResult{status, google::protobuf::rust::RustStringRawParts("")};
+}
+
+// Computes the serialized size of `m` into `size`, which also caches the sizes
+// of its submessages for `proto2_rust_Message_serialize_with_cached_sizes`.
+// Unset required fields are only reported if `check_required` is set.
+SerializeResult proto2_rust_Message_byte_size(const google::protobuf::MessageLite* m,
+                                              bool check_required,
+                                              size_t* size) {
+  if (check_required && !m->IsInitialized()) {
+    return MissingRequiredResult(m);
   }
-  return m->ParseFromArray(input.data, static_cast<int>(input.len));
+  *size = m->ByteSizeLong();
+  SerializeStatus status =
+      *size > INT_MAX ? SerializeStatus::kTooLarge : SerializeStatus::kOk;
+  return SerializeResult{status, google::protobuf::rust::RustStringRawParts("")};
 }
 
-bool proto2_rust_Message_serialize(const google::protobuf::MessageLite* m,
-                                   google::protobuf::rust::SerializedData* output) {
-  return google::protobuf::rust::SerializeMsg(m, output);
+// Serializes `m` into `buf`, which must have room for the size that the last
+// successful `proto2_rust_Message_byte_size` of `m` returned. `m` must not have
+// been modified since.
+void proto2_rust_Message_serialize_with_cached_sizes(
+    const google::protobuf::MessageLite* m, uint8_t* buf, bool deterministic) {
+  if (deterministic) {
+    // Map entries are only sorted when the stream asks for deterministic
+    // output, which `SerializeWithCachedSizesToArray` never does.
+    google::protobuf::io::ArrayOutputStream array(buf, m->GetCachedSize());
+    google::protobuf::io::CodedOutputStream coded(&array);
+    coded.SetSerializationDeterministic(true);
+    m->SerializeWithCachedSizes(&coded);
+  } else {
+    m->SerializeWithCachedSizesToArray(buf);
+  }
+}
+
+// Serializes `m` into `buf`, which has room for `len` bytes. `size` is set to
+// the serialized size, even if it is larger than `len`. Unset required fields
+// are only reported if `check_required` is set.
+SerializeResult proto2_rust_Message_serialize_into(
+    const google::protobuf::MessageLite* m, uint8_t* buf, size_t len,
+    bool deterministic, bool check_required, size_t* size) {
+  SerializeResult result =
+      proto2_rust_Message_byte_size(m, check_required, size);
+  if (result.status != SerializeStatus::kOk) return result;
+  if (*size > len) {
+    result.status = SerializeStatus::kBufferTooSmall;
+    return result;
+  }
+  proto2_rust_Message_serialize_with_cached_sizes(m, buf, deterministic);
+  return result;
 }
 
 void proto2_rust_Message_copy_from(google::protobuf::MessageLite* dst,
@@ -0,0 +1,234 @@
+#include <cstddef>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "absl/log/absl_check.h"
+#include "google/protobuf/descriptor.h"
+#include "google/protobuf/dynamic_message.h"
+#include "google/protobuf/message.h"
+#include "google/protobuf/message_lite.h"
+#include "rust/cpp_kernel/strings.h"
+
+namespace {
+
+using google::protobuf::Descriptor;
+using google::protobuf::DescriptorPool;
+using google::protobuf::DynamicMessageFactory;
+using google::protobuf::DynamicCastMessage;
+using google::protobuf::FieldDescriptor;
+using google::protobuf::Message;
+using google::protobuf::MessageFactory;
+using google::protobuf::MessageLite;
+using google::protobuf::rust::PtrAndLen;
+
+// Lite messages have no `Reflection`, so their fields can't be accessed
+// through descriptors.
+const Message& AsMessage(const MessageLite* m) {
+  const Message* msg = DynamicCastMessage<Message>(m);
+  ABSL_CHECK(msg != nullptr) << "reflection is not supported for lite messages";
+  return *msg;
+}
+
+Message& AsMessage(MessageLite* m) {
+  Message* msg = DynamicCastMessage<Message>(m);
+  ABSL_CHECK(msg != nullptr) << "reflection is not supported for lite messages";
+  return *msg;
+}
+
+// Returns a view of `s`, which is `scratch` if the field had to be copied out
+// of a non-flat representation such as an `absl::Cord`. In that case the copy
+// is moved to the heap, and ownership is passed to the caller through
+// `owned`.
+PtrAndLen BorrowString(const std::string& s, std::string& scratch,
+                       std::string** owned) {
+  if (&s == &scratch) {
+    *owned = new std::string(std::move(scratch));
+    return PtrAndLen{(*owned)->data(), (*owned)->size()};
+  }
+  *owned = nullptr;
+  return PtrAndLen{s.data(), s.size()};
+}
+
+}  // namespace
+
+extern "C" {
+
+bool proto2_rust_Reflection_is_supported(const MessageLite* m) {
+  return DynamicCastMessage<Message>(m) != nullptr;
+}
+
+bool proto2_rust_Reflection_has(const MessageLite* m,
+                                const FieldDescriptor* f) {
+  const Message& msg = AsMessage(m);
+  return msg.GetReflection()->HasField(msg, f);
+}
+
+void proto2_rust_Reflection_clear(MessageLite* m, const FieldDescriptor* f) {
+  Message& msg = AsMessage(m);
+  msg.GetReflection()->ClearField(&msg, f);
+}
+
+size_t proto2_rust_Reflection_field_size(const MessageLite* m,
+                                         const FieldDescriptor* f) {
+  const Message& msg = AsMessage(m);
+  return msg.GetReflection()->FieldSize(msg, f);
+}
+
+// Writes the first `cap` of the extensions that are set in `m` to `out`, in
+// order of field number, and returns how many are set.
+size_t proto2_rust_Reflection_list_extensions(const MessageLite* m,
+                                              const FieldDescriptor** out,
+                                              size_t cap) {
+  const Message& msg = AsMessage(m);
+  std::vector<const FieldDescriptor*> fields;
+  msg.GetReflection()->ListFields(msg, &fields);
+  size_t count = 0;
+  for (const FieldDescriptor* f : fields) {
+    if (!f->is_extension()) continue;
+    if (count < cap) out[count] = f;
+    ++count;
+  }
+  return count;
+}
+
+#define expose_reflection_scalar_methods(rust_type, cpp_type, Name)           \
+  cpp_type proto2_rust_Reflection_get_##rust_type(const MessageLite* m,       \
+                                                  const FieldDescriptor* f) { \
+    const Message& msg = AsMessage(m);                                        \
+    return msg.GetReflection()->Get##Name(msg, f);                            \
+  }                                                                           \
+  void proto2_rust_Reflection_set_##rust_type(                                \
+      MessageLite* m, const FieldDescriptor* f, cpp_type val) {               \
+    Message& msg = AsMessage(m);                                              \
+    msg.GetReflection()->Set##Name(&msg, f, val);                             \
+  }                                                                           \
+  cpp_type proto2_rust_Reflection_get_repeated_##rust_type(                   \
+      const MessageLite* m, const FieldDescriptor* f, size_t i) {             \
+    const Message& msg = AsMessage(m);                                        \
+    return msg.GetReflection()->GetRepeated##Name(msg, f, i);                 \
+  }                                                                           \
+  void proto2_rust_Reflection_set_repeated_##rust_type(                       \
+      MessageLite* m, const FieldDescriptor* f, size_t i, cpp_type val) {     \
+    Message& msg = AsMessage(m);                                              \
+    msg.GetReflection()->SetRepeated##Name(&msg, f, i, val);                  \
+  }                                                                           \
+  void proto2_rust_Reflection_add_##rust_type(                                \
+      MessageLite* m, const FieldDescriptor* f, cpp_type val) {               \
+    Message& msg = AsMessage(m);                                              \
+    msg.GetReflection()->Add##Name(&msg, f, val);                             \
+  }
+
+expose_reflection_scalar_methods(bool, bool, Bool);
+expose_reflection_scalar_methods(i32, int32_t, Int32);
+expose_reflection_scalar_methods(i64, int64_t, Int64);
+expose_reflection_scalar_methods(u32, uint32_t, UInt32);
+expose_reflection_scalar_methods(u64, uint64_t, UInt64);
+expose_reflection_scalar_methods(f32, float, Float);
+expose_reflection_scalar_methods(f64, double, Double);
+expose_reflection_scalar_methods(enum, int, EnumValue);
+
+#undef expose_reflection_scalar_methods
+
+PtrAndLen proto2_rust_Reflection_get_string(const MessageLite* m,
+                                            const FieldDescriptor* f,
+                                            std::string** owned) {
+  const Message& msg = AsMessage(m);
+  std::string scratch;
+  const std::string& s =
+      msg.GetReflection()->GetStringReference(msg, f, &scratch);
+  return BorrowString(s, scratch, owned);
+}
+
+void proto2_rust_Reflection_set_string(MessageLite* m,
+                                       const FieldDescriptor* f,
+                                       PtrAndLen val) {
+  Message& msg = AsMessage(m);
+  msg.GetReflection()->SetString(&msg, f, val.CopyToString());
+}
+
+PtrAndLen proto2_rust_Reflection_get_repeated_string(const MessageLite* m,
+                                                     const FieldDescriptor* f,
+                                                     size_t i,
+                                                     std::string** owned) {
+  const Message& msg = AsMessage(m);
+  std::string scratch;
+  const std::string& s =
+      msg.GetReflection()->GetRepeatedStringReference(msg, f, i, &scratch);
+  return BorrowString(s, scratch, owned);
+}
+
+void proto2_rust_Reflection_set_repeated_string(MessageLite* m,
+                                                const FieldDescriptor* f,
+                                                size_t i, PtrAndLen val) {
+  Message& msg = AsMessage(m);
+  msg.GetReflection()->SetRepeatedString(&msg, f, i, val.CopyToString());
+}
+
+void proto2_rust_Reflection_add_string(MessageLite* m, const FieldDescriptor* f,
+                                       PtrAndLen val) {
+  Message& msg = AsMessage(m);
+  msg.GetReflection()->AddString(&msg, f, val.CopyToString());
+}
+
+// Returns the default instance of the field's type if the field is unset.
+const MessageLite* proto2_rust_Reflection_get_message(
+    const MessageLite* m, const FieldDescriptor* f) {
+  const Message& msg = AsMessage(m);
+  return &msg.GetReflection()->GetMessage(msg, f);
+}
+
+MessageLite* proto2_rust_Reflection_mutable_message(MessageLite* m,
+                                                    const FieldDescriptor* f) {
+  Message& msg = AsMessage(m);
+  return msg.GetReflection()->MutableMessage(&msg, f);
+}
+
+const MessageLite* proto2_rust_Reflection_get_repeated_message(
+    const MessageLite* m, const FieldDescriptor* f, size_t i) {
+  const Message& msg = AsMessage(m);
+  return &msg.GetReflection()->GetRepeatedMessage(msg, f, i);
+}
+
+MessageLite* proto2_rust_Reflection_mutable_repeated_message(
+    MessageLite* m, const FieldDescriptor* f, size_t i) {
+  Message& msg = AsMessage(m);
+  return msg.GetReflection()->MutableRepeatedMessage(&msg, f, i);
+}
+
+MessageLite* proto2_rust_Reflection_add_message(MessageLite* m,
+                                                const FieldDescriptor* f) {
+  Message& msg = AsMessage(m);
+  return msg.GetReflection()->AddMessage(&msg, f);
+}
+
+void proto2_rust_Reflection_remove_last(MessageLite* m,
+                                        const FieldDescriptor* f) {
+  Message& msg = AsMessage(m);
+  msg.GetReflection()->RemoveLast(&msg, f);
+}
+
+void proto2_rust_Reflection_swap_elements(MessageLite* m,
+                                          const FieldDescriptor* f, size_t i,
+                                          size_t j) {
+  Message& msg = AsMessage(m);
+  msg.GetReflection()->SwapElements(&msg, f, i, j);
+}
+
+// Returns a new, heap-allocated message of the type `d`, or nullptr if `d`
+// has no generated class. `d` must be compiled in: the types of the lite pool
+// are implemented by a DynamicMessage, and those of runtime-built pools are
+// created by `proto2_rust_DescriptorPool_new_message`.
+MessageLite* proto2_rust_Reflection_new_message(const Descriptor* d) {
+  // Messages refer to the factory that created them, so it is never freed.
+  static DynamicMessageFactory* dynamic_factory = new DynamicMessageFactory();
+  MessageFactory* factory =
+      d->file()->pool() == DescriptorPool::generated_pool()
+          ? MessageFactory::generated_factory()
+          : dynamic_factory;
+  const Message* prototype = factory->GetPrototype(d);
+  if (prototype == nullptr) return nullptr;
+  return prototype->New();
+}
+
+}  // extern "C"
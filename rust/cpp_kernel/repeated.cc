@@ -43,9 +43,29 @@ extern "C" {
       google::protobuf::RepeatedField<ty>* r) {                                          \
     r->Clear();                                                                \
   }                                                                            \
+  void proto2_rust_RepeatedField_##rust_ty##_swap(google::protobuf::RepeatedField<ty>* r,\
+                                                  size_t a, size_t b) {        \
+    r->SwapElements(a, b);                                                     \
+  }                                                                            \
+  void proto2_rust_RepeatedField_##rust_ty##_truncate(                         \
+      google::protobuf::RepeatedField<ty>* r, size_t len) {                              \
+    if (len < static_cast<size_t>(r->size())) r->Truncate(len);                \
+  }                                                                            \
   void proto2_rust_RepeatedField_##rust_ty##_reserve(                          \
       google::protobuf::RepeatedField<ty>* r, size_t additional) {                       \
     r->Reserve(r->size() + additional);                                        \
+  }                                                                            \
+  const ty* proto2_rust_RepeatedField_##rust_ty##_data(                        \
+      const google::protobuf::RepeatedField<ty>* r) {                                    \
+    return r->data();                                                          \
+  }                                                                            \
+  ty* proto2_rust_RepeatedField_##rust_ty##_mutable_data(                      \
+      google::protobuf::RepeatedField<ty>* r) {                                          \
+    return r->mutable_data();                                                  \
+  }                                                                            \
+  void proto2_rust_RepeatedField_##rust_ty##_extend_from_slice(                \
+      google::protobuf::RepeatedField<ty>* r, const ty* data, size_t len) {              \
+    r->Add(data, data + len);                                                  \
   }
 
 expose_repeated_field_methods(int32_t, i32);
@@ -94,6 +114,16 @@ expose_repeated_field_methods(int64_t, i64);
       google::protobuf::RepeatedPtrField<std::string>* r) {                    \
     r->Clear();                                                      \
   }                                                                  \
+  void proto2_rust_RepeatedField_##ty##_swap(                        \
+      google::protobuf::RepeatedPtrField<std::string>* r, size_t a, size_t b) {\
+    r->SwapElements(a, b);                                           \
+  }                                                                  \
+  void proto2_rust_RepeatedField_##ty##_truncate(                    \
+      google::protobuf::RepeatedPtrField<std::string>* r, size_t len) {        \
+    if (len < static_cast<size_t>(r->size())) {                      \
+      r->DeleteSubrange(len, r->size() - len);                       \
+    }                                                                \
+  }                                                                  \
   void proto2_rust_RepeatedField_##ty##_reserve(                     \
       google::protobuf::RepeatedPtrField<std::string>* r, size_t additional) { \
     r->Reserve(r->size() + additional);                              \
@@ -147,6 +177,16 @@ void proto2_rust_RepeatedField_Message_copy_from(
   dst->MergeFrom<google::protobuf::MessageLite>(*src);
 }
 
+void proto2_rust_RepeatedField_Message_swap(RepeatedPtrFieldBase* field,
+                                            size_t a, size_t b) {
+  RustRepeatedMessageHelper::SwapElements(*field, a, b);
+}
+
+void proto2_rust_RepeatedField_Message_truncate(RepeatedPtrFieldBase* field,
+                                                size_t len) {
+  RustRepeatedMessageHelper::Truncate(*field, len);
+}
+
 void proto2_rust_RepeatedField_Message_reserve(RepeatedPtrFieldBase* field,
                                                size_t additional) {
   RustRepeatedMessageHelper::Reserve(*field, additional);
@@ -36,8 +36,10 @@ extern "C" struct SerializedData {
   size_t len;
 };
 
-inline bool SerializeMsg(const google::protobuf::MessageLite* msg, SerializedData* out) {
-  ABSL_DCHECK(msg->IsInitialized());
+// Like `SerializeMsg`, but also serializes messages with unset required
+// fields.
+inline bool SerializePartialMsg(const google::protobuf::MessageLite* msg,
+                                SerializedData* out) {
   size_t len = msg->ByteSizeLong();
   if (len > INT_MAX) {
     ABSL_LOG(ERROR) << msg->GetTypeName()
@@ -56,6 +58,11 @@ inline bool SerializeMsg(const google::protobuf::MessageLite* msg, SerializedDat
   return true;
 }
 
+inline bool SerializeMsg(const google::protobuf::MessageLite* msg, SerializedData* out) {
+  ABSL_DCHECK(msg->IsInitialized());
+  return SerializePartialMsg(msg, out);
+}
+
 }  // namespace rust
 }  // namespace protobuf
 }  // namespace google
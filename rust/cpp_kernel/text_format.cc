@@ -0,0 +1,88 @@
+#include <string>
+#include <utility>
+
+#include "absl/strings/str_cat.h"
+#include "absl/strings/string_view.h"
+#include "google/protobuf/io/tokenizer.h"
+#include "google/protobuf/message.h"
+#include "google/protobuf/message_lite.h"
+#include "google/protobuf/text_format.h"
+#include "rust/cpp_kernel/strings.h"
+
+namespace {
+
+using google::protobuf::DynamicCastMessage;
+using google::protobuf::Message;
+using google::protobuf::MessageLite;
+using google::protobuf::TextFormat;
+using google::protobuf::io::ColumnNumber;
+using google::protobuf::io::ErrorCollector;
+using google::protobuf::rust::PtrAndLen;
+
+constexpr absl::string_view kLiteError =
+    "text format is not supported for lite messages";
+
+// Records the first error that the parser reports.
+class FirstErrorCollector : public ErrorCollector {
+ public:
+  void RecordError(int line, ColumnNumber column,
+                   absl::string_view message) override {
+    if (!error_.empty()) return;
+    error_ = line < 0 ? std::string(message)
+                      : absl::StrCat(line + 1, ":", column + 1, ": ", message);
+  }
+
+  std::string& error() { return error_; }
+
+ private:
+  std::string error_;
+};
+
+}  // namespace
+
+extern "C" {
+
+// Prints `m` in text format. Returns whether it succeeded, and sets `*out` to
+// a new string that holds either the text or a description of the problem.
+bool proto2_rust_Message_to_text_format(const MessageLite* m, bool single_line,
+                                        bool print_unknown_fields,
+                                        std::string** out) {
+  const Message* msg = DynamicCastMessage<Message>(m);
+  if (msg == nullptr) {
+    *out = new std::string(kLiteError);
+    return false;
+  }
+  TextFormat::Printer printer;
+  printer.SetSingleLineMode(single_line);
+  printer.SetHideUnknownFields(!print_unknown_fields);
+  printer.SetUseUtf8StringEscaping(true);
+  std::string text;
+  printer.PrintToString(*msg, &text);
+  *out = new std::string(std::move(text));
+  return true;
+}
+
+// Parses the text format in `text` into `m`. If `merge` is false, `m` must be
+// empty and singular fields may appear at most once. Returns whether it
+// succeeded, and sets `*error` to a new string that describes the problem if
+// it didn't.
+bool proto2_rust_Message_parse_text_format(MessageLite* m, PtrAndLen text,
+                                           bool merge, std::string** error) {
+  Message* msg = DynamicCastMessage<Message>(m);
+  if (msg == nullptr) {
+    *error = new std::string(kLiteError);
+    return false;
+  }
+  FirstErrorCollector collector;
+  TextFormat::Parser parser;
+  parser.RecordErrorsTo(&collector);
+  bool ok = merge ? parser.MergeFromString(text.AsStringView(), msg)
+                  : parser.ParseFromString(text.AsStringView(), msg);
+  if (!ok) {
+    if (collector.error().empty()) collector.error() = "invalid text format";
+    *error = new std::string(std::move(collector.error()));
+  }
+  return ok;
+}
+
+}  // extern "C"
@@ -0,0 +1,91 @@
+#include <cstddef>
+#include <cstdint>
+
+#include "absl/strings/string_view.h"
+#include "google/protobuf/message.h"
+#include "google/protobuf/message_lite.h"
+#include "google/protobuf/unknown_field_set.h"
+#include "rust/cpp_kernel/strings.h"
+
+namespace {
+
+using google::protobuf::DynamicCastMessage;
+using google::protobuf::Message;
+using google::protobuf::MessageLite;
+using google::protobuf::UnknownField;
+using google::protobuf::UnknownFieldSet;
+
+// LINT.IfChange(unknown_field)
+struct RawUnknownField {
+  uint32_t number;
+  UnknownField::Type type;
+  // The value of varint, fixed32 and fixed64 fields.
+  uint64_t int_value;
+  // The value of length-delimited fields.
+  google::protobuf::rust::PtrAndLen bytes;
+  // The value of group fields.
+  const UnknownFieldSet* group;
+};
+// LINT.ThenChange(//depot/google3/third_party/protobuf/rust/cpp.rs:unknown_field)
+
+}  // namespace
+
+extern "C" {
+
+// Returns the unknown fields of `m`, or nullptr if `m` is a lite message.
+// Lite messages don't expose their unknown fields.
+const UnknownFieldSet* proto2_rust_Message_unknown_fields(
+    const MessageLite* m) {
+  const Message* full_msg = DynamicCastMessage<Message>(m);
+  if (full_msg == nullptr) return nullptr;
+  return &full_msg->GetReflection()->GetUnknownFields(*full_msg);
+}
+
+// Removes the unknown fields of `m`, but not those of its submessages. This is
+// a no-op for lite messages.
+void proto2_rust_Message_clear_unknown_fields(MessageLite* m) {
+  Message* full_msg = DynamicCastMessage<Message>(m);
+  if (full_msg == nullptr) return;
+  full_msg->GetReflection()->MutableUnknownFields(full_msg)->Clear();
+}
+
+// Removes the unknown fields of `m` and of all of its submessages. This is a
+// no-op for lite messages.
+void proto2_rust_Message_discard_unknown_fields(MessageLite* m) {
+  Message* full_msg = DynamicCastMessage<Message>(m);
+  if (full_msg == nullptr) return;
+  full_msg->DiscardUnknownFields();
+}
+
+size_t proto2_rust_UnknownFieldSet_field_count(const UnknownFieldSet* s) {
+  return static_cast<size_t>(s->field_count());
+}
+
+RawUnknownField proto2_rust_UnknownFieldSet_field(const UnknownFieldSet* s,
+                                                  size_t i) {
+  const UnknownField& field = s->field(static_cast<int>(i));
+  RawUnknownField raw{static_cast<uint32_t>(field.number()), field.type(), 0,
+                      google::protobuf::rust::PtrAndLen{nullptr, 0}, nullptr};
+  switch (field.type()) {
+    case UnknownField::TYPE_VARINT:
+      raw.int_value = field.varint();
+      break;
+    case UnknownField::TYPE_FIXED32:
+      raw.int_value = field.fixed32();
+      break;
+    case UnknownField::TYPE_FIXED64:
+      raw.int_value = field.fixed64();
+      break;
+    case UnknownField::TYPE_LENGTH_DELIMITED: {
+      absl::string_view bytes = field.length_delimited();
+      raw.bytes = google::protobuf::rust::PtrAndLen{bytes.data(), bytes.size()};
+      break;
+    }
+    case UnknownField::TYPE_GROUP:
+      raw.group = &field.group();
+      break;
+  }
+  return raw;
+}
+
+}  // extern "C"
@@ -0,0 +1,221 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Streams of length-delimited messages.
+//!
+//! Each message is written as its serialized size encoded as a varint,
+//! followed by the serialized message. This is the same framing used by Java's
+//! `writeDelimitedTo`/`parseDelimitedFrom` and C++'s
+//! `util/delimited_message_util.h`.
+
+use crate::__internal::Private;
+use crate::{Parse, ParseError, ParseErrorKind, ReadError, Serialize, WriteError};
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+/// The largest size prefix that is accepted by default; this is the maximum
+/// size of a serialized message.
+const DEFAULT_MAX_SIZE: usize = i32::MAX as usize;
+
+/// The maximum length of a varint encoding a `u64`.
+const MAX_VARINT_LEN: usize = 10;
+
+/// Writes `msg` to `writer`, prefixed with its size.
+///
+/// To write many messages, prefer [`DelimitedWriter`], which reuses its buffer.
+pub fn write_delimited(msg: &impl Serialize, writer: impl Write) -> Result<(), WriteError> {
+    DelimitedWriter::new(writer).write(msg)
+}
+
+/// Reads a single size-prefixed message from `reader`.
+///
+/// Returns `None` if `reader` is at its end before the first byte of the size
+/// prefix. To read many messages, prefer [`DelimitedReader`].
+pub fn read_delimited<M: Parse>(reader: impl Read) -> Option<Result<M, ReadError>> {
+    DelimitedReader::new(reader).next()
+}
+
+/// Writes size-prefixed messages to an [`io::Write`].
+#[derive(Debug)]
+pub struct DelimitedWriter<W> {
+    writer: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> DelimitedWriter<W> {
+    /// Creates a writer that writes messages to `writer`.
+    ///
+    /// Every message is written with a single call to
+    /// [`Write::write_all`], so `writer` does not need to be buffered.
+    pub fn new(writer: W) -> Self {
+        DelimitedWriter { writer, buf: Vec::new() }
+    }
+
+    /// Writes `msg`, prefixed with its size.
+    pub fn write(&mut self, msg: &impl Serialize) -> Result<(), WriteError> {
+        self.buf.clear();
+        // Reserve room for the largest possible prefix and serialize after it,
+        // so that the prefix and the message are written together.
+        self.buf.resize(MAX_VARINT_LEN, 0);
+        msg.serialize_to_vec(&mut self.buf)?;
+        let size = self.buf.len() - MAX_VARINT_LEN;
+
+        let mut prefix = [0; MAX_VARINT_LEN];
+        let prefix_len = encode_varint(size as u64, &mut prefix);
+        let start = MAX_VARINT_LEN - prefix_len;
+        self.buf[start..MAX_VARINT_LEN].copy_from_slice(&prefix[..prefix_len]);
+        self.writer.write_all(&self.buf[start..])?;
+        Ok(())
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Consumes this `DelimitedWriter` and returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads size-prefixed messages of type `M` from an [`io::Read`].
+///
+/// This is an iterator that yields each message in turn and ends when the
+/// reader is at its end between two messages. If the stream ends in the middle
+/// of a message, an [`io::ErrorKind::UnexpectedEof`] error is yielded instead.
+///
+/// The size prefix of each message is read one byte at a time, so wrapping an
+/// unbuffered reader in an [`io::BufReader`] is recommended.
+///
+/// After yielding an error the iterator is exhausted, since the position of
+/// the next message in the stream is unknown.
+pub struct DelimitedReader<M, R> {
+    reader: R,
+    buf: Vec<u8>,
+    max_size: usize,
+    done: bool,
+    _phantom: PhantomData<fn() -> M>,
+}
+
+impl<M: Parse, R: Read> DelimitedReader<M, R> {
+    /// Creates a reader that reads messages from `reader`.
+    pub fn new(reader: R) -> Self {
+        DelimitedReader {
+            reader,
+            buf: Vec::new(),
+            max_size: DEFAULT_MAX_SIZE,
+            done: false,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Sets the maximum size of a single message.
+    ///
+    /// Reading a message with a larger size prefix fails with
+    /// [`ReadError::TooLarge`] without reading the message. Defaults to the
+    /// maximum size of a serialized message, 2GiB - 1.
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Consumes this `DelimitedReader` and returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the size prefix of the next message, or returns `None` if the
+    /// reader is at its end.
+    fn read_size(&mut self) -> Result<Option<u64>, ReadError> {
+        let mut size = 0u64;
+        for i in 0..MAX_VARINT_LEN {
+            let mut byte = [0u8];
+            if let Err(err) = self.reader.read_exact(&mut byte) {
+                if i == 0 && err.kind() == io::ErrorKind::UnexpectedEof {
+                    return Ok(None);
+                }
+                return Err(err.into());
+            }
+            size |= u64::from(byte[0] & 0x7f) << (7 * i);
+            if byte[0] & 0x80 == 0 {
+                return Ok(Some(size));
+            }
+        }
+        Err(ParseError::new(Private, ParseErrorKind::Malformed).into())
+    }
+
+    fn read_message(&mut self) -> Result<Option<M>, ReadError> {
+        let Some(size) = self.read_size()? else {
+            return Ok(None);
+        };
+        if size > self.max_size as u64 {
+            return Err(ReadError::TooLarge { max_size: self.max_size });
+        }
+
+        self.buf.clear();
+        // Grow the buffer as data arrives rather than trusting the prefix.
+        (&mut self.reader).take(size).read_to_end(&mut self.buf)?;
+        if (self.buf.len() as u64) < size {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(Some(M::parse(&self.buf)?))
+    }
+}
+
+impl<M: Parse, R: Read> Iterator for DelimitedReader<M, R> {
+    type Item = Result<M, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_message().transpose();
+        self.done = !matches!(result, Some(Ok(_)));
+        result
+    }
+}
+
+/// Encodes `value` as a varint into the front of `buf`, returning the number
+/// of bytes used.
+fn encode_varint(mut value: u64, buf: &mut [u8; MAX_VARINT_LEN]) -> usize {
+    let mut len = 0;
+    while value >= 0x80 {
+        buf[len] = (value as u8) | 0x80;
+        value >>= 7;
+        len += 1;
+    }
+    buf[len] = value as u8;
+    len + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use googletest::prelude::*;
+
+    #[googletest::test]
+    fn test_encode_varint() {
+        let mut buf = [0; MAX_VARINT_LEN];
+        assert_that!(encode_varint(0, &mut buf), eq(1));
+        assert_that!(buf[0], eq(0));
+        assert_that!(encode_varint(300, &mut buf), eq(2));
+        assert_that!(buf[..2].to_vec(), eq(vec![0xac, 0x02]));
+        assert_that!(encode_varint(u64::MAX, &mut buf), eq(MAX_VARINT_LEN));
+        assert_that!(buf[MAX_VARINT_LEN - 1], eq(0x01));
+    }
+}
@@ -0,0 +1,649 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Structural comparison of messages, like C++'s `MessageDifferencer`.
+//!
+//! Where `==` only tells whether two messages are equal, a
+//! [`MessageDifferencer`] reports each field that differs, and can be told to
+//! ignore fields, to compare repeated fields regardless of order, and to
+//! compare floating point values approximately:
+//!
+//! ```ignore
+//! let differencer = MessageDifferencer::new()
+//!     .ignore_field(Config::descriptor().field_by_name("revision").unwrap())
+//!     .float_fraction_and_margin(1e-6, 0.0);
+//! for difference in differencer.compare(&expected, &actual) {
+//!     println!("{difference}");
+//! }
+//! ```
+//!
+//! Like `==`, the comparison ignores unknown fields. Extensions are compared
+//! like fields, as long as they are in the pool of the descriptor of the
+//! messages; others are parsed as unknown fields.
+
+use crate::reflect::{
+    FieldDescriptor, FieldType, MessageRef, ReflectMessage, ReflectValue, ReflectValueRef,
+};
+use crate::{TextFormatPrintOptions, ToTextFormat};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+/// How a [`Difference`] changes a field from the first message compared to the
+/// second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DifferenceKind {
+    /// The field or element is only set in the second message.
+    Added,
+    /// The field or element is only set in the first message.
+    Removed,
+    /// The field or element is set to different values in the two messages.
+    Modified,
+}
+
+/// A field or element of a repeated field or map that differs between two
+/// messages.
+///
+/// The path of a difference is the `.`-separated list of the names of the
+/// fields that lead to it from the messages that were compared, in which an
+/// element of a repeated field is followed by its index, such as
+/// `children[2].name`, and an entry of a map by its key, such as
+/// `labels["env"]`. An extension is named by its full name in brackets, as in
+/// the text format, such as `[pkg.priority]`. The index is in the second
+/// message for an added element and in the first message otherwise.
+#[derive(Debug, Clone)]
+pub struct Difference {
+    kind: DifferenceKind,
+    path: String,
+    field: FieldDescriptor,
+    old_value: Option<ReflectValue>,
+    new_value: Option<ReflectValue>,
+}
+
+impl Difference {
+    /// Returns how the field changes.
+    pub fn kind(&self) -> DifferenceKind {
+        self.kind
+    }
+
+    /// Returns the path of the field or element that differs.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the descriptor of the last field of the path.
+    pub fn field(&self) -> FieldDescriptor {
+        self.field.clone()
+    }
+
+    /// Returns the value in the first message, unless the field or element was
+    /// added.
+    pub fn old_value(&self) -> Option<&ReflectValue> {
+        self.old_value.as_ref()
+    }
+
+    /// Returns the value in the second message, unless the field or element
+    /// was removed.
+    pub fn new_value(&self) -> Option<&ReflectValue> {
+        self.new_value.as_ref()
+    }
+}
+
+impl fmt::Display for Difference {
+    /// Prints the difference on one line, such as `modified: count: 1 -> 2` or
+    /// `added: tags[0]: "new"`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.old_value, &self.new_value) {
+            (Some(old), Some(new)) => {
+                write!(f, "modified: {}: ", self.path)?;
+                write_value(f, &self.field, &old.as_value_ref())?;
+                f.write_str(" -> ")?;
+                write_value(f, &self.field, &new.as_value_ref())
+            }
+            (Some(value), None) | (None, Some(value)) => {
+                let kind = if self.kind == DifferenceKind::Added { "added" } else { "removed" };
+                write!(f, "{kind}: {}: ", self.path)?;
+                write_value(f, &self.field, &value.as_value_ref())
+            }
+            (None, None) => unreachable!("a difference has at least one value"),
+        }
+    }
+}
+
+/// How the elements of a repeated field are matched up with each other.
+#[derive(Debug, Clone)]
+enum RepeatedComparison {
+    /// As a set: each element matches an equal element at any index.
+    Set,
+    /// As a map: each element matches the element with the same value of the
+    /// given field of the element type.
+    Map(FieldDescriptor),
+}
+
+/// Compares two messages of the same type field by field.
+///
+/// By default, every field is compared, repeated fields element by element in
+/// order, map fields entry by entry, and floating point values exactly: a NaN
+/// is equal to an identical NaN, but `0.0` and `-0.0` differ, as with `==`.
+///
+/// # Examples
+/// ```ignore
+/// let item = Order::descriptor().field_by_name("items").unwrap();
+/// let sku = Item::descriptor().field_by_name("sku").unwrap();
+/// let differencer = MessageDifferencer::new().treat_as_map(item, sku);
+/// assert!(differencer.equals(&a, &b));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MessageDifferencer {
+    ignored_fields: HashSet<FieldDescriptor>,
+    ignored_paths: HashSet<String>,
+    repeated_comparisons: HashMap<FieldDescriptor, RepeatedComparison>,
+    repeated_fields_as_sets: bool,
+    fraction_and_margin: Option<(f64, f64)>,
+    partial: bool,
+}
+
+impl MessageDifferencer {
+    /// Returns a differencer that compares every field exactly.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets `field` to be ignored wherever it appears in the messages.
+    pub fn ignore_field(mut self, field: FieldDescriptor) -> Self {
+        self.ignored_fields.insert(field);
+        self
+    }
+
+    /// Sets the field at `path` to be ignored, where `path` is a `.`-separated
+    /// list of field names such as `"address.city"`. The path doesn't name
+    /// the elements of repeated fields, so it applies to all of them: with
+    /// `"children.name"`, the `name` of every element of `children` is
+    /// ignored. Extensions are named by their full name in brackets, such as
+    /// `"[pkg.priority]"`. A path that doesn't name a field has no effect.
+    pub fn ignore_path(mut self, path: &str) -> Self {
+        self.ignored_paths.insert(path.to_string());
+        self
+    }
+
+    /// Sets the repeated field `field` to be compared as a set, in which each
+    /// element of one message must be equal to a distinct element of the
+    /// other, at any index.
+    ///
+    /// # Panics
+    /// Panics if `field` is not a repeated field.
+    pub fn treat_as_set(mut self, field: FieldDescriptor) -> Self {
+        assert!(
+            field.is_repeated() && !field.is_map(),
+            "{} is not a repeated field",
+            field.full_name()
+        );
+        self.repeated_comparisons.insert(field, RepeatedComparison::Set);
+        self
+    }
+
+    /// Sets whether all repeated fields are compared as sets, except those
+    /// that [`treat_as_map`](Self::treat_as_map) is used for. Defaults to
+    /// false.
+    pub fn treat_repeated_fields_as_sets(mut self, as_sets: bool) -> Self {
+        self.repeated_fields_as_sets = as_sets;
+        self
+    }
+
+    /// Sets the repeated message field `field` to be compared as a map, in
+    /// which elements are matched up by the value of their field `key`, as if
+    /// it were a map field keyed by `key`. If several elements have the same
+    /// key, the last of them is used.
+    ///
+    /// # Panics
+    /// Panics if `field` is not a repeated message field, or if `key` is not a
+    /// singular field of the element type whose type could be a map key.
+    pub fn treat_as_map(mut self, field: FieldDescriptor, key: FieldDescriptor) -> Self {
+        let element = match field.message_type() {
+            Some(element) if field.is_repeated() && !field.is_map() => element,
+            _ => panic!("{} is not a repeated message field", field.full_name()),
+        };
+        assert!(
+            key.containing_type() == element,
+            "{} is not a field of {}",
+            key.full_name(),
+            element.full_name()
+        );
+        assert!(
+            !key.is_repeated()
+                && !matches!(
+                    key.field_type(),
+                    FieldType::Message | FieldType::Group | FieldType::Float | FieldType::Double
+                ),
+            "{} can't be used as a map key",
+            key.full_name()
+        );
+        self.repeated_comparisons.insert(field, RepeatedComparison::Map(key));
+        self
+    }
+
+    /// Sets floating point values to be compared approximately: two values
+    /// are equal if they differ by at most `margin`, or by at most `fraction`
+    /// times the larger of their magnitudes.
+    pub fn float_fraction_and_margin(mut self, fraction: f64, margin: f64) -> Self {
+        self.fraction_and_margin = Some((fraction, margin));
+        self
+    }
+
+    /// Sets whether only what is set in the first message is compared, so
+    /// that fields, elements and map entries that are only set in the second
+    /// message aren't differences. Defaults to false.
+    pub fn partial(mut self, partial: bool) -> Self {
+        self.partial = partial;
+        self
+    }
+
+    /// Returns the differences between `a` and `b`, in the order of the
+    /// fields of the message type, followed by the extensions in order of
+    /// field number. The [`DifferenceKind`] of each difference tells how it
+    /// changes `a` into `b`.
+    ///
+    /// # Panics
+    /// Panics if `a` and `b` are of different types.
+    pub fn compare<A: ReflectMessage, B: ReflectMessage>(&self, a: &A, b: &B) -> Vec<Difference> {
+        assert!(
+            A::descriptor() == B::descriptor(),
+            "can't compare a {} with a {}",
+            A::descriptor().full_name(),
+            B::descriptor().full_name()
+        );
+        let mut differences = Vec::new();
+        let root = Path { display: String::new(), names: String::new() };
+        self.compare_messages(&a.as_message_ref(), &b.as_message_ref(), &root, &mut differences);
+        differences
+    }
+
+    /// Returns whether `a` and `b` have no differences.
+    ///
+    /// # Panics
+    /// Panics if `a` and `b` are of different types.
+    pub fn equals<A: ReflectMessage, B: ReflectMessage>(&self, a: &A, b: &B) -> bool {
+        self.compare(a, b).is_empty()
+    }
+
+    fn compare_messages(
+        &self,
+        a: &MessageRef<'_>,
+        b: &MessageRef<'_>,
+        prefix: &Path,
+        out: &mut Vec<Difference>,
+    ) {
+        for field in a.descriptor().fields() {
+            self.compare_field(a, b, &field, prefix.field(&field), out);
+        }
+        let extensions: BTreeMap<_, _> = a
+            .extensions()
+            .into_iter()
+            .chain(b.extensions())
+            .map(|ext| (ext.number(), ext))
+            .collect();
+        for ext in extensions.into_values() {
+            self.compare_field(a, b, &ext, prefix.field(&ext), out);
+        }
+    }
+
+    /// Compares `field`, which may also be an extension, at `path`.
+    fn compare_field(
+        &self,
+        a: &MessageRef<'_>,
+        b: &MessageRef<'_>,
+        field: &FieldDescriptor,
+        path: Path,
+        out: &mut Vec<Difference>,
+    ) {
+        if self.ignored_fields.contains(field) || self.ignored_paths.contains(&path.names) {
+            return;
+        }
+        if field.is_map() {
+            let mut entries = BTreeMap::new();
+            for (key, value) in a.get_map(field) {
+                entries.entry(MapKey::new(&key)).or_insert((key, None, None)).1 = Some(value);
+            }
+            for (key, value) in b.get_map(field) {
+                entries.entry(MapKey::new(&key)).or_insert((key, None, None)).2 = Some(value);
+            }
+            let value_field = field.message_type().unwrap().field_by_number(2).unwrap();
+            for (key, a, b) in entries.into_values() {
+                let path = path.element(DisplayValue(field, &key));
+                self.compare_optional(&value_field, a, b, path, out);
+            }
+        } else if field.is_repeated() {
+            self.compare_repeated(a, b, field, &path, out);
+        } else {
+            let a = a.has_field(field).then(|| a.get_field(field));
+            let b = b.has_field(field).then(|| b.get_field(field));
+            self.compare_optional(field, a, b, path, out);
+        }
+    }
+
+    fn compare_repeated(
+        &self,
+        a: &MessageRef<'_>,
+        b: &MessageRef<'_>,
+        field: &FieldDescriptor,
+        path: &Path,
+        out: &mut Vec<Difference>,
+    ) {
+        let a: Vec<_> = a.get_repeated(field).iter().collect();
+        let b: Vec<_> = b.get_repeated(field).iter().collect();
+        let comparison = match self.repeated_comparisons.get(field) {
+            None if self.repeated_fields_as_sets => Some(RepeatedComparison::Set),
+            comparison => comparison.cloned(),
+        };
+        match comparison {
+            None => {
+                let len = a.len().max(b.len());
+                let (mut a, mut b) = (a.into_iter(), b.into_iter());
+                for i in 0..len {
+                    self.compare_optional(field, a.next(), b.next(), path.element(i), out);
+                }
+            }
+            Some(RepeatedComparison::Set) => {
+                let mut matched = vec![false; b.len()];
+                for (i, a) in a.iter().enumerate() {
+                    let found = (0..b.len())
+                        .find(|&j| !matched[j] && self.values_equal(field, a, &b[j], path));
+                    match found {
+                        Some(j) => matched[j] = true,
+                        None => out.push(removed(field, path.element(i).display, a)),
+                    }
+                }
+                if !self.partial {
+                    for (j, b) in b.iter().enumerate() {
+                        if !matched[j] {
+                            out.push(added(field, path.element(j).display, b));
+                        }
+                    }
+                }
+            }
+            Some(RepeatedComparison::Map(key)) => {
+                let mut entries = BTreeMap::new();
+                for a in a {
+                    let k = element_key(&a, &key);
+                    entries.entry(MapKey::new(&k)).or_insert((k, None, None)).1 = Some(a);
+                }
+                for b in b {
+                    let k = element_key(&b, &key);
+                    entries.entry(MapKey::new(&k)).or_insert((k, None, None)).2 = Some(b);
+                }
+                for (k, a, b) in entries.into_values() {
+                    let path = path.element(DisplayValue(&key, &k));
+                    self.compare_optional(field, a, b, path, out);
+                }
+            }
+        }
+    }
+
+    /// Compares a value that may be missing from either message.
+    fn compare_optional(
+        &self,
+        field: &FieldDescriptor,
+        a: Option<ReflectValueRef<'_>>,
+        b: Option<ReflectValueRef<'_>>,
+        path: Path,
+        out: &mut Vec<Difference>,
+    ) {
+        match (a, b) {
+            (None, None) => {}
+            (Some(a), None) => out.push(removed(field, path.display, &a)),
+            (None, Some(b)) => {
+                if !self.partial {
+                    out.push(added(field, path.display, &b))
+                }
+            }
+            (Some(ReflectValueRef::Message(a)), Some(ReflectValueRef::Message(b))) => {
+                self.compare_messages(&a, &b, &path, out)
+            }
+            (Some(a), Some(b)) => {
+                if !self.values_equal(field, &a, &b, &path) {
+                    out.push(Difference {
+                        kind: DifferenceKind::Modified,
+                        path: path.display,
+                        field: field.clone(),
+                        old_value: Some(a.to_owned()),
+                        new_value: Some(b.to_owned()),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Returns whether `a` and `b`, which are values of `field` at `path`,
+    /// have no differences.
+    fn values_equal(
+        &self,
+        field: &FieldDescriptor,
+        a: &ReflectValueRef<'_>,
+        b: &ReflectValueRef<'_>,
+        path: &Path,
+    ) -> bool {
+        match (a, b) {
+            (ReflectValueRef::Bool(a), ReflectValueRef::Bool(b)) => a == b,
+            (ReflectValueRef::I32(a), ReflectValueRef::I32(b)) => a == b,
+            (ReflectValueRef::I64(a), ReflectValueRef::I64(b)) => a == b,
+            (ReflectValueRef::U32(a), ReflectValueRef::U32(b)) => a == b,
+            (ReflectValueRef::U64(a), ReflectValueRef::U64(b)) => a == b,
+            (ReflectValueRef::Enum(a), ReflectValueRef::Enum(b)) => a == b,
+            (ReflectValueRef::F32(a), ReflectValueRef::F32(b)) => {
+                a.to_bits() == b.to_bits() || self.approximately_equal(*a as f64, *b as f64)
+            }
+            (ReflectValueRef::F64(a), ReflectValueRef::F64(b)) => {
+                a.to_bits() == b.to_bits() || self.approximately_equal(*a, *b)
+            }
+            (ReflectValueRef::String(a), ReflectValueRef::String(b)) => {
+                a.as_bytes() == b.as_bytes()
+            }
+            (ReflectValueRef::Bytes(a), ReflectValueRef::Bytes(b)) => **a == **b,
+            (ReflectValueRef::Message(a), ReflectValueRef::Message(b)) => {
+                let mut differences = Vec::new();
+                self.compare_messages(a, b, path, &mut differences);
+                differences.is_empty()
+            }
+            _ => unreachable!("{} has values of different types", field.full_name()),
+        }
+    }
+
+    fn approximately_equal(&self, a: f64, b: f64) -> bool {
+        let Some((fraction, margin)) = self.fraction_and_margin else {
+            return false;
+        };
+        let difference = (a - b).abs();
+        difference <= margin || difference <= fraction * a.abs().max(b.abs())
+    }
+}
+
+/// The path of a field or element, both as it is reported and as the list of
+/// field names that [`MessageDifferencer::ignore_path`] matches.
+struct Path {
+    display: String,
+    names: String,
+}
+
+impl Path {
+    /// Returns the path of `field` of the message at this path.
+    fn field(&self, field: &FieldDescriptor) -> Path {
+        let name = if field.is_extension() {
+            format!("[{}]", field.full_name())
+        } else {
+            field.name().to_string()
+        };
+        if self.names.is_empty() {
+            return Path { display: name.clone(), names: name };
+        }
+        Path {
+            display: format!("{}.{name}", self.display),
+            names: format!("{}.{name}", self.names),
+        }
+    }
+
+    /// Returns the path of the element at `index` of the field at this path,
+    /// which is a position or a map key.
+    fn element(&self, index: impl fmt::Display) -> Path {
+        Path { display: format!("{}[{index}]", self.display), names: self.names.clone() }
+    }
+}
+
+fn added(field: &FieldDescriptor, path: String, value: &ReflectValueRef<'_>) -> Difference {
+    Difference {
+        kind: DifferenceKind::Added,
+        path,
+        field: field.clone(),
+        old_value: None,
+        new_value: Some(value.to_owned()),
+    }
+}
+
+fn removed(field: &FieldDescriptor, path: String, value: &ReflectValueRef<'_>) -> Difference {
+    Difference {
+        kind: DifferenceKind::Removed,
+        path,
+        field: field.clone(),
+        old_value: Some(value.to_owned()),
+        new_value: None,
+    }
+}
+
+/// Returns the value of the field `key` of the message `element`.
+fn element_key<'msg>(
+    element: &ReflectValueRef<'msg>,
+    key: &FieldDescriptor,
+) -> ReflectValueRef<'msg> {
+    let ReflectValueRef::Message(element) = element else {
+        unreachable!("{} is a field of a message type", key.full_name())
+    };
+    element.get_field(key)
+}
+
+/// A map key in a form that sorts, so that differences are reported in a
+/// stable order.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum MapKey {
+    Bool(bool),
+    Signed(i64),
+    Unsigned(u64),
+    Bytes(Vec<u8>),
+}
+
+impl MapKey {
+    fn new(key: &ReflectValueRef<'_>) -> Self {
+        match key {
+            ReflectValueRef::Bool(v) => MapKey::Bool(*v),
+            ReflectValueRef::I32(v) | ReflectValueRef::Enum(v) => MapKey::Signed(*v as i64),
+            ReflectValueRef::I64(v) => MapKey::Signed(*v),
+            ReflectValueRef::U32(v) => MapKey::Unsigned(*v as u64),
+            ReflectValueRef::U64(v) => MapKey::Unsigned(*v),
+            ReflectValueRef::String(v) => MapKey::Bytes(v.as_bytes().to_vec()),
+            ReflectValueRef::Bytes(v) => MapKey::Bytes(v.to_vec()),
+            ReflectValueRef::F32(_) | ReflectValueRef::F64(_) | ReflectValueRef::Message(_) => {
+                unreachable!("{key:?} can't be a map key")
+            }
+        }
+    }
+}
+
+/// Displays a value of `field` like the text format does.
+struct DisplayValue<'a, 'msg>(&'a FieldDescriptor, &'a ReflectValueRef<'msg>);
+
+impl fmt::Display for DisplayValue<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_value(f, self.0, self.1)
+    }
+}
+
+fn write_value(
+    f: &mut fmt::Formatter,
+    field: &FieldDescriptor,
+    value: &ReflectValueRef<'_>,
+) -> fmt::Result {
+    match value {
+        ReflectValueRef::Bool(v) => write!(f, "{v}"),
+        ReflectValueRef::I32(v) => write!(f, "{v}"),
+        ReflectValueRef::I64(v) => write!(f, "{v}"),
+        ReflectValueRef::U32(v) => write!(f, "{v}"),
+        ReflectValueRef::U64(v) => write!(f, "{v}"),
+        ReflectValueRef::F32(v) => write!(f, "{v}"),
+        ReflectValueRef::F64(v) => write!(f, "{v}"),
+        ReflectValueRef::String(v) => write_quoted(f, v.as_bytes()),
+        ReflectValueRef::Bytes(v) => write_quoted(f, v),
+        ReflectValueRef::Enum(v) => match field.enum_type().and_then(|e| e.value_by_number(*v)) {
+            Some(value) => f.write_str(value.name()),
+            None => write!(f, "{v}"),
+        },
+        ReflectValueRef::Message(v) => {
+            let text =
+                v.to_text_format_with_options(&TextFormatPrintOptions::new().single_line(true));
+            if text.is_empty() {
+                f.write_str("{}")
+            } else {
+                write!(f, "{{ {text} }}")
+            }
+        }
+    }
+}
+
+/// Writes `bytes` as a quoted string, escaping anything that isn't printable
+/// ASCII.
+fn write_quoted(f: &mut fmt::Formatter, bytes: &[u8]) -> fmt::Result {
+    f.write_str("\"")?;
+    for &b in bytes {
+        match b {
+            b'"' => f.write_str("\\\"")?,
+            b'\\' => f.write_str("\\\\")?,
+            b'\n' => f.write_str("\\n")?,
+            b'\r' => f.write_str("\\r")?,
+            b'\t' => f.write_str("\\t")?,
+            0x20..=0x7e => write!(f, "{}", b as char)?,
+            _ => write!(f, "\\{b:03o}")?,
+        }
+    }
+    f.write_str("\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use googletest::prelude::*;
+
+    #[googletest::test]
+    fn test_map_key_order() {
+        let mut keys = vec![
+            MapKey::new(&ReflectValueRef::from("b")),
+            MapKey::new(&ReflectValueRef::from(-1i32)),
+            MapKey::new(&ReflectValueRef::from("a")),
+            MapKey::new(&ReflectValueRef::from(3i64)),
+        ];
+        keys.sort();
+        assert_that!(
+            keys,
+            elements_are![
+                eq(&MapKey::Signed(-1)),
+                eq(&MapKey::Signed(3)),
+                eq(&MapKey::Bytes(b"a".to_vec())),
+                eq(&MapKey::Bytes(b"b".to_vec()))
+            ]
+        );
+    }
+
+    #[googletest::test]
+    fn test_approximately_equal() {
+        let exact = MessageDifferencer::new();
+        assert_that!(exact.approximately_equal(1.0, 1.0 + 1e-9), eq(false));
+
+        let margin = MessageDifferencer::new().float_fraction_and_margin(0.0, 0.5);
+        assert_that!(margin.approximately_equal(1.0, 1.5), eq(true));
+        assert_that!(margin.approximately_equal(1.0, 1.6), eq(false));
+
+        let fraction = MessageDifferencer::new().float_fraction_and_margin(0.1, 0.0);
+        assert_that!(fraction.approximately_equal(100.0, 109.0), eq(true));
+        assert_that!(fraction.approximately_equal(100.0, 112.0), eq(false));
+        assert_that!(fraction.approximately_equal(f64::NAN, f64::NAN), eq(false));
+    }
+}
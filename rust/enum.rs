@@ -6,6 +6,7 @@
 // https://developers.google.com/open-source/licenses/bsd
 
 use crate::__internal::Private;
+use crate::reflect::ReflectEnum;
 use std::{
     error::Error,
     fmt::{Debug, Display},
@@ -19,7 +20,7 @@ use std::{
 ///   representation as erased enums in the runtime.
 ///   - For C++, this is `proto2::RepeatedField<c_int>`
 ///   - For UPB, this is an array compatible with `int32`
-pub unsafe trait Enum: TryFrom<i32> {
+pub unsafe trait Enum: TryFrom<i32> + ReflectEnum {
     /// The name of the enum.
     const NAME: &'static str;
 
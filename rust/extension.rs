@@ -0,0 +1,201 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Extensions: fields of a message that are declared outside of the message.
+
+use crate::__internal::runtime::{
+    InnerExtension, InnerExtensionRegistry, MutatorMessageRef, RawMessage,
+};
+use crate::__internal::Private;
+use crate::{IntoProxied, Proxied, View};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// A type that an extension can have.
+///
+/// This is implemented for the scalar types, [`ProtoString`], [`ProtoBytes`],
+/// generated enums and messages, and [`Repeated`] of any of them.
+///
+/// [`ProtoString`]: crate::ProtoString
+/// [`ProtoBytes`]: crate::ProtoBytes
+/// [`Repeated`]: crate::Repeated
+pub trait ExtensionType: Proxied + 'static {
+    /// The representation of the default value of an extension that can be
+    /// stored in a `const`.
+    #[doc(hidden)]
+    type Default: Copy + Send + Sync + 'static;
+
+    #[doc(hidden)]
+    fn default_view(_private: Private, default: Self::Default) -> View<'static, Self>;
+
+    /// # Safety
+    /// - `msg` must be a message of the type extended by `ext`.
+    /// - `msg` must be valid for `'msg`.
+    #[doc(hidden)]
+    unsafe fn get_extension<'msg>(
+        _private: Private,
+        msg: RawMessage,
+        ext: &InnerExtension,
+        default: Self::Default,
+    ) -> View<'msg, Self>;
+
+    /// # Safety
+    /// - `msg` must be a message of the type extended by `ext`.
+    #[doc(hidden)]
+    unsafe fn set_extension(
+        _private: Private,
+        msg: MutatorMessageRef<'_>,
+        ext: &InnerExtension,
+        val: impl IntoProxied<Self>,
+    );
+}
+
+/// Identifies an extension of type `T` of the message `M`.
+///
+/// A constant of this type is generated for every `extend` declaration, named
+/// after the extension in `SCREAMING_SNAKE_CASE`. It is passed to the
+/// `has_extension`, `get_extension`, `set_extension` and `clear_extension`
+/// methods of `M`, its view and its mut:
+///
+/// ```ignore
+/// msg.set_extension(&FOO_EXTENSION, 42);
+/// assert_eq!(msg.get_extension(&FOO_EXTENSION), 42);
+/// ```
+pub struct ExtensionId<M, T: ExtensionType> {
+    number: u32,
+    default: T::Default,
+    inner: InnerExtension,
+    _phantom: PhantomData<fn(M) -> T>,
+}
+
+impl<M, T: ExtensionType> ExtensionId<M, T> {
+    /// # Safety
+    /// - `inner` must access the extension of type `T` of the message `M` that
+    ///   has the field number `number` and the default value `default`.
+    #[doc(hidden)]
+    pub const unsafe fn new(
+        _private: Private,
+        number: u32,
+        default: T::Default,
+        inner: InnerExtension,
+    ) -> Self {
+        ExtensionId { number, default, inner, _phantom: PhantomData }
+    }
+
+    /// Returns the field number of the extension.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Returns the value that the extension has when it is not set.
+    pub fn default_value(&self) -> View<'static, T> {
+        T::default_view(Private, self.default)
+    }
+
+    /// # Safety
+    /// - `msg` must be a message of type `M`.
+    #[doc(hidden)]
+    pub unsafe fn has(&self, _private: Private, msg: RawMessage) -> bool {
+        // SAFETY: `msg` is extended by `self.inner`, as promised by the caller.
+        unsafe { self.inner.has(msg) }
+    }
+
+    /// # Safety
+    /// - `msg` must be a message of type `M`.
+    /// - `msg` must be valid for `'msg`.
+    #[doc(hidden)]
+    pub unsafe fn get<'msg>(&self, _private: Private, msg: RawMessage) -> View<'msg, T> {
+        // SAFETY: `msg` is extended by `self.inner`, as promised by the caller.
+        unsafe { T::get_extension(Private, msg, &self.inner, self.default) }
+    }
+
+    /// # Safety
+    /// - `msg` must be a message of type `M`.
+    #[doc(hidden)]
+    pub unsafe fn set(
+        &self,
+        _private: Private,
+        msg: MutatorMessageRef<'_>,
+        val: impl IntoProxied<T>,
+    ) {
+        // SAFETY: `msg` is extended by `self.inner`, as promised by the caller.
+        unsafe { T::set_extension(Private, msg, &self.inner, val) }
+    }
+
+    /// # Safety
+    /// - `msg` must be a mutable message of type `M`.
+    #[doc(hidden)]
+    pub unsafe fn clear(&self, _private: Private, msg: RawMessage) {
+        // SAFETY: `msg` is extended by `self.inner`, as promised by the caller.
+        unsafe { self.inner.clear(msg) }
+    }
+}
+
+impl<M, T: ExtensionType> Clone for ExtensionId<M, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M, T: ExtensionType> Copy for ExtensionId<M, T> {}
+
+impl<M, T: ExtensionType> fmt::Debug for ExtensionId<M, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtensionId").field("number", &self.number).finish_non_exhaustive()
+    }
+}
+
+/// A set of extensions that are parsed by
+/// [`Parse::parse_with_options`](crate::Parse::parse_with_options).
+///
+/// Parsing only recognizes the extensions that are passed to it with
+/// [`ParseOptions::extension_registry`](crate::ParseOptions::extension_registry);
+/// all other extensions are kept as unknown fields. This also applies to the
+/// extensions of submessages.
+///
+/// ```ignore
+/// let mut registry = ExtensionRegistry::new();
+/// registry.add(&FOO_EXTENSION);
+/// let msg = MyMessage::parse_with_options(
+///     &bytes, &ParseOptions::new().extension_registry(&registry))?;
+/// ```
+pub struct ExtensionRegistry {
+    inner: InnerExtensionRegistry,
+}
+
+impl ExtensionRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        ExtensionRegistry { inner: InnerExtensionRegistry::new() }
+    }
+
+    /// Adds `ext` to the registry. Adding an extension that is already in
+    /// the registry has no effect.
+    ///
+    /// # Panics
+    /// The C++ kernel panics if `ext` is declared in a lite file.
+    pub fn add<M, T: ExtensionType>(&mut self, ext: &ExtensionId<M, T>) {
+        self.inner.add(&ext.inner);
+    }
+
+    #[doc(hidden)]
+    pub fn inner(&self, _private: Private) -> &InnerExtensionRegistry {
+        &self.inner
+    }
+}
+
+impl Default for ExtensionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ExtensionRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtensionRegistry").finish_non_exhaustive()
+    }
+}
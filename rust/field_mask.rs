@@ -0,0 +1,453 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Utilities for `google.protobuf.FieldMask`, like C++'s and Java's
+//! `FieldMaskUtil`.
+//!
+//! A field mask is a set of paths such as `"name"` or `"address.city"`, each
+//! of which is a `.`-separated list of field names. Every name but the last
+//! must be a singular message field. A path selects its last field and
+//! everything within it, so `"address"` covers `"address.city"`.
+//!
+//! The functions here work on any generated message through reflection, and
+//! take the mask as anything that implements [`AsFieldMask`]: the generated
+//! `FieldMask` and its view, or a slice or `Vec` of strings. This covers the
+//! partial updates of [AIP-134](https://google.aip.dev/134):
+//!
+//! ```ignore
+//! field_mask::validate(&Book::descriptor(), &request.update_mask())?;
+//! field_mask::merge_with_mask(
+//!     &mut stored,
+//!     &request.book(),
+//!     &request.update_mask(),
+//!     &MergeOptions::new(),
+//! )?;
+//! ```
+
+use crate::reflect::{
+    FieldDescriptor, MessageDescriptor, MessageRef, MessageRefMut, ReflectMessage,
+    ReflectMessageMut, ReflectValueRef,
+};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A set of field paths that can be used as a field mask.
+pub trait AsFieldMask {
+    /// Returns the paths of the mask, in their original order.
+    fn to_paths(&self) -> Vec<String>;
+}
+
+impl<S: AsRef<str>> AsFieldMask for [S] {
+    fn to_paths(&self) -> Vec<String> {
+        self.iter().map(|path| path.as_ref().to_string()).collect()
+    }
+}
+
+impl<S: AsRef<str>, const N: usize> AsFieldMask for [S; N] {
+    fn to_paths(&self) -> Vec<String> {
+        self[..].to_paths()
+    }
+}
+
+impl<S: AsRef<str>> AsFieldMask for Vec<S> {
+    fn to_paths(&self) -> Vec<String> {
+        self[..].to_paths()
+    }
+}
+
+/// A path of a field mask that doesn't name a field of the message type it is
+/// used with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMaskError {
+    path: String,
+    message: String,
+}
+
+impl FieldMaskError {
+    fn new(path: &str, message: String) -> Self {
+        FieldMaskError { path: path.to_string(), message }
+    }
+
+    /// Returns the invalid path.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns a description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::error::Error for FieldMaskError {}
+
+impl fmt::Display for FieldMaskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid field mask path \"{}\": {}", self.path, self.message)
+    }
+}
+
+/// Options for [`merge_with_mask`].
+///
+/// # Examples
+/// ```ignore
+/// let options = MergeOptions::new().replace_repeated_fields(true);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    replace_message_fields: bool,
+    replace_repeated_fields: bool,
+}
+
+impl MergeOptions {
+    /// Returns the default options, which merge message fields and append to
+    /// repeated fields.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether a message field that the mask covers entirely is replaced
+    /// by the one of the source, rather than merged with it. Defaults to
+    /// false.
+    pub fn replace_message_fields(mut self, replace: bool) -> Self {
+        self.replace_message_fields = replace;
+        self
+    }
+
+    /// Returns whether message fields are replaced rather than merged.
+    pub fn is_replace_message_fields(&self) -> bool {
+        self.replace_message_fields
+    }
+
+    /// Sets whether a repeated or map field is replaced by the one of the
+    /// source, rather than having the elements of the source appended to it.
+    /// Defaults to false.
+    pub fn replace_repeated_fields(mut self, replace: bool) -> Self {
+        self.replace_repeated_fields = replace;
+        self
+    }
+
+    /// Returns whether repeated and map fields are replaced rather than
+    /// appended to.
+    pub fn is_replace_repeated_fields(&self) -> bool {
+        self.replace_repeated_fields
+    }
+}
+
+/// Checks that every path of `mask` names a field of the message type
+/// `descriptor`.
+pub fn validate(
+    descriptor: &MessageDescriptor,
+    mask: &(impl AsFieldMask + ?Sized),
+) -> Result<(), FieldMaskError> {
+    for path in mask.to_paths() {
+        resolve_path(descriptor, &path)?;
+    }
+    Ok(())
+}
+
+/// Returns the paths of `mask` sorted, without duplicates and without the
+/// paths that another path covers.
+pub fn canonicalize(mask: &(impl AsFieldMask + ?Sized)) -> Vec<String> {
+    let mut tree = PathTree::default();
+    tree.add_all(mask);
+    tree.to_paths()
+}
+
+/// Returns the canonical mask that covers the fields that either `a` or `b`
+/// covers.
+pub fn union(a: &(impl AsFieldMask + ?Sized), b: &(impl AsFieldMask + ?Sized)) -> Vec<String> {
+    let mut tree = PathTree::default();
+    tree.add_all(a);
+    tree.add_all(b);
+    tree.to_paths()
+}
+
+/// Returns the canonical mask that covers the fields that both `a` and `b`
+/// cover.
+pub fn intersection(
+    a: &(impl AsFieldMask + ?Sized),
+    b: &(impl AsFieldMask + ?Sized),
+) -> Vec<String> {
+    let mut tree = PathTree::default();
+    tree.add_all(a);
+    let mut out = PathTree::default();
+    for path in b.to_paths() {
+        tree.intersect(&path, &mut out);
+    }
+    out.to_paths()
+}
+
+/// Merges the fields of `src` that `mask` covers into `dst`.
+///
+/// A covered singular field is set to its value in `src`, or cleared if it is
+/// unset there. A covered message field is merged unless
+/// [`MergeOptions::replace_message_fields`] is set, and a covered repeated or
+/// map field is appended to unless [`MergeOptions::replace_repeated_fields`]
+/// is set. Fields that `mask` doesn't cover are left untouched.
+///
+/// Fails without modifying `dst` if `mask` isn't valid for the message type.
+///
+/// # Panics
+/// Panics if `src` is of a different type than `dst`.
+pub fn merge_with_mask<M: ReflectMessageMut, S: ReflectMessage>(
+    dst: &mut M,
+    src: &S,
+    mask: &(impl AsFieldMask + ?Sized),
+    options: &MergeOptions,
+) -> Result<(), FieldMaskError> {
+    assert!(
+        S::descriptor() == M::descriptor(),
+        "can't merge a {} into a {}",
+        S::descriptor().full_name(),
+        M::descriptor().full_name()
+    );
+    let tree = PathTree::parse(S::descriptor(), mask)?;
+    merge_tree(&tree, dst.as_message_mut(), src.as_message_ref(), options);
+    Ok(())
+}
+
+/// Clears the fields of `msg` that `mask` doesn't cover. Returns whether
+/// `msg` was modified.
+///
+/// Fails without modifying `msg` if `mask` isn't valid for the message type.
+pub fn trim<M: ReflectMessageMut>(
+    msg: &mut M,
+    mask: &(impl AsFieldMask + ?Sized),
+) -> Result<bool, FieldMaskError> {
+    let tree = PathTree::parse(M::descriptor(), mask)?;
+    Ok(trim_tree(&tree, msg.as_message_mut()))
+}
+
+/// Returns the field that `path` names, starting at the message type
+/// `descriptor`.
+fn resolve_path(
+    descriptor: &MessageDescriptor,
+    path: &str,
+) -> Result<FieldDescriptor, FieldMaskError> {
+    let mut message = descriptor.clone();
+    let mut names = path.split('.').peekable();
+    while let Some(name) = names.next() {
+        let Some(field) = message.field_by_name(name) else {
+            let message = if name.is_empty() {
+                "the path has an empty field name".to_string()
+            } else {
+                format!("{} has no field named \"{name}\"", message.full_name())
+            };
+            return Err(FieldMaskError::new(path, message));
+        };
+        if names.peek().is_none() {
+            return Ok(field);
+        }
+        match field.message_type() {
+            Some(nested) if !field.is_repeated() => message = nested,
+            _ => {
+                return Err(FieldMaskError::new(
+                    path,
+                    format!("{} is not a singular message field", field.full_name()),
+                ))
+            }
+        }
+    }
+    unreachable!("split always returns at least one name")
+}
+
+/// A field mask as a tree of field names, in which a leaf covers its field
+/// entirely.
+#[derive(Default)]
+struct PathTree {
+    children: BTreeMap<String, PathTree>,
+}
+
+impl PathTree {
+    /// Builds the tree of `mask` after checking that it is valid for the
+    /// message type `descriptor`.
+    fn parse(
+        descriptor: MessageDescriptor,
+        mask: &(impl AsFieldMask + ?Sized),
+    ) -> Result<PathTree, FieldMaskError> {
+        let paths = mask.to_paths();
+        for path in &paths {
+            resolve_path(&descriptor, path)?;
+        }
+        let mut tree = PathTree::default();
+        for path in &paths {
+            tree.add(path);
+        }
+        Ok(tree)
+    }
+
+    fn add_all(&mut self, mask: &(impl AsFieldMask + ?Sized)) {
+        for path in mask.to_paths() {
+            self.add(&path);
+        }
+    }
+
+    fn add(&mut self, path: &str) {
+        if path.is_empty() {
+            return;
+        }
+        let mut node = self;
+        for name in path.split('.') {
+            if node.children.get(name).is_some_and(|child| child.children.is_empty()) {
+                // A shorter path already covers this one.
+                return;
+            }
+            node = node.children.entry(name.to_string()).or_default();
+        }
+        // This path covers any longer ones that were added before.
+        node.children.clear();
+    }
+
+    /// Adds the part of `path` that this tree also covers to `out`.
+    fn intersect(&self, path: &str, out: &mut PathTree) {
+        if path.is_empty() {
+            return;
+        }
+        let mut node = self;
+        for name in path.split('.') {
+            match node.children.get(name) {
+                Some(child) if child.children.is_empty() => {
+                    out.add(path);
+                    return;
+                }
+                Some(child) => node = child,
+                None => return,
+            }
+        }
+        // `path` covers a subtree of this tree, whose leaves are in both.
+        let mut leaves = Vec::new();
+        node.collect_paths(path, &mut leaves);
+        for leaf in leaves {
+            out.add(&leaf);
+        }
+    }
+
+    fn to_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for (name, child) in &self.children {
+            child.collect_paths(name, &mut paths);
+        }
+        paths
+    }
+
+    fn collect_paths(&self, prefix: &str, out: &mut Vec<String>) {
+        if self.children.is_empty() {
+            out.push(prefix.to_string());
+        }
+        for (name, child) in &self.children {
+            child.collect_paths(&format!("{prefix}.{name}"), out);
+        }
+    }
+}
+
+fn merge_tree(
+    tree: &PathTree,
+    mut dst: MessageRefMut<'_>,
+    src: MessageRef<'_>,
+    options: &MergeOptions,
+) {
+    let descriptor = src.descriptor();
+    for (name, child) in &tree.children {
+        let field = descriptor.field_by_name(name).expect("the mask was validated");
+        if !child.children.is_empty() {
+            if src.has_field(&field) || dst.has_field(&field) {
+                let ReflectValueRef::Message(src) = src.get_field(&field) else {
+                    unreachable!("the mask was validated")
+                };
+                merge_tree(child, dst.mut_message(&field), src, options);
+            }
+        } else if field.is_map() {
+            let mut map = dst.mut_map(&field);
+            if options.replace_repeated_fields {
+                map.clear();
+            }
+            for (key, value) in src.get_map(&field) {
+                map.insert(key, value);
+            }
+        } else if field.is_repeated() {
+            let mut repeated = dst.mut_repeated(&field);
+            if options.replace_repeated_fields {
+                repeated.clear();
+            }
+            for value in src.get_repeated(&field).iter() {
+                repeated.push(value);
+            }
+        } else if field.message_type().is_some() {
+            if options.replace_message_fields {
+                dst.clear_field(&field);
+            }
+            if src.has_field(&field) {
+                let ReflectValueRef::Message(msg) = src.get_field(&field) else {
+                    unreachable!("{} is a message field", field.full_name())
+                };
+                dst.mut_message(&field).merge_from(msg);
+            }
+        } else if src.has_field(&field) || !field.has_presence() {
+            dst.set_field(&field, src.get_field(&field));
+        } else {
+            dst.clear_field(&field);
+        }
+    }
+}
+
+fn trim_tree(tree: &PathTree, mut msg: MessageRefMut<'_>) -> bool {
+    let mut modified = false;
+    for field in msg.descriptor().fields() {
+        match tree.children.get(field.name()) {
+            None => {
+                if msg.has_field(&field) {
+                    msg.clear_field(&field);
+                    modified = true;
+                }
+            }
+            Some(child) if !child.children.is_empty() => {
+                if msg.has_field(&field) {
+                    modified |= trim_tree(child, msg.mut_message(&field));
+                }
+            }
+            Some(_) => {}
+        }
+    }
+    modified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use googletest::prelude::*;
+
+    #[googletest::test]
+    fn test_canonicalize() {
+        assert_that!(
+            canonicalize(&["b", "a.c", "a", "b", "d.e", "d.f.g", "d.f"]),
+            elements_are![eq("a"), eq("b"), eq("d.e"), eq("d.f")]
+        );
+        assert_that!(
+            canonicalize(&["a.b", "a_b", "ab"]),
+            elements_are![eq("a.b"), eq("a_b"), eq("ab")]
+        );
+        assert_that!(canonicalize(&[""; 0]), is_empty());
+    }
+
+    #[googletest::test]
+    fn test_union() {
+        assert_that!(
+            union(&["a.b", "c"], &vec!["a.d", "c.e", "f"]),
+            elements_are![eq("a.b"), eq("a.d"), eq("c"), eq("f")]
+        );
+    }
+
+    #[googletest::test]
+    fn test_intersection() {
+        assert_that!(
+            intersection(&["a", "b.c", "d.e", "g"], &["a.x", "b", "d.f", "g"]),
+            elements_are![eq("a.x"), eq("b.c"), eq("g")]
+        );
+        assert_that!(intersection(&["a"], &["b"]), is_empty());
+    }
+}
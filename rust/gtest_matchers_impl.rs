@@ -8,10 +8,146 @@
 use googletest::description::Description;
 use googletest::matcher::{Matcher, MatcherBase, MatcherResult};
 use protobuf::__internal::MatcherEq;
+use protobuf::differencer::MessageDifferencer;
+use protobuf::reflect::{MessageDescriptor, ReflectMessage};
+use protobuf::FromTextFormat;
 
+/// Matches a message that is equal to an expected one.
+///
+/// By default, messages are compared like `==` does. The methods change how
+/// they are compared, like the C++ matchers of the same names, and can be
+/// combined:
+///
+/// ```ignore
+/// assert_that!(
+///     actual,
+///     proto_partially_eq(expected)
+///         .ignoring_fields(["metadata.revision"])
+///         .ignoring_repeated_field_ordering()
+/// );
+/// ```
 #[derive(MatcherBase)]
 pub struct MessageMatcher<T: MatcherEq> {
     expected: T,
+    /// The differencer that compares the messages, if any of the methods
+    /// were used; `==` is used otherwise.
+    differencer: Option<MessageDifferencer>,
+    /// Describes how the methods change the comparison.
+    modifiers: Vec<String>,
+}
+
+impl<T: MatcherEq> MessageMatcher<T> {
+    /// Only compares what is set in the expected message: fields,
+    /// extensions, elements and map entries that are only set in the actual
+    /// message are ignored.
+    pub fn partially(self) -> Self {
+        self.with_differencer(|differencer| differencer.partial(true), "partially".to_string())
+    }
+
+    /// Compares floating point values approximately: they are equal if they
+    /// differ by at most `margin`.
+    pub fn approximately(self, margin: f64) -> Self {
+        self.with_differencer(
+            |differencer| differencer.float_fraction_and_margin(0.0, margin),
+            format!("with floating point values within {margin}"),
+        )
+    }
+
+    /// Ignores the fields at `paths`, each of which is a `.`-separated list
+    /// of field names such as `"address.city"`. A path applies to every
+    /// element of the repeated fields that it goes through.
+    ///
+    /// # Panics
+    /// Panics if a path doesn't name a field of the message type.
+    pub fn ignoring_fields<S: AsRef<str>>(self, paths: impl IntoIterator<Item = S>) -> Self {
+        let paths: Vec<String> = paths.into_iter().map(|path| path.as_ref().to_string()).collect();
+        for path in &paths {
+            check_path(&<T as ReflectMessage>::descriptor(), path);
+        }
+        let modifier = format!("ignoring {}", paths.join(", "));
+        self.with_differencer(
+            |differencer| paths.iter().fold(differencer, |d, path| d.ignore_path(path)),
+            modifier,
+        )
+    }
+
+    /// Compares every repeated field as a set, regardless of the order of
+    /// its elements.
+    pub fn ignoring_repeated_field_ordering(self) -> Self {
+        self.with_differencer(
+            |differencer| differencer.treat_repeated_fields_as_sets(true),
+            "ignoring repeated field ordering".to_string(),
+        )
+    }
+
+    fn with_differencer(
+        mut self,
+        f: impl FnOnce(MessageDifferencer) -> MessageDifferencer,
+        modifier: String,
+    ) -> Self {
+        self.differencer = Some(f(self.differencer.take().unwrap_or_default()));
+        self.modifiers.push(modifier);
+        self
+    }
+
+    fn is_match(&self, actual: &T) -> bool {
+        match &self.differencer {
+            Some(differencer) => differencer.equals(&self.expected, actual),
+            None => actual.matches(&self.expected),
+        }
+    }
+
+    fn describe_result(&self, matcher_result: MatcherResult) -> Description {
+        let descriptor = <T as ReflectMessage>::descriptor();
+        let name = descriptor.full_name();
+        let mut description = match matcher_result {
+            MatcherResult::Match => format!("is equal to the expected {name}"),
+            MatcherResult::NoMatch => format!("is not equal to the expected {name}"),
+        };
+        for modifier in &self.modifiers {
+            description.push_str(", ");
+            description.push_str(modifier);
+        }
+        description.into()
+    }
+
+    /// Lists the fields in which `actual` differs from the expected message,
+    /// rather than printing the whole expected message.
+    fn explain_differences(&self, actual: &T) -> Description {
+        if self.is_match(actual) {
+            return format!("which {}", self.describe_result(MatcherResult::Match)).into();
+        }
+        if !actual.as_message_ref().supports_reflection() {
+            // The differencer can't look into lite messages.
+            return format!("which is not equal to {:?}", self.expected).into();
+        }
+        let differences =
+            self.differencer.clone().unwrap_or_default().compare(&self.expected, actual);
+        if differences.is_empty() {
+            // The messages only differ in what the differencer doesn't
+            // compare, such as unknown fields.
+            return format!("which is not equal to {:?}", self.expected).into();
+        }
+        let mut list = Description::new();
+        for difference in differences {
+            list = list.text(difference.to_string());
+        }
+        Description::new()
+            .text("which differs from the expected message in:")
+            .nested(list.bullet_list())
+    }
+}
+
+/// Panics unless `path` is a `.`-separated list of field names that starts at
+/// the message type `descriptor`.
+fn check_path(descriptor: &MessageDescriptor, path: &str) {
+    let mut message = Some(descriptor.clone());
+    for name in path.split('.') {
+        let field = message.and_then(|message| message.field_by_name(name)).unwrap_or_else(|| {
+            panic!("\"{path}\" is not a path of a field of {}", descriptor.full_name())
+        });
+        message = field.message_type();
+    }
 }
 
 impl<T> Matcher<&T> for MessageMatcher<T>
@@ -19,14 +155,15 @@ where
     T: MatcherEq,
 {
     fn matches(&self, actual: &T) -> MatcherResult {
-        actual.matches(&self.expected).into()
+        self.is_match(actual).into()
     }
 
     fn describe(&self, matcher_result: MatcherResult) -> Description {
-        match matcher_result {
-            MatcherResult::Match => format!("is equal to {:?}", self.expected).into(),
-            MatcherResult::NoMatch => format!("is not equal to {:?}", self.expected).into(),
-        }
+        self.describe_result(matcher_result)
+    }
+
+    fn explain_match(&self, actual: &T) -> Description {
+        self.explain_differences(actual)
     }
 }
 
@@ -35,17 +172,49 @@ where
     T: MatcherEq + Copy,
 {
     fn matches(&self, actual: T) -> MatcherResult {
-        actual.matches(&self.expected).into()
+        self.is_match(&actual).into()
     }
 
     fn describe(&self, matcher_result: MatcherResult) -> Description {
-        match matcher_result {
-            MatcherResult::Match => format!("is equal to {:?}", self.expected).into(),
-            MatcherResult::NoMatch => format!("is not equal to {:?}", self.expected).into(),
-        }
+        self.describe_result(matcher_result)
+    }
+
+    fn explain_match(&self, actual: T) -> Description {
+        self.explain_differences(&actual)
     }
 }
 
+/// Matches a message that is equal to `expected`.
 pub fn proto_eq<T: MatcherEq>(expected: T) -> MessageMatcher<T> {
-    MessageMatcher { expected }
+    MessageMatcher { expected, differencer: None, modifiers: Vec::new() }
+}
+
+/// Matches a message that has the fields that are set in `expected` set to
+/// the same values, like C++'s `Partially(EqualsProto(expected))`. See
+/// [`MessageMatcher::partially`].
+pub fn proto_partially_eq<T: MatcherEq>(expected: T) -> MessageMatcher<T> {
+    proto_eq(expected).partially()
+}
+
+/// Matches a message that is equal to `expected`, except that floating point
+/// values may differ by at most `margin`. See
+/// [`MessageMatcher::approximately`].
+pub fn proto_approx_eq<T: MatcherEq>(expected: T, margin: f64) -> MessageMatcher<T> {
+    proto_eq(expected).approximately(margin)
+}
+
+/// Matches a message that is equal to the one that `text` holds in the text
+/// format, like C++'s `EqualsProto("...")`:
+///
+/// ```ignore
+/// assert_that!(msg, proto_eq_text::<TestAllTypes>("optional_int32: 1"));
+/// ```
+///
+/// # Panics
+/// Panics if `text` can't be parsed as a `T`.
+pub fn proto_eq_text<T: MatcherEq + FromTextFormat>(text: &str) -> MessageMatcher<T> {
+    match T::parse_text_format(text) {
+        Ok(expected) => proto_eq(expected),
+        Err(e) => panic!("the expected message is invalid: {e}"),
+    }
 }
@@ -14,10 +14,12 @@ pub use paste::paste;
 
 use crate::map;
 pub use crate::r#enum::Enum;
+use crate::reflect::ReflectMessage;
 use crate::repeated;
 pub use crate::ProtoStr;
-use crate::Proxied;
+use crate::{Proxied, ReadError};
 pub use std::fmt::Debug;
+use std::io::Read;
 
 #[cfg(all(bzl, cpp_kernel))]
 #[path = "cpp.rs"]
@@ -26,6 +28,51 @@ pub mod runtime;
 #[path = "upb.rs"]
 pub mod runtime;
 
+// Used by the macros that implement the conversions of well-known types.
+pub use crate::{
+    __impl_any as impl_any, __impl_duration as impl_duration, __impl_field_mask as impl_field_mask,
+    __impl_list_value as impl_list_value, __impl_struct as impl_struct,
+    __impl_timestamp as impl_timestamp, __impl_value as impl_value,
+};
+#[cfg(feature = "chrono")]
+pub use chrono;
+#[cfg(feature = "serde")]
+pub use serde;
+#[cfg(feature = "serde_json")]
+pub use serde_json;
+#[cfg(feature = "time")]
+pub use time;
+
+/// Helpers for the conversions of well-known types.
+pub mod wkt {
+    pub use crate::well_known_types::*;
+}
+
+// Used by the generated code of every message and enum.
+pub use crate::{__impl_enum_serde as impl_enum_serde, __impl_message_serde as impl_message_serde};
+
+/// Helpers for the implementations of the `serde` traits.
+#[cfg(feature = "serde")]
+pub mod serde_support {
+    pub use crate::serde_support::*;
+}
+
+// Without the `serde` feature, the generated code implements no `serde`
+// traits.
+#[cfg(not(feature = "serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_message_serde {
+    ($msg:ident, $view:ident, $mut:ident) => {};
+}
+
+#[cfg(not(feature = "serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_enum_serde {
+    ($enum:ident) => {};
+}
+
 // TODO: Temporarily re-export these symbols which are now under
 // runtime under __internal directly since some external callers using it
 // through __internal.
@@ -47,10 +94,28 @@ pub struct Private;
 pub trait SealedInternal: Sized {}
 
 /// A trait used by the proto_eq() gtest macro.
-pub trait MatcherEq: SealedInternal + Debug {
+pub trait MatcherEq: SealedInternal + Debug + ReflectMessage {
     fn matches(&self, o: &Self) -> bool;
 }
 
+/// Reads all of `reader` into a buffer, failing if it produces more than
+/// `max_size` bytes.
+pub fn read_to_end_limited(
+    _: Private,
+    reader: impl Read,
+    max_size: usize,
+) -> Result<Vec<u8>, ReadError> {
+    let mut data = Vec::new();
+    // Read one byte past the limit to tell an input of exactly `max_size`
+    // bytes apart from a longer one.
+    let limit = u64::try_from(max_size).unwrap_or(u64::MAX).saturating_add(1);
+    reader.take(limit).read_to_end(&mut data)?;
+    if data.len() > max_size {
+        return Err(ReadError::TooLarge { max_size });
+    }
+    Ok(data)
+}
+
 /// Used by the proto! macro to get a default value for a repeated field.
 pub fn get_repeated_default_value<T: repeated::ProxiedInRepeated + Default>(
     _: Private,
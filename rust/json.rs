@@ -0,0 +1,150 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Conversion of messages to and from the canonical proto3 JSON mapping.
+//!
+//! See <https://protobuf.dev/programming-guides/proto3/#json> for the mapping,
+//! including the special representations of the well-known types such as
+//! `Timestamp`, `Duration`, `Struct` and `Any`.
+
+use crate::__internal::runtime::{json_decode, json_encode};
+use crate::__internal::Private;
+use crate::reflect::{
+    DynamicMessage, MessageDescriptor, MessageRef, MessageRefMut, ReflectMessage, ReflectMessageMut,
+};
+use crate::{JsonParseOptions, JsonPrintOptions};
+use std::fmt;
+
+/// An error that happened while printing or parsing JSON.
+///
+/// The message that describes the problem comes from the kernel, so its
+/// wording differs between kernels and should not be matched on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    parsing: bool,
+    message: String,
+}
+
+impl JsonError {
+    fn print(message: String) -> Self {
+        JsonError { parsing: false, message }
+    }
+
+    fn parse(message: String) -> Self {
+        JsonError { parsing: true, message }
+    }
+
+    /// Returns a description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.parsing {
+            write!(f, "Couldn't parse JSON into a proto: {}", self.message)
+        } else {
+            write!(f, "Couldn't print a proto as JSON: {}", self.message)
+        }
+    }
+}
+
+/// A message that can be printed as proto3 JSON. All generated messages,
+/// views and muts implement this trait, as do [`DynamicMessage`] and the
+/// type-erased [`MessageRef`] and [`MessageRefMut`].
+///
+/// Printing fails if the message holds a value that has no JSON
+/// representation, such as a `Timestamp` that is out of range, an `Any` whose
+/// type is not known, or a `string` field that is not UTF-8.
+pub trait ToJson {
+    /// Prints this message as JSON with the default options.
+    fn to_json(&self) -> Result<String, JsonError> {
+        self.to_json_with_options(&JsonPrintOptions::new())
+    }
+
+    /// Prints this message as JSON, as controlled by `options`.
+    fn to_json_with_options(&self, options: &JsonPrintOptions) -> Result<String, JsonError>;
+}
+
+impl ToJson for MessageRef<'_> {
+    fn to_json_with_options(&self, options: &JsonPrintOptions) -> Result<String, JsonError> {
+        // SAFETY: `self` is a message of the type `self.descriptor()`.
+        unsafe { json_encode(self.raw(Private), &self.descriptor(), options) }
+            .map_err(JsonError::print)
+    }
+}
+
+impl ToJson for MessageRefMut<'_> {
+    fn to_json_with_options(&self, options: &JsonPrintOptions) -> Result<String, JsonError> {
+        self.as_message_ref().to_json_with_options(options)
+    }
+}
+
+impl ToJson for DynamicMessage {
+    fn to_json_with_options(&self, options: &JsonPrintOptions) -> Result<String, JsonError> {
+        self.as_message_ref().to_json_with_options(options)
+    }
+}
+
+impl<T: ReflectMessage> ToJson for T {
+    fn to_json_with_options(&self, options: &JsonPrintOptions) -> Result<String, JsonError> {
+        self.as_message_ref().to_json_with_options(options)
+    }
+}
+
+/// A message that can be parsed from proto3 JSON. All generated messages
+/// implement this trait; [`DynamicMessage`] has inherent versions of its
+/// methods that take the type to parse.
+pub trait FromJson: Sized {
+    /// Parses a message from JSON with the default options.
+    fn from_json(json: &str) -> Result<Self, JsonError> {
+        Self::from_json_with_options(json, &JsonParseOptions::new())
+    }
+
+    /// Parses a message from JSON, as controlled by `options`.
+    fn from_json_with_options(json: &str, options: &JsonParseOptions) -> Result<Self, JsonError>;
+}
+
+impl<T: ReflectMessageMut + Default> FromJson for T {
+    fn from_json_with_options(json: &str, options: &JsonParseOptions) -> Result<Self, JsonError> {
+        let mut msg = T::default();
+        parse_into(msg.as_message_mut(), json, options).map(|()| msg)
+    }
+}
+
+impl DynamicMessage {
+    /// Parses a message of the type `descriptor` from JSON with the default
+    /// options.
+    pub fn from_json(descriptor: MessageDescriptor, json: &str) -> Result<Self, JsonError> {
+        Self::from_json_with_options(descriptor, json, &JsonParseOptions::new())
+    }
+
+    /// Parses a message of the type `descriptor` from JSON, as controlled by
+    /// `options`.
+    pub fn from_json_with_options(
+        descriptor: MessageDescriptor,
+        json: &str,
+        options: &JsonParseOptions,
+    ) -> Result<Self, JsonError> {
+        let mut msg = DynamicMessage::new(descriptor);
+        parse_into(msg.as_message_mut(), json, options).map(|()| msg)
+    }
+}
+
+/// Parses `json` into `msg`, which must be empty.
+fn parse_into(
+    mut msg: MessageRefMut<'_>,
+    json: &str,
+    options: &JsonParseOptions,
+) -> Result<(), JsonError> {
+    let descriptor = msg.descriptor();
+    // SAFETY: `msg` is an empty message of the type `descriptor`.
+    unsafe { json_decode(msg.inner(Private), &descriptor, json, options) }.map_err(JsonError::parse)
+}
@@ -0,0 +1,318 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Options that control serialization and parsing, in the wire format, in
+//! JSON and in the text format.
+
+/// Options for [`Serialize::serialize_with_options`].
+///
+/// ```ignore
+/// let bytes = msg.serialize_with_options(&SerializeOptions::new().deterministic(true))?;
+/// ```
+///
+/// [`Serialize::serialize_with_options`]: crate::Serialize::serialize_with_options
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    deterministic: bool,
+    check_required: bool,
+}
+
+impl SerializeOptions {
+    /// Returns the default options, which are the ones used by
+    /// [`Serialize::serialize`](crate::Serialize::serialize).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the output should be deterministic.
+    ///
+    /// Deterministic serialization sorts map entries by key, so that equal
+    /// messages serialize to the same bytes within a single binary. It is not
+    /// a canonical encoding: the output may differ across binaries built with
+    /// different versions of protobuf or different schemas, so it should not
+    /// be used to compare messages across processes that aren't built
+    /// together.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Returns whether the output should be deterministic.
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Sets whether serialization fails with
+    /// [`SerializeErrorKind::MissingRequired`] if a proto2 required field of
+    /// the message or of any of its submessages is unset.
+    ///
+    /// Off by default, so partially initialized messages can be serialized.
+    ///
+    /// [`SerializeErrorKind::MissingRequired`]: crate::SerializeErrorKind::MissingRequired
+    pub fn check_required(mut self, check_required: bool) -> Self {
+        self.check_required = check_required;
+        self
+    }
+
+    /// Returns whether unset required fields fail serialization.
+    pub fn is_check_required(&self) -> bool {
+        self.check_required
+    }
+}
+
+/// Options for [`Parse::parse_with_options`].
+///
+/// The defaults match [`Parse::parse`]. Stricter limits are useful when
+/// parsing untrusted input, but none of them caps the memory that parsing
+/// allocates:
+///
+/// ```ignore
+/// let options = ParseOptions::new().recursion_limit(16).max_input_len(64 << 10);
+/// let msg = MyMessage::parse_with_options(&bytes, &options)?;
+/// ```
+///
+/// [`Parse::parse_with_options`]: crate::Parse::parse_with_options
+/// [`Parse::parse`]: crate::Parse::parse
+#[derive(Debug, Clone)]
+pub struct ParseOptions<'r> {
+    pub(crate) recursion_limit: Option<u16>,
+    pub(crate) check_required: bool,
+    pub(crate) validate_utf8: bool,
+    pub(crate) extension_registry: Option<&'r crate::ExtensionRegistry>,
+    max_input_len: Option<usize>,
+}
+
+impl Default for ParseOptions<'_> {
+    fn default() -> Self {
+        ParseOptions {
+            recursion_limit: None,
+            check_required: true,
+            validate_utf8: false,
+            extension_registry: None,
+            max_input_len: None,
+        }
+    }
+}
+
+impl<'r> ParseOptions<'r> {
+    /// Returns the default options, which are the ones used by
+    /// [`Parse::parse`](crate::Parse::parse).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of levels that submessages may be nested.
+    ///
+    /// Input that nests deeper fails with
+    /// [`ParseErrorKind::MaxDepthExceeded`](crate::ParseErrorKind::MaxDepthExceeded).
+    /// Defaults to 100.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is 0.
+    pub fn recursion_limit(mut self, limit: u16) -> Self {
+        assert!(limit > 0, "the recursion limit must be at least 1");
+        self.recursion_limit = Some(limit);
+        self
+    }
+
+    /// Sets whether unset required fields make parsing fail with
+    /// [`ParseErrorKind::MissingRequired`](crate::ParseErrorKind::MissingRequired).
+    ///
+    /// Disabling the check allows parsing partial messages. Defaults to
+    /// `true`.
+    pub fn check_required(mut self, check_required: bool) -> Self {
+        self.check_required = check_required;
+        self
+    }
+
+    /// Sets whether all `string` fields are validated to be UTF-8, including
+    /// the ones that are not required to be by the schema, such as `string`
+    /// fields in proto2 files.
+    ///
+    /// Invalid UTF-8 fails with
+    /// [`ParseErrorKind::BadUtf8`](crate::ParseErrorKind::BadUtf8). The C++
+    /// kernel can only validate the fields of messages that are not generated
+    /// with `optimize_for = LITE_RUNTIME`. Defaults to `false`.
+    pub fn validate_utf8(mut self, validate_utf8: bool) -> Self {
+        self.validate_utf8 = validate_utf8;
+        self
+    }
+
+    /// Sets the maximum length of the input in bytes.
+    ///
+    /// Longer input fails with
+    /// [`ParseErrorKind::InputTooLong`](crate::ParseErrorKind::InputTooLong)
+    /// without being parsed. This is only an input-length limit, not a memory
+    /// limit: the memory that parsing allocates is not capped and can be many
+    /// times the length of the input, for example for packed repeated fields
+    /// of small varints or for many empty submessages. There is no limit by
+    /// default.
+    pub fn max_input_len(mut self, max_input_len: usize) -> Self {
+        self.max_input_len = Some(max_input_len);
+        self
+    }
+
+    /// Sets the extensions that are parsed.
+    ///
+    /// Extensions that are in the registry are parsed into their typed values;
+    /// all other extensions are kept as unknown fields. Without a registry,
+    /// the C++ kernel parses every extension that is linked into the binary
+    /// and upb parses none. The C++ kernel ignores the registry for lite
+    /// messages, which only lite extensions can extend, and always parses
+    /// their linked extensions.
+    pub fn extension_registry(mut self, registry: &'r crate::ExtensionRegistry) -> Self {
+        self.extension_registry = Some(registry);
+        self
+    }
+
+    /// Fails if `data` is longer than the maximum input length.
+    pub(crate) fn check_len(&self, data: &[u8]) -> Result<(), crate::ParseError> {
+        match self.max_input_len {
+            Some(max_input_len) if data.len() > max_input_len => Err(crate::ParseError::new(
+                crate::__internal::Private,
+                crate::ParseErrorKind::InputTooLong,
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Options for [`ToJson::to_json_with_options`].
+///
+/// ```ignore
+/// let options = JsonPrintOptions::new().preserve_proto_field_names(true);
+/// let json = msg.to_json_with_options(&options)?;
+/// ```
+///
+/// [`ToJson::to_json_with_options`]: crate::ToJson::to_json_with_options
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonPrintOptions {
+    preserve_proto_field_names: bool,
+    emit_default_values: bool,
+}
+
+impl JsonPrintOptions {
+    /// Returns the default options, which are the ones used by
+    /// [`ToJson::to_json`](crate::ToJson::to_json).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether fields are named as they are in the .proto file, such as
+    /// `foo_bar`, instead of by their lowerCamelCase JSON names, such as
+    /// `fooBar`. Defaults to `false`.
+    pub fn preserve_proto_field_names(mut self, preserve: bool) -> Self {
+        self.preserve_proto_field_names = preserve;
+        self
+    }
+
+    /// Returns whether fields are named as they are in the .proto file.
+    pub fn is_preserve_proto_field_names(&self) -> bool {
+        self.preserve_proto_field_names
+    }
+
+    /// Sets whether fields without presence are printed even if they hold
+    /// their default value, such as `0`, `""` or an empty repeated field.
+    ///
+    /// Fields with presence are only printed when they are set either way.
+    /// Defaults to `false`.
+    pub fn emit_default_values(mut self, emit: bool) -> Self {
+        self.emit_default_values = emit;
+        self
+    }
+
+    /// Returns whether fields without presence are printed even if they hold
+    /// their default value.
+    pub fn is_emit_default_values(&self) -> bool {
+        self.emit_default_values
+    }
+}
+
+/// Options for [`FromJson::from_json_with_options`].
+///
+/// ```ignore
+/// let options = JsonParseOptions::new().ignore_unknown_fields(true);
+/// let msg = MyMessage::from_json_with_options(json, &options)?;
+/// ```
+///
+/// [`FromJson::from_json_with_options`]: crate::FromJson::from_json_with_options
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonParseOptions {
+    ignore_unknown_fields: bool,
+}
+
+impl JsonParseOptions {
+    /// Returns the default options, which are the ones used by
+    /// [`FromJson::from_json`](crate::FromJson::from_json).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether JSON members that don't name a field of the message, and
+    /// enum values that are not known by name, are skipped instead of failing
+    /// the parse. Defaults to `false`.
+    pub fn ignore_unknown_fields(mut self, ignore: bool) -> Self {
+        self.ignore_unknown_fields = ignore;
+        self
+    }
+
+    /// Returns whether unknown JSON members are skipped.
+    pub fn is_ignore_unknown_fields(&self) -> bool {
+        self.ignore_unknown_fields
+    }
+}
+
+/// Options for [`ToTextFormat::to_text_format_with_options`].
+///
+/// ```ignore
+/// let options = TextFormatPrintOptions::new().single_line(true);
+/// let text = msg.to_text_format_with_options(&options);
+/// ```
+///
+/// [`ToTextFormat::to_text_format_with_options`]: crate::ToTextFormat::to_text_format_with_options
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextFormatPrintOptions {
+    single_line: bool,
+    print_unknown_fields: bool,
+}
+
+impl TextFormatPrintOptions {
+    /// Returns the default options, which are the ones used by
+    /// [`ToTextFormat::to_text_format`](crate::ToTextFormat::to_text_format).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the whole message is printed on one line, with fields
+    /// separated by spaces, instead of one field per line with submessages
+    /// indented. Defaults to `false`.
+    pub fn single_line(mut self, single_line: bool) -> Self {
+        self.single_line = single_line;
+        self
+    }
+
+    /// Returns whether the whole message is printed on one line.
+    pub fn is_single_line(&self) -> bool {
+        self.single_line
+    }
+
+    /// Sets whether unknown fields are printed, named by their field numbers.
+    ///
+    /// The output can then only be parsed by parsers that accept field
+    /// numbers, which [`FromTextFormat`](crate::FromTextFormat) does not.
+    /// Defaults to `false`.
+    pub fn print_unknown_fields(mut self, print: bool) -> Self {
+        self.print_unknown_fields = print;
+        self
+    }
+
+    /// Returns whether unknown fields are printed.
+    pub fn is_print_unknown_fields(&self) -> bool {
+        self.print_unknown_fields
+    }
+}
@@ -0,0 +1,63 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use crate::__internal::{Private, SealedInternal};
+use crate::{AsView, Message, View};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// A message parsed by [`Parse::parse_borrowed`] that may borrow from the
+/// buffer it was parsed from.
+///
+/// `string` and `bytes` fields of the message may point directly into the
+/// input buffer instead of being copied, so the message can only be read
+/// through views while the buffer is borrowed. Use
+/// [`to_owned`](ParsedView::to_owned) to get a message that no longer
+/// borrows from the buffer.
+///
+/// [`Parse::parse_borrowed`]: crate::Parse::parse_borrowed
+pub struct ParsedView<'buf, M> {
+    msg: M,
+    _phantom: PhantomData<&'buf [u8]>,
+}
+
+impl<'buf, M: Message> ParsedView<'buf, M> {
+    /// # Safety
+    /// - `msg` must not point into any memory other than its own and memory
+    ///   that is borrowed for `'buf`.
+    #[doc(hidden)]
+    pub unsafe fn new(_private: Private, msg: M) -> Self {
+        ParsedView { msg, _phantom: PhantomData }
+    }
+
+    /// Returns a view of the message.
+    pub fn as_view(&self) -> View<'_, M> {
+        self.msg.as_view()
+    }
+
+    /// Returns a copy of the message that does not borrow from the input
+    /// buffer.
+    pub fn to_owned(&self) -> M {
+        self.msg.clone()
+    }
+}
+
+impl<M> SealedInternal for ParsedView<'_, M> {}
+
+impl<M: Message> AsView for ParsedView<'_, M> {
+    type Proxied = M;
+
+    fn as_view(&self) -> View<'_, M> {
+        self.msg.as_view()
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for ParsedView<'_, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.msg, f)
+    }
+}
@@ -18,6 +18,8 @@
 
 pub use crate::{
     proto, AsMut as ProtoAsMut, AsView as ProtoAsView, Clear as ProtoClear,
-    ClearAndParse as ProtoClearAndParse, IntoMut as ProtoIntoMut, IntoView as ProtoIntoView,
-    MergeFrom as ProtoMergeFrom, Parse as ProtoParse, Serialize as ProtoSerialize,
+    ClearAndParse as ProtoClearAndParse, FromJson as ProtoFromJson,
+    FromTextFormat as ProtoFromTextFormat, IntoMut as ProtoIntoMut, IntoView as ProtoIntoView,
+    MergeFrom as ProtoMergeFrom, MergeTextFormat as ProtoMergeTextFormat, Parse as ProtoParse,
+    Serialize as ProtoSerialize, ToJson as ProtoToJson, ToTextFormat as ProtoToTextFormat,
 };
@@ -0,0 +1,1948 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Runtime reflection: descriptors of the messages, fields and enums that are
+//! defined in `.proto` files, and access to the fields of messages through
+//! those descriptors.
+//!
+//! Every generated message type implements [`ReflectMessage`], and every
+//! generated enum implements [`ReflectEnum`]:
+//!
+//! ```ignore
+//! let desc = MyMessage::descriptor();
+//! for field in desc.fields() {
+//!     println!("{} = {}", field.name(), field.number());
+//! }
+//! ```
+//!
+//! Descriptors are cheap handles that can be cloned and compared freely. The
+//! descriptors of compiled-in types live for the remainder of the program, and
+//! those of a [`DescriptorPool`] keep the pool alive.
+//!
+//! Fields can be read and written without knowing the type of the message at
+//! compile time:
+//!
+//! ```ignore
+//! let field = MyMessage::descriptor().field_by_name("count").unwrap();
+//! msg.set_field(&field, 5);
+//! assert!(matches!(msg.get_field(&field), ReflectValueRef::I32(5)));
+//! ```
+//!
+//! On the C++ kernel, messages of files compiled with
+//! `optimize_for = LITE_RUNTIME` have descriptors, but their fields can't be
+//! accessed through them: see [`MessageRef::supports_reflection`].
+//!
+//! Types that are not compiled into the binary can be loaded at runtime from a
+//! serialized `FileDescriptorSet` into a [`DescriptorPool`], and their messages
+//! handled as [`DynamicMessage`]s:
+//!
+//! ```ignore
+//! let pool = DescriptorPool::from_file_descriptor_set(&descriptor_set)?;
+//! let desc = pool.message_by_name("my.package.MyMessage").unwrap();
+//! let msg = DynamicMessage::parse(desc, &serialized)?;
+//! ```
+
+use crate::__internal::runtime::{
+    reflect_clear, reflect_clear_message, reflect_debug_string, reflect_extensions, reflect_get,
+    reflect_has, reflect_is_supported, reflect_map_get, reflect_map_insert, reflect_map_len,
+    reflect_map_remove, reflect_merge_from, reflect_mut_message, reflect_repeated_get,
+    reflect_repeated_len, reflect_repeated_mut_message, reflect_repeated_push,
+    reflect_repeated_set, reflect_serialize_into, reflect_serialize_to_vec, reflect_set,
+    InnerDescriptorPool, InnerDynamicMessage, InnerEnumDescriptor, InnerEnumValueDescriptor,
+    InnerFieldDescriptor, InnerFileDescriptor, InnerMapFieldIter, InnerMessageDescriptor,
+    InnerOneofDescriptor, MutatorMessageRef, RawMessage,
+};
+use crate::__internal::{read_to_end_limited, Private, SealedInternal};
+use crate::{
+    Clear, ClearAndParse, ParseError, ParseOptions, ProtoBytes, ProtoBytesCow, ProtoStr,
+    ProtoString, ProtoStringCow, ReadError, Serialize, SerializeError, SerializeOptions,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// A type that has a [`MessageDescriptor`]. All generated messages, views and
+/// muts implement this trait.
+///
+/// The provided methods read fields through their descriptors; they panic if
+/// `field` is not a field of this message, or if the message doesn't
+/// [support reflection](MessageRef::supports_reflection).
+pub trait ReflectMessage: SealedInternal {
+    /// Returns the descriptor of this message type.
+    fn descriptor() -> MessageDescriptor;
+
+    /// Returns a type-erased view of this message.
+    fn as_message_ref(&self) -> MessageRef<'_>;
+
+    /// See [`MessageRef::has_field`].
+    fn has_field(&self, field: &FieldDescriptor) -> bool {
+        self.as_message_ref().has_field(field)
+    }
+
+    /// See [`MessageRef::get_field`].
+    fn get_field(&self, field: &FieldDescriptor) -> ReflectValueRef<'_> {
+        self.as_message_ref().get_field(field)
+    }
+
+    /// See [`MessageRef::get_repeated`].
+    fn get_repeated(&self, field: &FieldDescriptor) -> RepeatedFieldRef<'_> {
+        self.as_message_ref().get_repeated(field)
+    }
+
+    /// See [`MessageRef::get_map`].
+    fn get_map(&self, field: &FieldDescriptor) -> MapFieldRef<'_> {
+        self.as_message_ref().get_map(field)
+    }
+}
+
+/// A message that can be modified through its descriptors. All generated
+/// messages and muts implement this trait.
+///
+/// The provided methods panic if `field` is not a field of this message, or
+/// if the message doesn't [support reflection](MessageRef::supports_reflection).
+pub trait ReflectMessageMut: ReflectMessage {
+    /// Returns a type-erased mutable view of this message.
+    fn as_message_mut(&mut self) -> MessageRefMut<'_>;
+
+    /// See [`MessageRefMut::set_field`].
+    fn set_field<'a>(&mut self, field: &FieldDescriptor, value: impl Into<ReflectValueRef<'a>>) {
+        self.as_message_mut().set_field(field, value)
+    }
+
+    /// See [`MessageRefMut::clear_field`].
+    fn clear_field(&mut self, field: &FieldDescriptor) {
+        self.as_message_mut().clear_field(field)
+    }
+
+    /// See [`MessageRefMut::mut_message`].
+    fn mut_message(&mut self, field: &FieldDescriptor) -> MessageRefMut<'_> {
+        self.as_message_mut().into_mut_message(field)
+    }
+
+    /// See [`MessageRefMut::mut_repeated`].
+    fn mut_repeated(&mut self, field: &FieldDescriptor) -> RepeatedFieldRefMut<'_> {
+        self.as_message_mut().into_mut_repeated(field)
+    }
+
+    /// See [`MessageRefMut::mut_map`].
+    fn mut_map(&mut self, field: &FieldDescriptor) -> MapFieldRefMut<'_> {
+        self.as_message_mut().into_mut_map(field)
+    }
+}
+
+/// A type that has an [`EnumDescriptor`]. All generated enums implement this
+/// trait.
+pub trait ReflectEnum: SealedInternal {
+    /// Returns the descriptor of this enum type.
+    fn descriptor() -> EnumDescriptor;
+}
+
+/// The declared type of a field.
+///
+/// The discriminants match `FieldDescriptorProto.Type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FieldType {
+    Double = 1,
+    Float = 2,
+    Int64 = 3,
+    UInt64 = 4,
+    Int32 = 5,
+    Fixed64 = 6,
+    Fixed32 = 7,
+    Bool = 8,
+    String = 9,
+    /// A delimited message field, such as a proto2 `group`.
+    Group = 10,
+    Message = 11,
+    Bytes = 12,
+    UInt32 = 13,
+    Enum = 14,
+    SFixed32 = 15,
+    SFixed64 = 16,
+    SInt32 = 17,
+    SInt64 = 18,
+}
+
+impl FieldType {
+    fn from_raw(raw: i32) -> FieldType {
+        match raw {
+            1 => FieldType::Double,
+            2 => FieldType::Float,
+            3 => FieldType::Int64,
+            4 => FieldType::UInt64,
+            5 => FieldType::Int32,
+            6 => FieldType::Fixed64,
+            7 => FieldType::Fixed32,
+            8 => FieldType::Bool,
+            9 => FieldType::String,
+            10 => FieldType::Group,
+            11 => FieldType::Message,
+            12 => FieldType::Bytes,
+            13 => FieldType::UInt32,
+            14 => FieldType::Enum,
+            15 => FieldType::SFixed32,
+            16 => FieldType::SFixed64,
+            17 => FieldType::SInt32,
+            18 => FieldType::SInt64,
+            _ => unreachable!("invalid field type {raw}"),
+        }
+    }
+}
+
+/// Implements the hidden constructor and accessor that take and return the
+/// pool of a descriptor, and equality and hashing. Descriptors are equal if
+/// they describe the same definition.
+macro_rules! impl_descriptor {
+    ($($t:ident($inner:ty)),* $(,)?) => {
+        $(
+            impl $t {
+                /// Returns the descriptor of `inner`, which belongs to the
+                /// pool that `pool` refers to.
+                #[doc(hidden)]
+                pub fn new_in(_private: Private, inner: $inner, pool: &PoolRef) -> Self {
+                    $t { inner, pool: pool.clone() }
+                }
+
+                #[doc(hidden)]
+                pub fn pool(&self, _private: Private) -> &PoolRef {
+                    &self.pool
+                }
+            }
+
+            impl PartialEq for $t {
+                fn eq(&self, other: &Self) -> bool {
+                    self.inner == other.inner
+                }
+            }
+
+            impl Eq for $t {}
+
+            impl Hash for $t {
+                fn hash<H: Hasher>(&self, state: &mut H) {
+                    self.inner.hash(state)
+                }
+            }
+        )*
+    };
+}
+
+impl_descriptor!(
+    FileDescriptor(InnerFileDescriptor),
+    MessageDescriptor(InnerMessageDescriptor),
+    FieldDescriptor(InnerFieldDescriptor),
+    OneofDescriptor(InnerOneofDescriptor),
+    EnumDescriptor(InnerEnumDescriptor),
+    EnumValueDescriptor(InnerEnumValueDescriptor),
+);
+
+/// The descriptor of a `.proto` file.
+#[derive(Clone)]
+pub struct FileDescriptor {
+    inner: InnerFileDescriptor,
+    pool: PoolRef,
+}
+
+impl FileDescriptor {
+    #[doc(hidden)]
+    pub fn new(_private: Private, inner: InnerFileDescriptor) -> Self {
+        FileDescriptor { inner, pool: PoolRef::default() }
+    }
+
+    #[doc(hidden)]
+    pub fn inner(&self, _private: Private) -> InnerFileDescriptor {
+        self.inner
+    }
+
+    /// The path of the file, relative to the root of the source tree, for
+    /// example `google/protobuf/empty.proto`.
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// The package of the file, or the empty string if it has none.
+    pub fn package(&self) -> &str {
+        self.inner.package()
+    }
+
+    /// The files that are imported by this file.
+    pub fn dependencies(&self) -> impl ExactSizeIterator<Item = FileDescriptor> {
+        let (inner, pool) = (self.inner, self.pool.clone());
+        (0..inner.dependency_count())
+            .map(move |i| FileDescriptor::new_in(Private, inner.dependency(i), &pool))
+    }
+
+    /// The top-level messages that are defined in this file.
+    pub fn messages(&self) -> impl ExactSizeIterator<Item = MessageDescriptor> {
+        let (inner, pool) = (self.inner, self.pool.clone());
+        (0..inner.message_count())
+            .map(move |i| MessageDescriptor::new_in(Private, inner.message(i), &pool))
+    }
+
+    /// The top-level enums that are defined in this file.
+    pub fn enums(&self) -> impl ExactSizeIterator<Item = EnumDescriptor> {
+        let (inner, pool) = (self.inner, self.pool.clone());
+        (0..inner.enum_count())
+            .map(move |i| EnumDescriptor::new_in(Private, inner.enum_type(i), &pool))
+    }
+}
+
+impl fmt::Debug for FileDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FileDescriptor").field(&self.name()).finish()
+    }
+}
+
+/// The descriptor of a message type.
+#[derive(Clone)]
+pub struct MessageDescriptor {
+    inner: InnerMessageDescriptor,
+    pool: PoolRef,
+}
+
+impl MessageDescriptor {
+    #[doc(hidden)]
+    pub fn new(_private: Private, inner: InnerMessageDescriptor) -> Self {
+        MessageDescriptor { inner, pool: PoolRef::default() }
+    }
+
+    #[doc(hidden)]
+    pub fn inner(&self, _private: Private) -> InnerMessageDescriptor {
+        self.inner
+    }
+
+    /// The name of the message, without its package or parent messages.
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// The fully qualified name of the message, for example
+    /// `google.protobuf.Empty`.
+    pub fn full_name(&self) -> &str {
+        self.inner.full_name()
+    }
+
+    /// The file that defines this message.
+    pub fn file(&self) -> FileDescriptor {
+        FileDescriptor::new_in(Private, self.inner.file(), &self.pool)
+    }
+
+    /// The message that this message is nested in, if any.
+    pub fn containing_type(&self) -> Option<MessageDescriptor> {
+        self.inner
+            .containing_type()
+            .map(|inner| MessageDescriptor::new_in(Private, inner, &self.pool))
+    }
+
+    /// Returns true if this is the synthesized entry type of a map field.
+    pub fn is_map_entry(&self) -> bool {
+        self.inner.is_map_entry()
+    }
+
+    /// The fields of the message, in the order they are declared.
+    pub fn fields(&self) -> impl ExactSizeIterator<Item = FieldDescriptor> {
+        let (inner, pool) = (self.inner, self.pool.clone());
+        (0..inner.field_count())
+            .map(move |i| FieldDescriptor::new_in(Private, inner.field(i), &pool))
+    }
+
+    /// Returns the field with the given number.
+    pub fn field_by_number(&self, number: u32) -> Option<FieldDescriptor> {
+        self.inner
+            .field_by_number(number)
+            .map(|inner| FieldDescriptor::new_in(Private, inner, &self.pool))
+    }
+
+    /// Returns the field with the given name, as it is spelled in the `.proto`
+    /// file.
+    pub fn field_by_name(&self, name: &str) -> Option<FieldDescriptor> {
+        self.inner
+            .field_by_name(name)
+            .map(|inner| FieldDescriptor::new_in(Private, inner, &self.pool))
+    }
+
+    /// The oneofs of the message, in the order they are declared.
+    ///
+    /// This does not include the synthetic oneofs that are generated for
+    /// proto3 `optional` fields.
+    pub fn oneofs(&self) -> impl ExactSizeIterator<Item = OneofDescriptor> {
+        let (inner, pool) = (self.inner, self.pool.clone());
+        (0..inner.oneof_count())
+            .map(move |i| OneofDescriptor::new_in(Private, inner.oneof(i), &pool))
+    }
+
+    /// The messages that are nested in this message, including map entries.
+    pub fn nested_messages(&self) -> impl ExactSizeIterator<Item = MessageDescriptor> {
+        let (inner, pool) = (self.inner, self.pool.clone());
+        (0..inner.nested_message_count())
+            .map(move |i| MessageDescriptor::new_in(Private, inner.nested_message(i), &pool))
+    }
+
+    /// The enums that are nested in this message.
+    pub fn nested_enums(&self) -> impl ExactSizeIterator<Item = EnumDescriptor> {
+        let (inner, pool) = (self.inner, self.pool.clone());
+        (0..inner.nested_enum_count())
+            .map(move |i| EnumDescriptor::new_in(Private, inner.nested_enum(i), &pool))
+    }
+}
+
+impl fmt::Debug for MessageDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MessageDescriptor").field(&self.full_name()).finish()
+    }
+}
+
+/// The descriptor of a field of a message.
+#[derive(Clone)]
+pub struct FieldDescriptor {
+    inner: InnerFieldDescriptor,
+    pool: PoolRef,
+}
+
+impl FieldDescriptor {
+    #[doc(hidden)]
+    pub fn new(_private: Private, inner: InnerFieldDescriptor) -> Self {
+        FieldDescriptor { inner, pool: PoolRef::default() }
+    }
+
+    #[doc(hidden)]
+    pub fn inner(&self, _private: Private) -> InnerFieldDescriptor {
+        self.inner
+    }
+
+    /// The name of the field, as it is spelled in the `.proto` file.
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// The fully qualified name of the field, for example
+    /// `google.protobuf.Duration.seconds`.
+    pub fn full_name(&self) -> &str {
+        self.inner.full_name()
+    }
+
+    /// The name of the field in the JSON format.
+    pub fn json_name(&self) -> &str {
+        self.inner.json_name()
+    }
+
+    /// The field number.
+    pub fn number(&self) -> u32 {
+        self.inner.number()
+    }
+
+    /// The position of the field in [`MessageDescriptor::fields`].
+    pub fn index(&self) -> usize {
+        self.inner.index()
+    }
+
+    /// The declared type of the field. For repeated and map fields, this is
+    /// the type of the elements and entries respectively.
+    pub fn field_type(&self) -> FieldType {
+        FieldType::from_raw(self.inner.field_type())
+    }
+
+    /// Returns true for repeated fields, including map fields.
+    pub fn is_repeated(&self) -> bool {
+        self.inner.is_repeated()
+    }
+
+    /// Returns true for map fields.
+    pub fn is_map(&self) -> bool {
+        self.inner.is_map()
+    }
+
+    /// Returns true if the field tracks whether it is set, in which case it
+    /// has a `has_` accessor.
+    pub fn has_presence(&self) -> bool {
+        self.inner.has_presence()
+    }
+
+    /// Returns true if the field is `required`.
+    pub fn is_required(&self) -> bool {
+        self.inner.is_required()
+    }
+
+    /// Returns true if this is an extension, in which case
+    /// [`containing_type`](Self::containing_type) is the message that it
+    /// extends.
+    pub fn is_extension(&self) -> bool {
+        self.inner.is_extension()
+    }
+
+    /// The message that this field belongs to.
+    pub fn containing_type(&self) -> MessageDescriptor {
+        MessageDescriptor::new_in(Private, self.inner.containing_type(), &self.pool)
+    }
+
+    /// The oneof that this field belongs to, if any. The synthetic oneofs of
+    /// proto3 `optional` fields are not returned.
+    pub fn containing_oneof(&self) -> Option<OneofDescriptor> {
+        self.inner
+            .containing_oneof()
+            .map(|inner| OneofDescriptor::new_in(Private, inner, &self.pool))
+    }
+
+    /// The message type of a message, group or map field.
+    pub fn message_type(&self) -> Option<MessageDescriptor> {
+        self.inner.message_type().map(|inner| MessageDescriptor::new_in(Private, inner, &self.pool))
+    }
+
+    /// The enum type of an enum field.
+    pub fn enum_type(&self) -> Option<EnumDescriptor> {
+        self.inner.enum_type().map(|inner| EnumDescriptor::new_in(Private, inner, &self.pool))
+    }
+}
+
+impl fmt::Debug for FieldDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FieldDescriptor").field(&self.full_name()).finish()
+    }
+}
+
+/// The descriptor of a oneof.
+#[derive(Clone)]
+pub struct OneofDescriptor {
+    inner: InnerOneofDescriptor,
+    pool: PoolRef,
+}
+
+impl OneofDescriptor {
+    #[doc(hidden)]
+    pub fn new(_private: Private, inner: InnerOneofDescriptor) -> Self {
+        OneofDescriptor { inner, pool: PoolRef::default() }
+    }
+
+    #[doc(hidden)]
+    pub fn inner(&self, _private: Private) -> InnerOneofDescriptor {
+        self.inner
+    }
+
+    /// The name of the oneof, as it is spelled in the `.proto` file.
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// The fully qualified name of the oneof.
+    pub fn full_name(&self) -> &str {
+        self.inner.full_name()
+    }
+
+    /// The message that this oneof belongs to.
+    pub fn containing_type(&self) -> MessageDescriptor {
+        MessageDescriptor::new_in(Private, self.inner.containing_type(), &self.pool)
+    }
+
+    /// The fields of the oneof, in the order they are declared.
+    pub fn fields(&self) -> impl ExactSizeIterator<Item = FieldDescriptor> {
+        let (inner, pool) = (self.inner, self.pool.clone());
+        (0..inner.field_count())
+            .map(move |i| FieldDescriptor::new_in(Private, inner.field(i), &pool))
+    }
+}
+
+impl fmt::Debug for OneofDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OneofDescriptor").field(&self.full_name()).finish()
+    }
+}
+
+/// The descriptor of an enum type.
+#[derive(Clone)]
+pub struct EnumDescriptor {
+    inner: InnerEnumDescriptor,
+    pool: PoolRef,
+}
+
+impl EnumDescriptor {
+    #[doc(hidden)]
+    pub fn new(_private: Private, inner: InnerEnumDescriptor) -> Self {
+        EnumDescriptor { inner, pool: PoolRef::default() }
+    }
+
+    #[doc(hidden)]
+    pub fn inner(&self, _private: Private) -> InnerEnumDescriptor {
+        self.inner
+    }
+
+    /// The name of the enum, without its package or parent messages.
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// The fully qualified name of the enum, for example
+    /// `google.protobuf.NullValue`.
+    pub fn full_name(&self) -> &str {
+        self.inner.full_name()
+    }
+
+    /// The file that defines this enum.
+    pub fn file(&self) -> FileDescriptor {
+        FileDescriptor::new_in(Private, self.inner.file(), &self.pool)
+    }
+
+    /// The message that this enum is nested in, if any.
+    pub fn containing_type(&self) -> Option<MessageDescriptor> {
+        self.inner
+            .containing_type()
+            .map(|inner| MessageDescriptor::new_in(Private, inner, &self.pool))
+    }
+
+    /// Returns true if the enum is closed, which means that unknown values
+    /// are stored as unknown fields instead of in enum fields.
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    /// The values of the enum, in the order they are declared.
+    pub fn values(&self) -> impl ExactSizeIterator<Item = EnumValueDescriptor> {
+        let (inner, pool) = (self.inner, self.pool.clone());
+        (0..inner.value_count())
+            .map(move |i| EnumValueDescriptor::new_in(Private, inner.value(i), &pool))
+    }
+
+    /// Returns the value with the given number. If several values share the
+    /// number, the first one that is declared is returned.
+    pub fn value_by_number(&self, number: i32) -> Option<EnumValueDescriptor> {
+        self.inner
+            .value_by_number(number)
+            .map(|inner| EnumValueDescriptor::new_in(Private, inner, &self.pool))
+    }
+}
+
+impl fmt::Debug for EnumDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("EnumDescriptor").field(&self.full_name()).finish()
+    }
+}
+
+/// The descriptor of a value of an enum.
+#[derive(Clone)]
+pub struct EnumValueDescriptor {
+    inner: InnerEnumValueDescriptor,
+    pool: PoolRef,
+}
+
+impl EnumValueDescriptor {
+    #[doc(hidden)]
+    pub fn new(_private: Private, inner: InnerEnumValueDescriptor) -> Self {
+        EnumValueDescriptor { inner, pool: PoolRef::default() }
+    }
+
+    /// The name of the value, as it is spelled in the `.proto` file.
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// The fully qualified name of the value. Enum values are siblings of
+    /// their enum, so this is for example `google.protobuf.NULL_VALUE`.
+    pub fn full_name(&self) -> &str {
+        self.inner.full_name()
+    }
+
+    /// The number of the value.
+    pub fn number(&self) -> i32 {
+        self.inner.number()
+    }
+
+    /// The enum that this value belongs to.
+    pub fn enum_type(&self) -> EnumDescriptor {
+        EnumDescriptor::new_in(Private, self.inner.enum_type(), &self.pool)
+    }
+}
+
+impl fmt::Debug for EnumValueDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("EnumValueDescriptor").field(&self.full_name()).finish()
+    }
+}
+
+/// A set of `.proto` files whose descriptors were built at runtime rather
+/// than compiled into the binary.
+///
+/// The pool is reference counted, so cloning it is cheap. Its descriptors and
+/// the [`DynamicMessage`]s of its types keep it alive, and it is freed once
+/// the last of them is dropped. The types that it defines are separate from
+/// any compiled-in types of the same name.
+#[derive(Clone)]
+pub struct DescriptorPool {
+    inner: InnerDescriptorPool,
+    pool: PoolRef,
+}
+
+impl DescriptorPool {
+    /// Builds a pool from a serialized `FileDescriptorSet`, such as the one
+    /// that is written by `protoc --include_imports --descriptor_set_out`.
+    ///
+    /// The set must contain every file that is imported by its files, in any
+    /// order.
+    pub fn from_file_descriptor_set(serialized: &[u8]) -> Result<Self, DescriptorPoolError> {
+        let files = parse_file_descriptor_set(serialized)?;
+        let order = build_order(&files)?;
+        let inner = InnerDescriptorPool::new();
+        // Frees the pool if a file fails to build.
+        let pool = PoolRef(Some(Arc::new(OwnedPool(inner))));
+        for file in order.into_iter().map(|i| &files[i]) {
+            // SAFETY: the imports of `file` come before it in `order`, and the
+            // pool isn't shared until it is returned.
+            if let Err(message) = unsafe { inner.build_file(file.name, file.serialized) } {
+                return Err(DescriptorPoolError::new(Some(file.name), message));
+            }
+        }
+        Ok(DescriptorPool { inner, pool })
+    }
+
+    /// Returns the file called `name`, if it is in the pool.
+    pub fn file_by_name(&self, name: &str) -> Option<FileDescriptor> {
+        self.inner.find_file(name).map(|inner| FileDescriptor::new_in(Private, inner, &self.pool))
+    }
+
+    /// Returns the message type called `full_name`, for example
+    /// `my.package.MyMessage`, if it is defined by a file in the pool.
+    pub fn message_by_name(&self, full_name: &str) -> Option<MessageDescriptor> {
+        self.inner
+            .find_message(full_name)
+            .map(|inner| MessageDescriptor::new_in(Private, inner, &self.pool))
+    }
+
+    /// Returns the enum type called `full_name`, if it is defined by a file in
+    /// the pool.
+    pub fn enum_by_name(&self, full_name: &str) -> Option<EnumDescriptor> {
+        self.inner
+            .find_enum(full_name)
+            .map(|inner| EnumDescriptor::new_in(Private, inner, &self.pool))
+    }
+}
+
+impl fmt::Debug for DescriptorPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DescriptorPool").finish_non_exhaustive()
+    }
+}
+
+/// Keeps the [`DescriptorPool`] that a descriptor belongs to alive. It is
+/// empty for the descriptors of compiled-in types, which are never freed.
+#[doc(hidden)]
+#[derive(Clone, Default)]
+pub struct PoolRef(Option<Arc<OwnedPool>>);
+
+impl PoolRef {
+    /// The pool that was built at runtime, if there is one.
+    pub fn inner(&self) -> Option<InnerDescriptorPool> {
+        self.0.as_ref().map(|owned| owned.0)
+    }
+}
+
+/// Frees its pool when it is dropped.
+struct OwnedPool(InnerDescriptorPool);
+
+impl Drop for OwnedPool {
+    fn drop(&mut self) {
+        // SAFETY: every descriptor of the pool holds a `PoolRef` to it, so none
+        // of them are left.
+        unsafe { self.0.free() }
+    }
+}
+
+/// An error that prevented a [`DescriptorPool`] from being built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptorPoolError {
+    file: Option<String>,
+    message: String,
+}
+
+impl DescriptorPoolError {
+    fn new(file: Option<&str>, message: impl Into<String>) -> Self {
+        DescriptorPoolError { file: file.map(String::from), message: message.into() }
+    }
+
+    /// Returns the name of the file that could not be built, if the failure
+    /// is specific to one file.
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// Returns a description of the failure.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::error::Error for DescriptorPoolError {}
+
+impl fmt::Display for DescriptorPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Couldn't build descriptor pool: {}", self.message)?;
+        if let Some(file) = &self.file {
+            write!(f, " (file `{file}`)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A file of a `FileDescriptorSet` that has not been built yet.
+struct UnbuiltFile<'a> {
+    name: &'a str,
+    dependencies: Vec<&'a str>,
+    serialized: &'a [u8],
+}
+
+/// Calls `f` with the number and contents of every length-delimited field of
+/// the serialized message `data`, skipping fields of other wire types. Returns
+/// `None` if `data` is malformed.
+///
+/// This is just enough of a parser to find the files of a `FileDescriptorSet`
+/// and their imports, which the kernels need before they can build anything.
+fn for_each_delimited_field<'a>(
+    mut data: &'a [u8],
+    mut f: impl FnMut(u32, &'a [u8]),
+) -> Option<()> {
+    fn read_varint(data: &mut &[u8]) -> Option<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = data.split_first()?;
+            *data = rest;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    while !data.is_empty() {
+        let tag = read_varint(&mut data)?;
+        let number = u32::try_from(tag >> 3).ok()?;
+        match tag & 7 {
+            0 => {
+                read_varint(&mut data)?;
+            }
+            1 => data = data.get(8..)?,
+            2 => {
+                let len = usize::try_from(read_varint(&mut data)?).ok()?;
+                f(number, data.get(..len)?);
+                data = &data[len..];
+            }
+            5 => data = data.get(4..)?,
+            // descriptor.proto has no groups.
+            _ => return None,
+        }
+    }
+    Some(())
+}
+
+fn parse_file_descriptor_set(data: &[u8]) -> Result<Vec<UnbuiltFile<'_>>, DescriptorPoolError> {
+    let malformed = || DescriptorPoolError::new(None, "the FileDescriptorSet is malformed");
+    let as_str = |s| std::str::from_utf8(s).map_err(|_| malformed());
+    let mut serialized_files = Vec::new();
+    // FileDescriptorSet.file
+    for_each_delimited_field(data, |number, value| {
+        if number == 1 {
+            serialized_files.push(value);
+        }
+    })
+    .ok_or_else(malformed)?;
+
+    serialized_files
+        .into_iter()
+        .map(|serialized| {
+            let (mut name, mut dependencies) = (None, Vec::new());
+            // FileDescriptorProto.name and FileDescriptorProto.dependency
+            for_each_delimited_field(serialized, |number, value| match number {
+                1 => name = Some(value),
+                3 => dependencies.push(value),
+                _ => {}
+            })
+            .ok_or_else(malformed)?;
+            let name = name.ok_or_else(|| {
+                DescriptorPoolError::new(None, "a file in the FileDescriptorSet has no name")
+            })?;
+            Ok(UnbuiltFile {
+                name: as_str(name)?,
+                dependencies: dependencies.into_iter().map(as_str).collect::<Result<_, _>>()?,
+                serialized,
+            })
+        })
+        .collect()
+}
+
+/// Returns the indices of `files` in an order in which every file comes after
+/// the files that it imports.
+fn build_order(files: &[UnbuiltFile<'_>]) -> Result<Vec<usize>, DescriptorPoolError> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unvisited,
+        Visiting,
+        Done,
+    }
+
+    fn visit(
+        i: usize,
+        files: &[UnbuiltFile<'_>],
+        by_name: &HashMap<&str, usize>,
+        states: &mut [State],
+        order: &mut Vec<usize>,
+    ) -> Result<(), DescriptorPoolError> {
+        let file = &files[i];
+        match states[i] {
+            State::Done => return Ok(()),
+            State::Visiting => {
+                return Err(DescriptorPoolError::new(Some(file.name), "the file imports itself"))
+            }
+            State::Unvisited => {}
+        }
+        states[i] = State::Visiting;
+        for dep in &file.dependencies {
+            let Some(&j) = by_name.get(dep) else {
+                let message = format!("the file imports `{dep}`, which is not in the set");
+                return Err(DescriptorPoolError::new(Some(file.name), message));
+            };
+            visit(j, files, by_name, states, order)?;
+        }
+        states[i] = State::Done;
+        order.push(i);
+        Ok(())
+    }
+
+    let mut by_name = HashMap::new();
+    for (i, file) in files.iter().enumerate() {
+        if by_name.insert(file.name, i).is_some() {
+            let message = "the file appears more than once in the set";
+            return Err(DescriptorPoolError::new(Some(file.name), message));
+        }
+    }
+    let mut states = vec![State::Unvisited; files.len()];
+    let mut order = Vec::with_capacity(files.len());
+    for i in 0..files.len() {
+        visit(i, files, &by_name, &mut states, &mut order)?;
+    }
+    Ok(order)
+}
+
+/// The value of a singular field, or of an element of a repeated field or
+/// map, that is borrowed from a message.
+///
+/// Integer fields use the variant that matches their Rust type: for example
+/// `int32`, `sint32` and `sfixed32` fields all hold an [`I32`](Self::I32).
+#[derive(Debug)]
+pub enum ReflectValueRef<'msg> {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    /// The value of a `string` field. This is only owned if the value is
+    /// stored in a form that can't be borrowed, such as a C++ `absl::Cord`.
+    String(ProtoStringCow<'msg>),
+    /// The value of a `bytes` field. This is only owned if the value is
+    /// stored in a form that can't be borrowed, such as a C++ `absl::Cord`.
+    Bytes(ProtoBytesCow<'msg>),
+    /// The number of an enum value. Open enums can hold unknown numbers.
+    Enum(i32),
+    Message(MessageRef<'msg>),
+}
+
+impl ReflectValueRef<'_> {
+    /// Copies the value out of the message that it is borrowed from.
+    pub fn to_owned(&self) -> ReflectValue {
+        match self {
+            ReflectValueRef::Bool(v) => ReflectValue::Bool(*v),
+            ReflectValueRef::I32(v) => ReflectValue::I32(*v),
+            ReflectValueRef::I64(v) => ReflectValue::I64(*v),
+            ReflectValueRef::U32(v) => ReflectValue::U32(*v),
+            ReflectValueRef::U64(v) => ReflectValue::U64(*v),
+            ReflectValueRef::F32(v) => ReflectValue::F32(*v),
+            ReflectValueRef::F64(v) => ReflectValue::F64(*v),
+            ReflectValueRef::String(v) => ReflectValue::String(ProtoString::from(v.as_bytes())),
+            ReflectValueRef::Bytes(v) => ReflectValue::Bytes(ProtoBytes::from(&**v)),
+            ReflectValueRef::Enum(v) => ReflectValue::Enum(*v),
+            ReflectValueRef::Message(v) => ReflectValue::Message(v.to_owned()),
+        }
+    }
+
+    /// Returns true if this is the default value of a field without presence,
+    /// which is not serialized.
+    fn is_zero(&self) -> bool {
+        match self {
+            ReflectValueRef::Bool(v) => !v,
+            ReflectValueRef::I32(v) | ReflectValueRef::Enum(v) => *v == 0,
+            ReflectValueRef::I64(v) => *v == 0,
+            ReflectValueRef::U32(v) => *v == 0,
+            ReflectValueRef::U64(v) => *v == 0,
+            // -0.0 is not the default value.
+            ReflectValueRef::F32(v) => v.to_bits() == 0,
+            ReflectValueRef::F64(v) => v.to_bits() == 0,
+            ReflectValueRef::String(v) => v.is_empty(),
+            ReflectValueRef::Bytes(v) => v.is_empty(),
+            ReflectValueRef::Message(_) => false,
+        }
+    }
+}
+
+macro_rules! impl_from_for_reflect_value {
+    ($($t:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$t> for ReflectValueRef<'_> {
+                fn from(v: $t) -> Self {
+                    ReflectValueRef::$variant(v)
+                }
+            }
+
+            impl From<$t> for ReflectValue {
+                fn from(v: $t) -> Self {
+                    ReflectValue::$variant(v)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_for_reflect_value!(
+    bool => Bool,
+    i32 => I32,
+    i64 => I64,
+    u32 => U32,
+    u64 => U64,
+    f32 => F32,
+    f64 => F64,
+);
+
+impl<'a> From<&'a str> for ReflectValueRef<'a> {
+    fn from(v: &'a str) -> Self {
+        ReflectValueRef::String(ProtoStringCow::Borrowed(ProtoStr::from_str(v)))
+    }
+}
+
+impl<'a> From<&'a ProtoStr> for ReflectValueRef<'a> {
+    fn from(v: &'a ProtoStr) -> Self {
+        ReflectValueRef::String(ProtoStringCow::Borrowed(v))
+    }
+}
+
+impl<'a> From<&'a [u8]> for ReflectValueRef<'a> {
+    fn from(v: &'a [u8]) -> Self {
+        ReflectValueRef::Bytes(ProtoBytesCow::Borrowed(v))
+    }
+}
+
+impl<'a> From<MessageRef<'a>> for ReflectValueRef<'a> {
+    fn from(v: MessageRef<'a>) -> Self {
+        ReflectValueRef::Message(v)
+    }
+}
+
+impl<'a> From<&'a ReflectValue> for ReflectValueRef<'a> {
+    fn from(v: &'a ReflectValue) -> Self {
+        v.as_value_ref()
+    }
+}
+
+/// An owned value of a field, which can be stored independently of any
+/// message.
+#[derive(Debug)]
+pub enum ReflectValue {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(ProtoString),
+    Bytes(ProtoBytes),
+    /// The number of an enum value. Open enums can hold unknown numbers.
+    Enum(i32),
+    Message(DynamicMessage),
+}
+
+impl ReflectValue {
+    /// Borrows the value, for example to pass it to
+    /// [`set_field`](MessageRefMut::set_field).
+    pub fn as_value_ref(&self) -> ReflectValueRef<'_> {
+        match self {
+            ReflectValue::Bool(v) => ReflectValueRef::Bool(*v),
+            ReflectValue::I32(v) => ReflectValueRef::I32(*v),
+            ReflectValue::I64(v) => ReflectValueRef::I64(*v),
+            ReflectValue::U32(v) => ReflectValueRef::U32(*v),
+            ReflectValue::U64(v) => ReflectValueRef::U64(*v),
+            ReflectValue::F32(v) => ReflectValueRef::F32(*v),
+            ReflectValue::F64(v) => ReflectValueRef::F64(*v),
+            ReflectValue::String(v) => {
+                ReflectValueRef::String(ProtoStringCow::Borrowed(v.as_view()))
+            }
+            ReflectValue::Bytes(v) => ReflectValueRef::Bytes(ProtoBytesCow::Borrowed(v.as_ref())),
+            ReflectValue::Enum(v) => ReflectValueRef::Enum(*v),
+            ReflectValue::Message(v) => ReflectValueRef::Message(v.as_message_ref()),
+        }
+    }
+}
+
+impl Clone for ReflectValue {
+    fn clone(&self) -> Self {
+        self.as_value_ref().to_owned()
+    }
+}
+
+impl From<&str> for ReflectValue {
+    fn from(v: &str) -> Self {
+        ReflectValue::String(ProtoString::from(v))
+    }
+}
+
+impl From<ProtoString> for ReflectValue {
+    fn from(v: ProtoString) -> Self {
+        ReflectValue::String(v)
+    }
+}
+
+impl From<&[u8]> for ReflectValue {
+    fn from(v: &[u8]) -> Self {
+        ReflectValue::Bytes(ProtoBytes::from(v))
+    }
+}
+
+impl From<ProtoBytes> for ReflectValue {
+    fn from(v: ProtoBytes) -> Self {
+        ReflectValue::Bytes(v)
+    }
+}
+
+impl From<DynamicMessage> for ReflectValue {
+    fn from(v: DynamicMessage) -> Self {
+        ReflectValue::Message(v)
+    }
+}
+
+/// Panics unless `field` is a field of `message` with the given shape.
+fn check_field(message: &MessageDescriptor, field: &FieldDescriptor, shape: FieldShape) {
+    assert!(
+        field.containing_type() == *message,
+        "{} is not a field of {}",
+        field.full_name(),
+        message.full_name()
+    );
+    let actual = if field.is_map() {
+        FieldShape::Map
+    } else if field.is_repeated() {
+        FieldShape::Repeated
+    } else {
+        FieldShape::Singular
+    };
+    assert!(actual == shape, "{} is a {actual} field, not a {shape} field", field.full_name());
+}
+
+/// Panics if `field` is an extension, which reflection can read but not
+/// modify.
+fn check_not_extension(field: &FieldDescriptor) {
+    assert!(
+        !field.is_extension(),
+        "{} is an extension, which can't be modified",
+        field.full_name()
+    );
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FieldShape {
+    Singular,
+    Repeated,
+    Map,
+}
+
+impl fmt::Display for FieldShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FieldShape::Singular => "singular",
+            FieldShape::Repeated => "repeated",
+            FieldShape::Map => "map",
+        })
+    }
+}
+
+/// Panics unless `value` can be stored in `field`, or in an element of it if
+/// it is repeated.
+fn check_value(field: &FieldDescriptor, value: &ReflectValueRef<'_>) {
+    let matches = match (field.field_type(), value) {
+        (FieldType::Bool, ReflectValueRef::Bool(_))
+        | (FieldType::Int32 | FieldType::SInt32 | FieldType::SFixed32, ReflectValueRef::I32(_))
+        | (FieldType::Int64 | FieldType::SInt64 | FieldType::SFixed64, ReflectValueRef::I64(_))
+        | (FieldType::UInt32 | FieldType::Fixed32, ReflectValueRef::U32(_))
+        | (FieldType::UInt64 | FieldType::Fixed64, ReflectValueRef::U64(_))
+        | (FieldType::Float, ReflectValueRef::F32(_))
+        | (FieldType::Double, ReflectValueRef::F64(_))
+        | (FieldType::String, ReflectValueRef::String(_))
+        | (FieldType::Bytes, ReflectValueRef::Bytes(_)) => true,
+        (FieldType::Enum, ReflectValueRef::Enum(number)) => {
+            let enum_type = field.enum_type().unwrap();
+            assert!(
+                !enum_type.is_closed() || enum_type.value_by_number(*number).is_some(),
+                "{number} is not a value of the closed enum {}",
+                enum_type.full_name()
+            );
+            true
+        }
+        (FieldType::Message | FieldType::Group, ReflectValueRef::Message(message)) => {
+            Some(message.descriptor()) == field.message_type()
+        }
+        _ => false,
+    };
+    assert!(
+        matches,
+        "{value:?} can't be stored in {} of type {:?}",
+        field.full_name(),
+        field.field_type()
+    );
+}
+
+/// Returns the key and value fields of the entries of the map field `field`.
+fn map_entry_fields(field: &FieldDescriptor) -> (FieldDescriptor, FieldDescriptor) {
+    let entry = field.message_type().unwrap();
+    (entry.field_by_number(1).unwrap(), entry.field_by_number(2).unwrap())
+}
+
+/// A type-erased view of a message, whose fields are accessed through their
+/// [`FieldDescriptor`]s.
+///
+/// All methods that take a field panic if it is not a field of this message,
+/// or if it is of a different shape (singular, repeated or map) than the
+/// method expects. They also panic if the message doesn't
+/// [support reflection](Self::supports_reflection).
+///
+/// Extensions of the message can be read like its fields, and the ones that
+/// are set are listed by [`extensions`](Self::extensions).
+#[derive(Clone)]
+pub struct MessageRef<'msg> {
+    descriptor: MessageDescriptor,
+    raw: RawMessage,
+    _phantom: PhantomData<&'msg ()>,
+}
+
+// SAFETY: a `MessageRef` is a shared borrow of a message, like a generated
+// view.
+unsafe impl Send for MessageRef<'_> {}
+unsafe impl Sync for MessageRef<'_> {}
+
+impl<'msg> MessageRef<'msg> {
+    /// # Safety
+    /// - `raw` must be a message of the type `descriptor` that outlives `'msg`
+    ///   and is not mutated during it.
+    #[doc(hidden)]
+    pub unsafe fn new(_private: Private, descriptor: MessageDescriptor, raw: RawMessage) -> Self {
+        MessageRef { descriptor, raw, _phantom: PhantomData }
+    }
+
+    #[doc(hidden)]
+    pub fn raw(&self, _private: Private) -> RawMessage {
+        self.raw
+    }
+
+    /// The descriptor of the type of this message.
+    pub fn descriptor(&self) -> MessageDescriptor {
+        self.descriptor.clone()
+    }
+
+    /// Returns whether the fields of this message can be accessed through
+    /// reflection.
+    ///
+    /// This is false for the messages of files compiled with
+    /// `optimize_for = LITE_RUNTIME` on the C++ kernel, which have a
+    /// descriptor but no C++ `Reflection`. Every method that accesses a field
+    /// or lists extensions panics for them.
+    pub fn supports_reflection(&self) -> bool {
+        // SAFETY: `self.raw` is a valid message.
+        unsafe { reflect_is_supported(self.raw) }
+    }
+
+    /// Panics unless this message supports reflection.
+    fn check_reflection(&self) {
+        assert!(
+            self.supports_reflection(),
+            "{} is a lite message, which doesn't support reflection",
+            self.descriptor.full_name()
+        );
+    }
+
+    /// Returns true if `field` is set.
+    ///
+    /// Fields without presence are set if they don't hold their default
+    /// value, and repeated and map fields are set if they aren't empty.
+    pub fn has_field(&self, field: &FieldDescriptor) -> bool {
+        self.check_reflection();
+        if field.is_map() {
+            return !self.get_map(field).is_empty();
+        }
+        if field.is_repeated() {
+            return !self.get_repeated(field).is_empty();
+        }
+        check_field(&self.descriptor, field, FieldShape::Singular);
+        if field.has_presence() {
+            // SAFETY: `field` is a singular field with presence of this message.
+            unsafe { reflect_has(self.raw, field) }
+        } else {
+            !self.get_field(field).is_zero()
+        }
+    }
+
+    /// Returns the value of the singular field `field`, or its default value
+    /// if it is unset.
+    pub fn get_field(&self, field: &FieldDescriptor) -> ReflectValueRef<'msg> {
+        self.check_reflection();
+        check_field(&self.descriptor, field, FieldShape::Singular);
+        // SAFETY: `field` is a singular field of this message.
+        unsafe { reflect_get(self.raw, field) }
+    }
+
+    /// Returns a view of the repeated field `field`.
+    pub fn get_repeated(&self, field: &FieldDescriptor) -> RepeatedFieldRef<'msg> {
+        self.check_reflection();
+        check_field(&self.descriptor, field, FieldShape::Repeated);
+        RepeatedFieldRef { msg: self.clone(), field: field.clone() }
+    }
+
+    /// Returns a view of the map field `field`.
+    pub fn get_map(&self, field: &FieldDescriptor) -> MapFieldRef<'msg> {
+        self.check_reflection();
+        check_field(&self.descriptor, field, FieldShape::Map);
+        MapFieldRef { msg: self.clone(), field: field.clone() }
+    }
+
+    /// Returns the extensions that are set in this message, in order of field
+    /// number. Only extensions that are in the pool of the descriptor of the
+    /// message are returned; others are kept as unknown fields.
+    pub fn extensions(&self) -> Vec<FieldDescriptor> {
+        self.check_reflection();
+        // SAFETY: `self.raw` is a message of the type `self.descriptor`.
+        unsafe { reflect_extensions(self.raw, &self.descriptor) }
+    }
+
+    /// Copies this message into a new [`DynamicMessage`].
+    pub fn to_owned(&self) -> DynamicMessage {
+        self.check_reflection();
+        // SAFETY: `self.raw` is a message of the type `self.descriptor`.
+        let inner = unsafe { InnerDynamicMessage::copy_of(&self.descriptor, self.raw) };
+        DynamicMessage { inner, descriptor: self.descriptor.clone() }
+    }
+}
+
+impl fmt::Debug for MessageRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // SAFETY: `self.raw` is a message of the type `self.descriptor`.
+        unsafe { reflect_debug_string(self.raw, &self.descriptor, f) }
+    }
+}
+
+/// A type-erased mutable view of a message, whose fields are accessed
+/// through their [`FieldDescriptor`]s.
+///
+/// All methods that take a field panic if it is not a field of this message,
+/// or if it is of a different shape (singular, repeated or map) than the
+/// method expects. They also panic if the message doesn't
+/// [support reflection](MessageRef::supports_reflection). Extensions can be
+/// read, but the methods that modify a field panic if it is an extension.
+pub struct MessageRefMut<'msg> {
+    descriptor: MessageDescriptor,
+    inner: MutatorMessageRef<'msg>,
+}
+
+impl<'msg> MessageRefMut<'msg> {
+    /// # Safety
+    /// - `inner` must be a message of the type `descriptor`.
+    #[doc(hidden)]
+    pub unsafe fn new(
+        _private: Private,
+        descriptor: MessageDescriptor,
+        inner: MutatorMessageRef<'msg>,
+    ) -> Self {
+        MessageRefMut { descriptor, inner }
+    }
+
+    #[doc(hidden)]
+    pub fn inner(&mut self, _private: Private) -> MutatorMessageRef<'_> {
+        self.inner
+    }
+
+    /// The descriptor of the type of this message.
+    pub fn descriptor(&self) -> MessageDescriptor {
+        self.descriptor.clone()
+    }
+
+    /// Returns a view of this message.
+    pub fn as_message_ref(&self) -> MessageRef<'_> {
+        // SAFETY: `self.inner` is a message of the type `self.descriptor`, and
+        // is borrowed immutably for the lifetime of the returned view.
+        unsafe { MessageRef::new(Private, self.descriptor.clone(), self.inner.msg()) }
+    }
+
+    /// See [`MessageRef::supports_reflection`].
+    pub fn supports_reflection(&self) -> bool {
+        self.as_message_ref().supports_reflection()
+    }
+
+    /// Reborrows this mutable view.
+    pub fn as_message_mut(&mut self) -> MessageRefMut<'_> {
+        MessageRefMut { descriptor: self.descriptor.clone(), inner: self.inner }
+    }
+
+    /// See [`MessageRef::has_field`].
+    pub fn has_field(&self, field: &FieldDescriptor) -> bool {
+        self.as_message_ref().has_field(field)
+    }
+
+    /// See [`MessageRef::get_field`].
+    pub fn get_field(&self, field: &FieldDescriptor) -> ReflectValueRef<'_> {
+        self.as_message_ref().get_field(field)
+    }
+
+    /// See [`MessageRef::get_repeated`].
+    pub fn get_repeated(&self, field: &FieldDescriptor) -> RepeatedFieldRef<'_> {
+        self.as_message_ref().get_repeated(field)
+    }
+
+    /// See [`MessageRef::get_map`].
+    pub fn get_map(&self, field: &FieldDescriptor) -> MapFieldRef<'_> {
+        self.as_message_ref().get_map(field)
+    }
+
+    /// See [`MessageRef::extensions`].
+    pub fn extensions(&self) -> Vec<FieldDescriptor> {
+        self.as_message_ref().extensions()
+    }
+
+    /// Sets the singular field `field` to `value`, which is copied into the
+    /// message. Setting a field of a oneof clears the other fields of the
+    /// oneof.
+    ///
+    /// # Panics
+    /// Panics if `field` is an extension, if `value` is not of the type of
+    /// `field`, or if it is not a value of the closed enum of `field`.
+    pub fn set_field<'a>(
+        &mut self,
+        field: &FieldDescriptor,
+        value: impl Into<ReflectValueRef<'a>>,
+    ) {
+        let value = value.into();
+        self.as_message_ref().check_reflection();
+        check_field(&self.descriptor, field, FieldShape::Singular);
+        check_not_extension(field);
+        check_value(field, &value);
+        // SAFETY: `field` is a singular field of this message, and `value` is of
+        // its type.
+        unsafe { reflect_set(self.inner, field, &value) }
+    }
+
+    /// Clears `field`, which may be of any shape.
+    ///
+    /// # Panics
+    /// Panics if `field` is an extension.
+    pub fn clear_field(&mut self, field: &FieldDescriptor) {
+        self.as_message_ref().check_reflection();
+        assert!(
+            field.containing_type() == self.descriptor,
+            "{} is not a field of {}",
+            field.full_name(),
+            self.descriptor.full_name()
+        );
+        check_not_extension(field);
+        // SAFETY: `field` is a field of this message.
+        unsafe { reflect_clear(self.inner, field) }
+    }
+
+    /// Clears all of the fields of this message.
+    pub fn clear(&mut self) {
+        // SAFETY: `self.inner` is a message of the type `self.descriptor`.
+        unsafe { reflect_clear_message(self.inner, &self.descriptor) }
+    }
+
+    /// Merges the fields of `src` into this message, like the `merge_from` of
+    /// generated messages.
+    ///
+    /// # Panics
+    /// Panics if `src` is of a different type than this message.
+    pub fn merge_from(&mut self, src: MessageRef<'_>) {
+        assert!(
+            src.descriptor() == self.descriptor,
+            "can't merge a {} into a {}",
+            src.descriptor().full_name(),
+            self.descriptor.full_name()
+        );
+        // SAFETY: both messages are of the type `self.descriptor`.
+        unsafe { reflect_merge_from(self.inner, &self.descriptor, src.raw(Private)) }
+    }
+
+    /// Returns a mutable view of the singular message field `field`, which is
+    /// set if it isn't already.
+    ///
+    /// # Panics
+    /// Panics if `field` is an extension.
+    pub fn mut_message(&mut self, field: &FieldDescriptor) -> MessageRefMut<'_> {
+        self.as_message_mut().into_mut_message(field)
+    }
+
+    /// Like [`mut_message`](Self::mut_message), but consumes `self`.
+    pub fn into_mut_message(self, field: &FieldDescriptor) -> MessageRefMut<'msg> {
+        self.as_message_ref().check_reflection();
+        check_field(&self.descriptor, field, FieldShape::Singular);
+        check_not_extension(field);
+        let descriptor = field
+            .message_type()
+            .unwrap_or_else(|| panic!("{} is not a message field", field.full_name()));
+        // SAFETY: `field` is a singular message field of this message.
+        let inner = unsafe { reflect_mut_message(self.inner, field) };
+        MessageRefMut { descriptor, inner }
+    }
+
+    /// Returns a mutable view of the repeated field `field`.
+    ///
+    /// # Panics
+    /// Panics if `field` is an extension.
+    pub fn mut_repeated(&mut self, field: &FieldDescriptor) -> RepeatedFieldRefMut<'_> {
+        self.as_message_mut().into_mut_repeated(field)
+    }
+
+    /// Like [`mut_repeated`](Self::mut_repeated), but consumes `self`.
+    pub fn into_mut_repeated(self, field: &FieldDescriptor) -> RepeatedFieldRefMut<'msg> {
+        self.as_message_ref().check_reflection();
+        check_field(&self.descriptor, field, FieldShape::Repeated);
+        check_not_extension(field);
+        RepeatedFieldRefMut { msg: self, field: field.clone() }
+    }
+
+    /// Returns a mutable view of the map field `field`.
+    pub fn mut_map(&mut self, field: &FieldDescriptor) -> MapFieldRefMut<'_> {
+        self.as_message_mut().into_mut_map(field)
+    }
+
+    /// Like [`mut_map`](Self::mut_map), but consumes `self`.
+    pub fn into_mut_map(self, field: &FieldDescriptor) -> MapFieldRefMut<'msg> {
+        self.as_message_ref().check_reflection();
+        check_field(&self.descriptor, field, FieldShape::Map);
+        MapFieldRefMut { msg: self, field: field.clone() }
+    }
+}
+
+impl fmt::Debug for MessageRefMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.as_message_ref(), f)
+    }
+}
+
+/// A view of a repeated field of a message.
+#[derive(Clone)]
+pub struct RepeatedFieldRef<'msg> {
+    msg: MessageRef<'msg>,
+    field: FieldDescriptor,
+}
+
+impl<'msg> RepeatedFieldRef<'msg> {
+    /// The descriptor of the field.
+    pub fn field(&self) -> FieldDescriptor {
+        self.field.clone()
+    }
+
+    /// The number of elements in the field.
+    pub fn len(&self) -> usize {
+        // SAFETY: `self.field` is a repeated field of `self.msg`.
+        unsafe { reflect_repeated_len(self.msg.raw, &self.field) }
+    }
+
+    /// Returns true if the field has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the element at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<ReflectValueRef<'msg>> {
+        if index >= self.len() {
+            return None;
+        }
+        // SAFETY: `self.field` is a repeated field of `self.msg`, and `index` is
+        // in bounds.
+        Some(unsafe { reflect_repeated_get(self.msg.raw, &self.field, index) })
+    }
+
+    /// Iterates over the elements of the field.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = ReflectValueRef<'msg>> + 'msg {
+        let this = self.clone();
+        // SAFETY: `this.field` is a repeated field of `this.msg`, and every index
+        // is in bounds.
+        (0..self.len()).map(move |i| unsafe { reflect_repeated_get(this.msg.raw, &this.field, i) })
+    }
+}
+
+impl fmt::Debug for RepeatedFieldRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// A mutable view of a repeated field of a message.
+pub struct RepeatedFieldRefMut<'msg> {
+    msg: MessageRefMut<'msg>,
+    field: FieldDescriptor,
+}
+
+impl<'msg> RepeatedFieldRefMut<'msg> {
+    /// The descriptor of the field.
+    pub fn field(&self) -> FieldDescriptor {
+        self.field.clone()
+    }
+
+    /// Returns a view of the field.
+    pub fn as_repeated_ref(&self) -> RepeatedFieldRef<'_> {
+        RepeatedFieldRef { msg: self.msg.as_message_ref(), field: self.field.clone() }
+    }
+
+    /// The number of elements in the field.
+    pub fn len(&self) -> usize {
+        self.as_repeated_ref().len()
+    }
+
+    /// Returns true if the field has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the element at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<ReflectValueRef<'_>> {
+        self.as_repeated_ref().get(index)
+    }
+
+    /// Sets the element at `index` to `value`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds, or if `value` can't be stored in
+    /// the field.
+    pub fn set<'a>(&mut self, index: usize, value: impl Into<ReflectValueRef<'a>>) {
+        let value = value.into();
+        let len = self.len();
+        assert!(index < len, "index {index} out of bounds for length {len}");
+        check_value(&self.field, &value);
+        // SAFETY: `self.field` is a repeated field of `self.msg`, `index` is in
+        // bounds and `value` is of the type of the field.
+        unsafe { reflect_repeated_set(self.msg.inner, &self.field, index, &value) }
+    }
+
+    /// Appends `value` to the field.
+    ///
+    /// # Panics
+    /// Panics if `value` can't be stored in the field.
+    pub fn push<'a>(&mut self, value: impl Into<ReflectValueRef<'a>>) {
+        let value = value.into();
+        check_value(&self.field, &value);
+        // SAFETY: `self.field` is a repeated field of `self.msg` and `value` is
+        // of its type.
+        unsafe { reflect_repeated_push(self.msg.inner, &self.field, &value) }
+    }
+
+    /// Removes all elements from the field.
+    pub fn clear(&mut self) {
+        self.msg.clear_field(&self.field)
+    }
+
+    /// Returns a mutable view of the message at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds or if this is not a message field.
+    pub fn mut_message(&mut self, index: usize) -> MessageRefMut<'_> {
+        let descriptor = self
+            .field
+            .message_type()
+            .unwrap_or_else(|| panic!("{} is not a message field", self.field.full_name()));
+        let len = self.len();
+        assert!(index < len, "index {index} out of bounds for length {len}");
+        // SAFETY: `self.field` is a repeated message field of `self.msg`, and
+        // `index` is in bounds.
+        let inner = unsafe { reflect_repeated_mut_message(self.msg.inner, &self.field, index) };
+        MessageRefMut { descriptor, inner }
+    }
+}
+
+impl fmt::Debug for RepeatedFieldRefMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.as_repeated_ref(), f)
+    }
+}
+
+/// A view of a map field of a message.
+#[derive(Clone)]
+pub struct MapFieldRef<'msg> {
+    msg: MessageRef<'msg>,
+    field: FieldDescriptor,
+}
+
+impl<'msg> MapFieldRef<'msg> {
+    /// The descriptor of the field.
+    pub fn field(&self) -> FieldDescriptor {
+        self.field.clone()
+    }
+
+    /// The number of entries in the map.
+    pub fn len(&self) -> usize {
+        // SAFETY: `self.field` is a map field of `self.msg`.
+        unsafe { reflect_map_len(self.msg.raw, &self.field) }
+    }
+
+    /// Returns true if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the value for `key`, if it is present.
+    ///
+    /// # Panics
+    /// Panics if `key` is not of the key type of the map.
+    pub fn get<'a>(&self, key: impl Into<ReflectValueRef<'a>>) -> Option<ReflectValueRef<'msg>> {
+        let key = key.into();
+        check_value(&map_entry_fields(&self.field).0, &key);
+        // SAFETY: `self.field` is a map field of `self.msg`, and `key` is of its
+        // key type.
+        unsafe { reflect_map_get(self.msg.raw, &self.field, &key) }
+    }
+
+    /// Iterates over the entries of the map, in no particular order.
+    pub fn iter(&self) -> MapFieldIter<'msg> {
+        // SAFETY: `self.field` is a map field of `self.msg`.
+        MapFieldIter { inner: unsafe { InnerMapFieldIter::new(self.msg.raw, &self.field) } }
+    }
+}
+
+impl fmt::Debug for MapFieldRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'msg> IntoIterator for MapFieldRef<'msg> {
+    type Item = (ReflectValueRef<'msg>, ReflectValueRef<'msg>);
+    type IntoIter = MapFieldIter<'msg>;
+
+    fn into_iter(self) -> MapFieldIter<'msg> {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a [`MapFieldRef`].
+pub struct MapFieldIter<'msg> {
+    inner: InnerMapFieldIter<'msg>,
+}
+
+impl<'msg> Iterator for MapFieldIter<'msg> {
+    type Item = (ReflectValueRef<'msg>, ReflectValueRef<'msg>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+/// A mutable view of a map field of a message.
+pub struct MapFieldRefMut<'msg> {
+    msg: MessageRefMut<'msg>,
+    field: FieldDescriptor,
+}
+
+impl<'msg> MapFieldRefMut<'msg> {
+    /// The descriptor of the field.
+    pub fn field(&self) -> FieldDescriptor {
+        self.field.clone()
+    }
+
+    /// Returns a view of the map.
+    pub fn as_map_ref(&self) -> MapFieldRef<'_> {
+        MapFieldRef { msg: self.msg.as_message_ref(), field: self.field.clone() }
+    }
+
+    /// The number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.as_map_ref().len()
+    }
+
+    /// Returns true if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the value for `key`, if it is present.
+    pub fn get<'a>(&self, key: impl Into<ReflectValueRef<'a>>) -> Option<ReflectValueRef<'_>> {
+        self.as_map_ref().get(key)
+    }
+
+    /// Sets the value for `key` to `value`. Returns true if `key` was not
+    /// already present.
+    ///
+    /// # Panics
+    /// Panics if `key` or `value` are not of the key and value types of the
+    /// map.
+    pub fn insert<'k, 'v>(
+        &mut self,
+        key: impl Into<ReflectValueRef<'k>>,
+        value: impl Into<ReflectValueRef<'v>>,
+    ) -> bool {
+        let (key, value) = (key.into(), value.into());
+        let (key_field, value_field) = map_entry_fields(&self.field);
+        check_value(&key_field, &key);
+        check_value(&value_field, &value);
+        // SAFETY: `self.field` is a map field of `self.msg`, and `key` and
+        // `value` are of its key and value types.
+        unsafe { reflect_map_insert(self.msg.inner, &self.field, &key, &value) }
+    }
+
+    /// Removes the entry for `key`. Returns true if it was present.
+    pub fn remove<'a>(&mut self, key: impl Into<ReflectValueRef<'a>>) -> bool {
+        let key = key.into();
+        check_value(&map_entry_fields(&self.field).0, &key);
+        // SAFETY: `self.field` is a map field of `self.msg`, and `key` is of its
+        // key type.
+        unsafe { reflect_map_remove(self.msg.inner, &self.field, &key) }
+    }
+
+    /// Removes all entries from the map.
+    pub fn clear(&mut self) {
+        self.msg.clear_field(&self.field)
+    }
+}
+
+impl fmt::Debug for MapFieldRefMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.as_map_ref(), f)
+    }
+}
+
+/// An owned message whose type is only known at runtime.
+///
+/// Its type may be a compiled-in type or one from a [`DescriptorPool`] that
+/// was built at runtime. Like generated messages, it implements [`Serialize`],
+/// [`Clear`] and [`ClearAndParse`], and it has inherent `parse` and
+/// `merge_from` methods that take the descriptor and the source message that
+/// the generated versions know statically.
+pub struct DynamicMessage {
+    // Declared first so that the message is dropped before the descriptor,
+    // which may hold the last reference to its pool.
+    inner: InnerDynamicMessage,
+    descriptor: MessageDescriptor,
+}
+
+// SAFETY: `DynamicMessage` owns its message like a generated message does.
+unsafe impl Send for DynamicMessage {}
+unsafe impl Sync for DynamicMessage {}
+
+impl DynamicMessage {
+    /// Creates an empty message of the type `descriptor`.
+    pub fn new(descriptor: MessageDescriptor) -> Self {
+        DynamicMessage { inner: InnerDynamicMessage::new(&descriptor), descriptor }
+    }
+
+    /// Parses a message of the type `descriptor` from its wire format.
+    pub fn parse(descriptor: MessageDescriptor, data: &[u8]) -> Result<Self, ParseError> {
+        let mut msg = Self::new(descriptor);
+        msg.clear_and_parse(data).map(|()| msg)
+    }
+
+    /// Parses a message of the type `descriptor` from its wire format, as
+    /// controlled by `options`.
+    pub fn parse_with_options(
+        descriptor: MessageDescriptor,
+        data: &[u8],
+        options: &ParseOptions<'_>,
+    ) -> Result<Self, ParseError> {
+        let mut msg = Self::new(descriptor);
+        // SAFETY: `msg.inner` is an empty message of the type `msg.descriptor`.
+        unsafe { msg.inner.parse_with_options(&msg.descriptor, data, options) }.map(|()| msg)
+    }
+
+    /// The descriptor of the type of this message.
+    pub fn descriptor(&self) -> MessageDescriptor {
+        self.descriptor.clone()
+    }
+
+    /// Returns a view of this message.
+    pub fn as_message_ref(&self) -> MessageRef<'_> {
+        // SAFETY: `self.inner` is a message of the type `self.descriptor`.
+        unsafe { MessageRef::new(Private, self.descriptor.clone(), self.inner.raw()) }
+    }
+
+    /// Returns a mutable view of this message.
+    pub fn as_message_mut(&mut self) -> MessageRefMut<'_> {
+        // SAFETY: `self.inner` is a message of the type `self.descriptor`.
+        unsafe {
+            MessageRefMut::new(
+                Private,
+                self.descriptor.clone(),
+                self.inner.as_mutator_message_ref(),
+            )
+        }
+    }
+
+    /// See [`MessageRefMut::merge_from`].
+    pub fn merge_from(&mut self, src: MessageRef<'_>) {
+        self.as_message_mut().merge_from(src)
+    }
+}
+
+impl SealedInternal for DynamicMessage {}
+
+impl Serialize for DynamicMessage {
+    fn serialize(&self) -> Result<Vec<u8>, SerializeError> {
+        self.serialize_with_options(&SerializeOptions::new())
+    }
+
+    fn serialize_with_options(
+        &self,
+        options: &SerializeOptions,
+    ) -> Result<Vec<u8>, SerializeError> {
+        let mut out = Vec::new();
+        // SAFETY: `self.inner` is a message of the type `self.descriptor`.
+        unsafe { reflect_serialize_to_vec(self.inner.raw(), &self.descriptor, &mut out, options) }?;
+        Ok(out)
+    }
+
+    fn serialize_to_vec(&self, out: &mut Vec<u8>) -> Result<(), SerializeError> {
+        let options = SerializeOptions::new();
+        // SAFETY: `self.inner` is a message of the type `self.descriptor`.
+        unsafe { reflect_serialize_to_vec(self.inner.raw(), &self.descriptor, out, &options) }
+    }
+
+    fn serialize_into(&self, buf: &mut [u8]) -> Result<usize, SerializeError> {
+        // SAFETY: `self.inner` is a message of the type `self.descriptor`.
+        unsafe { reflect_serialize_into(self.inner.raw(), &self.descriptor, buf) }
+    }
+}
+
+impl Clear for DynamicMessage {
+    fn clear(&mut self) {
+        self.as_message_mut().clear()
+    }
+}
+
+impl ClearAndParse for DynamicMessage {
+    fn clear_and_parse(&mut self, data: &[u8]) -> Result<(), ParseError> {
+        // SAFETY: `self.inner` is a message of the type `self.descriptor`.
+        unsafe { self.inner.clear_and_parse(&self.descriptor, data) }
+    }
+
+    fn merge_from_reader(&mut self, reader: impl Read, max_size: usize) -> Result<(), ReadError> {
+        let data = read_to_end_limited(Private, reader, max_size)?;
+        // SAFETY: `self.inner` is a message of the type `self.descriptor`.
+        unsafe { self.inner.merge_parse(&self.descriptor, &data) }?;
+        Ok(())
+    }
+}
+
+impl Clone for DynamicMessage {
+    fn clone(&self) -> Self {
+        self.as_message_ref().to_owned()
+    }
+}
+
+impl fmt::Debug for DynamicMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.as_message_ref(), f)
+    }
+}
@@ -5,6 +5,7 @@
 // license that can be found in the LICENSE file or at
 // https://developers.google.com/open-source/licenses/bsd
 
+use std::cmp::Ordering;
 use std::fmt::{self, Debug};
 use std::iter;
 use std::iter::FusedIterator;
@@ -13,6 +14,9 @@ use std::iter::FusedIterator;
 /// runtime-specific representation of a repeated scalar (`upb_Array*` on upb,
 /// and `RepeatedField<T>*` on cpp).
 use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::slice;
+use std::vec;
 
 use crate::{
     AsMut, AsView, IntoMut, IntoProxied, IntoView, Mut, MutProxied, MutProxy, Proxied, Proxy, View,
@@ -120,6 +124,26 @@ where
     }
 }
 
+impl<'msg, T> RepeatedView<'msg, T>
+where
+    T: ContiguousRepeated + 'msg,
+{
+    /// Returns the values in the repeated field as a slice, without copying
+    /// them.
+    #[inline]
+    pub fn as_slice(&self) -> &'msg [T] {
+        let len = self.len();
+        if len == 0 {
+            return &[];
+        }
+        // SAFETY:
+        // - `repeated_data` points to `len` values, as promised by the
+        //   implementer of `ContiguousRepeated`.
+        // - The values can't be mutated during `'msg`.
+        unsafe { slice::from_raw_parts(T::repeated_data(*self), len) }
+    }
+}
+
 #[doc(hidden)]
 impl<'msg, T> RepeatedMut<'msg, T> {
     /// # Safety
@@ -217,6 +241,286 @@ where
     pub fn clear(&mut self) {
         T::repeated_clear(self.as_mut())
     }
+
+    /// Swaps the values at `a` and `b`.
+    ///
+    /// Messages are swapped by pointer, so this does not copy them.
+    ///
+    /// # Panics
+    /// Panics if `a >= len` or `b >= len`
+    pub fn swap(&mut self, a: usize, b: usize) {
+        let len = self.len();
+        if a >= len || b >= len {
+            panic!("swap indices ({a}, {b}) out of bounds for repeated len {len}");
+        }
+        // SAFETY: `a` and `b` have been checked to be in-bounds
+        unsafe { T::repeated_swap_unchecked(self.as_mut(), a, b) }
+    }
+
+    /// Shortens the repeated field to its first `len` values, dropping the
+    /// rest. Does nothing if `len` is not less than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        T::repeated_truncate(self.as_mut(), len)
+    }
+
+    /// Inserts `val` at `index`, shifting the values after it to the right.
+    ///
+    /// # Panics
+    /// Panics if `index > len`
+    pub fn insert(&mut self, index: usize, val: impl IntoProxied<T>) {
+        let len = self.len();
+        if index > len {
+            panic!("insertion index {index} > repeated len {len}");
+        }
+        self.push(val);
+        self.rotate_right_from(index);
+    }
+
+    /// Reverses the order of the values.
+    pub fn reverse(&mut self) {
+        let len = self.len();
+        for i in 0..len / 2 {
+            // SAFETY: `i < len - 1 - i < len`
+            unsafe { T::repeated_swap_unchecked(self.as_mut(), i, len - 1 - i) }
+        }
+    }
+
+    /// Retains only the values for which `f` returns `true`, preserving their
+    /// order.
+    pub fn retain(&mut self, mut f: impl FnMut(View<T>) -> bool) {
+        let len = self.len();
+        let mut kept = 0;
+        for i in 0..len {
+            // SAFETY: `i < len`
+            if f(unsafe { self.get_unchecked(i) }) {
+                if kept != i {
+                    // SAFETY: `kept < i < len`
+                    unsafe { T::repeated_swap_unchecked(self.as_mut(), kept, i) }
+                }
+                kept += 1;
+            }
+        }
+        self.truncate(kept);
+    }
+
+    /// Removes consecutive values for which `same_bucket` returns `true`,
+    /// keeping the first of each run.
+    ///
+    /// Like [`Vec::dedup_by`], `same_bucket` is passed a value and the value
+    /// before it that is kept.
+    pub fn dedup_by(
+        &mut self,
+        mut same_bucket: impl for<'a> FnMut(View<'a, T>, View<'a, T>) -> bool,
+    ) {
+        let len = self.len();
+        if len <= 1 {
+            return;
+        }
+        let mut kept = 1;
+        for i in 1..len {
+            // SAFETY: `kept - 1 < kept <= i < len`
+            if !same_bucket(unsafe { self.get_unchecked(i) }, unsafe {
+                self.get_unchecked(kept - 1)
+            }) {
+                if kept != i {
+                    // SAFETY: `kept < i < len`
+                    unsafe { T::repeated_swap_unchecked(self.as_mut(), kept, i) }
+                }
+                kept += 1;
+            }
+        }
+        self.truncate(kept);
+    }
+
+    /// Removes consecutive repeated values, keeping the first of each run.
+    pub fn dedup(&mut self)
+    where
+        for<'a> View<'a, T>: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b)
+    }
+
+    /// Sorts the values with the comparator function `compare`.
+    ///
+    /// The sort is stable. The values are moved into place, so messages are
+    /// not copied.
+    pub fn sort_by(
+        &mut self,
+        mut compare: impl for<'a> FnMut(View<'a, T>, View<'a, T>) -> Ordering,
+    ) {
+        let len = self.len();
+        let view = self.as_view();
+        // `order[i]` is the current index of the value that belongs at `i`.
+        let mut order: Vec<usize> = (0..len).collect();
+        // SAFETY: every index in `order` is less than `len`
+        order.sort_by(|&a, &b| unsafe { compare(view.get_unchecked(a), view.get_unchecked(b)) });
+        self.permute(&order);
+    }
+
+    /// Sorts the values by the key that `f` extracts from them.
+    ///
+    /// The sort is stable. The values are moved into place, so messages are
+    /// not copied.
+    pub fn sort_by_key<K: Ord>(&mut self, mut f: impl FnMut(View<T>) -> K) {
+        self.sort_by(|a, b| f(a).cmp(&f(b)))
+    }
+
+    /// Removes the last value and returns it, or `None` if the repeated field
+    /// is empty.
+    ///
+    /// The returned value is a copy of the removed one, so a message is copied.
+    pub fn pop(&mut self) -> Option<T>
+    where
+        for<'a> View<'a, T>: IntoProxied<T>,
+    {
+        let last = self.len().checked_sub(1)?;
+        // SAFETY: `last < len`
+        let val = unsafe { self.get_unchecked(last) }.into_proxied(Private);
+        self.truncate(last);
+        Some(val)
+    }
+
+    /// Removes the value at `index` and returns it, shifting the values after
+    /// it to the left.
+    ///
+    /// The returned value is a copy of the removed one, so a message is copied.
+    ///
+    /// # Panics
+    /// Panics if `index >= len`
+    pub fn remove(&mut self, index: usize) -> T
+    where
+        for<'a> View<'a, T>: IntoProxied<T>,
+    {
+        let len = self.len();
+        if index >= len {
+            panic!("removal index {index} >= repeated len {len}");
+        }
+        self.drain(index..index + 1).next().unwrap()
+    }
+
+    /// Removes the values in `range` and returns them in order.
+    ///
+    /// Unlike [`Vec::drain`], the values are removed even if the returned
+    /// iterator isn't consumed. The returned values are copies of the removed
+    /// ones, so removed messages are copied; the values after `range` are
+    /// moved into place without being copied.
+    ///
+    /// # Panics
+    /// Panics if the range is decreasing, if its end is greater than `len`, or
+    /// if one of its bounds overflows `usize`
+    pub fn drain(&mut self, range: impl RangeBounds<usize>) -> vec::IntoIter<T>
+    where
+        for<'a> View<'a, T>: IntoProxied<T>,
+    {
+        let len = self.len();
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start
+                .checked_add(1)
+                .unwrap_or_else(|| panic!("attempted to index slice from after maximum usize")),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end
+                .checked_add(1)
+                .unwrap_or_else(|| panic!("attempted to index slice up to maximum usize")),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => len,
+        };
+        if start > end || end > len {
+            panic!("drain range {start}..{end} out of bounds for repeated len {len}");
+        }
+        let drained: Vec<T> =
+            self.iter().skip(start).take(end - start).map(|v| v.into_proxied(Private)).collect();
+        for i in end..len {
+            // SAFETY: `i - (end - start) < i < len`
+            unsafe { T::repeated_swap_unchecked(self.as_mut(), i - (end - start), i) }
+        }
+        self.truncate(len - (end - start));
+        drained.into_iter()
+    }
+
+    /// Splits the repeated field in two at `at`: the values from `at` onward
+    /// are removed and returned, and the first `at` values are kept.
+    ///
+    /// # Panics
+    /// Panics if `at > len`
+    pub fn split_off(&mut self, at: usize) -> Repeated<T>
+    where
+        for<'a> View<'a, T>: IntoProxied<T>,
+    {
+        let len = self.len();
+        if at > len {
+            panic!("split index {at} > repeated len {len}");
+        }
+        let mut tail = Repeated::new();
+        tail.as_mut().extend(self.iter().skip(at));
+        self.truncate(at);
+        tail
+    }
+
+    /// Moves the last value to `index`, shifting the values from `index`
+    /// onward to the right.
+    fn rotate_right_from(&mut self, index: usize) {
+        for i in (index + 1..self.len()).rev() {
+            // SAFETY: `i - 1 < i < len`
+            unsafe { T::repeated_swap_unchecked(self.as_mut(), i - 1, i) }
+        }
+    }
+
+    /// Moves the value at `order[i]` to `i` for every `i`, where `order` is a
+    /// permutation of the indices, by following its cycles with swaps.
+    fn permute(&mut self, order: &[usize]) {
+        let mut placed = vec![false; order.len()];
+        for start in 0..order.len() {
+            let mut i = start;
+            while !placed[i] {
+                placed[i] = true;
+                let from = order[i];
+                if from == start {
+                    break;
+                }
+                // SAFETY: `i` and `from` are indices of the repeated field
+                unsafe { T::repeated_swap_unchecked(self.as_mut(), i, from) }
+                i = from;
+            }
+        }
+    }
+}
+
+impl<'msg, T> RepeatedMut<'msg, T>
+where
+    T: ContiguousRepeated + 'msg,
+{
+    /// Returns the values in the repeated field as a slice, without copying
+    /// them.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        self.as_view().as_slice()
+    }
+
+    /// Returns the values in the repeated field as a mutable slice, without
+    /// copying them.
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        let len = self.len();
+        if len == 0 {
+            return &mut [];
+        }
+        // SAFETY:
+        // - `repeated_data_mut` points to `len` values, as promised by the
+        //   implementer of `ContiguousRepeated`.
+        // - The values can only be accessed through the slice while it is
+        //   borrowed from `self`.
+        unsafe { slice::from_raw_parts_mut(T::repeated_data_mut(self.as_mut()), len) }
+    }
+
+    /// Appends the values in `src` to the end of the repeated field with a
+    /// single copy.
+    #[inline]
+    pub fn extend_from_slice(&mut self, src: &[T]) {
+        T::repeated_extend_from_slice(self.as_mut(), src)
+    }
 }
 
 impl<T> Repeated<T>
@@ -309,6 +613,17 @@ pub unsafe trait ProxiedInRepeated: Proxied {
         val: impl IntoProxied<Self>,
     );
 
+    /// Swaps the values at `a` and `b`. Messages are swapped by pointer, not
+    /// copied.
+    ///
+    /// # Safety
+    /// `a` and `b` must be less than `Self::repeated_len(repeated)`
+    unsafe fn repeated_swap_unchecked(repeated: Mut<Repeated<Self>>, a: usize, b: usize);
+
+    /// Shortens the repeated field to `len` values, dropping the rest. Does
+    /// nothing if `len` is not less than `Self::repeated_len(repeated)`.
+    fn repeated_truncate(repeated: Mut<Repeated<Self>>, len: usize);
+
     /// Copies the values in the `src` repeated field into `dest`.
     fn repeated_copy_from(src: View<Repeated<Self>>, dest: Mut<Repeated<Self>>);
 
@@ -317,10 +632,34 @@ pub unsafe trait ProxiedInRepeated: Proxied {
     fn repeated_reserve(repeated: Mut<Repeated<Self>>, additional: usize);
 }
 
+/// Types whose values a `Repeated<T>` stores contiguously, so that they can be
+/// accessed as a slice.
+///
+/// The integer, floating point and `bool` types implement
+/// `ContiguousRepeated`.
+///
+/// # Safety
+/// - Unless `repeated_len(x)` is 0, `repeated_data(x)` and
+///   `repeated_data_mut(x)` must point to `repeated_len(x)` initialized values
+///   that stay valid until `x` is next mutated.
+pub unsafe trait ContiguousRepeated: ProxiedInRepeated + Copy {
+    /// Returns a pointer to the first value in the repeated field.
+    fn repeated_data(repeated: View<Repeated<Self>>) -> *const Self;
+
+    /// Returns a pointer to the first value in the repeated field, for
+    /// writing.
+    fn repeated_data_mut(repeated: Mut<Repeated<Self>>) -> *mut Self;
+
+    /// Appends the values in `src` to the end of the repeated field.
+    fn repeated_extend_from_slice(repeated: Mut<Repeated<Self>>, src: &[Self]);
+}
+
 /// An iterator over the values inside of a [`View<Repeated<T>>`](RepeatedView).
 pub struct RepeatedIter<'msg, T> {
     view: RepeatedView<'msg, T>,
     current_index: usize,
+    /// One past the index of the last value that hasn't been yielded.
+    end_index: usize,
 }
 
 impl<'msg, T> Debug for RepeatedIter<'msg, T> {
@@ -328,10 +667,20 @@ impl<'msg, T> Debug for RepeatedIter<'msg, T> {
         f.debug_struct("RepeatedIter")
             .field("view", &self.view)
             .field("current_index", &self.current_index)
+            .field("end_index", &self.end_index)
             .finish()
     }
 }
 
+impl<'msg, T> RepeatedIter<'msg, T>
+where
+    T: ProxiedInRepeated + 'msg,
+{
+    fn new(view: RepeatedView<'msg, T>) -> Self {
+        RepeatedIter { view, current_index: 0, end_index: view.len() }
+    }
+}
+
 /// A `repeated` field of `T`, used as the owned target for `Proxied`.
 ///
 /// Users will generally write [`View<Repeated<T>>`](RepeatedView) or
@@ -514,11 +863,13 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        let val = self.view.get(self.current_index);
-        if val.is_some() {
-            self.current_index += 1;
+        if self.current_index == self.end_index {
+            return None;
         }
-        val
+        // SAFETY: `current_index < end_index <= len`
+        let val = unsafe { self.view.get_unchecked(self.current_index) };
+        self.current_index += 1;
+        Some(val)
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -529,11 +880,25 @@ where
 
 impl<'msg, T: ProxiedInRepeated> ExactSizeIterator for RepeatedIter<'msg, T> {
     fn len(&self) -> usize {
-        self.view.len() - self.current_index
+        self.end_index - self.current_index
+    }
+}
+
+impl<'msg, T> DoubleEndedIterator for RepeatedIter<'msg, T>
+where
+    T: ProxiedInRepeated + 'msg,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.current_index == self.end_index {
+            return None;
+        }
+        self.end_index -= 1;
+        // SAFETY: `current_index <= end_index < len`
+        Some(unsafe { self.view.get_unchecked(self.end_index) })
     }
 }
 
-// TODO: impl DoubleEndedIterator
 impl<'msg, T: ProxiedInRepeated> FusedIterator for RepeatedIter<'msg, T> {}
 
 impl<'msg, T> iter::IntoIterator for RepeatedView<'msg, T>
@@ -544,7 +909,7 @@ where
     type IntoIter = RepeatedIter<'msg, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        RepeatedIter { view: self, current_index: 0 }
+        RepeatedIter::new(self)
     }
 }
 
@@ -556,7 +921,7 @@ where
     type IntoIter = RepeatedIter<'msg, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        RepeatedIter { view: *self, current_index: 0 }
+        RepeatedIter::new(*self)
     }
 }
 
@@ -568,7 +933,7 @@ where
     type IntoIter = RepeatedIter<'borrow, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        RepeatedIter { view: self.as_view(), current_index: 0 }
+        RepeatedIter::new(self.as_view())
     }
 }
 
@@ -649,6 +1014,59 @@ mod tests {
         assert_that!(r.as_mut(), elements_are![eq(0), eq(1), eq(2), eq(3)]);
     }
 
+    #[googletest::test]
+    fn test_repeated_vec_like_mutations() {
+        let mut r = Repeated::<i32>::new();
+        let mut r = r.as_mut();
+        r.extend([5, 3, 1, 4, 1, 5, 9, 2, 6]);
+
+        assert_that!(r.pop(), some(eq(6)));
+        r.insert(0, 7);
+        assert_that!(r.remove(1), eq(5));
+        assert_that!(r, elements_are![eq(7), eq(3), eq(1), eq(4), eq(1), eq(5), eq(9), eq(2)]);
+
+        r.sort_by_key(|v| v);
+        r.dedup();
+        assert_that!(r, elements_are![eq(1), eq(2), eq(3), eq(4), eq(5), eq(7), eq(9)]);
+
+        r.retain(|v| v % 2 == 1);
+        r.reverse();
+        assert_that!(r, elements_are![eq(9), eq(7), eq(5), eq(3), eq(1)]);
+
+        assert_that!(r.drain(1..3).collect::<Vec<_>>(), eq(vec![7, 5]));
+        let tail = r.split_off(1);
+        assert_that!(tail.as_view(), elements_are![eq(3), eq(1)]);
+        r.truncate(5);
+        assert_that!(r, elements_are![eq(9)]);
+        r.truncate(0);
+        assert_that!(r.pop(), none());
+    }
+
+    #[googletest::test]
+    fn test_repeated_slices() {
+        let mut r = Repeated::<f32>::new();
+        let mut r = r.as_mut();
+        assert_that!(r.as_slice(), empty());
+        assert_that!(r.as_mut_slice(), empty());
+
+        r.extend_from_slice(&[]);
+        r.extend_from_slice(&[1.0, 2.0]);
+        r.push(3.0);
+        r.extend_from_slice(&[4.0]);
+        assert_that!(r.as_slice(), eq(&[1.0, 2.0, 3.0, 4.0][..]));
+
+        for v in r.as_mut_slice() {
+            *v *= 2.0;
+        }
+        assert_that!(r, elements_are![eq(2.0), eq(4.0), eq(6.0), eq(8.0)]);
+        assert_that!(r.as_view().as_slice(), eq(&[2.0, 4.0, 6.0, 8.0][..]));
+
+        let mut b = Repeated::<bool>::new();
+        b.as_mut().extend_from_slice(&[true, false]);
+        b.as_mut().as_mut_slice()[1] = true;
+        assert_that!(b.as_view().as_slice(), eq(&[true, true][..]));
+    }
+
     #[googletest::test]
     fn test_repeated_iter_into_proxied() {
         let r: Repeated<i32> = [0, 1, 2, 3].into_iter().into_proxied(Private);
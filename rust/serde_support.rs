@@ -0,0 +1,1130 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Implementations of the `serde` traits, enabled by the `serde` feature.
+//!
+//! Values serialize to the canonical proto3 JSON mapping: fields are named by
+//! their lowerCamelCase JSON names, 64-bit integers are strings, `bytes` are
+//! base64 and enums are the names of their values. The well-known types, such
+//! as `Timestamp` and `Struct`, have their special JSON forms.
+//!
+//! Messages are converted through their JSON text, so they deserialize only
+//! from self-describing formats such as JSON, YAML and TOML. The other types
+//! fall back to plain numbers and bytes in formats that aren't human-readable.
+//!
+//! Like the conversions of the well-known types, the generated code of each
+//! message and enum invokes a macro below so that this feature of the runtime
+//! decides whether the traits are implemented.
+
+use crate::reflect::EnumDescriptor;
+use crate::{
+    FromJson, Map, MapView, ProtoBytes, ProtoStr, ProtoString, Proxied, ProxiedInMapValue,
+    ProxiedInRepeated, Repeated, RepeatedView, ToJson, View,
+};
+use serde::de::{self, Unexpected, Visitor};
+use serde::ser::{self, SerializeMap, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+/// A type whose values can be elements of a [`Repeated`] or values of a
+/// [`Map`] that implement the `serde` traits.
+///
+/// The primitive types implement this trait rather than using their own
+/// `serde` implementations, since the JSON mapping prints 64-bit integers as
+/// strings and floating point infinities and NaN as `"Infinity"`,
+/// `"-Infinity"` and `"NaN"`.
+pub trait SerdeValue: Proxied {
+    fn serialize_view<S: Serializer>(
+        view: &View<'_, Self>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>;
+
+    fn deserialize_value<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+/// A type whose values can be keys of a [`Map`] that implements the `serde`
+/// traits. Keys are always strings, as in JSON objects.
+pub trait SerdeMapKey: Proxied {
+    fn serialize_key<S: Serializer>(
+        view: &View<'_, Self>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>;
+
+    /// Parses a key, or returns `None` if `key` isn't a valid key.
+    fn parse_key(key: &str) -> Option<Self>;
+}
+
+/// Serializes a view with [`SerdeValue::serialize_view`].
+struct ValueOf<'a, T: Proxied + 'a>(View<'a, T>);
+
+impl<T: SerdeValue> Serialize for ValueOf<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        T::serialize_view(&self.0, serializer)
+    }
+}
+
+/// Serializes a view with [`SerdeMapKey::serialize_key`].
+struct KeyOf<'a, K: Proxied + 'a>(View<'a, K>);
+
+impl<K: SerdeMapKey> Serialize for KeyOf<'_, K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        K::serialize_key(&self.0, serializer)
+    }
+}
+
+/// Deserializes a value with [`SerdeValue::deserialize_value`].
+struct Owned<T>(T);
+
+impl<'de, T: SerdeValue> Deserialize<'de> for Owned<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize_value(deserializer).map(Owned)
+    }
+}
+
+impl SerdeValue for bool {
+    fn serialize_view<S: Serializer>(view: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bool(*view)
+    }
+
+    fn deserialize_value<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        bool::deserialize(deserializer)
+    }
+}
+
+macro_rules! impl_serde_value_for_integer {
+    ($($t:ty: $serialize:ident, $deserialize:ident, $as_string:literal;)*) => {$(
+        impl SerdeValue for $t {
+            fn serialize_view<S: Serializer>(
+                view: &$t,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                if $as_string && serializer.is_human_readable() {
+                    serializer.collect_str(view)
+                } else {
+                    serializer.$serialize(*view)
+                }
+            }
+
+            fn deserialize_value<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                if deserializer.is_human_readable() {
+                    deserializer.deserialize_any(IntegerVisitor(PhantomData))
+                } else {
+                    deserializer.$deserialize(IntegerVisitor(PhantomData))
+                }
+            }
+        }
+
+        impl SerdeMapKey for $t {
+            fn serialize_key<S: Serializer>(
+                view: &$t,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(view)
+            }
+
+            fn parse_key(key: &str) -> Option<Self> {
+                key.parse().ok()
+            }
+        }
+    )*};
+}
+
+impl_serde_value_for_integer! {
+    i32: serialize_i32, deserialize_i32, false;
+    u32: serialize_u32, deserialize_u32, false;
+    i64: serialize_i64, deserialize_i64, true;
+    u64: serialize_u64, deserialize_u64, true;
+}
+
+impl SerdeMapKey for bool {
+    fn serialize_key<S: Serializer>(view: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(view)
+    }
+
+    fn parse_key(key: &str) -> Option<Self> {
+        key.parse().ok()
+    }
+}
+
+/// Accepts an integer as a number, a string holding one, or a float without
+/// a fractional part, as the JSON mapping does.
+struct IntegerVisitor<T>(PhantomData<T>);
+
+impl<T> Visitor<'_> for IntegerVisitor<T>
+where
+    T: TryFrom<i64> + TryFrom<u64> + FromStr,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an integer or a string holding one")
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<T, E> {
+        T::try_from(v).map_err(|_| E::invalid_value(Unexpected::Signed(v), &self))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<T, E> {
+        T::try_from(v).map_err(|_| E::invalid_value(Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<T, E> {
+        // 2^64 is exactly representable, unlike `u64::MAX`.
+        if v.fract() != 0.0 || !(i64::MIN as f64..18_446_744_073_709_551_616.0).contains(&v) {
+            Err(E::invalid_value(Unexpected::Float(v), &self))
+        } else if v < 0.0 {
+            self.visit_i64(v as i64)
+        } else {
+            self.visit_u64(v as u64)
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        v.parse().map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+    }
+}
+
+macro_rules! impl_serde_value_for_float {
+    ($($t:ty: $serialize:ident, $deserialize:ident;)*) => {$(
+        impl SerdeValue for $t {
+            fn serialize_view<S: Serializer>(
+                view: &$t,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                if view.is_finite() || !serializer.is_human_readable() {
+                    serializer.$serialize(*view)
+                } else if view.is_nan() {
+                    serializer.serialize_str("NaN")
+                } else if view.is_sign_positive() {
+                    serializer.serialize_str("Infinity")
+                } else {
+                    serializer.serialize_str("-Infinity")
+                }
+            }
+
+            fn deserialize_value<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                let v = if deserializer.is_human_readable() {
+                    deserializer.deserialize_any(FloatVisitor)?
+                } else {
+                    deserializer.$deserialize(FloatVisitor)?
+                };
+                let narrowed = v as $t;
+                if v.is_finite() && narrowed.is_infinite() {
+                    return Err(de::Error::invalid_value(Unexpected::Float(v), &FloatVisitor));
+                }
+                Ok(narrowed)
+            }
+        }
+    )*};
+}
+
+impl_serde_value_for_float! {
+    f32: serialize_f32, deserialize_f32;
+    f64: serialize_f64, deserialize_f64;
+}
+
+/// Accepts a float as a number or a string holding one, including `"NaN"`,
+/// `"Infinity"` and `"-Infinity"`.
+struct FloatVisitor;
+
+impl Visitor<'_> for FloatVisitor {
+    type Value = f64;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a number or a string holding one")
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<f64, E> {
+        Ok(v)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<f64, E> {
+        Ok(v as f64)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<f64, E> {
+        Ok(v as f64)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<f64, E> {
+        match v {
+            "NaN" => Ok(f64::NAN),
+            "Infinity" => Ok(f64::INFINITY),
+            "-Infinity" => Ok(f64::NEG_INFINITY),
+            _ => match v.parse::<f64>() {
+                Ok(parsed) if parsed.is_finite() => Ok(parsed),
+                _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
+            },
+        }
+    }
+}
+
+impl Serialize for ProtoStr {
+    /// Fails if the string is not UTF-8.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.to_str().map_err(ser::Error::custom)?)
+    }
+}
+
+impl Serialize for ProtoString {
+    /// Fails if the string is not UTF-8.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_view().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProtoString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(ProtoString::from(s.as_str()))
+    }
+}
+
+impl SerdeValue for ProtoString {
+    fn serialize_view<S: Serializer>(view: &&ProtoStr, serializer: S) -> Result<S::Ok, S::Error> {
+        view.serialize(serializer)
+    }
+
+    fn deserialize_value<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ProtoString::deserialize(deserializer)
+    }
+}
+
+impl SerdeMapKey for ProtoString {
+    fn serialize_key<S: Serializer>(view: &&ProtoStr, serializer: S) -> Result<S::Ok, S::Error> {
+        view.serialize(serializer)
+    }
+
+    fn parse_key(key: &str) -> Option<Self> {
+        Some(ProtoString::from(key))
+    }
+}
+
+impl Serialize for ProtoBytes {
+    /// Serializes the bytes as a base64 string in human-readable formats.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(self.as_view(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProtoBytes {
+    /// Accepts base64 strings in both the standard and the URL-safe
+    /// alphabets, with or without padding, as well as raw bytes.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = if deserializer.is_human_readable() {
+            deserializer.deserialize_any(BytesVisitor)?
+        } else {
+            deserializer.deserialize_byte_buf(BytesVisitor)?
+        };
+        Ok(ProtoBytes::from(bytes.as_slice()))
+    }
+}
+
+impl SerdeValue for ProtoBytes {
+    fn serialize_view<S: Serializer>(view: &&[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(view, serializer)
+    }
+
+    fn deserialize_value<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ProtoBytes::deserialize(deserializer)
+    }
+}
+
+fn serialize_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&encode_base64(bytes))
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a base64 string or bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Vec<u8>, E> {
+        decode_base64(v).ok_or_else(|| E::invalid_value(Unexpected::Str(v), &self))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `bytes` in the standard base64 alphabet with padding.
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decodes base64 in either the standard or the URL-safe alphabet, with
+/// optional padding.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut n, mut bits) = (0u32, 0);
+    for c in text.bytes() {
+        let digit = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        n = n << 6 | digit as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((n >> bits) as u8);
+        }
+    }
+    // A single leftover digit can't encode a whole byte.
+    (bits < 6).then_some(out)
+}
+
+impl<T: ProxiedInRepeated + SerdeValue> Serialize for RepeatedView<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for element in self.iter() {
+            seq.serialize_element(&ValueOf::<T>(element))?;
+        }
+        seq.end()
+    }
+}
+
+impl<T: ProxiedInRepeated + SerdeValue> Serialize for Repeated<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_view().serialize(serializer)
+    }
+}
+
+impl<'de, T: ProxiedInRepeated + SerdeValue> Deserialize<'de> for Repeated<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(RepeatedVisitor(PhantomData))
+    }
+}
+
+struct RepeatedVisitor<T>(PhantomData<T>);
+
+impl<'de, T: ProxiedInRepeated + SerdeValue> Visitor<'de> for RepeatedVisitor<T> {
+    type Value = Repeated<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence")
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Repeated<T>, A::Error> {
+        let mut repeated = Repeated::new();
+        while let Some(Owned(value)) = seq.next_element::<Owned<T>>()? {
+            repeated.as_mut().push(value);
+        }
+        Ok(repeated)
+    }
+}
+
+impl<K, V> Serialize for MapView<'_, K, V>
+where
+    K: SerdeMapKey,
+    V: ProxiedInMapValue<K> + SerdeValue,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self.iter() {
+            map.serialize_entry(&KeyOf::<K>(key), &ValueOf::<V>(value))?;
+        }
+        map.end()
+    }
+}
+
+impl<K, V> Serialize for Map<K, V>
+where
+    K: SerdeMapKey,
+    V: ProxiedInMapValue<K> + SerdeValue,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_view().serialize(serializer)
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for Map<K, V>
+where
+    K: SerdeMapKey,
+    V: ProxiedInMapValue<K> + SerdeValue,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(MapVisitor(PhantomData))
+    }
+}
+
+struct MapVisitor<K, V>(PhantomData<(K, V)>);
+
+impl<'de, K, V> Visitor<'de> for MapVisitor<K, V>
+where
+    K: SerdeMapKey,
+    V: ProxiedInMapValue<K> + SerdeValue,
+{
+    type Value = Map<K, V>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map")
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut access: A) -> Result<Map<K, V>, A::Error> {
+        let mut map = Map::new();
+        while let Some((KeyString(key), Owned(value))) =
+            access.next_entry::<KeyString, Owned<V>>()?
+        {
+            let Some(key) = K::parse_key(&key) else {
+                return Err(de::Error::invalid_value(Unexpected::Str(&key), &"a map key"));
+            };
+            map.as_mut().insert(key.as_view(), value);
+        }
+        Ok(map)
+    }
+}
+
+/// A map key as a string. Formats such as YAML have keys that are numbers or
+/// booleans, which are converted to their string forms.
+struct KeyString(String);
+
+impl<'de> Deserialize<'de> for KeyString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(KeyStringVisitor)
+        } else {
+            deserializer.deserialize_string(KeyStringVisitor)
+        }
+    }
+}
+
+struct KeyStringVisitor;
+
+impl Visitor<'_> for KeyStringVisitor {
+    type Value = KeyString;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string, an integer or a boolean")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<KeyString, E> {
+        Ok(KeyString(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<KeyString, E> {
+        Ok(KeyString(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<KeyString, E> {
+        Ok(KeyString(v.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<KeyString, E> {
+        Ok(KeyString(v.to_string()))
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<KeyString, E> {
+        Ok(KeyString(v.to_string()))
+    }
+}
+
+/// The JSON value of `google.protobuf.NullValue`, which is `null` rather than
+/// the name of its only value.
+const NULL_VALUE: &str = "google.protobuf.NullValue";
+
+/// Serializes the enum value `number` of the type `descriptor` by its name,
+/// or by its number if it is unknown or the format is not human-readable.
+pub fn serialize_enum<S: Serializer>(
+    descriptor: EnumDescriptor,
+    number: i32,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if descriptor.full_name() == NULL_VALUE {
+        return serializer.serialize_unit();
+    }
+    match descriptor.value_by_number(number) {
+        Some(value) if serializer.is_human_readable() => serializer.serialize_str(value.name()),
+        _ => serializer.serialize_i32(number),
+    }
+}
+
+/// Deserializes the number of a value of the enum type `descriptor` from its
+/// name or number.
+pub fn deserialize_enum<'de, D: Deserializer<'de>>(
+    descriptor: EnumDescriptor,
+    deserializer: D,
+) -> Result<i32, D::Error> {
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_any(EnumVisitor(descriptor))
+    } else {
+        deserializer.deserialize_i32(EnumVisitor(descriptor))
+    }
+}
+
+struct EnumVisitor(EnumDescriptor);
+
+impl Visitor<'_> for EnumVisitor {
+    type Value = i32;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a value of {}", self.0.full_name())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<i32, E> {
+        match self.0.values().find(|value| value.name() == v) {
+            Some(value) => Ok(value.number()),
+            None => Err(E::invalid_value(Unexpected::Str(v), &self)),
+        }
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<i32, E> {
+        i32::try_from(v).map_err(|_| E::invalid_value(Unexpected::Signed(v), &self))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<i32, E> {
+        i32::try_from(v).map_err(|_| E::invalid_value(Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<i32, E> {
+        if self.0.full_name() == NULL_VALUE {
+            Ok(0)
+        } else {
+            Err(E::invalid_type(Unexpected::Unit, &self))
+        }
+    }
+}
+
+/// Serializes a message as the value that its JSON text holds.
+pub fn serialize_message<T, S>(msg: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ToJson + ?Sized,
+    S: Serializer,
+{
+    let json = msg.to_json().map_err(ser::Error::custom)?;
+    let value: Json = serde_json::from_str(&json).map_err(ser::Error::custom)?;
+    value.serialize(serializer)
+}
+
+/// Deserializes a message by parsing the JSON text of the deserialized value.
+pub fn deserialize_message<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromJson,
+    D: Deserializer<'de>,
+{
+    let value = Json::deserialize(deserializer)?;
+    let json = serde_json::to_string(&value).map_err(de::Error::custom)?;
+    T::from_json(&json).map_err(de::Error::custom)
+}
+
+/// A JSON value whose objects keep the order of their members, so that the
+/// fields of a message keep the order in which the kernel prints them.
+enum Json {
+    Null,
+    Bool(bool),
+    Number(serde_json::Number),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Serialize for Json {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Json::Null => serializer.serialize_unit(),
+            Json::Bool(b) => serializer.serialize_bool(*b),
+            Json::Number(n) => n.serialize(serializer),
+            Json::String(s) => serializer.serialize_str(s),
+            Json::Array(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+            Json::Object(members) => {
+                let mut map = serializer.serialize_map(Some(members.len()))?;
+                for (key, value) in members {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Json {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonVisitor)
+    }
+}
+
+struct JsonVisitor;
+
+impl<'de> Visitor<'de> for JsonVisitor {
+    type Value = Json;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a value that JSON can represent")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Json, E> {
+        Ok(Json::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Json, E> {
+        Ok(Json::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Json, D::Error> {
+        Json::deserialize(deserializer)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Json, E> {
+        Ok(Json::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Json, E> {
+        Ok(Json::Number(v.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Json, E> {
+        Ok(Json::Number(v.into()))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Json, E> {
+        // JSON has no infinities or NaN, but the JSON mapping accepts them as
+        // strings.
+        Ok(match serde_json::Number::from_f64(v) {
+            Some(n) => Json::Number(n),
+            None if v.is_nan() => Json::String("NaN".to_string()),
+            None if v > 0.0 => Json::String("Infinity".to_string()),
+            None => Json::String("-Infinity".to_string()),
+        })
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Json, E> {
+        Ok(Json::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Json, E> {
+        Ok(Json::String(v))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Json, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Json::Array(values))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut access: A) -> Result<Json, A::Error> {
+        let mut members = Vec::with_capacity(access.size_hint().unwrap_or(0));
+        while let Some((KeyString(key), value)) = access.next_entry()? {
+            members.push((key, value));
+        }
+        Ok(Json::Object(members))
+    }
+}
+
+/// Implements the `serde` traits for a generated message, its view and its
+/// mut.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_message_serde {
+    ($msg:ident, $view:ident, $mut:ident) => {
+        impl $crate::__internal::serde::Serialize for $msg {
+            fn serialize<S: $crate::__internal::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> ::std::result::Result<S::Ok, S::Error> {
+                $crate::__internal::serde_support::serialize_message(self, serializer)
+            }
+        }
+
+        impl $crate::__internal::serde::Serialize for $view<'_> {
+            fn serialize<S: $crate::__internal::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> ::std::result::Result<S::Ok, S::Error> {
+                $crate::__internal::serde_support::serialize_message(self, serializer)
+            }
+        }
+
+        impl $crate::__internal::serde::Serialize for $mut<'_> {
+            fn serialize<S: $crate::__internal::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> ::std::result::Result<S::Ok, S::Error> {
+                $crate::__internal::serde_support::serialize_message(self, serializer)
+            }
+        }
+
+        impl<'de> $crate::__internal::serde::Deserialize<'de> for $msg {
+            fn deserialize<D: $crate::__internal::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> ::std::result::Result<Self, D::Error> {
+                $crate::__internal::serde_support::deserialize_message(deserializer)
+            }
+        }
+
+        impl $crate::__internal::serde_support::SerdeValue for $msg {
+            fn serialize_view<S: $crate::__internal::serde::Serializer>(
+                view: &$view<'_>,
+                serializer: S,
+            ) -> ::std::result::Result<S::Ok, S::Error> {
+                $crate::__internal::serde_support::serialize_message(view, serializer)
+            }
+
+            fn deserialize_value<'de, D: $crate::__internal::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> ::std::result::Result<Self, D::Error> {
+                $crate::__internal::serde_support::deserialize_message(deserializer)
+            }
+        }
+    };
+}
+
+/// Implements the `serde` traits for a generated enum.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_enum_serde {
+    ($enum:ident) => {
+        impl $crate::__internal::serde::Serialize for $enum {
+            fn serialize<S: $crate::__internal::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> ::std::result::Result<S::Ok, S::Error> {
+                $crate::__internal::serde_support::serialize_enum(
+                    <Self as $crate::reflect::ReflectEnum>::descriptor(),
+                    i32::from(*self),
+                    serializer,
+                )
+            }
+        }
+
+        impl<'de> $crate::__internal::serde::Deserialize<'de> for $enum {
+            /// Fails for a number that is not a value of a closed enum.
+            fn deserialize<D: $crate::__internal::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> ::std::result::Result<Self, D::Error> {
+                let number = $crate::__internal::serde_support::deserialize_enum(
+                    <Self as $crate::reflect::ReflectEnum>::descriptor(),
+                    deserializer,
+                )?;
+                <Self as ::std::convert::TryFrom<i32>>::try_from(number)
+                    .map_err(<D::Error as $crate::__internal::serde::de::Error>::custom)
+            }
+        }
+
+        impl $crate::__internal::serde_support::SerdeValue for $enum {
+            fn serialize_view<S: $crate::__internal::serde::Serializer>(
+                view: &$enum,
+                serializer: S,
+            ) -> ::std::result::Result<S::Ok, S::Error> {
+                $crate::__internal::serde::Serialize::serialize(view, serializer)
+            }
+
+            fn deserialize_value<'de, D: $crate::__internal::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> ::std::result::Result<Self, D::Error> {
+                <Self as $crate::__internal::serde::Deserialize<'de>>::deserialize(deserializer)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::__internal::SealedInternal;
+    use crate::reflect::{
+        DescriptorPool, DynamicMessage, MessageDescriptor, MessageRef, MessageRefMut,
+        ReflectMessage, ReflectMessageMut,
+    };
+    use googletest::prelude::*;
+    use std::sync::OnceLock;
+
+    fn varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    /// Encodes a length-delimited field.
+    fn delimited(number: u32, contents: impl AsRef<[u8]>) -> Vec<u8> {
+        let contents = contents.as_ref();
+        let mut out = Vec::new();
+        varint(u64::from(number << 3 | 2), &mut out);
+        varint(contents.len() as u64, &mut out);
+        out.extend_from_slice(contents);
+        out
+    }
+
+    /// Encodes a varint field.
+    fn int(number: u32, value: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        varint(u64::from(number << 3), &mut out);
+        varint(value, &mut out);
+        out
+    }
+
+    /// Encodes an optional `FieldDescriptorProto` of a scalar type.
+    fn field(name: &str, number: u64, r#type: u64) -> Vec<u8> {
+        [delimited(1, name), int(3, number), int(4, 1), int(5, r#type)].concat()
+    }
+
+    fn timestamp_file() -> Vec<u8> {
+        let timestamp = [
+            delimited(1, "Timestamp"),
+            delimited(2, field("seconds", 1, 3)),
+            delimited(2, field("nanos", 2, 5)),
+        ];
+        [
+            delimited(1, "google/protobuf/timestamp.proto"),
+            delimited(2, "google.protobuf"),
+            delimited(4, timestamp.concat()),
+            delimited(12, "proto3"),
+        ]
+        .concat()
+    }
+
+    fn color_file() -> Vec<u8> {
+        let value = |name: &str, number: i64| [delimited(1, name), int(2, number as u64)].concat();
+        let color = [
+            delimited(1, "Color"),
+            delimited(2, value("COLOR_UNSPECIFIED", 0)),
+            delimited(2, value("RED", 1)),
+            delimited(2, value("BLACK", -1)),
+        ];
+        [
+            delimited(1, "serde/color.proto"),
+            delimited(2, "serde"),
+            delimited(5, color.concat()),
+            delimited(12, "proto3"),
+        ]
+        .concat()
+    }
+
+    fn pool() -> &'static DescriptorPool {
+        static POOL: OnceLock<DescriptorPool> = OnceLock::new();
+        POOL.get_or_init(|| {
+            let set = [delimited(1, timestamp_file()), delimited(1, color_file())].concat();
+            DescriptorPool::from_file_descriptor_set(&set).unwrap()
+        })
+    }
+
+    /// A `google.protobuf.Timestamp` that implements the `serde` traits like
+    /// a generated message.
+    #[derive(Debug)]
+    struct Timestamp(DynamicMessage);
+
+    impl SealedInternal for Timestamp {}
+
+    impl ReflectMessage for Timestamp {
+        fn descriptor() -> MessageDescriptor {
+            pool().message_by_name("google.protobuf.Timestamp").unwrap()
+        }
+
+        fn as_message_ref(&self) -> MessageRef<'_> {
+            self.0.as_message_ref()
+        }
+    }
+
+    impl ReflectMessageMut for Timestamp {
+        fn as_message_mut(&mut self) -> MessageRefMut<'_> {
+            self.0.as_message_mut()
+        }
+    }
+
+    impl Default for Timestamp {
+        fn default() -> Self {
+            Timestamp(DynamicMessage::new(Self::descriptor()))
+        }
+    }
+
+    impl Serialize for Timestamp {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize_message(self, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Timestamp {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize_message(deserializer)
+        }
+    }
+
+    /// A value of `serde.Color` that implements the `serde` traits like a
+    /// generated open enum.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Color(i32);
+
+    fn color() -> EnumDescriptor {
+        pool().enum_by_name("serde.Color").unwrap()
+    }
+
+    impl Serialize for Color {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize_enum(color(), self.0, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Color {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize_enum(color(), deserializer).map(Color)
+        }
+    }
+
+    #[googletest::test]
+    fn int64_round_trips_as_string() {
+        let mut repeated = Repeated::<i64>::new();
+        repeated.as_mut().extend([i64::MIN, -1, i64::MAX]);
+        let json = serde_json::to_string(&repeated).unwrap();
+        expect_that!(json, eq(r#"["-9223372036854775808","-1","9223372036854775807"]"#));
+        let back: Repeated<i64> = serde_json::from_str(&json).unwrap();
+        expect_that!(back.as_view().iter().collect::<Vec<_>>(), eq(vec![i64::MIN, -1, i64::MAX]));
+
+        // Numbers are accepted too.
+        let parsed: Repeated<u64> = serde_json::from_str(r#"[1, "2"]"#).unwrap();
+        expect_that!(parsed.as_view().iter().collect::<Vec<_>>(), eq(vec![1, 2]));
+    }
+
+    #[googletest::test]
+    fn bytes_round_trip_as_base64() {
+        let bytes = ProtoBytes::from(&b"\x00\xfbproto"[..]);
+        let json = serde_json::to_string(&bytes).unwrap();
+        expect_that!(json, eq(r#""APtwcm90bw==""#));
+        let back: ProtoBytes = serde_json::from_str(&json).unwrap();
+        expect_that!(back.as_view(), eq(b"\x00\xfbproto"));
+        expect_that!(serde_json::from_str::<ProtoBytes>(r#""a*b=""#), err(anything()));
+    }
+
+    #[googletest::test]
+    fn enum_round_trips_by_name_and_number() {
+        expect_that!(serde_json::to_string(&Color(1)).unwrap(), eq(r#""RED""#));
+        expect_that!(serde_json::to_string(&Color(-1)).unwrap(), eq(r#""BLACK""#));
+        // An unknown value of an open enum is printed by number.
+        expect_that!(serde_json::to_string(&Color(7)).unwrap(), eq("7"));
+
+        expect_that!(serde_json::from_str::<Color>(r#""RED""#).unwrap(), eq(Color(1)));
+        expect_that!(serde_json::from_str::<Color>("1").unwrap(), eq(Color(1)));
+        expect_that!(serde_json::from_str::<Color>("-1").unwrap(), eq(Color(-1)));
+        expect_that!(serde_json::from_str::<Color>("7").unwrap(), eq(Color(7)));
+        expect_that!(serde_json::from_str::<Color>(r#""GREEN""#), err(anything()));
+        expect_that!(serde_json::from_str::<Color>("3000000000"), err(anything()));
+    }
+
+    #[googletest::test]
+    fn repeated_round_trips() {
+        let mut repeated = Repeated::<ProtoString>::new();
+        repeated.as_mut().extend(["a", "b"]);
+        let json = serde_json::to_string(&repeated).unwrap();
+        expect_that!(json, eq(r#"["a","b"]"#));
+        let back: Repeated<ProtoString> = serde_json::from_str(&json).unwrap();
+        expect_that!(
+            back.as_view().iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+            eq(vec!["a", "b"])
+        );
+
+        let floats: Repeated<f64> = serde_json::from_str(r#"["NaN", "-Infinity", 1.5]"#).unwrap();
+        expect_that!(serde_json::to_string(&floats).unwrap(), eq(r#"["NaN","-Infinity",1.5]"#));
+        expect_that!(serde_json::from_str::<Repeated<i32>>("[1.5]"), err(anything()));
+    }
+
+    #[googletest::test]
+    fn map_with_non_string_keys_round_trips() {
+        let mut map = Map::<i32, ProtoString>::new();
+        map.as_mut().insert(-3, "a");
+        let json = serde_json::to_string(&map).unwrap();
+        expect_that!(json, eq(r#"{"-3":"a"}"#));
+        let back: Map<i32, ProtoString> = serde_json::from_str(&json).unwrap();
+        expect_that!(back.as_view().get(-3).map(|s| s.to_string()), some(eq("a")));
+        expect_that!(
+            serde_json::from_str::<Map<i32, ProtoString>>(r#"{"x":"a"}"#),
+            err(anything())
+        );
+
+        let mut map = Map::<bool, i64>::new();
+        map.as_mut().insert(true, 5);
+        let json = serde_json::to_string(&map).unwrap();
+        expect_that!(json, eq(r#"{"true":"5"}"#));
+        let back: Map<bool, i64> = serde_json::from_str(&json).unwrap();
+        expect_that!(back.as_view().get(true), some(eq(5)));
+    }
+
+    #[googletest::test]
+    fn timestamp_round_trips_as_rfc3339() {
+        let descriptor = Timestamp::descriptor();
+        let timestamp = Timestamp(
+            DynamicMessage::parse_text_format(descriptor, "seconds: 1 nanos: 500000000").unwrap(),
+        );
+        let json = serde_json::to_string(&timestamp).unwrap();
+        expect_that!(json, eq(r#""1970-01-01T00:00:01.500Z""#));
+        let back: Timestamp = serde_json::from_str(&json).unwrap();
+        expect_that!(back.0.to_text_format(), eq(timestamp.0.to_text_format()));
+        expect_that!(serde_json::from_str::<Timestamp>(r#""yesterday""#), err(anything()));
+    }
+
+    #[googletest::test]
+    fn base64_round_trips() {
+        for len in 0..8 {
+            let bytes: Vec<u8> = (0..len).map(|i| 0xf0 ^ (i * 37) as u8).collect();
+            let encoded = encode_base64(&bytes);
+            expect_that!(encoded.len() % 4, eq(0));
+            expect_that!(decode_base64(&encoded), some(eq(bytes.clone())));
+        }
+        expect_that!(encode_base64(b"proto"), eq("cHJvdG8="));
+    }
+
+    #[googletest::test]
+    fn base64_accepts_url_safe_and_unpadded() {
+        expect_that!(decode_base64("-_8"), some(eq(vec![0xfb, 0xff])));
+        expect_that!(decode_base64("+/8="), some(eq(vec![0xfb, 0xff])));
+        expect_that!(decode_base64("cHJvdG8"), some(eq(b"proto".to_vec())));
+        expect_that!(decode_base64("a"), none());
+        expect_that!(decode_base64("a*b="), none());
+    }
+}
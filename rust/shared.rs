@@ -7,6 +7,7 @@
 
 #![deny(unsafe_op_in_unsafe_fn)]
 
+use crate::__internal::Private;
 use std::fmt;
 
 // There are a number of manual `Debug` and similar impls instead of using their
@@ -19,18 +20,30 @@ pub use crate::codegen_traits::{
     interop::{MessageMutInterop, MessageViewInterop, OwnedMessageInterop},
     read::Serialize,
     write::{Clear, ClearAndParse, MergeFrom},
-    Message, MessageMut, MessageView,
+    Message, MessageMut, MessageName, MessageView,
 };
 pub use crate::cord::{ProtoBytesCow, ProtoStringCow};
+pub use crate::extension::{ExtensionId, ExtensionRegistry, ExtensionType};
+pub use crate::json::{FromJson, JsonError, ToJson};
 pub use crate::map::{Map, MapIter, MapMut, MapView, ProxiedInMapValue};
 pub use crate::optional::Optional;
+pub use crate::options::{
+    JsonParseOptions, JsonPrintOptions, ParseOptions, SerializeOptions, TextFormatPrintOptions,
+};
+pub use crate::parsed_view::ParsedView;
 pub use crate::proxied::{
     AsMut, AsView, IntoMut, IntoProxied, IntoView, Mut, MutProxied, MutProxy, Proxied, Proxy, View,
     ViewProxy,
 };
 pub use crate::r#enum::{Enum, UnknownEnumValue};
-pub use crate::repeated::{ProxiedInRepeated, Repeated, RepeatedIter, RepeatedMut, RepeatedView};
+pub use crate::repeated::{
+    ContiguousRepeated, ProxiedInRepeated, Repeated, RepeatedIter, RepeatedMut, RepeatedView,
+};
 pub use crate::string::{ProtoBytes, ProtoStr, ProtoString, Utf8Error};
+pub use crate::text_format::{FromTextFormat, MergeTextFormat, TextFormatError, ToTextFormat};
+pub use crate::type_registry::TypeRegistry;
+pub use crate::unknown_fields::{UnknownFields, UnknownFieldsIter, UnknownValue, WireType};
+pub use crate::well_known_types::{StructValue, TimeError};
 
 pub mod prelude;
 
@@ -54,15 +67,33 @@ pub mod __internal;
 
 mod codegen_traits;
 mod cord;
+pub mod delimited;
+pub mod differencer;
 #[path = "enum.rs"]
 mod r#enum;
+mod extension;
+pub mod field_mask;
+mod json;
 mod map;
 mod optional;
+mod options;
+mod parsed_view;
 mod primitive;
 mod proto_macro;
 mod proxied;
+pub mod reflect;
 mod repeated;
+#[cfg(feature = "serde")]
+mod serde_support;
 mod string;
+mod text_format;
+#[cfg(any(not(bzl), upb_kernel))]
+mod text_format_parser;
+#[cfg(any(not(bzl), upb_kernel))]
+mod text_format_printer;
+mod type_registry;
+mod unknown_fields;
+mod well_known_types;
 
 #[cfg(not(bzl))]
 #[path = "upb/lib.rs"]
@@ -81,26 +112,282 @@ use utf8;
 #[no_mangle]
 extern "C" fn __Disallow_Upb_And_Cpp_In_Same_Binary() {}
 
+/// The reason that parsing failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// The input is not valid wire format data for the message.
+    Malformed,
+    /// A `string` field contains invalid UTF-8.
+    BadUtf8,
+    /// Messages in the input are nested deeper than the recursion limit.
+    MaxDepthExceeded,
+    /// The parsed message does not have all of its required fields set.
+    MissingRequired,
+    /// The input contains a submessage whose type was not linked in.
+    UnlinkedSubMessage,
+    /// The parser failed to allocate memory.
+    OutOfMemory,
+    /// The input is longer than the maximum input length set in the
+    /// [`ParseOptions`].
+    InputTooLong,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ParseErrorKind::Malformed => "malformed wire format data",
+            ParseErrorKind::BadUtf8 => "string field contains invalid UTF-8",
+            ParseErrorKind::MaxDepthExceeded => "message nesting exceeds the recursion limit",
+            ParseErrorKind::MissingRequired => "missing required fields",
+            ParseErrorKind::UnlinkedSubMessage => "submessage type is not linked",
+            ParseErrorKind::OutOfMemory => "out of memory",
+            ParseErrorKind::InputTooLong => "input exceeds the maximum input length",
+        })
+    }
+}
+
 /// An error that happened during parsing.
-#[derive(Debug, Clone)]
-pub struct ParseError;
+///
+/// Besides the [`kind`](ParseError::kind) of failure, the error may carry the
+/// byte offset into the input and the dotted path of the field where the
+/// failure was detected. Which of these details are available depends on the
+/// kernel and on the kind of failure; only the kind is always present.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    offset: Option<usize>,
+    field_path: Option<String>,
+}
+
+impl ParseError {
+    #[doc(hidden)]
+    pub fn new(_private: Private, kind: ParseErrorKind) -> Self {
+        ParseError { kind, offset: None, field_path: None }
+    }
+
+    #[doc(hidden)]
+    pub fn with_offset(mut self, _private: Private, offset: usize) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    #[doc(hidden)]
+    pub fn with_field_path(mut self, _private: Private, field_path: impl Into<String>) -> Self {
+        self.field_path = Some(field_path.into());
+        self
+    }
+
+    /// Returns the reason that parsing failed.
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    /// Returns the offset into the input at which the failure was detected,
+    /// if known.
+    ///
+    /// This points at the start of the tag of the offending field.
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    /// Returns the dotted path (e.g. `"child.payload.optional_string"`) of the
+    /// field where the failure was detected, if known.
+    ///
+    /// For [`ParseErrorKind::MissingRequired`] this is the first unset
+    /// required field.
+    pub fn field_path(&self) -> Option<&str> {
+        self.field_path.as_deref()
+    }
+}
 
 impl std::error::Error for ParseError {}
 
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Couldn't deserialize given bytes into a proto")
+        write!(f, "Couldn't deserialize given bytes into a proto: {}", self.kind)?;
+        if let Some(field_path) = &self.field_path {
+            write!(f, " (field `{field_path}`)")?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " at byte offset {offset}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The reason that serialization failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SerializeErrorKind {
+    /// The message or one of its submessages does not have all of its
+    /// required fields set. Only reported if
+    /// [`SerializeOptions::check_required`] is set.
+    MissingRequired,
+    /// Messages are nested deeper than the recursion limit.
+    MaxDepthExceeded,
+    /// The serialized message would exceed the 2GiB size limit.
+    TooLarge,
+    /// The serialized message does not fit into the provided buffer.
+    BufferTooSmall,
+    /// The serializer failed to allocate memory.
+    OutOfMemory,
+}
+
+impl fmt::Display for SerializeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            SerializeErrorKind::MissingRequired => "missing required fields",
+            SerializeErrorKind::MaxDepthExceeded => "message nesting exceeds the recursion limit",
+            SerializeErrorKind::TooLarge => "message exceeds the maximum size of 2GiB",
+            SerializeErrorKind::BufferTooSmall => "message does not fit into the buffer",
+            SerializeErrorKind::OutOfMemory => "out of memory",
+        })
+    }
+}
+
+/// An error that happened while parsing a message from an [`io::Read`].
+///
+/// [`io::Read`]: std::io::Read
+#[derive(Debug)]
+pub enum ReadError {
+    /// The data that was read could not be parsed.
+    Parse(ParseError),
+    /// Reading the data failed.
+    Io(std::io::Error),
+    /// The reader produced more than the allowed maximum number of bytes.
+    TooLarge {
+        /// The maximum number of bytes that was allowed.
+        max_size: usize,
+    },
+}
+
+impl From<ParseError> for ReadError {
+    fn from(err: ParseError) -> Self {
+        ReadError::Parse(err)
+    }
+}
+
+impl From<std::io::Error> for ReadError {
+    fn from(err: std::io::Error) -> Self {
+        ReadError::Io(err)
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Parse(err) => Some(err),
+            ReadError::Io(err) => Some(err),
+            ReadError::TooLarge { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Parse(err) => fmt::Display::fmt(err, f),
+            ReadError::Io(err) => write!(f, "Couldn't read serialized proto: {err}"),
+            ReadError::TooLarge { max_size } => {
+                write!(f, "Serialized proto exceeds the maximum size of {max_size} bytes")
+            }
+        }
     }
 }
 
 /// An error that happened during serialization.
-#[derive(Debug, Clone)]
-pub struct SerializeError;
+///
+/// For [`SerializeErrorKind::MissingRequired`] the error also lists the
+/// dotted paths (e.g. `"child.repeated_child[2].a"`) of the required fields
+/// that are unset. Map values are addressed by their `Debug`-formatted key
+/// (e.g. `"map_field[\"key\"].a"` or `"map_field[7].a"`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerializeError {
+    kind: SerializeErrorKind,
+    missing_required_fields: Vec<String>,
+}
+
+impl SerializeError {
+    #[doc(hidden)]
+    pub fn new(_private: Private, kind: SerializeErrorKind) -> Self {
+        SerializeError { kind, missing_required_fields: Vec::new() }
+    }
+
+    #[doc(hidden)]
+    pub fn with_missing_required_fields(
+        mut self,
+        _private: Private,
+        missing_required_fields: Vec<String>,
+    ) -> Self {
+        self.missing_required_fields = missing_required_fields;
+        self
+    }
+
+    /// Returns the reason that serialization failed.
+    pub fn kind(&self) -> SerializeErrorKind {
+        self.kind
+    }
+
+    /// Returns the paths of the unset required fields.
+    ///
+    /// This is empty unless the kind is
+    /// [`SerializeErrorKind::MissingRequired`]. Required fields of extensions
+    /// are not listed.
+    pub fn missing_required_fields(&self) -> &[String] {
+        &self.missing_required_fields
+    }
+}
 
 impl std::error::Error for SerializeError {}
 
 impl fmt::Display for SerializeError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Couldn't serialize proto into bytes (depth too deep or missing required fields)")
+        write!(f, "Couldn't serialize proto into bytes: {}", self.kind)?;
+        if !self.missing_required_fields.is_empty() {
+            write!(f, ": {}", self.missing_required_fields.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// An error that happened while serializing a message to an [`io::Write`].
+///
+/// [`io::Write`]: std::io::Write
+#[derive(Debug)]
+pub enum WriteError {
+    /// The message could not be serialized.
+    Serialize(SerializeError),
+    /// Writing the serialized message failed.
+    Io(std::io::Error),
+}
+
+impl From<SerializeError> for WriteError {
+    fn from(err: SerializeError) -> Self {
+        WriteError::Serialize(err)
+    }
+}
+
+impl From<std::io::Error> for WriteError {
+    fn from(err: std::io::Error) -> Self {
+        WriteError::Io(err)
+    }
+}
+
+impl std::error::Error for WriteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WriteError::Serialize(err) => Some(err),
+            WriteError::Io(err) => Some(err),
+        }
+    }
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WriteError::Serialize(err) => fmt::Display::fmt(err, f),
+            WriteError::Io(err) => write!(f, "Couldn't write serialized proto: {err}"),
+        }
     }
 }
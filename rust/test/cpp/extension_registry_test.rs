@@ -0,0 +1,25 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use optimize_for_lite_rust_proto::OptimizeForLiteTestMessage;
+use protobuf::prelude::*;
+use protobuf::{ExtensionRegistry, ParseOptions};
+use unittest_rust_proto::OPTIONAL_INT32_EXTENSION;
+
+#[googletest::test]
+fn test_lite_message_ignores_extension_registry() {
+    let mut msg = OptimizeForLiteTestMessage::new();
+    msg.set_value("parsed");
+    let serialized = msg.serialize().unwrap();
+
+    let mut registry = ExtensionRegistry::new();
+    registry.add(&OPTIONAL_INT32_EXTENSION);
+    let options = ParseOptions::new().extension_registry(&registry);
+    let parsed = OptimizeForLiteTestMessage::parse_with_options(&serialized, &options).unwrap();
+    assert_that!(parsed.value(), eq("parsed"));
+}
@@ -0,0 +1,48 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use optimize_for_lite_rust_proto::OptimizeForLiteTestMessage;
+use protobuf::reflect::{ReflectMessage, ReflectMessageMut};
+use protobuf_gtest_matchers::proto_eq;
+
+#[googletest::test]
+fn test_lite_message_does_not_support_reflection() {
+    let msg = OptimizeForLiteTestMessage::new();
+    assert_that!(msg.as_message_ref().supports_reflection(), eq(false));
+    assert_that!(
+        OptimizeForLiteTestMessage::descriptor().full_name(),
+        eq("optimize_for_lite_test.OptimizeForLiteTestMessage")
+    );
+}
+
+#[test]
+#[should_panic(expected = "is a lite message, which doesn't support reflection")]
+fn test_lite_message_get_field_panics() {
+    let msg = OptimizeForLiteTestMessage::new();
+    let field = OptimizeForLiteTestMessage::descriptor().field_by_name("value").unwrap();
+    let _ = msg.get_field(&field);
+}
+
+#[test]
+#[should_panic(expected = "is a lite message, which doesn't support reflection")]
+fn test_lite_message_set_field_panics() {
+    let mut msg = OptimizeForLiteTestMessage::new();
+    let field = OptimizeForLiteTestMessage::descriptor().field_by_name("value").unwrap();
+    msg.set_field(&field, "value");
+}
+
+#[googletest::test]
+fn test_lite_message_proto_eq_explains_mismatch() {
+    let mut expected = OptimizeForLiteTestMessage::new();
+    expected.set_value("expected");
+    let mut actual = OptimizeForLiteTestMessage::new();
+    actual.set_value("actual");
+    expect_that!(&actual, not(proto_eq(expected.clone())));
+    let explanation = proto_eq(expected).explain_match(&actual).to_string();
+    expect_that!(explanation, contains_substring("which is not equal to"));
+}
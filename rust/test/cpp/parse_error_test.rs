@@ -0,0 +1,35 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use map_unittest_rust_proto::TestRequiredMessageMap;
+use protobuf::prelude::*;
+use protobuf::ParseErrorKind;
+use unittest_rust_proto::{TestAllTypes, TestRequired};
+
+#[googletest::test]
+fn parse_error_reports_offset_and_field_path() {
+    // A valid `optional_int32` field followed by a truncated varint.
+    let err = TestAllTypes::parse(&[0x08, 0x01, 0x08, 0x80]).unwrap_err();
+    assert_that!(err.kind(), eq(ParseErrorKind::Malformed));
+    assert_that!(err.offset(), some(eq(2)));
+    assert_that!(err.field_path(), some(eq("optional_int32")));
+}
+
+#[googletest::test]
+fn parse_error_reports_missing_required_field_with_comma_in_map_key() {
+    let mut value = TestRequired::new();
+    value.set_a(1);
+    value.set_b(2);
+    let mut msg = TestRequiredMessageMap::new();
+    msg.map_string_field_mut().insert("a,b", value);
+    let serialized = msg.serialize().unwrap();
+
+    let err = TestRequiredMessageMap::parse(&serialized).unwrap_err();
+    assert_that!(err.kind(), eq(ParseErrorKind::MissingRequired));
+    assert_that!(err.field_path(), some(eq(r#"map_string_field["a,b"].c"#)));
+}
@@ -322,3 +322,150 @@ fn test_repeated_bytes() {
     older_msg.repeated_bytes_mut().clear();
     assert_that!(older_msg.repeated_bytes(), empty());
 }
+
+fn nested(bb: i32) -> NestedMessage {
+    let mut nested = NestedMessage::new();
+    nested.set_bb(bb);
+    nested
+}
+
+fn bbs(msg: &TestAllTypes) -> Vec<i32> {
+    msg.repeated_nested_message().iter().map(|m| m.bb()).collect()
+}
+
+#[googletest::test]
+fn test_repeated_message_rearrange() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_nested_message_mut().extend([3, 1, 2, 1].map(nested));
+
+    msg.repeated_nested_message_mut().sort_by_key(|m| m.bb());
+    assert_that!(bbs(&msg), eq(vec![1, 1, 2, 3]));
+
+    msg.repeated_nested_message_mut().dedup();
+    assert_that!(bbs(&msg), eq(vec![1, 2, 3]));
+
+    msg.repeated_nested_message_mut().reverse();
+    assert_that!(bbs(&msg), eq(vec![3, 2, 1]));
+
+    msg.repeated_nested_message_mut().swap(0, 2);
+    assert_that!(bbs(&msg), eq(vec![1, 2, 3]));
+
+    msg.repeated_nested_message_mut().insert(1, nested(4));
+    assert_that!(bbs(&msg), eq(vec![1, 4, 2, 3]));
+
+    msg.repeated_nested_message_mut().retain(|m| m.bb() != 2);
+    assert_that!(bbs(&msg), eq(vec![1, 4, 3]));
+
+    msg.repeated_nested_message_mut().truncate(2);
+    assert_that!(bbs(&msg), eq(vec![1, 4]));
+
+    // Values pushed after a truncate don't bring back the dropped ones.
+    msg.repeated_nested_message_mut().push(NestedMessage::new());
+    assert_that!(bbs(&msg), eq(vec![1, 4, 0]));
+}
+
+#[googletest::test]
+fn test_repeated_message_remove() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_nested_message_mut().extend([1, 2, 3, 4, 5].map(nested));
+
+    assert_that!(msg.repeated_nested_message_mut().pop().map(|m| m.bb()), some(eq(5)));
+    assert_that!(msg.repeated_nested_message_mut().remove(0).bb(), eq(1));
+    assert_that!(bbs(&msg), eq(vec![2, 3, 4]));
+
+    let drained: Vec<i32> = msg.repeated_nested_message_mut().drain(1..).map(|m| m.bb()).collect();
+    assert_that!(drained, eq(vec![3, 4]));
+    assert_that!(bbs(&msg), eq(vec![2]));
+
+    msg.repeated_nested_message_mut().extend([6, 7].map(nested));
+    let tail = msg.repeated_nested_message_mut().split_off(1);
+    assert_that!(tail.as_view().iter().map(|m| m.bb()).collect::<Vec<_>>(), eq(vec![6, 7]));
+    assert_that!(bbs(&msg), eq(vec![2]));
+
+    // The removed values outlive the message they were removed from.
+    let popped = msg.repeated_nested_message_mut().pop();
+    drop(msg);
+    assert_that!(popped.map(|m| m.bb()), some(eq(2)));
+    assert_that!(tail.as_view().get(1).map(|m| m.bb()), some(eq(7)));
+}
+
+#[googletest::test]
+fn test_repeated_enum_rearrange() {
+    use test_all_types::NestedEnum;
+
+    let mut msg = TestAllTypes::new();
+    msg.repeated_nested_enum_mut().extend([
+        NestedEnum::Baz,
+        NestedEnum::Foo,
+        NestedEnum::Foo,
+        NestedEnum::Bar,
+    ]);
+    msg.repeated_nested_enum_mut().dedup();
+    msg.repeated_nested_enum_mut().reverse();
+    assert_that!(
+        msg.repeated_nested_enum(),
+        elements_are![eq(NestedEnum::Bar), eq(NestedEnum::Foo), eq(NestedEnum::Baz)]
+    );
+    assert_that!(msg.repeated_nested_enum_mut().remove(1), eq(NestedEnum::Foo));
+    assert_that!(msg.repeated_nested_enum_mut().pop(), some(eq(NestedEnum::Baz)));
+    assert_that!(msg.repeated_nested_enum(), elements_are![eq(NestedEnum::Bar)]);
+}
+
+#[googletest::test]
+fn test_repeated_strings_rearrange() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_string_mut().extend(["b", "c", "a"]);
+    msg.repeated_string_mut().sort_by(|a, b| a.cmp(b));
+    assert_that!(msg.repeated_string(), elements_are![eq("a"), eq("b"), eq("c")]);
+
+    let drained: Vec<String> =
+        msg.repeated_string_mut().drain(..2).map(|s| s.as_view().to_string()).collect();
+    assert_that!(drained, eq(vec!["a".to_string(), "b".to_string()]));
+    assert_that!(msg.repeated_string(), elements_are![eq("c")]);
+}
+
+#[test]
+#[should_panic(expected = "attempted to index slice up to maximum usize")]
+fn test_repeated_drain_inclusive_end_overflow() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().extend([1, 2]);
+    let _ = msg.repeated_int32_mut().drain(0..=usize::MAX);
+}
+
+#[test]
+#[should_panic(expected = "attempted to index slice from after maximum usize")]
+fn test_repeated_drain_exclusive_start_overflow() {
+    use std::ops::Bound;
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().extend([1, 2]);
+    let _ = msg.repeated_int32_mut().drain((Bound::Excluded(usize::MAX), Bound::Unbounded));
+}
+
+#[googletest::test]
+fn test_repeated_iter_double_ended() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().extend([1, 2, 3, 4]);
+    let mut iter = msg.repeated_int32().iter();
+    assert_that!(iter.next_back(), some(eq(4)));
+    assert_that!(iter.next(), some(eq(1)));
+    assert_that!(iter.len(), eq(2));
+    assert_that!(iter.rev().collect::<Vec<_>>(), eq(vec![3, 2]));
+}
+
+#[googletest::test]
+fn test_repeated_slices() {
+    let mut msg = TestAllTypes::new();
+    assert_that!(msg.repeated_float().as_slice(), empty());
+
+    msg.repeated_float_mut().extend_from_slice(&[1.0, 2.5]);
+    msg.repeated_float_mut().push(3.0);
+    assert_that!(msg.repeated_float().as_slice(), eq(&[1.0, 2.5, 3.0][..]));
+
+    msg.repeated_float_mut().as_mut_slice().reverse();
+    assert_that!(msg.repeated_float(), elements_are![eq(3.0), eq(2.5), eq(1.0)]);
+
+    let values: Vec<i64> = (0..1000).collect();
+    msg.repeated_int64_mut().extend_from_slice(&values);
+    assert_that!(msg.repeated_int64().as_slice(), eq(&values[..]));
+    assert_that!(msg.repeated_int64_mut().as_slice().iter().sum::<i64>(), eq(499500));
+}
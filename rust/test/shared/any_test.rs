@@ -0,0 +1,96 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use any_rust_proto::Any;
+use googletest::prelude::*;
+use protobuf::prelude::*;
+use protobuf::{MessageName, TypeRegistry};
+use unittest_proto3_rust_proto::{ForeignMessage, TestAllTypes};
+
+fn foreign(c: i32) -> ForeignMessage {
+    let mut msg = ForeignMessage::new();
+    msg.set_c(c);
+    msg
+}
+
+#[googletest::test]
+fn test_message_name() {
+    assert_that!(ForeignMessage::FULL_NAME, eq("proto3_unittest.ForeignMessage"));
+    assert_that!(
+        ForeignMessage::TYPE_URL,
+        eq("type.googleapis.com/proto3_unittest.ForeignMessage")
+    );
+    assert_that!(Any::FULL_NAME, eq("google.protobuf.Any"));
+}
+
+#[googletest::test]
+fn test_pack_unpack() {
+    let any = Any::pack(&foreign(5)).unwrap();
+    assert_that!(any.type_url(), eq("type.googleapis.com/proto3_unittest.ForeignMessage"));
+    assert_that!(any.is::<ForeignMessage>(), eq(true));
+    assert_that!(any.as_view().is::<ForeignMessage>(), eq(true));
+
+    let unpacked = any.unpack::<ForeignMessage>().unwrap().unwrap();
+    assert_that!(unpacked.c(), eq(5));
+    let unpacked = any.as_view().unpack::<ForeignMessage>().unwrap().unwrap();
+    assert_that!(unpacked.c(), eq(5));
+}
+
+#[googletest::test]
+fn test_unpack_other_type() {
+    let any = Any::pack(&foreign(5)).unwrap();
+    assert_that!(any.is::<TestAllTypes>(), eq(false));
+    assert_that!(any.unpack::<TestAllTypes>(), ok(none()));
+}
+
+#[googletest::test]
+fn test_type_url_prefix() {
+    // Only the part of the type URL after the last `/` names the type.
+    let mut any = Any::pack(&foreign(5)).unwrap();
+    any.set_type_url("example.com/types/proto3_unittest.ForeignMessage");
+    assert_that!(any.is::<ForeignMessage>(), eq(true));
+    any.set_type_url("proto3_unittest.ForeignMessage");
+    assert_that!(any.is::<ForeignMessage>(), eq(false));
+    any.set_type_url("example.com/xproto3_unittest.ForeignMessage");
+    assert_that!(any.is::<ForeignMessage>(), eq(false));
+}
+
+#[googletest::test]
+fn test_unpack_invalid_value() {
+    let mut any = Any::pack(&foreign(5)).unwrap();
+    any.set_value(b"\xff");
+    assert_that!(any.unpack::<ForeignMessage>(), err(anything()));
+}
+
+#[googletest::test]
+fn test_unpack_dynamic() {
+    let mut registry = TypeRegistry::new();
+    registry.add::<ForeignMessage>();
+    assert_that!(
+        format!("{registry:?}"),
+        eq("TypeRegistry { types: [\"proto3_unittest.ForeignMessage\"] }")
+    );
+
+    let any = Any::pack(&foreign(5)).unwrap();
+    let dynamic = any.unpack_dynamic(&registry).unwrap().unwrap();
+    assert_that!(dynamic.to_json(), ok(eq(r#"{"c":5}"#)));
+    assert_that!(dynamic.to_text_format(), eq("c: 5\n"));
+
+    let any = Any::pack(&TestAllTypes::new()).unwrap();
+    assert_that!(any.as_view().unpack_dynamic(&registry), ok(none()));
+}
+
+#[googletest::test]
+fn test_type_registry_find() {
+    let mut registry = TypeRegistry::default();
+    assert_that!(registry.find(ForeignMessage::TYPE_URL), none());
+    registry.add::<ForeignMessage>();
+    assert_that!(registry.find(ForeignMessage::TYPE_URL), some(anything()));
+    assert_that!(registry.find("example.com/proto3_unittest.ForeignMessage"), some(anything()));
+    assert_that!(registry.find("proto3_unittest.ForeignMessage"), none());
+    assert_that!(registry.unpack(ForeignMessage::TYPE_URL, b"\xff"), err(anything()));
+}
@@ -0,0 +1,98 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use protobuf::delimited::{read_delimited, write_delimited, DelimitedReader, DelimitedWriter};
+use protobuf::prelude::*;
+use protobuf::ReadError;
+use std::io;
+use unittest_rust_proto::TestAllTypes;
+
+fn msg_with_int32(value: i32) -> TestAllTypes {
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_int32(value);
+    msg
+}
+
+#[googletest::test]
+fn write_delimited_prefixes_size() {
+    let msg = msg_with_int32(150);
+    let mut out = Vec::new();
+    write_delimited(&msg, &mut out).unwrap();
+    // optional_int32 is field 1, 150 is encoded as the varint [0x96, 0x01].
+    assert_that!(out, eq(vec![0x03, 0x08, 0x96, 0x01]));
+}
+
+#[googletest::test]
+fn write_delimited_empty_message() {
+    let mut out = Vec::new();
+    write_delimited(&TestAllTypes::new().as_view(), &mut out).unwrap();
+    assert_that!(out, eq(vec![0x00]));
+}
+
+#[googletest::test]
+fn roundtrip_many_messages() {
+    let mut writer = DelimitedWriter::new(Vec::new());
+    for i in 0..100 {
+        writer.write(&msg_with_int32(i)).unwrap();
+    }
+    let mut large = TestAllTypes::new();
+    large.set_optional_bytes(vec![7u8; 1000]);
+    writer.write(&large.as_mut()).unwrap();
+    let data = writer.into_inner();
+
+    let mut reader = DelimitedReader::<TestAllTypes, _>::new(&data[..]);
+    for i in 0..100 {
+        let msg = reader.next().unwrap().unwrap();
+        assert_that!(msg.optional_int32(), eq(i));
+    }
+    let msg = reader.next().unwrap().unwrap();
+    assert_that!(msg.optional_bytes().len(), eq(1000));
+    assert!(reader.next().is_none());
+}
+
+#[googletest::test]
+fn read_delimited_empty_stream() {
+    assert!(read_delimited::<TestAllTypes>(io::empty()).is_none());
+}
+
+#[googletest::test]
+fn read_delimited_truncated_message() {
+    let data = [0x03, 0x08, 0x96];
+    let Some(Err(ReadError::Io(err))) = read_delimited::<TestAllTypes>(&data[..]) else {
+        panic!("expected an I/O error");
+    };
+    assert_that!(err.kind(), eq(io::ErrorKind::UnexpectedEof));
+}
+
+#[googletest::test]
+fn read_delimited_truncated_size() {
+    let data = [0x96];
+    let Some(Err(ReadError::Io(err))) = read_delimited::<TestAllTypes>(&data[..]) else {
+        panic!("expected an I/O error");
+    };
+    assert_that!(err.kind(), eq(io::ErrorKind::UnexpectedEof));
+}
+
+#[googletest::test]
+fn reader_enforces_max_size() {
+    let mut data = Vec::new();
+    write_delimited(&msg_with_int32(150), &mut data).unwrap();
+
+    let mut reader = DelimitedReader::<TestAllTypes, _>::new(&data[..]).with_max_size(2);
+    assert!(matches!(reader.next(), Some(Err(ReadError::TooLarge { max_size: 2 }))));
+    // The stream is no longer usable after an error.
+    assert!(reader.next().is_none());
+}
+
+#[googletest::test]
+fn reader_reports_parse_errors() {
+    let data = [0x02, 0xff, 0xff];
+    let Some(Err(ReadError::Parse(_))) = read_delimited::<TestAllTypes>(&data[..]) else {
+        panic!("expected a parse error");
+    };
+}
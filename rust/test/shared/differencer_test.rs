@@ -0,0 +1,207 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use map_unittest_rust_proto::TestMap;
+use protobuf::differencer::{DifferenceKind, MessageDifferencer};
+use protobuf::prelude::*;
+use protobuf::reflect::{FieldDescriptor, ReflectMessage, ReflectValue};
+use unittest_proto3_rust_proto::test_all_types::{NestedEnum, NestedMessage};
+use unittest_proto3_rust_proto::TestAllTypes;
+use unittest_rust_proto::{
+    TestAllExtensions, OPTIONAL_BOOL_EXTENSION, OPTIONAL_INT32_EXTENSION, OPTIONAL_STRING_EXTENSION,
+};
+
+fn field(name: &str) -> FieldDescriptor {
+    TestAllTypes::descriptor().field_by_name(name).unwrap()
+}
+
+fn differences(
+    differencer: &MessageDifferencer,
+    a: &impl ReflectMessage,
+    b: &impl ReflectMessage,
+) -> Vec<String> {
+    differencer.compare(a, b).iter().map(|difference| difference.to_string()).collect()
+}
+
+fn nested(bb: i32) -> NestedMessage {
+    let mut msg = NestedMessage::new();
+    msg.set_bb(bb);
+    msg
+}
+
+#[googletest::test]
+fn test_no_differences() {
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_int32(1);
+    msg.repeated_int32_mut().extend([1, 2]);
+    let differencer = MessageDifferencer::new();
+    assert_that!(differencer.compare(&msg, &msg.as_view()), is_empty());
+    assert_that!(differencer.equals(&msg, &msg.clone()), eq(true));
+}
+
+#[googletest::test]
+fn test_singular_fields() {
+    let mut a = TestAllTypes::new();
+    a.set_optional_int32(1);
+    a.set_optional_string("a");
+    a.set_optional_nested_enum(NestedEnum::Foo);
+    a.optional_nested_message_mut().set_bb(1);
+    let mut b = TestAllTypes::new();
+    b.set_optional_int32(2);
+    b.set_optional_bytes(b"\x00b");
+    b.set_optional_nested_enum(NestedEnum::Bar);
+    b.optional_nested_message_mut().set_bb(2);
+    assert_that!(
+        differences(&MessageDifferencer::new(), &a, &b),
+        elements_are![
+            eq("modified: optional_int32: 1 -> 2"),
+            eq("removed: optional_string: \"a\""),
+            eq("added: optional_bytes: \"\\000b\""),
+            eq("modified: optional_nested_message.bb: 1 -> 2"),
+            eq("modified: optional_nested_enum: FOO -> BAR"),
+        ]
+    );
+
+    let difference = &MessageDifferencer::new().compare(&a, &b)[0];
+    assert_that!(difference.kind(), eq(DifferenceKind::Modified));
+    assert_that!(difference.path(), eq("optional_int32"));
+    assert_that!(difference.field(), eq(field("optional_int32")));
+    assert!(matches!(difference.old_value(), Some(ReflectValue::I32(1))));
+    assert!(matches!(difference.new_value(), Some(ReflectValue::I32(2))));
+}
+
+#[googletest::test]
+fn test_repeated_fields() {
+    let mut a = TestAllTypes::new();
+    a.repeated_int32_mut().extend([1, 2, 3]);
+    let mut b = TestAllTypes::new();
+    b.repeated_int32_mut().extend([1, 5]);
+    b.repeated_nested_message_mut().push(nested(1).as_view());
+    assert_that!(
+        differences(&MessageDifferencer::new(), &a, &b),
+        elements_are![
+            eq("modified: repeated_int32[1]: 2 -> 5"),
+            eq("removed: repeated_int32[2]: 3"),
+            eq("added: repeated_nested_message[0]: { bb: 1 }"),
+        ]
+    );
+}
+
+#[googletest::test]
+fn test_map_fields() {
+    let mut a = TestMap::new();
+    a.map_string_string_mut().insert("a", "x");
+    a.map_string_string_mut().insert("b", "y");
+    let mut b = TestMap::new();
+    b.map_string_string_mut().insert("c", "x");
+    b.map_string_string_mut().insert("b", "z");
+    assert_that!(
+        differences(&MessageDifferencer::new(), &a, &b),
+        elements_are![
+            eq("removed: map_string_string[\"a\"]: \"x\""),
+            eq("modified: map_string_string[\"b\"]: \"y\" -> \"z\""),
+            eq("added: map_string_string[\"c\"]: \"x\""),
+        ]
+    );
+}
+
+#[googletest::test]
+fn test_ignore_field() {
+    let mut a = TestAllTypes::new();
+    a.set_optional_int32(1);
+    a.optional_nested_message_mut().set_bb(1);
+    let mut b = TestAllTypes::new();
+    b.set_optional_int32(2);
+    b.optional_nested_message_mut().set_bb(2);
+    let bb = NestedMessage::descriptor().field_by_name("bb").unwrap();
+    let differencer = MessageDifferencer::new().ignore_field(field("optional_int32"));
+    assert_that!(
+        differences(&differencer, &a, &b),
+        elements_are![eq("modified: optional_nested_message.bb: 1 -> 2")]
+    );
+    assert_that!(differencer.ignore_field(bb).equals(&a, &b), eq(true));
+}
+
+#[googletest::test]
+fn test_treat_as_set() {
+    let mut a = TestAllTypes::new();
+    a.repeated_int32_mut().extend([1, 2, 2]);
+    let mut b = TestAllTypes::new();
+    b.repeated_int32_mut().extend([2, 1, 3]);
+    let differencer = MessageDifferencer::new().treat_as_set(field("repeated_int32"));
+    assert_that!(
+        differences(&differencer, &a, &b),
+        elements_are![eq("removed: repeated_int32[2]: 2"), eq("added: repeated_int32[2]: 3")]
+    );
+
+    b.repeated_int32_mut().set(2, 2);
+    assert_that!(differencer.equals(&a, &b), eq(true));
+}
+
+#[googletest::test]
+fn test_treat_as_map() {
+    let mut a = TestAllTypes::new();
+    a.repeated_nested_message_mut().push(nested(1).as_view());
+    a.repeated_nested_message_mut().push(nested(2).as_view());
+    let mut b = TestAllTypes::new();
+    b.repeated_nested_message_mut().push(nested(3).as_view());
+    b.repeated_nested_message_mut().push(nested(1).as_view());
+    let bb = NestedMessage::descriptor().field_by_name("bb").unwrap();
+    let differencer = MessageDifferencer::new().treat_as_map(field("repeated_nested_message"), bb);
+    assert_that!(
+        differences(&differencer, &a, &b),
+        elements_are![
+            eq("removed: repeated_nested_message[2]: { bb: 2 }"),
+            eq("added: repeated_nested_message[3]: { bb: 3 }"),
+        ]
+    );
+}
+
+#[googletest::test]
+fn test_float_fraction_and_margin() {
+    let mut a = TestAllTypes::new();
+    a.set_optional_double(100.0);
+    a.repeated_float_mut().push(1.0);
+    let mut b = TestAllTypes::new();
+    b.set_optional_double(100.5);
+    b.repeated_float_mut().push(1.001);
+    assert_that!(MessageDifferencer::new().compare(&a, &b).len(), eq(2));
+
+    let differencer = MessageDifferencer::new().float_fraction_and_margin(0.01, 0.0);
+    assert_that!(differencer.compare(&a, &b), is_empty());
+    b.set_optional_double(102.0);
+    assert_that!(
+        differences(&differencer, &a, &b),
+        elements_are![eq("modified: optional_double: 100 -> 102")]
+    );
+}
+
+#[googletest::test]
+fn test_extensions() {
+    let mut a = TestAllExtensions::new();
+    a.set_extension(&OPTIONAL_INT32_EXTENSION, 1);
+    a.set_extension(&OPTIONAL_STRING_EXTENSION, "a");
+    let mut b = TestAllExtensions::new();
+    b.set_extension(&OPTIONAL_INT32_EXTENSION, 2);
+    b.set_extension(&OPTIONAL_BOOL_EXTENSION, true);
+    assert_that!(
+        differences(&MessageDifferencer::new(), &a, &b),
+        elements_are![
+            eq("modified: [protobuf_unittest.optional_int32_extension]: 1 -> 2"),
+            eq("added: [protobuf_unittest.optional_bool_extension]: true"),
+            eq("removed: [protobuf_unittest.optional_string_extension]: \"a\""),
+        ]
+    );
+
+    let differencer = MessageDifferencer::new()
+        .ignore_path("[protobuf_unittest.optional_int32_extension]")
+        .ignore_path("[protobuf_unittest.optional_string_extension]")
+        .partial(true);
+    assert_that!(differencer.compare(&a, &b), is_empty());
+    assert_that!(MessageDifferencer::new().equals(&a, &a), eq(true));
+}
@@ -0,0 +1,258 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use protobuf::prelude::*;
+use protobuf::reflect::{DescriptorPool, DynamicMessage, ReflectMessage, ReflectValueRef};
+use protobuf::{ParseErrorKind, ParseOptions, SerializeErrorKind, SerializeOptions};
+use unittest_rust_proto::TestAllTypes;
+
+fn varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Encodes a length-delimited field.
+fn delimited(number: u32, contents: impl AsRef<[u8]>) -> Vec<u8> {
+    let contents = contents.as_ref();
+    let mut out = Vec::new();
+    varint(u64::from(number << 3 | 2), &mut out);
+    varint(contents.len() as u64, &mut out);
+    out.extend_from_slice(contents);
+    out
+}
+
+/// Encodes a varint field.
+fn int(number: u32, value: u64) -> Vec<u8> {
+    let mut out = Vec::new();
+    varint(u64::from(number << 3), &mut out);
+    varint(value, &mut out);
+    out
+}
+
+/// Encodes a `FieldDescriptorProto` with the given label and type.
+fn field(name: &str, number: u64, label: u64, r#type: u64, type_name: Option<&str>) -> Vec<u8> {
+    let mut out = [delimited(1, name), int(3, number), int(4, label), int(5, r#type)].concat();
+    if let Some(type_name) = type_name {
+        out.extend(delimited(6, type_name));
+    }
+    out
+}
+
+const OPTIONAL: u64 = 1;
+const REQUIRED: u64 = 2;
+const REPEATED: u64 = 3;
+const INT32: u64 = 5;
+const STRING: u64 = 9;
+const MESSAGE: u64 = 11;
+
+fn address_file() -> Vec<u8> {
+    let address = [delimited(1, "Address"), delimited(2, field("city", 1, OPTIONAL, STRING, None))];
+    [delimited(1, "dynamic/address.proto"), delimited(2, "dynamic"), delimited(4, address.concat())]
+        .concat()
+}
+
+fn person_file() -> Vec<u8> {
+    let person = [
+        delimited(1, "Person"),
+        delimited(2, field("name", 1, REQUIRED, STRING, None)),
+        delimited(2, field("id", 2, OPTIONAL, INT32, None)),
+        delimited(2, field("emails", 3, REPEATED, STRING, None)),
+        delimited(2, field("address", 4, OPTIONAL, MESSAGE, Some(".dynamic.Address"))),
+    ];
+    [
+        delimited(1, "dynamic/person.proto"),
+        delimited(2, "dynamic"),
+        delimited(3, "dynamic/address.proto"),
+        delimited(4, person.concat()),
+    ]
+    .concat()
+}
+
+/// Returns a pool with the files above. The importing file comes first, so
+/// the pool has to sort them.
+fn pool() -> DescriptorPool {
+    let set = [delimited(1, person_file()), delimited(1, address_file())].concat();
+    DescriptorPool::from_file_descriptor_set(&set).unwrap()
+}
+
+#[googletest::test]
+fn test_descriptor_pool() {
+    let pool = pool();
+    let person = pool.message_by_name("dynamic.Person").unwrap();
+    assert_that!(person.name(), eq("Person"));
+    assert_that!(
+        person.fields().map(|f| f.name().to_string()).collect::<Vec<_>>(),
+        elements_are![eq("name"), eq("id"), eq("emails"), eq("address")]
+    );
+    assert_that!(person.field_by_name("name").unwrap().is_required(), eq(true));
+    assert_that!(person.field_by_name("id").unwrap().is_required(), eq(false));
+
+    let address = pool.message_by_name("dynamic.Address").unwrap();
+    assert_that!(person.field_by_name("address").unwrap().message_type(), some(eq(address)));
+
+    let file = pool.file_by_name("dynamic/person.proto").unwrap();
+    assert_that!(person.file(), eq(file.clone()));
+    assert_that!(
+        file.dependencies().map(|d| d.name().to_string()).collect::<Vec<_>>(),
+        elements_are![eq("dynamic/address.proto")]
+    );
+    assert_that!(pool.message_by_name("dynamic.Missing"), none());
+    assert_that!(pool.enum_by_name("dynamic.Person"), none());
+}
+
+#[googletest::test]
+fn test_descriptor_pool_errors() {
+    let err = DescriptorPool::from_file_descriptor_set(b"\x0a\x05").unwrap_err();
+    assert_that!(err.file(), none());
+
+    let set = delimited(1, person_file());
+    let err = DescriptorPool::from_file_descriptor_set(&set).unwrap_err();
+    assert_that!(err.file(), some(eq("dynamic/person.proto")));
+    assert_that!(err.message(), contains_substring("dynamic/address.proto"));
+
+    let set = [delimited(1, address_file()), delimited(1, address_file())].concat();
+    let err = DescriptorPool::from_file_descriptor_set(&set).unwrap_err();
+    assert_that!(err.file(), some(eq("dynamic/address.proto")));
+
+    // Two fields with the same number.
+    let bad = [
+        delimited(1, "Bad"),
+        delimited(2, field("a", 1, OPTIONAL, INT32, None)),
+        delimited(2, field("b", 1, OPTIONAL, INT32, None)),
+    ];
+    let file = [delimited(1, "dynamic/bad.proto"), delimited(4, bad.concat())].concat();
+    let err = DescriptorPool::from_file_descriptor_set(&delimited(1, file)).unwrap_err();
+    assert_that!(err.file(), some(eq("dynamic/bad.proto")));
+}
+
+#[googletest::test]
+fn test_descriptors_outlive_pool() {
+    let pool = pool();
+    let desc = pool.message_by_name("dynamic.Person").unwrap();
+    let city = pool.message_by_name("dynamic.Address").unwrap().field_by_name("city").unwrap();
+    drop(pool);
+
+    // The descriptors keep the pool alive.
+    assert_that!(desc.full_name(), eq("dynamic.Person"));
+    let address = city.containing_type();
+    assert_that!(address.full_name(), eq("dynamic.Address"));
+    let id = desc.field_by_name("id").unwrap();
+    let mut msg = DynamicMessage::new(desc);
+    msg.as_message_mut().set_field(&id, 3);
+    assert!(matches!(msg.as_message_ref().get_field(&id), ReflectValueRef::I32(3)));
+}
+
+#[googletest::test]
+fn test_serialize_and_parse() {
+    let pool = pool();
+    let desc = pool.message_by_name("dynamic.Person").unwrap();
+    let [name, id, emails, address] =
+        ["name", "id", "emails", "address"].map(|n| desc.field_by_name(n).unwrap());
+    let city = address.message_type().unwrap().field_by_name("city").unwrap();
+
+    let mut msg = DynamicMessage::new(desc.clone());
+    {
+        let mut m = msg.as_message_mut();
+        m.set_field(&name, "ada");
+        m.set_field(&id, 7);
+        m.mut_repeated(&emails).push("ada@example.com");
+        m.mut_message(&address).set_field(&city, "London");
+    }
+    let serialized = msg.serialize().unwrap();
+
+    let parsed = DynamicMessage::parse(desc, &serialized).unwrap();
+    let view = parsed.as_message_ref();
+    assert!(matches!(view.get_field(&id), ReflectValueRef::I32(7)));
+    let ReflectValueRef::String(s) = view.get_field(&name) else { panic!("not a string") };
+    assert_that!(&*s, eq("ada"));
+    assert_that!(view.get_repeated(&emails).len(), eq(1));
+    let ReflectValueRef::Message(addr) = view.get_field(&address) else { panic!("not a message") };
+    assert_that!(addr.has_field(&city), eq(true));
+    assert_that!(format!("{parsed:?}"), contains_substring("London"));
+
+    let mut buf = vec![0; serialized.len()];
+    assert_that!(parsed.serialize_into(&mut buf), ok(eq(serialized.len())));
+    assert_that!(buf, eq(&serialized));
+    assert_that!(parsed.serialize_into(&mut buf[1..]), err(anything()));
+}
+
+#[googletest::test]
+fn test_required_fields() {
+    let pool = pool();
+    let desc = pool.message_by_name("dynamic.Person").unwrap();
+    let mut msg = DynamicMessage::new(desc.clone());
+    msg.as_message_mut().set_field(&desc.field_by_name("id").unwrap(), 1);
+
+    assert_that!(msg.serialize(), ok(anything()));
+    let options = SerializeOptions::new().check_required(true);
+    let err = msg.serialize_with_options(&options).unwrap_err();
+    assert_that!(err.kind(), eq(SerializeErrorKind::MissingRequired));
+    assert_that!(err.missing_required_fields(), elements_are![eq("name")]);
+
+    let err = DynamicMessage::parse(desc.clone(), b"").unwrap_err();
+    assert_that!(err.kind(), eq(ParseErrorKind::MissingRequired));
+    let options = ParseOptions::new().check_required(false);
+    assert_that!(DynamicMessage::parse_with_options(desc, b"", &options), ok(anything()));
+}
+
+#[googletest::test]
+fn test_clear_and_merge() {
+    let pool = pool();
+    let desc = pool.message_by_name("dynamic.Person").unwrap();
+    let [name, id, emails] = ["name", "id", "emails"].map(|n| desc.field_by_name(n).unwrap());
+
+    let mut a = DynamicMessage::new(desc.clone());
+    a.as_message_mut().set_field(&name, "a");
+    a.as_message_mut().mut_repeated(&emails).push("a@example.com");
+    let mut b = DynamicMessage::new(desc);
+    b.as_message_mut().set_field(&id, 2);
+    b.as_message_mut().mut_repeated(&emails).push("b@example.com");
+
+    a.merge_from(b.as_message_ref());
+    assert!(matches!(a.as_message_ref().get_field(&id), ReflectValueRef::I32(2)));
+    assert_that!(a.as_message_ref().has_field(&name), eq(true));
+    assert_that!(a.as_message_ref().get_repeated(&emails).len(), eq(2));
+
+    let serialized = a.serialize().unwrap();
+    a.clear();
+    assert_that!(a.as_message_ref().has_field(&name), eq(false));
+    assert_that!(a.as_message_ref().get_repeated(&emails).len(), eq(0));
+
+    a.clear_and_parse(&serialized).unwrap();
+    assert_that!(a.as_message_ref().get_repeated(&emails).len(), eq(2));
+    a.merge_from_reader(&serialized[..], usize::MAX).unwrap();
+    assert_that!(a.as_message_ref().get_repeated(&emails).len(), eq(4));
+}
+
+#[googletest::test]
+#[should_panic(expected = "can't merge a dynamic.Address into a dynamic.Person")]
+fn test_merge_from_other_type_panics() {
+    let pool = pool();
+    let mut person = DynamicMessage::new(pool.message_by_name("dynamic.Person").unwrap());
+    let address = DynamicMessage::new(pool.message_by_name("dynamic.Address").unwrap());
+    person.merge_from(address.as_message_ref());
+}
+
+#[googletest::test]
+fn test_compiled_in_type() {
+    let mut typed = TestAllTypes::new();
+    typed.set_optional_int32(5);
+    typed.set_optional_string("hello");
+    let serialized = typed.serialize().unwrap();
+
+    let dynamic = DynamicMessage::parse(TestAllTypes::descriptor(), &serialized).unwrap();
+    let field = TestAllTypes::descriptor().field_by_name("optional_int32").unwrap();
+    assert!(matches!(dynamic.as_message_ref().get_field(&field), ReflectValueRef::I32(5)));
+
+    let reparsed = TestAllTypes::parse(&dynamic.serialize().unwrap()).unwrap();
+    assert_that!(reparsed.optional_int32(), eq(5));
+    assert_that!(reparsed.optional_string(), eq("hello"));
+}
@@ -0,0 +1,253 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use protobuf::prelude::*;
+use protobuf::{ExtensionRegistry, ParseOptions};
+use unittest_rust_proto::test_all_types::{NestedEnum, NestedMessage};
+use unittest_rust_proto::test_nested_extension::TEST;
+use unittest_rust_proto::{
+    ForeignEnum, TestAllExtensions, DEFAULT_INT32_EXTENSION, DEFAULT_NESTED_ENUM_EXTENSION,
+    DEFAULT_STRING_EXTENSION, OPTIONAL_BOOL_EXTENSION, OPTIONAL_BYTES_EXTENSION,
+    OPTIONAL_DOUBLE_EXTENSION, OPTIONAL_FOREIGN_ENUM_EXTENSION, OPTIONAL_INT32_EXTENSION,
+    OPTIONAL_NESTED_ENUM_EXTENSION, OPTIONAL_NESTED_MESSAGE_EXTENSION, OPTIONAL_STRING_EXTENSION,
+    OPTIONAL_UINT64_EXTENSION, REPEATED_INT32_EXTENSION, REPEATED_NESTED_ENUM_EXTENSION,
+    REPEATED_NESTED_MESSAGE_EXTENSION, REPEATED_STRING_EXTENSION,
+};
+
+#[googletest::test]
+fn test_scalar_extension() {
+    let mut msg = TestAllExtensions::new();
+    assert_that!(msg.has_extension(&OPTIONAL_INT32_EXTENSION), eq(false));
+    assert_that!(msg.get_extension(&OPTIONAL_INT32_EXTENSION), eq(0));
+
+    msg.set_extension(&OPTIONAL_INT32_EXTENSION, 42);
+    assert_that!(msg.has_extension(&OPTIONAL_INT32_EXTENSION), eq(true));
+    assert_that!(msg.get_extension(&OPTIONAL_INT32_EXTENSION), eq(42));
+
+    msg.set_extension(&OPTIONAL_UINT64_EXTENSION, u64::MAX);
+    msg.set_extension(&OPTIONAL_DOUBLE_EXTENSION, 1.5);
+    msg.set_extension(&OPTIONAL_BOOL_EXTENSION, true);
+    assert_that!(msg.get_extension(&OPTIONAL_UINT64_EXTENSION), eq(u64::MAX));
+    assert_that!(msg.get_extension(&OPTIONAL_DOUBLE_EXTENSION), eq(1.5));
+    assert_that!(msg.get_extension(&OPTIONAL_BOOL_EXTENSION), eq(true));
+
+    msg.clear_extension(&OPTIONAL_INT32_EXTENSION);
+    assert_that!(msg.has_extension(&OPTIONAL_INT32_EXTENSION), eq(false));
+    assert_that!(msg.get_extension(&OPTIONAL_INT32_EXTENSION), eq(0));
+    assert_that!(msg.has_extension(&OPTIONAL_BOOL_EXTENSION), eq(true));
+}
+
+#[googletest::test]
+fn test_string_and_bytes_extensions() {
+    let mut msg = TestAllExtensions::new();
+    assert_that!(msg.get_extension(&OPTIONAL_STRING_EXTENSION), eq(""));
+
+    msg.set_extension(&OPTIONAL_STRING_EXTENSION, "hello");
+    msg.set_extension(&OPTIONAL_BYTES_EXTENSION, b"\x00\xff");
+    assert_that!(msg.get_extension(&OPTIONAL_STRING_EXTENSION), eq("hello"));
+    assert_that!(msg.get_extension(&OPTIONAL_BYTES_EXTENSION), eq(b"\x00\xff"));
+
+    msg.set_extension(&OPTIONAL_STRING_EXTENSION, String::from("world"));
+    assert_that!(msg.get_extension(&OPTIONAL_STRING_EXTENSION), eq("world"));
+}
+
+#[googletest::test]
+fn test_enum_extensions() {
+    let mut msg = TestAllExtensions::new();
+    assert_that!(msg.get_extension(&OPTIONAL_NESTED_ENUM_EXTENSION), eq(NestedEnum::Foo));
+    assert_that!(msg.get_extension(&DEFAULT_NESTED_ENUM_EXTENSION), eq(NestedEnum::Bar));
+
+    msg.set_extension(&OPTIONAL_NESTED_ENUM_EXTENSION, NestedEnum::Baz);
+    msg.set_extension(&OPTIONAL_FOREIGN_ENUM_EXTENSION, ForeignEnum::ForeignBar);
+    assert_that!(msg.has_extension(&OPTIONAL_NESTED_ENUM_EXTENSION), eq(true));
+    assert_that!(msg.get_extension(&OPTIONAL_NESTED_ENUM_EXTENSION), eq(NestedEnum::Baz));
+    assert_that!(msg.get_extension(&OPTIONAL_FOREIGN_ENUM_EXTENSION), eq(ForeignEnum::ForeignBar));
+
+    let serialized = msg.serialize().unwrap();
+    let mut registry = ExtensionRegistry::new();
+    registry.add(&OPTIONAL_NESTED_ENUM_EXTENSION);
+    registry.add(&OPTIONAL_FOREIGN_ENUM_EXTENSION);
+    let options = ParseOptions::new().extension_registry(&registry);
+    let parsed = TestAllExtensions::parse_with_options(&serialized, &options).unwrap();
+    assert_that!(parsed.get_extension(&OPTIONAL_NESTED_ENUM_EXTENSION), eq(NestedEnum::Baz));
+    assert_that!(
+        parsed.get_extension(&OPTIONAL_FOREIGN_ENUM_EXTENSION),
+        eq(ForeignEnum::ForeignBar)
+    );
+
+    msg.clear_extension(&OPTIONAL_NESTED_ENUM_EXTENSION);
+    assert_that!(msg.has_extension(&OPTIONAL_NESTED_ENUM_EXTENSION), eq(false));
+}
+
+#[googletest::test]
+fn test_message_extension() {
+    let mut msg = TestAllExtensions::new();
+    assert_that!(msg.has_extension(&OPTIONAL_NESTED_MESSAGE_EXTENSION), eq(false));
+    assert_that!(msg.get_extension(&OPTIONAL_NESTED_MESSAGE_EXTENSION).bb(), eq(0));
+    assert_that!(OPTIONAL_NESTED_MESSAGE_EXTENSION.default_value().bb(), eq(0));
+
+    let mut nested = NestedMessage::new();
+    nested.set_bb(42);
+    msg.set_extension(&OPTIONAL_NESTED_MESSAGE_EXTENSION, nested);
+    assert_that!(msg.has_extension(&OPTIONAL_NESTED_MESSAGE_EXTENSION), eq(true));
+    assert_that!(msg.get_extension(&OPTIONAL_NESTED_MESSAGE_EXTENSION).bb(), eq(42));
+
+    let mut registry = ExtensionRegistry::new();
+    registry.add(&OPTIONAL_NESTED_MESSAGE_EXTENSION);
+    let options = ParseOptions::new().extension_registry(&registry);
+    let parsed =
+        TestAllExtensions::parse_with_options(&msg.serialize().unwrap(), &options).unwrap();
+    assert_that!(parsed.get_extension(&OPTIONAL_NESTED_MESSAGE_EXTENSION).bb(), eq(42));
+
+    msg.clear_extension(&OPTIONAL_NESTED_MESSAGE_EXTENSION);
+    assert_that!(msg.has_extension(&OPTIONAL_NESTED_MESSAGE_EXTENSION), eq(false));
+    assert_that!(msg.get_extension(&OPTIONAL_NESTED_MESSAGE_EXTENSION).bb(), eq(0));
+}
+
+#[googletest::test]
+fn test_repeated_extensions() {
+    let mut msg = TestAllExtensions::new();
+    assert_that!(msg.has_extension(&REPEATED_INT32_EXTENSION), eq(false));
+    assert_that!(msg.get_extension(&REPEATED_INT32_EXTENSION).len(), eq(0));
+    assert_that!(REPEATED_STRING_EXTENSION.default_value().len(), eq(0));
+
+    let mut nested = NestedMessage::new();
+    nested.set_bb(7);
+    msg.set_extension(&REPEATED_INT32_EXTENSION, [1, 2, 3].into_iter());
+    msg.set_extension(&REPEATED_STRING_EXTENSION, ["a", "b"].into_iter());
+    msg.set_extension(
+        &REPEATED_NESTED_ENUM_EXTENSION,
+        [NestedEnum::Bar, NestedEnum::Baz].into_iter(),
+    );
+    msg.set_extension(&REPEATED_NESTED_MESSAGE_EXTENSION, [nested].into_iter());
+
+    let mut registry = ExtensionRegistry::new();
+    registry.add(&REPEATED_INT32_EXTENSION);
+    registry.add(&REPEATED_STRING_EXTENSION);
+    registry.add(&REPEATED_NESTED_ENUM_EXTENSION);
+    registry.add(&REPEATED_NESTED_MESSAGE_EXTENSION);
+    let options = ParseOptions::new().extension_registry(&registry);
+    let parsed =
+        TestAllExtensions::parse_with_options(&msg.serialize().unwrap(), &options).unwrap();
+
+    for msg in [&msg, &parsed] {
+        assert_that!(msg.has_extension(&REPEATED_INT32_EXTENSION), eq(true));
+        assert_that!(
+            msg.get_extension(&REPEATED_INT32_EXTENSION),
+            elements_are![eq(1), eq(2), eq(3)]
+        );
+        assert_that!(
+            msg.get_extension(&REPEATED_STRING_EXTENSION),
+            elements_are![eq("a"), eq("b")]
+        );
+        assert_that!(
+            msg.get_extension(&REPEATED_NESTED_ENUM_EXTENSION),
+            elements_are![eq(NestedEnum::Bar), eq(NestedEnum::Baz)]
+        );
+        let messages = msg.get_extension(&REPEATED_NESTED_MESSAGE_EXTENSION);
+        assert_that!(messages.len(), eq(1));
+        assert_that!(messages.get(0).unwrap().bb(), eq(7));
+    }
+
+    // Setting a repeated extension replaces its elements.
+    msg.set_extension(&REPEATED_INT32_EXTENSION, [4].into_iter());
+    assert_that!(msg.get_extension(&REPEATED_INT32_EXTENSION), elements_are![eq(4)]);
+
+    msg.clear_extension(&REPEATED_INT32_EXTENSION);
+    assert_that!(msg.has_extension(&REPEATED_INT32_EXTENSION), eq(false));
+    assert_that!(msg.get_extension(&REPEATED_INT32_EXTENSION).len(), eq(0));
+}
+
+#[googletest::test]
+fn test_extension_defaults() {
+    assert_that!(DEFAULT_INT32_EXTENSION.number(), eq(61));
+    assert_that!(DEFAULT_INT32_EXTENSION.default_value(), eq(41));
+    assert_that!(DEFAULT_STRING_EXTENSION.default_value(), eq("hello"));
+
+    let msg = TestAllExtensions::new();
+    assert_that!(msg.has_extension(&DEFAULT_INT32_EXTENSION), eq(false));
+    assert_that!(msg.get_extension(&DEFAULT_INT32_EXTENSION), eq(41));
+    assert_that!(msg.get_extension(&DEFAULT_STRING_EXTENSION), eq("hello"));
+}
+
+#[googletest::test]
+fn test_nested_extension() {
+    let mut msg = TestAllExtensions::new();
+    assert_that!(msg.get_extension(&TEST), eq("test"));
+    msg.set_extension(&TEST, "set");
+    assert_that!(msg.get_extension(&TEST), eq("set"));
+}
+
+#[googletest::test]
+fn test_extension_view_and_mut() {
+    let mut msg = TestAllExtensions::new();
+    {
+        let mut msg_mut = msg.as_mut();
+        msg_mut.set_extension(&OPTIONAL_STRING_EXTENSION, "via mut");
+        assert_that!(msg_mut.has_extension(&OPTIONAL_STRING_EXTENSION), eq(true));
+        assert_that!(msg_mut.get_extension(&OPTIONAL_STRING_EXTENSION), eq("via mut"));
+    }
+    let view = msg.as_view();
+    assert_that!(view.has_extension(&OPTIONAL_STRING_EXTENSION), eq(true));
+    assert_that!(view.get_extension(&OPTIONAL_STRING_EXTENSION), eq("via mut"));
+
+    msg.as_mut().clear_extension(&OPTIONAL_STRING_EXTENSION);
+    assert_that!(msg.as_view().has_extension(&OPTIONAL_STRING_EXTENSION), eq(false));
+}
+
+#[googletest::test]
+fn test_parse_with_extension_registry() {
+    let mut msg = TestAllExtensions::new();
+    msg.set_extension(&OPTIONAL_INT32_EXTENSION, 7);
+    msg.set_extension(&OPTIONAL_STRING_EXTENSION, "parsed");
+    let serialized = msg.serialize().unwrap();
+
+    let mut registry = ExtensionRegistry::new();
+    registry.add(&OPTIONAL_INT32_EXTENSION);
+    registry.add(&OPTIONAL_STRING_EXTENSION);
+    // Adding an extension again has no effect.
+    registry.add(&OPTIONAL_INT32_EXTENSION);
+
+    let options = ParseOptions::new().extension_registry(&registry);
+    let parsed = TestAllExtensions::parse_with_options(&serialized, &options).unwrap();
+    assert_that!(parsed.has_extension(&OPTIONAL_INT32_EXTENSION), eq(true));
+    assert_that!(parsed.get_extension(&OPTIONAL_INT32_EXTENSION), eq(7));
+    assert_that!(parsed.get_extension(&OPTIONAL_STRING_EXTENSION), eq("parsed"));
+}
+
+#[googletest::test]
+fn test_unregistered_extensions_are_unknown() {
+    let mut msg = TestAllExtensions::new();
+    msg.set_extension(&OPTIONAL_INT32_EXTENSION, 7);
+    msg.set_extension(&OPTIONAL_STRING_EXTENSION, "unknown");
+    let serialized = msg.serialize().unwrap();
+
+    let mut registry = ExtensionRegistry::new();
+    registry.add(&OPTIONAL_INT32_EXTENSION);
+    let options = ParseOptions::new().extension_registry(&registry);
+    let parsed = TestAllExtensions::parse_with_options(&serialized, &options).unwrap();
+    assert_that!(parsed.get_extension(&OPTIONAL_INT32_EXTENSION), eq(7));
+    assert_that!(parsed.has_extension(&OPTIONAL_STRING_EXTENSION), eq(false));
+
+    // The unregistered extension is kept as an unknown field.
+    let reparsed = TestAllExtensions::parse_with_options(
+        &parsed.serialize().unwrap(),
+        &ParseOptions::new().extension_registry(&ExtensionRegistry::new()),
+    )
+    .unwrap();
+    assert_that!(reparsed.has_extension(&OPTIONAL_INT32_EXTENSION), eq(false));
+    let mut all = ExtensionRegistry::new();
+    all.add(&OPTIONAL_INT32_EXTENSION);
+    all.add(&OPTIONAL_STRING_EXTENSION);
+    let reparsed = TestAllExtensions::parse_with_options(
+        &parsed.serialize().unwrap(),
+        &ParseOptions::new().extension_registry(&all),
+    )
+    .unwrap();
+    assert_that!(reparsed.get_extension(&OPTIONAL_STRING_EXTENSION), eq("unknown"));
+}
@@ -0,0 +1,154 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use field_mask_rust_proto::FieldMask;
+use googletest::prelude::*;
+use protobuf::field_mask::{self, AsFieldMask, MergeOptions};
+use protobuf::prelude::*;
+use protobuf::reflect::ReflectMessage;
+use unittest_proto3_rust_proto::{NestedTestAllTypes, TestAllTypes};
+
+fn src() -> TestAllTypes {
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_int32(5);
+    msg.set_optional_string("src");
+    msg.optional_nested_message_mut().set_bb(1);
+    msg.optional_foreign_message_mut().set_c(2);
+    msg.repeated_int32_mut().extend([3, 4]);
+    msg
+}
+
+fn dst() -> TestAllTypes {
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_int32(1);
+    msg.set_optional_int64(2);
+    msg.set_optional_string("dst");
+    msg.repeated_int32_mut().push(1);
+    msg
+}
+
+#[googletest::test]
+fn test_generated_field_mask() {
+    let mask: FieldMask = ["optional_int32", "optional_nested_message.bb"].into_iter().collect();
+    assert_that!(mask.paths().len(), eq(2));
+    assert_that!(
+        mask.to_paths(),
+        elements_are![eq("optional_int32"), eq("optional_nested_message.bb")]
+    );
+    assert_that!(mask.as_view().to_paths(), eq(&mask.to_paths()));
+}
+
+#[googletest::test]
+fn test_validate() {
+    let descriptor = TestAllTypes::descriptor();
+    assert_that!(
+        field_mask::validate(&descriptor, &["optional_int32", "optional_nested_message.bb"]),
+        ok(eq(&()))
+    );
+
+    let error =
+        field_mask::validate(&descriptor, &["optional_int32", "no_such_field"]).unwrap_err();
+    assert_that!(error.path(), eq("no_such_field"));
+    assert_that!(error.to_string(), starts_with("Invalid field mask path \"no_such_field\": "));
+
+    // Only singular message fields can be traversed.
+    assert_that!(field_mask::validate(&descriptor, &["optional_int32.x"]), err(anything()));
+    assert_that!(
+        field_mask::validate(&descriptor, &["repeated_nested_message.bb"]),
+        err(anything())
+    );
+    assert_that!(field_mask::validate(&descriptor, &["optional_nested_message."]), err(anything()));
+}
+
+#[googletest::test]
+fn test_set_operations() {
+    let a: FieldMask = ["optional_int32", "optional_nested_message"].into_iter().collect();
+    let b = ["optional_nested_message.bb", "optional_string"];
+    assert_that!(
+        field_mask::union(&a, &b),
+        elements_are![eq("optional_int32"), eq("optional_nested_message"), eq("optional_string")]
+    );
+    assert_that!(field_mask::intersection(&a, &b), elements_are![eq("optional_nested_message.bb")]);
+    assert_that!(
+        field_mask::canonicalize(&["optional_string", "optional_int32", "optional_string"]),
+        elements_are![eq("optional_int32"), eq("optional_string")]
+    );
+}
+
+#[googletest::test]
+fn test_merge_with_mask() {
+    let mut msg = dst();
+    let mask = ["optional_int32", "optional_foreign_message.c", "repeated_int32", "optional_bytes"];
+    field_mask::merge_with_mask(&mut msg, &src(), &mask, &MergeOptions::new()).unwrap();
+    assert_that!(msg.optional_int32(), eq(5));
+    assert_that!(msg.optional_int64(), eq(2));
+    assert_that!(msg.optional_string(), eq("dst"));
+    assert_that!(msg.has_optional_nested_message(), eq(false));
+    assert_that!(msg.optional_foreign_message().c(), eq(2));
+    assert_that!(
+        msg.repeated_int32().iter().collect::<Vec<_>>(),
+        elements_are![eq(1), eq(3), eq(4)]
+    );
+}
+
+#[googletest::test]
+fn test_merge_with_mask_replace() {
+    let mut from = NestedTestAllTypes::new();
+    from.set_payload(src());
+    let mut to = NestedTestAllTypes::new();
+    to.set_payload(dst());
+    let mask = ["payload", "child.payload.repeated_int32"];
+
+    // By default, message fields are merged and repeated fields appended to.
+    let mut msg = to.clone();
+    field_mask::merge_with_mask(&mut msg, &from, &mask, &MergeOptions::new()).unwrap();
+    assert_that!(msg.payload().optional_int32(), eq(5));
+    assert_that!(msg.payload().optional_int64(), eq(2));
+    assert_that!(
+        msg.payload().repeated_int32().iter().collect::<Vec<_>>(),
+        elements_are![eq(1), eq(3), eq(4)]
+    );
+    // Neither message has `child`, so merging doesn't create it.
+    assert_that!(msg.has_child(), eq(false));
+
+    let options = MergeOptions::new().replace_message_fields(true).replace_repeated_fields(true);
+    let mut msg = to.clone();
+    field_mask::merge_with_mask(&mut msg, &from.as_view(), &mask, &options).unwrap();
+    assert_that!(msg.payload().optional_int32(), eq(5));
+    assert_that!(msg.payload().optional_int64(), eq(0));
+    assert_that!(
+        msg.payload().repeated_int32().iter().collect::<Vec<_>>(),
+        elements_are![eq(3), eq(4)]
+    );
+}
+
+#[googletest::test]
+fn test_merge_with_invalid_mask() {
+    let mut msg = dst();
+    let result = field_mask::merge_with_mask(
+        &mut msg,
+        &src(),
+        &["optional_int32", "no_such_field"],
+        &MergeOptions::new(),
+    );
+    assert_that!(result, err(anything()));
+    assert_that!(msg.optional_int32(), eq(1));
+}
+
+#[googletest::test]
+fn test_trim() {
+    let mut msg = src();
+    let mask: FieldMask = ["optional_int32", "optional_foreign_message.c"].into_iter().collect();
+    assert_that!(field_mask::trim(&mut msg, &mask), ok(eq(true)));
+    assert_that!(msg.optional_int32(), eq(5));
+    assert_that!(msg.optional_string(), eq(""));
+    assert_that!(msg.has_optional_nested_message(), eq(false));
+    assert_that!(msg.optional_foreign_message().c(), eq(2));
+    assert_that!(msg.repeated_int32().len(), eq(0));
+
+    assert_that!(field_mask::trim(&mut msg, &mask), ok(eq(false)));
+}
@@ -5,13 +5,16 @@
 // license that can be found in the LICENSE file or at
 // https://developers.google.com/open-source/licenses/bsd
 
+use googletest::matcher::MatcherResult;
 use googletest::prelude::*;
 use paste::paste;
 use protobuf::proto;
-use protobuf_gtest_matchers::proto_eq;
+use protobuf_gtest_matchers::{proto_approx_eq, proto_eq, proto_eq_text, proto_partially_eq};
 use unittest_proto3_rust_proto::test_all_types::NestedMessage;
 use unittest_proto3_rust_proto::TestAllTypes as TestAllTypesProto3;
-use unittest_rust_proto::TestAllTypes;
+use unittest_rust_proto::{
+    TestAllExtensions, TestAllTypes, OPTIONAL_INT32_EXTENSION, OPTIONAL_STRING_EXTENSION,
+};
 
 macro_rules! generate_eq_msgs_tests {
   ($(($type: ident, $name_ext: ident)),*) => {
@@ -72,3 +75,126 @@ fn proto_eq_works_on_view() {
         ]
     );
 }
+
+#[googletest::test]
+fn proto_eq_explains_differences() {
+    let expected = proto!(TestAllTypesProto3 { optional_int32: 1, optional_string: "a" });
+    let actual = proto!(TestAllTypesProto3 { optional_int32: 2, optional_string: "a" });
+    let explanation = proto_eq(expected).explain_match(&actual).to_string();
+    expect_that!(explanation, contains_substring("modified: optional_int32: 1 -> 2"));
+    expect_that!(explanation, not(contains_substring("optional_string")));
+}
+
+#[googletest::test]
+fn proto_partially_eq_ignores_fields_unset_in_expected() {
+    let expected = proto!(TestAllTypesProto3 { optional_int32: 1 });
+    let actual = proto!(TestAllTypesProto3 { optional_int32: 1, optional_string: "a" });
+    expect_that!(&actual, proto_partially_eq(expected.clone()));
+    expect_that!(&actual, not(proto_eq(expected.clone())));
+
+    let actual = proto!(TestAllTypesProto3 { optional_int32: 2, optional_string: "a" });
+    expect_that!(&actual, not(proto_partially_eq(expected)));
+}
+
+#[googletest::test]
+fn proto_partially_eq_compares_extensions() {
+    let mut expected = TestAllExtensions::new();
+    expected.set_extension(&OPTIONAL_INT32_EXTENSION, 1);
+    let mut actual = TestAllExtensions::new();
+    actual.set_extension(&OPTIONAL_INT32_EXTENSION, 1);
+    actual.set_extension(&OPTIONAL_STRING_EXTENSION, "a");
+    expect_that!(&actual, proto_partially_eq(expected.clone()));
+    expect_that!(&actual, not(proto_eq(expected.clone())));
+
+    actual.set_extension(&OPTIONAL_INT32_EXTENSION, 2);
+    expect_that!(&actual, not(proto_partially_eq(expected.clone())));
+    expect_that!(
+        proto_partially_eq(expected).explain_match(&actual).to_string(),
+        contains_substring("modified: [protobuf_unittest.optional_int32_extension]: 1 -> 2")
+    );
+}
+
+#[googletest::test]
+fn proto_approx_eq_compares_floats_within_margin() {
+    let expected = proto!(TestAllTypesProto3 { optional_double: 1.0, optional_float: 2.0 });
+    let actual = proto!(TestAllTypesProto3 { optional_double: 1.005, optional_float: 2.001 });
+    expect_that!(&actual, proto_approx_eq(expected.clone(), 0.01));
+    expect_that!(&actual, not(proto_approx_eq(expected.clone(), 0.001)));
+    expect_that!(&actual, not(proto_eq(expected)));
+}
+
+#[googletest::test]
+fn ignoring_fields_ignores_nested_paths() {
+    let expected = proto!(TestAllTypesProto3 {
+        optional_int32: 1,
+        optional_nested_message: NestedMessage { bb: 10 },
+        repeated_nested_message: [NestedMessage { bb: 20 }]
+    });
+    let actual = proto!(TestAllTypesProto3 {
+        optional_int32: 1,
+        optional_nested_message: NestedMessage { bb: 11 },
+        repeated_nested_message: [NestedMessage { bb: 21 }]
+    });
+    expect_that!(
+        &actual,
+        proto_eq(expected.clone())
+            .ignoring_fields(["optional_nested_message.bb", "repeated_nested_message.bb"])
+    );
+    expect_that!(&actual, not(proto_eq(expected).ignoring_fields(["optional_nested_message.bb"])));
+}
+
+#[googletest::test]
+#[should_panic(expected = "is not a path")]
+fn ignoring_fields_panics_on_unknown_field() {
+    let _ = proto_eq(TestAllTypesProto3::new()).ignoring_fields(["optional_nested_message.nope"]);
+}
+
+#[googletest::test]
+fn ignoring_repeated_field_ordering_compares_as_sets() {
+    let expected = proto!(TestAllTypesProto3 { repeated_int32: [1, 2, 3] });
+    let actual = proto!(TestAllTypesProto3 { repeated_int32: [3, 1, 2] });
+    expect_that!(&actual, proto_eq(expected.clone()).ignoring_repeated_field_ordering());
+    expect_that!(&actual, not(proto_eq(expected.clone())));
+
+    let actual = proto!(TestAllTypesProto3 { repeated_int32: [3, 1, 4] });
+    expect_that!(&actual, not(proto_eq(expected).ignoring_repeated_field_ordering()));
+}
+
+#[googletest::test]
+fn modifiers_combine() {
+    let expected = proto!(TestAllTypesProto3 { repeated_int32: [2, 1], optional_double: 1.0 });
+    let actual = proto!(TestAllTypesProto3 {
+        optional_int32: 5,
+        repeated_int32: [1, 2],
+        optional_double: 1.0001
+    });
+    let matcher =
+        proto_partially_eq(expected).approximately(0.01).ignoring_repeated_field_ordering();
+    expect_that!(
+        matcher.describe(MatcherResult::Match).to_string(),
+        all!(
+            contains_substring("partially"),
+            contains_substring("ignoring repeated field ordering")
+        )
+    );
+    expect_that!(&actual, matcher);
+}
+
+#[googletest::test]
+fn proto_eq_text_parses_expected() {
+    let actual = proto!(TestAllTypesProto3 {
+        optional_int32: 1,
+        optional_nested_message: NestedMessage { bb: 10 }
+    });
+    expect_that!(
+        &actual,
+        proto_eq_text::<TestAllTypesProto3>("optional_int32: 1 optional_nested_message { bb: 10 }")
+    );
+    expect_that!(&actual, not(proto_eq_text::<TestAllTypesProto3>("optional_int32: 2")));
+}
+
+#[googletest::test]
+#[should_panic(expected = "the expected message is invalid")]
+fn proto_eq_text_panics_on_invalid_text() {
+    let _ = proto_eq_text::<TestAllTypesProto3>("no_such_field: 1");
+}
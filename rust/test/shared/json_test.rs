@@ -0,0 +1,108 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use protobuf::prelude::*;
+use protobuf::reflect::{DynamicMessage, ReflectMessage};
+use protobuf::{JsonParseOptions, JsonPrintOptions};
+use unittest_proto3_rust_proto::{test_all_types, TestAllTypes};
+
+fn populated() -> TestAllTypes {
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_int32(5);
+    msg.set_optional_int64(-7);
+    msg.set_optional_string("hi");
+    msg.set_optional_bytes(b"\x01\x02");
+    msg.set_optional_nested_enum(test_all_types::NestedEnum::Bar);
+    msg.optional_nested_message_mut().set_bb(3);
+    msg.repeated_int32_mut().extend([1, 2]);
+    msg
+}
+
+#[googletest::test]
+fn test_to_json() {
+    let json = populated().to_json().unwrap();
+    // The canonical mapping quotes 64-bit integers, base64-encodes bytes and
+    // prints enums by name.
+    assert_that!(
+        json,
+        all![
+            contains_substring(r#""optionalInt32":5"#),
+            contains_substring(r#""optionalInt64":"-7""#),
+            contains_substring(r#""optionalString":"hi""#),
+            contains_substring(r#""optionalBytes":"AQI=""#),
+            contains_substring(r#""optionalNestedMessage":{"bb":3}"#),
+            contains_substring(r#""optionalNestedEnum":"BAR""#),
+            contains_substring(r#""repeatedInt32":[1,2]"#),
+        ]
+    );
+    assert_that!(populated().as_view().to_json(), ok(eq(&json)));
+    assert_that!(TestAllTypes::new().to_json(), ok(eq("{}")));
+}
+
+#[googletest::test]
+fn test_print_options() {
+    let options = JsonPrintOptions::new().preserve_proto_field_names(true);
+    let json = populated().to_json_with_options(&options).unwrap();
+    assert_that!(json, contains_substring(r#""optional_int32":5"#));
+    assert_that!(json, not(contains_substring("optionalInt32")));
+
+    let options = JsonPrintOptions::new().emit_default_values(true);
+    let json = TestAllTypes::new().to_json_with_options(&options).unwrap();
+    assert_that!(
+        json,
+        all![
+            contains_substring(r#""optionalInt32":0"#),
+            contains_substring(r#""optionalString":"""#),
+            contains_substring(r#""repeatedInt32":[]"#),
+            // Fields with presence are still omitted.
+            not(contains_substring("optionalNestedMessage")),
+        ]
+    );
+}
+
+#[googletest::test]
+fn test_from_json() {
+    let msg = TestAllTypes::from_json(&populated().to_json().unwrap()).unwrap();
+    assert_that!(msg.optional_int32(), eq(5));
+    assert_that!(msg.optional_int64(), eq(-7));
+    assert_that!(msg.optional_string(), eq("hi"));
+    assert_that!(msg.optional_bytes(), eq(b"\x01\x02"));
+    assert_that!(msg.optional_nested_enum(), eq(test_all_types::NestedEnum::Bar));
+    assert_that!(msg.optional_nested_message().bb(), eq(3));
+    assert_that!(msg.repeated_int32().iter().collect::<Vec<_>>(), elements_are![eq(1), eq(2)]);
+
+    // Both the JSON names and the proto names of fields are accepted, and
+    // 64-bit integers may be unquoted.
+    let msg = TestAllTypes::from_json(r#"{"optional_int32": 1, "optionalInt64": 2}"#).unwrap();
+    assert_that!(msg.optional_int32(), eq(1));
+    assert_that!(msg.optional_int64(), eq(2));
+}
+
+#[googletest::test]
+fn test_from_json_errors() {
+    let error = TestAllTypes::from_json(r#"{"optionalInt32": "#).unwrap_err();
+    assert_that!(error.to_string(), starts_with("Couldn't parse JSON into a proto: "));
+    assert_that!(TestAllTypes::from_json(r#"{"optionalInt32": "x"}"#), err(anything()));
+
+    let json = r#"{"optionalInt32": 1, "noSuchField": true}"#;
+    assert_that!(TestAllTypes::from_json(json), err(anything()));
+    let options = JsonParseOptions::new().ignore_unknown_fields(true);
+    let msg = TestAllTypes::from_json_with_options(json, &options).unwrap();
+    assert_that!(msg.optional_int32(), eq(1));
+}
+
+#[googletest::test]
+fn test_dynamic_message() {
+    let json = populated().to_json().unwrap();
+    let dynamic = DynamicMessage::from_json(TestAllTypes::descriptor(), &json).unwrap();
+    assert_that!(dynamic.to_json(), ok(eq(&json)));
+    assert_that!(dynamic.as_message_ref().to_json(), ok(eq(&json)));
+
+    let typed = TestAllTypes::parse(&dynamic.serialize().unwrap()).unwrap();
+    assert_that!(typed.optional_int32(), eq(5));
+}
@@ -0,0 +1,103 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use map_unittest_rust_proto::TestMap;
+use protobuf::prelude::*;
+use unittest_rust_proto::TestAllTypes;
+
+fn msg() -> TestAllTypes {
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_int32(1);
+    msg.set_optional_string("a");
+    msg.optional_nested_message_mut().set_bb(2);
+    msg.repeated_int32_mut().extend([3, 4]);
+    msg
+}
+
+#[googletest::test]
+fn test_eq() {
+    assert_that!(msg() == msg(), eq(true));
+    assert_that!(TestAllTypes::new() == TestAllTypes::new(), eq(true));
+    assert_that!(msg() == TestAllTypes::new(), eq(false));
+
+    let mut other = msg();
+    other.optional_nested_message_mut().set_bb(3);
+    assert_that!(msg() == other, eq(false));
+}
+
+#[googletest::test]
+fn test_eq_across_proxies() {
+    let a = msg();
+    let mut b = msg();
+    assert_that!(a == a.as_view(), eq(true));
+    assert_that!(a.as_view() == a, eq(true));
+    assert_that!(a.as_view() == b.as_view(), eq(true));
+    assert_that!(a == b.as_mut(), eq(true));
+    assert_that!(b.as_mut() == a, eq(true));
+    assert_that!(b.as_mut() == a.as_view(), eq(true));
+    assert_that!(a.as_view() == b.as_mut(), eq(true));
+
+    b.set_optional_int32(5);
+    assert_that!(a == b.as_view(), eq(false));
+    assert_that!(b.as_mut() == a, eq(false));
+}
+
+#[googletest::test]
+fn test_eq_presence() {
+    // Setting a field to its default value is still a difference.
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_int32(0);
+    assert_that!(msg == TestAllTypes::new(), eq(false));
+    msg.clear_optional_int32();
+    assert_that!(msg == TestAllTypes::new(), eq(true));
+}
+
+#[googletest::test]
+fn test_eq_repeated_order() {
+    let mut other = TestAllTypes::new();
+    other.set_optional_int32(1);
+    other.set_optional_string("a");
+    other.optional_nested_message_mut().set_bb(2);
+    other.repeated_int32_mut().extend([4, 3]);
+    assert_that!(msg() == other, eq(false));
+}
+
+#[googletest::test]
+fn test_eq_map_order() {
+    let mut a = TestMap::new();
+    a.map_int32_int32_mut().insert(1, 10);
+    a.map_int32_int32_mut().insert(2, 20);
+    let mut b = TestMap::new();
+    b.map_int32_int32_mut().insert(2, 20);
+    b.map_int32_int32_mut().insert(1, 10);
+    assert_that!(a == b, eq(true));
+
+    b.map_int32_int32_mut().insert(1, 11);
+    assert_that!(a == b, eq(false));
+}
+
+#[googletest::test]
+fn test_eq_floating_point() {
+    let mut a = TestAllTypes::new();
+    a.set_optional_double(f64::NAN);
+    let mut b = TestAllTypes::new();
+    b.set_optional_double(f64::NAN);
+    assert_that!(a == b, eq(true));
+
+    a.set_optional_float(0.0);
+    b.set_optional_float(-0.0);
+    assert_that!(a == b, eq(false));
+}
+
+#[googletest::test]
+fn test_eq_ignores_unknown_fields() {
+    // Field 1000, varint 1, is unknown to `TestAllTypes`.
+    let with_unknown = TestAllTypes::parse(&[0xc0, 0x3e, 0x01]).unwrap();
+    assert_that!(with_unknown.unknown_fields().is_empty(), eq(false));
+    assert_that!(with_unknown == TestAllTypes::new(), eq(true));
+}
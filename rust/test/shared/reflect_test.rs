@@ -0,0 +1,392 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use map_unittest_rust_proto::TestMap;
+use protobuf::prelude::*;
+use protobuf::reflect::{
+    DynamicMessage, FieldType, ReflectEnum, ReflectMessage, ReflectMessageMut, ReflectValue,
+    ReflectValueRef,
+};
+use unittest_rust_proto::{
+    test_all_types, ForeignEnum, ForeignMessage, TestAllExtensions, TestAllTypes, TestAllTypesMut,
+    TestAllTypesView, OPTIONAL_INT32_EXTENSION, OPTIONAL_STRING_EXTENSION,
+};
+
+#[googletest::test]
+fn test_message_descriptor() {
+    let desc = TestAllTypes::descriptor();
+    assert_that!(desc.name(), eq("TestAllTypes"));
+    assert_that!(desc.full_name(), eq("protobuf_unittest.TestAllTypes"));
+    assert_that!(desc.containing_type(), none());
+    assert_that!(desc.is_map_entry(), eq(false));
+    assert_that!(TestAllTypesView::descriptor(), eq(desc.clone()));
+    assert_that!(TestAllTypesMut::descriptor(), eq(desc));
+}
+
+#[googletest::test]
+fn test_file_descriptor() {
+    let file = TestAllTypes::descriptor().file();
+    assert_that!(file.name(), eq("google/protobuf/unittest.proto"));
+    assert_that!(file.package(), eq("protobuf_unittest"));
+    assert_that!(
+        file.dependencies().map(|d| d.name().to_string()).collect::<Vec<_>>(),
+        contains(eq("google/protobuf/unittest_import.proto"))
+    );
+    assert_that!(file.messages().any(|m| m == TestAllTypes::descriptor()), eq(true));
+    assert_that!(file.enums().any(|e| e == ForeignEnum::descriptor()), eq(true));
+}
+
+#[googletest::test]
+fn test_field_lookup() {
+    let desc = TestAllTypes::descriptor();
+    let field = desc.field_by_number(1).unwrap();
+    assert_that!(field.name(), eq("optional_int32"));
+    assert_that!(field.full_name(), eq("protobuf_unittest.TestAllTypes.optional_int32"));
+    assert_that!(field.json_name(), eq("optionalInt32"));
+    assert_that!(field.field_type(), eq(FieldType::Int32));
+    assert_that!(field.is_repeated(), eq(false));
+    assert_that!(field.has_presence(), eq(true));
+    assert_that!(field.containing_type(), eq(desc.clone()));
+    assert_that!(desc.field_by_name("optional_int32"), some(eq(field.clone())));
+    assert_that!(desc.fields().nth(field.index()), some(eq(field)));
+
+    assert_that!(desc.field_by_number(99999), none());
+    assert_that!(desc.field_by_name("not_a_field"), none());
+}
+
+#[googletest::test]
+fn test_field_types() {
+    let desc = TestAllTypes::descriptor();
+    let repeated = desc.field_by_name("repeated_string").unwrap();
+    assert_that!(repeated.field_type(), eq(FieldType::String));
+    assert_that!(repeated.is_repeated(), eq(true));
+    assert_that!(repeated.has_presence(), eq(false));
+
+    let nested = desc.field_by_name("optional_nested_message").unwrap();
+    assert_that!(nested.field_type(), eq(FieldType::Message));
+    assert_that!(
+        nested.message_type().map(|m| m.full_name().to_string()),
+        some(eq("protobuf_unittest.TestAllTypes.NestedMessage"))
+    );
+
+    let nested_enum = desc.field_by_name("optional_nested_enum").unwrap();
+    assert_that!(nested_enum.field_type(), eq(FieldType::Enum));
+    assert_that!(nested_enum.enum_type(), some(eq(test_all_types::NestedEnum::descriptor())));
+    assert_that!(nested_enum.message_type(), none());
+}
+
+#[googletest::test]
+fn test_map_field() {
+    let field = TestMap::descriptor().field_by_name("map_int32_int32").unwrap();
+    assert_that!(field.is_map(), eq(true));
+    assert_that!(field.is_repeated(), eq(true));
+    let entry = field.message_type().unwrap();
+    assert_that!(entry.is_map_entry(), eq(true));
+    assert_that!(entry.field_by_number(1).map(|f| f.name().to_string()), some(eq("key")));
+    assert_that!(entry.field_by_number(2).map(|f| f.name().to_string()), some(eq("value")));
+}
+
+#[googletest::test]
+fn test_oneof() {
+    let desc = TestAllTypes::descriptor();
+    let oneof = desc.oneofs().find(|o| o.name() == "oneof_field").unwrap();
+    assert_that!(oneof.full_name(), eq("protobuf_unittest.TestAllTypes.oneof_field"));
+    assert_that!(oneof.containing_type(), eq(desc.clone()));
+    assert_that!(
+        oneof.fields().map(|f| f.number()).collect::<Vec<_>>(),
+        elements_are![eq(111), eq(112), eq(113), eq(114), eq(115), eq(116), eq(117)]
+    );
+    let field = desc.field_by_name("oneof_string").unwrap();
+    assert_that!(field.containing_oneof(), some(eq(oneof)));
+    assert_that!(desc.field_by_name("optional_int32").unwrap().containing_oneof(), none());
+}
+
+#[googletest::test]
+fn test_nested_types() {
+    let desc = TestAllTypes::descriptor();
+    let nested = desc.nested_messages().find(|m| m.name() == "NestedMessage").unwrap();
+    assert_that!(nested.containing_type(), some(eq(desc.clone())));
+    assert_that!(nested.file(), eq(desc.file()));
+
+    let nested_enum = test_all_types::NestedEnum::descriptor();
+    assert_that!(desc.nested_enums().any(|e| e == nested_enum), eq(true));
+    assert_that!(nested_enum.containing_type(), some(eq(desc)));
+}
+
+#[googletest::test]
+fn test_enum_descriptor() {
+    let desc = test_all_types::NestedEnum::descriptor();
+    assert_that!(desc.name(), eq("NestedEnum"));
+    assert_that!(desc.full_name(), eq("protobuf_unittest.TestAllTypes.NestedEnum"));
+    assert_that!(desc.is_closed(), eq(true));
+    assert_that!(
+        desc.values().map(|v| v.name().to_string()).collect::<Vec<_>>(),
+        elements_are![eq("FOO"), eq("BAR"), eq("BAZ"), eq("NEG")]
+    );
+    let neg = desc.value_by_number(-1).unwrap();
+    assert_that!(neg.name(), eq("NEG"));
+    assert_that!(neg.full_name(), eq("protobuf_unittest.TestAllTypes.NEG"));
+    assert_that!(neg.number(), eq(-1));
+    assert_that!(neg.enum_type(), eq(desc.clone()));
+    assert_that!(desc.value_by_number(42), none());
+}
+
+#[googletest::test]
+fn test_get_and_set_scalars() {
+    let desc = TestAllTypes::descriptor();
+    let int32 = desc.field_by_name("optional_int32").unwrap();
+    let uint64 = desc.field_by_name("optional_uint64").unwrap();
+    let string = desc.field_by_name("optional_string").unwrap();
+    let bytes = desc.field_by_name("optional_bytes").unwrap();
+    let nested_enum = desc.field_by_name("optional_nested_enum").unwrap();
+
+    let mut msg = TestAllTypes::new();
+    assert_that!(msg.has_field(&int32), eq(false));
+    assert!(matches!(msg.get_field(&int32), ReflectValueRef::I32(0)));
+
+    msg.set_field(&int32, 42);
+    msg.set_field(&uint64, 7u64);
+    msg.set_field(&string, "hello");
+    msg.set_field(&bytes, &b"\x00\x01"[..]);
+    msg.set_field(&nested_enum, ReflectValueRef::Enum(test_all_types::NestedEnum::Baz.into()));
+
+    assert_that!(msg.optional_int32(), eq(42));
+    assert_that!(msg.optional_uint64(), eq(7));
+    assert_that!(msg.optional_string(), eq("hello"));
+    assert_that!(msg.optional_bytes(), eq(b"\x00\x01"));
+    assert_that!(msg.optional_nested_enum(), eq(test_all_types::NestedEnum::Baz));
+
+    assert_that!(msg.has_field(&int32), eq(true));
+    assert!(matches!(msg.get_field(&int32), ReflectValueRef::I32(42)));
+    let ReflectValueRef::String(s) = msg.get_field(&string) else {
+        panic!("expected a string, got {:?}", msg.get_field(&string));
+    };
+    assert_that!(&*s, eq("hello"));
+
+    msg.clear_field(&int32);
+    assert_that!(msg.has_optional_int32(), eq(false));
+}
+
+#[googletest::test]
+fn test_default_values() {
+    let desc = TestAllTypes::descriptor();
+    let msg = TestAllTypes::new();
+    let default_int32 = desc.field_by_name("default_int32").unwrap();
+    let default_string = desc.field_by_name("default_string").unwrap();
+    assert!(matches!(msg.get_field(&default_int32), ReflectValueRef::I32(41)));
+    let ReflectValueRef::String(s) = msg.get_field(&default_string) else {
+        panic!("expected a string");
+    };
+    assert_that!(&*s, eq("hello"));
+    assert_that!(msg.has_field(&default_int32), eq(false));
+}
+
+#[googletest::test]
+fn test_view_and_mut() {
+    let int32 = TestAllTypes::descriptor().field_by_name("optional_int32").unwrap();
+    let mut msg = TestAllTypes::new();
+    {
+        let mut msg_mut: TestAllTypesMut<'_> = msg.as_mut();
+        msg_mut.set_field(&int32, 5);
+        assert!(matches!(msg_mut.get_field(&int32), ReflectValueRef::I32(5)));
+    }
+    let view: TestAllTypesView<'_> = msg.as_view();
+    assert_that!(view.has_field(&int32), eq(true));
+    assert!(matches!(view.get_field(&int32), ReflectValueRef::I32(5)));
+    assert_that!(view.as_message_ref().descriptor(), eq(TestAllTypes::descriptor()));
+}
+
+#[googletest::test]
+fn test_message_fields() {
+    let desc = TestAllTypes::descriptor();
+    let foreign = desc.field_by_name("optional_foreign_message").unwrap();
+    let c = ForeignMessage::descriptor().field_by_name("c").unwrap();
+
+    let mut msg = TestAllTypes::new();
+    let ReflectValueRef::Message(unset) = msg.get_field(&foreign) else {
+        panic!("expected a message");
+    };
+    assert_that!(unset.descriptor(), eq(ForeignMessage::descriptor()));
+    assert_that!(msg.has_field(&foreign), eq(false));
+
+    msg.mut_message(&foreign).set_field(&c, 3);
+    assert_that!(msg.optional_foreign_message().c(), eq(3));
+
+    let mut other = ForeignMessage::new();
+    other.set_c(9);
+    msg.set_field(&foreign, other.as_message_ref());
+    assert_that!(msg.optional_foreign_message().c(), eq(9));
+    assert_that!(msg.has_field(&foreign), eq(true));
+}
+
+#[googletest::test]
+fn test_oneof_fields() {
+    let desc = TestAllTypes::descriptor();
+    let oneof_uint32 = desc.field_by_name("oneof_uint32").unwrap();
+    let oneof_string = desc.field_by_name("oneof_string").unwrap();
+    let oneof_nested_message = desc.field_by_name("oneof_nested_message").unwrap();
+    let bb = oneof_nested_message.message_type().unwrap().field_by_name("bb").unwrap();
+
+    let mut msg = TestAllTypes::new();
+    msg.set_field(&oneof_uint32, 7u32);
+    assert_that!(msg.has_field(&oneof_uint32), eq(true));
+    msg.set_field(&oneof_string, "hi");
+    assert_that!(msg.has_field(&oneof_uint32), eq(false));
+    assert_that!(msg.oneof_string(), eq("hi"));
+
+    msg.mut_message(&oneof_nested_message).set_field(&bb, 4);
+    assert_that!(msg.has_field(&oneof_string), eq(false));
+    assert_that!(msg.oneof_nested_message().bb(), eq(4));
+}
+
+#[googletest::test]
+fn test_repeated_fields() {
+    let desc = TestAllTypes::descriptor();
+    let repeated_int32 = desc.field_by_name("repeated_int32").unwrap();
+    let repeated_string = desc.field_by_name("repeated_string").unwrap();
+    let repeated_foreign = desc.field_by_name("repeated_foreign_message").unwrap();
+    let c = ForeignMessage::descriptor().field_by_name("c").unwrap();
+
+    let mut msg = TestAllTypes::new();
+    assert_that!(msg.has_field(&repeated_int32), eq(false));
+    {
+        let mut ints = msg.mut_repeated(&repeated_int32);
+        ints.push(1);
+        ints.push(2);
+        ints.set(0, 10);
+        assert_that!(ints.len(), eq(2));
+    }
+    msg.mut_repeated(&repeated_string).push("a");
+    msg.mut_repeated(&repeated_foreign).push(ForeignMessage::new().as_message_ref());
+    msg.mut_repeated(&repeated_foreign).mut_message(0).set_field(&c, 5);
+
+    assert_that!(msg.repeated_int32(), elements_are![eq(10), eq(2)]);
+    assert_that!(msg.repeated_string().get(0), some(eq("a")));
+    assert_that!(msg.repeated_foreign_message().get(0).unwrap().c(), eq(5));
+    assert_that!(msg.has_field(&repeated_int32), eq(true));
+
+    let ints = msg.get_repeated(&repeated_int32);
+    assert!(matches!(ints.get(1), Some(ReflectValueRef::I32(2))));
+    assert!(ints.get(2).is_none());
+    assert_that!(
+        ints.iter()
+            .map(|v| match v {
+                ReflectValueRef::I32(i) => i,
+                other => panic!("unexpected {other:?}"),
+            })
+            .collect::<Vec<_>>(),
+        elements_are![eq(10), eq(2)]
+    );
+
+    msg.mut_repeated(&repeated_int32).clear();
+    assert_that!(msg.repeated_int32().len(), eq(0));
+}
+
+#[googletest::test]
+fn test_map_fields() {
+    let desc = TestMap::descriptor();
+    let int32_int32 = desc.field_by_name("map_int32_int32").unwrap();
+    let string_string = desc.field_by_name("map_string_string").unwrap();
+
+    let mut msg = TestMap::new();
+    assert_that!(msg.mut_map(&int32_int32).insert(1, 10), eq(true));
+    assert_that!(msg.mut_map(&int32_int32).insert(1, 11), eq(false));
+    assert_that!(msg.mut_map(&int32_int32).insert(2, 20), eq(true));
+    msg.mut_map(&string_string).insert("k", "v");
+
+    assert_that!(msg.map_int32_int32().get(1), some(eq(11)));
+    assert_that!(msg.map_string_string().get("k").map(|v| v.to_string()), some(eq("v")));
+
+    let map = msg.get_map(&int32_int32);
+    assert_that!(map.len(), eq(2));
+    assert!(matches!(map.get(2), Some(ReflectValueRef::I32(20))));
+    assert!(map.get(3).is_none());
+    let mut keys: Vec<i32> = map
+        .iter()
+        .map(|(k, _)| match k {
+            ReflectValueRef::I32(k) => k,
+            other => panic!("unexpected {other:?}"),
+        })
+        .collect();
+    keys.sort();
+    assert_that!(keys, elements_are![eq(1), eq(2)]);
+
+    assert_that!(msg.mut_map(&int32_int32).remove(1), eq(true));
+    assert_that!(msg.mut_map(&int32_int32).remove(1), eq(false));
+    assert_that!(msg.map_int32_int32().len(), eq(1));
+}
+
+#[googletest::test]
+fn test_dynamic_message() {
+    let desc = TestAllTypes::descriptor();
+    let int32 = desc.field_by_name("optional_int32").unwrap();
+    let string = desc.field_by_name("optional_string").unwrap();
+
+    let mut msg = DynamicMessage::new(desc.clone());
+    assert_that!(msg.descriptor(), eq(desc));
+    msg.as_message_mut().set_field(&int32, 1);
+    msg.as_message_mut().set_field(&string, &ReflectValue::from("x"));
+
+    let copy = msg.clone();
+    msg.as_message_mut().clear_field(&int32);
+    assert!(matches!(copy.as_message_ref().get_field(&int32), ReflectValueRef::I32(1)));
+    assert_that!(msg.as_message_ref().has_field(&int32), eq(false));
+    assert_that!(format!("{copy:?}"), contains_substring("optional_string"));
+
+    let mut typed = TestAllTypes::new();
+    typed.set_optional_int32(3);
+    let owned = typed.as_message_ref().to_owned();
+    assert!(matches!(owned.as_message_ref().get_field(&int32), ReflectValueRef::I32(3)));
+}
+
+#[googletest::test]
+fn test_extensions() {
+    let mut msg = TestAllExtensions::new();
+    assert_that!(msg.as_message_ref().extensions(), is_empty());
+    msg.set_extension(&OPTIONAL_STRING_EXTENSION, "x");
+    msg.set_extension(&OPTIONAL_INT32_EXTENSION, 1);
+
+    let extensions = msg.as_message_ref().extensions();
+    assert_that!(
+        extensions.iter().map(|ext| ext.full_name().to_string()).collect::<Vec<_>>(),
+        elements_are![
+            eq("protobuf_unittest.optional_int32_extension"),
+            eq("protobuf_unittest.optional_string_extension")
+        ]
+    );
+    let int32 = &extensions[0];
+    assert_that!(int32.is_extension(), eq(true));
+    assert_that!(int32.containing_type(), eq(TestAllExtensions::descriptor()));
+    assert_that!(msg.has_field(int32), eq(true));
+    assert!(matches!(msg.get_field(int32), ReflectValueRef::I32(1)));
+    let field = TestAllTypes::descriptor().field_by_name("optional_int32").unwrap();
+    assert_that!(field.is_extension(), eq(false));
+}
+
+#[googletest::test]
+#[should_panic(expected = "is an extension")]
+fn test_set_extension_panics() {
+    let mut msg = TestAllExtensions::new();
+    msg.set_extension(&OPTIONAL_INT32_EXTENSION, 1);
+    let ext = msg.as_message_ref().extensions().remove(0);
+    msg.set_field(&ext, 2);
+}
+
+#[googletest::test]
+#[should_panic(expected = "is not a field of")]
+fn test_field_of_other_message_panics() {
+    let field = ForeignMessage::descriptor().field_by_name("c").unwrap();
+    TestAllTypes::new().get_field(&field);
+}
+
+#[googletest::test]
+#[should_panic(expected = "can't be stored in")]
+fn test_mismatched_value_panics() {
+    let field = TestAllTypes::descriptor().field_by_name("optional_int32").unwrap();
+    TestAllTypes::new().set_field(&field, 1u64);
+}
@@ -7,12 +7,18 @@
 
 use googletest::prelude::*;
 use protobuf::prelude::*;
-use protobuf::View;
+use protobuf::{
+    ParseErrorKind, ParseOptions, ReadError, SerializeErrorKind, SerializeOptions, View,
+};
 
+use map_unittest_rust_proto::{TestMap, TestRequiredMessageMap};
 use paste::paste;
+use std::io;
 use unittest_proto3_optional_rust_proto::TestProto3Optional;
 use unittest_proto3_rust_proto::TestAllTypes as TestAllTypesProto3;
-use unittest_rust_proto::TestAllTypes;
+use unittest_rust_proto::{
+    NestedTestAllTypes, TestAllTypes, TestPackedTypes, TestRequired, TestRequiredForeign,
+};
 
 macro_rules! generate_parameterized_serialization_test {
     ($(($type: ident, $name_ext: ident)),*) => {
@@ -52,6 +58,74 @@ macro_rules! generate_parameterized_serialization_test {
                 assert_that!(msg.optional_bytes(), eq(msg2.optional_bytes()));
             }
 
+            #[googletest::test]
+            fn [< serialize_to_vec_appends_ $name_ext>]() {
+                let mut msg = [< $type >]::new();
+                msg.set_optional_int64(42);
+                msg.set_optional_bytes(b"serialize to vec test");
+                let expected = msg.serialize().unwrap();
+
+                let mut out = b"prefix".to_vec();
+                msg.serialize_to_vec(&mut out).unwrap();
+                assert_that!(&out[..6], eq(b"prefix"));
+                assert_that!(&out[6..], eq(&expected[..]));
+
+                out.clear();
+                msg.as_view().serialize_to_vec(&mut out).unwrap();
+                msg.as_mut().serialize_to_vec(&mut out).unwrap();
+                assert_that!(out, eq([&expected[..], &expected[..]].concat()));
+
+                // Spare capacity that is too small for the message.
+                let mut out = Vec::with_capacity(expected.len() / 2);
+                out.push(0xaa);
+                msg.serialize_to_vec(&mut out).unwrap();
+                assert_that!(out, eq([&[0xaa][..], &expected[..]].concat()));
+            }
+
+            #[googletest::test]
+            fn [< serialize_into_slice_ $name_ext>]() {
+                let mut msg = [< $type >]::new();
+                msg.set_optional_int64(42);
+                msg.set_optional_bytes(b"serialize into test");
+                let expected = msg.serialize().unwrap();
+
+                let mut buf = [0u8; 64];
+                let len = msg.serialize_into(&mut buf).unwrap();
+                assert_that!(&buf[..len], eq(&expected[..]));
+
+                let mut small = vec![0u8; expected.len() - 1];
+                let err = msg.as_view().serialize_into(&mut small).unwrap_err();
+                assert_that!(err.kind(), eq(SerializeErrorKind::BufferTooSmall));
+            }
+
+            #[googletest::test]
+            fn [< serialize_to_writer_ $name_ext>]() {
+                let mut msg = [< $type >]::new();
+                msg.set_optional_int64(42);
+                msg.set_optional_bytes(b"serialize to writer test");
+
+                let mut out = Vec::new();
+                msg.as_mut().serialize_to_writer(&mut out).unwrap();
+                assert_that!(out, eq(msg.serialize().unwrap()));
+            }
+
+            #[googletest::test]
+            fn [< serialize_to_writer_with_buffer_ $name_ext>]() {
+                let mut msg = [< $type >]::new();
+                msg.set_optional_int64(42);
+                msg.set_optional_bytes(b"serialize to writer with buffer test");
+                let expected = msg.serialize().unwrap();
+
+                let mut buf = b"stale".to_vec();
+                let mut out = Vec::new();
+                msg.serialize_to_writer_with_buffer(&mut out, &mut buf).unwrap();
+                let capacity = buf.capacity();
+                msg.as_view().serialize_to_writer_with_buffer(&mut out, &mut buf).unwrap();
+                assert_that!(out, eq([&expected[..], &expected[..]].concat()));
+                assert_that!(buf, eq(expected));
+                assert_that!(buf.capacity(), eq(capacity));
+            }
+
             #[googletest::test]
             fn [< deserialize_empty_ $name_ext>]() {
                 assert!([< $type >]::parse(&[]).is_ok());
@@ -59,7 +133,8 @@ macro_rules! generate_parameterized_serialization_test {
 
             #[googletest::test]
             fn [< deserialize_error_ $name_ext>]() {
-                assert!([< $type >]::parse(b"not a serialized proto").is_err());
+                let err = [< $type >]::parse(b"not a serialized proto").unwrap_err();
+                assert_that!(err.kind(), eq(ParseErrorKind::Malformed));
             }
 
             #[googletest::test]
@@ -98,6 +173,292 @@ generate_parameterized_serialization_test!(
     (TestProto3Optional, proto3_optional)
 );
 
+#[googletest::test]
+fn deserialize_missing_required_field() {
+    let err = TestRequired::parse(&[]).unwrap_err();
+    assert_that!(err.kind(), eq(ParseErrorKind::MissingRequired));
+
+    let mut msg = TestRequired::new();
+    msg.set_a(1);
+    msg.set_b(2);
+    msg.set_c(3);
+    let serialized = msg.serialize().unwrap();
+    assert_that!(TestRequired::parse(&serialized), ok(anything()));
+}
+
+#[googletest::test]
+fn parse_with_options_recursion_limit() {
+    let mut msg = NestedTestAllTypes::new();
+    msg.child_mut().child_mut().child_mut().payload_mut().set_optional_int32(1);
+    let serialized = msg.serialize().unwrap();
+
+    // The submessages are nested four levels deep.
+    let options = ParseOptions::new().recursion_limit(4);
+    let parsed = NestedTestAllTypes::parse_with_options(&serialized, &options).unwrap();
+    assert_that!(parsed.child().child().child().payload().optional_int32(), eq(1));
+
+    let options = ParseOptions::new().recursion_limit(3);
+    let err = NestedTestAllTypes::parse_with_options(&serialized, &options).unwrap_err();
+    assert_that!(err.kind(), eq(ParseErrorKind::MaxDepthExceeded));
+}
+
+#[test]
+#[should_panic(expected = "the recursion limit must be at least 1")]
+fn parse_with_options_zero_recursion_limit() {
+    let _ = ParseOptions::new().recursion_limit(0);
+}
+
+#[googletest::test]
+fn parse_with_options_partial() {
+    // Only `a` (field 1) is set.
+    let serialized = [0x08, 0x01];
+    let err = TestRequired::parse_with_options(&serialized, &ParseOptions::new()).unwrap_err();
+    assert_that!(err.kind(), eq(ParseErrorKind::MissingRequired));
+
+    let options = ParseOptions::new().check_required(false);
+    let parsed = TestRequired::parse_with_options(&serialized, &options).unwrap();
+    assert_that!(parsed.a(), eq(1));
+    assert_that!(parsed.has_b(), eq(false));
+}
+
+#[googletest::test]
+fn parse_with_options_max_input_len() {
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_bytes(b"0123456789");
+    let serialized = msg.serialize().unwrap();
+
+    let options = ParseOptions::new().max_input_len(serialized.len());
+    let parsed = TestAllTypes::parse_with_options(&serialized, &options).unwrap();
+    assert_that!(parsed.optional_bytes(), eq(b"0123456789"));
+
+    let options = ParseOptions::new().max_input_len(serialized.len() - 1);
+    let err = TestAllTypes::parse_with_options(&serialized, &options).unwrap_err();
+    assert_that!(err.kind(), eq(ParseErrorKind::InputTooLong));
+}
+
+#[googletest::test]
+fn parse_with_options_max_input_len_does_not_bound_memory() {
+    // Every byte of the packed payload is an element that takes up four bytes
+    // once parsed, so the parsed message is larger than the input.
+    let mut msg = TestPackedTypes::new();
+    msg.packed_int32_mut().extend_from_slice(&[0; 1000]);
+    let serialized = msg.serialize().unwrap();
+    assert_that!(serialized.len(), lt(1010));
+
+    let options = ParseOptions::new().max_input_len(serialized.len());
+    let parsed = TestPackedTypes::parse_with_options(&serialized, &options).unwrap();
+    assert_that!(parsed.packed_int32().len(), eq(1000));
+}
+
+#[googletest::test]
+fn parse_borrowed() {
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_string("hello");
+    msg.set_optional_bytes(b"payload");
+    msg.repeated_bytes_mut().push(b"one");
+    let serialized = msg.serialize().unwrap();
+
+    let parsed = TestAllTypes::parse_borrowed(&serialized).unwrap();
+    assert_that!(parsed.as_view().optional_string(), eq("hello"));
+    assert_that!(parsed.as_view().optional_bytes(), eq(b"payload"));
+    assert_that!(parsed.as_view().repeated_bytes().get(0), some(eq(b"one")));
+
+    let owned = parsed.to_owned();
+    drop(parsed);
+    drop(serialized);
+    assert_that!(owned.optional_bytes(), eq(b"payload"));
+}
+
+#[googletest::test]
+fn parse_borrowed_errors() {
+    // A truncated varint.
+    let err = TestAllTypes::parse_borrowed(&[0x08, 0x80]).unwrap_err();
+    assert_that!(err.kind(), eq(ParseErrorKind::Malformed));
+
+    let err = TestRequired::parse_borrowed(&[]).unwrap_err();
+    assert_that!(err.kind(), eq(ParseErrorKind::MissingRequired));
+}
+
+#[googletest::test]
+fn serialize_partial_message() {
+    let mut msg = TestRequired::new();
+    msg.set_a(1);
+    let serialized = msg.serialize().unwrap();
+    assert_that!(serialized, eq(vec![0x08, 0x01]));
+
+    let mut out = Vec::new();
+    msg.serialize_to_vec(&mut out).unwrap();
+    assert_that!(out, eq(serialized.clone()));
+
+    let mut buf = [0; 2];
+    assert_that!(msg.serialize_into(&mut buf), ok(eq(2)));
+    assert_that!(buf.to_vec(), eq(serialized));
+
+    let mut msg = TestRequiredForeign::new();
+    msg.repeated_message_mut().push(TestRequired::new());
+    assert_that!(msg.as_view().serialize(), ok(anything()));
+}
+
+#[googletest::test]
+fn serialize_missing_required_field() {
+    let options = SerializeOptions::new().check_required(true);
+    let mut msg = TestRequired::new();
+    msg.set_a(1);
+    let err = msg.serialize_with_options(&options).unwrap_err();
+    assert_that!(err.kind(), eq(SerializeErrorKind::MissingRequired));
+    assert_that!(err.missing_required_fields(), elements_are![eq("b"), eq("c")]);
+
+    msg.set_b(2);
+    msg.set_c(3);
+    assert_that!(msg.serialize_with_options(&options), ok(anything()));
+}
+
+#[googletest::test]
+fn serialize_missing_required_field_in_submessage() {
+    let mut msg = TestRequiredForeign::new();
+    msg.optional_message_mut().set_a(1);
+    msg.repeated_message_mut().push(TestRequired::new());
+
+    let options = SerializeOptions::new().check_required(true);
+    let err = msg.as_view().serialize_with_options(&options).unwrap_err();
+    assert_that!(err.kind(), eq(SerializeErrorKind::MissingRequired));
+    assert_that!(
+        err.missing_required_fields(),
+        elements_are![
+            eq("optional_message.b"),
+            eq("optional_message.c"),
+            eq("repeated_message[0].a"),
+            eq("repeated_message[0].b"),
+            eq("repeated_message[0].c"),
+        ]
+    );
+}
+
+#[googletest::test]
+fn serialize_missing_required_field_in_map_value() {
+    let mut value = TestRequired::new();
+    value.set_a(1);
+    let mut msg = TestRequiredMessageMap::new();
+    msg.map_field_mut().insert(7, value);
+
+    let options = SerializeOptions::new().check_required(true);
+    let err = msg.serialize_with_options(&options).unwrap_err();
+    assert_that!(err.kind(), eq(SerializeErrorKind::MissingRequired));
+    assert_that!(
+        err.missing_required_fields(),
+        elements_are![eq("map_field[7].b"), eq("map_field[7].c")]
+    );
+}
+
+#[googletest::test]
+fn serialize_missing_required_field_in_map_value_with_comma_in_key() {
+    let mut value = TestRequired::new();
+    value.set_a(1);
+    value.set_b(2);
+    let mut msg = TestRequiredMessageMap::new();
+    msg.map_string_field_mut().insert("a,b", value);
+
+    let options = SerializeOptions::new().check_required(true);
+    let err = msg.serialize_with_options(&options).unwrap_err();
+    assert_that!(err.missing_required_fields(), elements_are![eq(r#"map_string_field["a,b"].c"#)]);
+}
+
+#[googletest::test]
+fn parse_from_reader() {
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_int64(42);
+    msg.set_optional_bytes(b"parse from reader test");
+    let serialized = msg.serialize().unwrap();
+
+    let parsed = TestAllTypes::parse_from_reader(&serialized[..], serialized.len()).unwrap();
+    assert_that!(parsed.optional_int64(), eq(42));
+    assert_that!(parsed.optional_bytes(), eq(b"parse from reader test"));
+
+    let buffered = io::BufReader::new(&serialized[..]);
+    let parsed = TestAllTypes::parse_from_reader(buffered, usize::MAX).unwrap();
+    assert_that!(parsed.optional_int64(), eq(42));
+}
+
+#[googletest::test]
+fn parse_from_reader_enforces_max_size() {
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_bytes(b"too large");
+    let serialized = msg.serialize().unwrap();
+
+    let result = TestAllTypes::parse_from_reader(&serialized[..], serialized.len() - 1);
+    let Err(ReadError::TooLarge { max_size }) = result else { panic!("expected TooLarge") };
+    assert_that!(max_size, eq(serialized.len() - 1));
+}
+
+#[googletest::test]
+fn parse_from_reader_reports_errors_separately() {
+    struct FailingReader;
+    impl io::Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken"))
+        }
+    }
+
+    let result = TestAllTypes::parse_from_reader(FailingReader, usize::MAX);
+    assert!(matches!(result, Err(ReadError::Io(_))));
+
+    let result = TestAllTypes::parse_from_reader(&b"not a serialized proto"[..], usize::MAX);
+    let Err(ReadError::Parse(err)) = result else { panic!("expected a parse error") };
+    assert_that!(err.kind(), eq(ParseErrorKind::Malformed));
+}
+
+#[googletest::test]
+fn merge_from_reader() {
+    let mut first = TestAllTypes::new();
+    first.set_optional_int32(1);
+    first.repeated_int32_mut().push(1);
+    let mut second = TestAllTypes::new();
+    second.set_optional_int64(2);
+    second.repeated_int32_mut().push(2);
+    let serialized = second.serialize().unwrap();
+
+    first.merge_from_reader(&serialized[..], usize::MAX).unwrap();
+    assert_that!(first.optional_int32(), eq(1));
+    assert_that!(first.optional_int64(), eq(2));
+    assert_that!(first.repeated_int32(), elements_are![eq(1), eq(2)]);
+
+    let result = first.merge_from_reader(&serialized[..], 0);
+    assert!(matches!(result, Err(ReadError::TooLarge { .. })));
+    assert_that!(first.repeated_int32(), elements_are![eq(1), eq(2)]);
+}
+
+#[googletest::test]
+fn serialize_deterministic_sorts_map_entries() {
+    let mut msg = TestMap::new();
+    for key in [3, 1, 2] {
+        msg.map_int32_int32_mut().insert(key, key * 10);
+    }
+    let options = SerializeOptions::new().deterministic(true);
+    let serialized = msg.serialize_with_options(&options).unwrap();
+
+    // Each entry is tag 1 (length-delimited), length 4, then key and value.
+    let expected: Vec<u8> =
+        [1u8, 2, 3].iter().flat_map(|&k| [0x0a, 4, 0x08, k, 0x10, k * 10]).collect();
+    assert_that!(serialized, eq(expected));
+    assert_that!(msg.as_view().serialize_with_options(&options).unwrap(), eq(serialized));
+}
+
+#[googletest::test]
+fn serialize_with_default_options() {
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_int32(42);
+    let serialized = msg.serialize_with_options(&SerializeOptions::new()).unwrap();
+    assert_that!(serialized, eq(msg.serialize().unwrap()));
+}
+
+#[googletest::test]
+fn serialize_with_options_missing_required_field() {
+    let msg = TestRequired::new();
+    let options = SerializeOptions::new().deterministic(true).check_required(true);
+    let err = msg.serialize_with_options(&options).unwrap_err();
+    assert_that!(err.kind(), eq(SerializeErrorKind::MissingRequired));
+}
+
 macro_rules! generate_parameterized_int32_byte_size_test {
     ($(($type: ident, $name_ext: ident)),*) => {
         paste! { $(
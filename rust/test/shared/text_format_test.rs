@@ -0,0 +1,190 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use map_unittest_rust_proto::TestMap;
+use protobuf::prelude::*;
+use protobuf::reflect::{DynamicMessage, ReflectMessage};
+use protobuf::TextFormatPrintOptions;
+use unittest_proto3_rust_proto::{test_all_types, TestAllTypes};
+
+fn populated() -> TestAllTypes {
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_int32(5);
+    msg.set_optional_string("hi");
+    msg.set_optional_bytes(b"\x01\x02");
+    msg.set_optional_nested_enum(test_all_types::NestedEnum::Bar);
+    msg.optional_nested_message_mut().set_bb(3);
+    msg.repeated_int32_mut().extend([1, 2]);
+    msg
+}
+
+#[googletest::test]
+fn test_to_text_format() {
+    let text = populated().to_text_format();
+    assert_that!(
+        text,
+        eq("optional_int32: 5\n\
+            optional_string: \"hi\"\n\
+            optional_bytes: \"\\001\\002\"\n\
+            optional_nested_message {\n  bb: 3\n}\n\
+            optional_nested_enum: BAR\n\
+            repeated_int32: 1\n\
+            repeated_int32: 2\n")
+    );
+    assert_that!(populated().as_view().to_text_format(), eq(&text));
+    assert_that!(TestAllTypes::new().to_text_format(), eq(""));
+}
+
+#[googletest::test]
+fn test_single_line() {
+    let options = TextFormatPrintOptions::new().single_line(true);
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_int32(5);
+    msg.optional_nested_message_mut().set_bb(3);
+    assert_that!(
+        msg.to_text_format_with_options(&options),
+        eq("optional_int32: 5 optional_nested_message { bb: 3 }")
+    );
+}
+
+#[googletest::test]
+fn test_maps_are_sorted() {
+    let mut msg = TestMap::new();
+    for (k, v) in [("b", "2"), ("c", "3"), ("a", "1")] {
+        msg.map_string_string_mut().insert(k, v);
+    }
+    for k in [3, -1, 2] {
+        msg.map_int32_int32_mut().insert(k, k);
+    }
+    let options = TextFormatPrintOptions::new().single_line(true);
+    assert_that!(
+        msg.to_text_format_with_options(&options),
+        eq("map_int32_int32 { key: -1 value: -1 } \
+            map_int32_int32 { key: 2 value: 2 } \
+            map_int32_int32 { key: 3 value: 3 } \
+            map_string_string { key: \"a\" value: \"1\" } \
+            map_string_string { key: \"b\" value: \"2\" } \
+            map_string_string { key: \"c\" value: \"3\" }")
+    );
+}
+
+#[googletest::test]
+fn test_string_map_keys_are_sorted_bytewise() {
+    let mut msg = TestMap::new();
+    for k in ["b", "ab", "a"] {
+        msg.map_string_string_mut().insert(k, k);
+    }
+    let options = TextFormatPrintOptions::new().single_line(true);
+    assert_that!(
+        msg.to_text_format_with_options(&options),
+        eq("map_string_string { key: \"a\" value: \"a\" } \
+            map_string_string { key: \"ab\" value: \"ab\" } \
+            map_string_string { key: \"b\" value: \"b\" }")
+    );
+}
+
+#[googletest::test]
+fn test_escaping() {
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_string("\"é'\\\n");
+    msg.set_optional_bytes(b"\"'\\\xff");
+    let options = TextFormatPrintOptions::new().single_line(true);
+    assert_that!(
+        msg.to_text_format_with_options(&options),
+        eq(r#"optional_string: "\"é\'\\\n" optional_bytes: "\"\'\\\377""#)
+    );
+    let parsed = TestAllTypes::parse_text_format(&msg.to_text_format()).unwrap();
+    assert_that!(parsed.optional_string(), eq("\"é'\\\n"));
+    assert_that!(parsed.optional_bytes(), eq(b"\"'\\\xff"));
+}
+
+#[googletest::test]
+fn test_unknown_fields() {
+    let msg = TestAllTypes::parse(&[
+        0xc0, 0x3e, 0x05, // 1000: 5
+        0xcd, 0x3e, 0xef, 0xbe, 0xad, 0xde, // 1001: 0xdeadbeef
+        0xd1, 0x3e, 0x01, 0, 0, 0, 0, 0, 0, 0, // 1002: 1
+        0xda, 0x3e, 0x01, b'"', // 1003: "\""
+    ])
+    .unwrap();
+    assert_that!(msg.to_text_format(), eq(""));
+    let options = TextFormatPrintOptions::new().single_line(true).print_unknown_fields(true);
+    assert_that!(
+        msg.to_text_format_with_options(&options),
+        eq(r#"1000: 5 1001: 0xdeadbeef 1002: 0x0000000000000001 1003: "\"""#)
+    );
+}
+
+#[googletest::test]
+fn test_parse_text_format() {
+    let msg = TestAllTypes::parse_text_format(&populated().to_text_format()).unwrap();
+    assert_that!(msg.optional_int32(), eq(5));
+    assert_that!(msg.optional_string(), eq("hi"));
+    assert_that!(msg.optional_bytes(), eq(b"\x01\x02"));
+    assert_that!(msg.optional_nested_enum(), eq(test_all_types::NestedEnum::Bar));
+    assert_that!(msg.optional_nested_message().bb(), eq(3));
+    assert_that!(msg.repeated_int32().iter().collect::<Vec<_>>(), elements_are![eq(1), eq(2)]);
+
+    // Comments, list syntax and `:` before messages are all accepted.
+    let msg = TestAllTypes::parse_text_format(
+        "# A comment.\n repeated_int32: [3, 4] optional_nested_message: <bb: 6>",
+    )
+    .unwrap();
+    assert_that!(msg.repeated_int32().iter().collect::<Vec<_>>(), elements_are![eq(3), eq(4)]);
+    assert_that!(msg.optional_nested_message().bb(), eq(6));
+}
+
+#[googletest::test]
+fn test_merge_text_format() {
+    let mut msg = populated();
+    msg.merge_text_format("optional_int32: 8 repeated_int32: 3").unwrap();
+    assert_that!(msg.optional_int32(), eq(8));
+    assert_that!(msg.optional_string(), eq("hi"));
+    assert_that!(
+        msg.repeated_int32().iter().collect::<Vec<_>>(),
+        elements_are![eq(1), eq(2), eq(3)]
+    );
+
+    msg.as_mut().merge_text_format("optional_nested_message { bb: 4 }").unwrap();
+    assert_that!(msg.optional_nested_message().bb(), eq(4));
+}
+
+#[googletest::test]
+fn test_singular_field_set_twice() {
+    let text = "optional_int32: 1 optional_int32: 2";
+    assert_that!(TestAllTypes::parse_text_format(text), err(anything()));
+
+    let mut msg = TestAllTypes::new();
+    msg.merge_text_format(text).unwrap();
+    assert_that!(msg.optional_int32(), eq(2));
+}
+
+#[googletest::test]
+fn test_parse_errors() {
+    let error = TestAllTypes::parse_text_format("optional_int32: ").unwrap_err();
+    assert_that!(error.to_string(), starts_with("Couldn't parse text format: "));
+    assert_that!(error.message(), starts_with("1:"));
+
+    assert_that!(TestAllTypes::parse_text_format("no_such_field: 1"), err(anything()));
+    assert_that!(TestAllTypes::parse_text_format("optional_int32: \"x\""), err(anything()));
+    assert_that!(TestAllTypes::parse_text_format("optional_int32: 4294967296"), err(anything()));
+    assert_that!(TestAllTypes::parse_text_format("optional_nested_enum: NOPE"), err(anything()));
+}
+
+#[googletest::test]
+fn test_dynamic_message() {
+    let text = populated().to_text_format();
+    let mut dynamic = DynamicMessage::parse_text_format(TestAllTypes::descriptor(), &text).unwrap();
+    assert_that!(dynamic.to_text_format(), eq(&text));
+    assert_that!(dynamic.as_message_ref().to_text_format(), eq(&text));
+
+    dynamic.merge_text_format("optional_int32: 9").unwrap();
+    let typed = TestAllTypes::parse(&dynamic.serialize().unwrap()).unwrap();
+    assert_that!(typed.optional_int32(), eq(9));
+    assert_that!(typed.optional_string(), eq("hi"));
+}
@@ -0,0 +1,153 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use protobuf::prelude::*;
+use protobuf::{ExtensionRegistry, ParseOptions, UnknownValue, WireType};
+use unittest_rust_proto::{
+    TestAllExtensions, TestAllTypes, TestEmptyMessage, OPTIONAL_INT32_EXTENSION,
+};
+
+// Unknown to `TestAllTypes`, in wire order:
+// - field 1000: varint 1
+// - field 1001: bytes "abc"
+// - field 1002: fixed64 2
+// - field 1003: fixed32 3
+// - field 2000: group containing field 1: varint 4
+const UNKNOWN_FIELDS: &[u8] = &[
+    0xc0, 0x3e, 0x01, //
+    0xca, 0x3e, 0x03, b'a', b'b', b'c', //
+    0xd1, 0x3e, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0xdd, 0x3e, 0x03, 0x00, 0x00, 0x00, //
+    0x83, 0x7d, 0x08, 0x04, 0x84, 0x7d,
+];
+
+#[googletest::test]
+fn test_no_unknown_fields() {
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_int32(1);
+    assert_that!(msg.unknown_fields().is_empty(), eq(true));
+    assert_that!(msg.unknown_fields().iter().count(), eq(0));
+}
+
+#[googletest::test]
+fn test_unknown_fields_iteration() {
+    let msg = TestEmptyMessage::parse(UNKNOWN_FIELDS).unwrap();
+    let unknown = msg.unknown_fields();
+    assert_that!(unknown.is_empty(), eq(false));
+
+    let fields: Vec<_> = unknown.iter().collect();
+    assert_that!(
+        fields.iter().map(|&(number, wire_type, _)| (number, wire_type)).collect::<Vec<_>>(),
+        eq(vec![
+            (1000, WireType::Varint),
+            (1001, WireType::LengthDelimited),
+            (1002, WireType::Fixed64),
+            (1003, WireType::Fixed32),
+            (2000, WireType::StartGroup),
+        ])
+    );
+    assert!(matches!(fields[0].2, UnknownValue::Varint(1)));
+    assert!(matches!(fields[1].2, UnknownValue::LengthDelimited(b"abc")));
+    assert!(matches!(fields[2].2, UnknownValue::Fixed64(2)));
+    assert!(matches!(fields[3].2, UnknownValue::Fixed32(3)));
+
+    let UnknownValue::Group(group) = fields[4].2 else {
+        panic!("expected a group, got {:?}", fields[4].2);
+    };
+    let group_fields: Vec<_> = group.into_iter().collect();
+    assert_that!(group_fields.len(), eq(1));
+    assert_that!((group_fields[0].0, group_fields[0].1), eq((1, WireType::Varint)));
+    assert!(matches!(group_fields[0].2, UnknownValue::Varint(4)));
+}
+
+#[googletest::test]
+fn test_unknown_fields_round_trip() {
+    let msg = TestEmptyMessage::parse(UNKNOWN_FIELDS).unwrap();
+    assert_that!(msg.serialize().unwrap(), eq(UNKNOWN_FIELDS));
+}
+
+#[googletest::test]
+fn test_clear_unknown_fields() {
+    let mut msg = TestEmptyMessage::parse(UNKNOWN_FIELDS).unwrap();
+    msg.clear_unknown_fields();
+    assert_that!(msg.unknown_fields().is_empty(), eq(true));
+    assert_that!(msg.serialize().unwrap(), empty());
+}
+
+// `TestAllTypes` with `optional_int32 = 1`, and an `optional_nested_message`
+// and a `repeated_nested_message` that each have the unknown field 99.
+const NESTED_UNKNOWN_FIELDS: &[u8] = &[
+    0x08, 0x01, //
+    0x92, 0x01, 0x03, 0x98, 0x06, 0x05, //
+    0x82, 0x03, 0x03, 0x98, 0x06, 0x05, //
+    0xc0, 0x3e, 0x01,
+];
+
+#[googletest::test]
+fn test_clear_unknown_fields_is_shallow() {
+    let mut msg = TestAllTypes::parse(NESTED_UNKNOWN_FIELDS).unwrap();
+    assert_that!(msg.unknown_fields().iter().count(), eq(1));
+    assert_that!(msg.optional_nested_message().unknown_fields().iter().count(), eq(1));
+
+    msg.clear_unknown_fields();
+    assert_that!(msg.unknown_fields().is_empty(), eq(true));
+    assert_that!(msg.optional_nested_message().unknown_fields().is_empty(), eq(false));
+    assert_that!(
+        msg.repeated_nested_message().get(0).unwrap().unknown_fields().is_empty(),
+        eq(false)
+    );
+}
+
+#[googletest::test]
+fn test_discard_unknown_fields() {
+    let mut msg = TestAllTypes::parse(NESTED_UNKNOWN_FIELDS).unwrap();
+    msg.discard_unknown_fields();
+
+    assert_that!(msg.unknown_fields().is_empty(), eq(true));
+    assert_that!(msg.optional_nested_message().unknown_fields().is_empty(), eq(true));
+    assert_that!(
+        msg.repeated_nested_message().get(0).unwrap().unknown_fields().is_empty(),
+        eq(true)
+    );
+    assert_that!(msg.optional_int32(), eq(1));
+    assert_that!(msg.has_optional_nested_message(), eq(true));
+}
+
+#[googletest::test]
+fn test_discard_unknown_fields_in_extensions() {
+    // The fields of `NESTED_UNKNOWN_FIELDS` have the numbers of
+    // `optional_int32_extension`, `optional_nested_message_extension` and
+    // `repeated_nested_message_extension`. Message extensions have no
+    // accessors, so they are read back through the matching fields of
+    // `TestAllTypes`.
+    let mut registry = ExtensionRegistry::new();
+    registry.add(&OPTIONAL_INT32_EXTENSION);
+    let options = ParseOptions::new().extension_registry(&registry);
+    let mut msg = TestAllExtensions::parse_with_options(NESTED_UNKNOWN_FIELDS, &options).unwrap();
+    msg.discard_unknown_fields();
+
+    assert_that!(msg.unknown_fields().is_empty(), eq(true));
+    assert_that!(msg.get_extension(&OPTIONAL_INT32_EXTENSION), eq(1));
+
+    let reparsed = TestAllTypes::parse(&msg.serialize().unwrap()).unwrap();
+    assert_that!(reparsed.unknown_fields().is_empty(), eq(true));
+    assert_that!(reparsed.optional_nested_message().unknown_fields().is_empty(), eq(true));
+    for nested in reparsed.repeated_nested_message() {
+        assert_that!(nested.unknown_fields().is_empty(), eq(true));
+    }
+}
+
+#[googletest::test]
+fn test_unknown_fields_on_mut() {
+    let mut msg = TestAllTypes::parse(NESTED_UNKNOWN_FIELDS).unwrap();
+    let mut nested = msg.optional_nested_message_mut();
+    assert_that!(nested.unknown_fields().iter().count(), eq(1));
+    nested.discard_unknown_fields();
+    assert_that!(nested.unknown_fields().is_empty(), eq(true));
+    assert_that!(msg.unknown_fields().is_empty(), eq(false));
+}
@@ -19,7 +19,7 @@ use protobuf::prelude::*;
 use feature_verify_rust_proto::Verify;
 use no_features_proto2_rust_proto::NoFeaturesProto2;
 use no_features_proto3_rust_proto::NoFeaturesProto3;
-use protobuf::{ParseError, ProtoStr};
+use protobuf::{ParseErrorKind, ParseOptions, ProtoStr};
 
 // We use 0b1000_0000, since 0b1XXX_XXXX in UTF-8 denotes a byte 2-4, but never
 // the first byte.
@@ -54,6 +54,23 @@ fn test_proto2() {
     assert_that!(parsed_result, ok(anything()));
 }
 
+#[googletest::test]
+fn test_proto2_with_validate_utf8_option() {
+    let mut msg = NoFeaturesProto2::new();
+    msg.set_my_field(make_non_utf8_proto_str());
+    let serialized_nonutf8 = msg.serialize().expect("serialization should not fail");
+
+    // Error on parsing when validation is forced.
+    let options = ParseOptions::new().validate_utf8(true);
+    let parse_error = NoFeaturesProto2::parse_with_options(&serialized_nonutf8, &options)
+        .expect_err("parsing invalid UTF-8 should fail");
+    assert_that!(parse_error.kind(), eq(ParseErrorKind::BadUtf8));
+
+    msg.set_my_field("valid");
+    let serialized = msg.serialize().unwrap();
+    assert_that!(NoFeaturesProto2::parse_with_options(&serialized, &options), ok(anything()));
+}
+
 #[googletest::test]
 fn test_proto3() {
     let non_utf8_str = make_non_utf8_proto_str();
@@ -69,7 +86,8 @@ fn test_proto3() {
 
     // Error on parsing.
     let parsed_result = NoFeaturesProto3::parse(&serialized_nonutf8);
-    assert_that!(parsed_result, err(matches_pattern!(&ParseError)));
+    let parse_error = parsed_result.expect_err("parsing invalid UTF-8 should fail");
+    assert_that!(parse_error.kind(), eq(ParseErrorKind::BadUtf8));
 }
 
 #[googletest::test]
@@ -87,5 +105,6 @@ fn test_verify() {
 
     // Error on parsing.
     let parsed_result = Verify::parse(&serialized_nonutf8);
-    assert_that!(parsed_result, err(matches_pattern!(&ParseError)));
+    let parse_error = parsed_result.expect_err("parsing invalid UTF-8 should fail");
+    assert_that!(parse_error.kind(), eq(ParseErrorKind::BadUtf8));
 }
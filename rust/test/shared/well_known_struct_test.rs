@@ -0,0 +1,119 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use protobuf::StructValue;
+use std::collections::BTreeMap;
+use struct_rust_proto::{ListValue, Struct, Value};
+
+fn tree() -> StructValue {
+    StructValue::Object(BTreeMap::from([
+        ("enabled".to_string(), StructValue::Bool(true)),
+        ("ratio".to_string(), StructValue::Number(0.25)),
+        ("name".to_string(), StructValue::String("beta".to_string())),
+        ("none".to_string(), StructValue::Null),
+        (
+            "regions".to_string(),
+            StructValue::List(vec![
+                StructValue::String("eu".to_string()),
+                StructValue::Object(BTreeMap::new()),
+            ]),
+        ),
+    ]))
+}
+
+#[googletest::test]
+fn test_value_round_trip() {
+    let msg = Value::from(tree());
+    assert_that!(msg.struct_value().fields().len(), eq(5));
+    assert_that!(msg.struct_value().fields().get("ratio").unwrap().number_value(), eq(0.25));
+    assert_that!(StructValue::from(msg.as_view()), eq(&tree()));
+    assert_that!(StructValue::from(msg), eq(&tree()));
+}
+
+#[googletest::test]
+fn test_value_kinds() {
+    let mut msg = Value::new();
+    assert_that!(StructValue::from(msg.as_view()), eq(&StructValue::Null));
+    msg.set_bool_value(false);
+    assert_that!(StructValue::from(msg.as_view()), eq(&StructValue::Bool(false)));
+    msg.set_string_value("x");
+    assert_that!(StructValue::from(msg.as_view()), eq(&StructValue::String("x".to_string())));
+
+    let msg = Value::from(StructValue::Null);
+    assert_that!(msg.has_null_value(), eq(true));
+    let msg = Value::from(StructValue::Number(-1.5));
+    assert_that!(msg.number_value(), eq(-1.5));
+}
+
+#[googletest::test]
+fn test_struct() {
+    let StructValue::Object(fields) = tree() else { unreachable!() };
+    let msg = Struct::from(fields.clone());
+    assert_that!(msg.fields().len(), eq(5));
+    assert_that!(msg.fields().get("enabled").unwrap().bool_value(), eq(true));
+    assert_that!(BTreeMap::<String, StructValue>::from(msg.as_view()), eq(&fields));
+    assert_that!(BTreeMap::<String, StructValue>::from(Struct::new()), is_empty());
+}
+
+#[googletest::test]
+fn test_list_value() {
+    let values = vec![StructValue::Number(1.0), StructValue::List(vec![StructValue::Null])];
+    let msg = ListValue::from(values.clone());
+    assert_that!(msg.values().len(), eq(2));
+    assert_that!(Vec::<StructValue>::from(msg.as_view()), eq(&values));
+    assert_that!(Vec::<StructValue>::from(msg), eq(&values));
+}
+
+// The conversions to and from serde_json are only built with
+// `--//rust:serde_json`.
+
+#[cfg(feature = "serde_json")]
+#[googletest::test]
+fn test_serde_json_round_trip() {
+    let json = serde_json::json!({
+        "enabled": true,
+        "ratio": 0.25,
+        "none": null,
+        "regions": ["eu", {"zones": [1.0, null, []]}, null],
+        "nested": {"empty": {}, "lists": [[true], []]},
+    });
+    let msg = Value::from(json.clone());
+    assert_that!(StructValue::from(msg.as_view()), eq(&StructValue::from(json.clone())));
+    assert_that!(msg.struct_value().fields().get("none").unwrap().has_null_value(), eq(true));
+    assert_that!(serde_json::Value::from(msg.as_view()), eq(&json));
+    assert_that!(serde_json::Value::from(msg), eq(&json));
+
+    let fields = json.as_object().unwrap().clone();
+    let msg = Struct::from(fields.clone());
+    assert_that!(msg.fields().len(), eq(5));
+    assert_that!(serde_json::Map::from(msg.as_view()), eq(&fields));
+    assert_that!(serde_json::Map::from(msg), eq(&fields));
+
+    let values = json["regions"].as_array().unwrap().clone();
+    let msg = ListValue::from(values.clone());
+    assert_that!(msg.values().len(), eq(3));
+    assert_that!(Vec::<serde_json::Value>::from(msg.as_view()), eq(&values));
+    assert_that!(Vec::<serde_json::Value>::from(msg), eq(&values));
+}
+
+#[cfg(feature = "serde_json")]
+#[googletest::test]
+fn test_serde_json_non_finite_numbers() {
+    // JSON has no NaN or infinity, so these numbers become null.
+    for number in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        let mut msg = Value::new();
+        msg.set_number_value(number);
+        assert_that!(serde_json::Value::from(msg.as_view()), eq(&serde_json::Value::Null));
+
+        let msg = ListValue::from(vec![StructValue::Number(number), StructValue::Number(1.5)]);
+        assert_that!(
+            Vec::<serde_json::Value>::from(msg),
+            eq(&vec![serde_json::Value::Null, serde_json::json!(1.5)])
+        );
+    }
+}
@@ -0,0 +1,257 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use duration_rust_proto::Duration;
+use googletest::prelude::*;
+use protobuf::TimeError;
+use std::time::{SystemTime, UNIX_EPOCH};
+use timestamp_rust_proto::Timestamp;
+
+fn timestamp(seconds: i64, nanos: i32) -> Timestamp {
+    let mut msg = Timestamp::new();
+    msg.set_seconds(seconds);
+    msg.set_nanos(nanos);
+    msg
+}
+
+fn duration(seconds: i64, nanos: i32) -> Duration {
+    let mut msg = Duration::new();
+    msg.set_seconds(seconds);
+    msg.set_nanos(nanos);
+    msg
+}
+
+#[googletest::test]
+fn test_timestamp_to_system_time() {
+    let time = SystemTime::try_from(timestamp(5, 7)).unwrap();
+    assert_that!(time, eq(UNIX_EPOCH + std::time::Duration::new(5, 7)));
+
+    // Before the epoch, `nanos` still counts forward in time.
+    let time = SystemTime::try_from(timestamp(-2, 250_000_000).as_view()).unwrap();
+    assert_that!(UNIX_EPOCH.duration_since(time), ok(eq(std::time::Duration::from_millis(1_750))));
+
+    let msg = Timestamp::try_from(time).unwrap();
+    assert_that!(msg.seconds(), eq(-2));
+    assert_that!(msg.nanos(), eq(250_000_000));
+}
+
+#[googletest::test]
+fn test_timestamp_validation() {
+    assert_that!(SystemTime::try_from(timestamp(0, -1)), err(eq(TimeError::InvalidNanos)));
+    assert_that!(
+        SystemTime::try_from(timestamp(0, 1_000_000_000)),
+        err(eq(TimeError::InvalidNanos))
+    );
+    // Years before 1 and after 9999 are out of range.
+    assert_that!(
+        SystemTime::try_from(timestamp(-62_135_596_801, 0)),
+        err(eq(TimeError::OutOfRange))
+    );
+    assert_that!(
+        SystemTime::try_from(timestamp(253_402_300_800, 0)),
+        err(eq(TimeError::OutOfRange))
+    );
+    let far_future = UNIX_EPOCH + std::time::Duration::from_secs(253_402_300_800);
+    assert_that!(Timestamp::try_from(far_future), err(eq(TimeError::OutOfRange)));
+}
+
+#[googletest::test]
+fn test_rfc3339() {
+    let msg = timestamp(63_108_020, 21_000_000);
+    assert_that!(msg.to_rfc3339(), ok(eq("1972-01-01T10:00:20.021Z")));
+    assert_that!(msg.as_view().to_rfc3339(), ok(eq("1972-01-01T10:00:20.021Z")));
+    assert_that!(timestamp(0, 0).to_rfc3339(), ok(eq("1970-01-01T00:00:00Z")));
+    assert_that!(timestamp(-1, 1_000).to_rfc3339(), ok(eq("1969-12-31T23:59:59.000001Z")));
+    assert_that!(timestamp(0, -1).to_rfc3339(), err(eq(TimeError::InvalidNanos)));
+
+    let parsed = Timestamp::from_rfc3339("1972-01-01T05:00:20.021-05:00").unwrap();
+    assert_that!(parsed.seconds(), eq(63_108_020));
+    assert_that!(parsed.nanos(), eq(21_000_000));
+    assert_that!(Timestamp::from_rfc3339("1972-01-01"), err(eq(TimeError::InvalidRfc3339)));
+    assert_that!(
+        Timestamp::from_rfc3339("1972-01-01T00:00:60Z"),
+        err(eq(TimeError::InvalidRfc3339))
+    );
+}
+
+#[googletest::test]
+fn test_duration_to_std() {
+    let std_duration = std::time::Duration::try_from(duration(3, 4)).unwrap();
+    assert_that!(std_duration, eq(std::time::Duration::new(3, 4)));
+
+    let msg = Duration::try_from(std::time::Duration::new(3, 4)).unwrap();
+    assert_that!(msg.seconds(), eq(3));
+    assert_that!(msg.nanos(), eq(4));
+
+    // `std::time::Duration` can't be negative.
+    assert_that!(
+        std::time::Duration::try_from(duration(-1, -5).as_view()),
+        err(eq(TimeError::OutOfRange))
+    );
+}
+
+#[googletest::test]
+fn test_duration_validation() {
+    assert_that!(std::time::Duration::try_from(duration(1, -5)), err(eq(TimeError::InvalidNanos)));
+    assert_that!(
+        std::time::Duration::try_from(duration(0, 1_000_000_000)),
+        err(eq(TimeError::InvalidNanos))
+    );
+    assert_that!(
+        std::time::Duration::try_from(duration(315_576_000_001, 0)),
+        err(eq(TimeError::OutOfRange))
+    );
+    assert_that!(
+        Duration::try_from(std::time::Duration::from_secs(315_576_000_001)),
+        err(eq(TimeError::OutOfRange))
+    );
+}
+
+// The conversions to and from chrono and time are only built with
+// `--//rust:chrono_and_time`.
+
+/// The earliest and the latest valid timestamps, 0001-01-01T00:00:00Z and
+/// 9999-12-31T23:59:59.999999999Z.
+#[cfg(any(feature = "chrono", feature = "time"))]
+const TIMESTAMP_MIN: (i64, i32) = (-62_135_596_800, 0);
+#[cfg(any(feature = "chrono", feature = "time"))]
+const TIMESTAMP_MAX: (i64, i32) = (253_402_300_799, 999_999_999);
+
+/// The longest valid durations, of 10,000 years.
+#[cfg(any(feature = "chrono", feature = "time"))]
+const DURATION_MIN: (i64, i32) = (-315_576_000_000, -999_999_999);
+#[cfg(any(feature = "chrono", feature = "time"))]
+const DURATION_MAX: (i64, i32) = (315_576_000_000, 999_999_999);
+
+#[cfg(feature = "chrono")]
+#[googletest::test]
+fn test_timestamp_chrono_round_trip() {
+    use chrono::{DateTime, Utc};
+
+    // Before the epoch, `nanos` still counts forward in time.
+    let time = DateTime::<Utc>::try_from(timestamp(-2, 250_000_000)).unwrap();
+    assert_that!(time, eq(DateTime::from_timestamp_millis(-1_750).unwrap()));
+
+    for (seconds, nanos) in [(-2, 250_000_000), TIMESTAMP_MIN, TIMESTAMP_MAX] {
+        let time = DateTime::<Utc>::try_from(timestamp(seconds, nanos).as_view()).unwrap();
+        let msg = Timestamp::try_from(time).unwrap();
+        assert_that!((msg.seconds(), msg.nanos()), eq((seconds, nanos)));
+    }
+}
+
+#[cfg(feature = "chrono")]
+#[googletest::test]
+fn test_timestamp_chrono_validation() {
+    use chrono::{DateTime, Utc};
+
+    assert_that!(
+        DateTime::<Utc>::try_from(timestamp(TIMESTAMP_MIN.0 - 1, 0)),
+        err(eq(TimeError::OutOfRange))
+    );
+    assert_that!(
+        DateTime::<Utc>::try_from(timestamp(TIMESTAMP_MAX.0 + 1, 0)),
+        err(eq(TimeError::OutOfRange))
+    );
+    assert_that!(DateTime::<Utc>::try_from(timestamp(0, -1)), err(eq(TimeError::InvalidNanos)));
+
+    // chrono can represent times before year 1 and after year 9999.
+    let too_early = DateTime::from_timestamp(TIMESTAMP_MIN.0 - 1, 0).unwrap();
+    assert_that!(Timestamp::try_from(too_early), err(eq(TimeError::OutOfRange)));
+    let too_late = DateTime::from_timestamp(TIMESTAMP_MAX.0 + 1, 0).unwrap();
+    assert_that!(Timestamp::try_from(too_late), err(eq(TimeError::OutOfRange)));
+}
+
+#[cfg(feature = "chrono")]
+#[googletest::test]
+fn test_duration_chrono_round_trip() {
+    use chrono::TimeDelta;
+
+    // -1.5s, which chrono stores as -2s plus 0.5s.
+    let delta = TimeDelta::try_from(duration(-1, -500_000_000)).unwrap();
+    assert_that!(delta, eq(TimeDelta::new(-2, 500_000_000).unwrap()));
+
+    for (seconds, nanos) in [(-1, -500_000_000), (1, 500_000_000), DURATION_MIN, DURATION_MAX] {
+        let delta = TimeDelta::try_from(duration(seconds, nanos).as_view()).unwrap();
+        let msg = Duration::try_from(delta).unwrap();
+        assert_that!((msg.seconds(), msg.nanos()), eq((seconds, nanos)));
+    }
+}
+
+#[cfg(feature = "chrono")]
+#[googletest::test]
+fn test_duration_chrono_validation() {
+    use chrono::TimeDelta;
+
+    assert_that!(TimeDelta::try_from(duration(-1, 5)), err(eq(TimeError::InvalidNanos)));
+    assert_that!(
+        TimeDelta::try_from(duration(DURATION_MAX.0 + 1, 0)),
+        err(eq(TimeError::OutOfRange))
+    );
+    let too_long = TimeDelta::new(DURATION_MAX.0 + 1, 0).unwrap();
+    assert_that!(Duration::try_from(too_long), err(eq(TimeError::OutOfRange)));
+    assert_that!(Duration::try_from(-too_long), err(eq(TimeError::OutOfRange)));
+}
+
+#[cfg(feature = "time")]
+#[googletest::test]
+fn test_timestamp_time_round_trip() {
+    use time::OffsetDateTime;
+
+    // Before the epoch, `nanos` still counts forward in time.
+    let time = OffsetDateTime::try_from(timestamp(-2, 250_000_000)).unwrap();
+    assert_that!(time.unix_timestamp_nanos(), eq(-1_750_000_000));
+
+    for (seconds, nanos) in [(-2, 250_000_000), TIMESTAMP_MIN, TIMESTAMP_MAX] {
+        let time = OffsetDateTime::try_from(timestamp(seconds, nanos).as_view()).unwrap();
+        let msg = Timestamp::try_from(time).unwrap();
+        assert_that!((msg.seconds(), msg.nanos()), eq((seconds, nanos)));
+    }
+}
+
+#[cfg(feature = "time")]
+#[googletest::test]
+fn test_timestamp_time_validation() {
+    use time::OffsetDateTime;
+
+    assert_that!(
+        OffsetDateTime::try_from(timestamp(TIMESTAMP_MIN.0 - 1, 0)),
+        err(eq(TimeError::OutOfRange))
+    );
+    assert_that!(
+        OffsetDateTime::try_from(timestamp(TIMESTAMP_MAX.0 + 1, 0)),
+        err(eq(TimeError::OutOfRange))
+    );
+    assert_that!(OffsetDateTime::try_from(timestamp(0, -1)), err(eq(TimeError::InvalidNanos)));
+
+    // time can represent times before year 1.
+    let too_early = OffsetDateTime::from_unix_timestamp(TIMESTAMP_MIN.0 - 1).unwrap();
+    assert_that!(Timestamp::try_from(too_early), err(eq(TimeError::OutOfRange)));
+}
+
+#[cfg(feature = "time")]
+#[googletest::test]
+fn test_duration_time_round_trip() {
+    for (seconds, nanos) in [(-1, -500_000_000), (1, 500_000_000), DURATION_MIN, DURATION_MAX] {
+        let time_duration = time::Duration::try_from(duration(seconds, nanos).as_view()).unwrap();
+        assert_that!(time_duration, eq(time::Duration::new(seconds, nanos)));
+        let msg = Duration::try_from(time_duration).unwrap();
+        assert_that!((msg.seconds(), msg.nanos()), eq((seconds, nanos)));
+    }
+}
+
+#[cfg(feature = "time")]
+#[googletest::test]
+fn test_duration_time_validation() {
+    assert_that!(time::Duration::try_from(duration(-1, 5)), err(eq(TimeError::InvalidNanos)));
+    assert_that!(
+        time::Duration::try_from(duration(DURATION_MAX.0 + 1, 0)),
+        err(eq(TimeError::OutOfRange))
+    );
+    let too_long = time::Duration::seconds(DURATION_MAX.0 + 1);
+    assert_that!(Duration::try_from(too_long), err(eq(TimeError::OutOfRange)));
+    assert_that!(Duration::try_from(-too_long), err(eq(TimeError::OutOfRange)));
+}
@@ -0,0 +1,28 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use protobuf_upb::prelude::*;
+use unittest_rust_proto::TestAllTypes;
+
+#[googletest::test]
+fn parse_borrowed_aliases_input() {
+    let payload = vec![0xab; 1024];
+    let mut msg = TestAllTypes::new();
+    msg.set_optional_bytes(&payload[..]);
+    let serialized = msg.serialize().unwrap();
+
+    let parsed = TestAllTypes::parse_borrowed(&serialized).unwrap();
+    let bytes = parsed.as_view().optional_bytes();
+    assert_that!(bytes, eq(&payload[..]));
+    assert_that!(serialized.as_ptr_range().contains(&bytes.as_ptr()), eq(true));
+
+    let owned = parsed.to_owned();
+    let bytes = owned.optional_bytes();
+    assert_that!(bytes, eq(&payload[..]));
+    assert_that!(serialized.as_ptr_range().contains(&bytes.as_ptr()), eq(false));
+}
@@ -0,0 +1,20 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use protobuf_upb::prelude::*;
+use protobuf_upb::ParseErrorKind;
+use unittest_rust_proto::TestAllTypes;
+
+#[googletest::test]
+fn parse_error_has_no_offset() {
+    // The upb decoder doesn't report where it failed.
+    let err = TestAllTypes::parse(&[0x08, 0x01, 0x08, 0x80]).unwrap_err();
+    assert_that!(err.kind(), eq(ParseErrorKind::Malformed));
+    assert_that!(err.offset(), none());
+    assert_that!(err.field_path(), none());
+}
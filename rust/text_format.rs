@@ -0,0 +1,164 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Conversion of messages to and from the text format, the human-readable
+//! format of `.textproto` files.
+//!
+//! See <https://protobuf.dev/reference/protobuf/textformat-spec/> for the
+//! grammar. Unlike the `Debug` output of messages, the output of
+//! [`ToTextFormat`] is stable: fields are printed in field number order, map
+//! entries are sorted by key, and the output can be parsed back by
+//! [`FromTextFormat`].
+
+use crate::__internal::runtime::{text_format_decode, text_format_encode};
+use crate::__internal::Private;
+use crate::reflect::{
+    DynamicMessage, MessageDescriptor, MessageRef, MessageRefMut, ReflectMessage, ReflectMessageMut,
+};
+use crate::TextFormatPrintOptions;
+use std::fmt;
+
+/// An error that happened while parsing the text format.
+///
+/// The message that describes the problem comes from the kernel, so its
+/// wording differs between kernels and should not be matched on. It starts
+/// with the line and column of the problem when there is one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextFormatError {
+    message: String,
+}
+
+impl TextFormatError {
+    /// Returns a description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::error::Error for TextFormatError {}
+
+impl fmt::Display for TextFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Couldn't parse text format: {}", self.message)
+    }
+}
+
+/// A message that can be printed in the text format. All generated messages,
+/// views and muts implement this trait, as do [`DynamicMessage`] and the
+/// type-erased [`MessageRef`] and [`MessageRefMut`].
+pub trait ToTextFormat {
+    /// Prints this message in the text format with the default options, one
+    /// field per line.
+    fn to_text_format(&self) -> String {
+        self.to_text_format_with_options(&TextFormatPrintOptions::new())
+    }
+
+    /// Prints this message in the text format, as controlled by `options`.
+    fn to_text_format_with_options(&self, options: &TextFormatPrintOptions) -> String;
+}
+
+impl ToTextFormat for MessageRef<'_> {
+    fn to_text_format_with_options(&self, options: &TextFormatPrintOptions) -> String {
+        // SAFETY: `self` is a message of the type `self.descriptor()`.
+        let mut text =
+            unsafe { text_format_encode(self.raw(Private), &self.descriptor(), options) };
+        if options.is_single_line() {
+            // Both kernels end every field with a space in single line mode.
+            text.truncate(text.trim_end().len());
+        }
+        text
+    }
+}
+
+impl ToTextFormat for MessageRefMut<'_> {
+    fn to_text_format_with_options(&self, options: &TextFormatPrintOptions) -> String {
+        self.as_message_ref().to_text_format_with_options(options)
+    }
+}
+
+impl ToTextFormat for DynamicMessage {
+    fn to_text_format_with_options(&self, options: &TextFormatPrintOptions) -> String {
+        self.as_message_ref().to_text_format_with_options(options)
+    }
+}
+
+impl<T: ReflectMessage> ToTextFormat for T {
+    fn to_text_format_with_options(&self, options: &TextFormatPrintOptions) -> String {
+        self.as_message_ref().to_text_format_with_options(options)
+    }
+}
+
+/// A message that can be parsed from the text format. All generated messages
+/// implement this trait; [`DynamicMessage`] has an inherent version of its
+/// method that takes the type to parse.
+pub trait FromTextFormat: Sized {
+    /// Parses a message from the text format.
+    ///
+    /// As in a `.textproto` file, each singular field may be set at most
+    /// once, and so may each oneof. Parsing fails if a required field is left
+    /// unset.
+    fn parse_text_format(text: &str) -> Result<Self, TextFormatError>;
+}
+
+impl<T: ReflectMessageMut + Default> FromTextFormat for T {
+    fn parse_text_format(text: &str) -> Result<Self, TextFormatError> {
+        let mut msg = T::default();
+        decode(msg.as_message_mut(), text, false).map(|()| msg)
+    }
+}
+
+/// A message that the text format can be merged into. All generated messages
+/// and muts implement this trait, as do [`DynamicMessage`] and
+/// [`MessageRefMut`].
+pub trait MergeTextFormat {
+    /// Merges the message in `text` into this one, like
+    /// [`MergeFrom`](crate::MergeFrom) would: singular fields that are set in
+    /// `text` replace those of this message, and repeated fields are
+    /// appended to.
+    ///
+    /// Unlike [`FromTextFormat::parse_text_format`], `text` may set a
+    /// singular field more than once, in which case the last value wins.
+    /// Merging fails if a required field is unset afterwards, and may leave
+    /// this message partially merged when it fails.
+    fn merge_text_format(&mut self, text: &str) -> Result<(), TextFormatError>;
+}
+
+impl MergeTextFormat for MessageRefMut<'_> {
+    fn merge_text_format(&mut self, text: &str) -> Result<(), TextFormatError> {
+        decode(self.as_message_mut(), text, true)
+    }
+}
+
+impl MergeTextFormat for DynamicMessage {
+    fn merge_text_format(&mut self, text: &str) -> Result<(), TextFormatError> {
+        decode(self.as_message_mut(), text, true)
+    }
+}
+
+impl<T: ReflectMessageMut> MergeTextFormat for T {
+    fn merge_text_format(&mut self, text: &str) -> Result<(), TextFormatError> {
+        decode(self.as_message_mut(), text, true)
+    }
+}
+
+impl DynamicMessage {
+    /// Parses a message of the type `descriptor` from the text format. See
+    /// [`FromTextFormat::parse_text_format`].
+    pub fn parse_text_format(
+        descriptor: MessageDescriptor,
+        text: &str,
+    ) -> Result<Self, TextFormatError> {
+        let mut msg = DynamicMessage::new(descriptor);
+        decode(msg.as_message_mut(), text, false).map(|()| msg)
+    }
+}
+
+/// Parses `text` into `msg`. Unless `merge` is set, `msg` must be empty and
+/// singular fields may only be set once.
+fn decode(msg: MessageRefMut<'_>, text: &str, merge: bool) -> Result<(), TextFormatError> {
+    text_format_decode(msg, text, merge).map_err(|message| TextFormatError { message })
+}
@@ -0,0 +1,912 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! A text format parser for the upb kernel, which has none of its own.
+//!
+//! It is written against the reflection API and follows the behavior of
+//! `google::protobuf::TextFormat::Parser`, including its error messages, so
+//! that both kernels accept the same input. The grammar is described at
+//! <https://protobuf.dev/reference/protobuf/textformat-spec/>.
+
+use crate::__internal::runtime::{
+    find_extension_by_name, find_message_by_name, merge_with_extensions, UnsetRequiredFields,
+};
+use crate::__internal::Private;
+use crate::reflect::{
+    DynamicMessage, FieldDescriptor, FieldType, MessageDescriptor, MessageRefMut, ReflectValue,
+};
+use crate::{ProtoBytes, ProtoString, Serialize};
+
+/// How deeply messages may be nested, which matches the default limit of
+/// the wire format parser.
+const RECURSION_LIMIT: usize = 100;
+
+/// The type URL prefixes of the `Any`s that can be expanded.
+const ANY_TYPE_URL_PREFIXES: [&str; 2] = ["type.googleapis.com/", "type.googleprod.com/"];
+
+/// Parses `text` into `msg`. Unless `merge` is set, singular fields may only
+/// be set once.
+pub fn parse(mut msg: MessageRefMut<'_>, text: &str, merge: bool) -> Result<(), String> {
+    let mut parser = Parser { tokenizer: Tokenizer::new(text).map_err(Error::into_string)?, merge };
+    parser.parse_fields(&mut msg, false, 0).map_err(Error::into_string)?;
+
+    let mut missing = Vec::new();
+    msg.as_message_ref().unset_required_fields("", &mut missing);
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Message missing required fields: {}", missing.join(", ")))
+    }
+}
+
+/// A parse error at a 0-based position in the input.
+struct Error {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl Error {
+    /// Formats the error like `TextFormat::Parser` does, with a 1-based
+    /// position.
+    fn into_string(self) -> String {
+        format!("{}:{}: {}", self.line + 1, self.column + 1, self.message)
+    }
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TokenKind {
+    Identifier,
+    Integer,
+    Float,
+    /// A quoted string, whose unescaped value is in `Token::bytes`.
+    String,
+    /// Any other single character.
+    Symbol,
+    End,
+}
+
+#[derive(Debug)]
+struct Token<'a> {
+    kind: TokenKind,
+    /// The text of the token as it appears in the input.
+    text: &'a str,
+    bytes: Vec<u8>,
+    line: usize,
+    column: usize,
+}
+
+/// Splits the input into tokens like `io::Tokenizer` does for the text
+/// format: `#` starts a comment, and floats may end with `f`.
+struct Tokenizer<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+    column: usize,
+    current: Token<'a>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(input: &'a str) -> Result<Self> {
+        let current =
+            Token { kind: TokenKind::End, text: "", bytes: Vec::new(), line: 0, column: 0 };
+        let mut tokenizer = Tokenizer { input, pos: 0, line: 0, column: 0, current };
+        tokenizer.next()?;
+        Ok(tokenizer)
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn peek_byte(&self) -> u8 {
+        self.input.as_bytes().get(self.pos).copied().unwrap_or(0)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek_char()?;
+        self.pos += c.len_utf8();
+        match c {
+            '\n' => {
+                self.line += 1;
+                self.column = 0;
+            }
+            // Tabs advance to the next multiple of 8, as in `io::Tokenizer`.
+            '\t' => self.column += 8 - self.column % 8,
+            _ => self.column += 1,
+        }
+        Some(c)
+    }
+
+    fn bump_while(&mut self, f: impl Fn(u8) -> bool) -> usize {
+        let mut n = 0;
+        while self.pos < self.input.len() && f(self.peek_byte()) {
+            self.bump();
+            n += 1;
+        }
+        n
+    }
+
+    fn error(&self, message: impl Into<String>) -> Error {
+        Error { line: self.line, column: self.column, message: message.into() }
+    }
+
+    /// Advances to the next token.
+    fn next(&mut self) -> Result<()> {
+        loop {
+            self.bump_while(|b| matches!(b, b' ' | b'\t' | b'\n' | b'\r' | b'\x0b' | b'\x0c'));
+            if self.peek_byte() != b'#' {
+                break;
+            }
+            self.bump_while(|b| b != b'\n');
+        }
+
+        let (start, line, column) = (self.pos, self.line, self.column);
+        let mut bytes = Vec::new();
+        let kind = match self.peek_byte() {
+            _ if self.pos == self.input.len() => TokenKind::End,
+            b if b.is_ascii_alphabetic() || b == b'_' => {
+                self.bump_while(|b| b.is_ascii_alphanumeric() || b == b'_');
+                TokenKind::Identifier
+            }
+            b if b.is_ascii_digit() => self.number(false)?,
+            b'.' if self.input.as_bytes().get(self.pos + 1).is_some_and(u8::is_ascii_digit) => {
+                self.bump();
+                self.number(true)?
+            }
+            quote @ (b'"' | b'\'') => {
+                self.bump();
+                self.string(quote, &mut bytes)?;
+                TokenKind::String
+            }
+            _ => {
+                self.bump();
+                TokenKind::Symbol
+            }
+        };
+        self.current = Token { kind, text: &self.input[start..self.pos], bytes, line, column };
+        Ok(())
+    }
+
+    /// Consumes the rest of a number, whose first character (a digit, or a
+    /// `.` followed by a digit) has been peeked or consumed.
+    fn number(&mut self, started_with_dot: bool) -> Result<TokenKind> {
+        let mut is_float = started_with_dot;
+        let started_with_zero = !started_with_dot && self.peek_byte() == b'0';
+        if started_with_dot {
+            self.bump_while(|b| b.is_ascii_digit());
+        } else {
+            self.bump();
+        }
+
+        if started_with_zero && matches!(self.peek_byte(), b'x' | b'X') {
+            self.bump();
+            if self.bump_while(|b| b.is_ascii_hexdigit()) == 0 {
+                return Err(self.error("\"0x\" must be followed by hex digits."));
+            }
+        } else if started_with_zero && self.peek_byte().is_ascii_digit() {
+            self.bump_while(|b| (b'0'..=b'7').contains(&b));
+            if self.peek_byte().is_ascii_digit() {
+                return Err(self.error("Numbers starting with leading zero must be in octal."));
+            }
+        } else {
+            if !started_with_dot {
+                self.bump_while(|b| b.is_ascii_digit());
+                if self.peek_byte() == b'.' {
+                    self.bump();
+                    is_float = true;
+                    self.bump_while(|b| b.is_ascii_digit());
+                }
+            }
+            if matches!(self.peek_byte(), b'e' | b'E') {
+                self.bump();
+                is_float = true;
+                if matches!(self.peek_byte(), b'-' | b'+') {
+                    self.bump();
+                }
+                if self.bump_while(|b| b.is_ascii_digit()) == 0 {
+                    return Err(self.error("\"e\" must be followed by exponent."));
+                }
+            }
+            if matches!(self.peek_byte(), b'f' | b'F') {
+                self.bump();
+                is_float = true;
+            }
+        }
+
+        let next = self.peek_byte();
+        if self.pos < self.input.len() && (next.is_ascii_alphabetic() || next == b'_') {
+            return Err(self.error("Need space between number and identifier."));
+        }
+        if next == b'.' {
+            return Err(self.error(if is_float {
+                "Already saw decimal point or exponent; can't have another one."
+            } else {
+                "Hex and octal numbers must be integers."
+            }));
+        }
+        Ok(if is_float { TokenKind::Float } else { TokenKind::Integer })
+    }
+
+    /// Consumes the rest of a string literal whose opening quote has been
+    /// consumed, appending its unescaped value to `out`.
+    fn string(&mut self, quote: u8, out: &mut Vec<u8>) -> Result<()> {
+        loop {
+            let Some(c) = self.peek_char() else {
+                return Err(self.error("Unexpected end of string."));
+            };
+            match c {
+                '\n' => return Err(self.error("String literals cannot cross line boundaries.")),
+                '\\' => {
+                    self.bump();
+                    self.escape(out)?;
+                }
+                _ if c as u32 == u32::from(quote) => {
+                    self.bump();
+                    return Ok(());
+                }
+                _ => {
+                    self.bump();
+                    out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+            }
+        }
+    }
+
+    /// Consumes an escape sequence whose backslash has been consumed.
+    fn escape(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        let b = self.peek_byte();
+        let simple = match b {
+            b'a' => Some(b'\x07'),
+            b'b' => Some(b'\x08'),
+            b'f' => Some(b'\x0c'),
+            b'n' => Some(b'\n'),
+            b'r' => Some(b'\r'),
+            b't' => Some(b'\t'),
+            b'v' => Some(b'\x0b'),
+            b'\\' | b'?' | b'\'' | b'"' => Some(b),
+            _ => None,
+        };
+        if let Some(byte) = simple {
+            self.bump();
+            out.push(byte);
+            return Ok(());
+        }
+        match b {
+            b'0'..=b'7' => {
+                // Up to three octal digits, truncated to a byte like in C++.
+                let value = self.digits(3, 8);
+                out.push(value as u8);
+            }
+            b'x' | b'X' => {
+                self.bump();
+                if !self.peek_byte().is_ascii_hexdigit() {
+                    return Err(self.error("Expected hex digits for escape sequence."));
+                }
+                out.push(self.digits(2, 16) as u8);
+            }
+            b'u' | b'U' => {
+                self.bump();
+                let len = if b == b'u' { 4 } else { 8 };
+                let start = self.pos;
+                let code_point = self.digits(len, 16);
+                if self.pos - start != len {
+                    return Err(self.error(if b == b'u' {
+                        "Expected four hex digits for \\u escape sequence."
+                    } else {
+                        "Expected eight hex digits up to 10ffff for \\U escape sequence."
+                    }));
+                }
+                // Surrogates and code points beyond the planes are rejected
+                // rather than combined or truncated.
+                let Some(c) = char::from_u32(code_point) else {
+                    return Err(self.error(format!(
+                        "Escape sequence is not a valid Unicode code point: {code_point:#x}."
+                    )));
+                };
+                out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+            _ => return Err(self.error("Invalid escape sequence in string literal.")),
+        }
+        Ok(())
+    }
+
+    /// Consumes up to `max_len` digits in `radix`, returning their value.
+    fn digits(&mut self, max_len: usize, radix: u32) -> u32 {
+        let mut value = 0;
+        for _ in 0..max_len {
+            let Some(digit) = (self.peek_byte() as char).to_digit(radix) else {
+                break;
+            };
+            self.bump();
+            value = value * radix + digit;
+        }
+        value
+    }
+}
+
+/// Parses an integer token, which may be hex or octal, that is at most `max`.
+fn parse_integer(text: &str, max: u64) -> Option<u64> {
+    let (digits, radix) = if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        (hex, 16)
+    } else if text.len() > 1 && text.starts_with('0') {
+        (&text[1..], 8)
+    } else {
+        (text, 10)
+    };
+    u64::from_str_radix(digits, radix).ok().filter(|&v| v <= max)
+}
+
+struct Parser<'a> {
+    tokenizer: Tokenizer<'a>,
+    merge: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn current(&self) -> &Token<'a> {
+        &self.tokenizer.current
+    }
+
+    fn error(&self, message: impl Into<String>) -> Error {
+        let token = self.current();
+        Error { line: token.line, column: token.column, message: message.into() }
+    }
+
+    fn looking_at(&self, text: &str) -> bool {
+        let token = self.current();
+        token.kind != TokenKind::String && token.text == text
+    }
+
+    fn looking_at_kind(&self, kind: TokenKind) -> bool {
+        self.current().kind == kind
+    }
+
+    fn try_consume(&mut self, text: &str) -> Result<bool> {
+        if !self.looking_at(text) {
+            return Ok(false);
+        }
+        self.tokenizer.next()?;
+        Ok(true)
+    }
+
+    fn consume(&mut self, text: &str) -> Result<()> {
+        if !self.try_consume(text)? {
+            let found = self.current().text;
+            return Err(self.error(format!("Expected \"{text}\", found \"{found}\".")));
+        }
+        Ok(())
+    }
+
+    fn consume_identifier(&mut self) -> Result<String> {
+        if !self.looking_at_kind(TokenKind::Identifier) {
+            return Err(self.error(format!("Expected identifier, got: {}", self.current().text)));
+        }
+        let identifier = self.current().text.to_string();
+        self.tokenizer.next()?;
+        Ok(identifier)
+    }
+
+    /// Consumes a dotted name like `foo.bar.Baz`.
+    fn consume_full_type_name(&mut self) -> Result<String> {
+        let mut name = self.consume_identifier()?;
+        while self.try_consume(".")? {
+            name.push('.');
+            name.push_str(&self.consume_identifier()?);
+        }
+        Ok(name)
+    }
+
+    /// Parses fields into `msg` until the end of the input, or if `nested`,
+    /// until a closing `}` or `>`, which is not consumed.
+    fn parse_fields(
+        &mut self,
+        msg: &mut MessageRefMut<'_>,
+        nested: bool,
+        depth: usize,
+    ) -> Result<()> {
+        // The singular extensions that have been set, since reflection can't
+        // tell whether they are.
+        let mut extensions = Vec::new();
+        loop {
+            let done = if nested {
+                self.looking_at(">") || self.looking_at("}")
+            } else {
+                self.looking_at_kind(TokenKind::End)
+            };
+            if done {
+                return Ok(());
+            }
+            self.parse_field(msg, &mut extensions, depth)?;
+        }
+    }
+
+    /// Parses a message value, including its `{ }` or `< >`, into `msg`.
+    fn parse_message(&mut self, msg: &mut MessageRefMut<'_>, depth: usize) -> Result<()> {
+        if depth >= RECURSION_LIMIT {
+            return Err(self.error(format!(
+                "Message is too deep, the parser exceeded the configured recursion limit of \
+                 {RECURSION_LIMIT}."
+            )));
+        }
+        let delimiter = if self.try_consume("<")? {
+            ">"
+        } else {
+            self.consume("{")?;
+            "}"
+        };
+        self.parse_fields(msg, true, depth + 1)?;
+        self.consume(delimiter)
+    }
+
+    fn parse_field(
+        &mut self,
+        msg: &mut MessageRefMut<'_>,
+        extensions: &mut Vec<u32>,
+        depth: usize,
+    ) -> Result<()> {
+        let descriptor = msg.descriptor();
+        if self.try_consume("[")? {
+            if descriptor.full_name() == "google.protobuf.Any" {
+                self.parse_any(msg, depth)?;
+            } else {
+                self.parse_extension(msg, extensions, depth)?;
+            }
+        } else {
+            let name = self.consume_identifier()?;
+            let Some(field) = find_field(&descriptor, &name) else {
+                return Err(self.error(format!(
+                    "Message type \"{}\" has no field named \"{name}\".",
+                    descriptor.full_name()
+                )));
+            };
+            if !self.merge {
+                self.check_not_set(msg, &field)?;
+            }
+            self.parse_field_value(msg, &field, depth)?;
+        }
+        // For historical reasons, fields may be followed by one `;` or `,`.
+        if !self.try_consume(";")? {
+            self.try_consume(",")?;
+        }
+        Ok(())
+    }
+
+    /// Fails if `field` or another member of its oneof is already set.
+    fn check_not_set(&self, msg: &MessageRefMut<'_>, field: &FieldDescriptor) -> Result<()> {
+        let name = field.name();
+        if !field.is_repeated() && msg.has_field(field) {
+            return Err(
+                self.error(format!("Non-repeated field \"{name}\" is specified multiple times."))
+            );
+        }
+        if let Some(oneof) = field.containing_oneof() {
+            if let Some(other) = oneof.fields().find(|other| msg.has_field(other)) {
+                return Err(self.error(format!(
+                    "Field \"{name}\" is specified along with field \"{}\", another member of \
+                     oneof \"{}\".",
+                    other.name(),
+                    oneof.name()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses the `:` and the value or values that follow a field name.
+    fn parse_field_value(
+        &mut self,
+        msg: &mut MessageRefMut<'_>,
+        field: &FieldDescriptor,
+        depth: usize,
+    ) -> Result<()> {
+        let is_message = field.message_type().is_some();
+        if is_message {
+            // The `:` is optional before messages.
+            self.try_consume(":")?;
+        } else {
+            self.consume(":")?;
+        }
+        if field.is_repeated() && self.try_consume("[")? {
+            // `[]` is an empty list.
+            if self.try_consume("]")? {
+                return Ok(());
+            }
+            loop {
+                self.parse_single_value(msg, field, depth)?;
+                if self.try_consume("]")? {
+                    return Ok(());
+                }
+                self.consume(",")?;
+            }
+        }
+        self.parse_single_value(msg, field, depth)
+    }
+
+    /// Parses one value of `field` and stores it in `msg`.
+    fn parse_single_value(
+        &mut self,
+        msg: &mut MessageRefMut<'_>,
+        field: &FieldDescriptor,
+        depth: usize,
+    ) -> Result<()> {
+        let Some(message_type) = field.message_type() else {
+            let value = self.parse_scalar(field)?;
+            if field.is_repeated() {
+                msg.mut_repeated(field).push(&value);
+            } else {
+                msg.set_field(field, &value);
+            }
+            return Ok(());
+        };
+        if field.is_map() {
+            // Entries are parsed on their own so that a later entry with the
+            // same key replaces an earlier one.
+            let mut entry = DynamicMessage::new(message_type.clone());
+            self.parse_message(&mut entry.as_message_mut(), depth)?;
+            let key = message_type.field_by_number(1).unwrap();
+            let value = message_type.field_by_number(2).unwrap();
+            let entry = entry.as_message_ref();
+            msg.mut_map(field).insert(entry.get_field(&key), entry.get_field(&value));
+        } else if field.is_repeated() {
+            let mut repeated = msg.mut_repeated(field);
+            repeated.push(DynamicMessage::new(message_type).as_message_ref());
+            let index = repeated.len() - 1;
+            self.parse_message(&mut repeated.mut_message(index), depth)?;
+        } else {
+            self.parse_message(&mut msg.mut_message(field), depth)?;
+        }
+        Ok(())
+    }
+
+    /// Parses a scalar value of `field`.
+    fn parse_scalar(&mut self, field: &FieldDescriptor) -> Result<ReflectValue> {
+        Ok(match field.field_type() {
+            FieldType::Int32 | FieldType::SInt32 | FieldType::SFixed32 => {
+                ReflectValue::I32(self.parse_signed(i32::MAX as u64)? as i32)
+            }
+            FieldType::Int64 | FieldType::SInt64 | FieldType::SFixed64 => {
+                ReflectValue::I64(self.parse_signed(i64::MAX as u64)?)
+            }
+            FieldType::UInt32 | FieldType::Fixed32 => {
+                ReflectValue::U32(self.parse_unsigned(u32::MAX.into())? as u32)
+            }
+            FieldType::UInt64 | FieldType::Fixed64 => {
+                ReflectValue::U64(self.parse_unsigned(u64::MAX)?)
+            }
+            // Values beyond the range of `float` become infinities.
+            FieldType::Float => ReflectValue::F32(self.parse_double()? as f32),
+            FieldType::Double => ReflectValue::F64(self.parse_double()?),
+            FieldType::Bool => ReflectValue::Bool(self.parse_bool(field)?),
+            FieldType::String => {
+                let bytes = self.parse_string()?;
+                if field.inner(Private).validates_utf8() && std::str::from_utf8(&bytes).is_err() {
+                    return Err(self.error(format!(
+                        "String field \"{}\" contains invalid UTF-8.",
+                        field.name()
+                    )));
+                }
+                ReflectValue::String(ProtoString::from(bytes.as_slice()))
+            }
+            FieldType::Bytes => {
+                ReflectValue::Bytes(ProtoBytes::from(self.parse_string()?.as_slice()))
+            }
+            FieldType::Enum => ReflectValue::Enum(self.parse_enum(field)?),
+            FieldType::Message | FieldType::Group => unreachable!("messages aren't scalars"),
+        })
+    }
+
+    fn parse_unsigned(&mut self, max: u64) -> Result<u64> {
+        if !self.looking_at_kind(TokenKind::Integer) {
+            return Err(self.error(format!("Expected integer, got: {}", self.current().text)));
+        }
+        let text = self.current().text;
+        let Some(value) = parse_integer(text, max) else {
+            return Err(self.error(format!("Integer out of range ({text})")));
+        };
+        self.tokenizer.next()?;
+        Ok(value)
+    }
+
+    fn parse_signed(&mut self, max: u64) -> Result<i64> {
+        if self.try_consume("-")? {
+            // Two's complement allows one more negative value than positive.
+            let value = self.parse_unsigned(max + 1)?;
+            Ok((value as i64).wrapping_neg())
+        } else {
+            Ok(self.parse_unsigned(max)? as i64)
+        }
+    }
+
+    fn parse_double(&mut self) -> Result<f64> {
+        let negative = self.try_consume("-")?;
+        let text = self.current().text;
+        let value = match self.current().kind {
+            TokenKind::Integer => {
+                if text.len() > 1 && text.starts_with('0') {
+                    return Err(self.error(format!("Expect a decimal number, got: {text}")));
+                }
+                text.parse().unwrap()
+            }
+            TokenKind::Float => text.trim_end_matches(['f', 'F']).parse().unwrap(),
+            TokenKind::Identifier => match text.to_ascii_lowercase().as_str() {
+                "inf" | "infinity" => f64::INFINITY,
+                "nan" => f64::NAN,
+                lower => return Err(self.error(format!("Expected double, got: {lower}"))),
+            },
+            _ => return Err(self.error(format!("Expected double, got: {text}"))),
+        };
+        self.tokenizer.next()?;
+        Ok(if negative { -value } else { value })
+    }
+
+    fn parse_bool(&mut self, field: &FieldDescriptor) -> Result<bool> {
+        if self.looking_at_kind(TokenKind::Integer) {
+            return Ok(self.parse_unsigned(1)? == 1);
+        }
+        match self.consume_identifier()?.as_str() {
+            "true" | "True" | "t" => Ok(true),
+            "false" | "False" | "f" => Ok(false),
+            value => Err(self.error(format!(
+                "Invalid value for boolean field \"{}\". Value: \"{value}\".",
+                field.name()
+            ))),
+        }
+    }
+
+    /// Parses adjacent string literals, which are concatenated.
+    fn parse_string(&mut self) -> Result<Vec<u8>> {
+        if !self.looking_at_kind(TokenKind::String) {
+            return Err(self.error(format!("Expected string, got: {}", self.current().text)));
+        }
+        let mut bytes = Vec::new();
+        while self.looking_at_kind(TokenKind::String) {
+            bytes.append(&mut self.tokenizer.current.bytes);
+            self.tokenizer.next()?;
+        }
+        Ok(bytes)
+    }
+
+    fn parse_enum(&mut self, field: &FieldDescriptor) -> Result<i32> {
+        let enum_type = field.enum_type().unwrap();
+        let (value, number) = if self.looking_at_kind(TokenKind::Identifier) {
+            let name = self.consume_identifier()?;
+            let number = enum_type.values().find(|v| v.name() == name).map(|v| v.number());
+            (name, number)
+        } else if self.looking_at("-") || self.looking_at_kind(TokenKind::Integer) {
+            let number = self.parse_signed(i32::MAX as u64)? as i32;
+            // Open enums accept numbers that have no name.
+            let known = !enum_type.is_closed() || enum_type.value_by_number(number).is_some();
+            (number.to_string(), known.then_some(number))
+        } else {
+            let text = self.current().text;
+            return Err(self.error(format!("Expected integer or identifier, got: {text}")));
+        };
+        number.ok_or_else(|| {
+            self.error(format!(
+                "Unknown enumeration value of \"{value}\" for field \"{}\".",
+                field.name()
+            ))
+        })
+    }
+
+    /// Parses an expanded `Any` after its `[`, like
+    /// `[type.googleapis.com/foo.Bar] { ... }`.
+    fn parse_any(&mut self, msg: &mut MessageRefMut<'_>, depth: usize) -> Result<()> {
+        let mut prefix = self.consume_full_type_name()?;
+        self.consume("/")?;
+        prefix.push('/');
+        let type_name = self.consume_full_type_name()?;
+        self.consume("]")?;
+        self.try_consume(":")?;
+
+        let descriptor = msg.descriptor();
+        let value_type = ANY_TYPE_URL_PREFIXES
+            .contains(&prefix.as_str())
+            .then(|| find_message_by_name(&descriptor, &type_name))
+            .flatten();
+        let Some(value_type) = value_type else {
+            return Err(self.error(format!(
+                "Could not find type \"{prefix}{type_name}\" stored in google.protobuf.Any."
+            )));
+        };
+        let mut value = DynamicMessage::new(value_type);
+        self.parse_message(&mut value.as_message_mut(), depth)?;
+        let Ok(serialized) = value.serialize() else {
+            return Err(self.error(format!(
+                "Value of type \"{type_name}\" stored in google.protobuf.Any has missing \
+                 required fields"
+            )));
+        };
+
+        let type_url = descriptor.field_by_number(1).unwrap();
+        let value = descriptor.field_by_number(2).unwrap();
+        if !self.merge && (msg.has_field(&type_url) || msg.has_field(&value)) {
+            return Err(self.error("Non-repeated Any specified multiple times."));
+        }
+        msg.set_field(&type_url, format!("{prefix}{type_name}").as_str());
+        msg.set_field(&value, serialized.as_slice());
+        Ok(())
+    }
+
+    /// Parses an extension after its `[`, like `[foo.bar_ext]: 1`.
+    ///
+    /// Reflection can't set extensions, so their values are encoded in the
+    /// wire format and merged into `msg`.
+    fn parse_extension(
+        &mut self,
+        msg: &mut MessageRefMut<'_>,
+        extensions: &mut Vec<u32>,
+        depth: usize,
+    ) -> Result<()> {
+        let name = self.consume_full_type_name()?;
+        self.consume("]")?;
+        let descriptor = msg.descriptor();
+        let Some(ext) = find_extension_by_name(&descriptor, &name) else {
+            return Err(self.error(format!(
+                "Extension \"{name}\" is not defined or is not an extension of \"{}\".",
+                descriptor.full_name()
+            )));
+        };
+        if !self.merge && !ext.is_repeated() {
+            if extensions.contains(&ext.number()) {
+                return Err(self
+                    .error(format!("Non-repeated field \"{name}\" is specified multiple times.")));
+            }
+            extensions.push(ext.number());
+        }
+
+        let mut data = Vec::new();
+        if ext.message_type().is_some() {
+            self.try_consume(":")?;
+        } else {
+            self.consume(":")?;
+        }
+        if ext.is_repeated() && self.try_consume("[")? {
+            if !self.try_consume("]")? {
+                loop {
+                    self.parse_extension_value(&ext, &mut data, depth)?;
+                    if self.try_consume("]")? {
+                        break;
+                    }
+                    self.consume(",")?;
+                }
+            }
+        } else {
+            self.parse_extension_value(&ext, &mut data, depth)?;
+        }
+        merge_with_extensions(msg.as_message_mut(), &data)
+            .map_err(|e| self.error(format!("Couldn't set extension \"{name}\": {e}")))
+    }
+
+    /// Parses one value of the extension `ext` and appends it to `data` in
+    /// the wire format.
+    fn parse_extension_value(
+        &mut self,
+        ext: &FieldDescriptor,
+        data: &mut Vec<u8>,
+        depth: usize,
+    ) -> Result<()> {
+        let number = ext.number();
+        let Some(message_type) = ext.message_type() else {
+            let value = self.parse_scalar(ext)?;
+            encode_scalar(data, number, ext.field_type(), &value);
+            return Ok(());
+        };
+        let mut value = DynamicMessage::new(message_type.clone());
+        self.parse_message(&mut value.as_message_mut(), depth)?;
+        let Ok(serialized) = value.serialize() else {
+            return Err(self.error(format!(
+                "Value of extension \"{}\" has missing required fields",
+                ext.full_name()
+            )));
+        };
+        if ext.field_type() == FieldType::Group {
+            encode_tag(data, number, WireType::StartGroup);
+            data.extend_from_slice(&serialized);
+            encode_tag(data, number, WireType::EndGroup);
+        } else {
+            encode_delimited(data, number, &serialized);
+        }
+        Ok(())
+    }
+}
+
+/// Looks up the field called `name`. Group-like fields may also be named by
+/// their message type, as they are printed by C++.
+fn find_field(descriptor: &MessageDescriptor, name: &str) -> Option<FieldDescriptor> {
+    if let Some(field) = descriptor.field_by_name(name) {
+        return Some(field);
+    }
+    descriptor
+        .field_by_name(&name.to_ascii_lowercase())
+        .filter(|field| is_group_like(field) && field.message_type().unwrap().name() == name)
+}
+
+/// Returns whether `field` is a delimited field that is declared like a
+/// proto2 `group`, with a message type of the same name in the same scope.
+pub(crate) fn is_group_like(field: &FieldDescriptor) -> bool {
+    if field.field_type() != FieldType::Group {
+        return false;
+    }
+    let message_type = field.message_type().unwrap();
+    field.name() == message_type.name().to_ascii_lowercase()
+        && message_type.file() == field.containing_type().file()
+        && message_type.containing_type() == Some(field.containing_type())
+}
+
+#[derive(Clone, Copy)]
+enum WireType {
+    Varint = 0,
+    Fixed64 = 1,
+    Delimited = 2,
+    StartGroup = 3,
+    EndGroup = 4,
+    Fixed32 = 5,
+}
+
+fn encode_varint(data: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        data.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    data.push(value as u8);
+}
+
+fn encode_tag(data: &mut Vec<u8>, number: u32, wire_type: WireType) {
+    encode_varint(data, u64::from(number) << 3 | wire_type as u64);
+}
+
+/// Appends the field `number` with the scalar `value` of type `field_type`
+/// to `data`.
+fn encode_scalar(data: &mut Vec<u8>, number: u32, field_type: FieldType, value: &ReflectValue) {
+    let varint = match (field_type, value) {
+        (_, ReflectValue::String(v)) => return encode_delimited(data, number, v.as_bytes()),
+        (_, ReflectValue::Bytes(v)) => return encode_delimited(data, number, v.as_view()),
+        (FieldType::Fixed32, ReflectValue::U32(v)) => {
+            return encode_fixed(data, number, WireType::Fixed32, &v.to_le_bytes());
+        }
+        (FieldType::SFixed32, ReflectValue::I32(v)) => {
+            return encode_fixed(data, number, WireType::Fixed32, &v.to_le_bytes());
+        }
+        (_, ReflectValue::F32(v)) => {
+            return encode_fixed(data, number, WireType::Fixed32, &v.to_le_bytes());
+        }
+        (FieldType::Fixed64, ReflectValue::U64(v)) => {
+            return encode_fixed(data, number, WireType::Fixed64, &v.to_le_bytes());
+        }
+        (FieldType::SFixed64, ReflectValue::I64(v)) => {
+            return encode_fixed(data, number, WireType::Fixed64, &v.to_le_bytes());
+        }
+        (_, ReflectValue::F64(v)) => {
+            return encode_fixed(data, number, WireType::Fixed64, &v.to_le_bytes());
+        }
+        (_, ReflectValue::Bool(v)) => u64::from(*v),
+        (FieldType::SInt32, ReflectValue::I32(v)) => u64::from(((v << 1) ^ (v >> 31)) as u32),
+        (FieldType::SInt64, ReflectValue::I64(v)) => ((v << 1) ^ (v >> 63)) as u64,
+        // Negative `int32`s and enums are sign-extended to 64 bits.
+        (_, ReflectValue::I32(v) | ReflectValue::Enum(v)) => i64::from(*v) as u64,
+        (_, ReflectValue::I64(v)) => *v as u64,
+        (_, ReflectValue::U32(v)) => u64::from(*v),
+        (_, ReflectValue::U64(v)) => *v,
+        (_, ReflectValue::Message(_)) => unreachable!("messages aren't scalars"),
+    };
+    encode_tag(data, number, WireType::Varint);
+    encode_varint(data, varint);
+}
+
+fn encode_fixed(data: &mut Vec<u8>, number: u32, wire_type: WireType, bytes: &[u8]) {
+    encode_tag(data, number, wire_type);
+    data.extend_from_slice(bytes);
+}
+
+fn encode_delimited(data: &mut Vec<u8>, number: u32, bytes: &[u8]) {
+    encode_tag(data, number, WireType::Delimited);
+    encode_varint(data, bytes.len() as u64);
+    data.extend_from_slice(bytes);
+}
@@ -0,0 +1,320 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! A text format printer for the upb kernel.
+//!
+//! `upb_TextEncode` is shared by every upb-based runtime, and its output
+//! differs from that of `google::protobuf::TextFormat::Printer`: it orders
+//! string map keys by length, prints fixed-width unknown fields in decimal and
+//! prints group-like fields by their field name. Rather than changing upb for
+//! all of its users, this printer is written against the reflection API like
+//! the parser is, and follows `TextFormat::Printer` with UTF-8 string escaping,
+//! as the C++ kernel configures it, so that both kernels print the same text.
+
+use crate::__internal::runtime::{parse_unknown_fields, unknown_fields};
+use crate::__internal::Private;
+use crate::reflect::{FieldDescriptor, MessageRef, ReflectValueRef};
+use crate::text_format_parser::is_group_like;
+use crate::{UnknownFields, UnknownValue};
+use std::cmp::Ordering;
+use std::fmt::Write;
+
+/// Prints `msg` in the text format. In single line mode every field, including
+/// the last one, ends with a space.
+pub fn print(msg: MessageRef<'_>, single_line: bool, print_unknown_fields: bool) -> String {
+    let mut printer = Printer { out: String::new(), single_line, print_unknown_fields, depth: 0 };
+    printer.print_message(&msg);
+    printer.out
+}
+
+struct Printer {
+    out: String,
+    single_line: bool,
+    print_unknown_fields: bool,
+    /// How deeply the field that is being printed is nested.
+    depth: usize,
+}
+
+impl Printer {
+    fn print_message(&mut self, msg: &MessageRef<'_>) {
+        let descriptor = msg.descriptor();
+        let mut fields: Vec<_> = descriptor.fields().chain(msg.extensions()).collect();
+        fields.retain(|field| msg.has_field(field));
+        fields.sort_by_key(FieldDescriptor::number);
+
+        for field in &fields {
+            if field.is_map() {
+                self.print_map(msg, field);
+            } else if field.is_repeated() {
+                for value in msg.get_repeated(field).iter() {
+                    self.print_field(field, value);
+                }
+            } else {
+                self.print_field(field, msg.get_field(field));
+            }
+        }
+
+        if self.print_unknown_fields {
+            // SAFETY: `msg` is borrowed, so it isn't mutated while its unknown
+            // fields are printed.
+            self.print_unknown_fields(unsafe { unknown_fields(msg.raw(Private)) });
+        }
+    }
+
+    /// Prints the entries of a map field as messages with a `key` and a
+    /// `value` field, sorted by key.
+    fn print_map(&mut self, msg: &MessageRef<'_>, field: &FieldDescriptor) {
+        let entry = field.message_type().unwrap();
+        let key_field = entry.field_by_number(1).unwrap();
+        let value_field = entry.field_by_number(2).unwrap();
+        let mut entries: Vec<_> = msg.get_map(field).iter().collect();
+        entries.sort_by(|(a, _), (b, _)| compare_map_keys(a, b));
+
+        for (key, value) in entries {
+            self.start_field();
+            self.print_field_name(field);
+            self.out.push_str(" {");
+            self.end_field();
+            self.depth += 1;
+            self.print_field(&key_field, key);
+            self.print_field(&value_field, value);
+            self.depth -= 1;
+            self.start_field();
+            self.out.push('}');
+            self.end_field();
+        }
+    }
+
+    fn print_field(&mut self, field: &FieldDescriptor, value: ReflectValueRef<'_>) {
+        self.start_field();
+        self.print_field_name(field);
+        if let ReflectValueRef::Message(msg) = value {
+            self.out.push_str(" {");
+            self.end_field();
+            self.depth += 1;
+            self.print_message(&msg);
+            self.depth -= 1;
+            self.start_field();
+            self.out.push('}');
+        } else {
+            self.out.push_str(": ");
+            self.print_value(field, value);
+        }
+        self.end_field();
+    }
+
+    fn print_field_name(&mut self, field: &FieldDescriptor) {
+        if field.is_extension() {
+            let _ = write!(self.out, "[{}]", field.full_name());
+        } else if is_group_like(field) {
+            // Groups are printed by the name of their type, which is how they
+            // are spelled in the `.proto` file.
+            self.out.push_str(field.message_type().unwrap().name());
+        } else {
+            self.out.push_str(field.name());
+        }
+    }
+
+    fn print_value(&mut self, field: &FieldDescriptor, value: ReflectValueRef<'_>) {
+        match value {
+            ReflectValueRef::Bool(v) => self.out.push_str(if v { "true" } else { "false" }),
+            ReflectValueRef::I32(v) => self.out.push_str(&v.to_string()),
+            ReflectValueRef::I64(v) => self.out.push_str(&v.to_string()),
+            ReflectValueRef::U32(v) => self.out.push_str(&v.to_string()),
+            ReflectValueRef::U64(v) => self.out.push_str(&v.to_string()),
+            ReflectValueRef::F32(v) => {
+                print_float(&mut self.out, f64::from(v), 6, 9, |s| s.parse() == Ok(v))
+            }
+            ReflectValueRef::F64(v) => {
+                print_float(&mut self.out, v, 15, 17, |s| s.parse() == Ok(v))
+            }
+            ReflectValueRef::String(v) => print_string(&mut self.out, v.as_bytes()),
+            ReflectValueRef::Bytes(v) => print_bytes(&mut self.out, &v),
+            ReflectValueRef::Enum(v) => {
+                // Open enums can hold numbers without a name.
+                match field.enum_type().unwrap().value_by_number(v) {
+                    Some(value) => self.out.push_str(value.name()),
+                    None => self.out.push_str(&v.to_string()),
+                }
+            }
+            ReflectValueRef::Message(_) => unreachable!("messages are printed by print_field"),
+        }
+    }
+
+    /// Prints unknown fields by number, like `TextFormat::Printer` does:
+    /// fixed-width values in hex, and length-delimited values as a message
+    /// if they parse as one and as bytes otherwise.
+    fn print_unknown_fields(&mut self, fields: UnknownFields<'_>) {
+        for (number, _, value) in fields {
+            self.start_field();
+            match value {
+                UnknownValue::Varint(v) => {
+                    let _ = write!(self.out, "{number}: {v}");
+                }
+                UnknownValue::Fixed32(v) => {
+                    let _ = write!(self.out, "{number}: 0x{v:08x}");
+                }
+                UnknownValue::Fixed64(v) => {
+                    let _ = write!(self.out, "{number}: 0x{v:016x}");
+                }
+                UnknownValue::LengthDelimited(data) => {
+                    match parse_unknown_fields(data).filter(|_| !data.is_empty()) {
+                        Some(nested) => self.print_unknown_group(number, nested),
+                        None => {
+                            let _ = write!(self.out, "{number}: ");
+                            print_bytes(&mut self.out, data);
+                        }
+                    }
+                }
+                UnknownValue::Group(nested) => self.print_unknown_group(number, nested),
+            }
+            self.end_field();
+        }
+    }
+
+    fn print_unknown_group(&mut self, number: u32, fields: UnknownFields<'_>) {
+        let _ = write!(self.out, "{number} {{");
+        self.end_field();
+        self.depth += 1;
+        self.print_unknown_fields(fields);
+        self.depth -= 1;
+        self.start_field();
+        self.out.push('}');
+    }
+
+    fn start_field(&mut self) {
+        if !self.single_line {
+            for _ in 0..self.depth {
+                self.out.push_str("  ");
+            }
+        }
+    }
+
+    fn end_field(&mut self) {
+        self.out.push(if self.single_line { ' ' } else { '\n' });
+    }
+}
+
+/// Orders map keys, which are all of the same integral, `bool` or `string`
+/// type.
+fn compare_map_keys(a: &ReflectValueRef<'_>, b: &ReflectValueRef<'_>) -> Ordering {
+    match (a, b) {
+        (ReflectValueRef::Bool(a), ReflectValueRef::Bool(b)) => a.cmp(b),
+        (ReflectValueRef::I32(a), ReflectValueRef::I32(b)) => a.cmp(b),
+        (ReflectValueRef::I64(a), ReflectValueRef::I64(b)) => a.cmp(b),
+        (ReflectValueRef::U32(a), ReflectValueRef::U32(b)) => a.cmp(b),
+        (ReflectValueRef::U64(a), ReflectValueRef::U64(b)) => a.cmp(b),
+        (ReflectValueRef::String(a), ReflectValueRef::String(b)) => a.as_bytes().cmp(b.as_bytes()),
+        _ => unreachable!("invalid map keys {a:?} and {b:?}"),
+    }
+}
+
+/// Prints `value` with the fewest significant digits, out of `digits` and
+/// `max_digits`, that `round_trips`, formatted like `%g`. This matches
+/// `SimpleDtoa()` and `SimpleFtoa()`.
+fn print_float(
+    out: &mut String,
+    value: f64,
+    digits: usize,
+    max_digits: usize,
+    round_trips: impl Fn(&str) -> bool,
+) {
+    if value.is_nan() {
+        out.push_str("nan");
+    } else if value.is_infinite() {
+        out.push_str(if value > 0.0 { "inf" } else { "-inf" });
+    } else {
+        let short = format_g(value, digits);
+        out.push_str(&if round_trips(&short) { short } else { format_g(value, max_digits) });
+    }
+}
+
+/// Formats a finite `value` like `printf("%.*g", precision, value)`.
+fn format_g(value: f64, precision: usize) -> String {
+    // `{:e}` rounds to the requested number of significant digits, which
+    // decides the exponent that `%g` chooses the notation by.
+    let scientific = format!("{:.*e}", precision - 1, value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    if exponent < -4 || exponent >= precision as i32 {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{sign}{:02}", trim_fraction(mantissa), exponent.unsigned_abs())
+    } else {
+        let decimals = (precision as i32 - 1 - exponent) as usize;
+        trim_fraction(&format!("{value:.decimals$}")).to_string()
+    }
+}
+
+/// Removes the trailing zeros of the fraction of a decimal number, and the
+/// decimal point if no fraction is left.
+fn trim_fraction(number: &str) -> &str {
+    if number.contains('.') {
+        number.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        number
+    }
+}
+
+/// Prints the value of a `string` field, which keeps valid UTF-8 as is and
+/// escapes the bytes that aren't.
+fn print_string(out: &mut String, mut value: &[u8]) {
+    out.push('"');
+    loop {
+        match std::str::from_utf8(value) {
+            Ok(valid) => {
+                escape_str(out, valid);
+                break;
+            }
+            Err(error) => {
+                let (valid, rest) = value.split_at(error.valid_up_to());
+                escape_str(out, std::str::from_utf8(valid).unwrap());
+                let invalid = error.error_len().unwrap_or(rest.len());
+                for &byte in &rest[..invalid] {
+                    escape_byte(out, byte);
+                }
+                value = &rest[invalid..];
+            }
+        }
+    }
+    out.push('"');
+}
+
+fn escape_str(out: &mut String, value: &str) {
+    for c in value.chars() {
+        if c.is_ascii() {
+            escape_byte(out, c as u8);
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+/// Prints the value of a `bytes` field, which escapes everything but
+/// printable ASCII.
+fn print_bytes(out: &mut String, value: &[u8]) {
+    out.push('"');
+    for &byte in value {
+        escape_byte(out, byte);
+    }
+    out.push('"');
+}
+
+/// Prints `byte` like `absl::CEscape()` does.
+fn escape_byte(out: &mut String, byte: u8) {
+    match byte {
+        b'\n' => out.push_str("\\n"),
+        b'\r' => out.push_str("\\r"),
+        b'\t' => out.push_str("\\t"),
+        b'"' => out.push_str("\\\""),
+        b'\'' => out.push_str("\\'"),
+        b'\\' => out.push_str("\\\\"),
+        b' '..=b'~' => out.push(byte as char),
+        _ => {
+            let _ = write!(out, "\\{byte:03o}");
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Lookup of message types by the type URLs of `google.protobuf.Any`.
+
+use crate::reflect::{DynamicMessage, MessageDescriptor};
+use crate::{Message, ParseError};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A set of message types that a `google.protobuf.Any` can be unpacked into
+/// when its type isn't known statically.
+///
+/// Unpacking looks up the type that the type URL of the `Any` names, and
+/// parses its value as a [`DynamicMessage`], which can then be printed with
+/// `Debug` or as JSON:
+///
+/// ```ignore
+/// let mut registry = TypeRegistry::new();
+/// registry.add::<OrderPlaced>();
+/// registry.add::<OrderShipped>();
+/// if let Some(event) = any.unpack_dynamic(&registry)? {
+///     println!("{event:?}");
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct TypeRegistry {
+    types: BTreeMap<String, MessageDescriptor>,
+}
+
+impl TypeRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        TypeRegistry { types: BTreeMap::new() }
+    }
+
+    /// Adds the generated message type `M` to the registry.
+    pub fn add<M: Message>(&mut self) {
+        self.add_descriptor(M::descriptor());
+    }
+
+    /// Adds the message type `descriptor`, which may also come from a
+    /// [`DescriptorPool`](crate::reflect::DescriptorPool) that was built at
+    /// runtime. Adding a type replaces any type of the same name.
+    pub fn add_descriptor(&mut self, descriptor: MessageDescriptor) {
+        self.types.insert(descriptor.full_name().to_string(), descriptor);
+    }
+
+    /// Returns the type that `type_url` names, if it is in the registry.
+    ///
+    /// Only the part of `type_url` after its last `/` is significant, so
+    /// `type.googleapis.com/foo.Bar` and `example.com/types/foo.Bar` both
+    /// name the type `foo.Bar`.
+    pub fn find(&self, type_url: &str) -> Option<MessageDescriptor> {
+        let (_, full_name) = type_url.rsplit_once('/')?;
+        self.types.get(full_name).cloned()
+    }
+
+    /// Parses `value` as the type that `type_url` names, or returns `None` if
+    /// that type isn't in the registry.
+    pub fn unpack(
+        &self,
+        type_url: &str,
+        value: &[u8],
+    ) -> Result<Option<DynamicMessage>, ParseError> {
+        self.find(type_url).map(|descriptor| DynamicMessage::parse(descriptor, value)).transpose()
+    }
+}
+
+impl fmt::Debug for TypeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypeRegistry").field("types", &self.types.keys()).finish()
+    }
+}
@@ -0,0 +1,139 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Unknown fields: data that was parsed into a message but does not belong to
+//! any of its known fields.
+
+use crate::__internal::runtime::{InnerUnknownFields, InnerUnknownFieldsIter};
+use crate::__internal::Private;
+use std::fmt;
+
+/// The wire type of an unknown field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WireType {
+    /// A varint-encoded integer.
+    Varint,
+    /// A little-endian 64-bit value.
+    Fixed64,
+    /// A length-prefixed sequence of bytes, such as a string or a submessage.
+    LengthDelimited,
+    /// A group, which is delimited by a start and an end tag.
+    StartGroup,
+    /// A little-endian 32-bit value.
+    Fixed32,
+}
+
+/// The value of an unknown field.
+///
+/// Unknown fields carry no type information beyond their wire type, so
+/// integers are not decoded further: a `sint32` field is returned as its
+/// zigzag-encoded varint, and a `float` as the bits of a [`Fixed32`].
+///
+/// [`Fixed32`]: UnknownValue::Fixed32
+#[derive(Debug, Clone, Copy)]
+pub enum UnknownValue<'msg> {
+    Varint(u64),
+    Fixed64(u64),
+    LengthDelimited(&'msg [u8]),
+    /// The fields between the start and the end tag of the group.
+    Group(UnknownFields<'msg>),
+    Fixed32(u32),
+}
+
+impl UnknownValue<'_> {
+    /// Returns the wire type that the value was encoded with.
+    pub fn wire_type(&self) -> WireType {
+        match self {
+            UnknownValue::Varint(_) => WireType::Varint,
+            UnknownValue::Fixed64(_) => WireType::Fixed64,
+            UnknownValue::LengthDelimited(_) => WireType::LengthDelimited,
+            UnknownValue::Group(_) => WireType::StartGroup,
+            UnknownValue::Fixed32(_) => WireType::Fixed32,
+        }
+    }
+}
+
+/// The unknown fields of a message, in the order they were parsed.
+///
+/// Iterating yields a `(field number, wire type, value)` tuple for every
+/// unknown field. A field that occurs several times on the wire is yielded
+/// once per occurrence:
+///
+/// ```ignore
+/// for (number, wire_type, value) in msg.unknown_fields() {
+///     println!("unexpected field {number} ({wire_type:?}): {value:?}");
+/// }
+/// ```
+///
+/// On the C++ kernel, messages that are compiled with
+/// `optimize_for = LITE_RUNTIME` do not expose their unknown fields and always
+/// appear to have none.
+#[derive(Clone, Copy)]
+pub struct UnknownFields<'msg> {
+    inner: InnerUnknownFields<'msg>,
+}
+
+impl<'msg> UnknownFields<'msg> {
+    #[doc(hidden)]
+    pub fn new(_private: Private, inner: InnerUnknownFields<'msg>) -> Self {
+        UnknownFields { inner }
+    }
+
+    /// Returns true if there are no unknown fields.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns an iterator over the unknown fields.
+    pub fn iter(&self) -> UnknownFieldsIter<'msg> {
+        UnknownFieldsIter { inner: self.inner.iter() }
+    }
+}
+
+impl fmt::Debug for UnknownFields<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'msg> IntoIterator for UnknownFields<'msg> {
+    type Item = (u32, WireType, UnknownValue<'msg>);
+    type IntoIter = UnknownFieldsIter<'msg>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'msg> IntoIterator for &UnknownFields<'msg> {
+    type Item = (u32, WireType, UnknownValue<'msg>);
+    type IntoIter = UnknownFieldsIter<'msg>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the [`UnknownFields`] of a message.
+pub struct UnknownFieldsIter<'msg> {
+    inner: InnerUnknownFieldsIter<'msg>,
+}
+
+impl<'msg> Iterator for UnknownFieldsIter<'msg> {
+    type Item = (u32, WireType, UnknownValue<'msg>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (number, value) = self.inner.next()?;
+        Some((number, value.wire_type(), value))
+    }
+}
+
+impl fmt::Debug for UnknownFieldsIter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnknownFieldsIter").finish_non_exhaustive()
+    }
+}
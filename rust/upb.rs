@@ -8,15 +8,25 @@
 //! UPB FFI wrapper code for use by Rust Protobuf.
 
 use crate::__internal::{Enum, Private, SealedInternal};
+use crate::reflect::{
+    EnumDescriptor, FieldDescriptor, FieldType, MessageDescriptor, MessageRef, MessageRefMut,
+    ReflectValueRef,
+};
 use crate::{
-    IntoProxied, Map, MapIter, MapMut, MapView, Mut, ProtoBytes, ProtoStr, ProtoString, Proxied,
-    ProxiedInMapValue, ProxiedInRepeated, Repeated, RepeatedMut, RepeatedView, View,
+    ContiguousRepeated, ExtensionType, IntoProxied, JsonParseOptions, JsonPrintOptions, Map,
+    MapIter, MapMut, MapView, Mut, ParseError, ParseErrorKind, ParseOptions, ProtoBytes,
+    ProtoBytesCow, ProtoStr, ProtoString, ProtoStringCow, Proxied, ProxiedInMapValue,
+    ProxiedInRepeated, Repeated, RepeatedMut, RepeatedView, SerializeError, SerializeErrorKind,
+    SerializeOptions, TextFormatPrintOptions, UnknownFields, UnknownValue, View,
 };
-use core::fmt::Debug;
+use core::ffi::c_char;
+use core::fmt::{self, Debug};
+use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
 use std::mem::{size_of, ManuallyDrop, MaybeUninit};
 use std::ptr::{self, NonNull};
 use std::slice;
-use std::sync::OnceLock;
+use std::sync::{Mutex, OnceLock, PoisonError};
 
 #[cfg(bzl)]
 extern crate upb;
@@ -72,6 +82,184 @@ impl IntoProxied<ProtoBytes> for SerializedData {
     }
 }
 
+/// Converts the status of a failed `upb_Decode` into a `ParseError`.
+///
+/// upb does not report where in the input decoding failed, so the returned
+/// error only carries the kind of failure.
+pub fn parse_error_from_decode_status(status: DecodeStatus) -> ParseError {
+    let kind = match status {
+        DecodeStatus::Ok => panic!("decoding succeeded"),
+        DecodeStatus::Malformed => ParseErrorKind::Malformed,
+        DecodeStatus::OutOfMemory => ParseErrorKind::OutOfMemory,
+        DecodeStatus::BadUtf8 => ParseErrorKind::BadUtf8,
+        DecodeStatus::MaxDepthExceeded => ParseErrorKind::MaxDepthExceeded,
+        DecodeStatus::MissingRequired => ParseErrorKind::MissingRequired,
+        DecodeStatus::UnlinkedSubMessage => ParseErrorKind::UnlinkedSubMessage,
+    };
+    ParseError::new(Private, kind)
+}
+
+/// Decodes `data` into `msg` (merge semantics) as controlled by `options`.
+///
+/// # Safety
+/// - `msg` must be mutable.
+/// - `msg` must be associated with `mini_table`.
+/// - `arena` must be the arena that owns `msg`.
+pub unsafe fn parse_with_options(
+    data: &[u8],
+    msg: RawMessage,
+    mini_table: *const upb_MiniTable,
+    arena: &Arena,
+    options: &ParseOptions<'_>,
+) -> Result<(), ParseError> {
+    options.check_len(data)?;
+    let mut decode_options = wire::decode_max_depth(options.recursion_limit.unwrap_or(0));
+    if options.check_required {
+        decode_options |= wire::DecodeOption::CheckRequired as i32;
+    }
+    if options.validate_utf8 {
+        decode_options |= wire::DecodeOption::AlwaysValidateUtf8 as i32;
+    }
+    let extreg = options.extension_registry.map(|r| r.inner(Private).raw);
+    // SAFETY:
+    // - `msg` is mutable and associated with `mini_table`, as promised by the
+    //   caller.
+    // - `extreg` is kept alive by the borrow in `options`.
+    // - `decode_options` does not alias the input.
+    unsafe { wire::decode_with_options(data, msg, mini_table, extreg, arena, decode_options) }
+        .map_err(parse_error_from_decode_status)
+}
+
+/// Decodes `data` into `msg` (merge semantics), letting the `string` and
+/// `bytes` fields of `msg` point into `data` instead of copying them.
+///
+/// # Safety
+/// - `msg` must be mutable.
+/// - `msg` must be associated with `mini_table`.
+/// - `arena` must be the arena that owns `msg`.
+/// - `data` must outlive every use of `msg`.
+pub unsafe fn parse_aliased(
+    data: &[u8],
+    msg: RawMessage,
+    mini_table: *const upb_MiniTable,
+    arena: &Arena,
+) -> Result<(), ParseError> {
+    let options = wire::DecodeOption::CheckRequired as i32 | wire::DecodeOption::AliasString as i32;
+    // SAFETY:
+    // - `msg` is mutable and associated with `mini_table`, as promised by the
+    //   caller.
+    // - `data` outlives `msg`, as promised by the caller.
+    unsafe { wire::decode_with_options(data, msg, mini_table, None, arena, options) }
+        .map_err(parse_error_from_decode_status)
+}
+
+/// Collects the paths of the unset required fields of a message and of its
+/// submessages.
+///
+/// upb itself only reports that some required field is unset, so this is
+/// implemented by generated code for every message view.
+#[doc(hidden)]
+pub trait UnsetRequiredFields {
+    /// Appends the path of every unset required field to `out`, each prefixed
+    /// with `prefix`.
+    fn unset_required_fields(self, prefix: &str, out: &mut Vec<String>);
+}
+
+/// Converts the status of a failed `upb_Encode` of `msg` into a
+/// `SerializeError`.
+pub fn serialize_error_from_encode_status(
+    status: EncodeStatus,
+    msg: impl UnsetRequiredFields,
+) -> SerializeError {
+    let kind = match status {
+        EncodeStatus::Ok => panic!("encoding succeeded"),
+        EncodeStatus::OutOfMemory => SerializeErrorKind::OutOfMemory,
+        EncodeStatus::MaxDepthExceeded => SerializeErrorKind::MaxDepthExceeded,
+        EncodeStatus::BufferTooSmall => SerializeErrorKind::BufferTooSmall,
+        EncodeStatus::MissingRequired => {
+            let mut missing = Vec::new();
+            msg.unset_required_fields("", &mut missing);
+            return SerializeError::new(Private, SerializeErrorKind::MissingRequired)
+                .with_missing_required_fields(Private, missing);
+        }
+    };
+    SerializeError::new(Private, kind)
+}
+
+/// Returns the `upb_Encode` options that correspond to `options`.
+fn encode_options(options: &SerializeOptions) -> i32 {
+    let mut encode_options = 0;
+    if options.is_deterministic() {
+        encode_options |= wire::EncodeOption::Deterministic as i32;
+    }
+    if options.is_check_required() {
+        encode_options |= wire::EncodeOption::CheckRequired as i32;
+    }
+    encode_options
+}
+
+/// Appends the serialization of `msg` to `out`.
+///
+/// upb can only learn the size of a message by encoding it, so the message is
+/// encoded once into a temporary arena, which sizes the single reservation in
+/// `out`, and then copied into `out`.
+///
+/// # Safety
+/// - `msg` must be associated with `mini_table`.
+pub unsafe fn serialize_to_vec(
+    msg: RawMessage,
+    mini_table: *const upb_MiniTable,
+    view: impl UnsetRequiredFields,
+    out: &mut Vec<u8>,
+    options: &SerializeOptions,
+) -> Result<(), SerializeError> {
+    // SAFETY: `mini_table` is the one associated with `msg`.
+    unsafe {
+        wire::encode_with(msg, mini_table, encode_options(options), |bytes| {
+            out.extend_from_slice(bytes)
+        })
+    }
+    .map_err(|status| serialize_error_from_encode_status(status, view))
+}
+
+/// Serializes `msg` as controlled by `options`.
+///
+/// # Safety
+/// - `msg` must be associated with `mini_table`.
+pub unsafe fn serialize_with_options(
+    msg: RawMessage,
+    mini_table: *const upb_MiniTable,
+    view: impl UnsetRequiredFields,
+    options: &SerializeOptions,
+) -> Result<Vec<u8>, SerializeError> {
+    let mut out = Vec::new();
+    // SAFETY: `mini_table` is the one associated with `msg`.
+    unsafe { serialize_to_vec(msg, mini_table, view, &mut out, options) }?;
+    Ok(out)
+}
+
+/// Serializes `msg` into the front of `buf`, returning the number of bytes
+/// written.
+///
+/// The message is encoded directly into `buf`, and encoding stops with
+/// `BufferTooSmall` as soon as the output exceeds it.
+///
+/// # Safety
+/// - `msg` must be associated with `mini_table`.
+pub unsafe fn serialize_into(
+    msg: RawMessage,
+    mini_table: *const upb_MiniTable,
+    view: impl UnsetRequiredFields,
+    buf: &mut [u8],
+) -> Result<usize, SerializeError> {
+    // SAFETY: the encoder only ever writes initialized bytes, so `buf` stays
+    // initialized.
+    let buf = unsafe { &mut *(buf as *mut [u8] as *mut [MaybeUninit<u8>]) };
+    // SAFETY: `mini_table` is the one associated with `msg`.
+    unsafe { wire::encode_into(msg, mini_table, 0, buf) }
+        .map_err(|status| serialize_error_from_encode_status(status, view))
+}
+
 /// The raw contents of every generated message.
 #[derive(Debug)]
 #[doc(hidden)]
@@ -267,6 +455,15 @@ macro_rules! impl_repeated_base {
             }
         }
         #[inline]
+        unsafe fn repeated_swap_unchecked(f: Mut<Repeated<$t>>, a: usize, b: usize) {
+            // SAFETY: `a` and `b` are in-bounds as promised by the caller.
+            unsafe { swap_repeated_unchecked(f, a, b) }
+        }
+        #[inline]
+        fn repeated_truncate(f: Mut<Repeated<$t>>, len: usize) {
+            truncate_repeated(f, len)
+        }
+        #[inline]
         fn repeated_reserve(mut f: Mut<Repeated<$t>>, additional: usize) {
             // SAFETY:
             // - `upb_Array_Reserve` is unsafe but assumed to be sound when called on a
@@ -303,6 +500,43 @@ macro_rules! impl_repeated_primitives {
                     }
                 }
             }
+
+            unsafe impl ContiguousRepeated for $t {
+                #[inline]
+                fn repeated_data(f: View<Repeated<$t>>) -> *const $t {
+                    // SAFETY: `f.as_raw()` is a valid `upb_Array*` of `$t`.
+                    unsafe { upb_Array_DataPtr(f.as_raw(Private)).cast() }
+                }
+
+                #[inline]
+                fn repeated_data_mut(mut f: Mut<Repeated<$t>>) -> *mut $t {
+                    // SAFETY: `f.as_raw()` is a valid `upb_Array*` of `$t`.
+                    unsafe { upb_Array_MutableDataPtr(f.as_raw(Private)).cast() }
+                }
+
+                fn repeated_extend_from_slice(mut f: Mut<Repeated<$t>>, src: &[$t]) {
+                    if src.is_empty() {
+                        return;
+                    }
+                    let arena = f.raw_arena(Private);
+                    // SAFETY:
+                    // - `upb_Array_Resize` is unsafe but assumed to be always sound to call.
+                    // - After the resize, the array has room for `src.len()` values after
+                    //   the first `len`, and `src` can't overlap the array.
+                    unsafe {
+                        let raw = f.as_raw(Private);
+                        let len = upb_Array_Size(raw);
+                        if !upb_Array_Resize(raw, len + src.len(), arena) {
+                            panic!("upb_Array_Resize failed.");
+                        }
+                        ptr::copy_nonoverlapping(
+                            src.as_ptr(),
+                            upb_Array_MutableDataPtr(raw).cast::<$t>().add(len),
+                            src.len(),
+                        );
+                    }
+                }
+            }
         )*
     }
 }
@@ -399,6 +633,42 @@ pub unsafe fn repeated_message_copy_from<T: ProxiedInRepeated>(
     }
 }
 
+/// Swaps the values at `a` and `b` of `repeated`.
+///
+/// Only the `upb_MessageValue`s are swapped, so messages are moved by pointer
+/// rather than copied.
+///
+/// # Safety
+/// - `a` and `b` must be less than the length of `repeated`.
+pub unsafe fn swap_repeated_unchecked<T: ProxiedInRepeated>(
+    mut repeated: Mut<Repeated<T>>,
+    a: usize,
+    b: usize,
+) {
+    // SAFETY:
+    // - `repeated.as_raw()` is a valid `upb_Array*`.
+    // - `a` and `b` are in-bounds as promised by the caller.
+    unsafe {
+        let raw = repeated.as_raw(Private);
+        let val_a = upb_Array_Get(raw, a);
+        upb_Array_Set(raw, a, upb_Array_Get(raw, b));
+        upb_Array_Set(raw, b, val_a);
+    }
+}
+
+/// Shortens `repeated` to `len` values if it is longer.
+pub fn truncate_repeated<T: ProxiedInRepeated>(mut repeated: Mut<Repeated<T>>, len: usize) {
+    // SAFETY:
+    // - `repeated.as_raw()` is a valid `upb_Array*`.
+    // - Shrinking an array never allocates, so it can't fail.
+    unsafe {
+        let raw = repeated.as_raw(Private);
+        if len < upb_Array_Size(raw) {
+            upb_Array_Resize(raw, len, repeated.raw_arena(Private));
+        }
+    }
+}
+
 /// Cast a `RepeatedView<SomeEnum>` to `RepeatedView<i32>`.
 pub fn cast_enum_repeated_view<E: Enum + ProxiedInRepeated>(
     repeated: RepeatedView<E>,
@@ -662,6 +932,1888 @@ impl UpbTypeConversions for ProtoString {
     }
 }
 
+/// The kernel-specific part of an [`ExtensionId`](crate::ExtensionId): a
+/// function that returns the MiniTable of the extension.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub struct InnerExtension {
+    mini_table: fn() -> *const upb_MiniTableExtension,
+}
+
+impl InnerExtension {
+    /// # Safety
+    /// - `mini_table` must return a pointer to a MiniTable extension that is
+    ///   valid for `'static`.
+    pub const unsafe fn new(mini_table: fn() -> *const upb_MiniTableExtension) -> Self {
+        InnerExtension { mini_table }
+    }
+
+    pub fn mini_table(&self) -> *const upb_MiniTableExtension {
+        (self.mini_table)()
+    }
+
+    /// # Safety
+    /// - `msg` must be a message of the type extended by `self`.
+    pub unsafe fn has(&self, msg: RawMessage) -> bool {
+        let mini_table = self.mini_table();
+        // SAFETY: a MiniTable extension starts with its field, which upb
+        // allows to alias.
+        if unsafe { upb_MiniTableField_IsArray(mini_table.cast()) } {
+            // Repeated extensions have no presence: they are set if they are
+            // not empty.
+            // SAFETY: `msg` is extended by `self`, as promised by the caller.
+            return unsafe { upb_Message_GetExtensionArray(msg, mini_table) }
+                .is_some_and(|raw| unsafe { upb_Array_Size(raw) } != 0);
+        }
+        // SAFETY: `msg` is extended by `self`, as promised by the caller.
+        unsafe { upb_Message_HasExtension(msg, mini_table) }
+    }
+
+    /// # Safety
+    /// - `msg` must be a mutable message of the type extended by `self`.
+    pub unsafe fn clear(&self, msg: RawMessage) {
+        // SAFETY: `msg` is extended by `self`, as promised by the caller.
+        unsafe { upb_Message_ClearExtension(msg, self.mini_table()) }
+    }
+}
+
+/// # Safety
+/// - `msg` must be a message of the type extended by `ext`.
+/// - `ext` must be an extension of type `T`.
+unsafe fn set_extension_value<T: UpbTypeConversions>(
+    msg: MutatorMessageRef<'_>,
+    ext: &InnerExtension,
+    val: T,
+) {
+    let raw_arena = msg.arena().raw();
+    // SAFETY: `raw_arena` is the arena of `msg`, so it outlives the value.
+    let val = unsafe { T::into_message_value_fuse_if_required(raw_arena, val) };
+    // SAFETY:
+    // - `msg` is extended by `ext`, as promised by the caller.
+    // - `val` is a `upb_MessageValue` of the variant for the extension's type.
+    let ok = unsafe {
+        upb_Message_SetExtension(
+            msg.msg(),
+            ext.mini_table(),
+            (&val as *const upb_MessageValue).cast(),
+            raw_arena,
+        )
+    };
+    assert!(ok, "upb_Message_SetExtension failed to allocate");
+}
+
+macro_rules! impl_extension_type_for_scalars {
+    ($($t:ty, $getter:ident;)*) => {
+        $(
+            impl ExtensionType for $t {
+                type Default = $t;
+
+                fn default_view(_private: Private, default: $t) -> $t {
+                    default
+                }
+
+                unsafe fn get_extension<'msg>(
+                    _private: Private,
+                    msg: RawMessage,
+                    ext: &InnerExtension,
+                    default: $t,
+                ) -> View<'msg, $t> {
+                    // SAFETY: `msg` is extended by `ext`, as promised by the caller.
+                    unsafe { $getter(msg, ext.mini_table(), default) }
+                }
+
+                unsafe fn set_extension(
+                    _private: Private,
+                    msg: MutatorMessageRef<'_>,
+                    ext: &InnerExtension,
+                    val: impl IntoProxied<$t>,
+                ) {
+                    // SAFETY: `msg` is extended by `ext`, as promised by the caller.
+                    unsafe { set_extension_value(msg, ext, val.into_proxied(Private)) }
+                }
+            }
+        )*
+    };
+}
+
+impl_extension_type_for_scalars!(
+    f32, upb_Message_GetExtensionFloat;
+    f64, upb_Message_GetExtensionDouble;
+    i32, upb_Message_GetExtensionInt32;
+    u32, upb_Message_GetExtensionUInt32;
+    i64, upb_Message_GetExtensionInt64;
+    u64, upb_Message_GetExtensionUInt64;
+    bool, upb_Message_GetExtensionBool;
+);
+
+impl ExtensionType for ProtoBytes {
+    type Default = &'static [u8];
+
+    fn default_view(_private: Private, default: &'static [u8]) -> &'static [u8] {
+        default
+    }
+
+    unsafe fn get_extension<'msg>(
+        _private: Private,
+        msg: RawMessage,
+        ext: &InnerExtension,
+        default: &'static [u8],
+    ) -> &'msg [u8] {
+        // SAFETY:
+        // - `msg` is extended by `ext`, as promised by the caller.
+        // - The returned bytes are either `default` or owned by the arena of
+        //   `msg`, which is valid for `'msg`.
+        unsafe { upb_Message_GetExtensionString(msg, ext.mini_table(), default.into()).as_ref() }
+    }
+
+    unsafe fn set_extension(
+        _private: Private,
+        msg: MutatorMessageRef<'_>,
+        ext: &InnerExtension,
+        val: impl IntoProxied<ProtoBytes>,
+    ) {
+        // SAFETY: `msg` is extended by `ext`, as promised by the caller.
+        unsafe { set_extension_value(msg, ext, val.into_proxied(Private)) }
+    }
+}
+
+impl ExtensionType for ProtoString {
+    type Default = &'static [u8];
+
+    fn default_view(_private: Private, default: &'static [u8]) -> &'static ProtoStr {
+        // SAFETY: default values of `string` extensions are UTF-8.
+        unsafe { ProtoStr::from_utf8_unchecked(default) }
+    }
+
+    unsafe fn get_extension<'msg>(
+        _private: Private,
+        msg: RawMessage,
+        ext: &InnerExtension,
+        default: &'static [u8],
+    ) -> &'msg ProtoStr {
+        // SAFETY: `msg` is extended by `ext`, as promised by the caller.
+        let bytes = unsafe { ProtoBytes::get_extension(Private, msg, ext, default) };
+        // SAFETY: `string` extensions are UTF-8, as with `string` fields.
+        unsafe { ProtoStr::from_utf8_unchecked(bytes) }
+    }
+
+    unsafe fn set_extension(
+        _private: Private,
+        msg: MutatorMessageRef<'_>,
+        ext: &InnerExtension,
+        val: impl IntoProxied<ProtoString>,
+    ) {
+        // SAFETY: `msg` is extended by `ext`, as promised by the caller.
+        unsafe { set_extension_value(msg, ext, val.into_proxied(Private)) }
+    }
+}
+
+/// Returns the message that a message extension of `msg` holds, or `None` if
+/// the extension is not set.
+///
+/// # Safety
+/// - `msg` must be a message of the type extended by `ext`.
+/// - `ext` must be a singular message extension.
+/// - `msg` must be valid for `'msg`.
+#[doc(hidden)]
+pub unsafe fn get_message_extension(msg: RawMessage, ext: &InnerExtension) -> Option<RawMessage> {
+    // SAFETY: `msg` is extended by `ext`, as promised by the caller.
+    unsafe { upb_Message_GetExtensionMessage(msg, ext.mini_table(), None) }
+}
+
+/// Sets a message extension of `msg` to `val`, fusing the arena of `val` into
+/// the arena of `msg`.
+///
+/// # Safety
+/// - `msg` must be a message of the type extended by `ext`.
+/// - `ext` must be a singular message extension of type `T`.
+#[doc(hidden)]
+pub unsafe fn set_message_extension<T: UpbTypeConversions>(
+    msg: MutatorMessageRef<'_>,
+    ext: &InnerExtension,
+    val: T,
+) {
+    // SAFETY: `msg` is extended by `ext`, as promised by the caller.
+    unsafe { set_extension_value(msg, ext, val) }
+}
+
+impl<T: ProxiedInRepeated + 'static> ExtensionType for Repeated<T> {
+    type Default = ();
+
+    fn default_view(_private: Private, _default: ()) -> RepeatedView<'static, T> {
+        empty_array()
+    }
+
+    unsafe fn get_extension<'msg>(
+        _private: Private,
+        msg: RawMessage,
+        ext: &InnerExtension,
+        _default: (),
+    ) -> RepeatedView<'msg, T> {
+        // SAFETY: `msg` is extended by `ext`, as promised by the caller.
+        let raw = unsafe { upb_Message_GetExtensionArray(msg, ext.mini_table()) };
+        // SAFETY: the array is owned by the arena of `msg`, which is valid for
+        // `'msg`, and has elements of type `T`.
+        raw.map_or_else(empty_array, |raw| unsafe { RepeatedView::from_raw(Private, raw) })
+    }
+
+    unsafe fn set_extension(
+        _private: Private,
+        msg: MutatorMessageRef<'_>,
+        ext: &InnerExtension,
+        val: impl IntoProxied<Repeated<T>>,
+    ) {
+        let val = val.into_proxied(Private);
+        let inner = val.inner(Private);
+        msg.arena().fuse(inner.arena());
+        let val = upb_MessageValue { array_val: Some(inner.raw()) };
+        // SAFETY:
+        // - `msg` is extended by `ext`, as promised by the caller.
+        // - `val` is an array of the extension's type that lives as long as
+        //   `msg`, since the arenas are fused.
+        let ok = unsafe {
+            upb_Message_SetExtension(
+                msg.msg(),
+                ext.mini_table(),
+                (&val as *const upb_MessageValue).cast(),
+                msg.arena().raw(),
+            )
+        };
+        assert!(ok, "upb_Message_SetExtension failed to allocate");
+    }
+}
+
+/// The kernel-specific part of an
+/// [`ExtensionRegistry`](crate::ExtensionRegistry).
+#[doc(hidden)]
+pub struct InnerExtensionRegistry {
+    // Owns the memory of `raw`.
+    _arena: Arena,
+    raw: RawExtensionRegistry,
+}
+
+impl InnerExtensionRegistry {
+    pub fn new() -> Self {
+        let arena = Arena::new();
+        // SAFETY: `arena` is valid and is owned by the registry.
+        let raw = unsafe { upb_ExtensionRegistry_New(arena.raw()) }
+            .expect("upb_ExtensionRegistry_New failed to allocate");
+        InnerExtensionRegistry { _arena: arena, raw }
+    }
+
+    pub fn add(&mut self, ext: &InnerExtension) {
+        // SAFETY: `self.raw` is valid and the MiniTable of `ext` is `'static`.
+        let status = unsafe { upb_ExtensionRegistry_Add(self.raw, ext.mini_table()) };
+        match status {
+            ExtensionRegistryStatus::Ok | ExtensionRegistryStatus::DuplicateEntry => {}
+            ExtensionRegistryStatus::OutOfMemory => {
+                panic!("upb_ExtensionRegistry_Add failed to allocate")
+            }
+        }
+    }
+}
+
+impl Default for InnerExtensionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// SAFETY:
+// - The registry is only mutated through `&mut self`.
+// - Parsing only reads the registry.
+unsafe impl Send for InnerExtensionRegistry {}
+unsafe impl Sync for InnerExtensionRegistry {}
+
+/// The kernel-specific part of [`UnknownFields`], which is the wire format
+/// data that upb retained for the unknown fields.
+#[doc(hidden)]
+#[derive(Clone, Copy)]
+pub struct InnerUnknownFields<'msg> {
+    data: &'msg [u8],
+}
+
+impl<'msg> InnerUnknownFields<'msg> {
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn iter(&self) -> InnerUnknownFieldsIter<'msg> {
+        InnerUnknownFieldsIter { data: self.data }
+    }
+}
+
+#[doc(hidden)]
+pub struct InnerUnknownFieldsIter<'msg> {
+    data: &'msg [u8],
+}
+
+impl<'msg> Iterator for InnerUnknownFieldsIter<'msg> {
+    type Item = (u32, UnknownValue<'msg>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let field = read_unknown_field(&mut self.data);
+        if field.is_none() {
+            // The data is exhausted or malformed; stop for good in both cases.
+            self.data = &[];
+        }
+        field
+    }
+}
+
+fn read_varint(data: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for (i, &byte) in data.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            *data = &data[i + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn read_bytes<'msg>(data: &mut &'msg [u8], len: usize) -> Option<&'msg [u8]> {
+    if data.len() < len {
+        return None;
+    }
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    Some(bytes)
+}
+
+/// Reads a tag and returns its field number and wire type.
+fn read_tag(data: &mut &[u8]) -> Option<(u32, u64)> {
+    let tag = read_varint(data)?;
+    let number = u32::try_from(tag >> 3).ok().filter(|&number| number != 0)?;
+    Some((number, tag & 7))
+}
+
+/// Reads the next field from `data`, or returns None if `data` is empty or
+/// malformed.
+fn read_unknown_field<'msg>(data: &mut &'msg [u8]) -> Option<(u32, UnknownValue<'msg>)> {
+    let (number, wire_type) = read_tag(data)?;
+    let value = match wire_type {
+        0 => UnknownValue::Varint(read_varint(data)?),
+        1 => UnknownValue::Fixed64(u64::from_le_bytes(read_bytes(data, 8)?.try_into().ok()?)),
+        2 => {
+            let len = usize::try_from(read_varint(data)?).ok()?;
+            UnknownValue::LengthDelimited(read_bytes(data, len)?)
+        }
+        3 => {
+            let body = *data;
+            loop {
+                let mut rest = *data;
+                match read_tag(&mut rest)? {
+                    (end_number, 4) if end_number == number => {
+                        let body = &body[..body.len() - data.len()];
+                        *data = rest;
+                        break UnknownValue::Group(UnknownFields::new(
+                            Private,
+                            InnerUnknownFields { data: body },
+                        ));
+                    }
+                    (_, 4) => return None,
+                    _ => {
+                        read_unknown_field(data)?;
+                    }
+                }
+            }
+        }
+        5 => UnknownValue::Fixed32(u32::from_le_bytes(read_bytes(data, 4)?.try_into().ok()?)),
+        _ => return None,
+    };
+    Some((number, value))
+}
+
+/// Returns the unknown fields that are encoded in `data`, or None if `data`
+/// isn't entirely valid wire format.
+pub fn parse_unknown_fields(data: &[u8]) -> Option<UnknownFields<'_>> {
+    let mut rest = data;
+    while !rest.is_empty() {
+        read_unknown_field(&mut rest)?;
+    }
+    Some(UnknownFields::new(Private, InnerUnknownFields { data }))
+}
+
+/// # Safety
+/// - `msg` must be valid for `'msg` and must not be mutated during `'msg`.
+pub unsafe fn unknown_fields<'msg>(msg: RawMessage) -> UnknownFields<'msg> {
+    let mut len = 0;
+    // SAFETY: `msg` is valid, as promised by the caller.
+    let ptr = unsafe { upb_Message_GetUnknown(msg, &mut len) };
+    let data = if len == 0 {
+        &[]
+    } else {
+        // SAFETY: upb returned `len` bytes of unknown data, which live as long
+        // as `msg` is not mutated.
+        unsafe { slice::from_raw_parts(ptr, len) }
+    };
+    UnknownFields::new(Private, InnerUnknownFields { data })
+}
+
+/// Removes the unknown fields of `msg`, but not those of its submessages.
+///
+/// # Safety
+/// - `msg` must be a valid mutable message.
+pub unsafe fn clear_unknown_fields(msg: RawMessage) {
+    // SAFETY: `msg` is valid and mutable, as promised by the caller.
+    unsafe { _upb_Message_DiscardUnknown_shallow(msg) }
+}
+
+/// Removes the unknown fields of `msg` and of all of its submessages.
+///
+/// # Safety
+/// - `msg` must be a valid mutable message.
+/// - `mini_table` must be the MiniTable associated with `msg`.
+pub unsafe fn discard_unknown_fields(msg: RawMessage, mini_table: *const upb_MiniTable) {
+    // SAFETY: `msg` is valid and mutable, as promised by the caller.
+    unsafe { _upb_Message_DiscardUnknown_shallow(msg) };
+
+    // SAFETY: `mini_table` is valid, as promised by the caller.
+    let field_count = unsafe { upb_MiniTable_FieldCount(mini_table) };
+    for i in 0..field_count as u32 {
+        // SAFETY: `i` is a valid field index of `mini_table`, and every field
+        // that is read is read as the type that the MiniTable gives it. The
+        // submessages of a mutable message are mutable too.
+        unsafe {
+            let f = upb_MiniTable_GetFieldByIndex(mini_table, i);
+            if upb_MiniTableField_CType(f) != CType::Message {
+                continue;
+            }
+            if upb_MiniTableField_IsMap(f) {
+                let entry = upb_MiniTable_MapEntrySubMessage(mini_table, f);
+                let value_field = upb_MiniTable_MapValue(entry);
+                if upb_MiniTableField_CType(value_field) != CType::Message {
+                    continue;
+                }
+                let value_mini_table = upb_MiniTable_SubMessage(entry, value_field);
+                let Some(map) = upb_Message_GetMap(msg, f) else { continue };
+                let mut iter = RawMapIter::new(map);
+                while let Some((_, value)) = iter.next_unchecked() {
+                    if let Some(sub) = value.msg_val {
+                        discard_unknown_fields(sub, value_mini_table);
+                    }
+                }
+            } else if upb_MiniTableField_IsArray(f) {
+                let sub_mini_table = upb_MiniTable_SubMessage(mini_table, f);
+                let Some(array) = upb_Message_GetArray(msg, f) else { continue };
+                for j in 0..upb_Array_Size(array) {
+                    if let Some(sub) = upb_Array_Get(array, j).msg_val {
+                        discard_unknown_fields(sub, sub_mini_table);
+                    }
+                }
+            } else if let Some(sub) = upb_Message_GetMessage(msg, f) {
+                discard_unknown_fields(sub, upb_MiniTable_SubMessage(mini_table, f));
+            }
+        }
+    }
+
+    let mut ext: *const upb_MiniTableExtension = ptr::null();
+    let mut iter = 0;
+    // SAFETY: `msg` is valid, and every extension that it yields is read as
+    // the type that its MiniTable gives it. A `upb_MiniTableExtension` starts
+    // with the `upb_MiniTableField` that describes it.
+    unsafe {
+        while upb_Message_NextExtensionReverse(msg, &mut ext, &mut iter) {
+            let f = ext.cast::<upb_MiniTableField>();
+            if upb_MiniTableField_CType(f) != CType::Message {
+                continue;
+            }
+            let sub_mini_table = upb_MiniTableExtension_GetSubMessage(ext);
+            if upb_MiniTableField_IsArray(f) {
+                let Some(array) = upb_Message_GetExtensionArray(msg, ext) else { continue };
+                for j in 0..upb_Array_Size(array) {
+                    if let Some(sub) = upb_Array_Get(array, j).msg_val {
+                        discard_unknown_fields(sub, sub_mini_table);
+                    }
+                }
+            } else if let Some(sub) = upb_Message_GetExtensionMessage(msg, ext, None) {
+                discard_unknown_fields(sub, sub_mini_table);
+            }
+        }
+    }
+}
+
+/// The descriptor of a `.proto` file that is compiled into the binary.
+///
+/// It is loaded into the global `upb_DefPool` the first time that one of its
+/// descriptors is requested, together with the files that it imports.
+#[doc(hidden)]
+pub struct FileDescriptorInit {
+    name: &'static str,
+    serialized: &'static [u8],
+    deps: &'static [&'static FileDescriptorInit],
+    layout: fn() -> *const upb_MiniTableFile,
+}
+
+impl FileDescriptorInit {
+    /// # Safety
+    /// - `serialized` must be the serialized `FileDescriptorProto` of the file
+    ///   called `name`, and `deps` must hold the files that it imports.
+    /// - `layout` must return the MiniTables that were generated for the file.
+    pub const unsafe fn new(
+        name: &'static str,
+        serialized: &'static [u8],
+        deps: &'static [&'static FileDescriptorInit],
+        layout: fn() -> *const upb_MiniTableFile,
+    ) -> Self {
+        FileDescriptorInit { name, serialized, deps, layout }
+    }
+}
+
+/// The pool that holds the descriptors of all generated files. It is never
+/// freed, so the defs that it owns are `'static`.
+struct GlobalDefPool(RawDefPool);
+
+// SAFETY: the pool is only accessed while holding the lock around it.
+unsafe impl Send for GlobalDefPool {}
+
+/// Calls `f` with the global pool after making sure that `file` is loaded.
+///
+/// Lookups in a `upb_DefPool` race with loading files into it, so `f` is
+/// called while holding the lock of the pool.
+fn with_loaded_file<R>(file: &'static FileDescriptorInit, f: impl FnOnce(RawDefPool) -> R) -> R {
+    let pool = global_def_pool().lock().unwrap_or_else(PoisonError::into_inner);
+    load_file(pool.0, file);
+    f(pool.0)
+}
+
+/// The pool that the descriptors of generated messages are loaded into.
+fn global_def_pool() -> &'static Mutex<GlobalDefPool> {
+    static GLOBAL_DEF_POOL: OnceLock<Mutex<GlobalDefPool>> = OnceLock::new();
+    GLOBAL_DEF_POOL.get_or_init(|| {
+        // SAFETY: always safe to call.
+        let raw = unsafe { upb_DefPool_New() }.expect("upb_DefPool_New failed to allocate");
+        Mutex::new(GlobalDefPool(raw))
+    })
+}
+
+fn load_file(pool: RawDefPool, file: &'static FileDescriptorInit) {
+    // SAFETY: `pool` is valid and `file.name` is valid for `len` bytes.
+    let loaded =
+        unsafe { upb_DefPool_FindFileByNameWithSize(pool, file.name.as_ptr(), file.name.len()) };
+    if !loaded.is_null() {
+        return;
+    }
+    for dep in file.deps {
+        load_file(pool, dep);
+    }
+    let filename = CString::new(file.name).expect("file names never contain NUL");
+    // The imports were loaded above, which lets them be deduplicated by name.
+    let no_deps = [ptr::null()];
+    let init = _upb_DefPool_Init {
+        deps: no_deps.as_ptr(),
+        layout: (file.layout)(),
+        filename: filename.as_ptr(),
+        descriptor: file.serialized.into(),
+    };
+    // SAFETY:
+    // - `pool` and `init` are valid.
+    // - The serialized descriptor is `'static`, so it outlives the pool.
+    let ok = unsafe { _upb_DefPool_LoadDefInit(pool, &init) };
+    assert!(ok, "failed to load the compiled-in descriptor of {}", file.name);
+}
+
+/// Returns the descriptor of the message called `full_name`, which must be
+/// defined in `file`.
+pub fn message_descriptor(file: &'static FileDescriptorInit, full_name: &str) -> MessageDescriptor {
+    // SAFETY: `pool` is valid and `full_name` is valid for `len` bytes.
+    let raw = with_loaded_file(file, |pool| unsafe {
+        upb_DefPool_FindMessageByNameWithSize(pool, full_name.as_ptr(), full_name.len())
+    });
+    let inner = InnerMessageDescriptor::from_raw(raw)
+        .unwrap_or_else(|| panic!("{full_name} is not defined in {}", file.name));
+    MessageDescriptor::new(Private, inner)
+}
+
+/// Returns the descriptor of the enum called `full_name`, which must be
+/// defined in `file`.
+pub fn enum_descriptor(file: &'static FileDescriptorInit, full_name: &str) -> EnumDescriptor {
+    let name = CString::new(full_name).expect("enum names never contain NUL");
+    // SAFETY: `pool` is valid and `name` is NUL-terminated.
+    let raw =
+        with_loaded_file(file, |pool| unsafe { upb_DefPool_FindEnumByName(pool, name.as_ptr()) });
+    let inner = InnerEnumDescriptor::from_raw(raw)
+        .unwrap_or_else(|| panic!("{full_name} is not defined in {}", file.name));
+    EnumDescriptor::new(Private, inner)
+}
+
+/// The kernel-specific part of a
+/// [`DescriptorPool`](crate::reflect::DescriptorPool): a `upb_DefPool` that
+/// holds files which were built at runtime, and the arena that holds their
+/// serialized descriptors.
+#[doc(hidden)]
+#[derive(Clone, Copy)]
+pub struct InnerDescriptorPool {
+    raw: RawDefPool,
+    arena: RawArena,
+}
+
+// SAFETY: pools are only written to by `build_file`, which is called before
+// the pool is shared.
+unsafe impl Send for InnerDescriptorPool {}
+unsafe impl Sync for InnerDescriptorPool {}
+
+impl InnerDescriptorPool {
+    #[allow(clippy::new_without_default)] // Pools are only freed explicitly.
+    pub fn new() -> Self {
+        // SAFETY: always safe to call.
+        let raw = unsafe { upb_DefPool_New() }.expect("upb_DefPool_New failed to allocate");
+        // The arena lives as long as the pool, and is freed by `free`.
+        let arena = ManuallyDrop::new(Arena::new());
+        InnerDescriptorPool { raw, arena: arena.raw() }
+    }
+
+    /// Builds the file from its serialized `FileDescriptorProto`, returning a
+    /// description of the problem on failure. upb builds the MiniTables of
+    /// the messages in the file from their descriptors.
+    ///
+    /// # Safety
+    /// - The imports of the file must already have been built.
+    /// - No other thread may be using the pool.
+    pub unsafe fn build_file(
+        self,
+        name: &str,
+        serialized: &[u8],
+    ) -> Result<InnerFileDescriptor, String> {
+        let filename =
+            CString::new(name).map_err(|_| "the file name contains a NUL byte".to_string())?;
+        // SAFETY: `self.arena` is valid, and this doesn't free it.
+        let arena = ManuallyDrop::new(unsafe { Arena::from_raw(self.arena) });
+        // upb aliases the descriptor, so it must live as long as the pool.
+        let serialized = arena.copy_slice_in(serialized).expect("alloc should never fail");
+        // The imports were built by the caller.
+        let no_deps = [ptr::null()];
+        let init = _upb_DefPool_Init {
+            deps: no_deps.as_ptr(),
+            layout: ptr::null(),
+            filename: filename.as_ptr(),
+            descriptor: serialized.into(),
+        };
+        // SAFETY:
+        // - The pool and `init` are valid, and no other thread uses the pool.
+        // - The serialized descriptor is on the arena of the pool.
+        if !unsafe { _upb_DefPool_LoadDefInit(self.raw, &init) } {
+            return Err(
+                "the file is malformed or conflicts with a file that was already built".to_string()
+            );
+        }
+        Ok(self.find_file(name).expect("the file was just built"))
+    }
+
+    /// Frees the pool.
+    ///
+    /// # Safety
+    /// - No descriptors of the pool may be used afterwards.
+    pub unsafe fn free(self) {
+        // SAFETY: the pool and its arena are valid and unused, as promised by
+        // the caller.
+        unsafe {
+            upb_DefPool_Free(self.raw);
+            drop(Arena::from_raw(self.arena));
+        }
+    }
+
+    // SAFETY (for the lookups below): the pool is valid and no longer written
+    // to, and names are valid for their length or NUL-terminated.
+    pub fn find_file(self, name: &str) -> Option<InnerFileDescriptor> {
+        InnerFileDescriptor::from_raw(unsafe {
+            upb_DefPool_FindFileByNameWithSize(self.raw, name.as_ptr(), name.len())
+        })
+    }
+    pub fn find_message(self, full_name: &str) -> Option<InnerMessageDescriptor> {
+        InnerMessageDescriptor::from_raw(unsafe {
+            upb_DefPool_FindMessageByNameWithSize(self.raw, full_name.as_ptr(), full_name.len())
+        })
+    }
+    pub fn find_enum(self, full_name: &str) -> Option<InnerEnumDescriptor> {
+        let name = CString::new(full_name).ok()?;
+        InnerEnumDescriptor::from_raw(unsafe {
+            upb_DefPool_FindEnumByName(self.raw, name.as_ptr())
+        })
+    }
+}
+
+/// Converts a string that is owned by a def. It is only `'static` if the pool
+/// is the global one; the public descriptors, which keep runtime-built pools
+/// alive, only lend it out for as long as they are borrowed.
+///
+/// # Safety
+/// - `s` must be a NUL-terminated string that lives as long as the pool.
+unsafe fn def_str(s: *const c_char) -> &'static str {
+    // SAFETY: `s` is NUL-terminated and lives as long as the pool, as promised
+    // by the caller.
+    unsafe { CStr::from_ptr(s) }.to_str().expect("descriptor names are valid UTF-8")
+}
+
+/// Defines a kernel-specific descriptor type, which is a pointer to a def.
+macro_rules! define_inner_descriptor {
+    ($(#[$attr:meta])* $name:ident, $def:ident) => {
+        $(#[$attr])*
+        #[doc(hidden)]
+        #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $name {
+            raw: NonNull<$def>,
+        }
+
+        // SAFETY: defs are immutable once they are built, and the pool that
+        // owns them outlives the public descriptors that hold them.
+        unsafe impl Send for $name {}
+        unsafe impl Sync for $name {}
+
+        impl $name {
+            fn from_raw(raw: *const $def) -> Option<Self> {
+                NonNull::new(raw.cast_mut()).map(|raw| $name { raw })
+            }
+
+            pub fn raw(self) -> *const $def {
+                self.raw.as_ptr()
+            }
+        }
+    };
+}
+
+define_inner_descriptor!(
+    /// The kernel-specific part of a
+    /// [`FileDescriptor`](crate::reflect::FileDescriptor).
+    InnerFileDescriptor,
+    upb_FileDef
+);
+define_inner_descriptor!(
+    /// The kernel-specific part of a
+    /// [`MessageDescriptor`](crate::reflect::MessageDescriptor).
+    InnerMessageDescriptor,
+    upb_MessageDef
+);
+define_inner_descriptor!(
+    /// The kernel-specific part of a
+    /// [`FieldDescriptor`](crate::reflect::FieldDescriptor).
+    InnerFieldDescriptor,
+    upb_FieldDef
+);
+define_inner_descriptor!(
+    /// The kernel-specific part of a
+    /// [`OneofDescriptor`](crate::reflect::OneofDescriptor).
+    InnerOneofDescriptor,
+    upb_OneofDef
+);
+define_inner_descriptor!(
+    /// The kernel-specific part of an
+    /// [`EnumDescriptor`](crate::reflect::EnumDescriptor).
+    InnerEnumDescriptor,
+    upb_EnumDef
+);
+define_inner_descriptor!(
+    /// The kernel-specific part of an
+    /// [`EnumValueDescriptor`](crate::reflect::EnumValueDescriptor).
+    InnerEnumValueDescriptor,
+    upb_EnumValueDef
+);
+
+// SAFETY (for all of the accessors below): `self.raw()` is a valid def that
+// lives as long as the pool, and indices are checked by the callers in
+// `reflect.rs` to be in bounds. Defs that are never null are unwrapped.
+impl InnerFileDescriptor {
+    pub fn name(self) -> &'static str {
+        unsafe { def_str(upb_FileDef_Name(self.raw())) }
+    }
+    pub fn package(self) -> &'static str {
+        unsafe { def_str(upb_FileDef_Package(self.raw())) }
+    }
+    pub fn dependency_count(self) -> usize {
+        unsafe { upb_FileDef_DependencyCount(self.raw()) as usize }
+    }
+    pub fn dependency(self, i: usize) -> InnerFileDescriptor {
+        Self::from_raw(unsafe { upb_FileDef_Dependency(self.raw(), i as i32) }).unwrap()
+    }
+    pub fn message_count(self) -> usize {
+        unsafe { upb_FileDef_TopLevelMessageCount(self.raw()) as usize }
+    }
+    pub fn message(self, i: usize) -> InnerMessageDescriptor {
+        let raw = unsafe { upb_FileDef_TopLevelMessage(self.raw(), i as i32) };
+        InnerMessageDescriptor::from_raw(raw).unwrap()
+    }
+    pub fn enum_count(self) -> usize {
+        unsafe { upb_FileDef_TopLevelEnumCount(self.raw()) as usize }
+    }
+    pub fn enum_type(self, i: usize) -> InnerEnumDescriptor {
+        InnerEnumDescriptor::from_raw(unsafe { upb_FileDef_TopLevelEnum(self.raw(), i as i32) })
+            .unwrap()
+    }
+}
+
+impl InnerMessageDescriptor {
+    pub fn name(self) -> &'static str {
+        unsafe { def_str(upb_MessageDef_Name(self.raw())) }
+    }
+    pub fn full_name(self) -> &'static str {
+        unsafe { def_str(upb_MessageDef_FullName(self.raw())) }
+    }
+    pub fn file(self) -> InnerFileDescriptor {
+        InnerFileDescriptor::from_raw(unsafe { upb_MessageDef_File(self.raw()) }).unwrap()
+    }
+    pub fn containing_type(self) -> Option<InnerMessageDescriptor> {
+        Self::from_raw(unsafe { upb_MessageDef_ContainingType(self.raw()) })
+    }
+    pub fn is_map_entry(self) -> bool {
+        unsafe { upb_MessageDef_IsMapEntry(self.raw()) }
+    }
+    pub fn field_count(self) -> usize {
+        unsafe { upb_MessageDef_FieldCount(self.raw()) as usize }
+    }
+    pub fn field(self, i: usize) -> InnerFieldDescriptor {
+        InnerFieldDescriptor::from_raw(unsafe { upb_MessageDef_Field(self.raw(), i as i32) })
+            .unwrap()
+    }
+    pub fn field_by_number(self, number: u32) -> Option<InnerFieldDescriptor> {
+        InnerFieldDescriptor::from_raw(unsafe {
+            upb_MessageDef_FindFieldByNumber(self.raw(), number)
+        })
+    }
+    pub fn field_by_name(self, name: &str) -> Option<InnerFieldDescriptor> {
+        InnerFieldDescriptor::from_raw(unsafe {
+            upb_MessageDef_FindFieldByNameWithSize(self.raw(), name.as_ptr(), name.len())
+        })
+    }
+    pub fn oneof_count(self) -> usize {
+        unsafe { upb_MessageDef_RealOneofCount(self.raw()) as usize }
+    }
+    pub fn oneof(self, i: usize) -> InnerOneofDescriptor {
+        InnerOneofDescriptor::from_raw(unsafe { upb_MessageDef_Oneof(self.raw(), i as i32) })
+            .unwrap()
+    }
+    pub fn nested_message_count(self) -> usize {
+        unsafe { upb_MessageDef_NestedMessageCount(self.raw()) as usize }
+    }
+    pub fn nested_message(self, i: usize) -> InnerMessageDescriptor {
+        Self::from_raw(unsafe { upb_MessageDef_NestedMessage(self.raw(), i as i32) }).unwrap()
+    }
+    pub fn nested_enum_count(self) -> usize {
+        unsafe { upb_MessageDef_NestedEnumCount(self.raw()) as usize }
+    }
+    pub fn nested_enum(self, i: usize) -> InnerEnumDescriptor {
+        InnerEnumDescriptor::from_raw(unsafe { upb_MessageDef_NestedEnum(self.raw(), i as i32) })
+            .unwrap()
+    }
+}
+
+impl InnerFieldDescriptor {
+    pub fn name(self) -> &'static str {
+        unsafe { def_str(upb_FieldDef_Name(self.raw())) }
+    }
+    pub fn full_name(self) -> &'static str {
+        unsafe { def_str(upb_FieldDef_FullName(self.raw())) }
+    }
+    pub fn json_name(self) -> &'static str {
+        unsafe { def_str(upb_FieldDef_JsonName(self.raw())) }
+    }
+    pub fn number(self) -> u32 {
+        unsafe { upb_FieldDef_Number(self.raw()) }
+    }
+    pub fn index(self) -> usize {
+        unsafe { upb_FieldDef_Index(self.raw()) as usize }
+    }
+    pub fn field_type(self) -> i32 {
+        unsafe { upb_FieldDef_Type(self.raw()) }
+    }
+    pub fn is_repeated(self) -> bool {
+        unsafe { upb_FieldDef_IsRepeated(self.raw()) }
+    }
+    pub fn is_map(self) -> bool {
+        unsafe { upb_FieldDef_IsMap(self.raw()) }
+    }
+    pub fn has_presence(self) -> bool {
+        unsafe { upb_FieldDef_HasPresence(self.raw()) }
+    }
+    pub fn is_required(self) -> bool {
+        unsafe { upb_FieldDef_IsRequired(self.raw()) }
+    }
+    pub fn is_extension(self) -> bool {
+        unsafe { upb_FieldDef_IsExtension(self.raw()) }
+    }
+    pub fn validates_utf8(self) -> bool {
+        unsafe { _upb_FieldDef_ValidateUtf8(self.raw()) }
+    }
+    pub fn containing_type(self) -> InnerMessageDescriptor {
+        InnerMessageDescriptor::from_raw(unsafe { upb_FieldDef_ContainingType(self.raw()) })
+            .unwrap()
+    }
+    pub fn containing_oneof(self) -> Option<InnerOneofDescriptor> {
+        InnerOneofDescriptor::from_raw(unsafe { upb_FieldDef_RealContainingOneof(self.raw()) })
+    }
+    pub fn message_type(self) -> Option<InnerMessageDescriptor> {
+        InnerMessageDescriptor::from_raw(unsafe { upb_FieldDef_MessageSubDef(self.raw()) })
+    }
+    pub fn enum_type(self) -> Option<InnerEnumDescriptor> {
+        InnerEnumDescriptor::from_raw(unsafe { upb_FieldDef_EnumSubDef(self.raw()) })
+    }
+}
+
+impl InnerOneofDescriptor {
+    pub fn name(self) -> &'static str {
+        unsafe { def_str(upb_OneofDef_Name(self.raw())) }
+    }
+    pub fn full_name(self) -> &'static str {
+        unsafe { def_str(upb_OneofDef_FullName(self.raw())) }
+    }
+    pub fn containing_type(self) -> InnerMessageDescriptor {
+        InnerMessageDescriptor::from_raw(unsafe { upb_OneofDef_ContainingType(self.raw()) })
+            .unwrap()
+    }
+    pub fn field_count(self) -> usize {
+        unsafe { upb_OneofDef_FieldCount(self.raw()) as usize }
+    }
+    pub fn field(self, i: usize) -> InnerFieldDescriptor {
+        InnerFieldDescriptor::from_raw(unsafe { upb_OneofDef_Field(self.raw(), i as i32) }).unwrap()
+    }
+}
+
+impl InnerEnumDescriptor {
+    pub fn name(self) -> &'static str {
+        unsafe { def_str(upb_EnumDef_Name(self.raw())) }
+    }
+    pub fn full_name(self) -> &'static str {
+        unsafe { def_str(upb_EnumDef_FullName(self.raw())) }
+    }
+    pub fn file(self) -> InnerFileDescriptor {
+        InnerFileDescriptor::from_raw(unsafe { upb_EnumDef_File(self.raw()) }).unwrap()
+    }
+    pub fn containing_type(self) -> Option<InnerMessageDescriptor> {
+        InnerMessageDescriptor::from_raw(unsafe { upb_EnumDef_ContainingType(self.raw()) })
+    }
+    pub fn is_closed(self) -> bool {
+        unsafe { upb_EnumDef_IsClosed(self.raw()) }
+    }
+    pub fn value_count(self) -> usize {
+        unsafe { upb_EnumDef_ValueCount(self.raw()) as usize }
+    }
+    pub fn value(self, i: usize) -> InnerEnumValueDescriptor {
+        InnerEnumValueDescriptor::from_raw(unsafe { upb_EnumDef_Value(self.raw(), i as i32) })
+            .unwrap()
+    }
+    pub fn value_by_number(self, number: i32) -> Option<InnerEnumValueDescriptor> {
+        InnerEnumValueDescriptor::from_raw(unsafe {
+            upb_EnumDef_FindValueByNumber(self.raw(), number)
+        })
+    }
+}
+
+impl InnerEnumValueDescriptor {
+    pub fn name(self) -> &'static str {
+        unsafe { def_str(upb_EnumValueDef_Name(self.raw())) }
+    }
+    pub fn full_name(self) -> &'static str {
+        unsafe { def_str(upb_EnumValueDef_FullName(self.raw())) }
+    }
+    pub fn number(self) -> i32 {
+        unsafe { upb_EnumValueDef_Number(self.raw()) }
+    }
+    pub fn enum_type(self) -> InnerEnumDescriptor {
+        InnerEnumDescriptor::from_raw(unsafe { upb_EnumValueDef_Enum(self.raw()) }).unwrap()
+    }
+}
+
+fn field_mini_table(f: &FieldDescriptor) -> *const upb_MiniTableField {
+    // SAFETY: the def is valid and was built with a MiniTable.
+    unsafe { upb_FieldDef_MiniTable(f.inner(Private).raw()) }
+}
+
+fn message_mini_table(m: &MessageDescriptor) -> *const upb_MiniTable {
+    // SAFETY: the def is valid and was built with a MiniTable.
+    unsafe { upb_MessageDef_MiniTable(m.inner(Private).raw()) }
+}
+
+/// Converts a value that is stored in `f`, or in an element or map entry of
+/// it, to a `ReflectValueRef`. Unset messages are `None`, and are read as
+/// empty messages.
+///
+/// # Safety
+/// - `val` must hold the type of `f`, and any data that it points to must
+///   outlive `'msg`.
+unsafe fn reflect_value_ref<'msg>(
+    f: &FieldDescriptor,
+    val: upb_MessageValue,
+) -> ReflectValueRef<'msg> {
+    // SAFETY: `val` holds the member for the type of `f`, as promised by the
+    // caller.
+    unsafe {
+        match f.field_type() {
+            FieldType::Bool => ReflectValueRef::Bool(val.bool_val),
+            FieldType::Int32 | FieldType::SInt32 | FieldType::SFixed32 => {
+                ReflectValueRef::I32(val.int32_val)
+            }
+            FieldType::Int64 | FieldType::SInt64 | FieldType::SFixed64 => {
+                ReflectValueRef::I64(val.int64_val)
+            }
+            FieldType::UInt32 | FieldType::Fixed32 => ReflectValueRef::U32(val.uint32_val),
+            FieldType::UInt64 | FieldType::Fixed64 => ReflectValueRef::U64(val.uint64_val),
+            FieldType::Float => ReflectValueRef::F32(val.float_val),
+            FieldType::Double => ReflectValueRef::F64(val.double_val),
+            FieldType::String => ReflectValueRef::String(ProtoStringCow::Borrowed(
+                ProtoStr::from_utf8_unchecked(val.str_val.as_ref()),
+            )),
+            FieldType::Bytes => {
+                ReflectValueRef::Bytes(ProtoBytesCow::Borrowed(val.str_val.as_ref()))
+            }
+            FieldType::Enum => ReflectValueRef::Enum(val.int32_val),
+            FieldType::Message | FieldType::Group => ReflectValueRef::Message(MessageRef::new(
+                Private,
+                f.message_type().unwrap(),
+                val.msg_val.unwrap_or_else(ScratchSpace::zeroed_block),
+            )),
+        }
+    }
+}
+
+/// Converts `val` to a `upb_MessageValue` that can be stored in a message
+/// that lives in `arena`. Strings and messages are copied into `arena`.
+fn upb_message_value(val: &ReflectValueRef<'_>, arena: &Arena) -> upb_MessageValue {
+    match val {
+        ReflectValueRef::String(s) => {
+            upb_MessageValue { str_val: arena.copy_slice_in(s.as_bytes()).unwrap().into() }
+        }
+        ReflectValueRef::Bytes(b) => {
+            upb_MessageValue { str_val: arena.copy_slice_in(b.as_ref()).unwrap().into() }
+        }
+        ReflectValueRef::Message(m) => {
+            // SAFETY: `m` is a valid message of the type `m.descriptor()`.
+            let copy = unsafe {
+                upb_Message_DeepClone(
+                    m.raw(Private),
+                    message_mini_table(&m.descriptor()),
+                    arena.raw(),
+                )
+            };
+            upb_MessageValue { msg_val: Some(copy.expect("alloc should never fail")) }
+        }
+        _ => upb_key_value(val),
+    }
+}
+
+/// Converts a scalar or a map key to a `upb_MessageValue`, borrowing strings
+/// rather than copying them.
+fn upb_key_value(val: &ReflectValueRef<'_>) -> upb_MessageValue {
+    match val {
+        ReflectValueRef::Bool(v) => upb_MessageValue { bool_val: *v },
+        ReflectValueRef::I32(v) | ReflectValueRef::Enum(v) => upb_MessageValue { int32_val: *v },
+        ReflectValueRef::I64(v) => upb_MessageValue { int64_val: *v },
+        ReflectValueRef::U32(v) => upb_MessageValue { uint32_val: *v },
+        ReflectValueRef::U64(v) => upb_MessageValue { uint64_val: *v },
+        ReflectValueRef::F32(v) => upb_MessageValue { float_val: *v },
+        ReflectValueRef::F64(v) => upb_MessageValue { double_val: *v },
+        ReflectValueRef::String(s) => upb_MessageValue { str_val: s.as_bytes().into() },
+        ReflectValueRef::Bytes(b) => upb_MessageValue { str_val: b.as_ref().into() },
+        ReflectValueRef::Message(_) => unreachable!("messages are never map keys"),
+    }
+}
+
+/// Returns whether the fields of `msg` can be accessed through reflection,
+/// which every upb message supports.
+///
+/// # Safety
+/// - `msg` must be a valid message.
+pub unsafe fn reflect_is_supported(_msg: RawMessage) -> bool {
+    true
+}
+
+/// Returns whether the singular field `f` with presence is set.
+///
+/// # Safety
+/// - `f` must be a singular field with presence of the valid message `msg`.
+pub unsafe fn reflect_has(msg: RawMessage, f: &FieldDescriptor) -> bool {
+    let mtf = field_mini_table(f);
+    // SAFETY: `f` is a field or an extension of `msg` with presence, and the
+    // MiniTable of an extension starts with its `upb_MiniTableField`.
+    unsafe {
+        if f.is_extension() {
+            upb_Message_HasExtension(msg, mtf.cast::<upb_MiniTableExtension>())
+        } else {
+            upb_Message_HasBaseField(msg, mtf)
+        }
+    }
+}
+
+/// Returns the value of the singular field `f`, or its default if it's unset.
+///
+/// # Safety
+/// - `f` must be a singular field of `msg`, which must outlive `'msg` without
+///   being mutated.
+pub unsafe fn reflect_get<'msg>(msg: RawMessage, f: &FieldDescriptor) -> ReflectValueRef<'msg> {
+    if f.is_extension() {
+        // SAFETY: `f` is an extension of `msg`, and the value that is read
+        // borrows from `msg` or the pool.
+        return unsafe {
+            reflect_value_ref(f, upb_Message_GetFieldByDef(msg, f.inner(Private).raw()))
+        };
+    }
+    let mtf = field_mini_table(f);
+    // SAFETY: the default of a field lives as long as the pool.
+    let default = unsafe { upb_FieldDef_Default(f.inner(Private).raw()) };
+    // SAFETY: `f` is a field of `msg` and is read with the getter for its
+    // type, which returns the default if it's unset.
+    let val = unsafe {
+        match f.field_type() {
+            FieldType::Bool => {
+                upb_MessageValue { bool_val: upb_Message_GetBool(msg, mtf, default.bool_val) }
+            }
+            FieldType::Int32 | FieldType::SInt32 | FieldType::SFixed32 | FieldType::Enum => {
+                upb_MessageValue { int32_val: upb_Message_GetInt32(msg, mtf, default.int32_val) }
+            }
+            FieldType::Int64 | FieldType::SInt64 | FieldType::SFixed64 => {
+                upb_MessageValue { int64_val: upb_Message_GetInt64(msg, mtf, default.int64_val) }
+            }
+            FieldType::UInt32 | FieldType::Fixed32 => {
+                upb_MessageValue { uint32_val: upb_Message_GetUInt32(msg, mtf, default.uint32_val) }
+            }
+            FieldType::UInt64 | FieldType::Fixed64 => {
+                upb_MessageValue { uint64_val: upb_Message_GetUInt64(msg, mtf, default.uint64_val) }
+            }
+            FieldType::Float => {
+                upb_MessageValue { float_val: upb_Message_GetFloat(msg, mtf, default.float_val) }
+            }
+            FieldType::Double => {
+                upb_MessageValue { double_val: upb_Message_GetDouble(msg, mtf, default.double_val) }
+            }
+            FieldType::String | FieldType::Bytes => {
+                upb_MessageValue { str_val: upb_Message_GetString(msg, mtf, default.str_val) }
+            }
+            FieldType::Message | FieldType::Group => {
+                upb_MessageValue { msg_val: upb_Message_GetMessage(msg, mtf) }
+            }
+        }
+    };
+    // SAFETY: `val` holds the type of `f` and borrows from `msg` or the pool.
+    unsafe { reflect_value_ref(f, val) }
+}
+
+/// Sets the singular field `f` to a copy of `val`.
+///
+/// # Safety
+/// - `f` must be a singular field of `msg`, and `val` must be of its type.
+pub unsafe fn reflect_set(
+    msg: MutatorMessageRef<'_>,
+    f: &FieldDescriptor,
+    val: &ReflectValueRef<'_>,
+) {
+    let is_message = matches!(val, ReflectValueRef::Message(_));
+    let val = upb_message_value(val, msg.arena());
+    // SAFETY: `f` is a field of `msg`, and `val` holds its type at offset 0.
+    // Setting a submessage also sets its presence and oneof case.
+    unsafe {
+        if is_message {
+            upb_Message_SetBaseFieldMessage(msg.msg(), field_mini_table(f), val.msg_val.unwrap());
+        } else {
+            upb_Message_SetBaseField(
+                msg.msg(),
+                field_mini_table(f),
+                &val as *const upb_MessageValue as *const core::ffi::c_void,
+            );
+        }
+    }
+}
+
+/// Clears the field `f`, which may be repeated or a map.
+///
+/// # Safety
+/// - `f` must be a field of `msg`.
+pub unsafe fn reflect_clear(msg: MutatorMessageRef<'_>, f: &FieldDescriptor) {
+    // SAFETY: `f` is a field of `msg`.
+    unsafe { upb_Message_ClearBaseField(msg.msg(), field_mini_table(f)) }
+}
+
+/// Returns the singular message field `f`, setting it to an empty message if
+/// it's unset.
+///
+/// # Safety
+/// - `f` must be a singular message field of `msg`.
+pub unsafe fn reflect_mut_message<'msg>(
+    msg: MutatorMessageRef<'msg>,
+    f: &FieldDescriptor,
+) -> MutatorMessageRef<'msg> {
+    let mtf = field_mini_table(f);
+    // SAFETY: `f` is a message field of `msg`, and the MiniTables are those of
+    // `msg` and of `f`. The slot of an inactive oneof member may hold another
+    // member, so it's replaced rather than read.
+    let sub = unsafe {
+        let inactive_oneof = f.containing_oneof().is_some()
+            && upb_Message_WhichOneofFieldNumber(msg.msg(), mtf) != f.number();
+        if inactive_oneof {
+            let sub =
+                upb_Message_New(message_mini_table(&f.message_type().unwrap()), msg.arena().raw())
+                    .expect("alloc should never fail");
+            upb_Message_SetBaseFieldMessage(msg.msg(), mtf, sub);
+            sub
+        } else {
+            upb_Message_GetOrCreateMutableMessage(
+                msg.msg(),
+                message_mini_table(&f.containing_type()),
+                mtf,
+                msg.arena().raw(),
+            )
+            .expect("alloc should never fail")
+        }
+    };
+    MutatorMessageRef::from_parent(msg, sub)
+}
+
+/// # Safety
+/// - `f` must be a repeated field of `msg`.
+pub unsafe fn reflect_repeated_len(msg: RawMessage, f: &FieldDescriptor) -> usize {
+    // SAFETY: `f` is a repeated field of `msg`.
+    unsafe { repeated_array(msg, f).map_or(0, |arr| upb_Array_Size(arr)) }
+}
+
+/// Returns the array of the repeated field or extension `f`, if it has one.
+///
+/// # Safety
+/// - `f` must be a repeated field or extension of `msg`.
+unsafe fn repeated_array(msg: RawMessage, f: &FieldDescriptor) -> Option<RawArray> {
+    let mtf = field_mini_table(f);
+    // SAFETY: `f` is a repeated field or extension of `msg`, and the MiniTable
+    // of an extension starts with its `upb_MiniTableField`.
+    unsafe {
+        if f.is_extension() {
+            upb_Message_GetExtensionArray(msg, mtf.cast::<upb_MiniTableExtension>())
+        } else {
+            upb_Message_GetArray(msg, mtf)
+        }
+    }
+}
+
+/// # Safety
+/// - `f` must be a repeated field of `msg`, which must outlive `'msg` without
+///   being mutated.
+/// - `i` must be less than the length of the field.
+pub unsafe fn reflect_repeated_get<'msg>(
+    msg: RawMessage,
+    f: &FieldDescriptor,
+    i: usize,
+) -> ReflectValueRef<'msg> {
+    // SAFETY: `f` is a non-empty repeated field of `msg`, so its array is set,
+    // and `i` is in bounds.
+    unsafe {
+        let arr = repeated_array(msg, f).unwrap();
+        reflect_value_ref(f, upb_Array_Get(arr, i))
+    }
+}
+
+/// # Safety
+/// - `f` must be a repeated field of `msg`, and `val` must be of its type.
+/// - `i` must be less than the length of the field.
+pub unsafe fn reflect_repeated_set(
+    msg: MutatorMessageRef<'_>,
+    f: &FieldDescriptor,
+    i: usize,
+    val: &ReflectValueRef<'_>,
+) {
+    let val = upb_message_value(val, msg.arena());
+    // SAFETY: `f` is a non-empty repeated field of `msg`, so its array is set,
+    // and `i` is in bounds.
+    unsafe {
+        let arr = upb_Message_GetArray(msg.msg(), field_mini_table(f)).unwrap();
+        upb_Array_Set(arr, i, val)
+    }
+}
+
+/// # Safety
+/// - `f` must be a repeated field of `msg`, and `val` must be of its type.
+pub unsafe fn reflect_repeated_push(
+    msg: MutatorMessageRef<'_>,
+    f: &FieldDescriptor,
+    val: &ReflectValueRef<'_>,
+) {
+    let val = upb_message_value(val, msg.arena());
+    // SAFETY: `f` is a repeated field of `msg`.
+    unsafe {
+        let arr =
+            upb_Message_GetOrCreateMutableArray(msg.msg(), field_mini_table(f), msg.arena().raw())
+                .expect("alloc should never fail");
+        assert!(upb_Array_Append(arr, val, msg.arena().raw()), "alloc should never fail");
+    }
+}
+
+/// # Safety
+/// - `f` must be a repeated message field of `msg`.
+/// - `i` must be less than the length of the field.
+pub unsafe fn reflect_repeated_mut_message<'msg>(
+    msg: MutatorMessageRef<'msg>,
+    f: &FieldDescriptor,
+    i: usize,
+) -> MutatorMessageRef<'msg> {
+    // SAFETY: `f` is a non-empty repeated message field of `msg`, so its array
+    // is set, and `i` is in bounds.
+    let sub = unsafe {
+        let arr = upb_Message_GetArray(msg.msg(), field_mini_table(f)).unwrap();
+        upb_Array_GetMutable(arr, i).msg.unwrap()
+    };
+    MutatorMessageRef::from_parent(msg, sub)
+}
+
+/// # Safety
+/// - `f` must be a map field of `msg`.
+pub unsafe fn reflect_map_len(msg: RawMessage, f: &FieldDescriptor) -> usize {
+    // SAFETY: `f` is a map field of `msg`.
+    unsafe { upb_Message_GetMap(msg, field_mini_table(f)).map_or(0, |map| upb_Map_Size(map)) }
+}
+
+/// # Safety
+/// - `f` must be a map field of `msg`, which must outlive `'msg` without
+///   being mutated.
+/// - `key` must be of the key type of `f`.
+pub unsafe fn reflect_map_get<'msg>(
+    msg: RawMessage,
+    f: &FieldDescriptor,
+    key: &ReflectValueRef<'_>,
+) -> Option<ReflectValueRef<'msg>> {
+    let entry = f.message_type().unwrap();
+    let mut val = upb_MessageValue::zeroed();
+    // SAFETY: `f` is a map field of `msg`, and `key` is of its key type.
+    unsafe {
+        let map = upb_Message_GetMap(msg, field_mini_table(f))?;
+        if !upb_Map_Get(map, upb_key_value(key), &mut val) {
+            return None;
+        }
+        Some(reflect_value_ref(&entry.field_by_number(2).unwrap(), val))
+    }
+}
+
+/// Sets the value for `key` to a copy of `val`, returning whether `key` was
+/// newly inserted.
+///
+/// # Safety
+/// - `f` must be a map field of `msg`, and `key` and `val` must be of its key
+///   and value types.
+pub unsafe fn reflect_map_insert(
+    msg: MutatorMessageRef<'_>,
+    f: &FieldDescriptor,
+    key: &ReflectValueRef<'_>,
+    val: &ReflectValueRef<'_>,
+) -> bool {
+    let arena = msg.arena();
+    let (key, val) = (upb_message_value(key, arena), upb_message_value(val, arena));
+    // SAFETY: `f` is a map field of `msg`, whose entry MiniTable is that of
+    // its message type.
+    let status = unsafe {
+        let map = upb_Message_GetOrCreateMutableMap(
+            msg.msg(),
+            message_mini_table(&f.message_type().unwrap()),
+            field_mini_table(f),
+            arena.raw(),
+        )
+        .expect("alloc should never fail");
+        upb_Map_Insert(map, key, val, arena.raw())
+    };
+    assert!(status != MapInsertStatus::OutOfMemory, "alloc should never fail");
+    status == MapInsertStatus::Inserted
+}
+
+/// Removes the entry for `key`, returning whether it was present.
+///
+/// # Safety
+/// - `f` must be a map field of `msg`, and `key` must be of its key type.
+pub unsafe fn reflect_map_remove(
+    msg: MutatorMessageRef<'_>,
+    f: &FieldDescriptor,
+    key: &ReflectValueRef<'_>,
+) -> bool {
+    // SAFETY: `f` is a map field of `msg`, and `key` is of its key type.
+    unsafe {
+        upb_Message_GetMap(msg.msg(), field_mini_table(f))
+            .is_some_and(|map| upb_Map_Delete(map, upb_key_value(key), ptr::null_mut()))
+    }
+}
+
+/// The kernel-specific part of a
+/// [`MapFieldIter`](crate::reflect::MapFieldIter).
+#[doc(hidden)]
+pub struct InnerMapFieldIter<'msg> {
+    iter: Option<RawMapIter>,
+    key_field: FieldDescriptor,
+    value_field: FieldDescriptor,
+    _phantom: PhantomData<&'msg ()>,
+}
+
+impl<'msg> InnerMapFieldIter<'msg> {
+    /// # Safety
+    /// - `f` must be a map field of `msg`, which must outlive `'msg` without
+    ///   being mutated.
+    pub unsafe fn new(msg: RawMessage, f: &FieldDescriptor) -> Self {
+        let entry = f.message_type().unwrap();
+        InnerMapFieldIter {
+            // SAFETY: `f` is a map field of `msg`.
+            iter: unsafe { upb_Message_GetMap(msg, field_mini_table(f)) }.map(RawMapIter::new),
+            key_field: entry.field_by_number(1).unwrap(),
+            value_field: entry.field_by_number(2).unwrap(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'msg> Iterator for InnerMapFieldIter<'msg> {
+    type Item = (ReflectValueRef<'msg>, ReflectValueRef<'msg>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // SAFETY: the map outlives `'msg`, and its keys and values are of the
+        // types of the entry fields.
+        unsafe {
+            let (key, val) = self.iter.as_mut()?.next_unchecked()?;
+            Some((
+                reflect_value_ref(&self.key_field, key),
+                reflect_value_ref(&self.value_field, val),
+            ))
+        }
+    }
+}
+
+/// The kernel-specific part of a
+/// [`DynamicMessage`](crate::reflect::DynamicMessage): a message on its own
+/// arena.
+#[doc(hidden)]
+pub struct InnerDynamicMessage {
+    inner: MessageInner,
+}
+
+impl InnerDynamicMessage {
+    pub fn new(descriptor: &MessageDescriptor) -> Self {
+        let arena = Arena::new();
+        // SAFETY: the MiniTable and the arena are valid.
+        let msg = unsafe { upb_Message_New(message_mini_table(descriptor), arena.raw()) }
+            .expect("alloc should never fail");
+        InnerDynamicMessage { inner: MessageInner { msg, arena } }
+    }
+
+    /// # Safety
+    /// - `msg` must be a valid message of the type `descriptor`.
+    pub unsafe fn copy_of(descriptor: &MessageDescriptor, msg: RawMessage) -> Self {
+        let arena = Arena::new();
+        // SAFETY: `msg` is of the type `descriptor`, as promised by the caller.
+        let msg =
+            unsafe { upb_Message_DeepClone(msg, message_mini_table(descriptor), arena.raw()) }
+                .expect("alloc should never fail");
+        InnerDynamicMessage { inner: MessageInner { msg, arena } }
+    }
+
+    pub fn raw(&self) -> RawMessage {
+        self.inner.msg
+    }
+
+    pub fn as_mutator_message_ref(&mut self) -> MutatorMessageRef<'_> {
+        MutatorMessageRef::new(&mut self.inner)
+    }
+
+    /// Parses `data` into this message, which must be empty.
+    ///
+    /// # Safety
+    /// - `self` must be a message of the type `descriptor`.
+    pub unsafe fn parse_with_options(
+        &mut self,
+        descriptor: &MessageDescriptor,
+        data: &[u8],
+        options: &ParseOptions<'_>,
+    ) -> Result<(), ParseError> {
+        // SAFETY:
+        // - The MiniTable is the one of `self.inner.msg`.
+        // - `self.inner.arena` is the arena that owns `self.inner.msg`.
+        unsafe {
+            parse_with_options(
+                data,
+                self.inner.msg,
+                message_mini_table(descriptor),
+                &self.inner.arena,
+                options,
+            )
+        }
+    }
+
+    /// Replaces the contents of this message with those parsed from `data`.
+    /// The message is unchanged if parsing fails.
+    ///
+    /// # Safety
+    /// - `self` must be a message of the type `descriptor`.
+    pub unsafe fn clear_and_parse(
+        &mut self,
+        descriptor: &MessageDescriptor,
+        data: &[u8],
+    ) -> Result<(), ParseError> {
+        let mut msg = Self::new(descriptor);
+        // SAFETY: `msg` is a message of the type `descriptor`.
+        unsafe { msg.merge_parse(descriptor, data) }?;
+        // This frees the arena of the old message.
+        *self = msg;
+        Ok(())
+    }
+
+    /// Merges the fields that are parsed from `data` into this message.
+    ///
+    /// # Safety
+    /// - `self` must be a message of the type `descriptor`.
+    pub unsafe fn merge_parse(
+        &mut self,
+        descriptor: &MessageDescriptor,
+        data: &[u8],
+    ) -> Result<(), ParseError> {
+        // SAFETY:
+        // - The MiniTable is the one of `self.inner.msg`.
+        // - `self.inner.arena` is the arena that owns `self.inner.msg`.
+        unsafe {
+            wire::decode(data, self.inner.msg, message_mini_table(descriptor), &self.inner.arena)
+        }
+        .map_err(parse_error_from_decode_status)
+    }
+}
+
+/// # Safety
+/// - `msg` must be a valid message of the type `descriptor`.
+pub unsafe fn reflect_debug_string(
+    msg: RawMessage,
+    descriptor: &MessageDescriptor,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    // SAFETY: `msg` is of the type `descriptor`, as promised by the caller.
+    let string = unsafe { debug_string(msg, message_mini_table(descriptor)) };
+    f.write_str(&string)
+}
+
+/// Appends the serialization of `msg` to `out`.
+///
+/// # Safety
+/// - `msg` must be a valid message of the type `descriptor`.
+pub unsafe fn reflect_serialize_to_vec(
+    msg: RawMessage,
+    descriptor: &MessageDescriptor,
+    out: &mut Vec<u8>,
+    options: &SerializeOptions,
+) -> Result<(), SerializeError> {
+    // SAFETY: `msg` is of the type `descriptor`, as promised by the caller.
+    unsafe {
+        let view = MessageRef::new(Private, descriptor.clone(), msg);
+        serialize_to_vec(msg, message_mini_table(descriptor), view, out, options)
+    }
+}
+
+/// Serializes `msg` into the front of `buf`, returning the number of bytes
+/// written.
+///
+/// # Safety
+/// - `msg` must be a valid message of the type `descriptor`.
+pub unsafe fn reflect_serialize_into(
+    msg: RawMessage,
+    descriptor: &MessageDescriptor,
+    buf: &mut [u8],
+) -> Result<usize, SerializeError> {
+    // SAFETY: `msg` is of the type `descriptor`, as promised by the caller.
+    unsafe {
+        let view = MessageRef::new(Private, descriptor.clone(), msg);
+        serialize_into(msg, message_mini_table(descriptor), view, buf)
+    }
+}
+
+/// Clears all of the fields of `msg`.
+///
+/// # Safety
+/// - `msg` must be a message of the type `descriptor`.
+pub unsafe fn reflect_clear_message(msg: MutatorMessageRef<'_>, descriptor: &MessageDescriptor) {
+    // SAFETY: `msg` is of the type `descriptor`, as promised by the caller.
+    unsafe { upb_Message_Clear(msg.msg(), message_mini_table(descriptor)) }
+}
+
+/// Merges the fields of `src` into `msg`.
+///
+/// # Safety
+/// - `msg` and `src` must both be messages of the type `descriptor`.
+pub unsafe fn reflect_merge_from(
+    msg: MutatorMessageRef<'_>,
+    descriptor: &MessageDescriptor,
+    src: RawMessage,
+) {
+    // SAFETY:
+    // - Both messages are of the type `descriptor`, as promised by the caller.
+    // - `msg.arena()` is the arena that owns `msg`.
+    let ok = unsafe {
+        upb_Message_MergeFrom(
+            msg.msg(),
+            src,
+            message_mini_table(descriptor),
+            ptr::null(),
+            msg.arena().raw(),
+        )
+    };
+    assert!(ok, "upb_Message_MergeFrom failed to allocate");
+}
+
+/// Calls `f` with the pool that `descriptor` belongs to, which is used to
+/// resolve the types of `Any`s and extensions.
+///
+/// If that is the pool of the generated messages, it is locked during the
+/// call, since lookups in it race with loading files into it.
+fn with_def_pool<R>(descriptor: &MessageDescriptor, f: impl FnOnce(RawDefPool) -> R) -> R {
+    // SAFETY: the def is valid, and so is the file that it belongs to.
+    let pool = unsafe { upb_FileDef_Pool(upb_MessageDef_File(descriptor.inner(Private).raw())) };
+    let pool = NonNull::new(pool.cast_mut()).expect("every file belongs to a pool");
+    let global = global_def_pool().lock().unwrap_or_else(PoisonError::into_inner);
+    let _guard = (global.0 == pool).then_some(global);
+    f(pool)
+}
+
+/// Prints `msg` as proto3 JSON, returning a description of the problem on
+/// failure.
+///
+/// # Safety
+/// - `msg` must be a valid message of the type `descriptor`.
+pub unsafe fn json_encode(
+    msg: RawMessage,
+    descriptor: &MessageDescriptor,
+    options: &JsonPrintOptions,
+) -> Result<String, String> {
+    let mut flags = 0;
+    if options.is_preserve_proto_field_names() {
+        flags |= JsonEncodeOptions::UseProtoNames as i32;
+    }
+    if options.is_emit_default_values() {
+        flags |= JsonEncodeOptions::EmitDefaults as i32;
+    }
+    let m = descriptor.inner(Private).raw();
+    let mut status = upb_Status::new();
+    let buf = with_def_pool(descriptor, |pool| {
+        let pool = pool.as_ptr().cast_const();
+        // Only find out the length first, then print into a buffer of that
+        // size.
+        // SAFETY: `msg` is of the type `m`, which belongs to `pool`, and `buf`
+        // is null with a size of 0.
+        let len = unsafe { upb_JsonEncode(msg, m, pool, flags, ptr::null_mut(), 0, &mut status) };
+        if !status.is_ok() {
+            return None;
+        }
+        // +1 for the trailing NUL.
+        let mut buf = vec![0u8; len + 1];
+        // SAFETY: as above, and `buf` is writable for its length.
+        let written = unsafe {
+            upb_JsonEncode(msg, m, pool, flags, buf.as_mut_ptr(), buf.len(), &mut status)
+        };
+        assert_eq!(len, written);
+        buf.truncate(len);
+        Some(buf)
+    });
+    let Some(buf) = buf else {
+        return Err(status.error_message());
+    };
+    // upb copies the bytes of `string` fields verbatim, and those of proto2
+    // fields aren't validated.
+    String::from_utf8(buf).map_err(|_| "a string field holds invalid UTF-8".to_string())
+}
+
+/// Merges the message that is encoded as proto3 JSON in `json` into `msg`,
+/// returning a description of the problem on failure.
+///
+/// # Safety
+/// - `msg` must be a message of the type `descriptor`.
+pub unsafe fn json_decode(
+    msg: MutatorMessageRef<'_>,
+    descriptor: &MessageDescriptor,
+    json: &str,
+    options: &JsonParseOptions,
+) -> Result<(), String> {
+    let mut flags = 0;
+    if options.is_ignore_unknown_fields() {
+        flags |= JsonDecodeOptions::IgnoreUnknown as i32;
+    }
+    let mut status = upb_Status::new();
+    // SAFETY:
+    // - `msg` is of the type `descriptor`, as promised by the caller, and
+    //   lives on `msg.arena()`.
+    // - `json` is readable for its length.
+    let ok = with_def_pool(descriptor, |pool| unsafe {
+        upb_JsonDecode(
+            json.as_ptr(),
+            json.len(),
+            msg.msg(),
+            descriptor.inner(Private).raw(),
+            pool.as_ptr().cast_const(),
+            flags,
+            msg.arena().raw(),
+            &mut status,
+        )
+    });
+    if ok {
+        Ok(())
+    } else {
+        Err(status.error_message())
+    }
+}
+
+/// Prints `msg` in the text format.
+///
+/// This uses the printer that is written against the reflection API rather
+/// than `upb_TextEncode`, whose output differs from that of the C++ kernel.
+///
+/// # Safety
+/// - `msg` must be a valid message of the type `descriptor`.
+pub unsafe fn text_format_encode(
+    msg: RawMessage,
+    descriptor: &MessageDescriptor,
+    options: &TextFormatPrintOptions,
+) -> String {
+    // SAFETY: `msg` is of the type `descriptor`, as promised by the caller, and
+    // is only borrowed while it is printed.
+    let msg = unsafe { MessageRef::new(Private, descriptor.clone(), msg) };
+    crate::text_format_printer::print(
+        msg,
+        options.is_single_line(),
+        options.is_print_unknown_fields(),
+    )
+}
+
+/// Parses the message that is encoded in the text format in `text` into
+/// `msg`. Unless `merge` is set, `msg` must be empty and singular fields may
+/// only be set once.
+///
+/// upb has no text format parser, so this one is written against the
+/// reflection API.
+pub fn text_format_decode(msg: MessageRefMut<'_>, text: &str, merge: bool) -> Result<(), String> {
+    crate::text_format_parser::parse(msg, text, merge)
+}
+
+/// Returns the extension of `extendee` that is called `full_name`, if there
+/// is one in the pool of `extendee`.
+pub fn find_extension_by_name(
+    extendee: &MessageDescriptor,
+    full_name: &str,
+) -> Option<FieldDescriptor> {
+    // SAFETY: `pool` is valid and `full_name` is valid for its length.
+    let raw = with_def_pool(extendee, |pool| unsafe {
+        upb_DefPool_FindExtensionByNameWithSize(pool, full_name.as_ptr(), full_name.len())
+    });
+    let ext = FieldDescriptor::new_in(
+        Private,
+        InnerFieldDescriptor::from_raw(raw)?,
+        extendee.pool(Private),
+    );
+    (ext.containing_type() == *extendee).then_some(ext)
+}
+
+/// Returns the extensions that are set in `msg` and are in the pool of
+/// `descriptor`, in order of field number.
+///
+/// # Safety
+/// - `msg` must be a valid message of the type `descriptor`.
+pub unsafe fn reflect_extensions(
+    msg: RawMessage,
+    descriptor: &MessageDescriptor,
+) -> Vec<FieldDescriptor> {
+    let mut ext: *const upb_MiniTableExtension = ptr::null();
+    let mut iter = 0;
+    let mut extensions = Vec::new();
+    with_def_pool(descriptor, |pool| {
+        // SAFETY: `msg` is valid, and so is every extension that it yields.
+        unsafe {
+            while upb_Message_NextExtensionReverse(msg, &mut ext, &mut iter) {
+                let raw = upb_DefPool_FindExtensionByMiniTable(pool, ext);
+                if let Some(inner) = InnerFieldDescriptor::from_raw(raw) {
+                    extensions.push(FieldDescriptor::new_in(
+                        Private,
+                        inner,
+                        descriptor.pool(Private),
+                    ));
+                }
+            }
+        }
+    });
+    extensions.sort_by_key(FieldDescriptor::number);
+    extensions
+}
+
+/// Returns the message called `full_name` in the pool that `scope` belongs
+/// to.
+pub fn find_message_by_name(
+    scope: &MessageDescriptor,
+    full_name: &str,
+) -> Option<MessageDescriptor> {
+    // SAFETY: `pool` is valid and `full_name` is valid for its length.
+    let raw = with_def_pool(scope, |pool| unsafe {
+        upb_DefPool_FindMessageByNameWithSize(pool, full_name.as_ptr(), full_name.len())
+    });
+    let inner = InnerMessageDescriptor::from_raw(raw)?;
+    Some(MessageDescriptor::new_in(Private, inner, scope.pool(Private)))
+}
+
+/// Merges `data`, which is in the wire format, into `msg`, decoding the
+/// extensions of `msg` that are in its pool.
+///
+/// Reflection only reaches the fields that are declared in the message, so
+/// this is how extensions are set by the text format parser.
+pub fn merge_with_extensions(mut msg: MessageRefMut<'_>, data: &[u8]) -> Result<(), ParseError> {
+    let descriptor = msg.descriptor();
+    let inner = msg.inner(Private);
+    with_def_pool(&descriptor, |pool| {
+        // SAFETY: `pool` is valid, and its registry lives as long as it does.
+        let extreg = NonNull::new(unsafe { upb_DefPool_ExtensionRegistry(pool) }.cast_mut());
+        // SAFETY:
+        // - `inner` is a mutable message of the type `descriptor`, and lives on
+        //   `inner.arena()`.
+        // - `extreg` is valid while the pool is.
+        unsafe {
+            wire::decode_with_options(
+                data,
+                inner.msg(),
+                message_mini_table(&descriptor),
+                extreg,
+                inner.arena(),
+                0,
+            )
+        }
+    })
+    .map_err(parse_error_from_decode_status)
+}
+
+impl UnsetRequiredFields for MessageRef<'_> {
+    fn unset_required_fields(self, prefix: &str, out: &mut Vec<String>) {
+        for field in self.descriptor().fields() {
+            let name = field.name();
+            if field.is_required() && !self.has_field(&field) {
+                out.push(format!("{prefix}{name}"));
+            }
+            if field.is_map() {
+                for (key, value) in self.get_map(&field) {
+                    if let ReflectValueRef::Message(value) = value {
+                        let key = MapKeyDebug(&key);
+                        value.unset_required_fields(&format!("{prefix}{name}[{key:?}]."), out);
+                    }
+                }
+            } else if field.message_type().is_none() {
+                continue;
+            } else if field.is_repeated() {
+                for (i, value) in self.get_repeated(&field).iter().enumerate() {
+                    if let ReflectValueRef::Message(value) = value {
+                        value.unset_required_fields(&format!("{prefix}{name}[{i}]."), out);
+                    }
+                }
+            } else if self.has_field(&field) {
+                if let ReflectValueRef::Message(value) = self.get_field(&field) {
+                    value.unset_required_fields(&format!("{prefix}{name}."), out);
+                }
+            }
+        }
+    }
+}
+
+/// Formats a map key like the `Debug` of its generated view.
+struct MapKeyDebug<'a, 'msg>(&'a ReflectValueRef<'msg>);
+
+impl Debug for MapKeyDebug<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            ReflectValueRef::Bool(v) => Debug::fmt(v, f),
+            ReflectValueRef::I32(v) => Debug::fmt(v, f),
+            ReflectValueRef::I64(v) => Debug::fmt(v, f),
+            ReflectValueRef::U32(v) => Debug::fmt(v, f),
+            ReflectValueRef::U64(v) => Debug::fmt(v, f),
+            ReflectValueRef::String(v) => Debug::fmt(&**v, f),
+            other => Debug::fmt(other, f),
+        }
+    }
+}
+
 #[doc(hidden)]
 pub struct RawMapIter {
     // TODO: Replace this `RawMap` with the const type.
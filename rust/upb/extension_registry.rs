@@ -6,7 +6,46 @@
 // https://developers.google.com/open-source/licenses/bsd
 
 use super::opaque_pointee::opaque_pointee;
+use super::{upb_MiniTableExtension, RawArena};
 use core::ptr::NonNull;
 
 opaque_pointee!(upb_ExtensionRegistry);
 pub type RawExtensionRegistry = NonNull<upb_ExtensionRegistry>;
+
+// LINT.IfChange(extension_registry_status)
+#[repr(C)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum ExtensionRegistryStatus {
+    Ok = 0,
+    DuplicateEntry = 1,
+    OutOfMemory = 2,
+}
+// LINT.ThenChange()
+
+extern "C" {
+    /// Creates a registry in `arena`, returning None if allocation failed.
+    ///
+    /// # Safety
+    /// - `arena` must be valid to deref and outlive every use of the registry
+    pub fn upb_ExtensionRegistry_New(arena: RawArena) -> Option<RawExtensionRegistry>;
+
+    /// # Safety
+    /// - `r` and `e` must be valid to deref
+    /// - `e` must outlive every use of `r`
+    pub fn upb_ExtensionRegistry_Add(
+        r: RawExtensionRegistry,
+        e: *const upb_MiniTableExtension,
+    ) -> ExtensionRegistryStatus;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[googletest::test]
+    fn assert_extension_registry_linked() {
+        use crate::assert_linked;
+        assert_linked!(upb_ExtensionRegistry_New);
+        assert_linked!(upb_ExtensionRegistry_Add);
+    }
+}
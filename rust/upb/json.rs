@@ -0,0 +1,115 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use super::{upb_DefPool, upb_MessageDef, RawArena, RawMessage};
+use core::ffi::{c_char, CStr};
+
+const UPB_STATUS_MAX_MESSAGE: usize = 511;
+
+/// ABI compatible struct with upb_Status.
+#[repr(C)]
+pub struct upb_Status {
+    ok: bool,
+    msg: [c_char; UPB_STATUS_MAX_MESSAGE],
+}
+
+impl upb_Status {
+    pub fn new() -> Self {
+        upb_Status { ok: true, msg: [0; UPB_STATUS_MAX_MESSAGE] }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.ok
+    }
+
+    /// The error message that was set by the failed operation.
+    pub fn error_message(&self) -> String {
+        // SAFETY: upb always NUL-terminates the message, and the buffer starts
+        // out zeroed.
+        let msg = unsafe { CStr::from_ptr(self.msg.as_ptr()) };
+        msg.to_string_lossy().into_owned()
+    }
+}
+
+impl Default for upb_Status {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Options for `upb_JsonEncode`, which may be or-ed together.
+#[repr(i32)]
+pub enum JsonEncodeOptions {
+    /// Emits fields without presence even if they hold their default value.
+    EmitDefaults = 1,
+
+    /// Uses the names of the fields in the .proto file instead of their
+    /// lowerCamelCase JSON names.
+    UseProtoNames = 2,
+
+    /// Emits enums as their numbers instead of their names.
+    FormatEnumsAsIntegers = 4,
+}
+
+/// Options for `upb_JsonDecode`, which may be or-ed together.
+#[repr(i32)]
+pub enum JsonDecodeOptions {
+    /// Skips JSON members that are not fields of the message.
+    IgnoreUnknown = 1,
+}
+
+extern "C" {
+    /// Encodes `msg` into `buf`, returning the length of the whole encoding
+    /// (excluding the NUL terminator) even if it didn't fit, like
+    /// `snprintf`. Returns `usize::MAX` and sets `status` on failure.
+    ///
+    /// # Safety
+    /// - `msg` must be a valid message of the type `m`
+    /// - `ext_pool` must be null or the pool that `m` belongs to
+    /// - `buf` must be writable for `size` bytes (`buf` may be null if `size`
+    ///   is 0)
+    pub fn upb_JsonEncode(
+        msg: RawMessage,
+        m: *const upb_MessageDef,
+        ext_pool: *const upb_DefPool,
+        options: i32,
+        buf: *mut u8,
+        size: usize,
+        status: *mut upb_Status,
+    ) -> usize;
+
+    /// Merges the message that is encoded as JSON in `buf` into `msg`,
+    /// returning false and setting `status` on failure.
+    ///
+    /// # Safety
+    /// - `buf` must be readable for `size` bytes
+    /// - `msg` must be a valid, mutable message of the type `m` that lives on
+    ///   `arena`
+    /// - `symtab` must be the pool that `m` belongs to
+    pub fn upb_JsonDecode(
+        buf: *const u8,
+        size: usize,
+        msg: RawMessage,
+        m: *const upb_MessageDef,
+        symtab: *const upb_DefPool,
+        options: i32,
+        arena: RawArena,
+        status: *mut upb_Status,
+    ) -> bool;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[googletest::test]
+    fn assert_json_linked() {
+        use crate::assert_linked;
+        assert_linked!(upb_JsonEncode);
+        assert_linked!(upb_JsonDecode);
+    }
+}
@@ -25,7 +25,13 @@ mod ctype;
 pub use ctype::CType;
 
 mod extension_registry;
-pub use extension_registry::{upb_ExtensionRegistry, RawExtensionRegistry};
+pub use extension_registry::{
+    upb_ExtensionRegistry, upb_ExtensionRegistry_Add, upb_ExtensionRegistry_New,
+    ExtensionRegistryStatus, RawExtensionRegistry,
+};
+
+mod json;
+pub use json::{upb_JsonDecode, upb_JsonEncode, upb_Status, JsonDecodeOptions, JsonEncodeOptions};
 
 mod map;
 pub use map::{
@@ -41,8 +47,12 @@ pub use message_value::{upb_MessageValue, upb_MutableMessageValue};
 
 mod mini_table;
 pub use mini_table::{
-    upb_MiniTable, upb_MiniTableField, upb_MiniTable_FindFieldByNumber,
-    upb_MiniTable_GetFieldByIndex, upb_MiniTable_SubMessage, RawMiniTable, RawMiniTableField,
+    upb_MiniTable, upb_MiniTableExtension, upb_MiniTableExtension_GetSubMessage,
+    upb_MiniTableField, upb_MiniTableField_CType, upb_MiniTableField_IsArray,
+    upb_MiniTableField_IsMap, upb_MiniTableFile, upb_MiniTable_FieldCount,
+    upb_MiniTable_FindFieldByNumber, upb_MiniTable_GetFieldByIndex,
+    upb_MiniTable_MapEntrySubMessage, upb_MiniTable_MapValue, upb_MiniTable_SubMessage,
+    RawMiniTable, RawMiniTableExtension, RawMiniTableField,
 };
 
 mod opaque_pointee;
@@ -50,6 +60,9 @@ mod opaque_pointee;
 mod owned_arena_box;
 pub use owned_arena_box::OwnedArenaBox;
 
+mod reflection;
+pub use reflection::*;
+
 mod string_view;
 pub use string_view::StringView;
 
@@ -7,8 +7,8 @@
 
 use super::opaque_pointee::opaque_pointee;
 use super::{
-    upb_ExtensionRegistry, upb_MiniTable, upb_MiniTableField, RawArena, RawArray, RawMap,
-    StringView,
+    upb_ExtensionRegistry, upb_MiniTable, upb_MiniTableExtension, upb_MiniTableField, RawArena,
+    RawArray, RawMap, StringView,
 };
 use core::ptr::NonNull;
 
@@ -301,6 +301,146 @@ extern "C" {
     /// - `m` and `f` must be valid to deref
     /// - `f` must be a field within a oneof associated with `m`
     pub fn upb_Message_WhichOneofFieldNumber(m: RawMessage, f: *const upb_MiniTableField) -> u32;
+
+    /// # Safety
+    /// - `m` and `e` must be valid to deref
+    /// - `e` must be an extension of the message type of `m`
+    pub fn upb_Message_HasExtension(m: RawMessage, e: *const upb_MiniTableExtension) -> bool;
+
+    /// # Safety
+    /// - `m` and `e` must be valid to deref
+    /// - `e` must be an extension of the message type of `m`
+    pub fn upb_Message_ClearExtension(m: RawMessage, e: *const upb_MiniTableExtension);
+
+    /// Sets the extension to the value pointed to by `val`. Returns false if
+    /// memory allocation failed.
+    ///
+    /// # Safety
+    /// - `m`, `e` and `arena` must be valid to deref
+    /// - `e` must be an extension of the message type of `m`
+    /// - `val` must point to a value of the extension's C type, such as a
+    ///   `upb_MessageValue` of the matching variant
+    pub fn upb_Message_SetExtension(
+        m: RawMessage,
+        e: *const upb_MiniTableExtension,
+        val: *const core::ffi::c_void,
+        arena: RawArena,
+    ) -> bool;
+
+    /// # Safety
+    /// - `m` and `e` must be valid to deref
+    /// - `e` must be a bool extension of the message type of `m`
+    pub fn upb_Message_GetExtensionBool(
+        m: RawMessage,
+        e: *const upb_MiniTableExtension,
+        default_val: bool,
+    ) -> bool;
+
+    /// # Safety
+    /// - `m` and `e` must be valid to deref
+    /// - `e` must be an i32 extension of the message type of `m`
+    pub fn upb_Message_GetExtensionInt32(
+        m: RawMessage,
+        e: *const upb_MiniTableExtension,
+        default_val: i32,
+    ) -> i32;
+
+    /// # Safety
+    /// - `m` and `e` must be valid to deref
+    /// - `e` must be an i64 extension of the message type of `m`
+    pub fn upb_Message_GetExtensionInt64(
+        m: RawMessage,
+        e: *const upb_MiniTableExtension,
+        default_val: i64,
+    ) -> i64;
+
+    /// # Safety
+    /// - `m` and `e` must be valid to deref
+    /// - `e` must be a u32 extension of the message type of `m`
+    pub fn upb_Message_GetExtensionUInt32(
+        m: RawMessage,
+        e: *const upb_MiniTableExtension,
+        default_val: u32,
+    ) -> u32;
+
+    /// # Safety
+    /// - `m` and `e` must be valid to deref
+    /// - `e` must be a u64 extension of the message type of `m`
+    pub fn upb_Message_GetExtensionUInt64(
+        m: RawMessage,
+        e: *const upb_MiniTableExtension,
+        default_val: u64,
+    ) -> u64;
+
+    /// # Safety
+    /// - `m` and `e` must be valid to deref
+    /// - `e` must be a f32 extension of the message type of `m`
+    pub fn upb_Message_GetExtensionFloat(
+        m: RawMessage,
+        e: *const upb_MiniTableExtension,
+        default_val: f32,
+    ) -> f32;
+
+    /// # Safety
+    /// - `m` and `e` must be valid to deref
+    /// - `e` must be a f64 extension of the message type of `m`
+    pub fn upb_Message_GetExtensionDouble(
+        m: RawMessage,
+        e: *const upb_MiniTableExtension,
+        default_val: f64,
+    ) -> f64;
+
+    /// # Safety
+    /// - `m` and `e` must be valid to deref
+    /// - `e` must be a string or bytes extension of the message type of `m`
+    pub fn upb_Message_GetExtensionString(
+        m: RawMessage,
+        e: *const upb_MiniTableExtension,
+        default_val: StringView,
+    ) -> StringView;
+
+    /// # Safety
+    /// - `m` and `e` must be valid to deref
+    /// - `e` must be a singular message extension of the message type of `m`
+    pub fn upb_Message_GetExtensionMessage(
+        m: RawMessage,
+        e: *const upb_MiniTableExtension,
+        default_val: Option<RawMessage>,
+    ) -> Option<RawMessage>;
+
+    /// # Safety
+    /// - `m` and `e` must be valid to deref
+    /// - `e` must be a repeated extension of the message type of `m`
+    pub fn upb_Message_GetExtensionArray(
+        m: RawMessage,
+        e: *const upb_MiniTableExtension,
+    ) -> Option<RawArray>;
+
+    /// Advances `iter` to the next extension that is set on `m`, walking them
+    /// in reverse order, and writes it to `result`. Returns false once every
+    /// extension has been visited. `iter` must start out as zero.
+    ///
+    /// # Safety
+    /// - `m`, `result` and `iter` must be valid to deref
+    pub fn upb_Message_NextExtensionReverse(
+        m: RawMessage,
+        result: *mut *const upb_MiniTableExtension,
+        iter: *mut usize,
+    ) -> bool;
+
+    /// Returns the unknown fields of `m` as the wire format data they were
+    /// parsed from, and sets `len` to its length.
+    ///
+    /// # Safety
+    /// - `m` must be valid to deref
+    pub fn upb_Message_GetUnknown(m: RawMessage, len: *mut usize) -> *const u8;
+
+    /// Removes the unknown fields of `m`, but not those of its submessages.
+    ///
+    /// # Safety
+    /// - `m` must be valid to deref
+    /// - `m` must not be frozen
+    pub fn _upb_Message_DiscardUnknown_shallow(m: RawMessage);
 }
 
 #[cfg(test)]
@@ -343,6 +483,22 @@ mod tests {
         assert_linked!(upb_Message_SetBaseFieldDouble);
         assert_linked!(upb_Message_SetBaseFieldString);
         assert_linked!(upb_Message_SetBaseFieldMessage);
+        assert_linked!(upb_Message_HasExtension);
+        assert_linked!(upb_Message_ClearExtension);
+        assert_linked!(upb_Message_SetExtension);
+        assert_linked!(upb_Message_GetExtensionBool);
+        assert_linked!(upb_Message_GetExtensionInt32);
+        assert_linked!(upb_Message_GetExtensionInt64);
+        assert_linked!(upb_Message_GetExtensionUInt32);
+        assert_linked!(upb_Message_GetExtensionUInt64);
+        assert_linked!(upb_Message_GetExtensionFloat);
+        assert_linked!(upb_Message_GetExtensionDouble);
+        assert_linked!(upb_Message_GetExtensionString);
+        assert_linked!(upb_Message_GetExtensionMessage);
+        assert_linked!(upb_Message_GetExtensionArray);
+        assert_linked!(upb_Message_NextExtensionReverse);
         assert_linked!(upb_Message_WhichOneofFieldNumber);
+        assert_linked!(upb_Message_GetUnknown);
+        assert_linked!(_upb_Message_DiscardUnknown_shallow);
     }
 }
@@ -6,6 +6,7 @@
 // https://developers.google.com/open-source/licenses/bsd
 
 use super::opaque_pointee::opaque_pointee;
+use super::CType;
 use core::ptr::NonNull;
 
 opaque_pointee!(upb_MiniTable);
@@ -14,6 +15,11 @@ pub type RawMiniTable = NonNull<upb_MiniTable>;
 opaque_pointee!(upb_MiniTableField);
 pub type RawMiniTableField = NonNull<upb_MiniTableField>;
 
+opaque_pointee!(upb_MiniTableExtension);
+pub type RawMiniTableExtension = NonNull<upb_MiniTableExtension>;
+
+opaque_pointee!(upb_MiniTableFile);
+
 extern "C" {
     /// Finds the field with the provided number, will return NULL if no such
     /// field is found.
@@ -45,6 +51,50 @@ extern "C" {
         m: *const upb_MiniTable,
         f: *const upb_MiniTableField,
     ) -> *const upb_MiniTable;
+
+    /// Returns the number of fields in `m`.
+    ///
+    /// # Safety
+    /// - `m` must be legal to deref
+    pub fn upb_MiniTable_FieldCount(m: *const upb_MiniTable) -> i32;
+
+    /// Returns the MiniTable of the entries of the map field `f`.
+    ///
+    /// # Safety
+    /// - `m` and `f` must be valid to deref
+    /// - `f` must be a map field associated with `m`
+    pub fn upb_MiniTable_MapEntrySubMessage(
+        m: *const upb_MiniTable,
+        f: *const upb_MiniTableField,
+    ) -> *const upb_MiniTable;
+
+    /// Returns the value field of the map entry MiniTable `m`.
+    ///
+    /// # Safety
+    /// - `m` must be legal to deref
+    /// - `m` must be the MiniTable of a map entry
+    pub fn upb_MiniTable_MapValue(m: *const upb_MiniTable) -> *const upb_MiniTableField;
+
+    /// Returns the MiniTable of the message type of the extension `e`.
+    ///
+    /// # Safety
+    /// - `e` must be legal to deref
+    /// - `e` must be a message typed extension
+    pub fn upb_MiniTableExtension_GetSubMessage(
+        e: *const upb_MiniTableExtension,
+    ) -> *const upb_MiniTable;
+
+    /// # Safety
+    /// - `f` must be legal to deref
+    pub fn upb_MiniTableField_CType(f: *const upb_MiniTableField) -> CType;
+
+    /// # Safety
+    /// - `f` must be legal to deref
+    pub fn upb_MiniTableField_IsArray(f: *const upb_MiniTableField) -> bool;
+
+    /// # Safety
+    /// - `f` must be legal to deref
+    pub fn upb_MiniTableField_IsMap(f: *const upb_MiniTableField) -> bool;
 }
 
 #[cfg(test)]
@@ -58,5 +108,12 @@ mod tests {
         assert_linked!(upb_MiniTable_FindFieldByNumber);
         assert_linked!(upb_MiniTable_GetFieldByIndex);
         assert_linked!(upb_MiniTable_SubMessage);
+        assert_linked!(upb_MiniTable_FieldCount);
+        assert_linked!(upb_MiniTable_MapEntrySubMessage);
+        assert_linked!(upb_MiniTable_MapValue);
+        assert_linked!(upb_MiniTableField_CType);
+        assert_linked!(upb_MiniTableField_IsArray);
+        assert_linked!(upb_MiniTableField_IsMap);
+        assert_linked!(upb_MiniTableExtension_GetSubMessage);
     }
 }
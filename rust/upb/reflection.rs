@@ -0,0 +1,251 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use super::opaque_pointee::opaque_pointee;
+use super::{
+    upb_ExtensionRegistry, upb_MessageValue, upb_MiniTable, upb_MiniTableExtension,
+    upb_MiniTableField, upb_MiniTableFile, RawMessage, StringView,
+};
+use core::ffi::c_char;
+use core::ptr::NonNull;
+
+opaque_pointee!(upb_DefPool);
+pub type RawDefPool = NonNull<upb_DefPool>;
+
+opaque_pointee!(upb_FileDef);
+opaque_pointee!(upb_MessageDef);
+opaque_pointee!(upb_FieldDef);
+opaque_pointee!(upb_OneofDef);
+opaque_pointee!(upb_EnumDef);
+opaque_pointee!(upb_EnumValueDef);
+
+/// ABI compatible struct with `_upb_DefPool_Init`, the compiled-in form of a
+/// `.proto` file that can be loaded into a `upb_DefPool`.
+#[repr(C)]
+pub struct _upb_DefPool_Init {
+    /// Null-terminated list of the files that this file imports.
+    pub deps: *const *const _upb_DefPool_Init,
+    /// The MiniTables generated for this file, which will be used by the
+    /// defs instead of building new ones.
+    pub layout: *const upb_MiniTableFile,
+    /// The NUL-terminated name of the file.
+    pub filename: *const c_char,
+    /// The serialized `FileDescriptorProto`. upb aliases these bytes, so they
+    /// must outlive the pool.
+    pub descriptor: StringView,
+}
+
+extern "C" {
+    pub fn upb_DefPool_New() -> Option<RawDefPool>;
+
+    /// # Safety
+    /// - `s` must be a live pool; no defs from it may be used afterwards
+    pub fn upb_DefPool_Free(s: RawDefPool);
+
+    /// Loads `init` and its dependencies into `s`, returning false if the
+    /// descriptor is malformed. Files that are already loaded are skipped.
+    ///
+    /// # Safety
+    /// - `s` and `init` must be valid to deref
+    /// - the descriptor referenced by `init` must outlive `s`
+    pub fn _upb_DefPool_LoadDefInit(s: RawDefPool, init: *const _upb_DefPool_Init) -> bool;
+
+    /// # Safety
+    /// - `s` must be valid to deref
+    /// - `name` must be valid to read for `size` bytes
+    pub fn upb_DefPool_FindMessageByNameWithSize(
+        s: RawDefPool,
+        name: *const u8,
+        size: usize,
+    ) -> *const upb_MessageDef;
+
+    /// # Safety
+    /// - `s` must be valid to deref
+    /// - `name` must be a NUL-terminated string
+    pub fn upb_DefPool_FindEnumByName(s: RawDefPool, name: *const c_char) -> *const upb_EnumDef;
+
+    /// # Safety
+    /// - `s` must be valid to deref
+    /// - `name` must be valid to read for `size` bytes
+    pub fn upb_DefPool_FindFileByNameWithSize(
+        s: RawDefPool,
+        name: *const u8,
+        size: usize,
+    ) -> *const upb_FileDef;
+
+    /// # Safety
+    /// - `s` must be valid to deref
+    /// - `name` must be valid to read for `size` bytes
+    pub fn upb_DefPool_FindExtensionByNameWithSize(
+        s: RawDefPool,
+        name: *const u8,
+        size: usize,
+    ) -> *const upb_FieldDef;
+
+    /// Returns the registry of every extension in `s`, which lives as long as
+    /// `s`.
+    ///
+    /// # Safety
+    /// - `s` must be valid to deref
+    pub fn upb_DefPool_ExtensionRegistry(s: RawDefPool) -> *const upb_ExtensionRegistry;
+
+    /// Returns the extension in `s` that was built with `ext`, or null if
+    /// there is none.
+    ///
+    /// # Safety
+    /// - `s` and `ext` must be valid to deref
+    pub fn upb_DefPool_FindExtensionByMiniTable(
+        s: RawDefPool,
+        ext: *const upb_MiniTableExtension,
+    ) -> *const upb_FieldDef;
+}
+
+// All of the accessors below require that the def they are passed is valid to
+// deref. Returned strings are NUL-terminated and live as long as the pool.
+extern "C" {
+    pub fn upb_FileDef_Name(f: *const upb_FileDef) -> *const c_char;
+    pub fn upb_FileDef_Pool(f: *const upb_FileDef) -> *const upb_DefPool;
+    pub fn upb_FileDef_Package(f: *const upb_FileDef) -> *const c_char;
+    pub fn upb_FileDef_DependencyCount(f: *const upb_FileDef) -> i32;
+    pub fn upb_FileDef_Dependency(f: *const upb_FileDef, i: i32) -> *const upb_FileDef;
+    pub fn upb_FileDef_TopLevelMessageCount(f: *const upb_FileDef) -> i32;
+    pub fn upb_FileDef_TopLevelMessage(f: *const upb_FileDef, i: i32) -> *const upb_MessageDef;
+    pub fn upb_FileDef_TopLevelEnumCount(f: *const upb_FileDef) -> i32;
+    pub fn upb_FileDef_TopLevelEnum(f: *const upb_FileDef, i: i32) -> *const upb_EnumDef;
+
+    pub fn upb_MessageDef_Name(m: *const upb_MessageDef) -> *const c_char;
+    pub fn upb_MessageDef_FullName(m: *const upb_MessageDef) -> *const c_char;
+    pub fn upb_MessageDef_File(m: *const upb_MessageDef) -> *const upb_FileDef;
+    pub fn upb_MessageDef_ContainingType(m: *const upb_MessageDef) -> *const upb_MessageDef;
+    pub fn upb_MessageDef_MiniTable(m: *const upb_MessageDef) -> *const upb_MiniTable;
+    pub fn upb_MessageDef_IsMapEntry(m: *const upb_MessageDef) -> bool;
+    pub fn upb_MessageDef_FieldCount(m: *const upb_MessageDef) -> i32;
+    pub fn upb_MessageDef_Field(m: *const upb_MessageDef, i: i32) -> *const upb_FieldDef;
+    pub fn upb_MessageDef_FindFieldByNumber(
+        m: *const upb_MessageDef,
+        number: u32,
+    ) -> *const upb_FieldDef;
+    /// # Safety
+    /// - `name` must be valid to read for `size` bytes
+    pub fn upb_MessageDef_FindFieldByNameWithSize(
+        m: *const upb_MessageDef,
+        name: *const u8,
+        size: usize,
+    ) -> *const upb_FieldDef;
+    pub fn upb_MessageDef_OneofCount(m: *const upb_MessageDef) -> i32;
+    /// Returns the number of oneofs that are not synthetic. Synthetic oneofs
+    /// are ordered after all real ones.
+    pub fn upb_MessageDef_RealOneofCount(m: *const upb_MessageDef) -> i32;
+    pub fn upb_MessageDef_Oneof(m: *const upb_MessageDef, i: i32) -> *const upb_OneofDef;
+    pub fn upb_MessageDef_NestedMessageCount(m: *const upb_MessageDef) -> i32;
+    pub fn upb_MessageDef_NestedMessage(m: *const upb_MessageDef, i: i32) -> *const upb_MessageDef;
+    pub fn upb_MessageDef_NestedEnumCount(m: *const upb_MessageDef) -> i32;
+    pub fn upb_MessageDef_NestedEnum(m: *const upb_MessageDef, i: i32) -> *const upb_EnumDef;
+
+    pub fn upb_FieldDef_Name(f: *const upb_FieldDef) -> *const c_char;
+    pub fn upb_FieldDef_FullName(f: *const upb_FieldDef) -> *const c_char;
+    pub fn upb_FieldDef_JsonName(f: *const upb_FieldDef) -> *const c_char;
+    pub fn upb_FieldDef_Number(f: *const upb_FieldDef) -> u32;
+    pub fn upb_FieldDef_Index(f: *const upb_FieldDef) -> u32;
+    /// Returns the `upb_FieldType`, whose values match
+    /// `FieldDescriptorProto.Type`.
+    pub fn upb_FieldDef_Type(f: *const upb_FieldDef) -> i32;
+    pub fn upb_FieldDef_IsRepeated(f: *const upb_FieldDef) -> bool;
+    pub fn upb_FieldDef_IsMap(f: *const upb_FieldDef) -> bool;
+    pub fn upb_FieldDef_HasPresence(f: *const upb_FieldDef) -> bool;
+    pub fn upb_FieldDef_IsRequired(f: *const upb_FieldDef) -> bool;
+    pub fn upb_FieldDef_IsExtension(f: *const upb_FieldDef) -> bool;
+    /// Returns whether `f` is a `string` field whose values must be UTF-8.
+    pub fn _upb_FieldDef_ValidateUtf8(f: *const upb_FieldDef) -> bool;
+    pub fn upb_FieldDef_ContainingType(f: *const upb_FieldDef) -> *const upb_MessageDef;
+    pub fn upb_FieldDef_ContainingOneof(f: *const upb_FieldDef) -> *const upb_OneofDef;
+    pub fn upb_FieldDef_RealContainingOneof(f: *const upb_FieldDef) -> *const upb_OneofDef;
+    pub fn upb_FieldDef_MessageSubDef(f: *const upb_FieldDef) -> *const upb_MessageDef;
+    pub fn upb_FieldDef_EnumSubDef(f: *const upb_FieldDef) -> *const upb_EnumDef;
+    /// Returns the MiniTable field that this field was built with.
+    pub fn upb_FieldDef_MiniTable(f: *const upb_FieldDef) -> *const upb_MiniTableField;
+    /// Returns the default value of a singular scalar, string or enum field.
+    /// Strings live as long as the pool.
+    pub fn upb_FieldDef_Default(f: *const upb_FieldDef) -> upb_MessageValue;
+
+    pub fn upb_OneofDef_Name(o: *const upb_OneofDef) -> *const c_char;
+    pub fn upb_OneofDef_FullName(o: *const upb_OneofDef) -> *const c_char;
+    pub fn upb_OneofDef_IsSynthetic(o: *const upb_OneofDef) -> bool;
+    pub fn upb_OneofDef_ContainingType(o: *const upb_OneofDef) -> *const upb_MessageDef;
+    pub fn upb_OneofDef_FieldCount(o: *const upb_OneofDef) -> i32;
+    pub fn upb_OneofDef_Field(o: *const upb_OneofDef, i: i32) -> *const upb_FieldDef;
+
+    pub fn upb_EnumDef_Name(e: *const upb_EnumDef) -> *const c_char;
+    pub fn upb_EnumDef_FullName(e: *const upb_EnumDef) -> *const c_char;
+    pub fn upb_EnumDef_File(e: *const upb_EnumDef) -> *const upb_FileDef;
+    pub fn upb_EnumDef_ContainingType(e: *const upb_EnumDef) -> *const upb_MessageDef;
+    pub fn upb_EnumDef_IsClosed(e: *const upb_EnumDef) -> bool;
+    pub fn upb_EnumDef_ValueCount(e: *const upb_EnumDef) -> i32;
+    pub fn upb_EnumDef_Value(e: *const upb_EnumDef, i: i32) -> *const upb_EnumValueDef;
+    pub fn upb_EnumDef_FindValueByNumber(
+        e: *const upb_EnumDef,
+        num: i32,
+    ) -> *const upb_EnumValueDef;
+
+    pub fn upb_EnumValueDef_Name(v: *const upb_EnumValueDef) -> *const c_char;
+    pub fn upb_EnumValueDef_FullName(v: *const upb_EnumValueDef) -> *const c_char;
+    pub fn upb_EnumValueDef_Number(v: *const upb_EnumValueDef) -> i32;
+    pub fn upb_EnumValueDef_Enum(v: *const upb_EnumValueDef) -> *const upb_EnumDef;
+}
+
+extern "C" {
+    /// Returns the value of `f` in `m`, or its default if it's unset. Unlike
+    /// the MiniTable accessors, this also reads extensions.
+    ///
+    /// # Safety
+    /// - `m` and `f` must be valid to deref
+    /// - `f` must be a field or an extension of the message type of `m`
+    pub fn upb_Message_GetFieldByDef(m: RawMessage, f: *const upb_FieldDef) -> upb_MessageValue;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[googletest::test]
+    fn assert_reflection_linked() {
+        use crate::assert_linked;
+        assert_linked!(upb_DefPool_New);
+        assert_linked!(upb_DefPool_Free);
+        assert_linked!(_upb_DefPool_LoadDefInit);
+        assert_linked!(upb_DefPool_FindMessageByNameWithSize);
+        assert_linked!(upb_DefPool_FindEnumByName);
+        assert_linked!(upb_DefPool_FindFileByNameWithSize);
+        assert_linked!(upb_DefPool_FindExtensionByNameWithSize);
+        assert_linked!(upb_DefPool_ExtensionRegistry);
+        assert_linked!(upb_DefPool_FindExtensionByMiniTable);
+        assert_linked!(upb_FileDef_Name);
+        assert_linked!(upb_FileDef_Pool);
+        assert_linked!(upb_MessageDef_FullName);
+        assert_linked!(upb_MessageDef_MiniTable);
+        assert_linked!(upb_MessageDef_FindFieldByNumber);
+        assert_linked!(upb_FieldDef_Type);
+        assert_linked!(upb_FieldDef_MiniTable);
+        assert_linked!(upb_FieldDef_Default);
+        assert_linked!(upb_FieldDef_IsRequired);
+        assert_linked!(upb_FieldDef_IsExtension);
+        assert_linked!(_upb_FieldDef_ValidateUtf8);
+        assert_linked!(upb_OneofDef_Field);
+        assert_linked!(upb_EnumDef_FindValueByNumber);
+        assert_linked!(upb_EnumValueDef_Number);
+        assert_linked!(upb_Message_GetFieldByDef);
+    }
+
+    #[googletest::test]
+    fn empty_pool_finds_nothing() {
+        let pool = unsafe { upb_DefPool_New() }.unwrap();
+        let name = "foo.Bar";
+        let msg = unsafe { upb_DefPool_FindMessageByNameWithSize(pool, name.as_ptr(), name.len()) };
+        assert!(msg.is_null());
+        unsafe { upb_DefPool_Free(pool) };
+    }
+}
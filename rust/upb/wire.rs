@@ -5,7 +5,10 @@
 // license that can be found in the LICENSE file or at
 // https://developers.google.com/open-source/licenses/bsd
 
-use super::{upb_ExtensionRegistry, upb_MiniTable, Arena, RawArena, RawMessage};
+use super::{
+    upb_ExtensionRegistry, upb_MiniTable, Arena, RawArena, RawExtensionRegistry, RawMessage,
+};
+use core::mem::MaybeUninit;
 
 // LINT.IfChange(encode_status)
 #[repr(C)]
@@ -15,6 +18,7 @@ pub enum EncodeStatus {
     OutOfMemory = 1,
     MaxDepthExceeded = 2,
     MissingRequired = 3,
+    BufferTooSmall = 4,
 }
 // LINT.ThenChange()
 
@@ -34,13 +38,27 @@ pub enum DecodeStatus {
 
 #[repr(i32)]
 #[allow(dead_code)]
-enum DecodeOption {
+pub enum EncodeOption {
+    Deterministic = 1,
+    SkipUnknown = 2,
+    CheckRequired = 4,
+}
+
+#[repr(i32)]
+#[allow(dead_code)]
+pub enum DecodeOption {
     AliasString = 1,
     CheckRequired = 2,
     ExperimentalAllowUnlinked = 4,
     AlwaysValidateUtf8 = 8,
 }
 
+/// Returns the decode option bits that limit the nesting of submessages to
+/// `max_depth` levels. Zero selects upb's default limit.
+pub const fn decode_max_depth(max_depth: u16) -> i32 {
+    (max_depth as i32) << 16
+}
+
 /// If Err, then EncodeStatus != Ok.
 ///
 /// # Safety
@@ -49,6 +67,22 @@ pub unsafe fn encode(
     msg: RawMessage,
     mini_table: *const upb_MiniTable,
 ) -> Result<Vec<u8>, EncodeStatus> {
+    // SAFETY: `mini_table` is the one associated with `msg`.
+    unsafe { encode_with(msg, mini_table, 0, <[u8]>::to_vec) }
+}
+
+/// Encodes `msg` with the given `options`, a bitwise OR of [`EncodeOption`]s,
+/// and passes the encoded bytes to `f`, without copying them out of the
+/// temporary arena they were encoded into. If Err, then EncodeStatus != Ok.
+///
+/// # Safety
+/// - `msg` must be associated with `mini_table`.
+pub unsafe fn encode_with<R>(
+    msg: RawMessage,
+    mini_table: *const upb_MiniTable,
+    options: i32,
+    f: impl FnOnce(&[u8]) -> R,
+) -> Result<R, EncodeStatus> {
     let arena = Arena::new();
     let mut buf: *mut u8 = core::ptr::null_mut();
     let mut len = 0usize;
@@ -56,17 +90,51 @@ pub unsafe fn encode(
     // SAFETY:
     // - `mini_table` is the one associated with `msg`.
     // - `buf` and `buf_size` are legally writable.
-    let status = unsafe { upb_Encode(msg, mini_table, 0, arena.raw(), &mut buf, &mut len) };
+    let status = unsafe { upb_Encode(msg, mini_table, options, arena.raw(), &mut buf, &mut len) };
 
     if status == EncodeStatus::Ok {
         assert!(!buf.is_null()); // EncodeStatus Ok should never return NULL data, even for len=0.
         // SAFETY: upb guarantees that `buf` is valid to read for `len`.
-        Ok(unsafe { &*core::ptr::slice_from_raw_parts(buf, len) }.to_vec())
+        Ok(f(unsafe { &*core::ptr::slice_from_raw_parts(buf, len) }))
     } else {
         Err(status)
     }
 }
 
+/// Encodes `msg` with the given `options`, a bitwise OR of [`EncodeOption`]s,
+/// directly into the front of `buf` and returns the number of bytes written,
+/// which are initialized. Fails with `EncodeStatus::BufferTooSmall` as soon as
+/// the output exceeds `buf`, in which case the contents of `buf` are
+/// unspecified.
+///
+/// # Safety
+/// - `msg` must be associated with `mini_table`.
+pub unsafe fn encode_into(
+    msg: RawMessage,
+    mini_table: *const upb_MiniTable,
+    options: i32,
+    buf: &mut [MaybeUninit<u8>],
+) -> Result<usize, EncodeStatus> {
+    let mut written = 0usize;
+    // SAFETY:
+    // - `mini_table` is the one associated with `msg`.
+    // - `buf` is legally writable for `buf.len()` bytes.
+    let status = unsafe {
+        upb_EncodeToBuffer(
+            msg,
+            mini_table,
+            options,
+            buf.as_mut_ptr().cast(),
+            buf.len(),
+            &mut written,
+        )
+    };
+    match status {
+        EncodeStatus::Ok => Ok(written),
+        _ => Err(status),
+    }
+}
+
 /// Decodes into the provided message (merge semantics). If Err, then
 /// DecodeStatus != Ok.
 ///
@@ -78,17 +146,41 @@ pub unsafe fn decode(
     msg: RawMessage,
     mini_table: *const upb_MiniTable,
     arena: &Arena,
+) -> Result<(), DecodeStatus> {
+    // SAFETY: the caller upholds the same requirements.
+    unsafe {
+        decode_with_options(buf, msg, mini_table, None, arena, DecodeOption::CheckRequired as i32)
+    }
+}
+
+/// Decodes into the provided message (merge semantics) with the given
+/// `options`, a bitwise OR of [`DecodeOption`]s and [`decode_max_depth`].
+/// Extensions found in `extreg` are decoded as extensions; all others are kept
+/// as unknown fields. If Err, then DecodeStatus != Ok.
+///
+/// # Safety
+/// - `msg` must be mutable.
+/// - `msg` must be associated with `mini_table`.
+/// - `extreg`, if present, must be valid to deref.
+/// - If `options` contains [`DecodeOption::AliasString`], `buf` must outlive
+///   every use of `msg`.
+pub unsafe fn decode_with_options(
+    buf: &[u8],
+    msg: RawMessage,
+    mini_table: *const upb_MiniTable,
+    extreg: Option<RawExtensionRegistry>,
+    arena: &Arena,
+    options: i32,
 ) -> Result<(), DecodeStatus> {
     let len = buf.len();
     let buf = buf.as_ptr();
-    let options = DecodeOption::CheckRequired as i32;
+    let extreg = extreg.map_or(core::ptr::null(), |r| r.as_ptr().cast_const());
 
     // SAFETY:
     // - `mini_table` is the one associated with `msg`
     // - `buf` is legally readable for at least `buf_size` bytes.
-    // - `extreg` is null.
-    let status =
-        unsafe { upb_Decode(buf, len, msg, mini_table, core::ptr::null(), options, arena.raw()) };
+    // - `extreg` is either null or valid to deref.
+    let status = unsafe { upb_Decode(buf, len, msg, mini_table, extreg, options, arena.raw()) };
     match status {
         DecodeStatus::Ok => Ok(()),
         _ => Err(status),
@@ -108,6 +200,19 @@ extern "C" {
         buf_size: *mut usize,
     ) -> EncodeStatus;
 
+    // SAFETY:
+    // - `mini_table` is the one associated with `msg`
+    // - `buf` is legally writable for `buf_size` bytes.
+    // - `written` is legally writable.
+    pub fn upb_EncodeToBuffer(
+        msg: RawMessage,
+        mini_table: *const upb_MiniTable,
+        options: i32,
+        buf: *mut u8,
+        buf_size: usize,
+        written: *mut usize,
+    ) -> EncodeStatus;
+
     // SAFETY:
     // - `mini_table` is the one associated with `msg`
     // - `buf` is legally readable for at least `buf_size` bytes.
@@ -126,12 +231,12 @@ extern "C" {
 #[cfg(test)]
 mod tests {
     use super::*;
-    
 
     #[googletest::test]
     fn assert_wire_linked() {
         use crate::assert_linked;
         assert_linked!(upb_Encode);
+        assert_linked!(upb_EncodeToBuffer);
         assert_linked!(upb_Decode);
     }
 }
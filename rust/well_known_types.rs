@@ -0,0 +1,1225 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Runtime support for the well-known types in `google/protobuf/*.proto`.
+//!
+//! The generated code of a well-known type invokes one of the macros below to
+//! implement conversions to and from the corresponding Rust types, since only
+//! the crate that defines a message may implement foreign traits such as
+//! `TryFrom<SystemTime>` for it. The macros are defined here rather than
+//! emitted by protoc so that the features of this crate, such as `chrono`,
+//! decide which conversions exist.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration as StdDuration, SystemTime, UNIX_EPOCH};
+
+/// The seconds of `0001-01-01T00:00:00Z`, the earliest valid `Timestamp`.
+const TIMESTAMP_MIN_SECONDS: i64 = -62_135_596_800;
+/// The seconds of `9999-12-31T23:59:59Z`, the latest valid `Timestamp`.
+const TIMESTAMP_MAX_SECONDS: i64 = 253_402_300_799;
+/// The seconds of the longest valid `Duration`, about 10,000 years.
+const DURATION_MAX_SECONDS: i64 = 315_576_000_000;
+const NANOS_PER_SECOND: i32 = 1_000_000_000;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// The reason that a conversion of a `google.protobuf.Timestamp` or
+/// `google.protobuf.Duration` failed.
+///
+/// A `Timestamp` is valid from `0001-01-01T00:00:00Z` to
+/// `9999-12-31T23:59:59.999999999Z` with `nanos` in `0..1_000_000_000`. A
+/// `Duration` is valid within about ±10,000 years, with `nanos` in
+/// `-999_999_999..=999_999_999` and of the same sign as `seconds`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TimeError {
+    /// The value is outside of the valid range of the well-known type, or of
+    /// the Rust type that it is converted to, such as a negative `Duration`
+    /// that is converted to a `std::time::Duration`.
+    OutOfRange,
+    /// The `nanos` field is outside of its valid range, or its sign differs
+    /// from that of `seconds`.
+    InvalidNanos,
+    /// The string is not a timestamp in the RFC 3339 format.
+    InvalidRfc3339,
+}
+
+impl fmt::Display for TimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            TimeError::OutOfRange => "time value is out of range",
+            TimeError::InvalidNanos => "nanos field is out of range or has the wrong sign",
+            TimeError::InvalidRfc3339 => "malformed RFC 3339 timestamp",
+        })
+    }
+}
+
+impl std::error::Error for TimeError {}
+
+/// The contents of a `google.protobuf.Value` as a native Rust value.
+///
+/// `Value`, `Struct` and `ListValue` convert to and from this type with
+/// `From`, which is easier to work with than the generated map and oneof
+/// accessors:
+///
+/// ```ignore
+/// let flags = Struct::from(BTreeMap::from([
+///     ("enabled".to_string(), StructValue::Bool(true)),
+///     ("ratio".to_string(), StructValue::Number(0.25)),
+/// ]));
+/// let fields = BTreeMap::<String, StructValue>::from(flags.as_view());
+/// ```
+///
+/// A `Value` with no kind set converts to [`StructValue::Null`]. With the
+/// `serde_json` feature, this type and the well-known types also convert to
+/// and from `serde_json::Value`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum StructValue {
+    /// A JSON `null`, the `null_value` of a `Value`.
+    #[default]
+    Null,
+    /// The `bool_value` of a `Value`.
+    Bool(bool),
+    /// The `number_value` of a `Value`.
+    Number(f64),
+    /// The `string_value` of a `Value`.
+    String(String),
+    /// The `list_value` of a `Value`.
+    List(Vec<StructValue>),
+    /// The `struct_value` of a `Value`, ordered by key.
+    Object(BTreeMap<String, StructValue>),
+}
+
+#[cfg(feature = "serde_json")]
+impl From<StructValue> for serde_json::Value {
+    /// Converts `value` to JSON. A number that is NaN or infinite becomes
+    /// `null`, since JSON can't represent it.
+    fn from(value: StructValue) -> Self {
+        match value {
+            StructValue::Null => serde_json::Value::Null,
+            StructValue::Bool(b) => serde_json::Value::Bool(b),
+            StructValue::Number(n) => serde_json::Value::from(n),
+            StructValue::String(s) => serde_json::Value::String(s),
+            StructValue::List(values) => values.into_iter().map(Self::from).collect(),
+            StructValue::Object(fields) => {
+                fields.into_iter().map(|(key, value)| (key, Self::from(value))).collect()
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl From<serde_json::Value> for StructValue {
+    /// Converts `value` from JSON. Numbers become `f64`, which loses the
+    /// precision of integers beyond 2^53.
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => StructValue::Null,
+            serde_json::Value::Bool(b) => StructValue::Bool(b),
+            serde_json::Value::Number(n) => StructValue::Number(n.as_f64().unwrap_or(f64::NAN)),
+            serde_json::Value::String(s) => StructValue::String(s),
+            serde_json::Value::Array(values) => {
+                StructValue::List(values.into_iter().map(Self::from).collect())
+            }
+            serde_json::Value::Object(fields) => StructValue::Object(
+                fields.into_iter().map(|(key, value)| (key, Self::from(value))).collect(),
+            ),
+        }
+    }
+}
+
+/// Checks that `seconds` and `nanos` form a valid `Timestamp`.
+pub fn check_timestamp(seconds: i64, nanos: i32) -> Result<(), TimeError> {
+    if !(0..NANOS_PER_SECOND).contains(&nanos) {
+        return Err(TimeError::InvalidNanos);
+    }
+    if !(TIMESTAMP_MIN_SECONDS..=TIMESTAMP_MAX_SECONDS).contains(&seconds) {
+        return Err(TimeError::OutOfRange);
+    }
+    Ok(())
+}
+
+/// Checks that `seconds` and `nanos` form a valid `Duration`.
+pub fn check_duration(seconds: i64, nanos: i32) -> Result<(), TimeError> {
+    if nanos <= -NANOS_PER_SECOND
+        || nanos >= NANOS_PER_SECOND
+        || (seconds > 0 && nanos < 0)
+        || (seconds < 0 && nanos > 0)
+    {
+        return Err(TimeError::InvalidNanos);
+    }
+    if !(-DURATION_MAX_SECONDS..=DURATION_MAX_SECONDS).contains(&seconds) {
+        return Err(TimeError::OutOfRange);
+    }
+    Ok(())
+}
+
+pub fn timestamp_to_system_time(seconds: i64, nanos: i32) -> Result<SystemTime, TimeError> {
+    check_timestamp(seconds, nanos)?;
+    let nanos = StdDuration::from_nanos(nanos as u64);
+    let time = if seconds >= 0 {
+        UNIX_EPOCH.checked_add(StdDuration::from_secs(seconds as u64) + nanos)
+    } else {
+        // `nanos` counts forward in time even before the epoch.
+        UNIX_EPOCH
+            .checked_sub(StdDuration::from_secs(seconds.unsigned_abs()))
+            .and_then(|time| time.checked_add(nanos))
+    };
+    time.ok_or(TimeError::OutOfRange)
+}
+
+pub fn system_time_to_timestamp(time: SystemTime) -> Result<(i64, i32), TimeError> {
+    let (seconds, nanos) = match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => (
+            i64::try_from(after.as_secs()).map_err(|_| TimeError::OutOfRange)?,
+            after.subsec_nanos() as i32,
+        ),
+        Err(e) => {
+            let before = e.duration();
+            let seconds = i64::try_from(before.as_secs()).map_err(|_| TimeError::OutOfRange)?;
+            match before.subsec_nanos() as i32 {
+                0 => (-seconds, 0),
+                nanos => (-seconds - 1, NANOS_PER_SECOND - nanos),
+            }
+        }
+    };
+    check_timestamp(seconds, nanos).map(|()| (seconds, nanos))
+}
+
+pub fn duration_to_std(seconds: i64, nanos: i32) -> Result<StdDuration, TimeError> {
+    check_duration(seconds, nanos)?;
+    if seconds < 0 || nanos < 0 {
+        return Err(TimeError::OutOfRange);
+    }
+    Ok(StdDuration::new(seconds as u64, nanos as u32))
+}
+
+pub fn std_to_duration(duration: StdDuration) -> Result<(i64, i32), TimeError> {
+    let seconds = i64::try_from(duration.as_secs()).map_err(|_| TimeError::OutOfRange)?;
+    let nanos = duration.subsec_nanos() as i32;
+    check_duration(seconds, nanos).map(|()| (seconds, nanos))
+}
+
+/// Formats a `Timestamp` like the JSON mapping does: in UTC, with 0, 3, 6 or
+/// 9 fractional digits.
+pub fn format_rfc3339(seconds: i64, nanos: i32) -> Result<String, TimeError> {
+    check_timestamp(seconds, nanos)?;
+    let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+    let time_of_day = seconds.rem_euclid(SECONDS_PER_DAY);
+    let (hour, minute, second) = (time_of_day / 3600, time_of_day / 60 % 60, time_of_day % 60);
+    let fraction = if nanos == 0 {
+        String::new()
+    } else if nanos % 1_000_000 == 0 {
+        format!(".{:03}", nanos / 1_000_000)
+    } else if nanos % 1_000 == 0 {
+        format!(".{:06}", nanos / 1_000)
+    } else {
+        format!(".{:09}", nanos)
+    };
+    Ok(format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}{fraction}Z"))
+}
+
+/// Parses a timestamp in the RFC 3339 format, such as
+/// `1972-01-01T10:00:20.021-05:00`, into the fields of a `Timestamp`.
+pub fn parse_rfc3339(text: &str) -> Result<(i64, i32), TimeError> {
+    let (seconds, nanos) =
+        parse_rfc3339_fields(text.as_bytes()).ok_or(TimeError::InvalidRfc3339)?;
+    check_timestamp(seconds, nanos).map(|()| (seconds, nanos))
+}
+
+fn parse_rfc3339_fields(text: &[u8]) -> Option<(i64, i32)> {
+    let separator_at = |i: usize, allowed: &[u8]| text.get(i).is_some_and(|c| allowed.contains(c));
+    if !(separator_at(4, b"-")
+        && separator_at(7, b"-")
+        && separator_at(10, b"Tt")
+        && separator_at(13, b":")
+        && separator_at(16, b":"))
+    {
+        return None;
+    }
+    let field = |start: usize, len: usize| parse_digits(text.get(start..start + len)?);
+    let (year, month, day) = (field(0, 4)?, field(5, 2)?, field(8, 2)?);
+    let (hour, minute, second) = (field(11, 2)?, field(14, 2)?, field(17, 2)?);
+    // Leap seconds can't be represented, so `second` may not be 60.
+    if !(1..=12).contains(&month)
+        || day < 1
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return None;
+    }
+
+    let mut rest = &text[19..];
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        let len = fraction.iter().take_while(|c| c.is_ascii_digit()).count();
+        if !(1..=9).contains(&len) {
+            return None;
+        }
+        nanos = parse_digits(&fraction[..len])? as i32 * 10_i32.pow(9 - len as u32);
+        rest = &fraction[len..];
+    }
+    let offset = match rest {
+        b"Z" | b"z" => 0,
+        [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+            let (hours, minutes) = (parse_digits(&[*h1, *h2])?, parse_digits(&[*m1, *m2])?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = hours * 3600 + minutes * 60;
+            if *sign == b'-' {
+                -offset
+            } else {
+                offset
+            }
+        }
+        _ => return None,
+    };
+    let seconds =
+        days_from_civil(year, month, day) * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second
+            - offset;
+    Some((seconds, nanos))
+}
+
+/// Parses a non-empty run of ASCII digits.
+fn parse_digits(digits: &[u8]) -> Option<i64> {
+    if digits.is_empty() {
+        return None;
+    }
+    digits
+        .iter()
+        .try_fold(0_i64, |value, c| c.is_ascii_digit().then(|| value * 10 + i64::from(c - b'0')))
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of days from 1970-01-01 to the given date of the
+/// proleptic Gregorian calendar.
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The inverse of [`days_from_civil`].
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Returns whether `type_url`, the type URL of an `Any`, names the message
+/// type `full_name`. Only the part after the last `/` is significant.
+pub fn any_type_url_matches(type_url: &[u8], full_name: &str) -> bool {
+    type_url.strip_suffix(full_name.as_bytes()).is_some_and(|prefix| prefix.ends_with(b"/"))
+}
+
+#[cfg(feature = "chrono")]
+pub fn timestamp_to_chrono(
+    seconds: i64,
+    nanos: i32,
+) -> Result<chrono::DateTime<chrono::Utc>, TimeError> {
+    check_timestamp(seconds, nanos)?;
+    chrono::DateTime::from_timestamp(seconds, nanos as u32).ok_or(TimeError::OutOfRange)
+}
+
+#[cfg(feature = "chrono")]
+pub fn chrono_to_timestamp<Tz: chrono::TimeZone>(
+    time: &chrono::DateTime<Tz>,
+) -> Result<(i64, i32), TimeError> {
+    // chrono represents a leap second with `nanos` past one second, which is
+    // rejected as invalid.
+    let (seconds, nanos) = (time.timestamp(), time.timestamp_subsec_nanos() as i32);
+    check_timestamp(seconds, nanos).map(|()| (seconds, nanos))
+}
+
+#[cfg(feature = "chrono")]
+pub fn duration_to_chrono(seconds: i64, nanos: i32) -> Result<chrono::TimeDelta, TimeError> {
+    check_duration(seconds, nanos)?;
+    // `TimeDelta::new` expects the nanoseconds to count forward in time.
+    let (seconds, nanos) =
+        if nanos < 0 { (seconds - 1, nanos + NANOS_PER_SECOND) } else { (seconds, nanos) };
+    chrono::TimeDelta::new(seconds, nanos as u32).ok_or(TimeError::OutOfRange)
+}
+
+#[cfg(feature = "chrono")]
+pub fn chrono_to_duration(duration: chrono::TimeDelta) -> Result<(i64, i32), TimeError> {
+    let (seconds, nanos) = (duration.num_seconds(), duration.subsec_nanos());
+    check_duration(seconds, nanos).map(|()| (seconds, nanos))
+}
+
+#[cfg(feature = "time")]
+pub fn timestamp_to_time(seconds: i64, nanos: i32) -> Result<time::OffsetDateTime, TimeError> {
+    check_timestamp(seconds, nanos)?;
+    let nanos = i128::from(seconds) * i128::from(NANOS_PER_SECOND) + i128::from(nanos);
+    time::OffsetDateTime::from_unix_timestamp_nanos(nanos).map_err(|_| TimeError::OutOfRange)
+}
+
+#[cfg(feature = "time")]
+pub fn time_to_timestamp(time: time::OffsetDateTime) -> Result<(i64, i32), TimeError> {
+    let (seconds, nanos) = (time.unix_timestamp(), time.nanosecond() as i32);
+    check_timestamp(seconds, nanos).map(|()| (seconds, nanos))
+}
+
+#[cfg(feature = "time")]
+pub fn duration_to_time(seconds: i64, nanos: i32) -> Result<time::Duration, TimeError> {
+    check_duration(seconds, nanos)?;
+    Ok(time::Duration::new(seconds, nanos))
+}
+
+#[cfg(feature = "time")]
+pub fn time_to_duration(duration: time::Duration) -> Result<(i64, i32), TimeError> {
+    let (seconds, nanos) = (duration.whole_seconds(), duration.subsec_nanoseconds());
+    check_duration(seconds, nanos).map(|()| (seconds, nanos))
+}
+
+/// Implements the conversions of `google.protobuf.Timestamp`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_timestamp {
+    ($msg:ident, $view:ident) => {
+        impl $msg {
+            /// Formats this timestamp in the RFC 3339 format in UTC, such as
+            /// `1972-01-01T10:00:20.021Z`, with 0, 3, 6 or 9 fractional
+            /// digits as needed.
+            pub fn to_rfc3339(
+                &self,
+            ) -> ::std::result::Result<::std::string::String, $crate::TimeError> {
+                self.as_view().to_rfc3339()
+            }
+
+            /// Parses a timestamp in the RFC 3339 format, such as
+            /// `1972-01-01T10:00:20.021-05:00`. The offset from UTC is applied
+            /// and then discarded.
+            pub fn from_rfc3339(text: &str) -> ::std::result::Result<Self, $crate::TimeError> {
+                let (seconds, nanos) = $crate::__internal::wkt::parse_rfc3339(text)?;
+                let mut msg = Self::new();
+                msg.set_seconds(seconds);
+                msg.set_nanos(nanos);
+                ::std::result::Result::Ok(msg)
+            }
+        }
+
+        impl $view<'_> {
+            /// Formats this timestamp in the RFC 3339 format in UTC, such as
+            /// `1972-01-01T10:00:20.021Z`, with 0, 3, 6 or 9 fractional
+            /// digits as needed.
+            pub fn to_rfc3339(
+                &self,
+            ) -> ::std::result::Result<::std::string::String, $crate::TimeError> {
+                $crate::__internal::wkt::format_rfc3339(self.seconds(), self.nanos())
+            }
+        }
+
+        impl ::std::convert::TryFrom<$view<'_>> for ::std::time::SystemTime {
+            type Error = $crate::TimeError;
+
+            fn try_from(timestamp: $view<'_>) -> ::std::result::Result<Self, Self::Error> {
+                $crate::__internal::wkt::timestamp_to_system_time(
+                    timestamp.seconds(),
+                    timestamp.nanos(),
+                )
+            }
+        }
+
+        impl ::std::convert::TryFrom<$msg> for ::std::time::SystemTime {
+            type Error = $crate::TimeError;
+
+            fn try_from(timestamp: $msg) -> ::std::result::Result<Self, Self::Error> {
+                Self::try_from(timestamp.as_view())
+            }
+        }
+
+        impl ::std::convert::TryFrom<::std::time::SystemTime> for $msg {
+            type Error = $crate::TimeError;
+
+            fn try_from(time: ::std::time::SystemTime) -> ::std::result::Result<Self, Self::Error> {
+                let (seconds, nanos) = $crate::__internal::wkt::system_time_to_timestamp(time)?;
+                let mut msg = Self::new();
+                msg.set_seconds(seconds);
+                msg.set_nanos(nanos);
+                ::std::result::Result::Ok(msg)
+            }
+        }
+
+        $crate::__impl_timestamp_chrono!($msg, $view);
+        $crate::__impl_timestamp_time!($msg, $view);
+    };
+}
+
+/// Implements the conversions of `google.protobuf.Duration`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_duration {
+    ($msg:ident, $view:ident) => {
+        impl ::std::convert::TryFrom<$view<'_>> for ::std::time::Duration {
+            type Error = $crate::TimeError;
+
+            fn try_from(duration: $view<'_>) -> ::std::result::Result<Self, Self::Error> {
+                $crate::__internal::wkt::duration_to_std(duration.seconds(), duration.nanos())
+            }
+        }
+
+        impl ::std::convert::TryFrom<$msg> for ::std::time::Duration {
+            type Error = $crate::TimeError;
+
+            fn try_from(duration: $msg) -> ::std::result::Result<Self, Self::Error> {
+                Self::try_from(duration.as_view())
+            }
+        }
+
+        impl ::std::convert::TryFrom<::std::time::Duration> for $msg {
+            type Error = $crate::TimeError;
+
+            fn try_from(
+                duration: ::std::time::Duration,
+            ) -> ::std::result::Result<Self, Self::Error> {
+                let (seconds, nanos) = $crate::__internal::wkt::std_to_duration(duration)?;
+                let mut msg = Self::new();
+                msg.set_seconds(seconds);
+                msg.set_nanos(nanos);
+                ::std::result::Result::Ok(msg)
+            }
+        }
+
+        $crate::__impl_duration_chrono!($msg, $view);
+        $crate::__impl_duration_time!($msg, $view);
+    };
+}
+
+/// Implements the packing and unpacking of `google.protobuf.Any`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_any {
+    ($msg:ident, $view:ident) => {
+        impl $msg {
+            /// Packs `msg` into a new `Any` with the type URL
+            /// [`M::TYPE_URL`]($crate::MessageName::TYPE_URL).
+            pub fn pack<M: $crate::Message>(
+                msg: &M,
+            ) -> ::std::result::Result<Self, $crate::SerializeError> {
+                let mut any = Self::new();
+                any.set_type_url(<M as $crate::MessageName>::TYPE_URL);
+                any.set_value($crate::Serialize::serialize(msg)?);
+                ::std::result::Result::Ok(any)
+            }
+
+            /// Returns whether this `Any` holds a message of the type `M`.
+            pub fn is<M: $crate::Message>(&self) -> bool {
+                self.as_view().is::<M>()
+            }
+
+            /// Parses the message in this `Any` if it is of the type `M`, and
+            /// returns `None` if it is of another type.
+            pub fn unpack<M: $crate::Message>(
+                &self,
+            ) -> ::std::result::Result<::std::option::Option<M>, $crate::ParseError> {
+                self.as_view().unpack::<M>()
+            }
+
+            /// Parses the message in this `Any` as the type in `registry` that
+            /// its type URL names, and returns `None` if there is no such type.
+            pub fn unpack_dynamic(
+                &self,
+                registry: &$crate::TypeRegistry,
+            ) -> ::std::result::Result<
+                ::std::option::Option<$crate::reflect::DynamicMessage>,
+                $crate::ParseError,
+            > {
+                self.as_view().unpack_dynamic(registry)
+            }
+        }
+
+        impl $view<'_> {
+            /// Returns whether this `Any` holds a message of the type `M`.
+            pub fn is<M: $crate::Message>(&self) -> bool {
+                $crate::__internal::wkt::any_type_url_matches(
+                    self.type_url().as_bytes(),
+                    <M as $crate::MessageName>::FULL_NAME,
+                )
+            }
+
+            /// Parses the message in this `Any` if it is of the type `M`, and
+            /// returns `None` if it is of another type.
+            pub fn unpack<M: $crate::Message>(
+                &self,
+            ) -> ::std::result::Result<::std::option::Option<M>, $crate::ParseError> {
+                if !self.is::<M>() {
+                    return ::std::result::Result::Ok(::std::option::Option::None);
+                }
+                <M as $crate::Parse>::parse(self.value()).map(::std::option::Option::Some)
+            }
+
+            /// Parses the message in this `Any` as the type in `registry` that
+            /// its type URL names, and returns `None` if there is no such type.
+            pub fn unpack_dynamic(
+                &self,
+                registry: &$crate::TypeRegistry,
+            ) -> ::std::result::Result<
+                ::std::option::Option<$crate::reflect::DynamicMessage>,
+                $crate::ParseError,
+            > {
+                match self.type_url().to_str() {
+                    ::std::result::Result::Ok(type_url) => registry.unpack(type_url, self.value()),
+                    ::std::result::Result::Err(_) => {
+                        ::std::result::Result::Ok(::std::option::Option::None)
+                    }
+                }
+            }
+        }
+    };
+}
+
+/// Implements the use of `google.protobuf.FieldMask` with the functions of
+/// [`field_mask`](crate::field_mask).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_field_mask {
+    ($msg:ident, $view:ident) => {
+        impl $crate::field_mask::AsFieldMask for $view<'_> {
+            fn to_paths(&self) -> ::std::vec::Vec<::std::string::String> {
+                self.paths().iter().map(|path| path.to_string()).collect()
+            }
+        }
+
+        impl $crate::field_mask::AsFieldMask for $msg {
+            fn to_paths(&self) -> ::std::vec::Vec<::std::string::String> {
+                self.as_view().to_paths()
+            }
+        }
+
+        impl<S: ::std::convert::AsRef<str>> ::std::iter::FromIterator<S> for $msg {
+            fn from_iter<I: ::std::iter::IntoIterator<Item = S>>(paths: I) -> Self {
+                let mut msg = Self::new();
+                msg.paths_mut().extend(paths.into_iter().map(|path| path.as_ref().to_string()));
+                msg
+            }
+        }
+    };
+}
+
+/// Implements the conversions of `google.protobuf.Struct`.
+///
+/// Like the other macros for `google/protobuf/struct.proto`, this expects to
+/// be invoked in the generated module of that file, where it names `Value`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_struct {
+    ($msg:ident, $view:ident) => {
+        impl ::std::convert::From<$view<'_>>
+            for ::std::collections::BTreeMap<::std::string::String, $crate::StructValue>
+        {
+            fn from(msg: $view<'_>) -> Self {
+                msg.fields()
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), $crate::StructValue::from(value)))
+                    .collect()
+            }
+        }
+
+        impl ::std::convert::From<$msg>
+            for ::std::collections::BTreeMap<::std::string::String, $crate::StructValue>
+        {
+            fn from(msg: $msg) -> Self {
+                Self::from(msg.as_view())
+            }
+        }
+
+        impl
+            ::std::convert::From<
+                ::std::collections::BTreeMap<::std::string::String, $crate::StructValue>,
+            > for $msg
+        {
+            fn from(
+                fields: ::std::collections::BTreeMap<::std::string::String, $crate::StructValue>,
+            ) -> Self {
+                let mut msg = Self::new();
+                let mut map = msg.fields_mut();
+                for (key, value) in fields {
+                    map.insert(key.as_str(), Value::from(value));
+                }
+                msg
+            }
+        }
+
+        $crate::__impl_struct_serde_json!($msg, $view);
+    };
+}
+
+/// Implements the conversions of `google.protobuf.Value`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_value {
+    ($msg:ident, $view:ident) => {
+        impl ::std::convert::From<$view<'_>> for $crate::StructValue {
+            fn from(msg: $view<'_>) -> Self {
+                match msg.kind() {
+                    value::Kind::NullValue(_) | value::Kind::not_set(_) => {
+                        $crate::StructValue::Null
+                    }
+                    value::Kind::NumberValue(n) => $crate::StructValue::Number(n),
+                    value::Kind::StringValue(s) => $crate::StructValue::String(s.to_string()),
+                    value::Kind::BoolValue(b) => $crate::StructValue::Bool(b),
+                    value::Kind::StructValue(s) => $crate::StructValue::Object(
+                        s.fields()
+                            .iter()
+                            .map(|(key, value)| (key.to_string(), Self::from(value)))
+                            .collect(),
+                    ),
+                    value::Kind::ListValue(l) => {
+                        $crate::StructValue::List(l.values().iter().map(Self::from).collect())
+                    }
+                }
+            }
+        }
+
+        impl ::std::convert::From<$msg> for $crate::StructValue {
+            fn from(msg: $msg) -> Self {
+                Self::from(msg.as_view())
+            }
+        }
+
+        impl ::std::convert::From<$crate::StructValue> for $msg {
+            fn from(value: $crate::StructValue) -> Self {
+                let mut msg = Self::new();
+                match value {
+                    $crate::StructValue::Null => {
+                        msg.set_null_value(::std::default::Default::default())
+                    }
+                    $crate::StructValue::Bool(b) => msg.set_bool_value(b),
+                    $crate::StructValue::Number(n) => msg.set_number_value(n),
+                    $crate::StructValue::String(s) => msg.set_string_value(s),
+                    $crate::StructValue::List(values) => {
+                        let mut list = msg.list_value_mut();
+                        let mut repeated = list.values_mut();
+                        for value in values {
+                            repeated.push(Self::from(value));
+                        }
+                    }
+                    $crate::StructValue::Object(fields) => {
+                        let mut object = msg.struct_value_mut();
+                        let mut map = object.fields_mut();
+                        for (key, value) in fields {
+                            map.insert(key.as_str(), Self::from(value));
+                        }
+                    }
+                }
+                msg
+            }
+        }
+
+        $crate::__impl_value_serde_json!($msg, $view);
+    };
+}
+
+/// Implements the conversions of `google.protobuf.ListValue`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_list_value {
+    ($msg:ident, $view:ident) => {
+        impl ::std::convert::From<$view<'_>> for ::std::vec::Vec<$crate::StructValue> {
+            fn from(msg: $view<'_>) -> Self {
+                msg.values().iter().map($crate::StructValue::from).collect()
+            }
+        }
+
+        impl ::std::convert::From<$msg> for ::std::vec::Vec<$crate::StructValue> {
+            fn from(msg: $msg) -> Self {
+                Self::from(msg.as_view())
+            }
+        }
+
+        impl ::std::convert::From<::std::vec::Vec<$crate::StructValue>> for $msg {
+            fn from(values: ::std::vec::Vec<$crate::StructValue>) -> Self {
+                let mut msg = Self::new();
+                let mut repeated = msg.values_mut();
+                for value in values {
+                    repeated.push(Value::from(value));
+                }
+                msg
+            }
+        }
+
+        $crate::__impl_list_value_serde_json!($msg, $view);
+    };
+}
+
+#[cfg(feature = "chrono")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_timestamp_chrono {
+    ($msg:ident, $view:ident) => {
+        impl ::std::convert::TryFrom<$view<'_>>
+            for $crate::__internal::chrono::DateTime<$crate::__internal::chrono::Utc>
+        {
+            type Error = $crate::TimeError;
+
+            fn try_from(timestamp: $view<'_>) -> ::std::result::Result<Self, Self::Error> {
+                $crate::__internal::wkt::timestamp_to_chrono(timestamp.seconds(), timestamp.nanos())
+            }
+        }
+
+        impl ::std::convert::TryFrom<$msg>
+            for $crate::__internal::chrono::DateTime<$crate::__internal::chrono::Utc>
+        {
+            type Error = $crate::TimeError;
+
+            fn try_from(timestamp: $msg) -> ::std::result::Result<Self, Self::Error> {
+                Self::try_from(timestamp.as_view())
+            }
+        }
+
+        impl<Tz: $crate::__internal::chrono::TimeZone>
+            ::std::convert::TryFrom<$crate::__internal::chrono::DateTime<Tz>> for $msg
+        {
+            type Error = $crate::TimeError;
+
+            fn try_from(
+                time: $crate::__internal::chrono::DateTime<Tz>,
+            ) -> ::std::result::Result<Self, Self::Error> {
+                let (seconds, nanos) = $crate::__internal::wkt::chrono_to_timestamp(&time)?;
+                let mut msg = Self::new();
+                msg.set_seconds(seconds);
+                msg.set_nanos(nanos);
+                ::std::result::Result::Ok(msg)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "chrono"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_timestamp_chrono {
+    ($msg:ident, $view:ident) => {};
+}
+
+#[cfg(feature = "chrono")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_duration_chrono {
+    ($msg:ident, $view:ident) => {
+        impl ::std::convert::TryFrom<$view<'_>> for $crate::__internal::chrono::TimeDelta {
+            type Error = $crate::TimeError;
+
+            fn try_from(duration: $view<'_>) -> ::std::result::Result<Self, Self::Error> {
+                $crate::__internal::wkt::duration_to_chrono(duration.seconds(), duration.nanos())
+            }
+        }
+
+        impl ::std::convert::TryFrom<$msg> for $crate::__internal::chrono::TimeDelta {
+            type Error = $crate::TimeError;
+
+            fn try_from(duration: $msg) -> ::std::result::Result<Self, Self::Error> {
+                Self::try_from(duration.as_view())
+            }
+        }
+
+        impl ::std::convert::TryFrom<$crate::__internal::chrono::TimeDelta> for $msg {
+            type Error = $crate::TimeError;
+
+            fn try_from(
+                duration: $crate::__internal::chrono::TimeDelta,
+            ) -> ::std::result::Result<Self, Self::Error> {
+                let (seconds, nanos) = $crate::__internal::wkt::chrono_to_duration(duration)?;
+                let mut msg = Self::new();
+                msg.set_seconds(seconds);
+                msg.set_nanos(nanos);
+                ::std::result::Result::Ok(msg)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "chrono"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_duration_chrono {
+    ($msg:ident, $view:ident) => {};
+}
+
+#[cfg(feature = "time")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_timestamp_time {
+    ($msg:ident, $view:ident) => {
+        impl ::std::convert::TryFrom<$view<'_>> for $crate::__internal::time::OffsetDateTime {
+            type Error = $crate::TimeError;
+
+            fn try_from(timestamp: $view<'_>) -> ::std::result::Result<Self, Self::Error> {
+                $crate::__internal::wkt::timestamp_to_time(timestamp.seconds(), timestamp.nanos())
+            }
+        }
+
+        impl ::std::convert::TryFrom<$msg> for $crate::__internal::time::OffsetDateTime {
+            type Error = $crate::TimeError;
+
+            fn try_from(timestamp: $msg) -> ::std::result::Result<Self, Self::Error> {
+                Self::try_from(timestamp.as_view())
+            }
+        }
+
+        impl ::std::convert::TryFrom<$crate::__internal::time::OffsetDateTime> for $msg {
+            type Error = $crate::TimeError;
+
+            fn try_from(
+                time: $crate::__internal::time::OffsetDateTime,
+            ) -> ::std::result::Result<Self, Self::Error> {
+                let (seconds, nanos) = $crate::__internal::wkt::time_to_timestamp(time)?;
+                let mut msg = Self::new();
+                msg.set_seconds(seconds);
+                msg.set_nanos(nanos);
+                ::std::result::Result::Ok(msg)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "time"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_timestamp_time {
+    ($msg:ident, $view:ident) => {};
+}
+
+#[cfg(feature = "time")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_duration_time {
+    ($msg:ident, $view:ident) => {
+        impl ::std::convert::TryFrom<$view<'_>> for $crate::__internal::time::Duration {
+            type Error = $crate::TimeError;
+
+            fn try_from(duration: $view<'_>) -> ::std::result::Result<Self, Self::Error> {
+                $crate::__internal::wkt::duration_to_time(duration.seconds(), duration.nanos())
+            }
+        }
+
+        impl ::std::convert::TryFrom<$msg> for $crate::__internal::time::Duration {
+            type Error = $crate::TimeError;
+
+            fn try_from(duration: $msg) -> ::std::result::Result<Self, Self::Error> {
+                Self::try_from(duration.as_view())
+            }
+        }
+
+        impl ::std::convert::TryFrom<$crate::__internal::time::Duration> for $msg {
+            type Error = $crate::TimeError;
+
+            fn try_from(
+                duration: $crate::__internal::time::Duration,
+            ) -> ::std::result::Result<Self, Self::Error> {
+                let (seconds, nanos) = $crate::__internal::wkt::time_to_duration(duration)?;
+                let mut msg = Self::new();
+                msg.set_seconds(seconds);
+                msg.set_nanos(nanos);
+                ::std::result::Result::Ok(msg)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "time"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_duration_time {
+    ($msg:ident, $view:ident) => {};
+}
+
+#[cfg(feature = "serde_json")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_struct_serde_json {
+    ($msg:ident, $view:ident) => {
+        impl ::std::convert::From<$view<'_>>
+            for $crate::__internal::serde_json::Map<
+                ::std::string::String,
+                $crate::__internal::serde_json::Value,
+            >
+        {
+            fn from(msg: $view<'_>) -> Self {
+                msg.fields()
+                    .iter()
+                    .map(|(key, value)| {
+                        (key.to_string(), $crate::__internal::serde_json::Value::from(value))
+                    })
+                    .collect()
+            }
+        }
+
+        impl ::std::convert::From<$msg>
+            for $crate::__internal::serde_json::Map<
+                ::std::string::String,
+                $crate::__internal::serde_json::Value,
+            >
+        {
+            fn from(msg: $msg) -> Self {
+                Self::from(msg.as_view())
+            }
+        }
+
+        impl
+            ::std::convert::From<
+                $crate::__internal::serde_json::Map<
+                    ::std::string::String,
+                    $crate::__internal::serde_json::Value,
+                >,
+            > for $msg
+        {
+            fn from(
+                fields: $crate::__internal::serde_json::Map<
+                    ::std::string::String,
+                    $crate::__internal::serde_json::Value,
+                >,
+            ) -> Self {
+                let mut msg = Self::new();
+                let mut map = msg.fields_mut();
+                for (key, value) in fields {
+                    map.insert(key.as_str(), Value::from(value));
+                }
+                msg
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "serde_json"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_struct_serde_json {
+    ($msg:ident, $view:ident) => {};
+}
+
+#[cfg(feature = "serde_json")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_value_serde_json {
+    ($msg:ident, $view:ident) => {
+        impl ::std::convert::From<$view<'_>> for $crate::__internal::serde_json::Value {
+            fn from(msg: $view<'_>) -> Self {
+                Self::from($crate::StructValue::from(msg))
+            }
+        }
+
+        impl ::std::convert::From<$msg> for $crate::__internal::serde_json::Value {
+            fn from(msg: $msg) -> Self {
+                Self::from(msg.as_view())
+            }
+        }
+
+        impl ::std::convert::From<$crate::__internal::serde_json::Value> for $msg {
+            fn from(value: $crate::__internal::serde_json::Value) -> Self {
+                Self::from($crate::StructValue::from(value))
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "serde_json"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_value_serde_json {
+    ($msg:ident, $view:ident) => {};
+}
+
+#[cfg(feature = "serde_json")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_list_value_serde_json {
+    ($msg:ident, $view:ident) => {
+        impl ::std::convert::From<$view<'_>>
+            for ::std::vec::Vec<$crate::__internal::serde_json::Value>
+        {
+            fn from(msg: $view<'_>) -> Self {
+                msg.values().iter().map($crate::__internal::serde_json::Value::from).collect()
+            }
+        }
+
+        impl ::std::convert::From<$msg> for ::std::vec::Vec<$crate::__internal::serde_json::Value> {
+            fn from(msg: $msg) -> Self {
+                Self::from(msg.as_view())
+            }
+        }
+
+        impl ::std::convert::From<::std::vec::Vec<$crate::__internal::serde_json::Value>> for $msg {
+            fn from(values: ::std::vec::Vec<$crate::__internal::serde_json::Value>) -> Self {
+                let mut msg = Self::new();
+                let mut repeated = msg.values_mut();
+                for value in values {
+                    repeated.push(Value::from(value));
+                }
+                msg
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "serde_json"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_list_value_serde_json {
+    ($msg:ident, $view:ident) => {};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use googletest::prelude::*;
+
+    #[googletest::test]
+    fn test_civil_days_round_trip() {
+        assert_that!(days_from_civil(1970, 1, 1), eq(0));
+        assert_that!(days_from_civil(2000, 3, 1), eq(11_017));
+        assert_that!(days_from_civil(1, 1, 1) * SECONDS_PER_DAY, eq(TIMESTAMP_MIN_SECONDS));
+        for days in [-719_162, -1, 0, 59, 60, 11_016, 2_932_896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_that!(days_from_civil(year, month, day), eq(days));
+        }
+    }
+
+    #[googletest::test]
+    fn test_format_rfc3339() {
+        assert_that!(format_rfc3339(0, 0), ok(eq("1970-01-01T00:00:00Z")));
+        assert_that!(format_rfc3339(63_108_020, 21_000_000), ok(eq("1972-01-01T10:00:20.021Z")));
+        assert_that!(format_rfc3339(-1, 1_000), ok(eq("1969-12-31T23:59:59.000001Z")));
+        assert_that!(format_rfc3339(0, 1), ok(eq("1970-01-01T00:00:00.000000001Z")));
+        assert_that!(format_rfc3339(TIMESTAMP_MIN_SECONDS, 0), ok(eq("0001-01-01T00:00:00Z")));
+        assert_that!(
+            format_rfc3339(TIMESTAMP_MAX_SECONDS, 999_999_999),
+            ok(eq("9999-12-31T23:59:59.999999999Z"))
+        );
+        assert_that!(format_rfc3339(TIMESTAMP_MAX_SECONDS + 1, 0), err(eq(TimeError::OutOfRange)));
+        assert_that!(format_rfc3339(0, -1), err(eq(TimeError::InvalidNanos)));
+    }
+
+    #[googletest::test]
+    fn test_parse_rfc3339() {
+        assert_that!(parse_rfc3339("1972-01-01T10:00:20.021Z"), ok(eq((63_108_020, 21_000_000))));
+        assert_that!(
+            parse_rfc3339("1972-01-01t05:00:20.021-05:00"),
+            ok(eq((63_108_020, 21_000_000)))
+        );
+        assert_that!(parse_rfc3339("1970-01-01T01:30:00+01:30"), ok(eq((0, 0))));
+        assert_that!(parse_rfc3339("1969-12-31T23:59:59.5z"), ok(eq((-1, 500_000_000))));
+        assert_that!(parse_rfc3339("2000-02-29T00:00:00Z"), ok(anything()));
+        assert_that!(parse_rfc3339("0001-01-01T00:00:00+00:01"), err(eq(TimeError::OutOfRange)));
+        for malformed in [
+            "",
+            "1970-01-01",
+            "1970-01-01 00:00:00Z",
+            "1970-01-01T00:00:00",
+            "1970-01-01T00:00:00.Z",
+            "1970-01-01T00:00:00.0000000001Z",
+            "1970-01-01T00:00:60Z",
+            "1970-13-01T00:00:00Z",
+            "1900-02-29T00:00:00Z",
+            "1970-01-01T00:00:00+1:00",
+            "1970-01-01T00:00:00+24:00",
+            "+970-01-01T00:00:00Z",
+        ] {
+            assert_that!(
+                parse_rfc3339(malformed),
+                err(eq(TimeError::InvalidRfc3339)),
+                "{malformed}"
+            );
+        }
+    }
+
+    #[googletest::test]
+    fn test_system_time() {
+        let time = timestamp_to_system_time(-2, 250_000_000).unwrap();
+        assert_that!(UNIX_EPOCH.duration_since(time).unwrap(), eq(StdDuration::from_millis(1_750)));
+        assert_that!(system_time_to_timestamp(time), ok(eq((-2, 250_000_000))));
+        let time = UNIX_EPOCH + StdDuration::new(5, 7);
+        assert_that!(system_time_to_timestamp(time), ok(eq((5, 7))));
+        assert_that!(timestamp_to_system_time(5, 7), ok(eq(time)));
+        assert_that!(
+            timestamp_to_system_time(0, NANOS_PER_SECOND),
+            err(eq(TimeError::InvalidNanos))
+        );
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[googletest::test]
+    fn test_struct_value_serde_json() {
+        let json = serde_json::json!({"a": [1.5, null, "x"], "b": {"c": true}});
+        let value = StructValue::from(json.clone());
+        assert_that!(
+            value,
+            eq(&StructValue::Object(BTreeMap::from([
+                (
+                    "a".to_string(),
+                    StructValue::List(vec![
+                        StructValue::Number(1.5),
+                        StructValue::Null,
+                        StructValue::String("x".to_string()),
+                    ])
+                ),
+                (
+                    "b".to_string(),
+                    StructValue::Object(BTreeMap::from([(
+                        "c".to_string(),
+                        StructValue::Bool(true)
+                    )]))
+                ),
+            ])))
+        );
+        assert_that!(serde_json::Value::from(value), eq(&json));
+        assert_that!(
+            serde_json::Value::from(StructValue::Number(f64::NAN)),
+            eq(&serde_json::Value::Null)
+        );
+    }
+
+    #[googletest::test]
+    fn test_any_type_url_matches() {
+        assert_that!(any_type_url_matches(b"type.googleapis.com/foo.Bar", "foo.Bar"), eq(true));
+        assert_that!(any_type_url_matches(b"example.com/a/b/foo.Bar", "foo.Bar"), eq(true));
+        assert_that!(any_type_url_matches(b"/foo.Bar", "foo.Bar"), eq(true));
+        assert_that!(any_type_url_matches(b"foo.Bar", "foo.Bar"), eq(false));
+        assert_that!(any_type_url_matches(b"type.googleapis.com/xfoo.Bar", "foo.Bar"), eq(false));
+        assert_that!(any_type_url_matches(b"type.googleapis.com/foo.Bar2", "foo.Bar"), eq(false));
+    }
+
+    #[googletest::test]
+    fn test_duration() {
+        assert_that!(check_duration(-1, -5), ok(anything()));
+        assert_that!(check_duration(0, -5), ok(anything()));
+        assert_that!(check_duration(1, -5), err(eq(TimeError::InvalidNanos)));
+        assert_that!(check_duration(-1, 5), err(eq(TimeError::InvalidNanos)));
+        assert_that!(check_duration(DURATION_MAX_SECONDS + 1, 0), err(eq(TimeError::OutOfRange)));
+
+        assert_that!(duration_to_std(3, 4), ok(eq(StdDuration::new(3, 4))));
+        assert_that!(duration_to_std(0, -1), err(eq(TimeError::OutOfRange)));
+        assert_that!(std_to_duration(StdDuration::new(3, 4)), ok(eq((3, 4))));
+        assert_that!(std_to_duration(StdDuration::MAX), err(eq(TimeError::OutOfRange)));
+    }
+}
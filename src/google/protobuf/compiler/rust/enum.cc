@@ -188,6 +188,8 @@ void GenerateEnumDefinition(Context& ctx, const EnumDescriptor& desc) {
           // The default value of an enum is the first listed value.
           // The compiler checks that this is equal to 0 for open enums.
           {"default_int_value", absl::StrCat(desc.value(0)->number())},
+          {"full_name", desc.full_name()},
+          {"file_descriptor", FileDescriptorStaticPath(ctx, *desc.file())},
           {"known_values_pattern",
            // TODO: Check validity in UPB/C++.
            absl::StrJoin(values, "|",
@@ -333,6 +335,22 @@ void GenerateEnumDefinition(Context& ctx, const EnumDescriptor& desc) {
           }
         }
 
+        unsafe fn repeated_swap_unchecked(
+            r: $pb$::Mut<$pb$::Repeated<Self>>,
+            a: usize,
+            b: usize,
+        ) {
+          // SAFETY: In-bounds as promised by the caller.
+          unsafe {
+            $pb$::ProxiedInRepeated::repeated_swap_unchecked(
+              $pbr$::cast_enum_repeated_mut(r), a, b)
+          }
+        }
+
+        fn repeated_truncate(r: $pb$::Mut<$pb$::Repeated<Self>>, len: usize) {
+          $pbr$::cast_enum_repeated_mut(r).truncate(len)
+        }
+
         fn repeated_copy_from(
             src: $pb$::View<$pb$::Repeated<Self>>,
             dest: $pb$::Mut<$pb$::Repeated<Self>>,
@@ -360,6 +378,58 @@ void GenerateEnumDefinition(Context& ctx, const EnumDescriptor& desc) {
         }
       }
 
+      impl $pb$::reflect::ReflectEnum for $name$ {
+        fn descriptor() -> $pb$::reflect::EnumDescriptor {
+          static DESCRIPTOR: $std$::sync::OnceLock<$pb$::reflect::EnumDescriptor> =
+              $std$::sync::OnceLock::new();
+          DESCRIPTOR.get_or_init(|| {
+            $pbr$::enum_descriptor(&$file_descriptor$, "$full_name$")
+          }).clone()
+        }
+      }
+
+      impl $pb$::ExtensionType for $name$ {
+        type Default = $name$;
+
+        fn default_view(_private: $pbi$::Private, default: $name$) -> $name$ {
+          default
+        }
+
+        unsafe fn get_extension<'msg>(
+            _private: $pbi$::Private,
+            msg: $pbr$::RawMessage,
+            ext: &$pbr$::InnerExtension,
+            default: $name$,
+        ) -> $pb$::View<'msg, $name$> {
+          // SAFETY:
+          // - `msg` is extended by `ext`, as promised by the caller.
+          // - Enum extensions are stored as their `i32` values, and the
+          //   parser keeps unknown values of closed enums as unknown fields.
+          $name$(unsafe {
+            <i32 as $pb$::ExtensionType>::get_extension(
+                $pbi$::Private, msg, ext, default.0)
+          })
+        }
+
+        unsafe fn set_extension(
+            _private: $pbi$::Private,
+            msg: $pbr$::MutatorMessageRef<'_>,
+            ext: &$pbr$::InnerExtension,
+            val: impl $pb$::IntoProxied<$name$>,
+        ) {
+          let val: $name$ = val.into_proxied($pbi$::Private);
+          // SAFETY:
+          // - `msg` is extended by `ext`, as promised by the caller.
+          // - Enum extensions are stored as their `i32` values.
+          unsafe {
+            <i32 as $pb$::ExtensionType>::set_extension(
+                $pbi$::Private, msg, ext, val.0)
+          }
+        }
+      }
+
+      $pbi$::impl_enum_serde!($name$);
+
       $type_conversions_impl$
       )rs");
 }
@@ -0,0 +1,321 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+#include "google/protobuf/compiler/rust/extension.h"
+
+#include <string>
+
+#include "absl/log/absl_check.h"
+#include "absl/strings/ascii.h"
+#include "absl/strings/str_cat.h"
+#include "google/protobuf/compiler/cpp/names.h"
+#include "google/protobuf/compiler/rust/accessors/accessor_case.h"
+#include "google/protobuf/compiler/rust/accessors/default_value.h"
+#include "google/protobuf/compiler/rust/context.h"
+#include "google/protobuf/compiler/rust/naming.h"
+#include "google/protobuf/compiler/rust/rust_field_type.h"
+#include "google/protobuf/compiler/rust/upb_helpers.h"
+#include "google/protobuf/descriptor.h"
+#include "google/protobuf/descriptor.pb.h"
+
+namespace google {
+namespace protobuf {
+namespace compiler {
+namespace rust {
+namespace {
+
+bool IsStringOrBytes(const FieldDescriptor& ext) {
+  RustFieldType type = GetRustFieldType(ext);
+  return type == RustFieldType::STRING || type == RustFieldType::BYTES;
+}
+
+std::string ExtensionRsName(const FieldDescriptor& ext) {
+  return RsSafeName(absl::AsciiStrToUpper(ext.name()));
+}
+
+void InnerExtension(Context& ctx, const FieldDescriptor& ext) {
+  switch (ctx.opts().kernel) {
+    case Kernel::kCpp:
+      ctx.Emit({{"has_thunk", ThunkName(ctx, ext, "has")},
+                {"clear_thunk", ThunkName(ctx, ext, "clear")},
+                {"get_thunk", ThunkName(ctx, ext, "get")},
+                {"set_thunk", ThunkName(ctx, ext, "set")},
+                {"descriptor_thunk", ThunkName(ctx, ext, "descriptor")}},
+               R"rs(
+                 extern "C" {
+                   fn $has_thunk$(raw_msg: $pbr$::RawMessage) -> bool;
+                   fn $clear_thunk$(raw_msg: $pbr$::RawMessage);
+                   fn $get_thunk$(raw_msg: $pbr$::RawMessage, out: *mut $std$::ffi::c_void);
+                   fn $set_thunk$(raw_msg: $pbr$::RawMessage, val: *const $std$::ffi::c_void);
+                   fn $descriptor_thunk$() -> *const $std$::ffi::c_void;
+                 }
+                 // SAFETY: the thunks access this extension and pass its
+                 // values as the kernel expects.
+                 let inner = unsafe {
+                   $pbr$::InnerExtension::new(
+                       $has_thunk$, $clear_thunk$, $get_thunk$, $set_thunk$, $descriptor_thunk$)
+                 };
+               )rs");
+      return;
+    case Kernel::kUpb:
+      ctx.Emit({{"minitable_symbol_name", UpbMiniTableExtensionName(ext)}},
+               R"rs(
+                 extern "C" {
+                   /// Opaque static extern for this extension's MiniTable,
+                   /// generated by the upb C MiniTable codegen. The only valid
+                   /// way to reference this static is with
+                   /// `std::ptr::addr_of!(..)`.
+                   static $minitable_symbol_name$: $pbr$::upb_MiniTableExtension;
+                 }
+                 // SAFETY: the MiniTable of the extension is a static.
+                 let inner = unsafe {
+                   $pbr$::InnerExtension::new(|| $std$::ptr::addr_of!($minitable_symbol_name$))
+                 };
+               )rs");
+      return;
+  }
+}
+
+}  // namespace
+
+void GenerateExtensionDefinition(Context& ctx, const FieldDescriptor& ext) {
+  ABSL_CHECK(ext.is_extension());
+  // Message and repeated extensions have no default value of their own: they
+  // default to the default instance and to an empty repeated field.
+  std::string default_value = "()";
+  if (!ext.is_repeated() && GetRustFieldType(ext) != RustFieldType::MESSAGE) {
+    default_value = DefaultValue(ctx, ext);
+    if (IsStringOrBytes(ext)) {
+      absl::StrAppend(&default_value, " as &[u8]");
+    }
+  }
+  std::string type = RsTypePath(ctx, ext);
+  if (ext.is_repeated()) {
+    type = absl::StrCat("::protobuf::Repeated<", type, ">");
+  }
+  ctx.Emit({{"name", ExtensionRsName(ext)},
+            {"Extendee", RsTypePath(ctx, *ext.containing_type())},
+            {"Type", type},
+            {"number", std::to_string(ext.number())},
+            {"default_value", default_value},
+            {"inner", [&] { InnerExtension(ctx, ext); }}},
+           R"rs(
+             #[allow(unused_unsafe)]
+             pub const $name$: $pb$::ExtensionId<$Extendee$, $Type$> = {
+               $inner$
+               // SAFETY: `inner` accesses this extension, which has the
+               // given number and default value.
+               unsafe { $pb$::ExtensionId::new($pbi$::Private, $number$, $default_value$, inner) }
+             };
+           )rs");
+}
+
+void GenerateExtensionThunksCc(Context& ctx, const FieldDescriptor& ext) {
+  ABSL_CHECK(ctx.is_cpp());
+  ABSL_CHECK(ext.is_extension());
+  ctx.Emit(
+      {{"abi", "\"C\""},  // Workaround for syntax highlight bug in VSCode.
+       {"ext", cpp::QualifiedExtensionName(&ext)},
+       {"QualifiedMsg", cpp::QualifiedClassName(ext.containing_type())},
+       {"has_thunk", ThunkName(ctx, ext, "has")},
+       {"clear_thunk", ThunkName(ctx, ext, "clear")},
+       {"get_thunk", ThunkName(ctx, ext, "get")},
+       {"set_thunk", ThunkName(ctx, ext, "set")},
+       {"descriptor_thunk", ThunkName(ctx, ext, "descriptor")},
+       {"full_name", ext.full_name()},
+       {"has",
+        // `HasExtension` is only defined for singular extensions.
+        ext.is_repeated()
+            ? absl::StrCat("msg->ExtensionSize(",
+                           cpp::QualifiedExtensionName(&ext), ") > 0")
+            : absl::StrCat("msg->HasExtension(",
+                           cpp::QualifiedExtensionName(&ext), ")")},
+       {"descriptor",
+        [&] {
+          // Extensions of lite files are not in the generated pool.
+          if (ext.file()->options().optimize_for() ==
+              FileOptions::LITE_RUNTIME) {
+            ctx.Emit("return nullptr;");
+            return;
+          }
+          ctx.Emit(R"cc(
+            static const auto* descriptor =
+                ::google::protobuf::DescriptorPool::generated_pool()->FindExtensionByName(
+                    "$full_name$");
+            return descriptor;
+          )cc");
+        }},
+       {"get_and_set",
+        [&] {
+          if (ext.is_repeated()) {
+            // The repeated field is passed by pointer. The C++ kernel stores
+            // repeated enums as a `RepeatedField<int32_t>`, as `ExtensionSet`
+            // does.
+            if (GetRustFieldType(ext) == RustFieldType::ENUM) {
+              ctx.Emit({{"Enum", cpp::QualifiedClassName(ext.enum_type())}},
+                       R"cc(
+                         void $get_thunk$(const $QualifiedMsg$* msg, void* out) {
+                           *static_cast<const void**>(out) =
+                               &msg->GetRepeatedExtension($ext$);
+                         }
+                         void $set_thunk$($QualifiedMsg$* msg, const void* val) {
+                           auto* field = msg->MutableRepeatedExtension($ext$);
+                           field->Clear();
+                           for (int32_t v : *static_cast<
+                                    const ::google::protobuf::RepeatedField<int32_t>*>(
+                                    val)) {
+                             field->Add(static_cast<$Enum$>(v));
+                           }
+                         }
+                       )cc");
+              return;
+            }
+            std::string container;
+            switch (GetRustFieldType(ext)) {
+              case RustFieldType::STRING:
+              case RustFieldType::BYTES:
+                container = "::google::protobuf::RepeatedPtrField<std::string>";
+                break;
+              case RustFieldType::MESSAGE:
+                container = absl::StrCat(
+                    "::google::protobuf::RepeatedPtrField<",
+                    cpp::QualifiedClassName(ext.message_type()), ">");
+                break;
+              default:
+                container = absl::StrCat("::google::protobuf::RepeatedField<",
+                                         cpp::PrimitiveTypeName(ext.cpp_type()),
+                                         ">");
+            }
+            ctx.Emit({{"Container", container}},
+                     R"cc(
+                       void $get_thunk$(const $QualifiedMsg$* msg, void* out) {
+                         *static_cast<const void**>(out) =
+                             &msg->GetRepeatedExtension($ext$);
+                       }
+                       void $set_thunk$($QualifiedMsg$* msg, const void* val) {
+                         *msg->MutableRepeatedExtension($ext$) =
+                             *static_cast<const $Container$*>(val);
+                       }
+                     )cc");
+          } else if (GetRustFieldType(ext) == RustFieldType::MESSAGE) {
+            // Messages are passed by pointer.
+            ctx.Emit({{"ExtMsg", cpp::QualifiedClassName(ext.message_type())}},
+                     R"cc(
+                       void $get_thunk$(const $QualifiedMsg$* msg, void* out) {
+                         *static_cast<const void**>(out) =
+                             &msg->GetExtension($ext$);
+                       }
+                       void $set_thunk$($QualifiedMsg$* msg, const void* val) {
+                         msg->MutableExtension($ext$)->CopyFrom(
+                             *static_cast<const $ExtMsg$*>(val));
+                       }
+                     )cc");
+          } else if (IsStringOrBytes(ext)) {
+            ctx.Emit(R"cc(
+              void $get_thunk$(const $QualifiedMsg$* msg, void* out) {
+                const std::string& val = msg->GetExtension($ext$);
+                *static_cast<::google::protobuf::rust::PtrAndLen*>(out) =
+                    ::google::protobuf::rust::PtrAndLen{val.data(), val.size()};
+              }
+              void $set_thunk$($QualifiedMsg$* msg, const void* val) {
+                msg->SetExtension(
+                    $ext$,
+                    static_cast<const ::google::protobuf::rust::PtrAndLen*>(val)
+                        ->CopyToString());
+              }
+            )cc");
+          } else if (GetRustFieldType(ext) == RustFieldType::ENUM) {
+            // Enum values are passed as their `int32_t` value.
+            ctx.Emit({{"Enum", cpp::QualifiedClassName(ext.enum_type())}},
+                     R"cc(
+                       void $get_thunk$(const $QualifiedMsg$* msg, void* out) {
+                         *static_cast<int32_t*>(out) = msg->GetExtension($ext$);
+                       }
+                       void $set_thunk$($QualifiedMsg$* msg, const void* val) {
+                         msg->SetExtension(
+                             $ext$, static_cast<$Enum$>(
+                                        *static_cast<const int32_t*>(val)));
+                       }
+                     )cc");
+          } else {
+            ctx.Emit({{"Scalar", cpp::PrimitiveTypeName(ext.cpp_type())}},
+                     R"cc(
+                       void $get_thunk$(const $QualifiedMsg$* msg, void* out) {
+                         *static_cast<$Scalar$*>(out) = msg->GetExtension($ext$);
+                       }
+                       void $set_thunk$($QualifiedMsg$* msg, const void* val) {
+                         msg->SetExtension($ext$,
+                                           *static_cast<const $Scalar$*>(val));
+                       }
+                     )cc");
+          }
+        }}},
+      R"cc(
+        // clang-format off
+        extern $abi$ {
+        bool $has_thunk$(const $QualifiedMsg$* msg) { return $has$; }
+        void $clear_thunk$($QualifiedMsg$* msg) { msg->ClearExtension($ext$); }
+        $get_and_set$
+        const void* $descriptor_thunk$() { $descriptor$ }
+        }  // extern $abi$
+        // clang-format on
+      )cc");
+}
+
+void GenerateExtensionAccessors(Context& ctx, const Descriptor& msg,
+                                AccessorCase accessor_case) {
+  if (msg.extension_range_count() == 0) {
+    return;
+  }
+  ctx.Emit({{"Msg", RsSafeName(msg.name())},
+            {"view_self", ViewReceiver(accessor_case)},
+            {"view_lifetime", ViewLifetime(accessor_case)},
+            {"mutators",
+             [&] {
+               if (accessor_case == AccessorCase::VIEW) {
+                 return;
+               }
+               ctx.Emit(R"rs(
+                 pub fn set_extension<T: $pb$::ExtensionType>(
+                     &mut self,
+                     ext: &$pb$::ExtensionId<$Msg$, T>,
+                     val: impl $pb$::IntoProxied<T>) {
+                   // SAFETY: `ext` is an extension of `$Msg$`.
+                   unsafe {
+                     ext.set($pbi$::Private, self.as_mutator_message_ref($pbi$::Private), val)
+                   }
+                 }
+
+                 pub fn clear_extension<T: $pb$::ExtensionType>(
+                     &mut self, ext: &$pb$::ExtensionId<$Msg$, T>) {
+                   // SAFETY: `ext` is an extension of `$Msg$`.
+                   unsafe { ext.clear($pbi$::Private, self.raw_msg()) }
+                 }
+               )rs");
+             }}},
+           R"rs(
+             pub fn has_extension<T: $pb$::ExtensionType>(
+                 $view_self$, ext: &$pb$::ExtensionId<$Msg$, T>) -> bool {
+               // SAFETY: `ext` is an extension of `$Msg$`.
+               unsafe { ext.has($pbi$::Private, self.raw_msg()) }
+             }
+
+             pub fn get_extension<T: $pb$::ExtensionType>(
+                 $view_self$, ext: &$pb$::ExtensionId<$Msg$, T>)
+                 -> $pb$::View<$view_lifetime$, T> {
+               // SAFETY: `ext` is an extension of `$Msg$`.
+               unsafe { ext.get($pbi$::Private, self.raw_msg()) }
+             }
+
+             $mutators$
+           )rs");
+}
+
+}  // namespace rust
+}  // namespace compiler
+}  // namespace protobuf
+}  // namespace google
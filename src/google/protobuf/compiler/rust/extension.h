@@ -0,0 +1,36 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2024 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+#ifndef GOOGLE_PROTOBUF_COMPILER_RUST_EXTENSION_H__
+#define GOOGLE_PROTOBUF_COMPILER_RUST_EXTENSION_H__
+
+#include "google/protobuf/compiler/rust/accessors/accessor_case.h"
+#include "google/protobuf/compiler/rust/context.h"
+#include "google/protobuf/descriptor.h"
+
+namespace google {
+namespace protobuf {
+namespace compiler {
+namespace rust {
+
+// Generates the `ExtensionId` constant for an extension in `.pb.rs`.
+void GenerateExtensionDefinition(Context& ctx, const FieldDescriptor& ext);
+
+// Generates the thunks for an extension in `.pb.thunks.cc`.
+void GenerateExtensionThunksCc(Context& ctx, const FieldDescriptor& ext);
+
+// Generates the `has_extension`, `get_extension`, `set_extension` and
+// `clear_extension` methods of an extendable message.
+void GenerateExtensionAccessors(Context& ctx, const Descriptor& msg,
+                                AccessorCase accessor_case);
+
+}  // namespace rust
+}  // namespace compiler
+}  // namespace protobuf
+}  // namespace google
+
+#endif  // GOOGLE_PROTOBUF_COMPILER_RUST_EXTENSION_H__
@@ -7,6 +7,8 @@
 
 #include "google/protobuf/compiler/rust/generator.h"
 
+#include <cstddef>
+#include <cstdint>
 #include <memory>
 #include <string>
 #include <utility>
@@ -15,19 +17,26 @@
 #include "absl/algorithm/container.h"
 #include "absl/container/flat_hash_map.h"
 #include "absl/container/flat_hash_set.h"
+#include "absl/log/absl_check.h"
 #include "absl/memory/memory.h"
 #include "absl/status/status.h"
 #include "absl/status/statusor.h"
+#include "absl/strings/escaping.h"
+#include "absl/strings/str_cat.h"
+#include "absl/strings/str_format.h"
 #include "absl/strings/string_view.h"
 #include "absl/types/span.h"
 #include "google/protobuf/compiler/code_generator.h"
 #include "google/protobuf/compiler/cpp/names.h"
+#include "google/protobuf/compiler/retention.h"
 #include "google/protobuf/compiler/rust/context.h"
 #include "google/protobuf/compiler/rust/crate_mapping.h"
 #include "google/protobuf/compiler/rust/enum.h"
+#include "google/protobuf/compiler/rust/extension.h"
 #include "google/protobuf/compiler/rust/message.h"
 #include "google/protobuf/compiler/rust/naming.h"
 #include "google/protobuf/compiler/rust/relative_path.h"
+#include "google/protobuf/compiler/rust/upb_helpers.h"
 #include "google/protobuf/descriptor.h"
 #include "google/protobuf/descriptor.pb.h"
 #include "google/protobuf/io/printer.h"
@@ -114,6 +123,103 @@ void DeclareSubmodulesForNonPrimarySrcs(
   }
 }
 
+// Escapes `str` for use in a Rust string literal. A `str` only accepts `\x`
+// escapes of ASCII characters, so every other character is written as a
+// `\u{..}` escape of its code point.
+std::string RsStringEscape(absl::string_view str) {
+  std::string escaped;
+  for (size_t i = 0; i < str.size();) {
+    unsigned char c = str[i];
+    if (c < 0x80) {
+      absl::StrAppend(&escaped, absl::CHexEscape(str.substr(i, 1)));
+      ++i;
+      continue;
+    }
+    size_t len = c >= 0xf0 ? 4 : c >= 0xe0 ? 3 : 2;
+    ABSL_CHECK(c >= 0xc0 && i + len <= str.size())
+        << "Invalid UTF-8 in " << absl::CHexEscape(str);
+    uint32_t code_point = c & (0x7f >> len);
+    for (size_t j = 1; j < len; ++j) {
+      unsigned char continuation = str[i + j];
+      ABSL_CHECK_EQ(continuation & 0xc0, 0x80)
+          << "Invalid UTF-8 in " << absl::CHexEscape(str);
+      code_point = (code_point << 6) | (continuation & 0x3f);
+    }
+    absl::StrAppendFormat(&escaped, "\\u{%x}", code_point);
+    i += len;
+  }
+  return escaped;
+}
+
+// Emits the hidden static that holds the descriptor of `file`, which is used
+// to implement `ReflectMessage` and `ReflectEnum` for its types.
+void EmitFileDescriptor(Context& ctx, const FileDescriptor& file) {
+  auto deps = [&] {
+    for (int i = 0; i < file.dependency_count(); ++i) {
+      const FileDescriptor* dep = file.dependency(i);
+      if (ctx.opts().strip_nonfunctional_codegen &&
+          IsKnownFeatureProto(dep->name())) {
+        continue;
+      }
+      ctx.Emit({{"dep", FileDescriptorStaticPath(ctx, *dep)}}, R"rs(
+        &$dep$,
+      )rs");
+    }
+  };
+
+  // The C++ kernel finds the descriptors of most files in the generated pool,
+  // only lite files need to embed theirs.
+  std::string serialized;
+  if (ctx.is_upb() ||
+      file.options().optimize_for() == FileOptions::LITE_RUNTIME) {
+    StripSourceRetentionOptions(file).SerializeToString(&serialized);
+  }
+
+  ctx.Emit({{"file_descriptor", FileDescriptorStaticName(file)},
+            {"name", RsStringEscape(file.name())},
+            {"serialized", absl::CHexEscape(serialized)},
+            {"deps", deps},
+            {"init",
+             [&] {
+               if (ctx.is_cpp()) {
+                 ctx.Emit(R"rs(
+                   // SAFETY: the descriptor and deps are those of this file.
+                   unsafe {
+                     $pbr$::FileDescriptorInit::new(
+                       "$name$", b"$serialized$", &[$deps$])
+                   }
+                 )rs");
+                 return;
+               }
+               ctx.Emit({{"layout", UpbMiniTableFileName(file)}}, R"rs(
+                 extern "C" {
+                   /// Opaque static extern for the MiniTables of this file,
+                   /// generated by the upb C MiniTable codegen. The only valid
+                   /// way to reference this static is with
+                   /// `std::ptr::addr_of!(..)`.
+                   static $layout$: $pbr$::upb_MiniTableFile;
+                 }
+                 // SAFETY: the descriptor, deps and MiniTables are those of
+                 // this file.
+                 unsafe {
+                   $pbr$::FileDescriptorInit::new(
+                     "$name$",
+                     b"$serialized$",
+                     &[$deps$],
+                     || $std$::ptr::addr_of!($layout$),
+                   )
+                 }
+               )rs");
+             }}},
+           R"rs(
+             #[doc(hidden)]
+             #[allow(unused_unsafe)]
+             pub static $file_descriptor$: $pbr$::FileDescriptorInit = {
+               $init$
+             };
+           )rs");
+}
+
 }  // namespace
 
 bool RustGenerator::Generate(const FileDescriptor* file,
@@ -208,6 +314,7 @@ bool RustGenerator::Generate(const FileDescriptor* file,
   }
 
   EmitPublicImports(rust_generator_context, ctx, *file);
+  EmitFileDescriptor(ctx, *file);
 
   for (int i = 0; i < file->message_type_count(); ++i) {
     auto& msg = *file->message_type(i);
@@ -241,6 +348,18 @@ bool RustGenerator::Generate(const FileDescriptor* file,
     }
   }
 
+  for (int i = 0; i < file->extension_count(); ++i) {
+    auto& ext = *file->extension(i);
+    GenerateExtensionDefinition(ctx, ext);
+    ctx.printer().PrintRaw("\n");
+
+    if (ctx.is_cpp()) {
+      auto thunks_ctx = ctx.WithPrinter(thunks_printer.get());
+      GenerateExtensionThunksCc(thunks_ctx, ext);
+      thunks_ctx.printer().PrintRaw("\n");
+    }
+  }
+
   return true;
 }
 
@@ -9,6 +9,7 @@
 
 #include <string>
 
+#include "absl/container/flat_hash_set.h"
 #include "absl/log/absl_check.h"
 #include "absl/log/absl_log.h"
 #include "absl/strings/str_cat.h"
@@ -19,6 +20,7 @@
 #include "google/protobuf/compiler/rust/accessors/accessors.h"
 #include "google/protobuf/compiler/rust/context.h"
 #include "google/protobuf/compiler/rust/enum.h"
+#include "google/protobuf/compiler/rust/extension.h"
 #include "google/protobuf/compiler/rust/naming.h"
 #include "google/protobuf/compiler/rust/oneof.h"
 #include "google/protobuf/compiler/rust/upb_helpers.h"
@@ -64,14 +66,10 @@ void MessageSerialize(Context& ctx, const Descriptor& msg) {
     case Kernel::kCpp:
       ctx.Emit({}, R"rs(
         let mut serialized_data = $pbr$::SerializedData::new();
-        let success = unsafe {
+        let result = unsafe {
           $pbr$::proto2_rust_Message_serialize(self.raw_msg(), &mut serialized_data)
         };
-        if success {
-          Ok(serialized_data.into_vec())
-        } else {
-          Err($pb$::SerializeError)
-        }
+        result.into_result().map(|()| serialized_data.into_vec())
       )rs");
       return;
 
@@ -83,9 +81,76 @@ void MessageSerialize(Context& ctx, const Descriptor& msg) {
           $pbr$::wire::encode(self.raw_msg(),
               <Self as $pbr$::AssociatedMiniTable>::mini_table())
         };
-        //~ TODO: This discards the info we have about the reason
-        //~ of the failure, we should try to keep it instead.
-        encoded.map_err(|_| $pb$::SerializeError)
+        encoded.map_err(|status| $pbr$::serialize_error_from_encode_status(status, *self))
+      )rs");
+      return;
+  }
+
+  ABSL_LOG(FATAL) << "unreachable";
+}
+
+void MessageSerializeToVec(Context& ctx, const Descriptor& msg) {
+  switch (ctx.opts().kernel) {
+    case Kernel::kCpp:
+      ctx.Emit({}, R"rs(
+        unsafe {
+          $pbr$::serialize_to_vec(self.raw_msg(), out, &$pb$::SerializeOptions::new())
+        }
+      )rs");
+      return;
+
+    case Kernel::kUpb:
+      ctx.Emit(R"rs(
+        // SAFETY: `MINI_TABLE` is the one associated with `self.raw_msg()`.
+        unsafe {
+          $pbr$::serialize_to_vec(self.raw_msg(),
+              <Self as $pbr$::AssociatedMiniTable>::mini_table(), *self, out,
+              &$pb$::SerializeOptions::new())
+        }
+      )rs");
+      return;
+  }
+
+  ABSL_LOG(FATAL) << "unreachable";
+}
+
+void MessageSerializeWithOptions(Context& ctx, const Descriptor& msg) {
+  switch (ctx.opts().kernel) {
+    case Kernel::kCpp:
+      ctx.Emit({}, R"rs(
+        unsafe { $pbr$::serialize_with_options(self.raw_msg(), options) }
+      )rs");
+      return;
+
+    case Kernel::kUpb:
+      ctx.Emit(R"rs(
+        // SAFETY: `MINI_TABLE` is the one associated with `self.raw_msg()`.
+        unsafe {
+          $pbr$::serialize_with_options(self.raw_msg(),
+              <Self as $pbr$::AssociatedMiniTable>::mini_table(), *self, options)
+        }
+      )rs");
+      return;
+  }
+
+  ABSL_LOG(FATAL) << "unreachable";
+}
+
+void MessageSerializeInto(Context& ctx, const Descriptor& msg) {
+  switch (ctx.opts().kernel) {
+    case Kernel::kCpp:
+      ctx.Emit({}, R"rs(
+        unsafe { $pbr$::serialize_into(self.raw_msg(), buf) }
+      )rs");
+      return;
+
+    case Kernel::kUpb:
+      ctx.Emit(R"rs(
+        // SAFETY: `MINI_TABLE` is the one associated with `self.raw_msg()`.
+        unsafe {
+          $pbr$::serialize_into(self.raw_msg(),
+              <Self as $pbr$::AssociatedMiniTable>::mini_table(), *self, buf)
+        }
       )rs");
       return;
   }
@@ -114,21 +179,41 @@ void MessageMutClear(Context& ctx, const Descriptor& msg) {
   }
 }
 
+void MessageMutDiscardUnknownFields(Context& ctx, const Descriptor& msg) {
+  switch (ctx.opts().kernel) {
+    case Kernel::kCpp:
+      ctx.Emit({},
+               R"rs(
+          // SAFETY: `self.raw_msg()` is a valid mutable message.
+          unsafe { $pbr$::discard_unknown_fields(self.raw_msg()) }
+        )rs");
+      return;
+    case Kernel::kUpb:
+      ctx.Emit(
+          R"rs(
+          // SAFETY: `MINI_TABLE` is the one associated with `self.raw_msg()`.
+          unsafe {
+            $pbr$::discard_unknown_fields(
+                self.raw_msg(),
+                <Self as $pbr$::AssociatedMiniTable>::mini_table())
+          }
+        )rs");
+      return;
+  }
+
+  ABSL_LOG(FATAL) << "unreachable";
+}
+
 void MessageClearAndParse(Context& ctx, const Descriptor& msg) {
   switch (ctx.opts().kernel) {
     case Kernel::kCpp:
       ctx.Emit({},
                R"rs(
-          let success = unsafe {
-            // SAFETY: `data.as_ptr()` is valid to read for `data.len()`.
-            let data = $pbr$::SerializedData::from_raw_parts(
-              $NonNull$::new(data.as_ptr() as *mut _).unwrap(),
-              data.len(),
-            );
-
-            $pbr$::proto2_rust_Message_parse(self.raw_msg(), data)
+          // SAFETY: `data` is valid to read for the duration of the call.
+          let result = unsafe {
+            $pbr$::proto2_rust_Message_parse(self.raw_msg(), data.into())
           };
-          success.then_some(()).ok_or($pb$::ParseError)
+          result.into_result()
         )rs");
       return;
 
@@ -156,7 +241,7 @@ void MessageClearAndParse(Context& ctx, const Descriptor& msg) {
             $std$::mem::swap(self, &mut msg);
             Ok(())
           }
-          Err(_) => Err($pb$::ParseError)
+          Err(status) => Err($pbr$::parse_error_from_decode_status(status))
         }
       )rs");
       return;
@@ -165,6 +250,114 @@ void MessageClearAndParse(Context& ctx, const Descriptor& msg) {
   ABSL_LOG(FATAL) << "unreachable";
 }
 
+void MessageParseWithOptions(Context& ctx, const Descriptor& msg) {
+  switch (ctx.opts().kernel) {
+    case Kernel::kCpp:
+      ctx.Emit({},
+               R"rs(
+          let msg = Self::new();
+          // SAFETY: `msg.raw_msg()` is a valid, mutable message.
+          unsafe {
+            $pbr$::parse_with_options(msg.raw_msg(), serialized, options)
+          }.map(|()| msg)
+        )rs");
+      return;
+
+    case Kernel::kUpb:
+      ctx.Emit(
+          R"rs(
+        let msg = Self::new();
+        // SAFETY:
+        // - `mini_table` is the one used to construct `msg.raw_msg()`
+        // - `msg.arena()` is the arena that owns `msg.raw_msg()`.
+        unsafe {
+          $pbr$::parse_with_options(
+              serialized,
+              msg.raw_msg(),
+              <Self as $pbr$::AssociatedMiniTable>::mini_table(),
+              msg.arena(),
+              options)
+        }.map(|()| msg)
+      )rs");
+      return;
+  }
+
+  ABSL_LOG(FATAL) << "unreachable";
+}
+
+void MessageParseBorrowed(Context& ctx, const Descriptor& msg) {
+  switch (ctx.opts().kernel) {
+    case Kernel::kCpp:
+      ctx.Emit({},
+               R"rs(
+          let msg = Self::parse(serialized)?;
+          // SAFETY: the C++ kernel copies `serialized` into `msg`.
+          Ok(unsafe { $pb$::ParsedView::new($pbi$::Private, msg) })
+        )rs");
+      return;
+
+    case Kernel::kUpb:
+      ctx.Emit(
+          R"rs(
+        let msg = Self::new();
+        // SAFETY:
+        // - `mini_table` is the one used to construct `msg.raw_msg()`
+        // - `msg.arena()` is the arena that owns `msg.raw_msg()`.
+        // - `serialized` outlives `msg`, since the returned `ParsedView`
+        //   borrows it and is the only way to access `msg`.
+        unsafe {
+          $pbr$::parse_aliased(
+              serialized,
+              msg.raw_msg(),
+              <Self as $pbr$::AssociatedMiniTable>::mini_table(),
+              msg.arena())
+        }?;
+        // SAFETY: `msg` only points into its arena and `serialized`.
+        Ok(unsafe { $pb$::ParsedView::new($pbi$::Private, msg) })
+      )rs");
+      return;
+  }
+
+  ABSL_LOG(FATAL) << "unreachable";
+}
+
+void MessageMergeFromReader(Context& ctx, const Descriptor& msg) {
+  switch (ctx.opts().kernel) {
+    case Kernel::kCpp:
+      ctx.Emit({},
+               R"rs(
+          let data = $pbi$::read_to_end_limited($pbi$::Private, reader, max_size)?;
+          // SAFETY: `data` is valid to read for the duration of the call.
+          let result = unsafe {
+            $pbr$::proto2_rust_Message_merge_parse(self.raw_msg(), data.as_slice().into())
+          };
+          Ok(result.into_result()?)
+        )rs");
+      return;
+
+    case Kernel::kUpb:
+      ctx.Emit(
+          R"rs(
+        let data = $pbi$::read_to_end_limited($pbi$::Private, reader, max_size)?;
+        // SAFETY:
+        // - `data.as_ptr()` is valid to read for `data.len()`
+        // - `mini_table` is the one used to construct `self.raw_msg()`
+        // - `self.arena()` is the arena that owns `self.raw_msg()`.
+        let status = unsafe {
+          $pbr$::wire::decode(
+              &data,
+              self.raw_msg(),
+              <Self as $pbr$::AssociatedMiniTable>::mini_table(),
+              self.arena())
+        };
+        status.map_err(|status| $pbr$::parse_error_from_decode_status(status).into())
+      )rs");
+      return;
+  }
+
+  ABSL_LOG(FATAL) << "unreachable";
+}
+
 void MessageDebug(Context& ctx, const Descriptor& msg) {
   switch (ctx.opts().kernel) {
     case Kernel::kCpp:
@@ -261,8 +454,98 @@ void IntoProxiedForMessage(Context& ctx, const Descriptor& msg) {
   ABSL_LOG(FATAL) << "unreachable";
 }
 
+// Returns whether `msg` or any message reachable from its fields has a
+// required field. `seen` guards against recursive message definitions.
+bool HasRequiredFields(const Descriptor& msg,
+                       absl::flat_hash_set<const Descriptor*>& seen) {
+  if (!seen.insert(&msg).second) return false;
+  for (int i = 0; i < msg.field_count(); ++i) {
+    const FieldDescriptor& field = *msg.field(i);
+    if (field.is_required()) return true;
+    if (field.message_type() != nullptr &&
+        HasRequiredFields(*field.message_type(), seen)) {
+      return true;
+    }
+  }
+  return false;
+}
+
+bool HasRequiredFields(const Descriptor& msg) {
+  absl::flat_hash_set<const Descriptor*> seen;
+  return HasRequiredFields(msg, seen);
+}
+
+// Emits the body of `UnsetRequiredFields::unset_required_fields`, which upb
+// uses to report which required fields are missing when encoding fails.
+void UnsetRequiredFieldChecks(Context& ctx, const Descriptor& msg) {
+  for (int i = 0; i < msg.field_count(); ++i) {
+    const FieldDescriptor& field = *msg.field(i);
+    std::string field_name = FieldNameWithCollisionAvoidance(field);
+    auto v = ctx.printer().WithVars({
+        {"field", RsSafeName(field_name)},
+        {"raw_field_name", field_name},
+        {"proto_name", field.name()},
+    });
+    if (field.is_required()) {
+      ctx.Emit(R"rs(
+        if !self.has_$raw_field_name$() {
+          out.push(format!("{prefix}$proto_name$"));
+        }
+      )rs");
+    }
+
+    if (field.is_map()) {
+      const FieldDescriptor& value = *field.message_type()->map_value();
+      if (value.message_type() == nullptr ||
+          !HasRequiredFields(*value.message_type())) {
+        continue;
+      }
+      ctx.Emit(R"rs(
+        for (key, value) in self.$field$().iter() {
+          $pbr$::UnsetRequiredFields::unset_required_fields(
+              value, &format!("{prefix}$proto_name$[{key:?}]."), out);
+        }
+      )rs");
+    } else if (field.message_type() == nullptr ||
+               !HasRequiredFields(*field.message_type())) {
+      continue;
+    } else if (field.is_repeated()) {
+      ctx.Emit(R"rs(
+        for (i, value) in self.$field$().iter().enumerate() {
+          $pbr$::UnsetRequiredFields::unset_required_fields(
+              value, &format!("{prefix}$proto_name$[{i}]."), out);
+        }
+      )rs");
+    } else {
+      ctx.Emit(R"rs(
+        if self.has_$raw_field_name$() {
+          $pbr$::UnsetRequiredFields::unset_required_fields(
+              self.$field$(), &format!("{prefix}$proto_name$."), out);
+        }
+      )rs");
+    }
+  }
+}
+
 void UpbGeneratedMessageTraitImpls(Context& ctx, const Descriptor& msg) {
   if (ctx.opts().kernel == Kernel::kUpb) {
+    if (HasRequiredFields(msg)) {
+      ctx.Emit({{"checks", [&] { UnsetRequiredFieldChecks(ctx, msg); }}},
+               R"rs(
+        impl $pbr$::UnsetRequiredFields for $Msg$View<'_> {
+          fn unset_required_fields(self, prefix: &str, out: &mut Vec<String>) {
+            $checks$
+          }
+        }
+      )rs");
+    } else {
+      ctx.Emit(R"rs(
+        impl $pbr$::UnsetRequiredFields for $Msg$View<'_> {
+          fn unset_required_fields(self, _prefix: &str, _out: &mut Vec<String>) {}
+        }
+      )rs");
+    }
+
     ctx.Emit({{"minitable", UpbMiniTableName(msg)}}, R"rs(
       unsafe impl $pbr$::AssociatedMiniTable for $Msg$ {
         #[inline(always)]
@@ -427,6 +710,23 @@ void MessageProxiedInRepeated(Context& ctx, const Descriptor& msg) {
             }
           }
 
+          unsafe fn repeated_swap_unchecked(
+            mut f: $pb$::Mut<$pb$::Repeated<Self>>,
+            a: usize,
+            b: usize,
+          ) {
+            // SAFETY:
+            // - `f.as_raw()` is a valid `RepeatedPtrField*`.
+            // - `a` and `b` are in-bounds as promised by the caller.
+            unsafe { $pbr$::proto2_rust_RepeatedField_Message_swap(f.as_raw($pbi$::Private), a, b) }
+          }
+
+          fn repeated_truncate(mut f: $pb$::Mut<$pb$::Repeated<Self>>, len: usize) {
+            // SAFETY:
+            // - `f.as_raw()` is a valid `RepeatedPtrField*`.
+            unsafe { $pbr$::proto2_rust_RepeatedField_Message_truncate(f.as_raw($pbi$::Private), len) }
+          }
+
           fn repeated_reserve(
             mut f: $pb$::Mut<$pb$::Repeated<Self>>,
             additional: usize,
@@ -525,6 +825,19 @@ void MessageProxiedInRepeated(Context& ctx, const Descriptor& msg) {
               }
           }
 
+          unsafe fn repeated_swap_unchecked(
+            f: $pb$::Mut<$pb$::Repeated<Self>>,
+            a: usize,
+            b: usize,
+          ) {
+            // SAFETY: `a` and `b` are in-bounds as promised by the caller.
+            unsafe { $pbr$::swap_repeated_unchecked(f, a, b) }
+          }
+
+          fn repeated_truncate(f: $pb$::Mut<$pb$::Repeated<Self>>, len: usize) {
+            $pbr$::truncate_repeated(f, len)
+          }
+
           fn repeated_reserve(
             mut f: $pb$::Mut<$pb$::Repeated<Self>>,
             additional: usize,
@@ -603,6 +916,44 @@ void TypeConversions(Context& ctx, const Descriptor& msg) {
   }
 }
 
+void ExtensionTypeImpl(Context& ctx, const Descriptor& msg) {
+  ctx.Emit({{"set_val",
+             // upb fuses the arena of the value into the arena of `msg`, the
+             // C++ kernel copies the value.
+             ctx.is_upb() ? "val" : "val.raw_msg()"}},
+           R"rs(
+      impl $pb$::ExtensionType for $Msg$ {
+          type Default = ();
+
+          fn default_view(_private: $pbi$::Private, _default: ()) -> $Msg$View<'static> {
+              $Msg$View::default()
+          }
+
+          unsafe fn get_extension<'msg>(
+              _private: $pbi$::Private,
+              msg: $pbr$::RawMessage,
+              ext: &$pbr$::InnerExtension,
+              _default: (),
+          ) -> $Msg$View<'msg> {
+              // SAFETY: `msg` is extended by `ext`, as promised by the caller.
+              unsafe { $pbr$::get_message_extension(msg, ext) }
+                  .map_or_else($Msg$View::default, |raw| $Msg$View::new($pbi$::Private, raw))
+          }
+
+          unsafe fn set_extension(
+              _private: $pbi$::Private,
+              msg: $pbr$::MutatorMessageRef<'_>,
+              ext: &$pbr$::InnerExtension,
+              val: impl $pb$::IntoProxied<$Msg$>,
+          ) {
+              let val: $Msg$ = val.into_proxied($pbi$::Private);
+              // SAFETY: `msg` is extended by `ext`, as promised by the caller.
+              unsafe { $pbr$::set_message_extension(msg, ext, $set_val$) }
+          }
+      }
+    )rs");
+}
+
 void GenerateDefaultInstanceImpl(Context& ctx, const Descriptor& msg) {
   if (ctx.is_upb()) {
     ctx.Emit("$pbr$::ScratchSpace::zeroed_block()");
@@ -613,6 +964,43 @@ void GenerateDefaultInstanceImpl(Context& ctx, const Descriptor& msg) {
   }
 }
 
+// Returns the name of the runtime macro that implements the conversions of a
+// well-known type, or an empty string if `msg` doesn't have any.
+absl::string_view WellKnownTypeMacro(const Descriptor& msg) {
+  if (msg.file()->name() == "google/protobuf/timestamp.proto" &&
+      msg.full_name() == "google.protobuf.Timestamp") {
+    return "impl_timestamp";
+  }
+  if (msg.file()->name() == "google/protobuf/duration.proto" &&
+      msg.full_name() == "google.protobuf.Duration") {
+    return "impl_duration";
+  }
+  if (msg.file()->name() == "google/protobuf/any.proto" &&
+      msg.full_name() == "google.protobuf.Any") {
+    return "impl_any";
+  }
+  if (msg.file()->name() == "google/protobuf/field_mask.proto" &&
+      msg.full_name() == "google.protobuf.FieldMask") {
+    return "impl_field_mask";
+  }
+  if (msg.file()->name() == "google/protobuf/struct.proto") {
+    if (msg.full_name() == "google.protobuf.Struct") return "impl_struct";
+    if (msg.full_name() == "google.protobuf.Value") return "impl_value";
+    if (msg.full_name() == "google.protobuf.ListValue") {
+      return "impl_list_value";
+    }
+  }
+  return "";
+}
+
+void WellKnownTypeImpls(Context& ctx, const Descriptor& msg) {
+  absl::string_view macro = WellKnownTypeMacro(msg);
+  if (macro.empty()) return;
+  ctx.Emit({{"macro", macro}, {"Msg", RsSafeName(msg.name())}}, R"rs(
+    $pbi$::$macro$!($Msg$, $Msg$View);
+  )rs");
+}
+
 }  // namespace
 
 void GenerateRs(Context& ctx, const Descriptor& msg) {
@@ -625,10 +1013,22 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
           {"Msg", RsSafeName(msg.name())},
           {"Msg::new", [&] { MessageNew(ctx, msg); }},
           {"Msg::serialize", [&] { MessageSerialize(ctx, msg); }},
+          {"Msg::serialize_with_options",
+           [&] { MessageSerializeWithOptions(ctx, msg); }},
+          {"Msg::serialize_to_vec", [&] { MessageSerializeToVec(ctx, msg); }},
+          {"Msg::serialize_into", [&] { MessageSerializeInto(ctx, msg); }},
           {"MsgMut::clear", [&] { MessageMutClear(ctx, msg); }},
+          {"MsgMut::discard_unknown_fields",
+           [&] { MessageMutDiscardUnknownFields(ctx, msg); }},
           {"Msg::clear_and_parse", [&] { MessageClearAndParse(ctx, msg); }},
+          {"Msg::parse_with_options",
+           [&] { MessageParseWithOptions(ctx, msg); }},
+          {"Msg::parse_borrowed", [&] { MessageParseBorrowed(ctx, msg); }},
+          {"Msg::merge_from_reader", [&] { MessageMergeFromReader(ctx, msg); }},
           {"Msg::drop", [&] { MessageDrop(ctx, msg); }},
           {"Msg::debug", [&] { MessageDebug(ctx, msg); }},
+          {"full_name", msg.full_name()},
+          {"file_descriptor", FileDescriptorStaticPath(ctx, *msg.file())},
           {"MsgMut::merge_from", [&] { MessageMutMergeFrom(ctx, msg); }},
           {"default_instance_impl",
            [&] { GenerateDefaultInstanceImpl(ctx, msg); }},
@@ -641,13 +1041,15 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
                GenerateOneofAccessors(ctx, *msg.real_oneof_decl(i),
                                       AccessorCase::OWNED);
              }
+             GenerateExtensionAccessors(ctx, msg, AccessorCase::OWNED);
            }},
           {"nested_in_msg",
            [&] {
-             // If we have no nested types, enums, or oneofs, bail out without
-             // emitting an empty mod some_msg.
+             // If we have no nested types, enums, oneofs, or extensions, bail
+             // out without emitting an empty mod some_msg.
              if (msg.nested_type_count() == 0 && msg.enum_type_count() == 0 &&
-                 msg.real_oneof_decl_count() == 0) {
+                 msg.real_oneof_decl_count() == 0 &&
+                 msg.extension_count() == 0) {
                return;
              }
              ctx.PushModule(RsSafeName(CamelToSnakeCase(msg.name())));
@@ -669,12 +1071,20 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
                      for (int i = 0; i < msg.real_oneof_decl_count(); ++i) {
                        GenerateOneofDefinition(ctx, *msg.real_oneof_decl(i));
                      }
+                   }},
+                  {"extensions",
+                   [&] {
+                     for (int i = 0; i < msg.extension_count(); ++i) {
+                       GenerateExtensionDefinition(ctx, *msg.extension(i));
+                     }
                    }}},
                  R"rs(
                    $nested_msgs$
                    $nested_enums$
 
                    $oneofs$
+
+                   $extensions$
                 )rs");
              ctx.PopModule();
            }},
@@ -707,6 +1117,7 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
                GenerateOneofAccessors(ctx, *msg.real_oneof_decl(i),
                                       AccessorCase::VIEW);
              }
+             GenerateExtensionAccessors(ctx, msg, AccessorCase::VIEW);
            }},
           {"accessor_fns_for_muts",
            [&] {
@@ -717,12 +1128,15 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
                GenerateOneofAccessors(ctx, *msg.real_oneof_decl(i),
                                       AccessorCase::MUT);
              }
+             GenerateExtensionAccessors(ctx, msg, AccessorCase::MUT);
            }},
           {"into_proxied_impl", [&] { IntoProxiedForMessage(ctx, msg); }},
           {"upb_generated_message_trait_impls",
            [&] { UpbGeneratedMessageTraitImpls(ctx, msg); }},
           {"repeated_impl", [&] { MessageProxiedInRepeated(ctx, msg); }},
           {"type_conversions_impl", [&] { TypeConversions(ctx, msg); }},
+          {"extension_type_impl", [&] { ExtensionTypeImpl(ctx, msg); }},
+          {"well_known_type_impls", [&] { WellKnownTypeImpls(ctx, msg); }},
           {"unwrap_upb",
            [&] {
              if (ctx.is_upb()) {
@@ -745,6 +1159,43 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
 
         impl $pb$::Message for $Msg$ {}
 
+        impl $pb$::MessageName for $Msg$ {
+          const FULL_NAME: &'static str = "$full_name$";
+          const TYPE_URL: &'static str = "type.googleapis.com/$full_name$";
+        }
+
+        impl $pb$::reflect::ReflectMessage for $Msg$ {
+          fn descriptor() -> $pb$::reflect::MessageDescriptor {
+            static DESCRIPTOR: $std$::sync::OnceLock<$pb$::reflect::MessageDescriptor> =
+                $std$::sync::OnceLock::new();
+            DESCRIPTOR.get_or_init(|| {
+              $pbr$::message_descriptor(&$file_descriptor$, "$full_name$")
+            }).clone()
+          }
+
+          fn as_message_ref(&self) -> $pb$::reflect::MessageRef<'_> {
+            // SAFETY: `self.raw_msg()` is a message of this type.
+            unsafe {
+              $pb$::reflect::MessageRef::new(
+                  $pbi$::Private,
+                  <Self as $pb$::reflect::ReflectMessage>::descriptor(),
+                  self.raw_msg())
+            }
+          }
+        }
+
+        impl $pb$::reflect::ReflectMessageMut for $Msg$ {
+          fn as_message_mut(&mut self) -> $pb$::reflect::MessageRefMut<'_> {
+            // SAFETY: `self` is a message of this type.
+            unsafe {
+              $pb$::reflect::MessageRefMut::new(
+                  $pbi$::Private,
+                  <Self as $pb$::reflect::ReflectMessage>::descriptor(),
+                  self.as_mutator_message_ref($pbi$::Private))
+            }
+          }
+        }
+
         impl $std$::default::Default for $Msg$ {
           fn default() -> Self {
             Self::new()
@@ -755,6 +1206,16 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
           fn parse(serialized: &[u8]) -> $Result$<Self, $pb$::ParseError> {
             Self::parse(serialized)
           }
+
+          fn parse_with_options(serialized: &[u8], options: &$pb$::ParseOptions)
+              -> $Result$<Self, $pb$::ParseError> {
+            $Msg::parse_with_options$
+          }
+
+          fn parse_borrowed(serialized: &[u8])
+              -> $Result$<$pb$::ParsedView<'_, Self>, $pb$::ParseError> {
+            $Msg::parse_borrowed$
+          }
         }
 
         impl $std$::fmt::Debug for $Msg$ {
@@ -774,6 +1235,20 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
           fn serialize(&self) -> $Result$<Vec<u8>, $pb$::SerializeError> {
             $pb$::AsView::as_view(self).serialize()
           }
+
+          fn serialize_with_options(
+              &self, options: &$pb$::SerializeOptions)
+              -> $Result$<Vec<u8>, $pb$::SerializeError> {
+            $pb$::AsView::as_view(self).serialize_with_options(options)
+          }
+
+          fn serialize_to_vec(&self, out: &mut Vec<u8>) -> $Result$<(), $pb$::SerializeError> {
+            $pb$::AsView::as_view(self).serialize_to_vec(out)
+          }
+
+          fn serialize_into(&self, buf: &mut [u8]) -> $Result$<usize, $pb$::SerializeError> {
+            $pb$::AsView::as_view(self).serialize_into(buf)
+          }
         }
 
         impl $pb$::Clear for $Msg$ {
@@ -786,6 +1261,11 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
           fn clear_and_parse(&mut self, data: &[u8]) -> $Result$<(), $pb$::ParseError> {
             $Msg::clear_and_parse$
           }
+
+          fn merge_from_reader(&mut self, reader: impl $std$::io::Read, max_size: usize)
+              -> $Result$<(), $pb$::ReadError> {
+            $Msg::merge_from_reader$
+          }
         }
 
         // SAFETY:
@@ -821,6 +1301,22 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
           type Message = $Msg$;
         }
 
+        impl $pb$::reflect::ReflectMessage for $Msg$View<'_> {
+          fn descriptor() -> $pb$::reflect::MessageDescriptor {
+            <$Msg$ as $pb$::reflect::ReflectMessage>::descriptor()
+          }
+
+          fn as_message_ref(&self) -> $pb$::reflect::MessageRef<'_> {
+            // SAFETY: `self.raw_msg()` is a message of this type.
+            unsafe {
+              $pb$::reflect::MessageRef::new(
+                  $pbi$::Private,
+                  <Self as $pb$::reflect::ReflectMessage>::descriptor(),
+                  self.raw_msg())
+            }
+          }
+        }
+
         impl $std$::fmt::Debug for $Msg$View<'_> {
           fn fmt(&self, f: &mut $std$::fmt::Formatter<'_>) -> $std$::fmt::Result {
             $Msg::debug$
@@ -831,6 +1327,20 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
           fn serialize(&self) -> $Result$<Vec<u8>, $pb$::SerializeError> {
             $Msg::serialize$
           }
+
+          fn serialize_with_options(
+              &self, options: &$pb$::SerializeOptions)
+              -> $Result$<Vec<u8>, $pb$::SerializeError> {
+            $Msg::serialize_with_options$
+          }
+
+          fn serialize_to_vec(&self, out: &mut Vec<u8>) -> $Result$<(), $pb$::SerializeError> {
+            $Msg::serialize_to_vec$
+          }
+
+          fn serialize_into(&self, buf: &mut [u8]) -> $Result$<usize, $pb$::SerializeError> {
+            $Msg::serialize_into$
+          }
         }
 
         impl $std$::default::Default for $Msg$View<'_> {
@@ -854,6 +1364,11 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
             $pb$::IntoProxied::into_proxied(*self, $pbi$::Private)
           }
 
+          pub fn unknown_fields(self) -> $pb$::UnknownFields<'msg> {
+            // SAFETY: `self.raw_msg()` is valid and is not mutated for `'msg`.
+            unsafe { $pbr$::unknown_fields(self.raw_msg()) }
+          }
+
           $accessor_fns_for_views$
         }
 
@@ -888,6 +1403,7 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
 
         $repeated_impl$
         $type_conversions_impl$
+        $extension_type_impl$
 
         #[allow(dead_code)]
         #[allow(non_camel_case_types)]
@@ -901,6 +1417,34 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
           type Message = $Msg$;
         }
 
+        impl $pb$::reflect::ReflectMessage for $Msg$Mut<'_> {
+          fn descriptor() -> $pb$::reflect::MessageDescriptor {
+            <$Msg$ as $pb$::reflect::ReflectMessage>::descriptor()
+          }
+
+          fn as_message_ref(&self) -> $pb$::reflect::MessageRef<'_> {
+            // SAFETY: `self.raw_msg()` is a message of this type.
+            unsafe {
+              $pb$::reflect::MessageRef::new(
+                  $pbi$::Private,
+                  <Self as $pb$::reflect::ReflectMessage>::descriptor(),
+                  self.raw_msg())
+            }
+          }
+        }
+
+        impl $pb$::reflect::ReflectMessageMut for $Msg$Mut<'_> {
+          fn as_message_mut(&mut self) -> $pb$::reflect::MessageRefMut<'_> {
+            // SAFETY: `self` is a message of this type.
+            unsafe {
+              $pb$::reflect::MessageRefMut::new(
+                  $pbi$::Private,
+                  <Self as $pb$::reflect::ReflectMessage>::descriptor(),
+                  self.as_mutator_message_ref($pbi$::Private))
+            }
+          }
+        }
+
         impl $std$::fmt::Debug for $Msg$Mut<'_> {
           fn fmt(&self, f: &mut $std$::fmt::Formatter<'_>) -> $std$::fmt::Result {
             $Msg::debug$
@@ -911,6 +1455,20 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
           fn serialize(&self) -> $Result$<Vec<u8>, $pb$::SerializeError> {
             $pb$::AsView::as_view(self).serialize()
           }
+
+          fn serialize_with_options(
+              &self, options: &$pb$::SerializeOptions)
+              -> $Result$<Vec<u8>, $pb$::SerializeError> {
+            $pb$::AsView::as_view(self).serialize_with_options(options)
+          }
+
+          fn serialize_to_vec(&self, out: &mut Vec<u8>) -> $Result$<(), $pb$::SerializeError> {
+            $pb$::AsView::as_view(self).serialize_to_vec(out)
+          }
+
+          fn serialize_into(&self, buf: &mut [u8]) -> $Result$<usize, $pb$::SerializeError> {
+            $pb$::AsView::as_view(self).serialize_into(buf)
+          }
         }
 
         impl $pb$::Clear for $Msg$Mut<'_> {
@@ -953,6 +1511,19 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
             $pb$::AsView::as_view(self).to_owned()
           }
 
+          pub fn unknown_fields(&self) -> $pb$::UnknownFields<'_> {
+            $pb$::AsView::as_view(self).unknown_fields()
+          }
+
+          pub fn clear_unknown_fields(&mut self) {
+            // SAFETY: `self.raw_msg()` is a valid mutable message.
+            unsafe { $pbr$::clear_unknown_fields(self.raw_msg()) }
+          }
+
+          pub fn discard_unknown_fields(&mut self) {
+            $MsgMut::discard_unknown_fields$
+          }
+
           $raw_arena_getter_for_msgmut$
 
           $accessor_fns_for_muts$
@@ -1027,6 +1598,18 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
             $Msg$Mut::new($pbi$::Private, &mut self.inner)
           }
 
+          pub fn unknown_fields(&self) -> $pb$::UnknownFields<'_> {
+            self.as_view().unknown_fields()
+          }
+
+          pub fn clear_unknown_fields(&mut self) {
+            self.as_mut().clear_unknown_fields()
+          }
+
+          pub fn discard_unknown_fields(&mut self) {
+            self.as_mut().discard_unknown_fields()
+          }
+
           $accessor_fns$
         }  // impl $Msg$
 
@@ -1060,6 +1643,10 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
 
         $upb_generated_message_trait_impls$
 
+        $well_known_type_impls$
+
+        $pbi$::impl_message_serde!($Msg$, $Msg$View, $Msg$Mut);
+
         $nested_in_msg$
       )rs");
 
@@ -1170,24 +1757,8 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
         }
       }
 
-      impl $pbi$::MatcherEq for $Msg$ {
-        fn matches(&self, o: &Self) -> bool {
-          $pbi$::MatcherEq::matches(
-            &$pb$::AsView::as_view(self),
-            &$pb$::AsView::as_view(o))
-        }
-      }
-
-      impl<'a> $pbi$::MatcherEq for $Msg$Mut<'a> {
-        fn matches(&self, o: &Self) -> bool {
-          $pbi$::MatcherEq::matches(
-            &$pb$::AsView::as_view(self),
-            &$pb$::AsView::as_view(o))
-        }
-      }
-
-      impl<'a> $pbi$::MatcherEq for $Msg$View<'a> {
-        fn matches(&self, o: &Self) -> bool {
+      impl<'a, 'b> $std$::cmp::PartialEq<$Msg$View<'b>> for $Msg$View<'a> {
+        fn eq(&self, o: &$Msg$View<'b>) -> bool {
           unsafe {
             $pbr$::raw_message_equals(self.msg, o.msg)
           }
@@ -1214,35 +1785,89 @@ void GenerateRs(Context& ctx, const Descriptor& msg) {
         }
       }
 
+      impl<'a, 'b> $std$::cmp::PartialEq<$Msg$View<'b>> for $Msg$View<'a> {
+        fn eq(&self, o: &$Msg$View<'b>) -> bool {
+          unsafe {
+            $pbr$::upb_Message_IsEqual(
+                self.msg,
+                o.msg,
+                <$Msg$ as $pbr$::AssociatedMiniTable>::mini_table(),
+                0)
+          }
+        }
+      }
+    )rs");
+  }
+
+  // Equality of the owned and mut types is defined in terms of their views,
+  // so any two of `Msg`, `MsgView` and `MsgMut` can be compared.
+  ctx.Emit({{"Msg", RsSafeName(msg.name())}}, R"rs(
+      impl $std$::cmp::PartialEq for $Msg$ {
+        fn eq(&self, o: &Self) -> bool {
+          $pb$::AsView::as_view(self) == $pb$::AsView::as_view(o)
+        }
+      }
+
+      impl<'b> $std$::cmp::PartialEq<$Msg$View<'b>> for $Msg$ {
+        fn eq(&self, o: &$Msg$View<'b>) -> bool {
+          $pb$::AsView::as_view(self) == *o
+        }
+      }
+
+      impl<'b> $std$::cmp::PartialEq<$Msg$Mut<'b>> for $Msg$ {
+        fn eq(&self, o: &$Msg$Mut<'b>) -> bool {
+          $pb$::AsView::as_view(self) == $pb$::AsView::as_view(o)
+        }
+      }
+
+      impl<'a> $std$::cmp::PartialEq<$Msg$> for $Msg$View<'a> {
+        fn eq(&self, o: &$Msg$) -> bool {
+          *self == $pb$::AsView::as_view(o)
+        }
+      }
+
+      impl<'a, 'b> $std$::cmp::PartialEq<$Msg$Mut<'b>> for $Msg$View<'a> {
+        fn eq(&self, o: &$Msg$Mut<'b>) -> bool {
+          *self == $pb$::AsView::as_view(o)
+        }
+      }
+
+      impl<'a> $std$::cmp::PartialEq<$Msg$> for $Msg$Mut<'a> {
+        fn eq(&self, o: &$Msg$) -> bool {
+          $pb$::AsView::as_view(self) == $pb$::AsView::as_view(o)
+        }
+      }
+
+      impl<'a, 'b> $std$::cmp::PartialEq<$Msg$View<'b>> for $Msg$Mut<'a> {
+        fn eq(&self, o: &$Msg$View<'b>) -> bool {
+          $pb$::AsView::as_view(self) == *o
+        }
+      }
+
+      impl<'a, 'b> $std$::cmp::PartialEq<$Msg$Mut<'b>> for $Msg$Mut<'a> {
+        fn eq(&self, o: &$Msg$Mut<'b>) -> bool {
+          $pb$::AsView::as_view(self) == $pb$::AsView::as_view(o)
+        }
+      }
+
       impl $pbi$::MatcherEq for $Msg$ {
         fn matches(&self, o: &Self) -> bool {
-          $pbi$::MatcherEq::matches(
-            &$pb$::AsView::as_view(self),
-            &$pb$::AsView::as_view(o))
+          self == o
         }
       }
 
       impl<'a> $pbi$::MatcherEq for $Msg$Mut<'a> {
         fn matches(&self, o: &Self) -> bool {
-          $pbi$::MatcherEq::matches(
-            &$pb$::AsView::as_view(self),
-            &$pb$::AsView::as_view(o))
+          self == o
         }
       }
 
       impl<'a> $pbi$::MatcherEq for $Msg$View<'a> {
         fn matches(&self, o: &Self) -> bool {
-          unsafe {
-            $pbr$::upb_Message_IsEqual(
-                self.msg,
-                o.msg,
-                <$Msg$ as $pbr$::AssociatedMiniTable>::mini_table(),
-                0)
-          }
+          self == o
         }
       }
-    )rs");
-  }
+  )rs");
 }  // NOLINT(readability/fn_size)
 
 // Generates code for a particular message in `.pb.thunk.cc`.
@@ -1265,6 +1890,12 @@ void GenerateThunksCc(Context& ctx, const Descriptor& msg) {
             GenerateThunksCc(ctx, *msg.nested_type(i));
           }
         }},
+       {"extension_thunks",
+        [&] {
+          for (int i = 0; i < msg.extension_count(); ++i) {
+            GenerateExtensionThunksCc(ctx, *msg.extension(i));
+          }
+        }},
        {"accessor_thunks",
         [&] {
           for (int i = 0; i < msg.field_count(); ++i) {
@@ -1296,6 +1927,7 @@ void GenerateThunksCc(Context& ctx, const Descriptor& msg) {
         // clang-format on
 
         $nested_msg_thunks$
+        $extension_thunks$
       )cc");
 }
 
@@ -235,6 +235,31 @@ std::string RustInternalModuleName(const FileDescriptor& file) {
       absl::StrReplaceAll(StripProto(file.name()), {{"_", "__"}, {"/", "_s"}}));
 }
 
+std::string FileDescriptorStaticName(const FileDescriptor& file) {
+  // Lowercase letters and digits are kept, `_` and `/` become `__` and `_S`,
+  // and every other byte becomes `_X` followed by its hex value. This keeps
+  // the names of files that only differ in case or punctuation distinct.
+  std::string name = "__FILE_DESCRIPTOR_";
+  for (char c : StripProto(file.name())) {
+    if (absl::ascii_islower(c) || absl::ascii_isdigit(c)) {
+      name.push_back(absl::ascii_toupper(c));
+    } else if (c == '_') {
+      absl::StrAppend(&name, "__");
+    } else if (c == '/') {
+      absl::StrAppend(&name, "_S");
+    } else {
+      absl::StrAppendFormat(&name, "_X%02X", static_cast<unsigned char>(c));
+    }
+  }
+  return name;
+}
+
+std::string FileDescriptorStaticPath(Context& ctx, const FileDescriptor& file) {
+  // Statics of non-primary files are re-exported at the root of their crate.
+  return absl::StrCat(RustModuleForContainingType(ctx, nullptr, file),
+                      FileDescriptorStaticName(file));
+}
+
 std::string FieldInfoComment(Context& ctx, const FieldDescriptor& field) {
   absl::string_view label = field.is_repeated() ? "repeated" : "optional";
   std::string comment = absl::StrCat(field.name(), ": ", label, " ",
@@ -99,6 +99,13 @@ std::string RustModule(Context& ctx, const OneofDescriptor& oneof);
 
 std::string RustInternalModuleName(const FileDescriptor& file);
 
+// Returns the name of the hidden static that holds the descriptor of `file`.
+std::string FileDescriptorStaticName(const FileDescriptor& file);
+
+// Returns the path of the descriptor static of `file`, relative to the current
+// scope or absolute if `file` is in a different crate.
+std::string FileDescriptorStaticPath(Context& ctx, const FileDescriptor& file);
+
 template <typename Desc>
 std::string GetUnderscoreDelimitedFullName(Context& ctx, const Desc& desc);
 
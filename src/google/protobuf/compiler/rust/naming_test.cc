@@ -9,6 +9,7 @@
 #include "google/protobuf/io/zero_copy_stream_impl_lite.h"
 
 using google::protobuf::compiler::rust::CamelToSnakeCase;
+using google::protobuf::compiler::rust::FileDescriptorStaticName;
 using google::protobuf::compiler::rust::RustInternalModuleName;
 using google::protobuf::compiler::rust::ScreamingSnakeToUpperCamelCase;
 
@@ -21,6 +22,21 @@ TEST(RustProtoNaming, RustInternalModuleName) {
   EXPECT_EQ(RustInternalModuleName(*fd), "strong__bad_slol");
 }
 
+TEST(RustProtoNaming, FileDescriptorStaticName) {
+  google::protobuf::DescriptorPool pool;
+  auto static_name = [&](const std::string& name) {
+    google::protobuf::FileDescriptorProto file;
+    file.set_name(name);
+    return FileDescriptorStaticName(*pool.BuildFile(file));
+  };
+  EXPECT_EQ(static_name("strong_bad/lol.proto"),
+            "__FILE_DESCRIPTOR_STRONG__BAD_SLOL");
+  EXPECT_EQ(static_name("strong-bad.proto"),
+            "__FILE_DESCRIPTOR_STRONG_X2DBAD");
+  EXPECT_EQ(static_name("Lol.proto"), "__FILE_DESCRIPTOR__X4COL");
+  EXPECT_EQ(static_name("l\xc3\xb6l.proto"), "__FILE_DESCRIPTOR_L_XC3_XB6L");
+}
+
 TEST(RustProtoNaming, CamelToSnakeCase) {
   // TODO: Review this behavior.
   EXPECT_EQ(CamelToSnakeCase("CamelCase"), "camel_case");
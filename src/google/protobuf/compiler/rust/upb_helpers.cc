@@ -16,6 +16,15 @@ std::string UpbMiniTableName(const Descriptor& msg) {
   return upb::generator::MiniTableMessageVarName(msg.full_name());
 }
 
+std::string UpbMiniTableExtensionName(const FieldDescriptor& ext) {
+  ABSL_CHECK(ext.is_extension());
+  return upb::generator::MiniTableExtensionVarName(ext.full_name());
+}
+
+std::string UpbMiniTableFileName(const FileDescriptor& file) {
+  return upb::generator::MiniTableFileVarName(file.name());
+}
+
 uint32_t UpbMiniTableFieldIndex(const FieldDescriptor& field) {
   auto* parent = field.containing_type();
   ABSL_CHECK(parent != nullptr);
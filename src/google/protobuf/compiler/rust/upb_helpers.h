@@ -21,6 +21,14 @@ namespace rust {
 // The symbol name for the MiniTable generated by upb MiniTable C codegen.
 std::string UpbMiniTableName(const Descriptor& msg);
 
+// The symbol name for the MiniTable extension generated by upb MiniTable C
+// codegen.
+std::string UpbMiniTableExtensionName(const FieldDescriptor& ext);
+
+// The symbol name for the MiniTables of all messages, enums and extensions of
+// `file`, generated by upb MiniTable C codegen.
+std::string UpbMiniTableFileName(const FileDescriptor& file);
+
 // The field index that the provided field will be in a upb_MiniTable.
 uint32_t UpbMiniTableFieldIndex(const FieldDescriptor& field);
 
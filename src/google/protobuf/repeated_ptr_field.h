@@ -1579,6 +1579,19 @@ class RustRepeatedMessageHelper {
   static MessageLite& At(RepeatedPtrFieldBase& field, size_t index) {
     return field.at<GenericTypeHandler<MessageLite>>(index);
   }
+
+  static void SwapElements(RepeatedPtrFieldBase& field, size_t index1,
+                           size_t index2) {
+    field.SwapElements(static_cast<int>(index1), static_cast<int>(index2));
+  }
+
+  // Removes the elements from `new_size` onward. Like `RemoveLast`, this
+  // clears them and keeps them allocated for reuse.
+  static void Truncate(RepeatedPtrFieldBase& field, size_t new_size) {
+    while (Size(field) > new_size) {
+      field.RemoveLast<GenericTypeHandler<MessageLite>>();
+    }
+  }
 };
 
 // STL-like iterator implementation for RepeatedPtrField.  You should not
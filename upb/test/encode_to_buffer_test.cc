@@ -0,0 +1,105 @@
+#include <cstddef>
+#include <string>
+#include <vector>
+
+#include <gtest/gtest.h>
+#include "google/protobuf/test_messages_proto2.upb.h"
+#include "google/protobuf/test_messages_proto2.upb_minitable.h"
+#include "upb/base/string_view.h"
+#include "upb/base/upcast.h"
+#include "upb/mem/arena.h"
+#include "upb/mini_table/message.h"
+#include "upb/wire/encode.h"
+
+namespace {
+
+static const upb_MiniTable* kTestMiniTable =
+    &protobuf_0test_0messages__proto2__TestAllTypesProto2_msg_init;
+
+class EncodeToBufferTest : public testing::Test {
+ protected:
+  EncodeToBufferTest()
+      : arena_(upb_Arena_New()),
+        msg_(protobuf_test_messages_proto2_TestAllTypesProto2_new(arena_)) {}
+  ~EncodeToBufferTest() override { upb_Arena_Free(arena_); }
+
+  // Returns the serialization of `msg_` from upb_Encode().
+  std::string Encode() {
+    char* buf;
+    size_t size;
+    EXPECT_EQ(upb_Encode(UPB_UPCAST(msg_), kTestMiniTable, 0, arena_, &buf,
+                         &size),
+              kUpb_EncodeStatus_Ok);
+    return std::string(buf, size);
+  }
+
+  // Encodes `msg_` with upb_EncodeToBuffer() into a buffer of `size` bytes,
+  // and on success stores the output in `out`.
+  upb_EncodeStatus EncodeToBuffer(size_t size, std::string* out) {
+    std::vector<char> buf(size);
+    size_t written;
+    upb_EncodeStatus status = upb_EncodeToBuffer(
+        UPB_UPCAST(msg_), kTestMiniTable, 0, buf.data(), size, &written);
+    if (status == kUpb_EncodeStatus_Ok) *out = std::string(buf.data(), written);
+    return status;
+  }
+
+  upb_Arena* arena_;
+  protobuf_test_messages_proto2_TestAllTypesProto2* msg_;
+};
+
+TEST_F(EncodeToBufferTest, EmptyMessage) {
+  char buf[1];
+  size_t written = 1;
+  EXPECT_EQ(upb_EncodeToBuffer(UPB_UPCAST(msg_), kTestMiniTable, 0, buf, 0,
+                               &written),
+            kUpb_EncodeStatus_Ok);
+  EXPECT_EQ(written, 0);
+}
+
+TEST_F(EncodeToBufferTest, StringLengthAtFront) {
+  // The string is long enough that its length prefix is a multi-byte varint,
+  // which is written at the very front of the buffer.
+  std::string value(200, 'a');
+  protobuf_test_messages_proto2_TestAllTypesProto2_set_optional_string(
+      msg_, upb_StringView_FromDataAndSize(value.data(), value.size()));
+  std::string expected = Encode();
+
+  std::string out;
+  EXPECT_EQ(EncodeToBuffer(expected.size() - 1, &out),
+            kUpb_EncodeStatus_BufferTooSmall);
+  ASSERT_EQ(EncodeToBuffer(expected.size(), &out), kUpb_EncodeStatus_Ok);
+  EXPECT_EQ(out, expected);
+  ASSERT_EQ(EncodeToBuffer(expected.size() + 1, &out), kUpb_EncodeStatus_Ok);
+  EXPECT_EQ(out, expected);
+}
+
+TEST_F(EncodeToBufferTest, LongVarintAtFront) {
+  // Negative int64 values are 10-byte varints, which the arena encoder
+  // reserves room for up front.
+  protobuf_test_messages_proto2_TestAllTypesProto2_set_optional_int64(msg_,
+                                                                      -1);
+  std::string expected = Encode();
+  ASSERT_EQ(expected.size(), 11);
+
+  std::string out;
+  EXPECT_EQ(EncodeToBuffer(expected.size() - 1, &out),
+            kUpb_EncodeStatus_BufferTooSmall);
+  ASSERT_EQ(EncodeToBuffer(expected.size(), &out), kUpb_EncodeStatus_Ok);
+  EXPECT_EQ(out, expected);
+  ASSERT_EQ(EncodeToBuffer(expected.size() + 1, &out), kUpb_EncodeStatus_Ok);
+  EXPECT_EQ(out, expected);
+}
+
+TEST_F(EncodeToBufferTest, LargerBuffer) {
+  protobuf_test_messages_proto2_TestAllTypesProto2_set_optional_int32(msg_,
+                                                                      322);
+  std::string expected = Encode();
+
+  // The output starts at the front of the buffer.
+  std::string out;
+  ASSERT_EQ(EncodeToBuffer(expected.size() + 16, &out), kUpb_EncodeStatus_Ok);
+  EXPECT_EQ(out, expected);
+}
+
+}  // namespace
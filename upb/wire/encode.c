@@ -99,6 +99,9 @@ UPB_NORETURN static void encode_err(upb_encstate* e, upb_EncodeStatus s) {
 
 UPB_NOINLINE
 static void encode_growbuffer(upb_encstate* e, size_t bytes) {
+  // Buffers provided by the caller of upb_EncodeToBuffer() cannot grow.
+  if (!e->arena) encode_err(e, kUpb_EncodeStatus_BufferTooSmall);
+
   size_t old_size = e->limit - e->buf;
   size_t new_size = upb_roundup_pow2(bytes + (e->limit - e->ptr));
   char* new_buf = upb_Arena_Realloc(e->arena, e->buf, old_size, new_size);
@@ -153,6 +156,16 @@ static void encode_longvarint(upb_encstate* e, uint64_t val) {
   size_t len;
   char* start;
 
+  if (UPB_UNLIKELY((size_t)(e->ptr - e->buf) < UPB_PB_VARINT_MAX_LEN &&
+                   !e->arena)) {
+    // The buffer of upb_EncodeToBuffer() can't grow, so only reserve the
+    // bytes that the varint needs in order not to spuriously run out of room.
+    char buf[UPB_PB_VARINT_MAX_LEN];
+    len = encode_varint64(val, buf);
+    encode_bytes(e, buf, len);
+    return;
+  }
+
   encode_reserve(e, UPB_PB_VARINT_MAX_LEN);
   len = encode_varint64(val, e->ptr);
   start = e->ptr + UPB_PB_VARINT_MAX_LEN - len;
@@ -704,6 +717,29 @@ upb_EncodeStatus upb_EncodeLengthPrefixed(const upb_Message* msg,
   return _upb_Encode(msg, l, options, arena, buf, size, true);
 }
 
+upb_EncodeStatus upb_EncodeToBuffer(const upb_Message* msg,
+                                    const upb_MiniTable* l, int options,
+                                    char* buf, size_t size, size_t* written) {
+  upb_encstate e;
+
+  e.status = kUpb_EncodeStatus_Ok;
+  e.arena = NULL;
+  e.buf = buf;
+  e.limit = buf + size;
+  e.ptr = e.limit;
+  e.depth = upb_EncodeOptions_GetEffectiveMaxDepth(options);
+  e.options = options;
+  _upb_mapsorter_init(&e.sorter);
+
+  char* out;
+  upb_EncodeStatus status = upb_Encoder_Encode(&e, msg, l, &out, written, false);
+  // The encoder writes backwards from the end of the buffer.
+  if (status == kUpb_EncodeStatus_Ok && *written > 0) {
+    memmove(buf, out, *written);
+  }
+  return status;
+}
+
 const char* upb_EncodeStatus_String(upb_EncodeStatus status) {
   switch (status) {
     case kUpb_EncodeStatus_Ok:
@@ -714,6 +750,8 @@ const char* upb_EncodeStatus_String(upb_EncodeStatus status) {
       return "Max depth exceeded";
     case kUpb_EncodeStatus_OutOfMemory:
       return "Arena alloc failed";
+    case kUpb_EncodeStatus_BufferTooSmall:
+      return "Buffer too small";
     default:
       return "Unknown encode status";
   }
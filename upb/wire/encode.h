@@ -48,6 +48,9 @@ typedef enum {
 
   // kUpb_EncodeOption_CheckRequired failed but the parse otherwise succeeded.
   kUpb_EncodeStatus_MissingRequired = 3,
+
+  // The output of upb_EncodeToBuffer() did not fit into the provided buffer.
+  kUpb_EncodeStatus_BufferTooSmall = 4,
 } upb_EncodeStatus;
 // LINT.ThenChange(//depot/google3/third_party/protobuf/rust/upb.rs:encode_status)
 
@@ -77,6 +80,16 @@ UPB_API upb_EncodeStatus upb_EncodeLengthPrefixed(const upb_Message* msg,
                                                   const upb_MiniTable* l,
                                                   int options, upb_Arena* arena,
                                                   char** buf, size_t* size);
+// Encodes the message into the caller-provided `buf`, which has room for
+// `size` bytes, without allocating the output from an arena. On success the
+// encoded message is at the front of `buf` and `*written` is set to its
+// length. Fails with kUpb_EncodeStatus_BufferTooSmall as soon as the output
+// exceeds `size` bytes, in which case the contents of `buf` are unspecified.
+UPB_API upb_EncodeStatus upb_EncodeToBuffer(const upb_Message* msg,
+                                            const upb_MiniTable* l,
+                                            int options, char* buf,
+                                            size_t size, size_t* written);
+
 // Utility function for wrapper languages to get an error string from a
 // upb_EncodeStatus.
 UPB_API const char* upb_EncodeStatus_String(upb_EncodeStatus status);